@@ -25,6 +25,16 @@
 //! If `7zz` (or `7z`) is not on `$PATH`, the `7zz_*` benches are
 //! silently skipped. The build-system installs `p7zip` in CI.
 //!
+//! ## Extracting to disk
+//!
+//! The `extract` group writes the whole tree to a scratch directory
+//! three ways: `extract_node_with` (one thread, one cursor),
+//! `extract_node_parallel` (one worker per core, positional reads on
+//! a shared `File`) and `7zz x`. This is the comparison that shows
+//! the multi-core speedup: on a large UDF image whose files sit on
+//! SSD or in the page cache, the parallel run should scale with cores
+//! while the other two stay flat.
+//!
 //! ## Why criterion
 //!
 //! The default `cargo bench` Harness has no statistical handling and
@@ -41,7 +51,9 @@ use std::process::{Command, Stdio};
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
-use isomage::{detect_and_parse_filesystem, TreeNode};
+use isomage::{
+    detect_and_parse_filesystem, extract_node_parallel, extract_node_with, ExtractOptions, TreeNode,
+};
 
 /// Walk the tree, calling `cat_node` on every file, writing into `io::sink()`.
 /// This is what an "extract everything to /dev/null" workload looks like.
@@ -115,6 +127,45 @@ fn seven_zip_extract_to_null(image_path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Extract the whole image under `out`, serially or with one worker
+/// per core. Returns the bytes written.
+fn extract_all_to_dir(image_path: &Path, out: &Path, parallel: bool) -> io::Result<u64> {
+    let mut file = File::open(image_path)?;
+    let root = detect_and_parse_filesystem(&mut file, image_path.to_str().unwrap_or("?"))
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let out = out.to_str().expect("scratch dir is UTF-8");
+    let options = ExtractOptions::new();
+    let summary = if parallel {
+        extract_node_parallel(&file, &root, out, &options)
+    } else {
+        extract_node_with(&mut file, &root, out, &options)
+    }
+    .map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(summary.bytes)
+}
+
+/// Run `7zz x -o<out> <image>`, overwriting what's already there.
+fn seven_zip_extract_to_dir(image_path: &Path, out: &Path) -> io::Result<()> {
+    let bin = if which("7zz").is_some() { "7zz" } else { "7z" };
+    let status = Command::new(bin)
+        .arg("x")
+        .arg(format!("-o{}", out.display()))
+        .arg("-bd")
+        .arg("-y")
+        .arg(image_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {:?}",
+            bin,
+            status.code()
+        )));
+    }
+    Ok(())
+}
+
 fn which(cmd: &str) -> Option<PathBuf> {
     let path = std::env::var_os("PATH")?;
     for dir in std::env::split_paths(&path) {
@@ -239,5 +290,49 @@ fn bench_seqread(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_seqread);
+fn bench_extract(c: &mut Criterion) {
+    let images: Vec<PathBuf> = corpus()
+        .into_iter()
+        .filter(|p| {
+            matches!(
+                p.extension().and_then(OsStr::to_str),
+                Some("iso" | "img" | "udf")
+            )
+        })
+        .collect();
+    let have_7z = which("7zz").or_else(|| which("7z")).is_some();
+
+    let mut group = c.benchmark_group("extract");
+    for img in &images {
+        let size = std::fs::metadata(img).map(|m| m.len()).unwrap_or(0);
+        let name = img
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or("unknown")
+            .to_string();
+        group.throughput(Throughput::Bytes(size));
+
+        // Each variant reuses its scratch directory across iterations;
+        // the default overwrite policy replaces what the last one wrote.
+        for (id, parallel) in [("isomage", false), ("isomage_parallel", true)] {
+            let out = tempfile::TempDir::new().expect("scratch dir");
+            group.bench_with_input(BenchmarkId::new(id, &name), img, |b, path| {
+                b.iter(|| {
+                    let n =
+                        extract_all_to_dir(path, out.path(), parallel).expect("isomage extract");
+                    black_box(n);
+                });
+            });
+        }
+        if have_7z {
+            let out = tempfile::TempDir::new().expect("scratch dir");
+            group.bench_with_input(BenchmarkId::new("7zz", &name), img, |b, path| {
+                b.iter(|| seven_zip_extract_to_dir(path, out.path()).expect("7zz extract"));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_seqread, bench_extract);
 criterion_main!(benches);
@@ -0,0 +1,568 @@
+//! Comparing two trees.
+//!
+//! [`diff_trees`] matches two trees by path and reports every entry that
+//! was added, removed or changed in kind, size or metadata.
+//! [`diff_images`] does the same and can also compare file contents,
+//! streaming both sides through [`cat_node`](crate::cat_node) and
+//! [`read_node_range`](crate::read_node_range) rather than loading whole files.
+//!
+//! The trees may come from different formats — an ISO against the
+//! SquashFS inside it, a ZIP against a TAR. Metadata fields are compared
+//! only where both formats record them, so an ISO without Rock Ridge
+//! never differs from a TAR in ownership.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use isomage::diff::{diff_images, Change, DiffOptions};
+//! use isomage::detect_and_parse_filesystem;
+//!
+//! let mut old = File::open("installer-1.0.iso")?;
+//! let mut new = File::open("installer-1.1.iso")?;
+//! let old_root = detect_and_parse_filesystem(&mut old, "installer-1.0.iso")?;
+//! let new_root = detect_and_parse_filesystem(&mut new, "installer-1.1.iso")?;
+//!
+//! let options = DiffOptions::new().content(true);
+//! for difference in diff_images(&mut old, &old_root, &mut new, &new_root, &options)? {
+//!     match difference.change {
+//!         Change::Added => println!("+ {}", difference.path),
+//!         Change::Removed => println!("- {}", difference.path),
+//!         Change::Modified(_) => println!("~ {}", difference.path),
+//!     }
+//! }
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, Write};
+
+use crate::tree::{EntryKind, Metadata, TreeNode};
+use crate::{Error, Result};
+
+/// Options for [`diff_trees`] and [`diff_images`].
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    metadata: bool,
+    content: bool,
+    ignore_case: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            metadata: true,
+            content: false,
+            ignore_case: false,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// The defaults: compare kind, size and metadata, not content; match
+    /// names exactly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare permission bits, owner, group, mtime (to the second) and
+    /// link target, each only where both sides record it. On by default.
+    pub fn metadata(mut self, yes: bool) -> Self {
+        self.metadata = yes;
+        self
+    }
+
+    /// Compare the bytes of regular files whose sizes agree.
+    /// [`diff_images`] only; [`diff_trees`] has no images to read.
+    pub fn content(mut self, yes: bool) -> Self {
+        self.content = yes;
+        self
+    }
+
+    /// Match names that differ only in case, for comparing a
+    /// case-insensitive filesystem against anything else. An exact match
+    /// is still preferred.
+    pub fn ignore_case(mut self, yes: bool) -> Self {
+        self.ignore_case = yes;
+        self
+    }
+}
+
+/// One entry that differs between the two trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// `/`-separated path from the roots, with a leading `/`. Names are
+    /// taken from the new tree where both have the entry.
+    pub path: String,
+    /// How it differs.
+    pub change: Change,
+}
+
+/// How an entry differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Only in the new tree.
+    Added,
+    /// Only in the old tree.
+    Removed,
+    /// In both, but not the same.
+    Modified(Changes),
+}
+
+/// Which aspects of an entry present in both trees differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Changes {
+    /// One is a directory, file, symlink, … and the other something else.
+    pub kind: bool,
+    /// File sizes differ. Directory sizes are not compared; their
+    /// contents are.
+    pub size: bool,
+    /// A metadata field both sides record differs.
+    pub metadata: bool,
+    /// File contents differ.
+    pub content: bool,
+}
+
+impl Changes {
+    fn any(&self) -> bool {
+        self.kind || self.size || self.metadata || self.content
+    }
+}
+
+/// Compare two trees by kind, size and (optionally) metadata.
+///
+/// The result is sorted by path. Every entry below an added or removed
+/// directory is reported too, as is every entry below a path that is a
+/// directory on one side and not on the other.
+pub fn diff_trees(old: &TreeNode, new: &TreeNode, options: &DiffOptions) -> Vec<Difference> {
+    let mut diff = Diff {
+        options,
+        out: Vec::new(),
+        same_size: Vec::new(),
+    };
+    diff.directory(old, new, "");
+    diff.out.sort_by(|a, b| a.path.cmp(&b.path));
+    diff.out
+}
+
+/// Compare two trees like [`diff_trees`], and with
+/// [`DiffOptions::content`] also compare the bytes of every pair of
+/// regular files whose sizes agree.
+///
+/// Content is streamed in chunks and a pair stops being read at the
+/// first differing chunk. A file whose data location one parser could
+/// not work out is not compared by content.
+pub fn diff_images<R1: Read + Seek, R2: Read + Seek>(
+    old_image: &mut R1,
+    old: &TreeNode,
+    new_image: &mut R2,
+    new: &TreeNode,
+    options: &DiffOptions,
+) -> Result<Vec<Difference>> {
+    let mut diff = Diff {
+        options,
+        out: Vec::new(),
+        same_size: Vec::new(),
+    };
+    diff.directory(old, new, "");
+    for (path, old_node, new_node, mut changes) in std::mem::take(&mut diff.same_size) {
+        if !same_content(old_image, old_node, new_image, new_node)? {
+            changes.content = true;
+        }
+        if changes.any() {
+            diff.out.push(Difference {
+                path,
+                change: Change::Modified(changes),
+            });
+        }
+    }
+    diff.out.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diff.out)
+}
+
+struct Diff<'o, 'n, 'x, 'y, 'p> {
+    options: &'p DiffOptions,
+    out: Vec<Difference>,
+    /// Regular files of equal size held back for a content check, with
+    /// the changes already found.
+    #[allow(clippy::type_complexity)]
+    same_size: Vec<(String, &'o TreeNode<'x>, &'n TreeNode<'y>, Changes)>,
+}
+
+impl<'o, 'n, 'x, 'y> Diff<'o, 'n, 'x, 'y, '_> {
+    fn directory(&mut self, old: &'o TreeNode<'x>, new: &'n TreeNode<'y>, prefix: &str) {
+        let mut unmatched: Vec<Option<&'o TreeNode<'x>>> = old.children.iter().map(Some).collect();
+        let mut by_name: HashMap<&str, usize> = HashMap::new();
+        for (i, child) in old.children.iter().enumerate() {
+            by_name.entry(&child.name).or_insert(i);
+        }
+
+        // Exact matches first, so a case-folded match never takes a name
+        // that a later entry matches exactly.
+        let mut pairs: Vec<Option<&'o TreeNode<'x>>> = new
+            .children
+            .iter()
+            .map(|child| {
+                let i = *by_name.get(child.name.as_ref())?;
+                unmatched[i].take()
+            })
+            .collect();
+        if self.options.ignore_case {
+            for (pair, child) in pairs.iter_mut().zip(&new.children) {
+                if pair.is_none() {
+                    *pair = unmatched
+                        .iter_mut()
+                        .find(|old| {
+                            old.is_some_and(|old| {
+                                crate::walk::eq_ignore_case(&old.name, &child.name)
+                            })
+                        })
+                        .and_then(Option::take);
+                }
+            }
+        }
+
+        for (pair, new_child) in pairs.into_iter().zip(&new.children) {
+            let path = format!("{}/{}", prefix, new_child.name);
+            match pair {
+                Some(old_child) => self.entry(old_child, new_child, path),
+                None => self.subtree(new_child, path, Change::Added),
+            }
+        }
+
+        for old_child in unmatched.into_iter().flatten() {
+            let path = format!("{}/{}", prefix, old_child.name);
+            self.subtree(old_child, path, Change::Removed);
+        }
+    }
+
+    fn entry(&mut self, old: &'o TreeNode<'x>, new: &'n TreeNode<'y>, path: String) {
+        let (old_kind, new_kind) = (kind(old), kind(new));
+        let changes = Changes {
+            kind: old_kind != new_kind,
+            size: !old.is_directory && !new.is_directory && old.size != new.size,
+            metadata: self.options.metadata && metadata_differs(&old.metadata, &new.metadata),
+            content: false,
+        };
+
+        if old.is_directory && new.is_directory {
+            if changes.any() {
+                self.push(path.clone(), changes);
+            }
+            self.directory(old, new, &path);
+            return;
+        }
+
+        if changes.kind {
+            self.push(path.clone(), changes);
+            // A directory replaced by a file, or the reverse.
+            if old.is_directory {
+                self.children(old, &path, Change::Removed);
+            } else if new.is_directory {
+                self.children(new, &path, Change::Added);
+            }
+            return;
+        }
+
+        if self.options.content && new_kind == EntryKind::File && !changes.size {
+            self.same_size.push((path, old, new, changes));
+            return;
+        }
+        if changes.any() {
+            self.push(path, changes);
+        }
+    }
+
+    fn push(&mut self, path: String, changes: Changes) {
+        self.out.push(Difference {
+            path,
+            change: Change::Modified(changes),
+        });
+    }
+
+    fn subtree(&mut self, node: &TreeNode, path: String, change: Change) {
+        self.children(node, &path, change);
+        self.out.push(Difference { path, change });
+    }
+
+    fn children(&mut self, node: &TreeNode, path: &str, change: Change) {
+        for entry in node.walk() {
+            self.out.push(Difference {
+                path: format!("{}{}", path, entry.path),
+                change,
+            });
+        }
+    }
+}
+
+fn kind(node: &TreeNode) -> EntryKind {
+    if node.is_directory {
+        EntryKind::Directory
+    } else {
+        node.metadata.kind
+    }
+}
+
+/// `true` if a field recorded on both sides differs.
+fn metadata_differs(old: &Metadata, new: &Metadata) -> bool {
+    fn differs<T: PartialEq>(old: &Option<T>, new: &Option<T>) -> bool {
+        matches!((old, new), (Some(old), Some(new)) if old != new)
+    }
+    differs(&old.mode.map(|m| m & 0o7777), &new.mode.map(|m| m & 0o7777))
+        || differs(&old.uid, &new.uid)
+        || differs(&old.gid, &new.gid)
+        || differs(&old.mtime.map(|t| t.secs), &new.mtime.map(|t| t.secs))
+        || differs(&old.link_target, &new.link_target)
+        || differs(&old.device, &new.device)
+}
+
+/// Stream `old` through [`cat_node`](crate::cat_node), reading the same
+/// range of `new` for each chunk. `true` if every byte agrees, or if
+/// either side has no data location to read.
+fn same_content<R1: Read + Seek, R2: Read + Seek>(
+    old_image: &mut R1,
+    old: &TreeNode,
+    new_image: &mut R2,
+    new: &TreeNode,
+) -> Result<bool> {
+    if old.data_extents().is_none() || new.data_extents().is_none() {
+        return Ok(true);
+    }
+    let mut compare = Compare {
+        image: new_image,
+        node: new,
+        offset: 0,
+        scratch: Vec::new(),
+        same: true,
+        error: None,
+    };
+    crate::cat_node(old_image, old, &mut compare)?;
+    match compare.error {
+        Some(e) => Err(e),
+        None => Ok(compare.same),
+    }
+}
+
+/// A writer that checks each chunk written to it against the same range
+/// of another file, and stops the copy with `BrokenPipe` — which
+/// `cat_node` treats as the reader going away — at the first mismatch.
+struct Compare<'i, 'n, R> {
+    image: &'i mut R,
+    node: &'n TreeNode<'n>,
+    offset: u64,
+    scratch: Vec<u8>,
+    same: bool,
+    error: Option<Error>,
+}
+
+impl<R: Read + Seek> Write for Compare<'_, '_, R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scratch.clear();
+        let read = crate::read_node_range(
+            self.image,
+            self.node,
+            self.offset,
+            buf.len() as u64,
+            &mut self.scratch,
+        );
+        if let Err(e) = read {
+            self.error = Some(e);
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        if self.scratch != buf {
+            self.same = false;
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.offset += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Timestamp;
+    use std::io::Cursor;
+
+    fn file(name: &str, location: u64, size: u64) -> TreeNode<'static> {
+        TreeNode::new_file_with_location(name.to_string(), size, location, size)
+    }
+
+    /// `/a.txt`, `/docs/b.txt`, `/docs/c.txt`, over `IMAGE`.
+    const IMAGE: &[u8] = b"aaaabbbbcccc";
+
+    fn tree() -> TreeNode<'static> {
+        let mut root = TreeNode::new_directory("/");
+        root.add_child(file("a.txt", 0, 4));
+        let mut docs = TreeNode::new_directory("docs");
+        docs.add_child(file("b.txt", 4, 4));
+        docs.add_child(file("c.txt", 8, 4));
+        root.add_child(docs);
+        root
+    }
+
+    fn modified(path: &str, changes: Changes) -> Difference {
+        Difference {
+            path: path.to_string(),
+            change: Change::Modified(changes),
+        }
+    }
+
+    fn entry(path: &str, change: Change) -> Difference {
+        Difference {
+            path: path.to_string(),
+            change,
+        }
+    }
+
+    #[test]
+    fn identical_trees() {
+        let options = DiffOptions::new().content(true);
+        assert!(diff_trees(&tree(), &tree(), &options).is_empty());
+        let diff = diff_images(
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &options,
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_resized() {
+        let old = tree();
+        let mut new = tree();
+        new.children.remove(0);
+        let docs = &mut new.children[0];
+        docs.children[0].size = 5;
+        let mut sub = TreeNode::new_directory("sub");
+        sub.add_child(file("d.txt", 0, 1));
+        docs.add_child(sub);
+
+        let size = Changes {
+            size: true,
+            ..Changes::default()
+        };
+        assert_eq!(
+            diff_trees(&old, &new, &DiffOptions::new()),
+            [
+                entry("/a.txt", Change::Removed),
+                modified("/docs/b.txt", size),
+                entry("/docs/sub", Change::Added),
+                entry("/docs/sub/d.txt", Change::Added),
+            ]
+        );
+        assert_eq!(
+            diff_trees(&new, &old, &DiffOptions::new())[0],
+            entry("/a.txt", Change::Added)
+        );
+    }
+
+    #[test]
+    fn kind_and_metadata() {
+        let old = tree();
+        let mut new = tree();
+        new.children[1] = file("docs", 0, 4);
+        new.children[0].metadata = Metadata {
+            mode: Some(0o755),
+            mtime: Some(Timestamp::new(100, 5)),
+            ..Metadata::new(EntryKind::File)
+        };
+        let mut old = old;
+        old.children[0].metadata.mtime = Some(Timestamp::new(100, 0));
+
+        let kind = Changes {
+            kind: true,
+            ..Changes::default()
+        };
+        // The mode is only on one side and the mtimes agree to the second.
+        assert_eq!(
+            diff_trees(&old, &new, &DiffOptions::new()),
+            [
+                modified("/docs", kind),
+                entry("/docs/b.txt", Change::Removed),
+                entry("/docs/c.txt", Change::Removed),
+            ]
+        );
+
+        old.children[0].metadata.mode = Some(0o644);
+        let metadata = Changes {
+            metadata: true,
+            ..Changes::default()
+        };
+        assert_eq!(
+            diff_trees(&old, &new, &DiffOptions::new())[0],
+            modified("/a.txt", metadata)
+        );
+        assert_eq!(
+            diff_trees(&old, &new, &DiffOptions::new().metadata(false)).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn content_across_images() {
+        let new_image = b"aaaaXbbbcccc";
+        let diff = diff_images(
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &mut Cursor::new(new_image),
+            &tree(),
+            &DiffOptions::new().content(true),
+        )
+        .unwrap();
+        let content = Changes {
+            content: true,
+            ..Changes::default()
+        };
+        assert_eq!(diff, [modified("/docs/b.txt", content)]);
+
+        // Same bytes at different places, as in a different format.
+        let mut moved = tree();
+        moved.children[0].file_location = Some(12);
+        let diff = diff_images(
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &mut Cursor::new(b"xxxxbbbbccccaaaa"),
+            &moved,
+            &DiffOptions::new().content(true),
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+
+        // Without content(true) the images are not read.
+        let diff = diff_images(
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &mut Cursor::new(Vec::new()),
+            &tree(),
+            &DiffOptions::new(),
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+
+        let err = diff_images(
+            &mut Cursor::new(IMAGE),
+            &tree(),
+            &mut Cursor::new(b"short"),
+            &tree(),
+            &DiffOptions::new().content(true),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Io(_)), "{err:?}");
+    }
+
+    #[test]
+    fn ignore_case() {
+        let old = tree();
+        let mut new = tree();
+        new.children[1].name = "DOCS".into();
+        assert_eq!(diff_trees(&old, &new, &DiffOptions::new()).len(), 6);
+        assert!(diff_trees(&old, &new, &DiffOptions::new().ignore_case(true)).is_empty());
+    }
+}
@@ -0,0 +1,537 @@
+//! El Torito boot catalogs (El Torito Bootable CD-ROM Format
+//! Specification 1.0).
+//!
+//! A bootable ISO 9660 image has a Boot Record volume descriptor that
+//! points at a boot catalog: a validation entry, the default entry, and
+//! optionally section headers that list further entries — one section
+//! per platform on hybrid BIOS/UEFI media. [`read_boot_catalog`] decodes
+//! the catalog. [`parse_iso9660`](crate::iso9660::parse_iso9660) also
+//! lists each boot image as a file under a virtual `[BOOT]` directory at
+//! the root, named after its platform (`x86.img`, `efi.img`, …), so it
+//! can be read with [`cat_node`](crate::cat_node).
+//!
+//! ```no_run
+//! let mut iso = std::fs::File::open("installer.iso")?;
+//! if let Some(catalog) = isomage::eltorito::read_boot_catalog(&mut iso)? {
+//!     for entry in &catalog.entries {
+//!         println!("{} {} at byte {}", entry.platform, entry.emulation, entry.image_offset());
+//!     }
+//! }
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use crate::image_io::source::{Source, Stream};
+use crate::iso9660;
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::TreeNode;
+use crate::{Error, Result};
+use std::fmt;
+use std::io::{Read, Seek};
+
+const SECTOR_SIZE: u64 = 2048;
+const ENTRY_SIZE: usize = 32;
+/// Catalog sectors read before giving up on finding the final section
+/// header. 64 sectors hold over 4000 entries.
+const MAX_CATALOG_SECTORS: u64 = 64;
+/// Boot system identifier of a Boot Record volume descriptor (El Torito
+/// 2.0), zero-padded to 32 bytes on disc.
+const EL_TORITO_ID: &[u8] = b"EL TORITO SPECIFICATION";
+
+/// Name of the virtual directory that holds the boot images.
+pub const BOOT_DIRECTORY: &str = "[BOOT]";
+
+/// The platform a boot entry is for (El Torito 2.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// 80x86 BIOS.
+    X86,
+    /// PowerPC.
+    PowerPc,
+    /// Mac.
+    Mac,
+    /// UEFI.
+    Efi,
+    /// Any other platform ID.
+    Other(u8),
+}
+
+impl Platform {
+    fn from_id(id: u8) -> Self {
+        match id {
+            0x00 => Platform::X86,
+            0x01 => Platform::PowerPc,
+            0x02 => Platform::Mac,
+            0xEF => Platform::Efi,
+            other => Platform::Other(other),
+        }
+    }
+
+    /// The platform ID byte as stored in the catalog.
+    pub fn id(self) -> u8 {
+        match self {
+            Platform::X86 => 0x00,
+            Platform::PowerPc => 0x01,
+            Platform::Mac => 0x02,
+            Platform::Efi => 0xEF,
+            Platform::Other(id) => id,
+        }
+    }
+
+    /// Lower-case stem for the boot image's file name under `[BOOT]`.
+    fn stem(self) -> String {
+        match self {
+            Platform::X86 => "x86".into(),
+            Platform::PowerPc => "ppc".into(),
+            Platform::Mac => "mac".into(),
+            Platform::Efi => "efi".into(),
+            Platform::Other(id) => format!("platform-{id:02x}"),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Platform::X86 => f.write_str("x86"),
+            Platform::PowerPc => f.write_str("PowerPC"),
+            Platform::Mac => f.write_str("Mac"),
+            Platform::Efi => f.write_str("EFI"),
+            Platform::Other(id) => write!(f, "platform 0x{id:02x}"),
+        }
+    }
+}
+
+/// What the BIOS emulates while booting an entry (El Torito 2.2, boot
+/// media type).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emulation {
+    /// The image is loaded as is; the usual choice for boot loaders and
+    /// for EFI system partition images.
+    NoEmulation,
+    /// A 1.2 MB floppy disk.
+    Floppy1200,
+    /// A 1.44 MB floppy disk.
+    Floppy1440,
+    /// A 2.88 MB floppy disk.
+    Floppy2880,
+    /// A hard disk; the image starts with a master boot record.
+    HardDisk,
+    /// A media type the specification does not define.
+    Other(u8),
+}
+
+impl Emulation {
+    fn from_media_type(media: u8) -> Self {
+        match media & 0x0F {
+            0 => Emulation::NoEmulation,
+            1 => Emulation::Floppy1200,
+            2 => Emulation::Floppy1440,
+            3 => Emulation::Floppy2880,
+            4 => Emulation::HardDisk,
+            other => Emulation::Other(other),
+        }
+    }
+
+    /// Size of the emulated disk, for the floppy types.
+    pub fn floppy_size(self) -> Option<u64> {
+        match self {
+            Emulation::Floppy1200 => Some(1_228_800),
+            Emulation::Floppy1440 => Some(1_474_560),
+            Emulation::Floppy2880 => Some(2_949_120),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Emulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Emulation::NoEmulation => f.write_str("no emulation"),
+            Emulation::Floppy1200 => f.write_str("1.2 MB floppy"),
+            Emulation::Floppy1440 => f.write_str("1.44 MB floppy"),
+            Emulation::Floppy2880 => f.write_str("2.88 MB floppy"),
+            Emulation::HardDisk => f.write_str("hard disk"),
+            Emulation::Other(media) => write!(f, "media type {media}"),
+        }
+    }
+}
+
+/// One bootable image: the default entry or a section entry (El Torito
+/// 2.2 and 2.4).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootEntry {
+    /// The platform of the validation entry for the default entry, of
+    /// the section header for a section entry.
+    pub platform: Platform,
+    /// Boot indicator `0x88`; `false` for `0x00`, "not bootable".
+    pub bootable: bool,
+    /// Boot media type.
+    pub emulation: Emulation,
+    /// Real-mode segment the image is loaded at. `0` means the
+    /// traditional `0x7C0`.
+    pub load_segment: u16,
+    /// Partition type byte from the image's partition table, for hard
+    /// disk emulation.
+    pub system_type: u8,
+    /// Number of 512-byte virtual sectors the BIOS loads.
+    pub sector_count: u16,
+    /// First 2048-byte sector of the image.
+    pub load_rba: u32,
+}
+
+impl BootEntry {
+    /// Byte offset of the image in the ISO.
+    pub fn image_offset(&self) -> u64 {
+        self.load_rba as u64 * SECTOR_SIZE
+    }
+
+    fn decode(raw: &[u8], platform: Platform) -> Option<Self> {
+        let bootable = match raw[0] {
+            0x88 => true,
+            0x00 => false,
+            _ => return None,
+        };
+        Some(BootEntry {
+            platform,
+            bootable,
+            emulation: Emulation::from_media_type(raw[1]),
+            load_segment: u16::from_le_bytes([raw[2], raw[3]]),
+            system_type: raw[4],
+            sector_count: u16::from_le_bytes([raw[6], raw[7]]),
+            load_rba: u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]),
+        })
+    }
+}
+
+/// A decoded boot catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootCatalog {
+    /// Byte offset of the catalog in the ISO.
+    pub offset: u64,
+    /// Platform of the validation entry.
+    pub platform: Platform,
+    /// Manufacturer or developer ID string of the validation entry,
+    /// trailing NULs and spaces removed.
+    pub id: String,
+    /// The default entry followed by every section entry, in catalog
+    /// order.
+    pub entries: Vec<BootEntry>,
+}
+
+/// Read the boot catalog of the last session of the ISO 9660 image
+/// `file`, or `None` if it has no El Torito Boot Record.
+///
+/// Errors if `file` is not ISO 9660 or if the catalog's validation entry
+/// or default entry is malformed.
+pub fn read_boot_catalog<R: Read + Seek>(file: &mut R) -> Result<Option<BootCatalog>> {
+    limits::metered(Format::Iso9660, None, file, |file| {
+        let mut src = Stream(file);
+        let start = iso9660::last_session(&mut src, &mut ())?;
+        let volume = iso9660::open_volume(&mut src, start, &mut ())?;
+        volume
+            .boot_catalog
+            .map(|sector| decode_catalog(&mut src, sector))
+            .transpose()
+    })
+}
+
+/// The boot catalog sector named by the Boot Record volume descriptor
+/// `vd`, or `None` if `vd` is some other boot system's.
+pub(crate) fn catalog_sector(vd: &[u8]) -> Option<u32> {
+    let id = vd.get(7..39)?;
+    let len = EL_TORITO_ID.len();
+    if &id[..len] != EL_TORITO_ID || id[len..].iter().any(|&b| b != 0) {
+        return None;
+    }
+    let pointer = vd.get(71..75)?;
+    Some(u32::from_le_bytes(pointer.try_into().ok()?))
+}
+
+/// Decode the boot catalog at `sector`.
+pub(crate) fn decode_catalog<'a, S: Source<'a>>(src: &mut S, sector: u32) -> Result<BootCatalog> {
+    let offset = sector as u64 * SECTOR_SIZE;
+    let first = src.bytes(offset, SECTOR_SIZE as usize)?;
+
+    // Validation entry (El Torito 2.1): header ID 1, key bytes 55 AA, and
+    // 16-bit words that sum to zero.
+    let validation = &first[..ENTRY_SIZE];
+    let checksum = validation.chunks_exact(2).fold(0u16, |sum, w| {
+        sum.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+    });
+    if validation[0] != 1 || validation[30..32] != [0x55, 0xAA] || checksum != 0 {
+        return Err(Error::corrupt_at(
+            Format::Iso9660,
+            offset,
+            "El Torito validation entry is invalid",
+        ));
+    }
+    let platform = Platform::from_id(validation[1]);
+    let id = String::from_utf8_lossy(&validation[4..28])
+        .trim_end_matches(['\0', ' '])
+        .to_string();
+
+    let default =
+        BootEntry::decode(&first[ENTRY_SIZE..2 * ENTRY_SIZE], platform).ok_or_else(|| {
+            Error::corrupt_at(
+                Format::Iso9660,
+                offset + ENTRY_SIZE as u64,
+                "El Torito default entry has an invalid boot indicator",
+            )
+        })?;
+    let mut entries = vec![default];
+
+    // Section headers (2.3), each followed by its section entries (2.4)
+    // and their extension entries. A catalog with only a default entry
+    // is followed by zeros.
+    let mut buffer = first.into_owned();
+    let mut at = 2 * ENTRY_SIZE;
+    let mut section: Option<(Platform, u16)> = None;
+    let mut last_section = false;
+    loop {
+        if at + ENTRY_SIZE > buffer.len() {
+            let read = buffer.len() as u64 / SECTOR_SIZE;
+            if read == MAX_CATALOG_SECTORS {
+                break;
+            }
+            limits::alloc(SECTOR_SIZE)?;
+            match src.bytes(offset + read * SECTOR_SIZE, SECTOR_SIZE as usize) {
+                Ok(next) => buffer.extend_from_slice(&next),
+                // A catalog in the last sector of the image.
+                Err(_) => break,
+            }
+        }
+        let raw = &buffer[at..at + ENTRY_SIZE];
+        at += ENTRY_SIZE;
+        match section {
+            Some((platform, left)) if left > 0 => match raw[0] {
+                // Section entry extension (2.5): more selection criteria.
+                0x44 => {}
+                _ => {
+                    let entry = BootEntry::decode(raw, platform).ok_or_else(|| {
+                        Error::corrupt_at(
+                            Format::Iso9660,
+                            offset + (at - ENTRY_SIZE) as u64,
+                            "El Torito section entry has an invalid boot indicator",
+                        )
+                    })?;
+                    limits::entry("boot image")?;
+                    entries.push(entry);
+                    section = Some((platform, left - 1));
+                }
+            },
+            _ if last_section => break,
+            _ => match raw[0] {
+                0x90 | 0x91 => {
+                    last_section = raw[0] == 0x91;
+                    let count = u16::from_le_bytes([raw[2], raw[3]]);
+                    section = Some((Platform::from_id(raw[1]), count));
+                }
+                // Extensions trailing the last entry of a section.
+                0x44 => {}
+                _ => break,
+            },
+        }
+    }
+
+    Ok(BootCatalog {
+        offset,
+        platform,
+        id,
+        entries,
+    })
+}
+
+/// Build the virtual `[BOOT]` directory for `catalog`, sizing each image
+/// as described on [`image_length`].
+pub(crate) fn boot_directory<'a, S: Source<'a>>(
+    src: &mut S,
+    catalog: &BootCatalog,
+    root: &TreeNode,
+) -> TreeNode<'static> {
+    let mut dir = TreeNode::new_directory(BOOT_DIRECTORY);
+    let mut seen: Vec<Platform> = Vec::new();
+    for entry in &catalog.entries {
+        let n = seen.iter().filter(|&&p| p == entry.platform).count();
+        seen.push(entry.platform);
+        let name = match n {
+            0 => format!("{}.img", entry.platform.stem()),
+            n => format!("{}-{}.img", entry.platform.stem(), n + 1),
+        };
+        let length = image_length(src, entry, root);
+        dir.add_child(TreeNode::new_file_with_location(
+            name,
+            length,
+            entry.image_offset(),
+            length,
+        ));
+    }
+    dir
+}
+
+/// How many bytes of the image an entry boots from.
+///
+/// The sector count in the catalog is what the BIOS loads, which for a
+/// no-emulation image is often only its first sector — and a `u16`, too
+/// small for an EFI system partition image. So:
+///
+/// - a floppy image is the emulated floppy's size;
+/// - a hard disk image ends where its last MBR partition does;
+/// - any other image is the ISO file that starts at the same sector when
+///   there is one, else the FAT volume it holds, else `sector_count`
+///   512-byte sectors.
+fn image_length<'a, S: Source<'a>>(src: &mut S, entry: &BootEntry, root: &TreeNode) -> u64 {
+    let loaded = entry.sector_count as u64 * 512;
+    if let Some(size) = entry.emulation.floppy_size() {
+        return size;
+    }
+    let Ok(first) = src.bytes(entry.image_offset(), 512) else {
+        return loaded;
+    };
+    let signed = first[510..512] == [0x55, 0xAA];
+    if entry.emulation == Emulation::HardDisk {
+        let end = (0..4)
+            .map(|i| {
+                let p = &first[446 + 16 * i..462 + 16 * i];
+                let start = u32::from_le_bytes([p[8], p[9], p[10], p[11]]) as u64;
+                let count = u32::from_le_bytes([p[12], p[13], p[14], p[15]]) as u64;
+                (start + count) * 512
+            })
+            .max()
+            .unwrap_or(0);
+        return if signed && end > 0 { end } else { loaded };
+    }
+    let file = root
+        .walk()
+        .map(|e| e.node)
+        .find(|n| !n.is_directory && n.file_location == Some(entry.image_offset()));
+    if let Some(file) = file {
+        return file.size;
+    }
+    if signed {
+        let bytes_per_sector = u16::from_le_bytes([first[11], first[12]]) as u64;
+        let small = u16::from_le_bytes([first[19], first[20]]) as u64;
+        let large = u32::from_le_bytes([first[32], first[33], first[34], first[35]]) as u64;
+        let sectors = if small != 0 { small } else { large };
+        if bytes_per_sector.is_power_of_two() && (512..=4096).contains(&bytes_per_sector) {
+            return sectors * bytes_per_sector;
+        }
+    }
+    loaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_io::source::Borrowed;
+
+    /// A catalog at sector 0 of `catalog`'s bytes: validation entry for
+    /// x86, then the given raw entries after the default one.
+    fn catalog(default: [u8; 32], rest: &[[u8; 32]]) -> Vec<u8> {
+        let mut validation = [0u8; 32];
+        validation[0] = 1;
+        validation[4..10].copy_from_slice(b"isomag");
+        validation[30] = 0x55;
+        validation[31] = 0xAA;
+        let sum = validation.chunks_exact(2).fold(0u16, |s, w| {
+            s.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+        });
+        validation[28..30].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+
+        let mut img = vec![0u8; SECTOR_SIZE as usize];
+        img[..32].copy_from_slice(&validation);
+        img[32..64].copy_from_slice(&default);
+        for (i, e) in rest.iter().enumerate() {
+            img[64 + 32 * i..96 + 32 * i].copy_from_slice(e);
+        }
+        img
+    }
+
+    fn entry(indicator: u8, media: u8, sectors: u16, rba: u32) -> [u8; 32] {
+        let mut e = [0u8; 32];
+        e[0] = indicator;
+        e[1] = media;
+        e[6..8].copy_from_slice(&sectors.to_le_bytes());
+        e[8..12].copy_from_slice(&rba.to_le_bytes());
+        e
+    }
+
+    fn header(indicator: u8, platform: u8, count: u16) -> [u8; 32] {
+        let mut h = [0u8; 32];
+        h[0] = indicator;
+        h[1] = platform;
+        h[2..4].copy_from_slice(&count.to_le_bytes());
+        h
+    }
+
+    #[test]
+    fn boot_record_names_the_catalog() {
+        let mut vd = vec![0u8; 2048];
+        vd[7..7 + EL_TORITO_ID.len()].copy_from_slice(EL_TORITO_ID);
+        vd[71..75].copy_from_slice(&33u32.to_le_bytes());
+        assert_eq!(catalog_sector(&vd), Some(33));
+        vd[7] = b'X';
+        assert_eq!(catalog_sector(&vd), None);
+    }
+
+    #[test]
+    fn decodes_default_and_section_entries() {
+        let img = catalog(
+            entry(0x88, 0, 4, 40),
+            &[
+                header(0x91, 0xEF, 2),
+                entry(0x88, 0, 0, 50),
+                entry(0x44, 0, 0, 0),
+                entry(0x00, 2, 1, 60),
+            ],
+        );
+        let cat = decode_catalog(&mut Borrowed(&img), 0).unwrap();
+        assert_eq!(cat.platform, Platform::X86);
+        assert_eq!(cat.id, "isomag");
+        assert_eq!(cat.entries.len(), 3);
+        assert_eq!(cat.entries[0].load_rba, 40);
+        assert_eq!(cat.entries[0].sector_count, 4);
+        assert_eq!(cat.entries[1].platform, Platform::Efi);
+        assert_eq!(cat.entries[1].emulation, Emulation::NoEmulation);
+        assert!(!cat.entries[2].bootable);
+        assert_eq!(cat.entries[2].emulation, Emulation::Floppy1440);
+        assert_eq!(cat.entries[2].image_offset(), 60 * 2048);
+    }
+
+    #[test]
+    fn default_entry_alone_is_a_catalog() {
+        let img = catalog(entry(0x88, 4, 1, 40), &[]);
+        let cat = decode_catalog(&mut Borrowed(&img), 0).unwrap();
+        assert_eq!(cat.entries.len(), 1);
+        assert_eq!(cat.entries[0].emulation, Emulation::HardDisk);
+    }
+
+    #[test]
+    fn bad_validation_entry_is_corrupt() {
+        let mut img = catalog(entry(0x88, 0, 4, 40), &[]);
+        img[28] ^= 1; // checksum
+        let err = decode_catalog(&mut Borrowed(&img), 0).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Corrupt {
+                    offset: Some(0),
+                    ..
+                }
+            ),
+            "{err}"
+        );
+
+        let img = catalog(entry(0x12, 0, 4, 40), &[]);
+        assert!(decode_catalog(&mut Borrowed(&img), 0).is_err());
+    }
+
+    #[test]
+    fn platform_ids_round_trip() {
+        for id in [0x00, 0x01, 0x02, 0xEF, 0x7F] {
+            assert_eq!(Platform::from_id(id).id(), id);
+        }
+        assert_eq!(Platform::from_id(0xEF).to_string(), "EFI");
+        assert_eq!(Platform::from_id(0x7F).stem(), "platform-7f");
+    }
+}
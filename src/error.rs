@@ -0,0 +1,210 @@
+//! The crate-wide error type.
+//!
+//! Every fallible public function returns [`Error`]. Each `formats::*`
+//! module keeps its own detailed error enum for its internals and
+//! converts into this one at the API boundary, so callers can sort
+//! failures into a handful of categories — the input is not an image at
+//! all, it is damaged, it uses something unsupported — without string
+//! matching.
+
+use crate::limits::{Limit, Violation};
+use crate::probe::Format;
+use std::fmt;
+use std::io;
+
+/// Why an isomage operation failed.
+///
+/// New variants may be added in minor releases; match with a wildcard
+/// arm. `Display` gives the same human-readable message the parser
+/// produced.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input is not an image this build can read. `format` is set
+    /// when a specific format was tried and its signature was absent.
+    NotRecognized {
+        format: Option<Format>,
+        message: String,
+    },
+    /// The format was recognized but one of its structures is malformed.
+    /// `offset` is the byte offset of the bad structure in the image,
+    /// where the parser knows it.
+    Corrupt {
+        format: Format,
+        offset: Option<u64>,
+        message: String,
+    },
+    /// The image is valid but uses something this crate does not
+    /// implement — a compression codec, a version, or a format whose
+    /// Cargo feature is disabled in this build.
+    Unsupported { format: Format, message: String },
+    /// The image, or the part of it requested, is encrypted.
+    Encrypted { format: Format },
+    /// [`extract_node`](crate::extract_node) refused an entry whose name
+    /// could escape the output directory.
+    UnsafePath { name: String, message: String },
+    /// A directory was passed where a file was expected.
+    NotAFile { name: String },
+    /// A file was passed where a directory was expected.
+    NotADirectory { name: String },
+    /// The file's bytes have no location in the image (for example a
+    /// compressed stream), so they cannot be read.
+    NoData { name: String },
+    /// The parse went over one of the caps in its
+    /// [`ParseOptions`](crate::limits::ParseOptions). `max` is the cap.
+    LimitExceeded {
+        format: Format,
+        limit: Limit,
+        max: u64,
+    },
+    /// An I/O error from the image or the output filesystem.
+    Io(io::Error),
+}
+
+/// The result type returned by every fallible public function in this crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn not_recognized(format: Format, message: impl fmt::Display) -> Self {
+        Error::NotRecognized {
+            format: Some(format),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn corrupt(format: Format, message: impl fmt::Display) -> Self {
+        Error::Corrupt {
+            format,
+            offset: None,
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn corrupt_at(format: Format, offset: u64, message: impl fmt::Display) -> Self {
+        Error::Corrupt {
+            format,
+            offset: Some(offset),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn unsupported(format: Format, message: impl fmt::Display) -> Self {
+        Error::Unsupported {
+            format,
+            message: message.to_string(),
+        }
+    }
+
+    /// Keep the category, replace the message. Used where several
+    /// parsers' failures are summarised into one error.
+    pub(crate) fn with_message(self, new: String) -> Self {
+        match self {
+            Error::NotRecognized { format, .. } => Error::NotRecognized {
+                format,
+                message: new,
+            },
+            Error::Corrupt { format, offset, .. } => Error::Corrupt {
+                format,
+                offset,
+                message: new,
+            },
+            Error::Unsupported { format, .. } => Error::Unsupported {
+                format,
+                message: new,
+            },
+            Error::UnsafePath { name, .. } => Error::UnsafePath { name, message: new },
+            Error::Io(e) => Error::Io(io::Error::new(e.kind(), new)),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotRecognized { message, .. }
+            | Error::Unsupported { message, .. }
+            | Error::UnsafePath { message, .. } => f.write_str(message),
+            Error::Corrupt {
+                offset: Some(offset),
+                message,
+                ..
+            } => write!(f, "{message} (at byte {offset})"),
+            Error::Corrupt { message, .. } => f.write_str(message),
+            Error::Encrypted { format } => write!(f, "{format} data is encrypted"),
+            Error::NotAFile { name } => write!(f, "'{name}' is a directory, not a file"),
+            Error::NotADirectory { name } => write!(f, "'{name}' is not a directory"),
+            Error::NoData { name } => {
+                write!(f, "File location information not available for '{name}'")
+            }
+            Error::LimitExceeded { format, limit, max } => {
+                write!(f, "{format} parse stopped: more than {max} {limit}")
+            }
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// A limit violation raised inside a parser arrives as an
+    /// `io::Error`; it becomes [`Error::LimitExceeded`] or
+    /// [`Error::Corrupt`] here.
+    fn from(e: io::Error) -> Self {
+        match Violation::of(&e) {
+            Some(v) => v.into_error(),
+            None => Error::Io(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_display_carries_offset() {
+        let e = Error::corrupt_at(Format::Iso9660, 34816, "Directory record too short");
+        assert_eq!(e.to_string(), "Directory record too short (at byte 34816)");
+        assert!(matches!(
+            e,
+            Error::Corrupt {
+                format: Format::Iso9660,
+                offset: Some(34816),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn with_message_keeps_category() {
+        let e = Error::unsupported(Format::Squashfs, "compressed").with_message("summary".into());
+        assert!(matches!(
+            e,
+            Error::Unsupported {
+                format: Format::Squashfs,
+                ..
+            }
+        ));
+        assert_eq!(e.to_string(), "summary");
+
+        let io = Error::from(io::Error::new(io::ErrorKind::NotFound, "x"));
+        let io = io.with_message("summary".into());
+        assert!(matches!(&io, Error::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        assert!(std::error::Error::source(&io).is_some());
+    }
+
+    #[test]
+    fn error_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Error>();
+    }
+}
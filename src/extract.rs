@@ -0,0 +1,1406 @@
+//! Extracting a file or subtree to disk.
+//!
+//! [`extract_node`](crate::extract_node) runs with the default
+//! [`ExtractOptions`]: overwrite existing files, restore nothing, write
+//! symlinks and hard links as regular files. [`extract_node_with`] takes
+//! explicit options and returns an [`ExtractSummary`] of what was done.
+//!
+//! [`extract_node_parallel`] does the same from several threads over a
+//! shared [`ReadAt`] image.
+//!
+//! None of them print anything; the `*_observed` variants report
+//! progress to an [`Observer`] instead. Every entry name is validated
+//! and every output path checked to stay inside the output directory;
+//! see [`extract_node`](crate::extract_node) for the rules.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::panic;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::image_io::ReadAt;
+use crate::observe::{Event, Observer};
+use crate::tree::{EntryKind, Extent, Metadata, TreeNode};
+use crate::{copy_extents, io_context, Error, Result};
+
+/// What to do when an entry's output path already exists.
+///
+/// Existing directories are always merged into; the policy applies to
+/// files, symlinks and hard links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnExisting {
+    /// Replace it. The old entry is removed first, so a symlink or hard
+    /// link in the way is never written through.
+    #[default]
+    Overwrite,
+    /// Leave it alone and count the entry in [`ExtractSummary::skipped`].
+    Skip,
+    /// Stop with an [`io::ErrorKind::AlreadyExists`] error.
+    Error,
+}
+
+/// Options for [`extract_node_with`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use isomage::{extract_node_with, open_any, ExtractOptions, OnExisting};
+///
+/// let mut file = File::open("backup.tar")?;
+/// let (root, _) = open_any(&mut file)?;
+/// let options = ExtractOptions::new()
+///     .on_existing(OnExisting::Skip)
+///     .restore_permissions(true)
+///     .restore_times(true)
+///     .symlinks(true)
+///     .exclude("**/*.log");
+/// let summary = extract_node_with(&mut file, &root, "/tmp/restore", &options)?;
+/// println!("{} files, {} bytes", summary.files, summary.bytes);
+/// # Ok::<(), isomage::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    on_existing: OnExisting,
+    permissions: bool,
+    times: bool,
+    symlinks: bool,
+    hard_links: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    dry_run: bool,
+    threads: usize,
+}
+
+impl ExtractOptions {
+    /// The defaults: overwrite, restore nothing, no links, no filters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy for output paths that already exist.
+    pub fn on_existing(mut self, policy: OnExisting) -> Self {
+        self.on_existing = policy;
+        self
+    }
+
+    /// Apply each entry's [`Metadata::mode`] where the format records
+    /// one. Only the `0o777` bits are applied — setuid, setgid and
+    /// sticky are never restored. Off platforms without POSIX modes,
+    /// only the owner-write bit is honoured, as the read-only flag.
+    pub fn restore_permissions(mut self, yes: bool) -> Self {
+        self.permissions = yes;
+        self
+    }
+
+    /// Apply each entry's [`Metadata::mtime`] where the format records
+    /// one. Directory times are restored on Unix only.
+    pub fn restore_times(mut self, yes: bool) -> Self {
+        self.times = yes;
+        self
+    }
+
+    /// Create [`EntryKind::Symlink`] entries as symbolic links instead
+    /// of regular files. A link whose target is absolute, or whose
+    /// relative target would resolve outside the output directory, is
+    /// refused with [`Error::UnsafePath`]. So is a link whose target
+    /// passes through a symlink — one already in the output directory
+    /// or one this extraction created — and a link created where an
+    /// earlier link's target passes, since either could chain out of the
+    /// output directory (`x -> ..` with `y -> x/../..`).
+    pub fn symlinks(mut self, yes: bool) -> Self {
+        self.symlinks = yes;
+        self
+    }
+
+    /// Create [`EntryKind::Hardlink`] entries (TAR) as hard links to the
+    /// already-extracted entry they name. The target path is taken
+    /// relative to the output directory, so extract from the tree root;
+    /// it must be a regular file reached without passing through a
+    /// symlink.
+    pub fn hard_links(mut self, yes: bool) -> Self {
+        self.hard_links = yes;
+        self
+    }
+
+    /// Extract only entries matching `pattern`. May be given several
+    /// times; an entry is extracted if any pattern matches its path or
+    /// the path of a directory above it.
+    ///
+    /// Patterns match `/`-separated paths relative to the extracted
+    /// node: `*` and `?` match within one component, and a `**`
+    /// component matches any number of components.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Skip entries matching `pattern` (same syntax as
+    /// [`include`](Self::include)). A matching directory is skipped
+    /// with everything under it. Exclusion wins over inclusion.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Run every check — names, containment, existing files, data
+    /// locations — and count what would be written, without touching
+    /// the filesystem.
+    pub fn dry_run(mut self, yes: bool) -> Self {
+        self.dry_run = yes;
+        self
+    }
+
+    /// Number of worker threads for [`extract_node_parallel`]. `0`, the
+    /// default, uses [`std::thread::available_parallelism`]. The serial
+    /// entry points ignore it.
+    pub fn threads(mut self, count: usize) -> Self {
+        self.threads = count;
+        self
+    }
+
+    fn excluded(&self, path: &str) -> bool {
+        self.exclude.iter().any(|p| glob_match(p, path))
+    }
+
+    fn included(&self, path: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|p| glob_match(p, path))
+    }
+}
+
+/// What [`extract_node_with`] did, or would do under
+/// [`ExtractOptions::dry_run`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExtractSummary {
+    /// Regular files written.
+    pub files: u64,
+    /// Directories created or merged into.
+    pub directories: u64,
+    /// Symbolic links created.
+    pub symlinks: u64,
+    /// Hard links created.
+    pub hard_links: u64,
+    /// Entries left alone because their path existed and the policy is
+    /// [`OnExisting::Skip`].
+    pub skipped: u64,
+    /// Entries left out by the include / exclude patterns.
+    pub filtered: u64,
+    /// File bytes written.
+    pub bytes: u64,
+}
+
+/// Extract `node` (a file or a directory subtree) to `output_path` with
+/// `options`.
+///
+/// The safety rules of [`extract_node`](crate::extract_node) apply
+/// unchanged. Extraction stops at the first error; entries written
+/// before it stay on disk.
+pub fn extract_node_with<R: Read + Seek>(
+    file: &mut R,
+    node: &TreeNode,
+    output_path: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractSummary> {
+    extract_node_observed(file, node, output_path, options, &mut ())
+}
+
+/// Like [`extract_node_with`], but reports each directory, entry and
+/// copied chunk to `observer`. On a dry run the directory and entry
+/// events still arrive, so they list what would be written.
+pub fn extract_node_observed<R: Read + Seek>(
+    file: &mut R,
+    node: &TreeNode,
+    output_path: &str,
+    options: &ExtractOptions,
+    observer: &mut dyn Observer,
+) -> Result<ExtractSummary> {
+    let root = output_root(output_path, options)?;
+    let mut job = Extraction {
+        data: Data::Stream(file),
+        options,
+        observer,
+        root: &root,
+        links: Links::default(),
+        summary: ExtractSummary::default(),
+    };
+    job.run(node)?;
+    Ok(job.summary)
+}
+
+/// Like [`extract_node_with`], but writes file data from several
+/// threads at once.
+///
+/// `image` is shared by up to [`ExtractOptions::threads`] workers, each
+/// reading through [`ReadAt`] — pass a `&File` for `pread`, or an
+/// `image_io::MmapImage` (with `--features mmap`). Names, containment and
+/// existing paths are checked, and directories and symlinks created,
+/// on the calling thread before any file data is written. Hard links
+/// and directory permissions and times follow once every file is done.
+/// What ends up on disk is the same as with [`extract_node_with`].
+///
+/// Errors do not depend on thread timing: the one returned is the error
+/// the serial extraction would have stopped at, except that hard links
+/// are only attempted after every file has been written. Files after
+/// the failing entry may or may not be on disk.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use isomage::{detect_and_parse_filesystem, extract_node_parallel, ExtractOptions};
+///
+/// let mut file = File::open("bluray.iso")?;
+/// let root = detect_and_parse_filesystem(&mut file, "bluray.iso")?;
+/// let options = ExtractOptions::new().threads(8);
+/// let summary = extract_node_parallel(&file, &root, "/tmp/bluray", &options)?;
+/// println!("{} files, {} bytes", summary.files, summary.bytes);
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn extract_node_parallel<R: ReadAt + Sync + ?Sized>(
+    image: &R,
+    node: &TreeNode,
+    output_path: &str,
+    options: &ExtractOptions,
+) -> Result<ExtractSummary> {
+    extract_node_parallel_observed(image, node, output_path, options, &mut ())
+}
+
+/// Like [`extract_node_parallel`], but reports progress to `observer`.
+///
+/// Events are delivered on the calling thread, never from a worker.
+/// Each file's `EntryStarted`, `BytesCopied` and `EntryFinished` arrive
+/// in order, but events for different files interleave.
+pub fn extract_node_parallel_observed<R: ReadAt + Sync + ?Sized>(
+    image: &R,
+    node: &TreeNode,
+    output_path: &str,
+    options: &ExtractOptions,
+    observer: &mut dyn Observer,
+) -> Result<ExtractSummary> {
+    let root = output_root(output_path, options)?;
+    let mut job = Extraction {
+        data: Data::Deferred(Deferred::default()),
+        options,
+        observer,
+        root: &root,
+        links: Links::default(),
+        summary: ExtractSummary::default(),
+    };
+    // Files queued before a failed check come earlier in tree order, so
+    // write them first: one of them failing is the error serial
+    // extraction would have reported.
+    let planned = job.run(node);
+    let Data::Deferred(deferred) = mem::replace(&mut job.data, Data::Deferred(Deferred::default()))
+    else {
+        unreachable!("parallel extraction always defers");
+    };
+    let files: Vec<FileJob<'_>> = deferred.files.into_iter().flatten().collect();
+    write_files(image, &files, options, job.observer)?;
+    planned?;
+
+    for (node, link, target) in deferred.hard_links {
+        job.hard_link(node, link, &target)?;
+    }
+    for (metadata, target) in deferred.directories {
+        job.restore_directory(metadata, &target)?;
+    }
+    Ok(job.summary)
+}
+
+/// Create (or, on a dry run, just resolve) the output directory.
+fn output_root(output_path: &str, options: &ExtractOptions) -> Result<PathBuf> {
+    if options.dry_run {
+        return Ok(fs::canonicalize(output_path).unwrap_or_else(|_| PathBuf::from(output_path)));
+    }
+    fs::create_dir_all(output_path).map_err(|e| {
+        io_context(
+            e,
+            format!("cannot create output directory '{}'", output_path),
+        )
+    })?;
+    fs::canonicalize(output_path).map_err(|e| {
+        io_context(
+            e,
+            format!("cannot canonicalize output directory '{}'", output_path),
+        )
+    })
+}
+
+/// Object-safe `Read + Seek`, so [`Extraction`] needn't be generic.
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Where file data goes during the tree walk.
+enum Data<'a> {
+    /// Copy each file as it is reached.
+    Stream(&'a mut dyn ReadSeek),
+    /// Queue files for the workers; see [`extract_node_parallel`].
+    Deferred(Deferred<'a>),
+}
+
+/// Work the parallel walk leaves for after the tree has been checked.
+#[derive(Default)]
+struct Deferred<'a> {
+    /// Files in tree order. `None` marks one replaced by a later entry
+    /// with the same output path.
+    files: Vec<Option<FileJob<'a>>>,
+    /// Index into `files` of each queued output path.
+    queued: HashMap<PathBuf, usize>,
+    hard_links: Vec<(&'a TreeNode<'a>, &'a str, PathBuf)>,
+    /// Directories whose metadata to restore, children before parents.
+    directories: Vec<(&'a Metadata, PathBuf)>,
+}
+
+struct FileJob<'a> {
+    node: &'a TreeNode<'a>,
+    extents: Cow<'a, [Extent]>,
+    length: u64,
+    target: PathBuf,
+}
+
+struct Extraction<'a> {
+    data: Data<'a>,
+    options: &'a ExtractOptions,
+    observer: &'a mut dyn Observer,
+    root: &'a Path,
+    links: Links,
+    summary: ExtractSummary,
+}
+
+/// The symlinks an extraction has created, and every path their targets
+/// pass through on the way.
+#[derive(Default)]
+struct Links {
+    created: HashSet<PathBuf>,
+    traversed: HashSet<PathBuf>,
+}
+
+impl<'a> Extraction<'a> {
+    fn run(&mut self, node: &'a TreeNode<'a>) -> Result<()> {
+        let root = self.root;
+        // The synthetic root node ("/") is the tree root from the parser. We
+        // don't want to create a literal "/" subdirectory in the destination —
+        // its children become the top level instead.
+        if node.is_directory && node.name == "/" {
+            for child in &node.children {
+                self.entry(child, root, "", false)?;
+            }
+            Ok(())
+        } else {
+            self.entry(node, root, "", false)
+        }
+    }
+
+    /// Extract `node` into the directory `here`. `parent` is the
+    /// pattern path of `here`; `included` is set once an include
+    /// pattern has matched a directory above.
+    fn entry(
+        &mut self,
+        node: &'a TreeNode<'a>,
+        here: &Path,
+        parent: &str,
+        included: bool,
+    ) -> Result<()> {
+        let target = safe_join(self.root, here, &node.name)?;
+        let path = if parent.is_empty() {
+            node.name.to_string()
+        } else {
+            format!("{}/{}", parent, node.name)
+        };
+        if self.options.excluded(&path) {
+            self.summary.filtered += 1;
+            return Ok(());
+        }
+        let included = included || self.options.included(&path);
+
+        if node.is_directory {
+            if !included && !self.wants_any(node, &path) {
+                self.summary.filtered += 1;
+                return Ok(());
+            }
+            return self.directory(node, &target, &path, included);
+        }
+        if !included {
+            self.summary.filtered += 1;
+            return Ok(());
+        }
+        match (node.metadata.kind, node.metadata.link_target.as_deref()) {
+            (EntryKind::Symlink, Some(link)) if self.options.symlinks => {
+                self.symlink(node, link, here, &target)
+            }
+            (EntryKind::Hardlink, Some(link)) if self.options.hard_links => match &mut self.data {
+                Data::Deferred(deferred) if !self.options.dry_run => {
+                    deferred.hard_links.push((node, link, target));
+                    Ok(())
+                }
+                _ => self.hard_link(node, link, &target),
+            },
+            _ => self.file(node, here, &target),
+        }
+    }
+
+    /// Whether an include pattern matches anything under `dir`.
+    fn wants_any(&self, dir: &TreeNode, path: &str) -> bool {
+        dir.children.iter().any(|child| {
+            let path = format!("{}/{}", path, child.name);
+            !self.options.excluded(&path)
+                && (self.options.included(&path)
+                    || (child.is_directory && self.wants_any(child, &path)))
+        })
+    }
+
+    fn directory(
+        &mut self,
+        node: &'a TreeNode<'a>,
+        target: &Path,
+        path: &str,
+        included: bool,
+    ) -> Result<()> {
+        match fs::symlink_metadata(target) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(unsafe_path(
+                    &node.name,
+                    format!(
+                        "refusing to extract through existing symlink {}",
+                        target.display()
+                    ),
+                ));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(io_context(e, format!("cannot stat '{}'", target.display()))),
+        }
+        if !self.options.dry_run {
+            fs::create_dir_all(target)
+                .map_err(|e| io_context(e, format!("cannot create '{}'", target.display())))?;
+        }
+        self.summary.directories += 1;
+        self.observer
+            .event(&Event::DirectoryCreated { path: target });
+
+        for child in &node.children {
+            self.entry(child, target, path, included)?;
+        }
+        if self.options.dry_run {
+            return Ok(());
+        }
+        if let Data::Deferred(deferred) = &mut self.data {
+            deferred
+                .directories
+                .push((&node.metadata, target.to_path_buf()));
+            Ok(())
+        } else {
+            self.restore_directory(&node.metadata, target)
+        }
+    }
+
+    fn file(&mut self, node: &'a TreeNode<'a>, here: &Path, target: &Path) -> Result<()> {
+        let extents = node.data_extents().ok_or_else(|| Error::NoData {
+            name: node.name.to_string(),
+        })?;
+        let length = node.file_length.unwrap_or(node.size);
+        if !self.make_room(target)? {
+            return Ok(());
+        }
+        self.summary.files += 1;
+        self.summary.bytes += length;
+        if self.options.dry_run {
+            self.observer.event(&Event::EntryStarted {
+                path: target,
+                length,
+            });
+            self.finished(target, length);
+            return Ok(());
+        }
+
+        fs::create_dir_all(here)?;
+        let file = match &mut self.data {
+            Data::Stream(file) => &mut **file,
+            Data::Deferred(deferred) => {
+                deferred
+                    .queued
+                    .insert(target.to_path_buf(), deferred.files.len());
+                deferred.files.push(Some(FileJob {
+                    node,
+                    extents,
+                    length,
+                    target: target.to_path_buf(),
+                }));
+                return Ok(());
+            }
+        };
+        self.observer.event(&Event::EntryStarted {
+            path: target,
+            length,
+        });
+        let observer = &mut *self.observer;
+        write_file(file, node, &extents, length, target, self.options, |done| {
+            observer.event(&Event::BytesCopied {
+                path: target,
+                done,
+                total: length,
+            })
+        })?;
+        self.finished(target, length);
+        Ok(())
+    }
+
+    fn symlink(&mut self, node: &TreeNode, link: &str, here: &Path, target: &Path) -> Result<()> {
+        let depth = here
+            .strip_prefix(self.root)
+            .map_or(0, |p| p.components().count());
+        if !link_stays_inside(link, depth) {
+            return Err(unsafe_path(
+                &node.name,
+                format!(
+                    "refusing to create symlink '{}' -> '{}' pointing outside the output directory",
+                    node.name, link
+                ),
+            ));
+        }
+        let route = link_route(here, link);
+        let through = route
+            .iter()
+            .any(|dir| self.links.created.contains(dir) || is_symlink(dir));
+        if through || self.links.traversed.contains(target) {
+            return Err(unsafe_path(
+                &node.name,
+                format!(
+                    "refusing to create symlink '{}' -> '{}' chained through another symlink",
+                    node.name, link
+                ),
+            ));
+        }
+        if !self.make_room(target)? {
+            return Ok(());
+        }
+        self.observer.event(&Event::EntryStarted {
+            path: target,
+            length: 0,
+        });
+        if !self.options.dry_run {
+            create_symlink(link, target).map_err(|e| {
+                io_context(e, format!("cannot create symlink '{}'", target.display()))
+            })?;
+        }
+        // The link's own directories count as passed through too: a
+        // symlink put there later would move what it points at.
+        let ancestors = here
+            .ancestors()
+            .take_while(|dir| dir.starts_with(self.root) && *dir != self.root);
+        self.links
+            .traversed
+            .extend(route.into_iter().chain(ancestors.map(Path::to_path_buf)));
+        self.links.created.insert(target.to_path_buf());
+        self.finished(target, 0);
+        self.summary.symlinks += 1;
+        Ok(())
+    }
+
+    fn hard_link(&mut self, node: &TreeNode, link: &str, target: &Path) -> Result<()> {
+        let source = self.link_source(node, link)?;
+        if !self.make_room(target)? {
+            return Ok(());
+        }
+        self.observer.event(&Event::EntryStarted {
+            path: target,
+            length: 0,
+        });
+        if !self.options.dry_run {
+            fs::hard_link(&source, target).map_err(|e| {
+                io_context(e, format!("cannot create hard link '{}'", target.display()))
+            })?;
+        }
+        self.finished(target, 0);
+        self.summary.hard_links += 1;
+        Ok(())
+    }
+
+    fn finished(&mut self, path: &Path, bytes: u64) {
+        self.observer.event(&Event::EntryFinished { path, bytes });
+    }
+
+    /// Resolve a hard link's archive path under the output root,
+    /// refusing anything that leaves it or passes through a symlink.
+    fn link_source(&self, node: &TreeNode, link: &str) -> Result<PathBuf> {
+        let refuse = |why: &str| {
+            unsafe_path(
+                &node.name,
+                format!(
+                    "refusing to create hard link '{}' -> '{}': {}",
+                    node.name, link, why
+                ),
+            )
+        };
+        let mut source = self.root.to_path_buf();
+        for component in Path::new(link.trim_start_matches('/')).components() {
+            match component {
+                Component::Normal(part) => source.push(part),
+                Component::CurDir => {}
+                _ => return Err(refuse("target leaves the output directory")),
+            }
+            if self.options.dry_run {
+                continue;
+            }
+            match fs::symlink_metadata(&source) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    return Err(refuse("target passes through a symlink"));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(io_context(
+                        e,
+                        format!("hard link target '{}' not extracted", link),
+                    ));
+                }
+            }
+        }
+        if !self.options.dry_run && !fs::symlink_metadata(&source)?.is_file() {
+            return Err(refuse("target is not a regular file"));
+        }
+        Ok(source)
+    }
+
+    /// Apply the [`OnExisting`] policy to `target`. Returns `false` if
+    /// the entry should be skipped.
+    fn make_room(&mut self, target: &Path) -> Result<bool> {
+        // A file queued for the workers isn't on disk yet, but counts as
+        // existing: otherwise a later entry could land first, or a
+        // symlink could be created where a worker will write.
+        if let Data::Deferred(deferred) = &mut self.data {
+            if let Some(&index) = deferred.queued.get(target) {
+                if self.options.on_existing == OnExisting::Overwrite {
+                    deferred.files[index] = None;
+                    deferred.queued.remove(target);
+                    return Ok(true);
+                }
+                return self.existing(target, false);
+            }
+        }
+        match fs::symlink_metadata(target) {
+            Ok(meta) => self.existing(target, meta.is_dir()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(io_context(e, format!("cannot stat '{}'", target.display()))),
+        }
+    }
+
+    fn existing(&mut self, target: &Path, is_dir: bool) -> Result<bool> {
+        match self.options.on_existing {
+            OnExisting::Skip => {
+                self.summary.skipped += 1;
+                Ok(false)
+            }
+            OnExisting::Error => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", target.display()),
+            )
+            .into()),
+            OnExisting::Overwrite if is_dir => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' is an existing directory", target.display()),
+            )
+            .into()),
+            OnExisting::Overwrite => {
+                if !self.options.dry_run {
+                    fs::remove_file(target).map_err(|e| {
+                        io_context(e, format!("cannot replace '{}'", target.display()))
+                    })?;
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    /// Directory times and modes are applied after the children are
+    /// written, so neither is disturbed by — nor blocks — extraction.
+    fn restore_directory(&self, metadata: &Metadata, target: &Path) -> Result<()> {
+        #[cfg(unix)]
+        if self.options.times {
+            if let Some(mtime) = metadata.mtime {
+                fs::File::open(target)?.set_modified(mtime.to_system_time())?;
+            }
+        }
+        restore_permissions(self.options, metadata, target)
+    }
+}
+
+/// Write one file's data to `target`, then its time and mode as
+/// `options` ask. `progress` gets the running byte count after each
+/// chunk.
+fn write_file<R: Read + Seek + ?Sized>(
+    file: &mut R,
+    node: &TreeNode,
+    extents: &[Extent],
+    length: u64,
+    target: &Path,
+    options: &ExtractOptions,
+    mut progress: impl FnMut(u64),
+) -> Result<()> {
+    let mut output = fs::File::create(target)
+        .map_err(|e| io_context(e, format!("cannot create '{}'", target.display())))?;
+    let mut done = 0;
+    copy_extents(file, extents, length, |buf| {
+        output.write_all(buf)?;
+        done += buf.len() as u64;
+        progress(done);
+        Ok(true)
+    })?;
+    if options.times {
+        if let Some(mtime) = node.metadata.mtime {
+            output.set_modified(mtime.to_system_time())?;
+        }
+    }
+    drop(output);
+    restore_permissions(options, &node.metadata, target)
+}
+
+fn restore_permissions(options: &ExtractOptions, metadata: &Metadata, target: &Path) -> Result<()> {
+    let Some(mode) = metadata.mode.filter(|_| options.permissions) else {
+        return Ok(());
+    };
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        fs::Permissions::from_mode(mode & 0o777)
+    };
+    #[cfg(not(unix))]
+    let permissions = {
+        let mut permissions = fs::metadata(target)?.permissions();
+        permissions.set_readonly(mode & 0o200 == 0);
+        permissions
+    };
+    fs::set_permissions(target, permissions).map_err(|e| {
+        io_context(
+            e,
+            format!("cannot set permissions on '{}'", target.display()),
+        )
+    })
+}
+
+/// A worker's report about `files[index]`, forwarded to the observer by
+/// the calling thread.
+enum Progress {
+    Started(usize),
+    Copied(usize, u64),
+    Finished(usize),
+}
+
+/// Write `files` from a pool of workers and return the failure with the
+/// lowest index, if any.
+///
+/// Workers claim indices in increasing order and stop claiming once an
+/// index above a known failure comes up. Every file before the first
+/// failure is therefore attempted, so which error wins does not depend
+/// on scheduling.
+fn write_files<R: ReadAt + Sync + ?Sized>(
+    image: &R,
+    files: &[FileJob<'_>],
+    options: &ExtractOptions,
+    observer: &mut dyn Observer,
+) -> Result<()> {
+    let workers = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(files.len());
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(usize::MAX);
+    let (sender, receiver) = mpsc::channel();
+
+    let failure = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let (next, failed, sender) = (&next, &failed, sender.clone());
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= files.len() || index > failed.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let job = &files[index];
+                    // A send only fails once the receiver is gone,
+                    // which happens after every worker has finished.
+                    let _ = sender.send(Progress::Started(index));
+                    let mut reader = Positioned { image, offset: 0 };
+                    let written = write_file(
+                        &mut reader,
+                        job.node,
+                        &job.extents,
+                        job.length,
+                        &job.target,
+                        options,
+                        |done| {
+                            let _ = sender.send(Progress::Copied(index, done));
+                        },
+                    );
+                    match written {
+                        Ok(()) => {
+                            let _ = sender.send(Progress::Finished(index));
+                        }
+                        Err(e) => {
+                            failed.fetch_min(index, Ordering::Relaxed);
+                            return Some((index, e));
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        for progress in receiver {
+            let event = match progress {
+                Progress::Started(i) => Event::EntryStarted {
+                    path: &files[i].target,
+                    length: files[i].length,
+                },
+                Progress::Copied(i, done) => Event::BytesCopied {
+                    path: &files[i].target,
+                    done,
+                    total: files[i].length,
+                },
+                Progress::Finished(i) => Event::EntryFinished {
+                    path: &files[i].target,
+                    bytes: files[i].length,
+                },
+            };
+            observer.event(&event);
+        }
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap_or_else(|p| panic::resume_unwind(p)))
+            .min_by_key(|&(index, _)| index)
+    });
+    match failure {
+        Some((_, e)) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// `Read + Seek` over a shared [`ReadAt`], one per worker.
+///
+/// Reads are all-or-nothing: a read running past the end of the image
+/// fails instead of coming back short, which is all
+/// [`copy_extents`] needs.
+struct Positioned<'a, R: ?Sized> {
+    image: &'a R,
+    offset: u64,
+}
+
+impl<R: ReadAt + ?Sized> Read for Positioned<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.image.read_exact_at(buf, self.offset)?;
+        self.offset += buf.len() as u64;
+        Ok(buf.len())
+    }
+}
+
+impl<R: ReadAt + ?Sized> Seek for Positioned<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek from the end of a positional reader",
+                ))
+            }
+        };
+        self.offset = offset.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of image")
+        })?;
+        Ok(self.offset)
+    }
+}
+
+fn unsafe_path(name: &str, message: String) -> Error {
+    Error::UnsafePath {
+        name: name.to_string(),
+        message,
+    }
+}
+
+/// Reject names that, if joined to a parent path, could escape it or
+/// produce ambiguous filesystem behaviour.
+///
+/// Conservative on purpose: rejects anything ISO/UDF parsers could
+/// possibly stamp into a `TreeNode.name` that the host filesystem would
+/// then interpret as something other than a single in-directory entry.
+pub(crate) fn validate_entry_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(unsafe_path(
+            name,
+            format!("refusing to extract entry with unsafe name {:?}", name),
+        ));
+    }
+    if name.contains('/') || name.contains('\\') || name.contains('\0') {
+        return Err(unsafe_path(
+            name,
+            format!(
+                "refusing to extract entry whose name contains a path separator or NUL byte: {:?}",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Compute the on-disk target for `name` inside `here`, verifying the
+/// result stays under `root`. Defense in depth: even with
+/// `validate_entry_name` already called, we re-check `starts_with(root)`
+/// in case some future caller bypasses validation.
+pub(crate) fn safe_join(root: &Path, here: &Path, name: &str) -> Result<PathBuf> {
+    validate_entry_name(name)?;
+    let target = here.join(name);
+    if !target.starts_with(root) {
+        return Err(unsafe_path(
+            name,
+            format!(
+                "path escape: entry '{}' would write outside output directory {}",
+                name,
+                root.display()
+            ),
+        ));
+    }
+    Ok(target)
+}
+
+/// Whether a relative symlink target, resolved from a directory `depth`
+/// components below the output root, stays inside it. Lexical only: a
+/// target that passes through another symlink is caught with
+/// [`link_route`].
+fn link_stays_inside(link: &str, depth: usize) -> bool {
+    if link.is_empty() || link.contains('\0') {
+        return false;
+    }
+    let mut depth = depth;
+    for component in Path::new(link).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/// The paths a relative symlink target, resolved lexically from `here`,
+/// goes through before its last component — including those a later
+/// `..` steps back out of. Call after [`link_stays_inside`].
+fn link_route(here: &Path, link: &str) -> Vec<PathBuf> {
+    let mut at = here.to_path_buf();
+    let mut route = Vec::new();
+    let mut components = Path::new(link).components().peekable();
+    while let Some(component) = components.next() {
+        match component {
+            Component::Normal(name) => {
+                at.push(name);
+                if components.peek().is_some() {
+                    route.push(at.clone());
+                }
+            }
+            Component::ParentDir => {
+                at.pop();
+            }
+            _ => {}
+        }
+    }
+    route
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+#[cfg(unix)]
+fn create_symlink(link: &str, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(windows)]
+fn create_symlink(link: &str, target: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(link, target)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_link: &str, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symlinks are not supported on this platform",
+    ))
+}
+
+/// Case-sensitive [`walk::glob_match`](crate::walk::glob_match), as
+/// include and exclude patterns use it.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    crate::walk::glob_match(pattern, path, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Timestamp;
+    use std::io::Cursor;
+
+    /// `a.txt`, `docs/b.md`, `docs/c.log`, all backed by bytes of
+    /// `DATA`.
+    const DATA: &[u8] = b"0123456789";
+
+    fn tree() -> TreeNode<'static> {
+        let file = |name: &str| {
+            TreeNode::new_file_with_location(name.to_string(), 10, 0, 10).with_metadata(Metadata {
+                mode: Some(0o640),
+                mtime: Some(Timestamp::new(946_684_800, 0)),
+                ..Metadata::new(EntryKind::File)
+            })
+        };
+        let mut root = TreeNode::new_directory("/".to_string());
+        root.add_child(file("a.txt"));
+        let mut docs = TreeNode::new_directory("docs".to_string());
+        docs.add_child(file("b.md"));
+        docs.add_child(file("c.log"));
+        root.add_child(docs);
+        root
+    }
+
+    fn link(name: &str, kind: EntryKind, target: &str) -> TreeNode<'static> {
+        TreeNode::new_file_with_location(name.to_string(), 0, 0, 0).with_metadata(Metadata {
+            link_target: Some(target.to_string()),
+            ..Metadata::new(kind)
+        })
+    }
+
+    fn extract(root: &TreeNode, out: &Path, options: &ExtractOptions) -> Result<ExtractSummary> {
+        extract_node_with(&mut Cursor::new(DATA), root, out.to_str().unwrap(), options)
+    }
+
+    fn extract_parallel(
+        root: &TreeNode,
+        out: &Path,
+        options: &ExtractOptions,
+    ) -> Result<ExtractSummary> {
+        extract_node_parallel(DATA, root, out.to_str().unwrap(), options)
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*.md", "b.md"));
+        assert!(!glob_match("*.md", "docs/b.md"));
+        assert!(glob_match("**/*.md", "docs/b.md"));
+        assert!(glob_match("**/*.md", "b.md"));
+        assert!(glob_match("docs/**", "docs/x/y"));
+        assert!(glob_match("d?cs", "docs"));
+        assert!(!glob_match("docs", "docs/b.md"));
+    }
+
+    #[test]
+    fn link_containment() {
+        assert!(link_stays_inside("b.txt", 0));
+        assert!(link_stays_inside("../b.txt", 1));
+        assert!(!link_stays_inside("../b.txt", 0));
+        assert!(!link_stays_inside("a/../../b", 0));
+        assert!(!link_stays_inside("/etc/passwd", 3));
+    }
+
+    #[test]
+    fn existing_file_policies() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a.txt"), b"old").unwrap();
+
+        let skip = ExtractOptions::new().on_existing(OnExisting::Skip);
+        let summary = extract(&tree(), tmp.path(), &skip).unwrap();
+        assert_eq!((summary.files, summary.skipped), (2, 1));
+        assert_eq!(std::fs::read(tmp.path().join("a.txt")).unwrap(), b"old");
+
+        let error = ExtractOptions::new().on_existing(OnExisting::Error);
+        let err = extract(&tree(), tmp.path(), &error).unwrap_err();
+        assert!(matches!(&err, Error::Io(e) if e.kind() == io::ErrorKind::AlreadyExists));
+
+        extract(&tree(), tmp.path(), &ExtractOptions::new()).unwrap();
+        assert_eq!(std::fs::read(tmp.path().join("a.txt")).unwrap(), DATA);
+    }
+
+    #[test]
+    fn filters_and_dry_run() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let options = ExtractOptions::new()
+            .include("docs")
+            .exclude("**/*.log")
+            .dry_run(true);
+        let summary = extract(&tree(), tmp.path(), &options).unwrap();
+        assert_eq!((summary.files, summary.directories), (1, 1));
+        assert_eq!((summary.filtered, summary.bytes), (2, 10));
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+
+        let summary = extract(&tree(), tmp.path(), &options.dry_run(false)).unwrap();
+        assert_eq!(summary.files, 1);
+        assert!(tmp.path().join("docs/b.md").exists());
+        assert!(!tmp.path().join("docs/c.log").exists());
+        assert!(!tmp.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn observer_sees_every_step() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut events = Vec::new();
+        let summary = extract_node_observed(
+            &mut Cursor::new(DATA),
+            &tree(),
+            tmp.path().to_str().unwrap(),
+            &ExtractOptions::new().exclude("a.txt"),
+            &mut |event: &Event<'_>| {
+                let name = |p: &Path| p.file_name().unwrap().to_string_lossy().into_owned();
+                events.push(match event {
+                    Event::DirectoryCreated { path } => format!("dir {}", name(path)),
+                    Event::EntryStarted { path, length } => {
+                        format!("start {} {length}", name(path))
+                    }
+                    Event::BytesCopied { done, total, .. } => format!("copied {done}/{total}"),
+                    Event::EntryFinished { path, bytes } => format!("end {} {bytes}", name(path)),
+                    _ => "other".to_string(),
+                });
+            },
+        )
+        .unwrap();
+        assert_eq!(summary.files, 2);
+        assert_eq!(
+            events,
+            [
+                "dir docs",
+                "start b.md 10",
+                "copied 10/10",
+                "end b.md 10",
+                "start c.log 10",
+                "copied 10/10",
+                "end c.log 10",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_mode_and_mtime() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::TempDir::new().unwrap();
+        let options = ExtractOptions::new()
+            .restore_permissions(true)
+            .restore_times(true);
+        extract(&tree(), tmp.path(), &options).unwrap();
+        let meta = std::fs::metadata(tmp.path().join("docs/b.md")).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o640);
+        assert_eq!(
+            meta.modified().unwrap(),
+            Timestamp::new(946_684_800, 0).to_system_time()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_and_hard_links() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut root = tree();
+        root.add_child(link("to-a", EntryKind::Symlink, "a.txt"));
+        root.add_child(link("hard-a", EntryKind::Hardlink, "a.txt"));
+        let options = ExtractOptions::new().symlinks(true).hard_links(true);
+        let summary = extract(&root, tmp.path(), &options).unwrap();
+        assert_eq!((summary.symlinks, summary.hard_links), (1, 1));
+        let to_a = tmp.path().join("to-a");
+        assert!(std::fs::symlink_metadata(&to_a)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read(&to_a).unwrap(), DATA);
+        assert_eq!(std::fs::read(tmp.path().join("hard-a")).unwrap(), DATA);
+
+        // Hard links wait for the workers to write their source.
+        let parallel = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            extract_parallel(&root, parallel.path(), &options).unwrap(),
+            summary
+        );
+        assert_eq!(std::fs::read(parallel.path().join("hard-a")).unwrap(), DATA);
+
+        // Without the options both are plain (here empty) files.
+        let plain = tempfile::TempDir::new().unwrap();
+        extract(&root, plain.path(), &ExtractOptions::new()).unwrap();
+        assert!(std::fs::symlink_metadata(plain.path().join("to-a"))
+            .unwrap()
+            .is_file());
+    }
+
+    #[test]
+    fn parallel_extraction_matches_serial() {
+        let serial = tempfile::TempDir::new().unwrap();
+        let parallel = tempfile::TempDir::new().unwrap();
+        let options = ExtractOptions::new().restore_times(true).threads(3);
+        let expected = extract(&tree(), serial.path(), &options).unwrap();
+
+        let mut finished = Vec::new();
+        let summary = extract_node_parallel_observed(
+            DATA,
+            &tree(),
+            parallel.path().to_str().unwrap(),
+            &options,
+            &mut |event: &Event<'_>| {
+                if let Event::EntryFinished { path, .. } = event {
+                    finished.push(path.file_name().unwrap().to_owned());
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(summary, expected);
+        finished.sort();
+        assert_eq!(finished, ["a.txt", "b.md", "c.log"]);
+        for name in ["a.txt", "docs/b.md", "docs/c.log"] {
+            let written = serial.path().join(name);
+            let copied = parallel.path().join(name);
+            assert_eq!(
+                std::fs::read(&copied).unwrap(),
+                std::fs::read(&written).unwrap()
+            );
+            assert_eq!(
+                std::fs::metadata(&copied).unwrap().modified().unwrap(),
+                std::fs::metadata(&written).unwrap().modified().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_errors_are_deterministic() {
+        // Files 7 and 20 have extents too short for their size; the
+        // earlier one must always be the error reported.
+        let mut root = TreeNode::new_directory("/".to_string());
+        for i in 0..32 {
+            let covered = match i {
+                7 => 3,
+                20 => 5,
+                _ => 10,
+            };
+            root.add_child(TreeNode::new_file_with_extents(
+                format!("f{:02}", i),
+                10,
+                vec![Extent::data(0, covered)],
+            ));
+        }
+        let tmp = tempfile::TempDir::new().unwrap();
+        let serial = extract(&root, tmp.path(), &ExtractOptions::new()).unwrap_err();
+        assert!(serial.to_string().contains("only 3 of 10"), "{}", serial);
+        for _ in 0..20 {
+            let tmp = tempfile::TempDir::new().unwrap();
+            let err =
+                extract_parallel(&root, tmp.path(), &ExtractOptions::new().threads(8)).unwrap_err();
+            assert_eq!(err.to_string(), serial.to_string());
+            assert!(tmp.path().join("f06").exists());
+        }
+    }
+
+    #[test]
+    fn parallel_duplicate_paths_follow_the_policy() {
+        let mut root = TreeNode::new_directory("/".to_string());
+        root.add_child(TreeNode::new_file_with_location(
+            "a.txt".to_string(),
+            10,
+            0,
+            10,
+        ));
+        root.add_child(TreeNode::new_file_with_location(
+            "a.txt".to_string(),
+            4,
+            6,
+            4,
+        ));
+        let read = |dir: &tempfile::TempDir| std::fs::read(dir.path().join("a.txt")).unwrap();
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        extract_parallel(&root, tmp.path(), &ExtractOptions::new()).unwrap();
+        assert_eq!(read(&tmp), b"6789");
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let skip = ExtractOptions::new().on_existing(OnExisting::Skip);
+        let summary = extract_parallel(&root, tmp.path(), &skip).unwrap();
+        assert_eq!((summary.files, summary.skipped), (1, 1));
+        assert_eq!(read(&tmp), DATA);
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let error = ExtractOptions::new().on_existing(OnExisting::Error);
+        let err = extract_parallel(&root, tmp.path(), &error).unwrap_err();
+        assert!(matches!(&err, Error::Io(e) if e.kind() == io::ErrorKind::AlreadyExists));
+        // The entry before the failed check is still written.
+        assert_eq!(read(&tmp), DATA);
+    }
+
+    #[test]
+    fn escaping_links_are_refused() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let options = ExtractOptions::new().symlinks(true).hard_links(true);
+        for (kind, target) in [
+            (EntryKind::Symlink, "../outside"),
+            (EntryKind::Symlink, "/etc/passwd"),
+            (EntryKind::Hardlink, "../outside"),
+        ] {
+            let mut root = TreeNode::new_directory("/".to_string());
+            root.add_child(link("evil", kind, target));
+            let err = extract(&root, tmp.path(), &options).unwrap_err();
+            assert!(matches!(err, Error::UnsafePath { .. }), "{target}: {err}");
+        }
+    }
+
+    #[test]
+    fn link_routes() {
+        let here = Path::new("/out/d");
+        assert!(link_route(here, "b.txt").is_empty());
+        assert_eq!(link_route(here, "x/../.."), [PathBuf::from("/out/d/x")]);
+        assert_eq!(link_route(here, "../e/f"), [PathBuf::from("/out/e")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn chained_links_are_refused() {
+        let options = ExtractOptions::new().symlinks(true);
+        // Each link stays inside on its own; together `d/y` resolves to
+        // the parent of the output directory. Refused whichever comes
+        // first.
+        for order in [["x", "y"], ["y", "x"]] {
+            let mut d = TreeNode::new_directory("d".to_string());
+            for name in order {
+                let target = if name == "x" { ".." } else { "x/../.." };
+                d.add_child(link(name, EntryKind::Symlink, target));
+            }
+            let mut root = TreeNode::new_directory("/".to_string());
+            root.add_child(d);
+            for dry_run in [false, true] {
+                let tmp = tempfile::TempDir::new().unwrap();
+                let err =
+                    extract(&root, tmp.path(), &options.clone().dry_run(dry_run)).unwrap_err();
+                assert!(matches!(err, Error::UnsafePath { .. }), "{order:?}: {err}");
+            }
+        }
+
+        // A target through a symlink already in the output is refused too.
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink("..", tmp.path().join("up")).unwrap();
+        let mut root = TreeNode::new_directory("/".to_string());
+        root.add_child(link("y", EntryKind::Symlink, "up/x"));
+        let err = extract(&root, tmp.path(), &options).unwrap_err();
+        assert!(matches!(err, Error::UnsafePath { .. }), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn existing_symlinked_directory_is_not_followed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), tmp.path().join("docs")).unwrap();
+        let err = extract(&tree(), tmp.path(), &ExtractOptions::new()).unwrap_err();
+        assert!(matches!(err, Error::UnsafePath { .. }));
+        assert_eq!(std::fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+}
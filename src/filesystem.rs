@@ -0,0 +1,375 @@
+//! Lazy, handle-based access to a filesystem image.
+//!
+//! The `detect_and_parse` entry points walk every directory up front and
+//! hand back a complete [`TreeNode`] hierarchy. That is convenient for
+//! a CD, but on an NTFS or ext4 volume with millions of files it means
+//! minutes of I/O and gigabytes of nodes before the first byte of the
+//! one file you wanted.
+//!
+//! [`Filesystem`] reads one directory at a time instead. A
+//! [`DirEntry`] is a cheap handle — a name, a directory flag and a
+//! format-specific locator (an inode number, an MFT record, a directory
+//! record offset) — and each call touches only the metadata it needs:
+//!
+//! - [`read_dir`](Filesystem::read_dir) lists one directory;
+//! - [`stat`](Filesystem::stat) decodes one entry into a childless
+//!   [`TreeNode`];
+//! - [`lookup`](Filesystem::lookup) walks a path one component at a time;
+//! - [`open`](Filesystem::open) streams a file's bytes as a
+//!   [`NodeReader`].
+//!
+//! [`to_tree`](Filesystem::to_tree) builds the eager tree on top of
+//! these calls for callers that do want everything.
+//!
+//! | Format    | Type                                   | Handle              |
+//! |-----------|----------------------------------------|---------------------|
+//! | ISO 9660  | [`IsoFs`](crate::iso9660::IsoFs)       | directory record    |
+//! | UDF       | [`UdfFs`](crate::udf::UdfFs)           | file entry (ICB)    |
+//! | ext2/3/4  | `formats::ext::ExtFs`                  | inode number        |
+//! | FAT       | `formats::fat::FatFs`                  | 8.3 directory entry |
+//! | NTFS      | `formats::ntfs::NtfsFs`                | MFT record number   |
+//! | HFS+      | `formats::hfsplus::HfsPlusFs`          | catalog record      |
+//! | SquashFS  | `formats::squashfs::SquashFs`          | inode reference     |
+
+use crate::probe::Format;
+use crate::tree::{Extent, TreeNode};
+use crate::{Error, Result};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Deepest directory nesting [`Filesystem::to_tree`] follows before
+/// giving up, so a directory that lists one of its ancestors cannot
+/// recurse forever.
+const MAX_TREE_DEPTH: usize = 256;
+
+/// A handle to one file or directory inside a [`Filesystem`].
+///
+/// Handles are only meaningful to the filesystem that returned them.
+/// They hold no borrow, so they can be stored and passed back later.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirEntry {
+    /// The entry's name inside its parent directory; `"/"` for the root.
+    pub name: String,
+    /// `true` if the entry is a directory that [`Filesystem::read_dir`]
+    /// can list.
+    pub is_directory: bool,
+    /// Format-specific locator; see the table in the module docs.
+    pub(crate) id: u64,
+}
+
+impl DirEntry {
+    pub(crate) fn new(name: String, is_directory: bool, id: u64) -> Self {
+        Self {
+            name,
+            is_directory,
+            id,
+        }
+    }
+}
+
+/// A filesystem image opened for on-demand reads.
+///
+/// Implementors only read the structures a call needs: listing a
+/// directory never decodes its grandchildren, and [`stat`](Self::stat)
+/// never lists anything. Entries are returned in on-disk order, without
+/// `.` and `..`.
+pub trait Filesystem {
+    /// The underlying image the filesystem reads from.
+    type Image: Read + Seek;
+
+    /// The on-disk format, as reported in this filesystem's errors.
+    fn format(&self) -> Format;
+
+    /// The root directory, named `"/"`.
+    fn root(&self) -> DirEntry;
+
+    /// List the entries of `dir`. Fails if `dir` is not a directory.
+    fn read_dir(&mut self, dir: &DirEntry) -> Result<Vec<DirEntry>>;
+
+    /// Decode `entry` into a [`TreeNode`] named after it, with size,
+    /// data location and metadata filled in but no children.
+    fn stat(&mut self, entry: &DirEntry) -> Result<TreeNode<'static>>;
+
+    /// The image the filesystem reads from, for streaming file data.
+    fn image(&mut self) -> &mut Self::Image;
+
+    /// Resolve a `/`-separated path relative to the root, reading only
+    /// the directories along the way. Empty components and `.` are
+    /// ignored; returns `Ok(None)` if some component does not exist.
+    fn lookup(&mut self, path: &str) -> Result<Option<DirEntry>> {
+        let mut current = self.root();
+        for part in path.split('/').filter(|p| !p.is_empty() && *p != ".") {
+            if !current.is_directory {
+                return Ok(None);
+            }
+            match self
+                .read_dir(&current)?
+                .into_iter()
+                .find(|e| e.name == part)
+            {
+                Some(next) => current = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+
+    /// Open a file for reading. Fails for directories and for files
+    /// whose bytes are not addressable in the image (resident or
+    /// compressed data).
+    fn open(&mut self, entry: &DirEntry) -> Result<NodeReader<'_, Self::Image>> {
+        let node = self.stat(entry)?;
+        NodeReader::new(self.image(), &node)
+    }
+
+    /// Read every directory and return the full tree rooted at `"/"`,
+    /// with directory sizes calculated — the same shape the eager
+    /// parsers produce.
+    fn to_tree(&mut self) -> Result<TreeNode<'static>> {
+        let root = self.root();
+        let mut node = build_subtree(self, &root, 0)?;
+        node.name = "/".into();
+        node.calculate_directory_size();
+        Ok(node)
+    }
+}
+
+fn build_subtree<F: Filesystem + ?Sized>(
+    fs: &mut F,
+    entry: &DirEntry,
+    depth: usize,
+) -> Result<TreeNode<'static>> {
+    let mut node = fs.stat(entry)?;
+    if entry.is_directory {
+        if depth >= MAX_TREE_DEPTH {
+            return Err(Error::corrupt(
+                fs.format(),
+                format!("directory nesting deeper than {}", MAX_TREE_DEPTH),
+            ));
+        }
+        for child in fs.read_dir(entry)? {
+            let child = build_subtree(fs, &child, depth + 1)?;
+            node.add_child(child);
+        }
+    }
+    Ok(node)
+}
+
+/// `Read + Seek` over one file's bytes, returned by [`open_node`](crate::open_node)
+/// and [`Filesystem::open`].
+///
+/// Multi-extent files are presented as one stream; sparse holes read as
+/// zeros. Reads never go past the file's length, and a file whose extent
+/// list is shorter than its length fails with `UnexpectedEof` at the gap.
+#[derive(Debug)]
+pub struct NodeReader<'a, R> {
+    image: &'a mut R,
+    extents: Vec<Extent>,
+    len: u64,
+    pos: u64,
+}
+
+impl<'a, R: Read + Seek> NodeReader<'a, R> {
+    /// Wrap `image` so it reads `node`'s data. Fails if `node` is a
+    /// directory or carries no usable location.
+    pub(crate) fn new(image: &'a mut R, node: &TreeNode) -> Result<Self> {
+        if node.is_directory {
+            return Err(Error::NotAFile {
+                name: node.name.to_string(),
+            });
+        }
+        let extents = node
+            .data_extents()
+            .ok_or_else(|| Error::NoData {
+                name: node.name.to_string(),
+            })?
+            .into_owned();
+        Ok(Self {
+            image,
+            extents,
+            len: node.file_length.unwrap_or(node.size),
+            pos: 0,
+        })
+    }
+
+    /// Length of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// `true` iff the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<R: Read + Seek> Read for NodeReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let want = (self.len - self.pos).min(buf.len() as u64);
+        let mut start = 0u64;
+        for extent in &self.extents {
+            let end = start + extent.length;
+            if self.pos < end {
+                let into = self.pos - start;
+                let n = want.min(end - self.pos) as usize;
+                let out = &mut buf[..n];
+                match extent.location {
+                    None => out.fill(0),
+                    Some(location) => {
+                        self.image.seek(SeekFrom::Start(location + into))?;
+                        self.image.read_exact(out)?;
+                    }
+                }
+                self.pos += n as u64;
+                return Ok(n);
+            }
+            start = end;
+        }
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "extent list ends before the file does",
+        ))
+    }
+}
+
+impl<R: Read + Seek> Seek for NodeReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n);
+            }
+            SeekFrom::End(d) => (self.len, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        match base.checked_add_signed(delta) {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+/// The error every `read_dir` returns when handed a non-directory.
+pub(crate) fn not_a_directory(entry: &DirEntry) -> Error {
+    Error::NotADirectory {
+        name: entry.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A two-level in-memory filesystem: `/a.txt` and `/sub/b.txt`.
+    struct Toy {
+        image: Cursor<Vec<u8>>,
+    }
+
+    impl Filesystem for Toy {
+        type Image = Cursor<Vec<u8>>;
+
+        fn format(&self) -> Format {
+            Format::Iso9660
+        }
+
+        fn root(&self) -> DirEntry {
+            DirEntry::new("/".into(), true, 0)
+        }
+
+        fn read_dir(&mut self, dir: &DirEntry) -> Result<Vec<DirEntry>> {
+            match dir.id {
+                0 => Ok(vec![
+                    DirEntry::new("a.txt".into(), false, 1),
+                    DirEntry::new("sub".into(), true, 2),
+                ]),
+                2 => Ok(vec![DirEntry::new("b.txt".into(), false, 3)]),
+                _ => Err(not_a_directory(dir)),
+            }
+        }
+
+        fn stat(&mut self, entry: &DirEntry) -> Result<TreeNode<'static>> {
+            Ok(match entry.id {
+                1 => TreeNode::new_file_with_location(entry.name.clone(), 3, 0, 3),
+                3 => TreeNode::new_file_with_extents(
+                    entry.name.clone(),
+                    6,
+                    vec![Extent::data(3, 2), Extent::hole(2), Extent::data(5, 2)],
+                ),
+                _ => TreeNode::new_directory(entry.name.clone()),
+            })
+        }
+
+        fn image(&mut self) -> &mut Self::Image {
+            &mut self.image
+        }
+    }
+
+    fn toy() -> Toy {
+        Toy {
+            image: Cursor::new(b"abcdefg".to_vec()),
+        }
+    }
+
+    #[test]
+    fn lookup_walks_components() {
+        let mut fs = toy();
+        let b = fs.lookup("/sub//b.txt").unwrap().unwrap();
+        assert_eq!(b.id, 3);
+        assert_eq!(fs.lookup("").unwrap().unwrap().name, "/");
+        assert!(fs.lookup("sub/missing").unwrap().is_none());
+        assert!(fs.lookup("a.txt/under-a-file").unwrap().is_none());
+    }
+
+    #[test]
+    fn open_stitches_extents_and_holes() {
+        let mut fs = toy();
+        let b = fs.lookup("sub/b.txt").unwrap().unwrap();
+        let mut r = fs.open(&b).unwrap();
+        assert_eq!(r.len(), 6);
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"de\0\0fg");
+
+        r.seek(SeekFrom::End(-3)).unwrap();
+        let mut tail = Vec::new();
+        r.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, b"\0fg");
+    }
+
+    #[test]
+    fn open_rejects_directories() {
+        let mut fs = toy();
+        let sub = fs.lookup("sub").unwrap().unwrap();
+        assert!(fs.open(&sub).is_err());
+        let a = fs.lookup("a.txt").unwrap().unwrap();
+        assert!(fs.read_dir(&a).is_err());
+    }
+
+    #[test]
+    fn to_tree_matches_eager_shape() {
+        let tree = toy().to_tree().unwrap();
+        assert_eq!(tree.name, "/");
+        assert_eq!(tree.size, 9);
+        assert_eq!(tree.find_node("sub/b.txt").unwrap().size, 6);
+    }
+
+    #[test]
+    fn short_extent_list_is_an_error() {
+        let node = TreeNode::new_file_with_extents("x", 10, vec![Extent::data(0, 4)]);
+        let mut image = Cursor::new(vec![1u8; 16]);
+        let mut r = NodeReader::new(&mut image, &node).unwrap();
+        let mut out = Vec::new();
+        let err = r.read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(out.len(), 4);
+    }
+}
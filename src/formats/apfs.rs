@@ -30,6 +30,9 @@
 
 use std::io::{Read, Seek, SeekFrom};
 
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
 use crate::tree::TreeNode;
 
 // ── Magic numbers ─────────────────────────────────────────────────────────────
@@ -133,6 +136,16 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Apfs, e),
+            e => crate::Error::corrupt(Format::Apfs, e),
+        }
+    }
+}
+
 // ── Parsed NX Superblock ───────────────────────────────────────────────────────
 
 /// Fields from the NX Superblock (container superblock) that we need.
@@ -280,15 +293,27 @@ fn read_volume_name<R: Read + Seek>(r: &mut R, block_num: u64, block_size: u32)
 /// Per-volume file trees are **not** traversed in this implementation: only
 /// the container-level volume list is returned. Full FS B-tree traversal is
 /// deferred to a future PR.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Apfs, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
     let nx = read_nx_superblock(r)?;
     let mut root = TreeNode::new_directory("/".to_string());
 
+    limits::dir_entries(nx.fs_oids.len() as u64)?;
     for &fs_oid in &nx.fs_oids {
         // For the container-level omap, fs_oid entries are physical block
         // addresses — we read the APSB directly at that block.
-        let name = read_volume_name(r, fs_oid, nx.block_size)
-            .unwrap_or_else(|| format!("volume_{fs_oid}"));
+        let name = read_volume_name(r, fs_oid, nx.block_size).unwrap_or_else(|| {
+            recover::note(
+                fs_oid.checked_mul(nx.block_size as u64),
+                "volume superblock",
+                "unreadable, listed by object id",
+            );
+            format!("volume_{fs_oid}")
+        });
+        limits::entry(&name)?;
         root.add_child(TreeNode::new_directory(name));
     }
 
@@ -52,6 +52,9 @@
 
 use std::io::{self, Read, Seek, SeekFrom};
 
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
 use crate::tree::TreeNode;
 
 // ── Magic & constants ──────────────────────────────────────────────────────────
@@ -106,6 +109,16 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Dmg, e),
+            Error::BadVersion(_) => crate::Error::unsupported(Format::Dmg, e),
+        }
+    }
+}
+
 // ── Parsed koly trailer ────────────────────────────────────────────────────────
 
 /// Parsed fields from the 512-byte koly trailer.
@@ -328,7 +341,11 @@ fn extract_keyed_string(text: &str, key: &str) -> Option<String> {
 ///
 /// `file_location` is always `None` for directory children — decoding
 /// the binary Mish/blkx extent data is not done here.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Dmg, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
     let koly = read_koly(r)?;
 
     // Attempt to read the XML plist.
@@ -344,12 +361,23 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
     let xml_text = if xml_sane {
         r.seek(SeekFrom::Start(koly.xml_offset))?;
         let read_len = koly.xml_length as usize;
+        limits::alloc(koly.xml_length)?;
         let mut raw = vec![0u8; read_len];
         match r.read_exact(&mut raw) {
             Ok(()) => String::from_utf8_lossy(&raw).into_owned(),
-            Err(_) => String::new(),
+            Err(e) => {
+                recover::note(Some(koly.xml_offset), "property list", e);
+                String::new()
+            }
         }
     } else {
+        if koly.xml_length > 0 {
+            recover::note(
+                Some(koly.xml_offset),
+                "property list",
+                format_args!("{} bytes do not fit in the image", koly.xml_length),
+            );
+        }
         String::new()
     };
 
@@ -364,7 +392,9 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
         disk_node.file_length = Some(disk_size);
         root.add_child(disk_node);
     } else {
+        limits::dir_entries(entries.len() as u64)?;
         for entry in &entries {
+            limits::entry(&entry.name)?;
             let node = TreeNode::new_directory(entry.name.clone());
             root.add_child(node);
         }
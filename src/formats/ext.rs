@@ -23,21 +23,27 @@
 //! - Symlinks appear in the tree with correct size; devices/FIFOs/sockets
 //!   are silently skipped.
 //!
-//! ## `file_location` semantics
+//! ## File data
 //!
-//! A file's `file_location` is set **only** when all data resides in a
-//! single physically-contiguous block run. This is true for:
-//! - Extent-tree depth=0 with exactly one extent.
-//! - Classical mode with only direct blocks that happen to be contiguous
-//!   and the file fits within the 12 direct pointers.
+//! A file whose blocks form one physically-contiguous run gets a plain
+//! `file_location`. Anything else — multiple extents, scattered indirect
+//! blocks, sparse regions — is described by [`Extent`](crate::tree::Extent)s
+//! in file order. Gaps in the extent tree, unwritten (preallocated)
+//! extents and zero block pointers become holes that read as zeros.
 //!
-//! Multi-extent, indirect-block, and inline-data files still appear in
-//! the tree with correct `size`, but `file_location = None`. `cat_node`
-//! will refuse those; `extract_node` can be extended later.
+//! Inline-data files still appear in the tree with correct `size`, but
+//! have neither a location nor extents; `cat_node` refuses those.
 
+use std::borrow::Cow;
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::filesystem::{self, Filesystem};
+use crate::image_io::source::{Borrowed, Bytes, Source, Stream};
+use crate::image_io::RandomAccess;
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{self, EntryKind, Metadata, Timestamp, TreeNode};
 
 // ── ECMA / Linux kernel spec constants ───────────────────────────────────────
 
@@ -110,6 +116,15 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadSuperblock => crate::Error::not_recognized(Format::Ext, e),
+        }
+    }
+}
+
 // ── Superblock ────────────────────────────────────────────────────────────────
 
 /// Parsed ext superblock fields we actually use.
@@ -151,16 +166,17 @@ impl Superblock {
     }
 }
 
-fn read_superblock<R: Read + Seek>(file: &mut R, base_offset: u64) -> Result<Superblock, Error> {
-    file.seek(SeekFrom::Start(base_offset + SUPERBLOCK_OFFSET))?;
-    let mut sb = [0u8; 264]; // up to s_desc_size at offset 236+2=238, take 264 for safety
-    file.read_exact(&mut sb).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::UnexpectedEof {
-            Error::TooShort
-        } else {
-            Error::Io(e)
-        }
-    })?;
+fn read_superblock<'a, S: Source<'a>>(src: &mut S, base_offset: u64) -> Result<Superblock, Error> {
+    // Up to s_desc_size at offset 236+2=238, take 264 for safety.
+    let sb = src
+        .bytes(base_offset + SUPERBLOCK_OFFSET, 264)
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                Error::TooShort
+            } else {
+                Error::Io(e)
+            }
+        })?;
     let magic = u16::from_le_bytes([sb[56], sb[57]]);
     if magic != EXT_MAGIC {
         return Err(Error::BadSuperblock);
@@ -227,17 +243,15 @@ struct Bgd {
     inode_table: u64, // block number of the inode table
 }
 
-fn read_bgd<R: Read + Seek>(
-    file: &mut R,
+fn read_bgd<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     group: u64,
 ) -> Result<Bgd, Error> {
     let desc_size = sb.desc_size_effective();
     let offset = sb.bgd_table_offset(base_offset) + group * desc_size;
-    file.seek(SeekFrom::Start(offset))?;
-    let mut buf = vec![0u8; desc_size as usize];
-    file.read_exact(&mut buf)?;
+    let buf = src.bytes(offset, desc_size as usize)?;
 
     let inode_table_lo = u32::from_le_bytes(buf[8..12].try_into().unwrap()) as u64;
     let inode_table = if sb.has_incompat(INCOMPAT_64BIT) && desc_size >= 64 {
@@ -253,11 +267,18 @@ fn read_bgd<R: Read + Seek>(
 // ── Inode ─────────────────────────────────────────────────────────────────────
 
 /// Parsed inode fields.
+#[derive(Default)]
 struct Inode {
     mode: u16,
     size: u64, // full 64-bit size (lo | hi<<32 for regular files)
     flags: u32,
     i_block: [u32; 15], // raw 60-byte block-pointer / extent-root area
+    uid: u32,           // i_uid | l_i_uid_high << 16
+    gid: u32,           // i_gid | l_i_gid_high << 16
+    atime: Option<Timestamp>,
+    ctime: Option<Timestamp>,
+    mtime: Option<Timestamp>,
+    crtime: Option<Timestamp>, // only with a large inode (i_extra_isize >= 24)
 }
 
 impl Inode {
@@ -284,29 +305,67 @@ impl Inode {
     fn is_inline(&self) -> bool {
         self.flags & EXT4_INLINE_DATA_FL != 0
     }
+
+    /// Format-neutral view of the inode's ownership, permissions and times.
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            kind: EntryKind::from_posix_mode(self.mode as u32),
+            mode: Some((self.mode & 0o7777) as u32),
+            uid: Some(self.uid),
+            gid: Some(self.gid),
+            mtime: self.mtime,
+            atime: self.atime,
+            ctime: self.ctime,
+            btime: self.crtime,
+            link_target: None,
+            nlink: None,
+            device: None,
+        }
+    }
 }
 
-fn read_inode<R: Read + Seek>(
-    file: &mut R,
+/// Decode an inode timestamp: signed 32-bit seconds, optionally widened
+/// by the matching `_extra` field (low 2 bits extend the epoch, upper 30
+/// bits are nanoseconds).
+fn inode_time(secs: u32, extra: Option<u32>) -> Timestamp {
+    let mut t = Timestamp::new(secs as i32 as i64, 0);
+    if let Some(extra) = extra {
+        t.secs += ((extra & 0x3) as i64) << 32;
+        let nanos = extra >> 2;
+        if nanos < 1_000_000_000 {
+            t.nanos = nanos;
+        }
+    }
+    t
+}
+
+/// Byte offset of inode `inode_num` in the image.
+fn inode_offset<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     inode_num: u32,
-) -> Result<Inode, Error> {
+) -> Result<u64, Error> {
     if inode_num == 0 {
         return Err(Error::BadSuperblock);
     }
     let group = (inode_num as u64 - 1) / sb.inodes_per_group as u64;
     let local_index = (inode_num as u64 - 1) % sb.inodes_per_group as u64;
 
-    let bgd = read_bgd(file, sb, base_offset, group)?;
-    let inode_offset =
-        base_offset + bgd.inode_table * sb.block_size() + local_index * sb.inode_size as u64;
+    let bgd = read_bgd(src, sb, base_offset, group)?;
+    Ok(base_offset + bgd.inode_table * sb.block_size() + local_index * sb.inode_size as u64)
+}
 
-    file.seek(SeekFrom::Start(inode_offset))?;
-    // Read at least 112 bytes (up through i_size_high at 108..112).
-    let read_len = (sb.inode_size as usize).max(112);
-    let mut buf = vec![0u8; read_len];
-    file.read_exact(&mut buf)?;
+fn read_inode<'a, S: Source<'a>>(
+    src: &mut S,
+    sb: &Superblock,
+    base_offset: u64,
+    inode_num: u32,
+) -> Result<Inode, Error> {
+    let inode_offset = inode_offset(src, sb, base_offset, inode_num)?;
+    // Read at least the 128-byte base inode (through l_i_gid_high at 122..124).
+    let read_len = (sb.inode_size as usize).max(128);
+    let buf = src.bytes(inode_offset, read_len)?;
 
     let mode = u16::from_le_bytes([buf[0], buf[1]]);
     let size_lo = u32::from_le_bytes(buf[4..8].try_into().unwrap());
@@ -328,28 +387,46 @@ fn read_inode<R: Read + Seek>(
         size_lo as u64
     };
 
+    let le16 = |at: usize| u16::from_le_bytes([buf[at], buf[at + 1]]) as u32;
+    let le32 = |at: usize| u32::from_le_bytes(buf[at..at + 4].try_into().unwrap());
+    let uid = le16(2) | le16(120) << 16;
+    let gid = le16(24) | le16(122) << 16;
+
+    // Large inodes: i_extra_isize at 128 says how many of the extra
+    // fields (ctime/mtime/atime _extra, crtime, crtime_extra) exist.
+    let extra_isize = if buf.len() >= 130 {
+        le16(128) as usize
+    } else {
+        0
+    };
+    let extra = |at: usize| (at + 4 <= 128 + extra_isize && at + 4 <= buf.len()).then(|| le32(at));
+
     Ok(Inode {
         mode,
         size,
         flags,
         i_block,
+        uid,
+        gid,
+        atime: Some(inode_time(le32(8), extra(140))),
+        ctime: Some(inode_time(le32(12), extra(132))),
+        mtime: Some(inode_time(le32(16), extra(136))),
+        crtime: extra(144).map(|secs| inode_time(secs, extra(148))),
     })
 }
 
 // ── Block reading ─────────────────────────────────────────────────────────────
 
-/// Read `block_num` (filesystem-relative block number) into `buf`.
-fn read_block<R: Read + Seek>(
-    file: &mut R,
+/// Read `block_num` (filesystem-relative block number).
+fn read_block<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     block_num: u64,
-    buf: &mut Vec<u8>,
-) -> Result<(), Error> {
-    buf.resize(sb.block_size() as usize, 0);
-    file.seek(SeekFrom::Start(base_offset + block_num * sb.block_size()))?;
-    file.read_exact(buf)?;
-    Ok(())
+) -> Result<Cow<'a, [u8]>, Error> {
+    // A wild block number reads past the end of the image.
+    let offset = base_offset.saturating_add(block_num.saturating_mul(sb.block_size()));
+    Ok(src.bytes(offset, sb.block_size() as usize)?)
 }
 
 // ── Extent tree ───────────────────────────────────────────────────────────────
@@ -358,6 +435,7 @@ fn read_block<R: Read + Seek>(
 /// physical block phys.
 #[derive(Debug, Clone, Copy)]
 struct Extent {
+    logical: u32,    // first logical block covered (ee_block)
     len: u16,        // number of blocks (ee_len & 0x7FFF)
     phys: u64,       // physical starting block
     unwritten: bool, // bit 15 of ee_len: preallocated but not yet written
@@ -393,6 +471,7 @@ fn parse_leaf_extents(data: &[u8], entries: u16) -> Vec<Extent> {
         if off + 12 > data.len() {
             break;
         }
+        let logical = u32::from_le_bytes(data[off..off + 4].try_into().unwrap());
         let ee_len = u16::from_le_bytes([data[off + 4], data[off + 5]]);
         let ee_start_hi = u16::from_le_bytes([data[off + 6], data[off + 7]]) as u64;
         let ee_start_lo = u32::from_le_bytes(data[off + 8..off + 12].try_into().unwrap()) as u64;
@@ -400,6 +479,7 @@ fn parse_leaf_extents(data: &[u8], entries: u16) -> Vec<Extent> {
         let unwritten = ee_len & 0x8000 != 0;
         let len = ee_len & 0x7FFF;
         out.push(Extent {
+            logical,
             len,
             phys,
             unwritten,
@@ -429,8 +509,8 @@ fn parse_idx_entries(data: &[u8], entries: u16) -> Vec<ExtentIdx> {
 ///
 /// Recursion is bounded by the depth field in each header; max depth 5 per
 /// kernel, and we hard-cap at 5 to be safe on corrupted images.
-fn collect_extents<R: Read + Seek>(
-    file: &mut R,
+fn collect_extents<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     node_data: &[u8],
@@ -452,12 +532,10 @@ fn collect_extents<R: Read + Seek>(
 
     // Internal node: recurse into each child block.
     let idx_entries = parse_idx_entries(node_data, entries);
-    let mut block_buf = Vec::new();
     let mut all_extents = Vec::new();
     for idx in idx_entries {
-        read_block(file, sb, base_offset, idx.leaf, &mut block_buf)?;
-        let child_extents =
-            collect_extents(file, sb, base_offset, &block_buf, remaining_depth - 1)?;
+        let block = read_block(src, sb, base_offset, idx.leaf)?;
+        let child_extents = collect_extents(src, sb, base_offset, &block, remaining_depth - 1)?;
         all_extents.extend(child_extents);
     }
     Ok(all_extents)
@@ -466,16 +544,13 @@ fn collect_extents<R: Read + Seek>(
 // ── Classical block pointer iteration ────────────────────────────────────────
 
 /// Read one block of u32 block pointers from `block_num`.
-fn read_ptr_block<R: Read + Seek>(
-    file: &mut R,
+fn read_ptr_block<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     block_num: u64,
 ) -> Result<Vec<u32>, Error> {
-    let bs = sb.block_size() as usize;
-    let mut buf = vec![0u8; bs];
-    file.seek(SeekFrom::Start(base_offset + block_num * sb.block_size()))?;
-    file.read_exact(&mut buf)?;
+    let buf = read_block(src, sb, base_offset, block_num)?;
     Ok(buf
         .chunks_exact(4)
         .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
@@ -488,8 +563,8 @@ fn read_ptr_block<R: Read + Seek>(
 ///
 /// Handles direct (0..11), single-indirect (12), double-indirect (13),
 /// and triple-indirect (14) pointers.
-fn collect_classical_blocks<R: Read + Seek>(
-    file: &mut R,
+fn collect_classical_blocks<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     inode: &Inode,
@@ -515,7 +590,7 @@ fn collect_classical_blocks<R: Read + Seek>(
     // Single-indirect: i_block[12].
     let si = inode.i_block[12];
     if si != 0 {
-        let ptrs = read_ptr_block(file, sb, base_offset, si as u64)?;
+        let ptrs = read_ptr_block(src, sb, base_offset, si as u64)?;
         for blk in ptrs {
             if blk == 0 || covered >= size {
                 break;
@@ -532,12 +607,12 @@ fn collect_classical_blocks<R: Read + Seek>(
     // Double-indirect: i_block[13].
     let di = inode.i_block[13];
     if di != 0 {
-        let l1 = read_ptr_block(file, sb, base_offset, di as u64)?;
+        let l1 = read_ptr_block(src, sb, base_offset, di as u64)?;
         'di_outer: for l1ptr in l1 {
             if l1ptr == 0 || covered >= size {
                 break;
             }
-            let l2 = read_ptr_block(file, sb, base_offset, l1ptr as u64)?;
+            let l2 = read_ptr_block(src, sb, base_offset, l1ptr as u64)?;
             for blk in l2 {
                 if blk == 0 || covered >= size {
                     break 'di_outer;
@@ -555,17 +630,17 @@ fn collect_classical_blocks<R: Read + Seek>(
     // Triple-indirect: i_block[14].
     let ti = inode.i_block[14];
     if ti != 0 {
-        let l1 = read_ptr_block(file, sb, base_offset, ti as u64)?;
+        let l1 = read_ptr_block(src, sb, base_offset, ti as u64)?;
         'ti_outer: for l1ptr in l1 {
             if l1ptr == 0 || covered >= size {
                 break;
             }
-            let l2 = read_ptr_block(file, sb, base_offset, l1ptr as u64)?;
+            let l2 = read_ptr_block(src, sb, base_offset, l1ptr as u64)?;
             'ti_middle: for l2ptr in l2 {
                 if l2ptr == 0 || covered >= size {
                     break 'ti_outer;
                 }
-                let l3 = read_ptr_block(file, sb, base_offset, l2ptr as u64)?;
+                let l3 = read_ptr_block(src, sb, base_offset, l2ptr as u64)?;
                 for blk in l3 {
                     if blk == 0 || covered >= size {
                         break 'ti_middle;
@@ -584,13 +659,17 @@ fn collect_classical_blocks<R: Read + Seek>(
 
 /// One directory entry (partially parsed — just what we need).
 #[derive(Debug)]
-struct DirEntry {
+struct DirEntry<'a> {
     inode: u32,
-    name: String,
+    name: Cow<'a, str>,
+    /// The entry's `file_type` byte, or `0` (unknown) without
+    /// `INCOMPAT_FILETYPE`.
+    file_type: u8,
 }
 
 /// Scan a raw directory data block for entries, pushing valid ones into `out`.
-fn scan_dir_block(data: &[u8], has_filetype: bool, out: &mut Vec<DirEntry>) {
+fn scan_dir_block<'a>(block: Bytes<'a, '_>, has_filetype: bool, out: &mut Vec<DirEntry<'a>>) {
+    let data = &*block;
     let mut pos = 0usize;
     while pos + 8 <= data.len() {
         let inode = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
@@ -603,11 +682,14 @@ fn scan_dir_block(data: &[u8], has_filetype: bool, out: &mut Vec<DirEntry>) {
             break;
         }
         if inode != 0 && name_len > 0 && pos + 8 + name_len <= data.len() {
-            let raw = &data[pos + 8..pos + 8 + name_len];
-            let name = String::from_utf8_lossy(raw).into_owned();
+            let name = block.slice(pos + 8..pos + 8 + name_len).utf8_lossy();
             if name != "." && name != ".." {
-                let _file_type = if has_filetype { data[pos + 7] } else { 0 };
-                out.push(DirEntry { inode, name });
+                let file_type = if has_filetype { data[pos + 7] } else { 0 };
+                out.push(DirEntry {
+                    inode,
+                    name,
+                    file_type,
+                });
             }
         }
         pos += rec_len.max(1); // guard against rec_len=0 infinite loop
@@ -615,15 +697,14 @@ fn scan_dir_block(data: &[u8], has_filetype: bool, out: &mut Vec<DirEntry>) {
 }
 
 /// Read all directory entries from an inode.
-fn read_dir_entries<R: Read + Seek>(
-    file: &mut R,
+fn read_dir_entries<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
     inode: &Inode,
-) -> Result<Vec<DirEntry>, Error> {
+) -> Result<Vec<DirEntry<'a>>, Error> {
     let has_filetype = sb.has_incompat(INCOMPAT_FILETYPE);
     let mut entries = Vec::new();
-    let mut block_buf = Vec::new();
 
     if inode.uses_extents() {
         // i_block holds the extent tree root (60 bytes = 12-byte header + up
@@ -633,142 +714,377 @@ fn read_dir_entries<R: Read + Seek>(
             .iter()
             .flat_map(|&w| w.to_le_bytes())
             .collect();
-        let extents = collect_extents(file, sb, base_offset, &root_bytes, 5)?;
+        let extents = collect_extents(src, sb, base_offset, &root_bytes, 5)?;
         for ext in extents {
             for i in 0..ext.len as u64 {
-                read_block(file, sb, base_offset, ext.phys + i, &mut block_buf)?;
-                scan_dir_block(&block_buf, has_filetype, &mut entries);
+                let block = read_block(src, sb, base_offset, ext.phys + i)?;
+                scan_dir_block(Bytes::of(&block), has_filetype, &mut entries);
+                limits::dir_entries(entries.len() as u64)?;
             }
         }
     } else {
         // Classical block pointers: collect block numbers first, then read
         // each block in a separate pass to avoid split-borrow issues.
-        let blk_nums = collect_classical_blocks(file, sb, base_offset, inode, inode.size)?;
+        let blk_nums = collect_classical_blocks(src, sb, base_offset, inode, inode.size)?;
         for blk in blk_nums {
-            read_block(file, sb, base_offset, blk, &mut block_buf)?;
-            scan_dir_block(&block_buf, has_filetype, &mut entries);
+            let block = read_block(src, sb, base_offset, blk)?;
+            scan_dir_block(Bytes::of(&block), has_filetype, &mut entries);
+            limits::dir_entries(entries.len() as u64)?;
         }
     }
 
     Ok(entries)
 }
 
-// ── File location detection ────────────────────────────────────────────────────
+// ── File data extents ─────────────────────────────────────────────────────────
 
-/// Try to find a single contiguous physical location for `inode`'s data.
-/// Returns `Some(byte_offset_in_image)` only when the entire file content
-/// lives in one unbroken physical run.
-fn single_run_location<R: Read + Seek>(
-    file: &mut R,
+/// Append `length` bytes at `location` (`None` = hole) to `runs`, merging
+/// with the previous run when the two are adjacent.
+fn push_run(runs: &mut Vec<tree::Extent>, location: Option<u64>, length: u64) {
+    if let Some(last) = runs.last_mut() {
+        let adjacent = match (last.location, location) {
+            (Some(a), Some(b)) => a + last.length == b,
+            (None, None) => true,
+            _ => false,
+        };
+        if adjacent {
+            last.length += length;
+            return;
+        }
+    }
+    runs.push(tree::Extent { location, length });
+}
+
+/// Walk one classical block pointer at indirection `level` (0 = data
+/// block, 1 = single-indirect, ...), appending runs until `remaining`
+/// blocks are covered. A zero pointer is a hole spanning everything the
+/// pointer would have addressed.
+fn walk_classical<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
-    inode: &Inode,
-) -> Result<Option<u64>, Error> {
-    if inode.size == 0 {
-        return Ok(None);
+    ptr: u32,
+    level: u32,
+    remaining: &mut u64,
+    runs: &mut Vec<tree::Extent>,
+) -> Result<(), Error> {
+    if *remaining == 0 {
+        return Ok(());
+    }
+    let bs = sb.block_size();
+    let span = (bs / 4).saturating_pow(level).min(*remaining);
+    if ptr == 0 {
+        push_run(runs, None, span * bs);
+        *remaining -= span;
+        return Ok(());
+    }
+    if level == 0 {
+        push_run(runs, Some(base_offset + ptr as u64 * bs), bs);
+        *remaining -= 1;
+        return Ok(());
+    }
+    for child in read_ptr_block(src, sb, base_offset, ptr as u64)? {
+        if *remaining == 0 {
+            break;
+        }
+        walk_classical(src, sb, base_offset, child, level - 1, remaining, runs)?;
     }
-    if inode.is_inline() {
+    Ok(())
+}
+
+/// Map `inode`'s data onto image byte ranges, in file order and in whole
+/// blocks; [`TreeNode::new_file_with_extents`] trims the tail to `size`.
+///
+/// Gaps in the extent tree, unwritten (preallocated) extents and zero
+/// classical block pointers all become holes: they read back as zeros,
+/// exactly as the kernel returns them. Returns `None` for empty and
+/// inline-data files, which have no blocks to point at.
+fn data_extents<'a, S: Source<'a>>(
+    src: &mut S,
+    sb: &Superblock,
+    base_offset: u64,
+    inode: &Inode,
+) -> Result<Option<Vec<tree::Extent>>, Error> {
+    if inode.size == 0 || inode.is_inline() {
         return Ok(None);
     }
 
+    let bs = sb.block_size();
+    let needed_blocks = inode.size.div_ceil(bs);
+    let mut runs = Vec::new();
+
     if inode.uses_extents() {
         let root_bytes: Vec<u8> = inode
             .i_block
             .iter()
             .flat_map(|&w| w.to_le_bytes())
             .collect();
-        let extents = collect_extents(file, sb, base_offset, &root_bytes, 5)?;
-        // Single contiguous run: exactly one non-unwritten extent whose block
-        // count covers the full file. Unwritten extents are preallocated but
-        // contain stale on-disk data; reading them would return garbage.
-        if extents.len() == 1 {
-            let ext = &extents[0];
-            if !ext.unwritten {
-                let needed_blocks = inode.size.div_ceil(sb.block_size());
-                if ext.len as u64 >= needed_blocks {
-                    return Ok(Some(base_offset + ext.phys * sb.block_size()));
-                }
+        let mut extents = collect_extents(src, sb, base_offset, &root_bytes, 5)?;
+        extents.sort_by_key(|e| e.logical);
+        let mut next: u64 = 0;
+        for ext in extents {
+            let logical = ext.logical as u64;
+            if logical < next || ext.len == 0 {
+                // Overlapping or empty extent on a corrupted image.
+                continue;
+            }
+            if logical >= needed_blocks {
+                break;
+            }
+            if logical > next {
+                push_run(&mut runs, None, (logical - next) * bs);
             }
+            // Unwritten extents are preallocated but contain stale
+            // on-disk data; they read as zeros.
+            let location = (!ext.unwritten).then(|| base_offset + ext.phys * bs);
+            push_run(&mut runs, location, ext.len as u64 * bs);
+            next = logical + ext.len as u64;
+        }
+    } else {
+        let mut remaining = needed_blocks;
+        for (i, &ptr) in inode.i_block.iter().enumerate() {
+            let level = i.saturating_sub(11) as u32;
+            walk_classical(src, sb, base_offset, ptr, level, &mut remaining, &mut runs)?;
         }
-        return Ok(None);
     }
 
-    // Classical mode: check that the direct blocks are a contiguous run.
-    // We only check if the file fits within the direct pointer range
-    // (i_block[0..11]) to keep the logic simple and reliable.
-    let bs = sb.block_size();
-    let needed_blocks = inode.size.div_ceil(bs);
-    if needed_blocks > 12 {
-        // Would require indirect blocks; skip contiguous check.
-        return Ok(None);
+    // A sparse tail past the last mapped block.
+    let mapped: u64 = runs.iter().map(|r| r.length).sum();
+    if mapped < inode.size {
+        push_run(&mut runs, None, inode.size - mapped);
     }
+    Ok(Some(runs))
+}
+
+// ── Symlink targets ───────────────────────────────────────────────────────────
 
-    // Require all needed direct blocks to be consecutive.
-    let first = inode.i_block[0] as u64;
-    if first == 0 {
+/// Longest symlink target we will read (Linux `PATH_MAX`).
+const MAX_SYMLINK_LEN: u64 = 4096;
+
+/// Read a symlink's target path.
+///
+/// Fast symlinks (target shorter than 60 bytes) and inline-data symlinks
+/// keep the target in `i_block` itself; slow symlinks store it in data
+/// blocks like a regular file.
+fn read_symlink_target<'a, S: Source<'a>>(
+    src: &mut S,
+    sb: &Superblock,
+    base_offset: u64,
+    inode: &Inode,
+) -> Result<Option<String>, Error> {
+    if inode.size == 0 || inode.size > MAX_SYMLINK_LEN {
         return Ok(None);
     }
-    for i in 1..needed_blocks as usize {
-        if inode.i_block[i] as u64 != first + i as u64 {
+    let len = inode.size as usize;
+
+    let bytes = if inode.is_inline() || (!inode.uses_extents() && len < 60) {
+        let raw: Vec<u8> = inode.i_block.iter().flat_map(|w| w.to_le_bytes()).collect();
+        raw[..len.min(60)].to_vec()
+    } else {
+        let Some(runs) = data_extents(src, sb, base_offset, inode)? else {
             return Ok(None);
+        };
+        let mut out = Vec::with_capacity(len);
+        for run in runs {
+            let take = run.length.min((len - out.len()) as u64) as usize;
+            match run.location {
+                Some(location) => out.extend_from_slice(&src.bytes(location, take)?),
+                None => out.resize(out.len() + take, 0),
+            }
+            if out.len() == len {
+                break;
+            }
         }
-    }
-    Ok(Some(base_offset + first * bs))
+        out
+    };
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
 }
 
 // ── Tree building ─────────────────────────────────────────────────────────────
 
 /// Recursively build a `TreeNode` tree rooted at `inode_num`.
 /// `depth` is the current recursion depth (starts at 0 for root).
-fn build_tree<R: Read + Seek>(
-    file: &mut R,
+fn build_tree<'a, S: Source<'a>>(
+    src: &mut S,
     sb: &Superblock,
     base_offset: u64,
-    name: String,
+    name: Cow<'a, str>,
     inode_num: u32,
     depth: usize,
-) -> Result<Option<TreeNode>, Error> {
+) -> Result<Option<TreeNode<'a>>, Error> {
     if depth > MAX_DEPTH {
+        recover::note(
+            None,
+            "directory",
+            format_args!("inode {inode_num} is nested more than {MAX_DEPTH} levels deep"),
+        );
         return Ok(None);
     }
 
-    let inode = read_inode(file, sb, base_offset, inode_num)?;
+    let inode = read_inode(src, sb, base_offset, inode_num)?;
 
     if inode.is_dir() {
-        let mut node = TreeNode::new_directory(name);
-        let entries = read_dir_entries(file, sb, base_offset, &inode)?;
+        let _level = limits::enter(inode_num.into())?;
+        let mut node = TreeNode::new_directory(name).with_metadata(inode.metadata());
+        let entries = read_dir_entries(src, sb, base_offset, &inode)?;
         for entry in entries {
-            if let Some(child) =
-                build_tree(file, sb, base_offset, entry.name, entry.inode, depth + 1)?
-            {
-                node.add_child(child);
+            limits::entry(&entry.name)?;
+            match build_tree(src, sb, base_offset, entry.name, entry.inode, depth + 1) {
+                Ok(Some(child)) => node.add_child(child),
+                Ok(None) => {}
+                Err(e) => {
+                    let offset = inode_offset(src, sb, base_offset, entry.inode).ok();
+                    recover::skip(offset, "inode", e)?;
+                }
             }
         }
         Ok(Some(node))
-    } else if inode.is_reg() {
+    } else {
+        inode_node(src, sb, base_offset, name, &inode)
+    }
+}
+
+/// Build the childless node for a non-directory inode. Returns `None` for
+/// block/char devices, FIFOs and sockets, which the tree leaves out.
+fn inode_node<'a, S: Source<'a>>(
+    src: &mut S,
+    sb: &Superblock,
+    base_offset: u64,
+    name: Cow<'a, str>,
+    inode: &Inode,
+) -> Result<Option<TreeNode<'a>>, Error> {
+    if inode.is_reg() {
         // Inline-data files: in tree but no location.
         if inode.is_inline() {
-            return Ok(Some(TreeNode::new_file(name, inode.size)));
+            let node = TreeNode::new_file(name, inode.size).with_metadata(inode.metadata());
+            return Ok(Some(node));
         }
-        let loc = single_run_location(file, sb, base_offset, &inode)?;
-        let node = match loc {
-            Some(offset) => TreeNode::new_file_with_location(name, inode.size, offset, inode.size),
+        let node = match data_extents(src, sb, base_offset, inode)? {
+            Some(extents) => TreeNode::new_file_with_extents(name, inode.size, extents),
             None => TreeNode::new_file(name, inode.size),
         };
-        Ok(Some(node))
+        Ok(Some(node.with_metadata(inode.metadata())))
     } else if inode.is_symlink() {
         // Fast symlinks store the target path in i_block directly; there are
         // no data blocks. Non-fast symlinks do use blocks, but we can't
         // reliably distinguish without reading more state. Never set
         // file_location for symlinks to avoid returning a bogus offset.
-        Ok(Some(TreeNode::new_file(name, inode.size)))
+        let mut metadata = inode.metadata();
+        metadata.link_target = read_symlink_target(src, sb, base_offset, inode)?;
+        Ok(Some(
+            TreeNode::new_file(name, inode.size).with_metadata(metadata),
+        ))
     } else {
         // Block/char devices, FIFOs, sockets — skip.
-        let _ = inode.file_type_char(); // suppress unused warning
         Ok(None)
     }
 }
 
+// ── Lazy access ───────────────────────────────────────────────────────────────
+
+/// Directory-entry `file_type` values (ext4 `EXT4_FT_*`).
+const FT_UNKNOWN: u8 = 0;
+const FT_REG_FILE: u8 = 1;
+const FT_DIR: u8 = 2;
+const FT_SYMLINK: u8 = 7;
+
+/// An ext2/3/4 filesystem opened for lazy, per-directory reads.
+///
+/// Only the superblock is read up front. Handles are inode numbers; a
+/// directory listing uses the entries' `file_type` byte where the
+/// filesystem records one and reads no child inodes, so listing a
+/// directory costs its data blocks alone. Devices, FIFOs and sockets
+/// are left out, as in [`detect_and_parse`].
+#[derive(Debug)]
+pub struct ExtFs<R> {
+    image: R,
+    sb: Superblock,
+    base_offset: u64,
+}
+
+impl<R: Read + Seek> ExtFs<R> {
+    /// Read the superblock of the filesystem starting at `image`'s
+    /// current position.
+    pub fn open(mut image: R) -> Result<Self, Error> {
+        let base_offset = image.stream_position()?;
+        let sb = read_superblock(&mut Stream(&mut image), base_offset)?;
+        Ok(Self {
+            image,
+            sb,
+            base_offset,
+        })
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+}
+
+impl<R: Read + Seek> Filesystem for ExtFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Ext
+    }
+
+    fn root(&self) -> filesystem::DirEntry {
+        // Root inode is always #2.
+        filesystem::DirEntry::new("/".to_string(), true, 2)
+    }
+
+    fn read_dir(&mut self, dir: &filesystem::DirEntry) -> crate::Result<Vec<filesystem::DirEntry>> {
+        let (src, sb, base) = (&mut Stream(&mut self.image), &self.sb, self.base_offset);
+        let inode = read_inode(src, sb, base, dir.id as u32)?;
+        if !inode.is_dir() {
+            return Err(filesystem::not_a_directory(dir));
+        }
+        let mut out = Vec::new();
+        for entry in read_dir_entries(src, sb, base, &inode)? {
+            let is_directory = match entry.file_type {
+                FT_DIR => true,
+                FT_REG_FILE | FT_SYMLINK => false,
+                // No file_type byte: the inode's mode says (S_IFDIR >> 12,
+                // S_IFREG >> 12, S_IFLNK >> 12).
+                FT_UNKNOWN => {
+                    let child = read_inode(src, sb, base, entry.inode)?;
+                    match child.file_type_char() {
+                        0x4 => true,
+                        0x8 | 0xA => false,
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            out.push(filesystem::DirEntry::new(
+                entry.name.into_owned(),
+                is_directory,
+                entry.inode as u64,
+            ));
+        }
+        Ok(out)
+    }
+
+    fn stat(&mut self, entry: &filesystem::DirEntry) -> crate::Result<TreeNode<'static>> {
+        let (src, sb, base) = (&mut Stream(&mut self.image), &self.sb, self.base_offset);
+        let inode = read_inode(src, sb, base, entry.id as u32)?;
+        let name = Cow::Owned(entry.name.clone());
+        if inode.is_dir() {
+            return Ok(TreeNode::new_directory(name).with_metadata(inode.metadata()));
+        }
+        match inode_node(src, sb, base, name, &inode)? {
+            Some(node) => Ok(node),
+            // A device or FIFO handed back by the caller: its metadata
+            // still says what it is.
+            None => Ok(TreeNode::new_file(entry.name.clone(), 0).with_metadata(inode.metadata())),
+        }
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
 // ── Public API ────────────────────────────────────────────────────────────────
 
 /// Return `true` if the stream at its current position looks like an ext
@@ -807,14 +1123,29 @@ fn detect_inner<R: Read + Seek>(file: &mut R) -> bool {
 ///
 /// `file`'s current position is treated as the filesystem's base offset,
 /// allowing this function to parse ext partitions that start mid-image.
-pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode, Error> {
-    let base_offset = file.stream_position()?;
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Ext, None, file, |file| {
+        let base_offset = file.stream_position()?;
+        parse_tree(&mut Stream(file), base_offset)
+    })
+}
 
-    let sb = read_superblock(file, base_offset)?;
+/// Parse an ext2/3/4 filesystem that is already in memory or mapped,
+/// starting at offset 0 of `image`.
+///
+/// Directory entry names are stored as bytes with no encoding; those
+/// that are valid UTF-8 borrow from `image` instead of being copied.
+/// Directory and pointer blocks are read in place as well.
+pub fn detect_and_parse_image<R: RandomAccess + ?Sized>(image: &R) -> Result<TreeNode<'_>, Error> {
+    limits::scope(Format::Ext, None, || parse_tree(&mut Borrowed(image), 0))
+}
+
+fn parse_tree<'a, S: Source<'a>>(src: &mut S, base_offset: u64) -> Result<TreeNode<'a>, Error> {
+    let sb = read_superblock(src, base_offset)?;
 
     // Root inode is always #2.
     let mut root =
-        build_tree(file, &sb, base_offset, "/".to_string(), 2, 0)?.ok_or(Error::BadSuperblock)?;
+        build_tree(src, &sb, base_offset, Cow::Borrowed("/"), 2, 0)?.ok_or(Error::BadSuperblock)?;
 
     root.calculate_directory_size();
     Ok(root)
@@ -1073,6 +1404,15 @@ mod tests {
         assert_eq!(root.children[0].size, 12);
     }
 
+    #[test]
+    fn image_names_borrow_from_directory_blocks() {
+        let img = make_ext2_image();
+        let root = detect_and_parse_image(&img).expect("parse ext2 image");
+        assert!(matches!(root.children[0].name, Cow::Borrowed("hello.txt")));
+        let streamed = detect_and_parse(&mut cursor_of(&img)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+    }
+
     #[test]
     fn parse_ext2_file_location() {
         let img = make_ext2_image();
@@ -1257,6 +1597,7 @@ mod tests {
             size: 0,
             flags: 0,
             i_block: [0; 15],
+            ..Default::default()
         };
         assert_eq!(inode.file_type_char(), 2);
     }
@@ -1275,7 +1616,7 @@ mod tests {
             feature_incompat: INCOMPAT_FILETYPE,
             desc_size: 32,
         };
-        let result = read_inode(&mut c, &sb, 0, 0);
+        let result = read_inode(&mut Stream(&mut c), &sb, 0, 0);
         assert!(matches!(result, Err(Error::BadSuperblock)));
     }
 
@@ -1486,6 +1827,49 @@ mod tests {
         img
     }
 
+    #[test]
+    fn symlink_target_read_from_i_block() {
+        let mut img = make_ext2_with_symlink();
+        let symlink_off = 5 * 1024 + 3 * 128;
+        img[symlink_off + 40..symlink_off + 47].copy_from_slice(b"foo/bar");
+        let mut c = cursor_of(&img);
+        let root = detect_and_parse(&mut c).unwrap();
+        let link = root.find_node("/link").unwrap();
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+        assert_eq!(link.metadata.link_target.as_deref(), Some("foo/bar"));
+    }
+
+    #[test]
+    fn regular_file_metadata_from_inode() {
+        let mut img = make_ext2_image();
+        let file_off = 5 * 1024 + 2 * 128;
+        img[file_off + 2..file_off + 4].copy_from_slice(&1000u16.to_le_bytes()); // uid lo
+        img[file_off + 120..file_off + 122].copy_from_slice(&1u16.to_le_bytes()); // uid hi
+        img[file_off + 24..file_off + 26].copy_from_slice(&50u16.to_le_bytes()); // gid
+        img[file_off + 16..file_off + 20].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+        let mut c = cursor_of(&img);
+        let root = detect_and_parse(&mut c).unwrap();
+        let file = root.find_node("/hello.txt").unwrap();
+        let m = &file.metadata;
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!(m.mode, Some(0o644));
+        assert_eq!(m.uid, Some(1000 | 1 << 16));
+        assert_eq!(m.gid, Some(50));
+        assert_eq!(m.mtime, Some(Timestamp::new(1_700_000_000, 0)));
+        // 128-byte inodes have no crtime.
+        assert_eq!(m.btime, None);
+        assert_eq!(root.metadata.mode, Some(0o755));
+    }
+
+    #[test]
+    fn inode_time_extra_extends_epoch_and_nanos() {
+        // Negative seconds (pre-1970) stay negative without an extra field.
+        assert_eq!(inode_time(u32::MAX, None), Timestamp::new(-1, 0));
+        // Epoch bits = 1 push the time past 2038; nanos = 5.
+        let t = inode_time(0, Some(1 | 5 << 2));
+        assert_eq!(t, Timestamp::new(1 << 32, 5));
+    }
+
     #[test]
     fn symlink_appears_in_tree() {
         let img = make_ext2_with_symlink();
@@ -1524,6 +1908,43 @@ mod tests {
             file.file_location.is_none(),
             "discontiguous blocks should yield no file_location"
         );
+        assert_eq!(
+            file.extents,
+            vec![
+                tree::Extent::data(7 * BS as u64, BS as u64),
+                tree::Extent::data(9 * BS as u64, BS as u64),
+            ]
+        );
+    }
+
+    #[test]
+    fn sparse_direct_pointer_reads_as_zeros() {
+        let mut img = make_ext2_image();
+        const INODE_TABLE_BLK: usize = 5;
+        const BS: usize = 1024;
+        const INODE_SIZE: usize = 128;
+        const FILE_INUM: usize = 3;
+        let file_off = INODE_TABLE_BLK * BS + (FILE_INUM - 1) * INODE_SIZE;
+        // 3 KB file: block 7, a hole, then block 9.
+        img[file_off + 4..file_off + 8].copy_from_slice(&(3072u32).to_le_bytes());
+        img[file_off + 44..file_off + 48].copy_from_slice(&0u32.to_le_bytes()); // i_block[1]
+        img[file_off + 48..file_off + 52].copy_from_slice(&9u32.to_le_bytes()); // i_block[2]
+        img[9 * BS..10 * BS].fill(b'z');
+        let mut c = cursor_of(&img);
+        let root = detect_and_parse(&mut c).expect("parse failed");
+        let file = root
+            .children
+            .iter()
+            .find(|n| n.name == "hello.txt")
+            .unwrap();
+        assert_eq!(file.extents[1], tree::Extent::hole(BS as u64));
+
+        let mut out = Vec::new();
+        crate::cat_node(&mut c, file, &mut out).unwrap();
+        assert_eq!(out.len(), 3072);
+        assert_eq!(&out[..12], b"hello world\n");
+        assert!(out[BS..2 * BS].iter().all(|&b| b == 0));
+        assert!(out[2 * BS..].iter().all(|&b| b == b'z'));
     }
 
     // ── read_bgd with 64-bit descriptor ──────────────────────────────────────
@@ -1549,7 +1970,7 @@ mod tests {
         img[2048 + 8..2048 + 12].copy_from_slice(&5u32.to_le_bytes());
         img[2048 + 40..2048 + 44].copy_from_slice(&2u32.to_le_bytes());
         let mut c = Cursor::new(img);
-        let bgd = read_bgd(&mut c, &sb, 0, 0).expect("read_bgd should succeed");
+        let bgd = read_bgd(&mut Stream(&mut c), &sb, 0, 0).expect("read_bgd should succeed");
         // inode_table = (2 << 32) | 5 = 8589934597
         assert_eq!(bgd.inode_table, (2u64 << 32) | 5u64);
     }
@@ -1627,22 +2048,24 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(img);
-        let extents = collect_extents(&mut c, &sb, 0, &root, 5).expect("collect_extents");
+        let extents =
+            collect_extents(&mut Stream(&mut c), &sb, 0, &root, 5).expect("collect_extents");
         assert_eq!(extents.len(), 1);
         assert_eq!(extents[0].phys, 10);
         assert_eq!(extents[0].len, 1);
         assert!(!extents[0].unwritten);
     }
 
-    // ── single_run_location edge cases ────────────────────────────────────────
+    // ── data_extents edge cases ───────────────────────────────────────────────
 
     #[test]
-    fn single_run_location_returns_none_for_empty_inode() {
+    fn data_extents_returns_none_for_empty_inode() {
         let inode = Inode {
             mode: S_IFREG,
             size: 0,
             flags: 0,
             i_block: [0; 15],
+            ..Default::default()
         };
         let sb = Superblock {
             inodes_per_group: 256,
@@ -1653,17 +2076,18 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let loc = single_run_location(&mut c, &sb, 0, &inode).unwrap();
-        assert!(loc.is_none());
+        let runs = data_extents(&mut Stream(&mut c), &sb, 0, &inode).unwrap();
+        assert!(runs.is_none());
     }
 
     #[test]
-    fn single_run_location_returns_none_for_inline_inode() {
+    fn data_extents_returns_none_for_inline_inode() {
         let inode = Inode {
             mode: S_IFREG,
             size: 10,
             flags: EXT4_INLINE_DATA_FL,
             i_block: [0; 15],
+            ..Default::default()
         };
         let sb = Superblock {
             inodes_per_group: 256,
@@ -1674,15 +2098,15 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let loc = single_run_location(&mut c, &sb, 0, &inode).unwrap();
-        assert!(loc.is_none());
+        let runs = data_extents(&mut Stream(&mut c), &sb, 0, &inode).unwrap();
+        assert!(runs.is_none());
     }
 
     #[test]
-    fn single_run_location_extent_unwritten_returns_none() {
+    fn data_extents_unwritten_extent_is_hole() {
         // Build an inode with EXT4_EXTENTS_FL and a single UNWRITTEN extent.
-        // single_run_location should return None because unwritten extents
-        // contain stale on-disk data.
+        // Unwritten extents contain stale on-disk data, so the whole file
+        // must read back as a hole.
         let em = EXTENT_MAGIC.to_le_bytes();
         let mut i_block_bytes = [0u8; 60];
         i_block_bytes[0..2].copy_from_slice(&em); // magic
@@ -1705,6 +2129,7 @@ mod tests {
             size: 1024,
             flags: EXT4_EXTENTS_FL,
             i_block,
+            ..Default::default()
         };
         let sb = Superblock {
             inodes_per_group: 256,
@@ -1715,8 +2140,10 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let loc = single_run_location(&mut c, &sb, 0, &inode).unwrap();
-        assert!(loc.is_none(), "unwritten extent should yield no location");
+        let runs = data_extents(&mut Stream(&mut c), &sb, 0, &inode)
+            .unwrap()
+            .unwrap();
+        assert_eq!(runs, vec![tree::Extent::hole(1024)]);
     }
 
     #[test]
@@ -1732,7 +2159,7 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let extents = collect_extents(&mut c, &sb, 0, &data, 5).unwrap();
+        let extents = collect_extents(&mut Stream(&mut c), &sb, 0, &data, 5).unwrap();
         assert!(extents.is_empty());
     }
 
@@ -1754,7 +2181,7 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let extents = collect_extents(&mut c, &sb, 0, &data, 0).unwrap();
+        let extents = collect_extents(&mut Stream(&mut c), &sb, 0, &data, 0).unwrap();
         assert!(extents.is_empty());
     }
 
@@ -1940,20 +2367,22 @@ mod tests {
         data[4..6].copy_from_slice(&4u16.to_le_bytes()); // rec_len=4 < 8 → break
         data[6] = 1; // name_len
         let mut entries: Vec<DirEntry> = Vec::new();
-        scan_dir_block(&data, false, &mut entries);
+        scan_dir_block(Bytes::Buffer(&data), false, &mut entries);
         assert!(entries.is_empty(), "bad rec_len should break immediately");
     }
 
-    // ── single_run_location: first block zero → None ──────────────────────────
+    // ── data_extents: all block pointers zero → one hole ──────────────────────
 
     #[test]
-    fn single_run_location_first_block_zero_returns_none() {
-        // Regular file with size=12 but i_block[0]=0 → first=0 → Ok(None) (line 709).
+    fn data_extents_all_zero_pointers_is_sparse() {
+        // Regular file with size=12 but no blocks allocated: one block-sized
+        // hole, trimmed to 12 bytes later by new_file_with_extents.
         let inode = Inode {
             mode: S_IFREG | 0o644,
             size: 12,
             flags: 0,
             i_block: [0; 15], // all zero → first=i_block[0]=0
+            ..Default::default()
         };
         let sb = Superblock {
             inodes_per_group: 256,
@@ -1964,8 +2393,10 @@ mod tests {
             desc_size: 32,
         };
         let mut c = Cursor::new(vec![0u8; 0]);
-        let loc = single_run_location(&mut c, &sb, 0, &inode).unwrap();
-        assert!(loc.is_none(), "zero first block → no location");
+        let runs = data_extents(&mut Stream(&mut c), &sb, 0, &inode)
+            .unwrap()
+            .unwrap();
+        assert_eq!(runs, vec![tree::Extent::hole(1024)]);
     }
 
     // ── build_tree: block-device inode is skipped ─────────────────────────────
@@ -2064,10 +2495,11 @@ mod tests {
             size: 12289,
             flags: 0,
             i_block,
+            ..Default::default()
         };
 
         let mut c = Cursor::new(img);
-        let blocks = collect_classical_blocks(&mut c, &sb, 0, &inode, 12289).unwrap();
+        let blocks = collect_classical_blocks(&mut Stream(&mut c), &sb, 0, &inode, 12289).unwrap();
         // Should have 12 direct + 1 DI block = 13 total.
         assert_eq!(blocks.len(), 13);
         assert_eq!(blocks[0], 1);
@@ -2114,10 +2546,11 @@ mod tests {
             size: 12289,
             flags: 0,
             i_block,
+            ..Default::default()
         };
 
         let mut c = Cursor::new(img);
-        let blocks = collect_classical_blocks(&mut c, &sb, 0, &inode, 12289).unwrap();
+        let blocks = collect_classical_blocks(&mut Stream(&mut c), &sb, 0, &inode, 12289).unwrap();
         // Should have 12 direct + 1 TI block = 13 total.
         assert_eq!(blocks.len(), 13);
         assert_eq!(blocks[12], 20); // TI-resolved block
@@ -2138,10 +2571,46 @@ mod tests {
             desc_size: 32,
         };
         let mut c = cursor_of(&img);
-        let result = build_tree(&mut c, &sb, 0, "deep".to_string(), 2, MAX_DEPTH + 1);
+        let result = build_tree(&mut Stream(&mut c), &sb, 0, "deep".into(), 2, MAX_DEPTH + 1);
         assert!(
             result.unwrap().is_none(),
             "depth > MAX_DEPTH should return None"
         );
     }
+
+    // ── ExtFs ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn ext_fs_to_tree_matches_detect_and_parse() {
+        let img = make_ext2_with_symlink();
+        let eager = detect_and_parse(&mut cursor_of(&img)).unwrap();
+        let lazy = ExtFs::open(cursor_of(&img)).unwrap().to_tree().unwrap();
+        assert_eq!(format!("{:?}", lazy), format!("{:?}", eager));
+    }
+
+    #[test]
+    fn ext_fs_reads_file_through_lookup() {
+        let img = make_ext2_image();
+        let mut fs = ExtFs::open(cursor_of(&img)).unwrap();
+        let entry = fs.lookup("hello.txt").unwrap().unwrap();
+        assert_eq!(entry.id, 3);
+        let mut out = Vec::new();
+        fs.open(&entry).unwrap().read_to_end(&mut out).unwrap();
+        let expected = detect_and_parse(&mut cursor_of(&img)).unwrap();
+        let node = expected.find_node("hello.txt").unwrap();
+        assert_eq!(out.len() as u64, node.size);
+        assert!(fs.read_dir(&entry).is_err());
+    }
+
+    #[test]
+    fn ext_fs_without_filetype_reads_child_inodes() {
+        let mut img = make_ext2_with_symlink();
+        let incompat = 1024 + 96;
+        img[incompat..incompat + 4].copy_from_slice(&0u32.to_le_bytes());
+        let mut fs = ExtFs::open(cursor_of(&img)).unwrap();
+        let root = fs.root();
+        let entries = fs.read_dir(&root).unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|e| !e.is_directory));
+    }
 }
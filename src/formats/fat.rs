@@ -9,8 +9,10 @@
 //! at the file's bytes in the *original reader* relative to whatever byte
 //! offset `detect_and_parse` was called at. For files whose clusters are
 //! physically contiguous (the typical case for freshly-written images), this
-//! means `cat_node` works directly. For fragmented files, `file_location` is
-//! `None` and `cat_node` returns an error.
+//! means `cat_node` works directly. Fragmented files carry their cluster
+//! chain as a list of [`Extent`]s instead, which `cat_node` stitches
+//! together. Only files whose chain is shorter than their recorded size
+//! are left without a location.
 //!
 //! References — Microsoft FAT Specification, 2004 ("fatgen103.doc"):
 //!   § 2   BPB layout
@@ -21,7 +23,11 @@
 
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem};
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
 
 // ---------------------------------------------------------------------------
 // Error
@@ -33,6 +39,8 @@ pub enum Error {
     TooShort,
     /// Boot-sector signature absent or BPB fields are out of spec.
     BadBootSector,
+    /// A [`FatFs`] handle no longer points at a live directory entry.
+    BadDirEntry,
     /// Underlying I/O error.
     Io(std::io::Error),
 }
@@ -42,6 +50,7 @@ impl std::fmt::Display for Error {
         match self {
             Error::TooShort => write!(f, "image too short for a FAT boot sector"),
             Error::BadBootSector => write!(f, "invalid FAT BPB / boot sector"),
+            Error::BadDirEntry => write!(f, "handle does not point at a FAT directory entry"),
             Error::Io(e) => write!(f, "FAT I/O: {e}"),
         }
     }
@@ -63,6 +72,16 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadBootSector => crate::Error::not_recognized(Format::Fat, e),
+            e => crate::Error::corrupt(Format::Fat, e),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // FAT type
 // ---------------------------------------------------------------------------
@@ -95,6 +114,7 @@ fn is_bad_cluster(fat_type: FatType, cluster: u32) -> bool {
 // Parsed BPB + derived geometry (§ 2–4)
 // ---------------------------------------------------------------------------
 
+#[derive(Debug)]
 struct Context {
     /// Absolute byte offset of the FAT filesystem's boot sector in the reader.
     base_offset: u64,
@@ -301,13 +321,26 @@ fn read_dir_bytes<R: Read + Seek>(
     file: &mut R,
     start_cluster: u32,
 ) -> Result<Vec<u8>, Error> {
+    read_dir_region(ctx, file, start_cluster).map(|(bytes, _)| bytes)
+}
+
+/// Like [`read_dir_bytes`], also returning the cluster chain the bytes
+/// came from (empty for the fixed root-directory region), so a position
+/// in the buffer can be mapped back to the image with [`dir_pos_abs`].
+fn read_dir_region<R: Read + Seek>(
+    ctx: &Context,
+    file: &mut R,
+    start_cluster: u32,
+) -> Result<(Vec<u8>, Vec<u32>), Error> {
     if start_cluster == 0 {
         file.seek(SeekFrom::Start(ctx.base_offset + ctx.root_dir_rel))?;
+        limits::alloc(ctx.root_dir_size_bytes)?;
         let mut buf = vec![0u8; ctx.root_dir_size_bytes as usize];
         file.read_exact(&mut buf)?;
-        return Ok(buf);
+        return Ok((buf, Vec::new()));
     }
     let chain = ctx.cluster_chain(file, start_cluster)?;
+    limits::alloc(chain.len() as u64 * ctx.bytes_per_cluster)?;
     let mut buf = Vec::with_capacity(chain.len() * ctx.bytes_per_cluster as usize);
     for &cluster in &chain {
         file.seek(SeekFrom::Start(ctx.cluster_abs(cluster)))?;
@@ -315,7 +348,18 @@ fn read_dir_bytes<R: Read + Seek>(
         buf.resize(start + ctx.bytes_per_cluster as usize, 0);
         file.read_exact(&mut buf[start..])?;
     }
-    Ok(buf)
+    Ok((buf, chain))
+}
+
+/// Absolute image offset of byte `pos` of a directory read by
+/// [`read_dir_region`] from `chain`.
+fn dir_pos_abs(ctx: &Context, chain: &[u32], pos: usize) -> u64 {
+    if chain.is_empty() {
+        return ctx.base_offset + ctx.root_dir_rel + pos as u64;
+    }
+    let pos = pos as u64;
+    let cluster = chain[(pos / ctx.bytes_per_cluster) as usize];
+    ctx.cluster_abs(cluster) + pos % ctx.bytes_per_cluster
 }
 
 /// Extract the 13 UTF-16LE code units from an LFN entry (§ 6.3).
@@ -375,10 +419,27 @@ fn short_name_83(entry: &[u8]) -> String {
 }
 
 struct RawEntry {
+    /// Byte position of the 8.3 entry inside the directory's bytes.
+    pos: usize,
     name: String,
     is_dir: bool,
     file_size: u32,
     start_cluster: u32,
+    metadata: Metadata,
+}
+
+/// Decode a FAT date/time pair (§ 6.3). `tenths` is the 10 ms
+/// creation-time refinement (0–199), or 0 where the format has none.
+///
+/// FAT stores local time with no zone; it is reported as if it were UTC.
+/// A zero date means "not recorded".
+fn fat_timestamp(date: u16, time: u16, tenths: u8) -> Option<Timestamp> {
+    let t = Timestamp::from_dos(date, time)?;
+    let tenths = tenths.min(199) as u32;
+    Some(Timestamp::new(
+        t.secs + (tenths / 100) as i64,
+        (tenths % 100) * 10_000_000,
+    ))
 }
 
 /// Parse all valid (non-deleted, non-dot) 32-byte directory entries from
@@ -392,7 +453,7 @@ fn parse_dir_entries(dir_bytes: &[u8]) -> Vec<RawEntry> {
     let mut out = Vec::new();
     let mut lfn_pieces: Vec<(u8, [u16; 13])> = Vec::new();
 
-    for chunk in dir_bytes.chunks_exact(32) {
+    for (index, chunk) in dir_bytes.chunks_exact(32).enumerate() {
         let first = chunk[0];
         if first == 0x00 {
             break; // end-of-directory marker
@@ -435,22 +496,28 @@ fn parse_dir_entries(dir_bytes: &[u8]) -> Vec<RawEntry> {
         let cluster_lo = u16::from_le_bytes([chunk[26], chunk[27]]) as u32;
         let start_cluster = (cluster_hi << 16) | cluster_lo;
 
+        let le16 = |at: usize| u16::from_le_bytes([chunk[at], chunk[at + 1]]);
+        let mut metadata = Metadata::new(if is_dir {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        });
+        metadata.btime = fat_timestamp(le16(16), le16(14), chunk[13]);
+        metadata.atime = fat_timestamp(le16(18), 0, 0);
+        metadata.mtime = fat_timestamp(le16(24), le16(22), 0);
+
         out.push(RawEntry {
+            pos: index * 32,
             name,
             is_dir,
             file_size,
             start_cluster,
+            metadata,
         });
     }
     out
 }
 
-/// True when every consecutive pair of clusters is adjacent in physical
-/// cluster number (i.e. the file's bytes are one contiguous run on disk).
-fn is_contiguous(chain: &[u32]) -> bool {
-    chain.windows(2).all(|w| w[1] == w[0] + 1)
-}
-
 /// Recursively build a [`TreeNode`] subtree rooted at `start_cluster`
 /// (pass `0` for the FAT12/16 root directory, or the actual root-cluster
 /// for FAT32 and subdirectories).
@@ -459,20 +526,35 @@ fn build_tree<R: Read + Seek>(
     file: &mut R,
     start_cluster: u32,
     depth: u32,
-) -> Result<Vec<TreeNode>, Error> {
+) -> Result<Vec<TreeNode<'static>>, Error> {
     if depth > 32 {
+        recover::note(
+            (start_cluster >= 2).then(|| ctx.cluster_abs(start_cluster)),
+            "directory",
+            "nested more than 32 levels deep",
+        );
         return Ok(Vec::new());
     }
 
+    let _level = limits::enter(start_cluster.into())?;
     let dir_bytes = read_dir_bytes(ctx, file, start_cluster)?;
     let entries = parse_dir_entries(&dir_bytes);
+    limits::dir_entries(entries.len() as u64)?;
     let mut nodes = Vec::with_capacity(entries.len());
 
     for entry in entries {
+        limits::entry(&entry.name)?;
         if entry.is_dir {
-            let mut dir_node = TreeNode::new_directory(entry.name);
-            let children = if entry.start_cluster >= 2 {
-                build_tree(ctx, file, entry.start_cluster, depth + 1)?
+            let start_cluster = entry.start_cluster;
+            let mut dir_node = TreeNode::new_directory(entry.name).with_metadata(entry.metadata);
+            let children = if start_cluster >= 2 {
+                match build_tree(ctx, file, start_cluster, depth + 1) {
+                    Ok(children) => children,
+                    Err(e) => {
+                        recover::skip(Some(ctx.cluster_abs(start_cluster)), "directory", e)?;
+                        Vec::new()
+                    }
+                }
             } else {
                 Vec::new()
             };
@@ -481,31 +563,150 @@ fn build_tree<R: Read + Seek>(
             }
             nodes.push(dir_node);
         } else {
-            let node = if entry.start_cluster >= 2 && entry.file_size > 0 {
-                let chain = ctx.cluster_chain(file, entry.start_cluster)?;
-                let required_clusters =
-                    (entry.file_size as u64).div_ceil(ctx.bytes_per_cluster) as usize;
-                if !chain.is_empty() && is_contiguous(&chain) && chain.len() >= required_clusters {
-                    TreeNode::new_file_with_location(
-                        entry.name,
-                        entry.file_size as u64,
-                        ctx.cluster_abs(chain[0]),
-                        entry.file_size as u64,
-                    )
-                } else {
-                    // Fragmented or truncated chain: tree entry exists but cat_node won't work.
-                    TreeNode::new_file(entry.name, entry.file_size as u64)
+            let (name, start_cluster) = (entry.name.clone(), entry.start_cluster);
+            match file_node(ctx, file, entry) {
+                Ok(node) => nodes.push(node),
+                Err(e) => {
+                    let offset = (start_cluster >= 2).then(|| ctx.cluster_abs(start_cluster));
+                    recover::skip(offset, "cluster chain", e)?;
+                    nodes.push(TreeNode::new_file(name, 0));
                 }
-            } else {
-                // Zero-length or no-cluster file.
-                TreeNode::new_file(entry.name, entry.file_size as u64)
-            };
-            nodes.push(node);
+            }
         }
     }
     Ok(nodes)
 }
 
+/// Build the node for a non-directory entry from its cluster chain.
+fn file_node<R: Read + Seek>(
+    ctx: &Context,
+    file: &mut R,
+    entry: RawEntry,
+) -> Result<TreeNode<'static>, Error> {
+    let node = if entry.start_cluster >= 2 && entry.file_size > 0 {
+        let chain = ctx.cluster_chain(file, entry.start_cluster)?;
+        let required_clusters = (entry.file_size as u64).div_ceil(ctx.bytes_per_cluster) as usize;
+        if !chain.is_empty() && chain.len() >= required_clusters {
+            // One extent per cluster; adjacent clusters are merged
+            // back into a single run by `new_file_with_extents`.
+            let extents = chain
+                .iter()
+                .map(|&c| Extent::data(ctx.cluster_abs(c), ctx.bytes_per_cluster))
+                .collect();
+            TreeNode::new_file_with_extents(entry.name, entry.file_size as u64, extents)
+        } else {
+            // Truncated chain: tree entry exists but cat_node won't work.
+            recover::note(
+                Some(ctx.cluster_abs(entry.start_cluster)),
+                "cluster chain",
+                format_args!(
+                    "'{}' needs {required_clusters} clusters, chain has {}",
+                    entry.name,
+                    chain.len()
+                ),
+            );
+            TreeNode::new_file(entry.name, entry.file_size as u64)
+        }
+    } else {
+        // Zero-length or no-cluster file.
+        TreeNode::new_file(entry.name, entry.file_size as u64)
+    };
+    Ok(node.with_metadata(entry.metadata))
+}
+
+// ---------------------------------------------------------------------------
+// Lazy access
+// ---------------------------------------------------------------------------
+
+/// Handle id of the root directory, which has no 8.3 entry of its own.
+/// No real entry can sit at absolute offset 0: that is the boot sector.
+const ROOT_ID: u64 = 0;
+
+/// A FAT12/16/32 filesystem opened for lazy, per-directory reads.
+///
+/// Only the BPB is read up front. Handles are the absolute image offset
+/// of an entry's 8.3 directory record, so [`stat`](Filesystem::stat)
+/// reads 32 bytes and, for files, the cluster chain.
+#[derive(Debug)]
+pub struct FatFs<R> {
+    image: R,
+    ctx: Context,
+}
+
+impl<R: Read + Seek> FatFs<R> {
+    /// Read the BPB of the filesystem starting at `image`'s current
+    /// position.
+    pub fn open(mut image: R) -> Result<Self, Error> {
+        let ctx = read_bpb(&mut image)?;
+        Ok(Self { image, ctx })
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    /// Re-read the 8.3 entry at `offset`, named `name`.
+    fn entry_at(&mut self, offset: u64, name: &str) -> Result<RawEntry, Error> {
+        self.image.seek(SeekFrom::Start(offset))?;
+        let mut raw = [0u8; 32];
+        self.image.read_exact(&mut raw)?;
+        let mut entry = parse_dir_entries(&raw).pop().ok_or(Error::BadDirEntry)?;
+        entry.name = name.to_string();
+        Ok(entry)
+    }
+}
+
+impl<R: Read + Seek> Filesystem for FatFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Fat
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, ROOT_ID)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> crate::Result<Vec<DirEntry>> {
+        let start_cluster = if dir.id == ROOT_ID {
+            match self.ctx.fat_type {
+                FatType::Fat12 | FatType::Fat16 => 0, // fixed root-dir region
+                FatType::Fat32 => self.ctx.root_cluster,
+            }
+        } else {
+            let entry = self.entry_at(dir.id, &dir.name)?;
+            if !entry.is_dir {
+                return Err(not_a_directory(dir));
+            }
+            if entry.start_cluster < 2 {
+                return Ok(Vec::new());
+            }
+            entry.start_cluster
+        };
+        let (bytes, chain) = read_dir_region(&self.ctx, &mut self.image, start_cluster)?;
+        Ok(parse_dir_entries(&bytes)
+            .into_iter()
+            .map(|e| DirEntry::new(e.name, e.is_dir, dir_pos_abs(&self.ctx, &chain, e.pos)))
+            .collect())
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> crate::Result<TreeNode<'static>> {
+        if entry.id == ROOT_ID {
+            return Ok(TreeNode::new_directory(entry.name.clone()));
+        }
+        let raw = self.entry_at(entry.id, &entry.name)?;
+        if raw.is_dir {
+            return Ok(TreeNode::new_directory(raw.name).with_metadata(raw.metadata));
+        }
+        Ok(file_node(&self.ctx, &mut self.image, raw)?)
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
@@ -528,7 +729,11 @@ pub fn detect<R: Read + Seek>(file: &mut R) -> bool {
 /// The caller must position `file` at the first byte of the FAT filesystem
 /// (the BPB sector). For a raw single-filesystem image that is byte 0; for
 /// a partitioned image the caller must seek to the partition start first.
-pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Fat, None, file, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
     let ctx = read_bpb(file)?;
 
     let root_cluster = match ctx.fat_type {
@@ -1167,6 +1372,20 @@ mod tests {
 
     #[test]
     fn subdirectory_with_children_recurses() {
+        let img = make_fat12_with_subdir();
+        let mut cursor = Cursor::new(&img);
+        let tree = detect_and_parse(&mut cursor).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        let dir = &tree.children[0];
+        assert!(dir.is_directory);
+        assert_eq!(dir.name, "SUBDIR");
+        assert_eq!(dir.children.len(), 1);
+        let child = &dir.children[0];
+        assert_eq!(child.name, "CHILD.TXT");
+        assert_eq!(child.size, 5);
+    }
+
+    fn make_fat12_with_subdir() -> Vec<u8> {
         // FAT12 image with a subdirectory at cluster 2 containing a file at cluster 3.
         // Sectors: 0=BPB, 1=FAT1, 2=FAT2, 3=root-dir, 4=cluster2(subdir), 5=cluster3(file-data).
         // data_start = (1 + 2*1)*512 + 16*32 = 1536 + 512 = 2048
@@ -1214,17 +1433,32 @@ mod tests {
 
         // File data at cluster 3 (offset 2560)
         img[2560..2565].copy_from_slice(b"hello");
+        img
+    }
 
-        let mut cursor = Cursor::new(&img);
-        let tree = detect_and_parse(&mut cursor).unwrap();
-        assert_eq!(tree.children.len(), 1);
-        let dir = &tree.children[0];
-        assert!(dir.is_directory);
-        assert_eq!(dir.name, "SUBDIR");
-        assert_eq!(dir.children.len(), 1);
-        let child = &dir.children[0];
-        assert_eq!(child.name, "CHILD.TXT");
-        assert_eq!(child.size, 5);
+    #[test]
+    fn fat_fs_handles_are_entry_offsets() {
+        let img = make_fat12_with_subdir();
+        let mut fs = FatFs::open(Cursor::new(&img)).unwrap();
+        let subdir = fs.lookup("SUBDIR").unwrap().unwrap();
+        assert!(subdir.is_directory);
+        assert_eq!(subdir.id, 1536);
+        let child = fs.lookup("SUBDIR/CHILD.TXT").unwrap().unwrap();
+        assert_eq!(child.id, 2048);
+
+        let mut out = String::new();
+        fs.open(&child).unwrap().read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello");
+        assert!(fs.read_dir(&child).is_err());
+    }
+
+    #[test]
+    fn fat_fs_to_tree_matches_detect_and_parse() {
+        for img in [make_fat12_image(), make_fat12_with_subdir()] {
+            let eager = detect_and_parse(&mut Cursor::new(&img)).unwrap();
+            let lazy = FatFs::open(Cursor::new(&img)).unwrap().to_tree().unwrap();
+            assert_eq!(format!("{:?}", lazy), format!("{:?}", eager));
+        }
     }
 
     // ── build_tree depth limit ────────────────────────────────────────────────
@@ -1276,6 +1510,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fragmented_chain_file_reads_through_extents() {
+        // Chain 3 → 2: the file's second cluster sits *before* its first.
+        let mut img = make_fat12_image();
+        let f1 = 512usize;
+        img[f1 + 3] = 0xFF; // entry 2 = EOC (0xFFF)
+        img[f1 + 4] = 0x2F; // entry 2 high nibble | entry 3 low nibble (0x2)
+        img[f1 + 5] = 0x00; // entry 3 high byte
+        let rd = 512 * 3;
+        img[rd + 26..rd + 28].copy_from_slice(&3u16.to_le_bytes()); // first cluster = 3
+        img[rd + 28..rd + 32].copy_from_slice(&600u32.to_le_bytes());
+        img[512 * 5..512 * 6].fill(b'a'); // cluster 3
+        img[512 * 4..512 * 5].fill(b'b'); // cluster 2
+
+        let mut cursor = Cursor::new(img);
+        let tree = detect_and_parse(&mut cursor).unwrap();
+        let file = &tree.children[0];
+        assert!(file.file_location.is_none());
+        assert_eq!(
+            file.extents,
+            vec![Extent::data(512 * 5, 512), Extent::data(512 * 4, 88)]
+        );
+
+        let mut out = Vec::new();
+        crate::cat_node(&mut cursor, file, &mut out).unwrap();
+        assert_eq!(out.len(), 600);
+        assert!(out[..512].iter().all(|&b| b == b'a'));
+        assert!(out[512..].iter().all(|&b| b == b'b'));
+    }
+
+    #[test]
+    fn fat_timestamp_decodes_date_time_and_tenths() {
+        // 2023-07-14, 13:45:58, plus 150 × 10 ms.
+        let date = ((2023 - 1980) << 9) | (7 << 5) | 14;
+        let time = (13 << 11) | (45 << 5) | (58 / 2);
+        let t = fat_timestamp(date, time, 150).unwrap();
+        let base = Timestamp::from_civil(2023, 7, 14, 13, 45, 58).unwrap();
+        assert_eq!(t, Timestamp::new(base.secs + 1, 500_000_000));
+        assert!(fat_timestamp(0, time, 0).is_none());
+    }
+
+    #[test]
+    fn file_entry_carries_write_time() {
+        let mut img = make_fat12_image();
+        let rd = 512 * 3;
+        let date: u16 = ((2001 - 1980) << 9) | (9 << 5) | 9;
+        img[rd + 24..rd + 26].copy_from_slice(&date.to_le_bytes());
+        let mut cursor = Cursor::new(img);
+        let tree = detect_and_parse(&mut cursor).unwrap();
+        let meta = &tree.children[0].metadata;
+        assert_eq!(meta.kind, EntryKind::File);
+        assert_eq!(meta.mtime, Timestamp::from_civil(2001, 9, 9, 0, 0, 0));
+        assert_eq!(meta.mode, None);
+    }
+
     #[test]
     fn zero_size_file_has_no_location() {
         let mut img = make_fat12_image();
@@ -23,9 +23,11 @@
 //!   LBA fields), but every real disk image we care about uses 512.
 //!   Detecting 4K sectors is in scope for v3.1.
 
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
 use crate::tree::TreeNode;
 
 const SECTOR_SIZE: u64 = 512;
@@ -91,6 +93,16 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadSignature => crate::Error::not_recognized(Format::Gpt, e),
+            Error::UnsupportedEntrySize(_) => crate::Error::unsupported(Format::Gpt, e),
+        }
+    }
+}
+
 /// Parsed GPT header. Only the fields we need to walk the entry
 /// array; the others (revision, header size, CRCs, disk GUID) are
 /// available in a follow-on if/when we want them.
@@ -103,12 +115,12 @@ pub struct Header {
 
 /// Read and parse the GPT header at LBA 1, then the partition entry
 /// array it points at. Returns one [`Partition`] per non-empty entry.
-pub fn parse(file: &mut File) -> Result<Vec<Partition>, Error> {
+pub fn parse<R: Read + Seek>(file: &mut R) -> Result<Vec<Partition>, Error> {
     let header = read_header(file)?;
     read_entries(file, &header)
 }
 
-fn read_header(file: &mut File) -> Result<Header, Error> {
+fn read_header<R: Read + Seek>(file: &mut R) -> Result<Header, Error> {
     file.seek(SeekFrom::Start(SECTOR_SIZE))?;
     let mut sector = [0u8; SECTOR_SIZE as usize];
     if file.read(&mut sector)? < SECTOR_SIZE as usize {
@@ -141,7 +153,7 @@ pub fn parse_header_sector(sector: &[u8]) -> Result<Header, Error> {
     })
 }
 
-fn read_entries(file: &mut File, header: &Header) -> Result<Vec<Partition>, Error> {
+fn read_entries<R: Read + Seek>(file: &mut R, header: &Header) -> Result<Vec<Partition>, Error> {
     let total = (header.num_entries as u64).saturating_mul(header.entry_size as u64);
     // 16 KiB for a typical 128 × 128 layout. Cap to prevent a
     // pathological header from triggering a multi-gigabyte alloc.
@@ -151,6 +163,7 @@ fn read_entries(file: &mut File, header: &Header) -> Result<Vec<Partition>, Erro
     }
 
     file.seek(SeekFrom::Start(header.entries_lba * SECTOR_SIZE))?;
+    limits::alloc(total)?;
     let mut buf = vec![0u8; total as usize];
     file.read_exact(&mut buf)?;
 
@@ -167,6 +180,13 @@ fn read_entries(file: &mut File, header: &Header) -> Result<Vec<Partition>, Erro
         let first_lba = u64::from_le_bytes(entry[32..40].try_into().unwrap());
         let last_lba = u64::from_le_bytes(entry[40..48].try_into().unwrap());
         // last_lba is inclusive (UEFI §5.3.3 table 5-7).
+        if last_lba < first_lba {
+            recover::note(
+                Some(header.entries_lba * SECTOR_SIZE + start as u64),
+                "partition entry",
+                format_args!("ends at LBA {last_lba}, before its start at LBA {first_lba}"),
+            );
+        }
         let length = last_lba
             .saturating_add(1)
             .saturating_sub(first_lba)
@@ -201,7 +221,7 @@ fn decode_utf16le(bytes: &[u8]) -> String {
 
 /// Convert a parsed partition list to the `TreeNode` shape `cat_node`
 /// expects. Mirrors `mbr::to_tree`.
-pub fn to_tree(partitions: &[Partition]) -> TreeNode {
+pub fn to_tree(partitions: &[Partition]) -> TreeNode<'static> {
     let mut root = TreeNode::new_directory("/".to_string());
     for p in partitions {
         // Prefer the human-readable name if non-empty; fall back to
@@ -240,9 +260,34 @@ fn sanitize(s: &str) -> String {
 }
 
 /// One-call detect + parse + tree.
-pub fn detect_and_parse(file: &mut File) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Gpt, None, file, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
     let parts = parse(file)?;
-    Ok(to_tree(&parts))
+    let root = to_tree(&parts);
+    limits::dir_entries(root.children.len() as u64)?;
+    for child in &root.children {
+        limits::entry(&child.name)?;
+    }
+    Ok(root)
+}
+
+/// Like [`detect_and_parse`], but also parses the filesystem inside each
+/// partition. Partitions holding a recognised filesystem become
+/// directories containing its tree, with locations translated to
+/// absolute offsets in `file`; the rest stay raw byte ranges. See
+/// [`crate::probe::open_filesystem_at`].
+pub fn detect_and_parse_recursive<R: Read + Seek>(
+    file: &mut R,
+) -> Result<TreeNode<'static>, Error> {
+    // The partitions' filesystems share this parse's limits.
+    limits::metered(Format::Gpt, None, file, |file| {
+        let mut root = detect_and_parse(file)?;
+        crate::probe::descend_into_partitions(file, &mut root);
+        Ok(root)
+    })
 }
 
 #[cfg(test)]
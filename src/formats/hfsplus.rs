@@ -17,15 +17,21 @@
 //!   every leaf record in key order.
 //! - Decodes UTF-16 BE filenames (§2.1, HFSPlusUniStr255).
 //! - Builds a [`TreeNode`] tree rooted at the HFS+ root folder (CNID 2).
-//! - Does **not** read resource forks, extended attributes, or file data. The
-//!   `file_location` field is populated for files whose data fork fits in a
-//!   single contiguous extent so `cat_node` can serve it; multi-extent files
-//!   get `file_location = None`.
+//! - Carries the catalog dates and BSD owner/mode (§4.3.6–7) as
+//!   [`Metadata`]; a symlink's target is read from its data fork.
+//! - Does **not** read resource forks, extended attributes, or other file
+//!   data. A file's data fork is described by the up-to-eight extents in its
+//!   catalog record (§5.2) so `cat_node` can serve it; forks that continue
+//!   in the extents overflow file get no location.
 
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem};
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
 
 // ── Magic numbers (§4.2 Volume Header signature field) ─────────────────────
 
@@ -44,10 +50,13 @@ const VOLUME_HEADER_SIZE: usize = 512;
 
 /// CNID of the root folder. Children of the volume root have parent_cnid = 2.
 const HFS_ROOT_FOLDER_CNID: u32 = 2;
+/// Parent CNID of the root folder's catalog record.
+const HFS_ROOT_PARENT_CNID: u32 = 1;
 
 // ── B-tree node kinds ──────────────────────────────────────────────────────
 
 const BTREE_LEAF_NODE: u8 = 0xFF;
+const BTREE_INDEX_NODE: u8 = 0x00;
 const BTREE_HEADER_NODE: u8 = 0x01;
 
 // ── Catalog record types ───────────────────────────────────────────────────
@@ -57,6 +66,12 @@ const RECORD_TYPE_FILE: u16 = 0x0002;
 const RECORD_TYPE_FOLDER_THREAD: u16 = 0x0003;
 const RECORD_TYPE_FILE_THREAD: u16 = 0x0004;
 
+/// Seconds between the HFS+ epoch (1904-01-01 GMT) and the Unix epoch.
+const HFS_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Longest symlink target we will read out of a data fork.
+const MAX_SYMLINK_LEN: u64 = 4096;
+
 // ── Error type ─────────────────────────────────────────────────────────────
 
 /// Errors that can arise while detecting or parsing an HFS+ volume.
@@ -109,6 +124,17 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::HfsPlus, e),
+            Error::BadVersion => crate::Error::unsupported(Format::HfsPlus, e),
+            e => crate::Error::corrupt(Format::HfsPlus, e),
+        }
+    }
+}
+
 // ── On-disk structs (all fields big-endian) ────────────────────────────────
 
 /// HFSPlusForkData (80 bytes, §2.4).
@@ -153,14 +179,28 @@ impl ForkData {
         Some(self.extents[0].0 as u64 * block_size)
     }
 
-    /// Returns `true` if the entire fork fits in a single contiguous extent
-    /// (i.e. only extents[0] is non-zero and covers all total_blocks).
-    fn is_single_extent(&self) -> bool {
+    /// Returns the fork's on-disk runs in file order, as byte ranges.
+    ///
+    /// Only the eight extents stored inline in the catalog record are used
+    /// (§5.2); forks that continue in the extents overflow file return
+    /// `None`, as do empty forks.
+    fn data_extents(&self, block_size: u64) -> Option<Vec<Extent>> {
         if self.total_blocks == 0 {
-            return false;
+            return None;
+        }
+        let mut out = Vec::new();
+        let mut covered: u64 = 0;
+        for &(start_block, block_count) in &self.extents {
+            if block_count == 0 {
+                break;
+            }
+            out.push(Extent::data(
+                start_block as u64 * block_size,
+                block_count as u64 * block_size,
+            ));
+            covered += block_count as u64;
         }
-        // extents[0] must cover all blocks, everything else must be zero.
-        self.extents[0].1 == self.total_blocks && self.extents[1..].iter().all(|&(_, c)| c == 0)
+        (covered == self.total_blocks as u64).then_some(out)
     }
 }
 
@@ -213,9 +253,11 @@ impl VolumeHeader {
 
 // ── B-tree header record (§4.3.1) ─────────────────────────────────────────
 
-/// Subset of the B-tree header record we need to navigate the leaf chain.
+/// Subset of the B-tree header record we need to navigate the leaf chain
+/// and to search the tree from its root.
 struct BTreeHeader {
     node_size: u16,
+    root_node: u32,
     first_leaf_node: u32,
 }
 
@@ -223,13 +265,14 @@ impl BTreeHeader {
     /// Parse from the 106-byte header record (first record in the header node).
     fn from_bytes(b: &[u8]) -> Self {
         // tree_depth at [0..2] — not needed
-        // root_node  at [2..6] — not needed for leaf-chain walk
+        let root_node = u32::from_be_bytes(b[2..6].try_into().unwrap());
         // leaf_records at [6..10] — not needed
         let first_leaf_node = u32::from_be_bytes(b[10..14].try_into().unwrap());
         // last_leaf_node at [14..18] — not needed
         let node_size = u16::from_be_bytes(b[18..20].try_into().unwrap());
         Self {
             node_size,
+            root_node,
             first_leaf_node,
         }
     }
@@ -244,6 +287,7 @@ enum CatalogRecord {
         parent_cnid: u32,
         name: String,
         cnid: u32,
+        metadata: Metadata,
     },
     File {
         parent_cnid: u32,
@@ -252,8 +296,9 @@ enum CatalogRecord {
         cnid: u32,
         /// Logical length of the data fork in bytes.
         file_length: u64,
-        /// Byte offset of the data fork, if it fits in one extent.
-        file_location: Option<u64>,
+        /// Data fork runs, if every extent is in the catalog record.
+        extents: Option<Vec<Extent>>,
+        metadata: Metadata,
     },
     /// Thread record — gives us name+parent for a CNID we already know.
     Thread {
@@ -315,15 +360,82 @@ pub fn parse_volume_header<R: Read + Seek>(r: &mut R) -> Result<VolumeHeader, Er
 ///
 /// The tree root is `"/"` (a directory), with one child per entry directly
 /// under the volume root (CNID 2). Subdirectories are populated recursively.
-/// Files get `file_location = Some(offset)` only when their data fork fits in
-/// a single contiguous allocation-block extent; multi-extent files fall back
-/// to `None`.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+/// Files carry their data fork's extents when all of them are stored in the
+/// catalog record; forks that spill into the extents overflow file get no
+/// location.
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::HfsPlus, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
     let header = parse_volume_header(r)?;
-    let records = read_catalog_leaf_records(r, &header)?;
+    let mut records = read_catalog_leaf_records(r, &header)?;
+    check_limits(&records)?;
+    read_symlink_targets(r, &mut records)?;
     Ok(build_tree(&records, header.block_size as u64))
 }
 
+/// Check the catalog against the parse limits before building the tree:
+/// every file and folder is an entry, each parent's records are its
+/// directory listing, and a folder's depth is the length of its parent
+/// chain. Depths are memoized, so each folder's chain is walked once; a
+/// chain that loops or never reaches the root is left for
+/// [`build_tree`] to drop.
+fn check_limits(records: &[CatalogRecord]) -> std::io::Result<()> {
+    let mut listed: HashMap<u32, u64> = HashMap::new();
+    let mut parents: HashMap<u32, u32> = HashMap::new();
+    for rec in records {
+        let (parent_cnid, name) = match rec {
+            CatalogRecord::Folder {
+                parent_cnid,
+                name,
+                cnid,
+                ..
+            } => {
+                parents.insert(*cnid, *parent_cnid);
+                (parent_cnid, name)
+            }
+            CatalogRecord::File {
+                parent_cnid, name, ..
+            } => (parent_cnid, name),
+            CatalogRecord::Thread { .. } => continue,
+        };
+        limits::entry(name)?;
+        let count = listed.entry(*parent_cnid).or_default();
+        *count += 1;
+        limits::dir_entries(*count)?;
+    }
+
+    // `None` marks a folder that is not below the root.
+    let mut depths: HashMap<u32, Option<u64>> = HashMap::new();
+    depths.insert(HFS_ROOT_FOLDER_CNID, Some(0));
+    for &start in parents.keys() {
+        let mut chain = Vec::new();
+        let mut cnid = start;
+        let known = loop {
+            if let Some(&depth) = depths.get(&cnid) {
+                break depth;
+            }
+            if chain.len() > parents.len() {
+                break None;
+            }
+            chain.push(cnid);
+            match parents.get(&cnid) {
+                Some(&parent) => cnid = parent,
+                None => break None,
+            }
+        };
+        for (above, cnid) in chain.into_iter().rev().enumerate() {
+            let depth = known.map(|depth| depth + above as u64 + 1);
+            if let Some(depth) = depth {
+                limits::depth(depth)?;
+            }
+            depths.insert(cnid, depth);
+        }
+    }
+    Ok(())
+}
+
 // ── Catalog B-tree leaf-chain walker ──────────────────────────────────────
 
 /// Read all leaf records from the catalog B-tree by following the forward
@@ -353,39 +465,8 @@ fn read_catalog_leaf_records<R: Read + Seek>(
     };
 
     // ── Step 1: Read the B-tree header node (node 0) ──
-    // We read exactly 120 bytes (14-byte node descriptor + 106-byte BTHeaderRec).
-    // Per the HFS+ spec (TN1150 §2.2), the B-tree header record ALWAYS begins
-    // at byte 14 of the header node, immediately after the node descriptor.
-    // We do not use the offset table to locate record 0 here because the offset
-    // table lives at the END of the node — and we don't know node_size yet.
-    r.seek(SeekFrom::Start(cat_offset))?;
-    let mut header_node_buf = vec![0u8; 120]; // 14 (descriptor) + 106 (BTHeaderRec)
-    r.read_exact(&mut header_node_buf).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::UnexpectedEof {
-            Error::TooShort
-        } else {
-            Error::Io(e)
-        }
-    })?;
-
-    // B-tree node descriptor is 14 bytes at the start of every node.
-    let node_kind = header_node_buf[8];
-    if node_kind != BTREE_HEADER_NODE {
-        // Node 0 must be the header node.
-        return Err(Error::BadCatalog);
-    }
-    let num_records_in_header = u16::from_be_bytes([header_node_buf[10], header_node_buf[11]]);
-    if num_records_in_header < 1 {
-        return Err(Error::TooShort);
-    }
-
-    // The BTHeaderRec starts at byte 14 (immediately after the node descriptor).
-    let btree_header = BTreeHeader::from_bytes(&header_node_buf[14..]);
-
+    let btree_header = read_btree_header(r, cat_offset)?;
     let node_size = btree_header.node_size as u64;
-    if node_size < 512 {
-        return Err(Error::BadCatalog);
-    }
 
     let mut first_leaf = btree_header.first_leaf_node;
     if first_leaf == 0 {
@@ -400,22 +481,29 @@ fn read_catalog_leaf_records<R: Read + Seek>(
     let mut visited = 0u32;
 
     loop {
+        // Seek to the start of this leaf node.
+        let node_offset = cat_offset + first_leaf as u64 * node_size;
         if visited > max_nodes {
-            return Err(Error::TooDeep);
+            // The rest of the chain loops; keep the records read so far.
+            recover::skip(Some(node_offset), "catalog node", Error::TooDeep)?;
+            break;
         }
         visited += 1;
 
-        // Seek to the start of this leaf node.
-        let node_offset = cat_offset + first_leaf as u64 * node_size;
         r.seek(SeekFrom::Start(node_offset))?;
         let mut node_buf = vec![0u8; node_size as usize];
-        r.read_exact(&mut node_buf).map_err(|e| {
+        let read = r.read_exact(&mut node_buf).map_err(|e| {
             if e.kind() == std::io::ErrorKind::UnexpectedEof {
                 Error::TooShort
             } else {
                 Error::Io(e)
             }
-        })?;
+        });
+        if let Err(e) = read {
+            // Without the node there is no link to the next one.
+            recover::skip(Some(node_offset), "catalog node", e)?;
+            break;
+        }
 
         // Node descriptor (14 bytes).
         let f_link = u32::from_be_bytes(node_buf[0..4].try_into().unwrap());
@@ -424,6 +512,11 @@ fn read_catalog_leaf_records<R: Read + Seek>(
 
         if kind != BTREE_LEAF_NODE {
             // Should never happen in a healthy volume; tolerate it by skipping.
+            recover::note(
+                Some(node_offset),
+                "catalog node",
+                format_args!("kind {kind} in the leaf chain"),
+            );
             if f_link == 0 {
                 break;
             }
@@ -432,7 +525,9 @@ fn read_catalog_leaf_records<R: Read + Seek>(
         }
 
         // ── Parse each record in this leaf node ──
-        parse_leaf_node_records(&node_buf, num_records, block_size, &mut records)?;
+        if let Err(e) = parse_leaf_node_records(&node_buf, num_records, block_size, &mut records) {
+            recover::skip(Some(node_offset), "catalog node", e)?;
+        }
 
         if f_link == 0 {
             break;
@@ -443,6 +538,44 @@ fn read_catalog_leaf_records<R: Read + Seek>(
     Ok(records)
 }
 
+/// Read and validate the B-tree header record from node 0 of the catalog
+/// file at `cat_offset`.
+fn read_btree_header<R: Read + Seek>(r: &mut R, cat_offset: u64) -> Result<BTreeHeader, Error> {
+    // We read exactly 120 bytes (14-byte node descriptor + 106-byte BTHeaderRec).
+    // Per the HFS+ spec (TN1150 §2.2), the B-tree header record ALWAYS begins
+    // at byte 14 of the header node, immediately after the node descriptor.
+    // We do not use the offset table to locate record 0 here because the offset
+    // table lives at the END of the node — and we don't know node_size yet.
+    r.seek(SeekFrom::Start(cat_offset))?;
+    let mut header_node_buf = vec![0u8; 120]; // 14 (descriptor) + 106 (BTHeaderRec)
+    r.read_exact(&mut header_node_buf).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            Error::TooShort
+        } else {
+            Error::Io(e)
+        }
+    })?;
+
+    // B-tree node descriptor is 14 bytes at the start of every node.
+    let node_kind = header_node_buf[8];
+    if node_kind != BTREE_HEADER_NODE {
+        // Node 0 must be the header node.
+        return Err(Error::BadCatalog);
+    }
+    let num_records_in_header = u16::from_be_bytes([header_node_buf[10], header_node_buf[11]]);
+    if num_records_in_header < 1 {
+        return Err(Error::TooShort);
+    }
+
+    // The BTHeaderRec starts at byte 14 (immediately after the node descriptor).
+    let btree_header = BTreeHeader::from_bytes(&header_node_buf[14..]);
+
+    if btree_header.node_size < 512 {
+        return Err(Error::BadCatalog);
+    }
+    Ok(btree_header)
+}
+
 /// Parse all catalog records from one leaf node's byte buffer.
 fn parse_leaf_node_records(
     node_buf: &[u8],
@@ -450,104 +583,185 @@ fn parse_leaf_node_records(
     block_size: u64,
     out: &mut Vec<CatalogRecord>,
 ) -> Result<(), Error> {
-    let node_size = node_buf.len();
-
     for i in 0..num_records as usize {
-        // The offset table is at the END of the node, one u16 per entry,
-        // going backwards (§4.3.3). offset[i] is at:
-        //   node_size - 2*(i+1)
-        let off_idx = node_size.saturating_sub(2 * (i + 1));
-        if off_idx + 2 > node_size {
-            break;
-        }
-        let rec_start = u16::from_be_bytes([node_buf[off_idx], node_buf[off_idx + 1]]) as usize;
-        if rec_start >= node_size {
-            continue;
+        if let Some(record) = node_record(node_buf, i).and_then(|r| parse_record(r, block_size)) {
+            out.push(record);
         }
-        let rec_data = &node_buf[rec_start..];
-
-        // ── Catalog key (§4.3.5) ──
-        // [0..2] u16 key_length
-        // [2..6] u32 parent_cnid (BE)
-        // [6..8] u16 name.length (BE)
-        // [8..8+2*name.length] UTF-16 BE code units
-        if rec_data.len() < 6 {
-            continue;
+    }
+    Ok(())
+}
+
+/// The bytes of record `i` of a B-tree node, from its start to the end of
+/// the node.
+///
+/// The offset table is at the END of the node, one u16 per entry, going
+/// backwards (§4.3.3): offset[i] is at `node_size - 2*(i+1)`.
+fn node_record(node_buf: &[u8], i: usize) -> Option<&[u8]> {
+    let node_size = node_buf.len();
+    let off_idx = node_size.checked_sub(2 * (i + 1))?;
+    let rec_start = u16::from_be_bytes([node_buf[off_idx], node_buf[off_idx + 1]]) as usize;
+    node_buf.get(rec_start..).filter(|rec| !rec.is_empty())
+}
+
+/// Split a catalog record into its key's parent CNID, the key's name
+/// (UTF-16 BE) and the bytes that follow the key: the record data in a
+/// leaf node, the child node number in an index node.
+///
+/// ```text
+/// [0..2] u16 key_length
+/// [2..6] u32 parent_cnid (BE)
+/// [6..8] u16 name.length (BE)
+/// [8..8+2*name.length] UTF-16 BE code units
+/// ```
+fn split_catalog_key(rec_data: &[u8]) -> Option<(u32, &[u8], &[u8])> {
+    if rec_data.len() < 6 {
+        return None;
+    }
+    let key_length = u16::from_be_bytes([rec_data[0], rec_data[1]]) as usize;
+    if key_length < 6 || rec_data.len() < 2 + key_length {
+        return None;
+    }
+    let parent_cnid = u32::from_be_bytes(rec_data[2..6].try_into().unwrap());
+    let name_len = u16::from_be_bytes([rec_data[6], rec_data[7]]) as usize;
+    let name = rec_data.get(8..8 + name_len * 2)?;
+
+    // The data starts immediately after the key, rounded up to the next
+    // 2-byte boundary from the record start (§4.3.5).
+    let raw_data_off = 2 + key_length; // key_length does NOT include the 2-byte key_length field itself
+    let data_off = (raw_data_off + 1) & !1; // align to 2 bytes
+    if rec_data.len() < data_off + 2 {
+        return None;
+    }
+    Some((parent_cnid, name, &rec_data[data_off..]))
+}
+
+/// Decode one catalog leaf record (§4.3.5). Returns `None` for malformed
+/// records and unknown record types.
+fn parse_record(rec_data: &[u8], block_size: u64) -> Option<CatalogRecord> {
+    let (parent_cnid, name, data) = split_catalog_key(rec_data)?;
+    let name = decode_utf16_be(name);
+    let record_type = u16::from_be_bytes([data[0], data[1]]);
+
+    match record_type {
+        RECORD_TYPE_FOLDER => {
+            // Folder record (248 bytes total, §4.3.6).
+            // [8..12] u32 cnid
+            if data.len() < 12 {
+                return None;
+            }
+            let cnid = u32::from_be_bytes(data[8..12].try_into().unwrap());
+            let metadata = Metadata {
+                kind: EntryKind::Directory,
+                ..catalog_metadata(data)
+            };
+            Some(CatalogRecord::Folder {
+                parent_cnid,
+                name,
+                cnid,
+                metadata,
+            })
         }
-        let key_length = u16::from_be_bytes([rec_data[0], rec_data[1]]) as usize;
-        if key_length < 6 || rec_data.len() < 2 + key_length {
-            continue;
+        RECORD_TYPE_FILE => {
+            // File record (248 bytes total, §4.3.7).
+            // [8..12]    u32 cnid
+            // [88..168]  HFSPlusForkData data_fork (80 bytes)
+            if data.len() < 168 {
+                return None;
+            }
+            let cnid = u32::from_be_bytes(data[8..12].try_into().unwrap());
+            let data_fork = ForkData::from_bytes(&data[88..168]);
+            let file_length = data_fork.logical_size;
+            let extents = data_fork.data_extents(block_size);
+            let metadata = catalog_metadata(data);
+            Some(CatalogRecord::File {
+                parent_cnid,
+                name,
+                cnid,
+                file_length,
+                extents,
+                metadata,
+            })
         }
-        let parent_cnid = u32::from_be_bytes(rec_data[2..6].try_into().unwrap());
-        let name_len = u16::from_be_bytes([rec_data[6], rec_data[7]]) as usize;
-        let name_bytes_len = name_len * 2;
-        if rec_data.len() < 8 + name_bytes_len {
-            continue;
+        RECORD_TYPE_FOLDER_THREAD | RECORD_TYPE_FILE_THREAD => {
+            // Thread records are keyed by CNID; we don't need them for
+            // tree construction since we already get names from the
+            // corresponding folder/file records.
+            Some(CatalogRecord::Thread {
+                cnid_key: parent_cnid, // in thread records, the key parent field holds the CNID
+                record_type,
+            })
         }
-        let name = decode_utf16_be(&rec_data[8..8 + name_bytes_len]);
+        // Unknown record type — skip.
+        _ => None,
+    }
+}
 
-        // The catalog data starts immediately after the key, rounded up to
-        // the next 2-byte boundary from the record start (§4.3.5).
-        let raw_data_off = 2 + key_length; // key_length does NOT include the 2-byte key_length field itself
-        let data_off = (raw_data_off + 1) & !1; // align to 2 bytes
-        if rec_data.len() < data_off + 2 {
+/// Convert an HFS+ date (seconds since 1904, GMT); zero means "not set".
+fn hfs_time(secs: u32) -> Option<Timestamp> {
+    (secs != 0).then(|| Timestamp::new(secs as i64 - HFS_EPOCH_OFFSET, 0))
+}
+
+/// Decode the dates and `HFSPlusBSDInfo` shared by folder and file records
+/// (§4.3.6–7). The record must be at least 48 bytes long.
+///
+/// ```text
+/// [12..16] createDate   [16..20] contentModDate
+/// [20..24] attributeModDate   [24..28] accessDate
+/// [32..36] ownerID   [36..40] groupID   [42..44] fileMode
+/// ```
+///
+/// A `fileMode` of zero means the volume never recorded BSD permissions, so
+/// mode, owner and group are left unset.
+fn catalog_metadata(data: &[u8]) -> Metadata {
+    let be32 = |at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+    let file_mode = u16::from_be_bytes([data[42], data[43]]) as u32;
+    let mut metadata = Metadata {
+        kind: EntryKind::from_posix_mode(file_mode),
+        btime: hfs_time(be32(12)),
+        mtime: hfs_time(be32(16)),
+        ctime: hfs_time(be32(20)),
+        atime: hfs_time(be32(24)),
+        ..Metadata::default()
+    };
+    if file_mode != 0 {
+        metadata.mode = Some(file_mode & 0o7777);
+        metadata.uid = Some(be32(32));
+        metadata.gid = Some(be32(36));
+    }
+    metadata
+}
+
+/// Fill in `link_target` for every symlink file record by reading its data
+/// fork, which holds the UTF-8 target path.
+fn read_symlink_targets<R: Read + Seek>(
+    r: &mut R,
+    records: &mut [CatalogRecord],
+) -> Result<(), Error> {
+    for rec in records {
+        let CatalogRecord::File {
+            file_length,
+            extents: Some(extents),
+            metadata,
+            ..
+        } = rec
+        else {
+            continue;
+        };
+        if metadata.kind != EntryKind::Symlink || *file_length > MAX_SYMLINK_LEN {
             continue;
         }
-        let data = &rec_data[data_off..];
-        let record_type = u16::from_be_bytes([data[0], data[1]]);
-
-        match record_type {
-            RECORD_TYPE_FOLDER => {
-                // Folder record (248 bytes total, §4.3.6).
-                // [8..12] u32 cnid
-                if data.len() < 12 {
-                    continue;
-                }
-                let cnid = u32::from_be_bytes(data[8..12].try_into().unwrap());
-                out.push(CatalogRecord::Folder {
-                    parent_cnid,
-                    name,
-                    cnid,
-                });
-            }
-            RECORD_TYPE_FILE => {
-                // File record (248 bytes total, §4.3.7).
-                // [8..12]    u32 cnid
-                // [88..168]  HFSPlusForkData data_fork (80 bytes)
-                if data.len() < 168 {
-                    continue;
-                }
-                let cnid = u32::from_be_bytes(data[8..12].try_into().unwrap());
-                let data_fork = ForkData::from_bytes(&data[88..168]);
-                let file_length = data_fork.logical_size;
-                let file_location = if data_fork.is_single_extent() {
-                    data_fork.first_extent_offset(block_size)
-                } else {
-                    None
-                };
-                out.push(CatalogRecord::File {
-                    parent_cnid,
-                    name,
-                    cnid,
-                    file_length,
-                    file_location,
-                });
-            }
-            RECORD_TYPE_FOLDER_THREAD | RECORD_TYPE_FILE_THREAD => {
-                // Thread records are keyed by CNID; we don't need them for
-                // tree construction since we already get names from the
-                // corresponding folder/file records.
-                out.push(CatalogRecord::Thread {
-                    cnid_key: parent_cnid, // in thread records, the key parent field holds the CNID
-                    record_type,
-                });
-            }
-            _ => {
-                // Unknown record type — skip.
+        let mut target = Vec::with_capacity(*file_length as usize);
+        for extent in extents.iter() {
+            let want = (*file_length - target.len() as u64).min(extent.length) as usize;
+            let start = target.len();
+            target.resize(start + want, 0);
+            if let Some(location) = extent.location {
+                r.seek(SeekFrom::Start(location))?;
+                r.read_exact(&mut target[start..])?;
             }
         }
+        metadata.link_target = Some(String::from_utf8_lossy(&target).into_owned());
     }
-
     Ok(())
 }
 
@@ -555,7 +769,10 @@ fn parse_leaf_node_records(
 
 /// Navigate a tree by a slice of directory-name segments and return a
 /// mutable reference to the matching node, or `None` if not found.
-fn find_by_path_mut<'a>(node: &'a mut TreeNode, path: &[String]) -> Option<&'a mut TreeNode> {
+fn find_by_path_mut<'a>(
+    node: &'a mut TreeNode<'static>,
+    path: &[String],
+) -> Option<&'a mut TreeNode<'static>> {
     if path.is_empty() {
         return Some(node);
     }
@@ -606,35 +823,36 @@ fn cnid_path(cnid: u32, folder_map: &std::collections::HashMap<u32, (String, u32
 /// 1. Index all folder CNIDs → mutable TreeNode placeholders.
 /// 2. Walk records in order, attaching each file/folder to its parent.
 /// 3. Return the root node (CNID 2) with `calculate_directory_size` applied.
-fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
+fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode<'static> {
     // ── Pass 1: collect all folder records ──
     // Map from CNID to (name, parent_cnid) for every folder.
     let mut folder_map: HashMap<u32, (String, u32)> = HashMap::new();
     folder_map.insert(HFS_ROOT_FOLDER_CNID, ("/".to_string(), 0));
+    let mut folder_meta: HashMap<u32, &Metadata> = HashMap::new();
 
     for rec in records {
         if let CatalogRecord::Folder {
             cnid,
             name,
             parent_cnid,
+            metadata,
         } = rec
         {
             if *cnid != HFS_ROOT_FOLDER_CNID {
                 folder_map.insert(*cnid, (name.clone(), *parent_cnid));
             }
+            folder_meta.insert(*cnid, metadata);
         }
     }
 
     // ── Pass 2: build a flat map of TreeNodes, keyed by CNID ──
     let mut nodes: HashMap<u32, TreeNode> = HashMap::new();
-    nodes.insert(
-        HFS_ROOT_FOLDER_CNID,
-        TreeNode::new_directory("/".to_string()),
-    );
     for (&cnid, (name, _)) in &folder_map {
-        if cnid != HFS_ROOT_FOLDER_CNID {
-            nodes.insert(cnid, TreeNode::new_directory(name.clone()));
+        let mut node = TreeNode::new_directory(name.clone());
+        if let Some(&metadata) = folder_meta.get(&cnid) {
+            node = node.with_metadata(metadata.clone());
         }
+        nodes.insert(cnid, node);
     }
 
     // ── Pass 3: collect file nodes and their parent CNIDs ──
@@ -644,20 +862,12 @@ fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
             parent_cnid,
             name,
             file_length,
-            file_location,
+            extents,
+            metadata,
             ..
         } = rec
         {
-            let node = if let Some(loc) = file_location {
-                // Adjust file_location: the extent start_block is relative to
-                // the beginning of the *volume*, which (for a bare HFS+ image)
-                // starts at byte 0. block_size is already in bytes.
-                // We pass it through as-is; the caller's image must be seekable
-                // to this offset.
-                TreeNode::new_file_with_location(name.clone(), *file_length, *loc, *file_length)
-            } else {
-                TreeNode::new_file(name.clone(), *file_length)
-            };
+            let node = file_node(name.clone(), *file_length, extents, metadata);
             file_items.push((*parent_cnid, node));
         }
     }
@@ -684,6 +894,7 @@ fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
         if remaining.is_empty() {
             break;
         }
+        let pending = remaining.len();
         let mut still_pending: Vec<(u32, u32)> = Vec::new();
         for (parent_cnid, child_cnid) in remaining.drain(..) {
             if nodes.contains_key(&child_cnid) && nodes.contains_key(&parent_cnid) {
@@ -693,7 +904,22 @@ fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
                 still_pending.push((parent_cnid, child_cnid));
             }
         }
+        // A pass that attaches nothing leaves only orphans and loops,
+        // which later passes cannot attach either.
+        let stuck = still_pending.len() == pending;
         remaining = still_pending;
+        if stuck {
+            break;
+        }
+    }
+    for (parent_cnid, child_cnid) in remaining {
+        recover::note(
+            None,
+            "catalog record",
+            format_args!(
+                "folder {child_cnid} has no path to the root through folder {parent_cnid}"
+            ),
+        );
     }
 
     // Attach files to their parents.  After Pass 4 only the root node
@@ -705,8 +931,17 @@ fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
         let path = cnid_path(parent_cnid, &folder_map);
         if let Some(parent) = find_by_path_mut(root_node, &path) {
             parent.add_child(file_node);
+        } else {
+            // Orphan / corrupted image: drop the file.
+            recover::note(
+                None,
+                "catalog record",
+                format_args!(
+                    "file '{}' has no path to the root through folder {parent_cnid}",
+                    file_node.name
+                ),
+            );
         }
-        // If path not found (orphan / corrupted image), silently drop.
     }
 
     // Sort children alphabetically to produce a stable output order.
@@ -719,6 +954,25 @@ fn build_tree(records: &[CatalogRecord], _block_size: u64) -> TreeNode {
     root
 }
 
+/// The node for a file record.
+fn file_node(
+    name: String,
+    file_length: u64,
+    extents: &Option<Vec<Extent>>,
+    metadata: &Metadata,
+) -> TreeNode<'static> {
+    let node = if let Some(extents) = extents {
+        // Extent start blocks are relative to the beginning of the
+        // *volume*, which (for a bare HFS+ image) starts at byte 0.
+        // We pass them through as-is; the caller's image must be
+        // seekable to these offsets.
+        TreeNode::new_file_with_extents(name, file_length, extents.clone())
+    } else {
+        TreeNode::new_file(name, file_length)
+    };
+    node.with_metadata(metadata.clone())
+}
+
 /// Sort a [`TreeNode`]'s children (and their children) alphabetically by name.
 fn sort_children_recursive(node: &mut TreeNode) {
     node.children.sort_by(|a, b| a.name.cmp(&b.name));
@@ -729,6 +983,221 @@ fn sort_children_recursive(node: &mut TreeNode) {
     }
 }
 
+// ── Lazy access ────────────────────────────────────────────────────────────
+
+/// An HFS+ or HFSX volume opened for lazy, per-directory reads.
+///
+/// Only the volume header and the catalog's B-tree header are read up
+/// front. Handles are catalog record positions (`node << 16 | record`);
+/// listing a folder searches the catalog B-tree from its root for the
+/// folder's first child, then reads the leaf nodes holding its children.
+/// Entries come back in catalog key order, which is not the sorted order
+/// [`detect_and_parse`] produces. Like it, this reads a catalog stored in
+/// its first extent only.
+#[derive(Debug)]
+pub struct HfsPlusFs<R> {
+    image: R,
+    block_size: u64,
+    cat_offset: u64,
+    node_size: u64,
+    root_node: u32,
+    /// Number of nodes in the catalog file; bounds every walk.
+    node_count: u32,
+    root: u64,
+}
+
+impl<R: Read + Seek> HfsPlusFs<R> {
+    /// Read the volume header and catalog B-tree header, then locate the
+    /// root folder's catalog record.
+    pub fn open(mut image: R) -> Result<Self, Error> {
+        let header = parse_volume_header(&mut image)?;
+        let block_size = header.block_size as u64;
+        let cat_offset = header
+            .cat_file
+            .first_extent_offset(block_size)
+            .ok_or(Error::BadCatalog)?;
+        let btree_header = read_btree_header(&mut image, cat_offset)?;
+        let node_size = btree_header.node_size as u64;
+        let mut fs = Self {
+            image,
+            block_size,
+            cat_offset,
+            node_size,
+            root_node: btree_header.root_node,
+            node_count: (header.cat_file.logical_size / node_size).min(u32::MAX as u64) as u32,
+            root: 0,
+        };
+        fs.root = fs
+            .children(HFS_ROOT_PARENT_CNID)?
+            .into_iter()
+            .find_map(|(pos, rec)| match rec {
+                CatalogRecord::Folder {
+                    cnid: HFS_ROOT_FOLDER_CNID,
+                    ..
+                } => Some(pos),
+                _ => None,
+            })
+            .ok_or(Error::BadCatalog)?;
+        Ok(fs)
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    fn read_node(&mut self, node: u32) -> Result<Vec<u8>, Error> {
+        if node >= self.node_count {
+            return Err(Error::BadCatalog);
+        }
+        let offset = self.cat_offset + node as u64 * self.node_size;
+        self.image.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0u8; self.node_size as usize];
+        self.image.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decode the catalog record at handle position `pos`.
+    fn record_at(&mut self, pos: u64) -> Result<CatalogRecord, Error> {
+        let buf = self.read_node((pos >> 16) as u32)?;
+        let record = node_record(&buf, (pos & 0xFFFF) as usize)
+            .and_then(|rec| parse_record(rec, self.block_size));
+        let mut record = record.ok_or(Error::BadCatalog)?;
+        read_symlink_targets(&mut self.image, std::slice::from_mut(&mut record))?;
+        Ok(record)
+    }
+
+    /// Every folder and file record whose key names `parent` as its
+    /// parent, with its position.
+    fn children(&mut self, parent: u32) -> Result<Vec<(u64, CatalogRecord)>, Error> {
+        let mut out = Vec::new();
+        if self.root_node == 0 {
+            return Ok(out);
+        }
+
+        // Descend through index nodes to the leaf that holds the key
+        // (parent, ""), the folder's thread record, or would hold it.
+        let mut node = self.root_node;
+        let mut buf = self.read_node(node)?;
+        let mut steps = 0u32;
+        while buf[8] == BTREE_INDEX_NODE {
+            steps += 1;
+            if steps > self.node_count {
+                return Err(Error::TooDeep);
+            }
+            let num_records = u16::from_be_bytes([buf[10], buf[11]]) as usize;
+            let mut child = None;
+            for i in 0..num_records {
+                let Some((key_parent, name, data)) =
+                    node_record(&buf, i).and_then(split_catalog_key)
+                else {
+                    continue;
+                };
+                if key_parent > parent || (key_parent == parent && !name.is_empty()) {
+                    break;
+                }
+                if data.len() >= 4 {
+                    child = Some(u32::from_be_bytes(data[0..4].try_into().unwrap()));
+                }
+            }
+            // Every key is greater: the first child is where it would go.
+            if child.is_none() {
+                child = node_record(&buf, 0)
+                    .and_then(split_catalog_key)
+                    .filter(|(_, _, data)| data.len() >= 4)
+                    .map(|(_, _, data)| u32::from_be_bytes(data[0..4].try_into().unwrap()));
+            }
+            node = child.ok_or(Error::BadCatalog)?;
+            buf = self.read_node(node)?;
+        }
+        if buf[8] != BTREE_LEAF_NODE {
+            return Err(Error::BadCatalog);
+        }
+
+        // Children are contiguous in key order, possibly across leaves.
+        loop {
+            steps += 1;
+            if steps > self.node_count {
+                return Err(Error::TooDeep);
+            }
+            let num_records = u16::from_be_bytes([buf[10], buf[11]]) as usize;
+            for i in 0..num_records {
+                let Some(rec) = node_record(&buf, i) else {
+                    continue;
+                };
+                let Some((key_parent, _, _)) = split_catalog_key(rec) else {
+                    continue;
+                };
+                if key_parent > parent {
+                    return Ok(out);
+                }
+                if key_parent < parent {
+                    continue;
+                }
+                if let Some(record @ (CatalogRecord::Folder { .. } | CatalogRecord::File { .. })) =
+                    parse_record(rec, self.block_size)
+                {
+                    out.push(((node as u64) << 16 | i as u64, record));
+                }
+            }
+            let f_link = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+            if f_link == 0 {
+                return Ok(out);
+            }
+            node = f_link;
+            buf = self.read_node(node)?;
+        }
+    }
+}
+
+impl<R: Read + Seek> Filesystem for HfsPlusFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::HfsPlus
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, self.root)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> crate::Result<Vec<DirEntry>> {
+        let CatalogRecord::Folder { cnid, .. } = self.record_at(dir.id)? else {
+            return Err(not_a_directory(dir));
+        };
+        let entries = self
+            .children(cnid)?
+            .into_iter()
+            .filter_map(|(pos, record)| match record {
+                CatalogRecord::Folder { name, .. } => Some(DirEntry::new(name, true, pos)),
+                CatalogRecord::File { name, .. } => Some(DirEntry::new(name, false, pos)),
+                CatalogRecord::Thread { .. } => None,
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> crate::Result<TreeNode<'static>> {
+        let name = entry.name.clone();
+        match self.record_at(entry.id)? {
+            CatalogRecord::Folder { metadata, .. } => {
+                Ok(TreeNode::new_directory(name).with_metadata(metadata))
+            }
+            CatalogRecord::File {
+                file_length,
+                extents,
+                metadata,
+                ..
+            } => Ok(file_node(name, file_length, &extents, &metadata)),
+            CatalogRecord::Thread { .. } => Err(Error::BadCatalog.into()),
+        }
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
 // ── UTF-16 BE decoding ─────────────────────────────────────────────────────
 
 /// Decode a big-endian UTF-16 byte slice into a `String`.
@@ -859,7 +1328,11 @@ mod tests {
         b[16..20].copy_from_slice(&10u32.to_be_bytes()); // start_block
         b[20..24].copy_from_slice(&2u32.to_be_bytes()); // block_count
         let fork = ForkData::from_bytes(&b);
-        assert!(fork.is_single_extent(), "should be single-extent fork");
+        assert_eq!(
+            fork.data_extents(512),
+            Some(vec![Extent::data(10 * 512, 2 * 512)]),
+            "should be single-extent fork"
+        );
         assert_eq!(
             fork.first_extent_offset(512),
             Some(10 * 512),
@@ -868,7 +1341,7 @@ mod tests {
     }
 
     #[test]
-    fn fork_data_multi_extent_not_single() {
+    fn fork_data_multi_extent_lists_every_run() {
         let mut b = [0u8; 80];
         // total_blocks = 4, two extents of 2 each
         b[12..16].copy_from_slice(&4u32.to_be_bytes());
@@ -877,12 +1350,28 @@ mod tests {
         b[24..28].copy_from_slice(&20u32.to_be_bytes());
         b[28..32].copy_from_slice(&2u32.to_be_bytes());
         let fork = ForkData::from_bytes(&b);
-        assert!(
-            !fork.is_single_extent(),
-            "two-extent fork should not be single-extent"
+        assert_eq!(
+            fork.data_extents(512),
+            Some(vec![
+                Extent::data(10 * 512, 2 * 512),
+                Extent::data(20 * 512, 2 * 512)
+            ])
         );
     }
 
+    #[test]
+    fn fork_data_overflow_extents_return_none() {
+        let mut b = [0u8; 80];
+        // total_blocks = 6 but the inline extents only cover 4.
+        b[12..16].copy_from_slice(&6u32.to_be_bytes());
+        b[16..20].copy_from_slice(&10u32.to_be_bytes());
+        b[20..24].copy_from_slice(&2u32.to_be_bytes());
+        b[24..28].copy_from_slice(&20u32.to_be_bytes());
+        b[28..32].copy_from_slice(&2u32.to_be_bytes());
+        let fork = ForkData::from_bytes(&b);
+        assert!(fork.data_extents(512).is_none());
+    }
+
     #[test]
     fn detect_and_parse_empty_catalog() {
         // Build an image where the catalog file's logical_size is 0 (no records).
@@ -904,7 +1393,8 @@ mod tests {
             name: "hello.txt".to_string(),
             cnid: 10,
             file_length: 42,
-            file_location: Some(4096),
+            extents: Some(vec![Extent::data(4096, 4096)]),
+            metadata: Metadata::default(),
         }];
         let root = build_tree(&records, 4096);
         assert_eq!(root.name, "/");
@@ -920,13 +1410,15 @@ mod tests {
                 parent_cnid: HFS_ROOT_FOLDER_CNID,
                 name: "docs".to_string(),
                 cnid: 20,
+                metadata: Metadata::default(),
             },
             CatalogRecord::File {
                 parent_cnid: 20, // child of "docs"
                 name: "readme.txt".to_string(),
                 cnid: 21,
                 file_length: 100,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             },
         ];
         let root = build_tree(&records, 4096);
@@ -950,7 +1442,8 @@ mod tests {
                 name: "f.bin".to_string(),
                 cnid: 5,
                 file_length: 0,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             },
         ];
         let root = build_tree(&records, 4096);
@@ -965,7 +1458,8 @@ mod tests {
             name: "sparse.dat".to_string(),
             cnid: 30,
             file_length: 200,
-            file_location: None,
+            extents: None,
+            metadata: Metadata::default(),
         }];
         let root = build_tree(&records, 512);
         let node = &root.children[0];
@@ -1031,6 +1525,7 @@ mod tests {
             parent_cnid,
             name,
             cnid: c,
+            ..
         } = &out[0]
         {
             assert_eq!(*parent_cnid, HFS_ROOT_FOLDER_CNID);
@@ -1063,19 +1558,66 @@ mod tests {
         if let CatalogRecord::File {
             name,
             file_length,
-            file_location,
+            extents,
             ..
         } = &out[0]
         {
             assert_eq!(name, "file.txt");
             assert_eq!(*file_length, 1024);
             // single extent → location = start_block * block_size = 10 * 4096
-            assert_eq!(*file_location, Some(10 * 4096));
+            assert_eq!(*extents, Some(vec![Extent::data(10 * 4096, 4096)]));
         } else {
             panic!("expected File record");
         }
     }
 
+    #[test]
+    fn catalog_metadata_reads_dates_and_bsd_info() {
+        let mut rec = vec![0u8; 248];
+        rec[0..2].copy_from_slice(&RECORD_TYPE_FILE.to_be_bytes());
+        // 2000-01-01T00:00:00Z in HFS+ seconds, then +1 s per later date.
+        let base = (946_684_800 + HFS_EPOCH_OFFSET) as u32;
+        for (i, at) in [12, 16, 20, 24].into_iter().enumerate() {
+            rec[at..at + 4].copy_from_slice(&(base + i as u32).to_be_bytes());
+        }
+        rec[32..36].copy_from_slice(&501u32.to_be_bytes());
+        rec[36..40].copy_from_slice(&20u32.to_be_bytes());
+        rec[42..44].copy_from_slice(&0o120755u16.to_be_bytes());
+        let m = catalog_metadata(&rec);
+        assert_eq!(m.kind, EntryKind::Symlink);
+        assert_eq!(m.mode, Some(0o755));
+        assert_eq!((m.uid, m.gid), (Some(501), Some(20)));
+        assert_eq!(m.btime, Some(Timestamp::new(946_684_800, 0)));
+        assert_eq!(m.mtime, Some(Timestamp::new(946_684_801, 0)));
+        assert_eq!(m.ctime, Some(Timestamp::new(946_684_802, 0)));
+        assert_eq!(m.atime, Some(Timestamp::new(946_684_803, 0)));
+
+        // No BSD info recorded: owner and mode stay unset.
+        rec[42..44].copy_from_slice(&0u16.to_be_bytes());
+        let m = catalog_metadata(&rec);
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!((m.mode, m.uid, m.gid), (None, None, None));
+    }
+
+    #[test]
+    fn symlink_target_read_from_data_fork() {
+        let mut img = vec![0u8; 1024];
+        img[512..522].copy_from_slice(b"../target\0");
+        let mut records = vec![CatalogRecord::File {
+            parent_cnid: HFS_ROOT_FOLDER_CNID,
+            name: "link".to_string(),
+            cnid: 40,
+            file_length: 9,
+            extents: Some(vec![Extent::data(512, 512)]),
+            metadata: Metadata::new(EntryKind::Symlink),
+        }];
+        read_symlink_targets(&mut Cursor::new(img), &mut records).unwrap();
+        let root = build_tree(&records, 512);
+        let link = &root.children[0];
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+        assert_eq!(link.metadata.link_target.as_deref(), Some("../target"));
+    }
+
     #[test]
     fn parse_leaf_node_thread_record() {
         let key = make_catalog_key(HFS_ROOT_FOLDER_CNID, "");
@@ -1231,16 +1773,16 @@ mod tests {
         ));
     }
 
-    // ── ForkData::is_single_extent zero total_blocks ─────────────────────────
+    // ── ForkData::data_extents zero total_blocks ─────────────────────────────
 
     #[test]
-    fn fork_data_is_single_extent_zero_total_blocks_returns_false() {
+    fn fork_data_data_extents_zero_total_blocks_returns_none() {
         let fd = ForkData {
             logical_size: 0,
             total_blocks: 0,
             extents: [(0, 0); 8],
         };
-        assert!(!fd.is_single_extent());
+        assert!(fd.data_extents(4096).is_none());
     }
 
     // ── BTreeHeader::from_bytes ───────────────────────────────────────────────
@@ -1264,7 +1806,7 @@ mod tests {
         root.add_child(TreeNode::new_file("a.txt".to_string(), 0));
         root.add_child(TreeNode::new_file("m.txt".to_string(), 0));
         sort_children_recursive(&mut root);
-        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_ref()).collect();
         assert_eq!(names, ["a.txt", "m.txt", "z.txt"]);
     }
 
@@ -1442,8 +1984,8 @@ mod tests {
     }
 
     #[test]
-    fn parse_leaf_node_file_multi_extent_no_location() {
-        // File with two extents → is_single_extent() = false → file_location = None.
+    fn parse_leaf_node_file_multi_extent_lists_runs() {
+        // File with two inline extents → both runs are kept, in order.
         let key = make_catalog_key(HFS_ROOT_FOLDER_CNID, "big.dat");
         let mut rec = vec![0u8; 248];
         rec[0..2].copy_from_slice(&RECORD_TYPE_FILE.to_be_bytes());
@@ -1459,10 +2001,13 @@ mod tests {
         let mut out = Vec::new();
         parse_leaf_node_records(&node, 1, 4096, &mut out).unwrap();
         assert_eq!(out.len(), 1);
-        if let CatalogRecord::File { file_location, .. } = &out[0] {
-            assert!(
-                file_location.is_none(),
-                "multi-extent file should have no location"
+        if let CatalogRecord::File { extents, .. } = &out[0] {
+            assert_eq!(
+                *extents,
+                Some(vec![
+                    Extent::data(10 * 4096, 2 * 4096),
+                    Extent::data(20 * 4096, 2 * 4096)
+                ])
             );
         } else {
             panic!("expected File record");
@@ -1479,13 +2024,15 @@ mod tests {
                 parent_cnid: HFS_ROOT_FOLDER_CNID,
                 name: "docs".to_string(),
                 cnid: 10,
+                metadata: Metadata::default(),
             },
             CatalogRecord::File {
                 parent_cnid: 10,
                 name: "readme.txt".to_string(),
                 cnid: 11,
                 file_length: 42,
-                file_location: Some(8192),
+                extents: Some(vec![Extent::data(8192, 512)]),
+                metadata: Metadata::default(),
             },
         ];
         let root = build_tree(&records, 512);
@@ -1504,6 +2051,7 @@ mod tests {
             parent_cnid: 9999, // no such parent in records
             name: "orphan".to_string(),
             cnid: 50,
+            metadata: Metadata::default(),
         }];
         let root = build_tree(&records, 512);
         // Orphan subdirectory not attached to root.
@@ -1660,18 +2208,21 @@ mod tests {
                 parent_cnid: HFS_ROOT_FOLDER_CNID,
                 name: "B".to_string(),
                 cnid: 20,
+                metadata: Metadata::default(),
             },
             CatalogRecord::Folder {
                 parent_cnid: 9999,
                 name: "A".to_string(),
                 cnid: 10,
+                metadata: Metadata::default(),
             },
             CatalogRecord::File {
                 parent_cnid: 10,
                 name: "x.txt".to_string(),
                 cnid: 30,
                 file_length: 1,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             },
         ];
         let root = build_tree(&records, 512);
@@ -1752,4 +2303,147 @@ mod tests {
         let mut c = Cursor::new(&img);
         assert!(matches!(detect_and_parse(&mut c), Err(Error::TooDeep)));
     }
+
+    // ── HfsPlusFs ─────────────────────────────────────────────────────────────
+
+    /// A 512-byte B-tree node holding `records` in order.
+    fn make_node(kind: u8, f_link: u32, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut node = vec![0u8; 512];
+        node[0..4].copy_from_slice(&f_link.to_be_bytes());
+        node[8] = kind;
+        node[10..12].copy_from_slice(&(records.len() as u16).to_be_bytes());
+        let mut at = 14usize;
+        for (i, rec) in records.iter().enumerate() {
+            node[at..at + rec.len()].copy_from_slice(rec);
+            node[510 - 2 * i..512 - 2 * i].copy_from_slice(&(at as u16).to_be_bytes());
+            at += rec.len();
+        }
+        node
+    }
+
+    fn folder_rec(parent: u32, name: &str, cnid: u32) -> Vec<u8> {
+        let mut rec = make_catalog_key(parent, name);
+        let mut data = vec![0u8; 88];
+        data[0..2].copy_from_slice(&RECORD_TYPE_FOLDER.to_be_bytes());
+        data[8..12].copy_from_slice(&cnid.to_be_bytes());
+        data[16..20].copy_from_slice(&(cnid + 3_000_000_000).to_be_bytes()); // contentModDate
+        rec.extend(data);
+        rec
+    }
+
+    fn file_rec(parent: u32, name: &str, cnid: u32, len: u64, block: u32) -> Vec<u8> {
+        let mut rec = make_catalog_key(parent, name);
+        let mut data = vec![0u8; 248];
+        data[0..2].copy_from_slice(&RECORD_TYPE_FILE.to_be_bytes());
+        data[8..12].copy_from_slice(&cnid.to_be_bytes());
+        data[88..96].copy_from_slice(&len.to_be_bytes());
+        if len > 0 {
+            data[100..104].copy_from_slice(&1u32.to_be_bytes()); // total_blocks
+            data[104..108].copy_from_slice(&block.to_be_bytes());
+            data[108..112].copy_from_slice(&1u32.to_be_bytes());
+        }
+        rec.extend(data);
+        rec
+    }
+
+    fn thread_rec(cnid: u32) -> Vec<u8> {
+        let mut rec = make_catalog_key(cnid, "");
+        rec.extend(RECORD_TYPE_FOLDER_THREAD.to_be_bytes());
+        rec.extend([0u8; 6]);
+        rec
+    }
+
+    fn index_rec(parent: u32, name: &str, child: u32) -> Vec<u8> {
+        let mut rec = make_catalog_key(parent, name);
+        rec.extend(child.to_be_bytes());
+        rec
+    }
+
+    /// A volume whose catalog has an index root (node 1) over two leaves:
+    ///
+    /// ```text
+    /// node 2: (1,"Vol") root  (2,"") thread  (2,"a.txt") file
+    /// node 3: (2,"docs") folder 16  (16,"") thread  (16,"b.txt") empty file
+    /// ```
+    fn make_indexed_catalog() -> Vec<u8> {
+        let mut img = vec![0u8; 6656];
+        let h = &mut img[1024..1536];
+        h[0..2].copy_from_slice(&HFS_PLUS_MAGIC.to_be_bytes());
+        h[2..4].copy_from_slice(&4u16.to_be_bytes());
+        h[40..44].copy_from_slice(&512u32.to_be_bytes());
+        h[272..280].copy_from_slice(&2048u64.to_be_bytes()); // cat logical_size
+        h[284..288].copy_from_slice(&4u32.to_be_bytes()); // cat total_blocks
+        h[288..292].copy_from_slice(&4u32.to_be_bytes()); // start_block
+        h[292..296].copy_from_slice(&4u32.to_be_bytes()); // block_count
+
+        let mut header = make_node(BTREE_HEADER_NODE, 0, &[]);
+        header[10..12].copy_from_slice(&1u16.to_be_bytes());
+        header[16..20].copy_from_slice(&1u32.to_be_bytes()); // root_node
+        header[24..28].copy_from_slice(&2u32.to_be_bytes()); // first_leaf_node
+        header[32..34].copy_from_slice(&512u16.to_be_bytes()); // node_size
+        let nodes = [
+            header,
+            make_node(
+                BTREE_INDEX_NODE,
+                0,
+                &[index_rec(1, "Vol", 2), index_rec(2, "docs", 3)],
+            ),
+            make_node(
+                BTREE_LEAF_NODE,
+                3,
+                &[
+                    folder_rec(1, "Vol", HFS_ROOT_FOLDER_CNID),
+                    thread_rec(HFS_ROOT_FOLDER_CNID),
+                    file_rec(HFS_ROOT_FOLDER_CNID, "a.txt", 20, 7, 12),
+                ],
+            ),
+            make_node(
+                BTREE_LEAF_NODE,
+                0,
+                &[
+                    folder_rec(HFS_ROOT_FOLDER_CNID, "docs", 16),
+                    thread_rec(16),
+                    file_rec(16, "b.txt", 21, 0, 0),
+                ],
+            ),
+        ];
+        for (i, node) in nodes.iter().enumerate() {
+            img[2048 + i * 512..2560 + i * 512].copy_from_slice(node);
+        }
+        img[6144..6151].copy_from_slice(b"hi hfs\n");
+        img
+    }
+
+    #[test]
+    fn hfsplus_fs_searches_catalog_across_leaves() {
+        let mut fs = HfsPlusFs::open(Cursor::new(make_indexed_catalog())).unwrap();
+        let root = fs.root();
+        let names: Vec<_> = fs
+            .read_dir(&root)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.name, e.is_directory))
+            .collect();
+        assert_eq!(names, [("a.txt".into(), false), ("docs".into(), true)]);
+
+        let b = fs.lookup("docs/b.txt").unwrap().expect("docs/b.txt");
+        assert_eq!(b.id, 3 << 16 | 2);
+        assert!(fs.read_dir(&b).is_err());
+
+        let a = fs.lookup("a.txt").unwrap().unwrap();
+        let mut data = Vec::new();
+        fs.open(&a).unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"hi hfs\n");
+    }
+
+    #[test]
+    fn hfsplus_fs_to_tree_matches_detect_and_parse() {
+        let img = make_indexed_catalog();
+        let eager = detect_and_parse(&mut Cursor::new(&img)).unwrap();
+        let lazy = HfsPlusFs::open(Cursor::new(&img))
+            .unwrap()
+            .to_tree()
+            .unwrap();
+        assert_eq!(format!("{lazy:?}"), format!("{eager:?}"));
+    }
 }
@@ -16,9 +16,10 @@
 //! this and returns [`Error::ProtectiveMbr`], so callers (typically
 //! [`super::raw`]) can fall through to GPT.
 
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 
+use crate::limits;
+use crate::probe::Format;
 use crate::tree::TreeNode;
 
 /// Byte size of one logical sector in the MBR scheme. The partition
@@ -85,13 +86,24 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadSignature | Error::ProtectiveMbr => {
+                crate::Error::not_recognized(Format::Mbr, e)
+            }
+        }
+    }
+}
+
 /// Read and parse the MBR. Returns the list of non-empty partitions.
 ///
 /// An entry is "non-empty" if its `type_code` is non-zero and its
 /// sector-count field is non-zero — empty slots are legal and common.
 ///
 /// The returned partitions are in slot order (0…3), not LBA order.
-pub fn parse(file: &mut File) -> Result<Vec<Partition>, Error> {
+pub fn parse<R: Read + Seek>(file: &mut R) -> Result<Vec<Partition>, Error> {
     file.seek(SeekFrom::Start(0))?;
     let mut sector = [0u8; SECTOR_SIZE as usize];
     if file.read(&mut sector)? < SECTOR_SIZE as usize {
@@ -153,7 +165,7 @@ pub fn parse_sector(sector: &[u8]) -> Result<Vec<Partition>, Error> {
 /// by `cat_node` / `extract_node`. Each partition becomes a child
 /// file of the root, named `partition-0`/`partition-1`/… with the
 /// type code in hex appended for disambiguation in `ls`-style tools.
-pub fn to_tree(partitions: &[Partition]) -> TreeNode {
+pub fn to_tree(partitions: &[Partition]) -> TreeNode<'static> {
     let mut root = TreeNode::new_directory("/".to_string());
     for p in partitions {
         let name = format!("partition-{}-type-{:02x}", p.index, p.type_code);
@@ -173,9 +185,34 @@ pub fn to_tree(partitions: &[Partition]) -> TreeNode {
 
 /// One-call detect + parse + tree. Returns the same [`TreeNode`]
 /// shape the v2 ISO/UDF parsers do.
-pub fn detect_and_parse(file: &mut File) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Mbr, None, file, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
     let parts = parse(file)?;
-    Ok(to_tree(&parts))
+    let root = to_tree(&parts);
+    limits::dir_entries(root.children.len() as u64)?;
+    for child in &root.children {
+        limits::entry(&child.name)?;
+    }
+    Ok(root)
+}
+
+/// Like [`detect_and_parse`], but also parses the filesystem inside each
+/// partition. Partitions holding a recognised filesystem become
+/// directories containing its tree, with locations translated to
+/// absolute offsets in `file`; the rest stay raw byte ranges. See
+/// [`crate::probe::open_filesystem_at`].
+pub fn detect_and_parse_recursive<R: Read + Seek>(
+    file: &mut R,
+) -> Result<TreeNode<'static>, Error> {
+    // The partitions' filesystems share this parse's limits.
+    limits::metered(Format::Mbr, None, file, |file| {
+        let mut root = detect_and_parse(file)?;
+        crate::probe::descend_into_partitions(file, &mut root);
+        Ok(root)
+    })
 }
 
 #[cfg(test)]
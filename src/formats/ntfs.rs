@@ -20,17 +20,24 @@
 //! - System files (MFT record numbers 0–11) are excluded from the output
 //!   tree. Records with the in-use flag clear are skipped.
 //!
-//! ## `file_location` semantics
+//! ## File data
 //!
-//! A file's `file_location` is set **only** when the `$DATA` attribute is
-//! resident (small file whose data lives inside the MFT record itself) or
-//! when the non-resident attribute has exactly one run in its runlist. For
-//! multi-run files the location is `None`; `cat_node` will refuse those.
-
-use std::collections::HashMap;
+//! Resident `$DATA` (small files stored inside the MFT record) and
+//! single-run non-resident streams get a plain `file_location`. Multi-run
+//! streams carry their runlist as [`Extent`]s; sparse
+//! runs and the region past `initialized_size` become holes. Compressed
+//! and encrypted streams, and runlists continued in another record via
+//! `$ATTRIBUTE_LIST`, have no location; `cat_node` will refuse those.
+
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem, NodeReader};
+use crate::index::UpCase;
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
 
 // ── Constants ─────────────────────────────────────────────────────────────────
 
@@ -42,8 +49,19 @@ const ATTR_STANDARD_INFORMATION: u32 = 0x10;
 const ATTR_ATTRIBUTE_LIST: u32 = 0x20;
 const ATTR_FILE_NAME: u32 = 0x30;
 const ATTR_DATA: u32 = 0x80;
+const ATTR_INDEX_ROOT: u32 = 0x90;
+const ATTR_INDEX_ALLOCATION: u32 = 0xA0;
+const ATTR_REPARSE_POINT: u32 = 0xC0;
 const ATTR_END: u32 = 0xFFFF_FFFF;
 
+/// Attribute header flags (u16 at offset 12).
+const ATTR_FLAG_COMPRESSION_MASK: u16 = 0x00FF;
+const ATTR_FLAG_ENCRYPTED: u16 = 0x4000;
+
+/// Reparse tags whose data names a link target.
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+
 /// MFT record numbers reserved for NTFS system metadata files.
 /// Records 0–11 inclusive are system files; user data starts at record 12.
 const SYSTEM_RECORD_COUNT: u64 = 12;
@@ -51,6 +69,9 @@ const SYSTEM_RECORD_COUNT: u64 = 12;
 /// Root directory MFT record number.
 const ROOT_MFT_RECORD: u64 = 5;
 
+/// `$UpCase` MFT record number.
+const UPCASE_MFT_RECORD: u64 = 10;
+
 /// Maximum directory nesting depth; guards against corrupted images that
 /// would otherwise cause unbounded recursion.
 const MAX_DEPTH: usize = 32;
@@ -76,6 +97,10 @@ pub enum Error {
     Io(std::io::Error),
     /// Directory hierarchy exceeds the maximum recursion depth; likely corrupt.
     TooDeep,
+    /// A handle named an MFT record that is free or not a `FILE` record.
+    BadRecord(u64),
+    /// A directory's `$I30` index is missing or corrupt.
+    BadIndex(u64),
 }
 
 impl std::fmt::Display for Error {
@@ -86,6 +111,8 @@ impl std::fmt::Display for Error {
             Error::BadClusterSize => write!(f, "NTFS cluster or MFT record size is invalid"),
             Error::Io(e) => write!(f, "NTFS I/O error: {e}"),
             Error::TooDeep => write!(f, "NTFS directory tree exceeded maximum recursion depth"),
+            Error::BadRecord(n) => write!(f, "NTFS MFT record {n} is not an in-use FILE record"),
+            Error::BadIndex(n) => write!(f, "NTFS MFT record {n} has no readable directory index"),
         }
     }
 }
@@ -105,6 +132,16 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Ntfs, e),
+            e => crate::Error::corrupt(Format::Ntfs, e),
+        }
+    }
+}
+
 // ── Boot sector ───────────────────────────────────────────────────────────────
 
 /// Parsed fields from the NTFS boot sector that we actually use.
@@ -224,7 +261,9 @@ fn read_mft_record<R: Read + Seek>(
     }
 
     if !apply_fixup(&mut buf) {
-        return Ok(None); // corrupted update-sequence; skip
+        // corrupted update-sequence; skip
+        recover::note(Some(offset), "MFT record", "update sequence does not match");
+        return Ok(None);
     }
 
     // Flags at offset 22: bit 0 = in-use.
@@ -370,29 +409,22 @@ fn parse_filename_attr(data: &[u8]) -> Option<FileNameAttr> {
 /// One decoded runlist entry: a contiguous cluster run.
 #[derive(Debug, Clone)]
 struct Run {
-    start_lcn: u64,
-    // cluster count; used by tests and by future multi-run $DATA extraction.
-    #[allow(dead_code)]
+    /// First logical cluster, or `None` for a sparse run.
+    start_lcn: Option<u64>,
+    /// Cluster count.
     length: u64,
 }
 
 /// Decode an NTFS runlist from `data`, returning the list of runs in order.
 ///
-/// Each entry begins with a 1-byte header whose high nibble is the byte-length
-/// of the cluster-count field and whose low nibble is the byte-length of the
-/// (signed) cluster-offset field. `0x00` is the end marker.
-/// Decode a NTFS runlist into physical runs.
-///
-/// Returns `(runs, had_sparse)` where `had_sparse` is `true` when at least
-/// one sparse entry (off_size == 0) was encountered. Callers that want to set
-/// `file_location` must check `had_sparse` because a sparse+data runlist can
-/// yield a single physical run that does not actually cover the full logical
-/// extent.
-fn decode_runlist(data: &[u8]) -> (Vec<Run>, bool) {
+/// Each entry begins with a 1-byte header whose low nibble is the byte-length
+/// of the cluster-count field and whose high nibble is the byte-length of the
+/// (signed) cluster-offset field. `0x00` is the end marker. Sparse entries
+/// (off_size == 0) are kept, in order, with `start_lcn = None`.
+fn decode_runlist(data: &[u8]) -> Vec<Run> {
     let mut runs = Vec::new();
     let mut pos = 0usize;
     let mut prev_lcn: i64 = 0;
-    let mut had_sparse = false;
 
     while pos < data.len() {
         let header = data[pos];
@@ -401,8 +433,8 @@ fn decode_runlist(data: &[u8]) -> (Vec<Run>, bool) {
         }
         pos += 1;
 
-        let len_size = (header >> 4) as usize; // byte count for cluster count
-        let off_size = (header & 0x0F) as usize; // byte count for cluster offset
+        let len_size = (header & 0x0F) as usize; // byte count for cluster count
+        let off_size = (header >> 4) as usize; // byte count for cluster offset
 
         if pos + len_size + off_size > data.len() {
             break;
@@ -437,18 +469,70 @@ fn decode_runlist(data: &[u8]) -> (Vec<Run>, bool) {
         };
 
         prev_lcn += delta;
-        if off_size == 0 {
-            // Sparse run: logical zeros, no physical clusters.
-            had_sparse = true;
-        } else {
-            runs.push(Run {
-                start_lcn: prev_lcn as u64,
-                length,
-            });
-        }
+        // Sparse run (off_size == 0): logical zeros, no physical clusters.
+        let start_lcn = (off_size != 0).then_some(prev_lcn as u64);
+        runs.push(Run { start_lcn, length });
+    }
+
+    runs
+}
+
+/// Map a non-resident `$DATA` attribute (`nr_slice`, starting at the
+/// attribute header) onto image byte ranges.
+///
+/// Sparse runs become holes, as does everything past `initialized_size`,
+/// which NTFS reads back as zeros. Returns `None` for compressed or
+/// encrypted streams, whose clusters do not hold the file's bytes, and for
+/// runlists that do not cover `data_size` (a stream continued in another
+/// record through `$ATTRIBUTE_LIST`).
+fn nonresident_extents(
+    nr_slice: &[u8],
+    cluster_size: u64,
+    volume_base: u64,
+) -> Option<Vec<Extent>> {
+    if nr_slice.len() < 64 {
+        return None;
+    }
+    let flags = u16::from_le_bytes([nr_slice[12], nr_slice[13]]);
+    if flags & (ATTR_FLAG_COMPRESSION_MASK | ATTR_FLAG_ENCRYPTED) != 0 {
+        return None;
+    }
+    let starting_vcn = u64::from_le_bytes(nr_slice[16..24].try_into().unwrap());
+    if starting_vcn != 0 {
+        return None;
+    }
+    let data_size = u64::from_le_bytes(nr_slice[48..56].try_into().unwrap());
+    let initialized_size = u64::from_le_bytes(nr_slice[56..64].try_into().unwrap()).min(data_size);
+    let runlist_offset = u16::from_le_bytes([nr_slice[32], nr_slice[33]]) as usize;
+    if runlist_offset >= nr_slice.len() {
+        return None;
     }
 
-    (runs, had_sparse)
+    let mut extents = Vec::new();
+    let mut logical: u64 = 0;
+    for run in decode_runlist(&nr_slice[runlist_offset..]) {
+        let length = run.length.checked_mul(cluster_size)?;
+        match run.start_lcn {
+            None => extents.push(Extent::hole(length)),
+            Some(lcn) => {
+                // Include the volume base offset for images embedded in a
+                // larger file (e.g. NTFS inside a partition image).
+                let location = volume_base.checked_add(lcn.checked_mul(cluster_size)?)?;
+                let data = initialized_size.saturating_sub(logical).min(length);
+                if data > 0 {
+                    extents.push(Extent::data(location, data));
+                }
+                if data < length {
+                    extents.push(Extent::hole(length - data));
+                }
+            }
+        }
+        logical = logical.saturating_add(length);
+    }
+    if logical < data_size {
+        return None;
+    }
+    Some(extents)
 }
 
 // ── Per-record info ────────────────────────────────────────────────────────────
@@ -461,7 +545,11 @@ struct RecordInfo {
     parent_ref: u64,
     is_directory: bool,
     file_size: u64,
-    file_location: Option<u64>,
+    /// `$DATA` runs in file order; `None` when they cannot be located.
+    extents: Option<Vec<Extent>>,
+    /// Times from `$STANDARD_INFORMATION` and any symlink/junction target.
+    /// `kind` is settled when the tree is built.
+    metadata: Metadata,
 }
 
 /// Parse a FILE record buffer into a `RecordInfo`.
@@ -484,7 +572,8 @@ fn extract_record_info(
 
     // Collect $DATA information.
     let mut file_size: u64 = 0;
-    let mut file_location: Option<u64> = None;
+    let mut extents: Option<Vec<Extent>> = None;
+    let mut metadata = Metadata::default();
 
     // We need the attribute byte offset from the start of the record to
     // compute resident data locations.  Recompute the attr start from the
@@ -542,33 +631,32 @@ fn extract_record_info(
                         if attr_pos + 24 <= buf.len() {
                             let value_offset =
                                 u16::from_le_bytes([buf[attr_pos + 20], buf[attr_pos + 21]]) as u64;
-                            file_location =
-                                Some(mft_record_abs_offset + attr_pos as u64 + value_offset);
+                            let location = mft_record_abs_offset + attr_pos as u64 + value_offset;
+                            extents = Some(vec![Extent::data(location, file_size)]);
                         }
                     }
                 } else if let Some(nr_slice) = attr.nonresident_slice {
                     // Non-resident $DATA: read data_size and decode runlist.
                     if nr_slice.len() >= 64 {
-                        let data_size = u64::from_le_bytes(nr_slice[48..56].try_into().unwrap());
-                        file_size = data_size;
-
-                        let runlist_offset =
-                            u16::from_le_bytes([nr_slice[32], nr_slice[33]]) as usize;
-                        if runlist_offset < nr_slice.len() {
-                            let (runs, had_sparse) = decode_runlist(&nr_slice[runlist_offset..]);
-                            // Single contiguous non-sparse run gets a file_location.
-                            // Include the volume base offset for images embedded in
-                            // a larger file (e.g. NTFS inside a partition image).
-                            if runs.len() == 1 && !had_sparse {
-                                file_location =
-                                    Some(volume_base + runs[0].start_lcn * cluster_size);
-                            }
-                        }
+                        file_size = u64::from_le_bytes(nr_slice[48..56].try_into().unwrap());
+                        extents = nonresident_extents(nr_slice, cluster_size, volume_base);
                     }
                 }
             }
 
-            ATTR_STANDARD_INFORMATION | ATTR_ATTRIBUTE_LIST => {
+            ATTR_STANDARD_INFORMATION => {
+                if let Some(data) = attr.resident_data {
+                    apply_standard_information(data, &mut metadata);
+                }
+            }
+
+            ATTR_REPARSE_POINT => {
+                if let Some(data) = attr.resident_data {
+                    metadata.link_target = parse_reparse_target(data);
+                }
+            }
+
+            ATTR_ATTRIBUTE_LIST => {
                 // Not needed for tree construction.
             }
 
@@ -589,10 +677,53 @@ fn extract_record_info(
         parent_ref: fn_attr.parent_ref,
         is_directory: fn_attr.is_directory,
         file_size,
-        file_location,
+        extents,
+        metadata,
     })
 }
 
+/// Convert an NTFS `FILETIME`; zero means "not set".
+fn ntfs_time(ticks: u64) -> Option<Timestamp> {
+    (ticks != 0).then(|| Timestamp::from_windows_filetime(ticks))
+}
+
+/// Copy the four timestamps out of a resident `$STANDARD_INFORMATION`
+/// value: creation, modification, MFT change and access, in that order.
+fn apply_standard_information(data: &[u8], metadata: &mut Metadata) {
+    if data.len() < 32 {
+        return;
+    }
+    let time = |at: usize| ntfs_time(u64::from_le_bytes(data[at..at + 8].try_into().unwrap()));
+    metadata.btime = time(0);
+    metadata.mtime = time(8);
+    metadata.ctime = time(16);
+    metadata.atime = time(24);
+}
+
+/// Extract the print name from a symlink or junction `$REPARSE_POINT`
+/// value. Other reparse tags (dedup, cloud files, ...) return `None`.
+fn parse_reparse_target(data: &[u8]) -> Option<String> {
+    if data.len() < 16 {
+        return None;
+    }
+    let tag = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    // Symlinks carry a 4-byte Flags field before the path buffer.
+    let path_buffer = match tag {
+        IO_REPARSE_TAG_SYMLINK => 20,
+        IO_REPARSE_TAG_MOUNT_POINT => 16,
+        _ => return None,
+    };
+    let le16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+    let (offset, length) = (le16(12), le16(14));
+    let start = path_buffer + offset;
+    let name = data.get(start..start.checked_add(length)?)?;
+    let units: Vec<u16> = name
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
 /// Higher value = better namespace for display purposes.
 fn namespace_priority(ns: u8) -> u8 {
     match ns {
@@ -606,13 +737,34 @@ fn namespace_priority(ns: u8) -> u8 {
 
 // ── Tree construction ─────────────────────────────────────────────────────────
 
+/// The node for a record that is not followed as a directory: a file, or
+/// a symlink or junction (even a directory one), which is reported as a
+/// link.
+fn leaf_node(name: String, info: &RecordInfo) -> TreeNode<'static> {
+    if info.metadata.link_target.is_some() {
+        let metadata = Metadata {
+            kind: EntryKind::Symlink,
+            ..info.metadata.clone()
+        };
+        return TreeNode::new_file(name, 0).with_metadata(metadata);
+    }
+    let node = match &info.extents {
+        Some(extents) => TreeNode::new_file_with_extents(name, info.file_size, extents.clone()),
+        None => TreeNode::new_file(name, info.file_size),
+    };
+    node.with_metadata(Metadata {
+        kind: EntryKind::File,
+        ..info.metadata.clone()
+    })
+}
+
 /// Build a `TreeNode` subtree rooted at `mft_num` from the flat record map.
 fn build_tree_recursive(
     mft_num: u64,
     name: String,
     children_map: &HashMap<u64, Vec<RecordInfo>>,
     depth: usize,
-) -> Result<TreeNode, Error> {
+) -> Result<TreeNode<'static>, Error> {
     if depth > MAX_DEPTH {
         return Err(Error::TooDeep);
     }
@@ -622,31 +774,28 @@ fn build_tree_recursive(
     let is_dir = children_map.contains_key(&mft_num);
 
     if is_dir || mft_num == ROOT_MFT_RECORD {
+        let _level = limits::enter(mft_num)?;
         let mut node = TreeNode::new_directory(name);
         if let Some(children) = children_map.get(&mft_num) {
+            limits::dir_entries(children.len() as u64)?;
             for child in children {
                 let child_name = child.name.clone();
                 let child_num = child.mft_num;
                 let child_is_dir = child.is_directory;
-                if child_is_dir {
+                if child_is_dir && child.metadata.link_target.is_none() {
                     match build_tree_recursive(child_num, child_name, children_map, depth + 1) {
-                        Ok(child_node) => node.add_child(child_node),
+                        Ok(child_node) => node.add_child(child_node.with_metadata(Metadata {
+                            kind: EntryKind::Directory,
+                            ..child.metadata.clone()
+                        })),
                         Err(Error::TooDeep) => {
                             // Skip this subtree; don't propagate TooDeep.
+                            recover::note(None, "directory", Error::TooDeep);
                         }
-                        Err(e) => return Err(e),
+                        Err(e) => recover::skip(None, "directory", e)?,
                     }
                 } else {
-                    let file_node = match child.file_location {
-                        Some(loc) => TreeNode::new_file_with_location(
-                            child_name,
-                            child.file_size,
-                            loc,
-                            child.file_size,
-                        ),
-                        None => TreeNode::new_file(child_name, child.file_size),
-                    };
-                    node.add_child(file_node);
+                    node.add_child(leaf_node(child_name, child));
                 }
             }
         }
@@ -660,6 +809,246 @@ fn build_tree_recursive(
     }
 }
 
+// ── Lazy access ───────────────────────────────────────────────────────────────
+
+/// Index entry flags (u16 at offset 12 of an index entry).
+const INDEX_ENTRY_SUBNODE: u16 = 0x01;
+const INDEX_ENTRY_LAST: u16 = 0x02;
+
+/// `$FILE_NAME` file attribute bits that decide how an index key is listed.
+const FILE_ATTR_DIRECTORY: u32 = 0x10;
+const FILE_ATTR_REPARSE_POINT: u32 = 0x400;
+/// Set on directories by Windows, which often leaves `0x10` clear in
+/// `$FILE_NAME` copies.
+const FILE_ATTR_INDEX_PRESENT: u32 = 0x1000_0000;
+
+/// An NTFS volume opened for lazy, per-directory reads.
+///
+/// Only the boot sector is read up front. Handles are MFT record numbers;
+/// listing a directory walks its `$I30` index B-tree (`$INDEX_ROOT` plus
+/// any `$INDEX_ALLOCATION` blocks) and takes names and directory flags
+/// from the index keys, so no child record is read. Entries come back in
+/// index (collation) order. System files and DOS 8.3 aliases are left
+/// out, as in [`detect_and_parse`].
+#[derive(Debug)]
+pub struct NtfsFs<R> {
+    image: R,
+    base: u64,
+    boot: BootSector,
+}
+
+impl<R: Read + Seek> NtfsFs<R> {
+    /// Read the boot sector of the volume starting at `image`'s current
+    /// position.
+    pub fn open(mut image: R) -> Result<Self, Error> {
+        let base = image.stream_position()?;
+        let mut boot_buf = [0u8; 512];
+        image
+            .read_exact(&mut boot_buf)
+            .map_err(|_| Error::TooShort)?;
+        let boot = parse_boot_sector(&boot_buf)?;
+        Ok(Self { image, base, boot })
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    /// Load the volume's `$UpCase` table, for looking names up the way
+    /// this volume compares them with [`crate::index::PathIndex`].
+    pub fn upcase(&mut self) -> crate::Result<UpCase> {
+        let offset = self.record_offset(UPCASE_MFT_RECORD);
+        let buf = self.record(UPCASE_MFT_RECORD)?;
+        let info = extract_record_info(
+            &buf,
+            UPCASE_MFT_RECORD,
+            offset,
+            self.boot.cluster_size,
+            self.base,
+        )
+        .ok_or(Error::BadRecord(UPCASE_MFT_RECORD))?;
+        let mut table = Vec::new();
+        crate::cat_node(
+            &mut self.image,
+            &leaf_node(info.name.clone(), &info),
+            &mut table,
+        )?;
+        Ok(UpCase::from_le_bytes(&table))
+    }
+
+    fn record_offset(&self, mft_num: u64) -> u64 {
+        self.base + self.boot.mft_offset + mft_num * self.boot.mft_record_size
+    }
+
+    fn record(&mut self, mft_num: u64) -> Result<Vec<u8>, Error> {
+        let offset = self.record_offset(mft_num);
+        read_mft_record(&mut self.image, offset, self.boot.mft_record_size)?
+            .ok_or(Error::BadRecord(mft_num))
+    }
+}
+
+/// State for one in-order walk of a directory's index B-tree.
+struct IndexWalk<'a, R> {
+    image: &'a mut R,
+    mft_num: u64,
+    /// `$INDEX_ALLOCATION` stream, as a node [`NodeReader`] can read.
+    allocation: Option<TreeNode<'static>>,
+    block_size: u64,
+    /// Bytes per VCN in subnode pointers: a cluster, or 512 when index
+    /// blocks are smaller than a cluster.
+    vcn_size: u64,
+    visited: HashSet<u64>,
+    out: Vec<DirEntry>,
+}
+
+impl<R: Read + Seek> IndexWalk<'_, R> {
+    /// Visit the entries of the index node whose header starts at `header`.
+    fn node(&mut self, buf: &[u8], header: usize, depth: usize) -> crate::Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(Error::TooDeep.into());
+        }
+        let le32 = |at: usize| -> Option<usize> {
+            Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().unwrap()) as usize)
+        };
+        let (Some(first), Some(used)) = (le32(header), le32(header + 4)) else {
+            return Err(Error::BadIndex(self.mft_num).into());
+        };
+        let end = header.saturating_add(used).min(buf.len());
+        let mut pos = header.saturating_add(first);
+        while pos + 16 <= end {
+            let file_ref = u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+            let len = u16::from_le_bytes([buf[pos + 8], buf[pos + 9]]) as usize;
+            let key_len = u16::from_le_bytes([buf[pos + 10], buf[pos + 11]]) as usize;
+            let flags = u16::from_le_bytes([buf[pos + 12], buf[pos + 13]]);
+            if len < 16 || pos + len > end {
+                break;
+            }
+            // Keys in a subnode sort before this entry.
+            if flags & INDEX_ENTRY_SUBNODE != 0 && len >= 24 {
+                let vcn = u64::from_le_bytes(buf[pos + len - 8..pos + len].try_into().unwrap());
+                self.subnode(vcn, depth + 1)?;
+            }
+            if flags & INDEX_ENTRY_LAST != 0 {
+                break;
+            }
+            let key = buf.get(pos + 16..pos + 16 + key_len);
+            if let Some(fn_attr) = key.and_then(parse_filename_attr) {
+                let mft_num = file_ref & 0x0000_FFFF_FFFF_FFFF;
+                if fn_attr.namespace != NS_DOS && mft_num >= SYSTEM_RECORD_COUNT {
+                    let key = key.unwrap();
+                    let attrs = u32::from_le_bytes(key[56..60].try_into().unwrap());
+                    let is_directory = attrs & (FILE_ATTR_DIRECTORY | FILE_ATTR_INDEX_PRESENT) != 0
+                        && attrs & FILE_ATTR_REPARSE_POINT == 0;
+                    self.out
+                        .push(DirEntry::new(fn_attr.name, is_directory, mft_num));
+                }
+            }
+            pos += len;
+        }
+        Ok(())
+    }
+
+    /// Read the `INDX` block at `vcn` and visit its entries.
+    fn subnode(&mut self, vcn: u64, depth: usize) -> crate::Result<()> {
+        // A block already seen means a cycle in a corrupt index.
+        if !self.visited.insert(vcn) {
+            return Ok(());
+        }
+        let bad = || Error::BadIndex(self.mft_num);
+        let allocation = self.allocation.as_ref().ok_or_else(bad)?;
+        let offset = vcn.checked_mul(self.vcn_size).ok_or_else(bad)?;
+        let mut block = vec![0u8; self.block_size as usize];
+        let mut reader = NodeReader::new(self.image, allocation)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut block)?;
+        if &block[0..4] != b"INDX" || !apply_fixup(&mut block) {
+            return Err(bad().into());
+        }
+        self.node(&block, 24, depth)
+    }
+}
+
+impl<R: Read + Seek> Filesystem for NtfsFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Ntfs
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, ROOT_MFT_RECORD)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> crate::Result<Vec<DirEntry>> {
+        if !dir.is_directory {
+            return Err(not_a_directory(dir));
+        }
+        let buf = self.record(dir.id)?;
+        let attrs = parse_attributes(&buf);
+        let root = attrs
+            .iter()
+            .find(|a| a.attr_type == ATTR_INDEX_ROOT)
+            .and_then(|a| a.resident_data)
+            .filter(|value| value.len() >= 32)
+            .ok_or(Error::BadIndex(dir.id))?;
+        let block_size = u32::from_le_bytes(root[8..12].try_into().unwrap()) as u64;
+        if !(512..=65536).contains(&block_size) || !block_size.is_power_of_two() {
+            return Err(Error::BadIndex(dir.id).into());
+        }
+        let cluster_size = self.boot.cluster_size;
+        let allocation = attrs
+            .iter()
+            .find(|a| a.attr_type == ATTR_INDEX_ALLOCATION)
+            .and_then(|a| a.nonresident_slice)
+            .and_then(|slice| {
+                let extents = nonresident_extents(slice, cluster_size, self.base)?;
+                let size = u64::from_le_bytes(slice[48..56].try_into().unwrap());
+                Some(TreeNode::new_file_with_extents(
+                    String::new(),
+                    size,
+                    extents,
+                ))
+            });
+
+        let mut walk = IndexWalk {
+            image: &mut self.image,
+            mft_num: dir.id,
+            allocation,
+            block_size,
+            vcn_size: if block_size >= cluster_size {
+                cluster_size
+            } else {
+                512
+            },
+            visited: HashSet::new(),
+            out: Vec::new(),
+        };
+        // The root's node header follows its 16-byte index root header.
+        walk.node(root, 16, 0)?;
+        Ok(walk.out)
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> crate::Result<TreeNode<'static>> {
+        let offset = self.record_offset(entry.id);
+        let buf = self.record(entry.id)?;
+        let info = extract_record_info(&buf, entry.id, offset, self.boot.cluster_size, self.base)
+            .ok_or(Error::BadRecord(entry.id))?;
+        let name = entry.name.clone();
+        if entry.is_directory {
+            return Ok(TreeNode::new_directory(name).with_metadata(Metadata {
+                kind: EntryKind::Directory,
+                ..info.metadata
+            }));
+        }
+        Ok(leaf_node(name, &info))
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
 // ── Public API ────────────────────────────────────────────────────────────────
 
 /// Return `true` if the stream at its current position looks like an NTFS
@@ -692,7 +1081,11 @@ fn detect_inner<R: Read + Seek>(file: &mut R, base: u64) -> bool {
 ///
 /// `file`'s current position is treated as the start of the NTFS volume,
 /// allowing parsing of NTFS partitions that begin mid-image.
-pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Ntfs, None, file, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
     let base = file.stream_position()?;
 
     // Read and parse the boot sector (512 bytes).
@@ -712,7 +1105,14 @@ pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode, Error>
         let record_offset = base + boot.mft_offset + mft_num * boot.mft_record_size;
         let record_abs = base + boot.mft_offset + mft_num * boot.mft_record_size;
 
-        match read_mft_record(file, record_offset, boot.mft_record_size)? {
+        let record = match read_mft_record(file, record_offset, boot.mft_record_size) {
+            Ok(record) => record,
+            Err(e) => {
+                recover::skip(Some(record_abs), "MFT record", e)?;
+                None
+            }
+        };
+        match record {
             None => {
                 // Free / unused MFT slot or UnexpectedEof. Real volumes have free
                 // slots interspersed, so we cannot stop here. We rely on the
@@ -725,6 +1125,7 @@ pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode, Error>
                     // Skip the root directory record itself (record 5) from
                     // the flat list; we'll handle it as the tree root.
                     if mft_num != ROOT_MFT_RECORD {
+                        limits::entry(&info.name)?;
                         records.push(info);
                     }
                 }
@@ -1154,10 +1555,9 @@ mod tests {
     fn decode_runlist_single_run() {
         // Header 0x11: len_size=1, off_size=1. Count=8, delta=+3.
         let data = [0x11u8, 8, 3, 0x00];
-        let (runs, had_sparse) = decode_runlist(&data);
-        assert!(!had_sparse);
+        let runs = decode_runlist(&data);
         assert_eq!(runs.len(), 1);
-        assert_eq!(runs[0].start_lcn, 3);
+        assert_eq!(runs[0].start_lcn, Some(3));
         assert_eq!(runs[0].length, 8);
     }
 
@@ -1166,26 +1566,35 @@ mod tests {
         // Run 1: 0x11 count=4 delta=+10 → LCN 10, len 4.
         // Run 2: 0x11 count=2 delta=+5  → LCN 15, len 2.
         let data = [0x11, 4, 10, 0x11, 2, 5, 0x00];
-        let (runs, had_sparse) = decode_runlist(&data);
-        assert!(!had_sparse);
+        let runs = decode_runlist(&data);
         assert_eq!(runs.len(), 2);
-        assert_eq!(runs[0].start_lcn, 10);
+        assert_eq!(runs[0].start_lcn, Some(10));
         assert_eq!(runs[0].length, 4);
-        assert_eq!(runs[1].start_lcn, 15);
+        assert_eq!(runs[1].start_lcn, Some(15));
         assert_eq!(runs[1].length, 2);
     }
 
+    #[test]
+    fn decode_runlist_asymmetric_header() {
+        // Header 0x21: len_size=1 (low nibble), off_size=2 (high nibble).
+        // Count=0x18, delta=0x5634.
+        let data = [0x21, 0x18, 0x34, 0x56, 0x00];
+        let runs = decode_runlist(&data);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].length, 0x18);
+        assert_eq!(runs[0].start_lcn, Some(0x5634));
+    }
+
     #[test]
     fn decode_runlist_negative_delta() {
         // Run 1: count=8, delta=+20 → LCN 20.
         // Run 2: count=4, delta=-5  → LCN 15.
         // -5 in two's complement as i8 = 0xFB.
         let data = [0x11, 8, 20, 0x11, 4, 0xFBu8, 0x00];
-        let (runs, had_sparse) = decode_runlist(&data);
-        assert!(!had_sparse);
+        let runs = decode_runlist(&data);
         assert_eq!(runs.len(), 2);
-        assert_eq!(runs[0].start_lcn, 20);
-        assert_eq!(runs[1].start_lcn, 15);
+        assert_eq!(runs[0].start_lcn, Some(20));
+        assert_eq!(runs[1].start_lcn, Some(15));
     }
 
     #[test]
@@ -1226,6 +1635,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn upcase_reads_record_10() {
+        let mut img = make_minimal_ntfs_image();
+        let mut fs = NtfsFs::open(cursor_of(&img)).unwrap();
+        assert!(fs.upcase().is_err(), "record 10 is not in use");
+
+        // Record 10's resident $DATA becomes the table, two bytes per entry.
+        write_file_record(&mut img, 16384 + 10 * 1024, 10, false);
+        let upcase = NtfsFs::open(cursor_of(&img)).unwrap().upcase().unwrap();
+        assert_eq!(upcase.map(0), u16::from_le_bytes(*b"he"));
+        assert_eq!(upcase.map(4), u16::from_le_bytes(*b"fs"));
+        assert_eq!(upcase.map(5), 5, "past the table maps to itself");
+    }
+
     // ── From<io::Error> conversion ────────────────────────────────────────────
 
     #[test]
@@ -1558,18 +1981,75 @@ mod tests {
     fn decode_runlist_truncated_run_breaks() {
         // Header says len_size=1, off_size=1, but only 1 data byte follows → break (line 408).
         let data = [0x11u8, 8]; // header + only 1 byte (len byte), no offset byte
-        let (runs, _) = decode_runlist(&data);
+        let runs = decode_runlist(&data);
         assert!(runs.is_empty(), "truncated run should produce no runs");
     }
 
     #[test]
     fn decode_runlist_sparse_run() {
-        // off_size=0: sparse run, had_sparse=true (lines 440-442).
-        // Header 0x10: len_size=1, off_size=0. count=4.
-        let data = [0x10u8, 4, 0x00]; // header + count + terminator
-        let (runs, had_sparse) = decode_runlist(&data);
-        assert!(had_sparse, "off_size=0 should set had_sparse");
-        assert!(runs.is_empty(), "sparse run has no physical location");
+        // off_size=0: sparse run, kept in order without a location.
+        // Header 0x01: len_size=1, off_size=0. count=4.
+        let data = [0x01u8, 4, 0x00]; // header + count + terminator
+        let runs = decode_runlist(&data);
+        assert_eq!(runs.len(), 1);
+        assert!(
+            runs[0].start_lcn.is_none(),
+            "sparse run has no physical location"
+        );
+        assert_eq!(runs[0].length, 4);
+    }
+
+    // ── nonresident_extents ──────────────────────────────────────────────────
+
+    /// Non-resident `$DATA` header (64 bytes) followed by `runlist`.
+    fn make_nr_slice(data_size: u64, initialized: u64, flags: u16, runlist: &[u8]) -> Vec<u8> {
+        let mut s = vec![0u8; 64];
+        s[8] = 1; // non_resident
+        s[12..14].copy_from_slice(&flags.to_le_bytes());
+        s[32..34].copy_from_slice(&64u16.to_le_bytes()); // runlist_offset
+        s[48..56].copy_from_slice(&data_size.to_le_bytes());
+        s[56..64].copy_from_slice(&initialized.to_le_bytes());
+        s.extend_from_slice(runlist);
+        s
+    }
+
+    #[test]
+    fn nonresident_extents_multi_run_with_sparse() {
+        // LCN 10 ×2, sparse ×1, LCN 4 ×1 (delta −6).
+        let runlist = [0x11, 2, 10, 0x01, 1, 0x11, 1, 0xFA, 0x00];
+        let slice = make_nr_slice(4 * 4096, 4 * 4096, 0, &runlist);
+        let ext = nonresident_extents(&slice, 4096, 512).unwrap();
+        assert_eq!(
+            ext,
+            vec![
+                Extent::data(512 + 10 * 4096, 2 * 4096),
+                Extent::hole(4096),
+                Extent::data(512 + 4 * 4096, 4096),
+            ]
+        );
+    }
+
+    #[test]
+    fn nonresident_extents_past_initialized_size_is_hole() {
+        let runlist = [0x11, 2, 10, 0x00];
+        let slice = make_nr_slice(2 * 4096, 5000, 0, &runlist);
+        let ext = nonresident_extents(&slice, 4096, 0).unwrap();
+        assert_eq!(
+            ext,
+            vec![Extent::data(10 * 4096, 5000), Extent::hole(2 * 4096 - 5000)]
+        );
+    }
+
+    #[test]
+    fn nonresident_extents_compressed_or_short_is_none() {
+        let runlist = [0x11, 2, 10, 0x00];
+        let compressed = make_nr_slice(2 * 4096, 2 * 4096, 0x0001, &runlist);
+        assert!(nonresident_extents(&compressed, 4096, 0).is_none());
+        let encrypted = make_nr_slice(2 * 4096, 2 * 4096, ATTR_FLAG_ENCRYPTED, &runlist);
+        assert!(nonresident_extents(&encrypted, 4096, 0).is_none());
+        // Runlist covers 2 clusters of a 3-cluster stream.
+        let short = make_nr_slice(3 * 4096, 3 * 4096, 0, &runlist);
+        assert!(nonresident_extents(&short, 4096, 0).is_none());
     }
 
     // ── namespace_priority: all branches ─────────────────────────────────────
@@ -1605,7 +2085,8 @@ mod tests {
                     parent_ref: ROOT_MFT_RECORD,
                     is_directory: false,
                     file_size: 100,
-                    file_location: Some(4096),
+                    extents: Some(vec![Extent::data(4096, 100)]),
+                    metadata: Metadata::default(),
                 },
                 RecordInfo {
                     mft_num: 21,
@@ -1613,7 +2094,8 @@ mod tests {
                     parent_ref: ROOT_MFT_RECORD,
                     is_directory: false,
                     file_size: 200,
-                    file_location: None, // covers TreeNode::new_file branch (line 647)
+                    extents: None, // covers TreeNode::new_file branch (line 647)
+                    metadata: Metadata::default(),
                 },
             ],
         );
@@ -1646,7 +2128,8 @@ mod tests {
                 parent_ref: ROOT_MFT_RECORD,
                 is_directory: true,
                 file_size: 0,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             }],
         );
         map.insert(
@@ -1657,7 +2140,8 @@ mod tests {
                 parent_ref: 20,
                 is_directory: false,
                 file_size: 42,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             }],
         );
         let root = build_tree_recursive(ROOT_MFT_RECORD, "/".to_string(), &map, 0).unwrap();
@@ -1682,7 +2166,8 @@ mod tests {
                 parent_ref: ROOT_MFT_RECORD,
                 is_directory: true,
                 file_size: 0,
-                file_location: None,
+                extents: None,
+                metadata: Metadata::default(),
             }],
         );
         // At depth=MAX_DEPTH, root itself succeeds (depth==MAX_DEPTH, not >MAX_DEPTH).
@@ -1752,8 +2237,9 @@ mod tests {
         img[nr_start + 8] = 1; // non_resident
                                // runlist_offset at +32..34 = 64 (after fixed 64-byte header)
         img[nr_start + 32..nr_start + 34].copy_from_slice(&64u16.to_le_bytes());
-        // data_size at +48..56 = 8 * 4096 = 32768
+        // data_size at +48..56 and initialized_size at +56..64 = 8 * 4096 = 32768
         img[nr_start + 48..nr_start + 56].copy_from_slice(&(8u64 * 4096u64).to_le_bytes());
+        img[nr_start + 56..nr_start + 64].copy_from_slice(&(8u64 * 4096u64).to_le_bytes());
         // Runlist at +64: single run header=0x11 (len_size=1, off_size=1), count=8, delta=+4 (LCN=4)
         img[nr_start + 64] = 0x11; // header
         img[nr_start + 65] = 8; // cluster count
@@ -1904,6 +2390,80 @@ mod tests {
         assert_eq!(info.unwrap().name, "A");
     }
 
+    #[test]
+    fn extract_record_info_standard_information_sets_times() {
+        let mut si_attr = make_resident_attr(ATTR_STANDARD_INFORMATION, 48);
+        // 2000-01-01T00:00:00Z as FILETIME, then +1 s for each later field.
+        let base = 125_911_584_000_000_000u64;
+        for (i, field) in si_attr[24..56].chunks_exact_mut(8).enumerate() {
+            field.copy_from_slice(&(base + i as u64 * 10_000_000).to_le_bytes());
+        }
+        let buf = make_eri_buf(ROOT_MFT_RECORD, NS_WIN32_DOS, &si_attr);
+        let info = extract_record_info(&buf, 12, 0, 4096, 0).unwrap();
+        assert_eq!(info.metadata.btime, Some(Timestamp::new(946_684_800, 0)));
+        assert_eq!(info.metadata.mtime, Some(Timestamp::new(946_684_801, 0)));
+        assert_eq!(info.metadata.ctime, Some(Timestamp::new(946_684_802, 0)));
+        assert_eq!(info.metadata.atime, Some(Timestamp::new(946_684_803, 0)));
+    }
+
+    fn reparse_data(tag: u32, print_name: &str) -> Vec<u8> {
+        let name: Vec<u8> = print_name
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let path_buffer = if tag == IO_REPARSE_TAG_SYMLINK {
+            20
+        } else {
+            16
+        };
+        let mut data = vec![0u8; path_buffer];
+        data[0..4].copy_from_slice(&tag.to_le_bytes());
+        // Substitute name is empty; print name starts at offset 0.
+        data[14..16].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&name);
+        data
+    }
+
+    #[test]
+    fn parse_reparse_target_symlink_and_junction() {
+        assert_eq!(
+            parse_reparse_target(&reparse_data(IO_REPARSE_TAG_SYMLINK, "..\\target.txt")),
+            Some("..\\target.txt".to_string())
+        );
+        assert_eq!(
+            parse_reparse_target(&reparse_data(IO_REPARSE_TAG_MOUNT_POINT, "C:\\Users")),
+            Some("C:\\Users".to_string())
+        );
+        assert_eq!(parse_reparse_target(&reparse_data(0x8000_0013, "x")), None);
+        assert_eq!(parse_reparse_target(&[0u8; 8]), None);
+    }
+
+    #[test]
+    fn build_tree_recursive_reparse_point_becomes_symlink() {
+        let mut children_map: HashMap<u64, Vec<RecordInfo>> = HashMap::new();
+        children_map.insert(
+            ROOT_MFT_RECORD,
+            vec![RecordInfo {
+                mft_num: 40,
+                name: "link".to_string(),
+                parent_ref: ROOT_MFT_RECORD,
+                is_directory: true,
+                file_size: 0,
+                extents: None,
+                metadata: Metadata {
+                    link_target: Some("C:\\data".to_string()),
+                    ..Metadata::default()
+                },
+            }],
+        );
+        let root =
+            build_tree_recursive(ROOT_MFT_RECORD, "/".to_string(), &children_map, 0).unwrap();
+        let link = &root.children[0];
+        assert!(!link.is_directory);
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+        assert_eq!(link.metadata.link_target.as_deref(), Some("C:\\data"));
+    }
+
     #[test]
     fn extract_record_info_attribute_list_in_record() {
         // $ATTRIBUTE_LIST hits the same arm.
@@ -2138,7 +2698,7 @@ mod tests {
         let info = extract_record_info(&buf, 12, 0, 4096, 0);
         // Still returns Some (valid $FILE_NAME present) but file_location=None.
         assert!(info.is_some());
-        assert!(info.unwrap().file_location.is_none());
+        assert!(info.unwrap().extents.is_none());
     }
 
     // ── detect_and_parse: parent_ref == mft_num skip ─────────────────────────
@@ -2198,4 +2758,163 @@ mod tests {
             "self-referencing record should be excluded from tree"
         );
     }
+
+    // ── NtfsFs ────────────────────────────────────────────────────────────────
+
+    /// One `$I30` index entry whose key is a `$FILE_NAME` value.
+    fn index_entry(mft_num: u64, name: &str, attrs: u32, namespace: u8) -> Vec<u8> {
+        let units: Vec<u8> = name.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        let key_len = 66 + units.len();
+        let len = (16 + key_len + 7) & !7;
+        let mut e = vec![0u8; len];
+        e[0..8].copy_from_slice(&(mft_num | 1 << 48).to_le_bytes()); // sequence 1
+        e[8..10].copy_from_slice(&(len as u16).to_le_bytes());
+        e[10..12].copy_from_slice(&(key_len as u16).to_le_bytes());
+        let key = &mut e[16..];
+        key[0..8].copy_from_slice(&ROOT_MFT_RECORD.to_le_bytes());
+        key[56..60].copy_from_slice(&attrs.to_le_bytes());
+        key[64] = name.encode_utf16().count() as u8;
+        key[65] = namespace;
+        key[66..66 + units.len()].copy_from_slice(&units);
+        e
+    }
+
+    /// The entry that ends an index node, optionally pointing at a subnode.
+    fn last_entry(subnode_vcn: Option<u64>) -> Vec<u8> {
+        match subnode_vcn {
+            None => {
+                let mut e = vec![0u8; 16];
+                e[8..10].copy_from_slice(&16u16.to_le_bytes());
+                e[12..14].copy_from_slice(&INDEX_ENTRY_LAST.to_le_bytes());
+                e
+            }
+            Some(vcn) => {
+                let mut e = vec![0u8; 24];
+                e[8..10].copy_from_slice(&24u16.to_le_bytes());
+                let flags = INDEX_ENTRY_LAST | INDEX_ENTRY_SUBNODE;
+                e[12..14].copy_from_slice(&flags.to_le_bytes());
+                e[16..24].copy_from_slice(&vcn.to_le_bytes());
+                e
+            }
+        }
+    }
+
+    /// Replace the `ATTR_END` marker of the FILE record at `offset` with
+    /// `attr`, followed by a new marker.
+    fn append_attr(img: &mut [u8], offset: usize, attr: &[u8]) {
+        let mut pos = offset + 56;
+        while u32::from_le_bytes(img[pos..pos + 4].try_into().unwrap()) != ATTR_END {
+            pos += u32::from_le_bytes(img[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        }
+        img[pos..pos + attr.len()].copy_from_slice(attr);
+        let end = pos + attr.len();
+        img[end..end + 4].copy_from_slice(&ATTR_END.to_le_bytes());
+        img[offset + 24..offset + 28].copy_from_slice(&((end + 4 - offset) as u32).to_le_bytes());
+    }
+
+    /// Give the root directory record an `$INDEX_ROOT` holding `entries`.
+    fn write_root_index(img: &mut [u8], entries: &[u8], has_subnodes: bool) {
+        let mut value = vec![0u8; 32];
+        value[0..4].copy_from_slice(&ATTR_FILE_NAME.to_le_bytes());
+        value[4..8].copy_from_slice(&1u32.to_le_bytes()); // COLLATION_FILE_NAME
+        value[8..12].copy_from_slice(&4096u32.to_le_bytes()); // index block size
+        value[12] = 1; // clusters per index block
+        let used = (16 + entries.len()) as u32;
+        value[16..20].copy_from_slice(&16u32.to_le_bytes()); // entries offset
+        value[20..24].copy_from_slice(&used.to_le_bytes());
+        value[24..28].copy_from_slice(&used.to_le_bytes());
+        value[28] = has_subnodes as u8;
+        value.extend_from_slice(entries);
+
+        let mut attr = make_resident_attr(ATTR_INDEX_ROOT, value.len());
+        attr[24..24 + value.len()].copy_from_slice(&value);
+        append_attr(img, 16384 + 5 * 1024, &attr);
+    }
+
+    fn ntfs_with_root_entries() -> Vec<u8> {
+        let mut img = make_minimal_ntfs_image();
+        let mut entries = index_entry(12, "HELLO~1.TXT", 0x20, NS_DOS);
+        entries.extend(index_entry(0, "$MFT", 0x06, NS_WIN32_DOS));
+        entries.extend(index_entry(12, "hello.txt", 0x20, NS_WIN32));
+        entries.extend(last_entry(None));
+        write_root_index(&mut img, &entries, false);
+        img
+    }
+
+    #[test]
+    fn ntfs_fs_lists_index_root_without_system_files_or_dos_names() {
+        let mut fs = NtfsFs::open(cursor_of(&ntfs_with_root_entries())).unwrap();
+        let root = fs.root();
+        let entries = fs.read_dir(&root).unwrap();
+        assert_eq!(entries, vec![DirEntry::new("hello.txt".into(), false, 12)]);
+
+        let hello = fs.lookup("/hello.txt").unwrap().expect("hello.txt");
+        let mut data = Vec::new();
+        fs.open(&hello).unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"hello ntfs\n");
+        assert!(fs.read_dir(&hello).is_err());
+        assert!(fs.lookup("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn ntfs_fs_to_tree_matches_detect_and_parse() {
+        let img = ntfs_with_root_entries();
+        let eager = detect_and_parse(&mut cursor_of(&img)).unwrap();
+        let lazy = NtfsFs::open(cursor_of(&img)).unwrap().to_tree().unwrap();
+        assert_eq!(
+            format!("{:?}", lazy.children),
+            format!("{:?}", eager.children)
+        );
+        assert_eq!(lazy.size, eager.size);
+    }
+
+    #[test]
+    fn ntfs_fs_walks_index_allocation_blocks() {
+        let mut img = make_minimal_ntfs_image();
+        img.resize(40 * 1024, 0);
+        write_root_index(&mut img, &last_entry(Some(0)), true);
+
+        // $INDEX_ALLOCATION: one 4096-byte block at cluster 9.
+        let mut attr = vec![0u8; 72];
+        attr[0..4].copy_from_slice(&ATTR_INDEX_ALLOCATION.to_le_bytes());
+        attr[4..8].copy_from_slice(&72u32.to_le_bytes());
+        attr[8] = 1; // non-resident
+        attr[32..34].copy_from_slice(&64u16.to_le_bytes()); // runlist offset
+        for field in [40, 48, 56] {
+            attr[field..field + 8].copy_from_slice(&4096u64.to_le_bytes());
+        }
+        attr[64..67].copy_from_slice(&[0x11, 1, 9]);
+        append_attr(&mut img, 16384 + 5 * 1024, &attr);
+
+        let block = 9 * 4096;
+        img[block..block + 4].copy_from_slice(b"INDX");
+        img[block + 4..block + 6].copy_from_slice(&40u16.to_le_bytes()); // usa offset
+        img[block + 6..block + 8].copy_from_slice(&9u16.to_le_bytes()); // usa count
+        let mut entries = index_entry(12, "hello.txt", 0x20, NS_WIN32_DOS);
+        entries.extend(index_entry(13, "sub", 0x1000_0000, NS_WIN32_DOS));
+        entries.extend(last_entry(None));
+        let used = (40 + entries.len()) as u32;
+        img[block + 24..block + 28].copy_from_slice(&40u32.to_le_bytes());
+        img[block + 28..block + 32].copy_from_slice(&used.to_le_bytes());
+        img[block + 64..block + 64 + entries.len()].copy_from_slice(&entries);
+
+        let mut fs = NtfsFs::open(cursor_of(&img)).unwrap();
+        let root = fs.root();
+        let entries = fs.read_dir(&root).unwrap();
+        let names: Vec<_> = entries
+            .iter()
+            .map(|e| (e.name.as_ref(), e.is_directory))
+            .collect();
+        assert_eq!(names, [("hello.txt", false), ("sub", true)]);
+        // Record 13 was never written.
+        let err = fs.stat(&entries[1]).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::Corrupt {
+                format: Format::Ntfs,
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), Error::BadRecord(13).to_string());
+    }
 }
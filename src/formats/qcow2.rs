@@ -5,16 +5,26 @@
 //! This reader handles:
 //!
 //! - **Version 2 and 3 QCOW2 images**: the 72-byte common header is
-//!   parsed for both; version-3 extended fields are accepted but not
-//!   acted upon.
+//!   parsed for both. A version-3 image with any `incompatible_features`
+//!   bit other than dirty set (corrupt, external data file, compression
+//!   type, extended L2 entries) returns [`Error::UnsupportedFeatures`],
+//!   since reading it as a plain image would return the wrong data. The
+//!   dirty bit only marks stale refcounts, which a reader never uses;
+//!   the other version-3 extended fields are accepted but not acted upon.
 //! - **Unencrypted images only**: `encryption_method` must be 0.
 //!   AES (legacy, method=1) and LUKS (method=2) both return
 //!   [`Error::Encrypted`].
+//! - **Standalone images only**: an overlay with a nonzero
+//!   `backing_file_offset` returns [`Error::BackingFile`]. Its
+//!   unwritten clusters live in the backing image, which this reader
+//!   does not open.
 //!
-//! L1/L2 table traversal is not performed — we report `disk_size`
-//! as the virtual disk size and set `file_location = None` because
-//! QCOW2 data is addressed through copy-on-write cluster tables, never
-//! as a single contiguous extent.
+//! The tree reports `disk_size` as the virtual disk size. QCOW2 data is
+//! addressed through copy-on-write cluster tables, never as a single
+//! contiguous extent, so the disk node carries one extent per allocated
+//! cluster and a hole for each unallocated or zero one. [`VirtualDisk`]
+//! walks the same L1/L2 tables on the fly and exposes the guest disk as
+//! `Read + Seek` for the other parsers in the crate.
 //!
 //! ## Header layout (big-endian, bytes 0..72)
 //!
@@ -32,11 +42,14 @@
 //! [56]   u32  refcount_table_clusters
 //! [60]   u32  nb_snapshots
 //! [64]   u64  snapshots_offset
+//! [72]   u64  incompatible_features  // version 3 only
 //! ```
 
 use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::{Extent, TreeNode};
 
 /// Magic bytes at offset 0 of every QCOW2 file.
 ///
@@ -52,6 +65,24 @@ const CLUSTER_BITS_MIN: u32 = 9;
 /// Maximum valid `cluster_bits` (cluster_size = 2 MiB).
 const CLUSTER_BITS_MAX: u32 = 21;
 
+/// Size of the version-3 header up to and including
+/// `incompatible_features`.
+const V3_HEADER_SIZE: usize = 80;
+
+/// Bits 9..=55 of an L1 or L2 entry: the host byte offset of the table
+/// or cluster.
+const OFFSET_MASK: u64 = 0x00FF_FFFF_FFFF_FE00;
+
+/// L2 entry bit 62: the cluster is compressed.
+const L2_COMPRESSED: u64 = 1 << 62;
+
+/// L2 entry bit 0 (version 3): the cluster reads as all zeros.
+const L2_ZERO: u64 = 1;
+
+/// `incompatible_features` bit 0: refcounts may be stale after an
+/// unclean shutdown. Guest data is intact, so reading is safe.
+const FEATURE_DIRTY: u64 = 1;
+
 // ── Error type ────────────────────────────────────────────────────────────────
 
 /// Reasons [`detect`] or [`detect_and_parse`] can fail.
@@ -68,6 +99,14 @@ pub enum Error {
     Encrypted,
     /// `cluster_bits` is outside `9..=21`. The observed value is included.
     BadClusterBits(u32),
+    /// The L1 table does not fit inside the file.
+    BadL1Table,
+    /// A version-3 `incompatible_features` bit this reader does not
+    /// implement is set. The observed field is included.
+    UnsupportedFeatures(u64),
+    /// `backing_file_offset` is non-zero: the image is an overlay whose
+    /// unwritten clusters come from another file.
+    BackingFile,
     /// Underlying I/O error.
     Io(io::Error),
 }
@@ -90,6 +129,18 @@ impl std::fmt::Display for Error {
             Error::BadClusterBits(b) => {
                 write!(f, "QCOW2 cluster_bits {b} is out of range (must be 9..=21)")
             }
+            Error::BadL1Table => {
+                write!(f, "QCOW2 L1 table lies outside the file")
+            }
+            Error::UnsupportedFeatures(bits) => {
+                write!(f, "QCOW2 incompatible features {bits:#x} are not supported")
+            }
+            Error::BackingFile => {
+                write!(
+                    f,
+                    "QCOW2 overlay images with a backing file are not supported"
+                )
+            }
             Error::Io(e) => write!(f, "QCOW2 I/O error: {e}"),
         }
     }
@@ -110,29 +161,54 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Qcow2, e),
+            Error::UnsupportedVersion(_) | Error::UnsupportedFeatures(_) | Error::BackingFile => {
+                crate::Error::unsupported(Format::Qcow2, e)
+            }
+            Error::Encrypted => crate::Error::Encrypted {
+                format: Format::Qcow2,
+            },
+            e => crate::Error::corrupt(Format::Qcow2, e),
+        }
+    }
+}
+
 // ── Header ────────────────────────────────────────────────────────────────────
 
 /// Parsed QCOW2 header — only the fields used after validation.
 ///
-/// `version`, `cluster_bits`, and `encryption_method` are validated
-/// inside `read_header` and not stored; only `disk_size` is returned.
+/// `version` and `encryption_method` are validated inside `read_header`
+/// and not stored.
 struct Header {
+    /// `cluster_bits`: log2 of the cluster size (offset 20).
+    cluster_bits: u32,
     /// `disk_size`: virtual disk size in bytes (offset 24).
     disk_size: u64,
+    /// `l1_size`: number of L1 table entries (offset 36).
+    l1_size: u32,
+    /// `l1_table_offset`: byte offset of the L1 table (offset 40).
+    l1_table_offset: u64,
 }
 
 /// Read and validate a QCOW2 header from the current stream position.
 ///
 /// Expects the stream to be positioned at byte 0 before the call.
 fn read_header<R: Read + Seek>(r: &mut R) -> Result<Header, Error> {
-    let mut buf = [0u8; HEADER_SIZE];
-    r.read_exact(&mut buf).map_err(|e| {
-        if e.kind() == io::ErrorKind::UnexpectedEof {
-            Error::TooShort
-        } else {
-            Error::Io(e)
-        }
-    })?;
+    let read = |r: &mut R, buf: &mut [u8]| {
+        r.read_exact(buf).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                Error::TooShort
+            } else {
+                Error::Io(e)
+            }
+        })
+    };
+    let mut buf = [0u8; V3_HEADER_SIZE];
+    read(r, &mut buf[..HEADER_SIZE])?;
 
     // Verify magic.
     let magic = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
@@ -146,6 +222,28 @@ fn read_header<R: Read + Seek>(r: &mut R) -> Result<Header, Error> {
         return Err(Error::UnsupportedVersion(version));
     }
 
+    // incompatible_features at [72] (version 3). Only the dirty bit is
+    // harmless to a reader; every other bit, known or not, changes how
+    // clusters must be read.
+    if version == 3 {
+        read(r, &mut buf[HEADER_SIZE..])?;
+        let features = u64::from_be_bytes([
+            buf[72], buf[73], buf[74], buf[75], buf[76], buf[77], buf[78], buf[79],
+        ]);
+        if features & !FEATURE_DIRTY != 0 {
+            return Err(Error::UnsupportedFeatures(features));
+        }
+    }
+
+    // backing_file_offset at [8]. Clusters an overlay has not written
+    // read through to the backing file, which is never opened here.
+    let backing_file_offset = u64::from_be_bytes([
+        buf[8], buf[9], buf[10], buf[11], buf[12], buf[13], buf[14], buf[15],
+    ]);
+    if backing_file_offset != 0 {
+        return Err(Error::BackingFile);
+    }
+
     // cluster_bits at [20].
     let cluster_bits = u32::from_be_bytes([buf[20], buf[21], buf[22], buf[23]]);
     if !(CLUSTER_BITS_MIN..=CLUSTER_BITS_MAX).contains(&cluster_bits) {
@@ -163,7 +261,17 @@ fn read_header<R: Read + Seek>(r: &mut R) -> Result<Header, Error> {
         return Err(Error::Encrypted);
     }
 
-    Ok(Header { disk_size })
+    let l1_size = u32::from_be_bytes([buf[36], buf[37], buf[38], buf[39]]);
+    let l1_table_offset = u64::from_be_bytes([
+        buf[40], buf[41], buf[42], buf[43], buf[44], buf[45], buf[46], buf[47],
+    ]);
+
+    Ok(Header {
+        cluster_bits,
+        disk_size,
+        l1_size,
+        l1_table_offset,
+    })
 }
 
 // ── Detection ─────────────────────────────────────────────────────────────────
@@ -210,27 +318,222 @@ fn detect_inner<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 /// └── disk.qcow2 (file, size = virtual disk size)
 /// ```
 ///
-/// `file_location` is always `None`: QCOW2 data is addressed through
-/// L1/L2 cluster tables with copy-on-write semantics, never as a
-/// single contiguous extent. `file_length` reports the virtual disk
-/// size from the header.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
-    r.seek(SeekFrom::Start(0))?;
-    let hdr = read_header(r)?;
-    let disk_size = hdr.disk_size;
+/// QCOW2 data is addressed through L1/L2 cluster tables with
+/// copy-on-write semantics, never as a single contiguous extent, so
+/// `disk.qcow2` gets one extent per allocated cluster and a hole for
+/// each unallocated or zero one. `file_length` reports the virtual disk
+/// size from the header. An image with compressed clusters has no byte
+/// range to point at; its node carries no extents and cannot be read
+/// with `cat_node`.
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Qcow2, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    let mut disk = VirtualDisk::open(r)?;
+    let disk_size = disk.len();
 
     let mut root = TreeNode::new_directory("/".to_string());
 
-    // file_location = None: data is addressed via L1/L2 tables with
-    // copy-on-write semantics, not as a contiguous byte range.
-    let mut disk_node = TreeNode::new_file("disk.qcow2".to_string(), disk_size);
-    disk_node.file_length = Some(disk_size);
+    let disk_node = match disk.extents() {
+        Ok(extents) => {
+            TreeNode::new_file_with_extents("disk.qcow2".to_string(), disk_size, extents)
+        }
+        // A compressed cluster is not a byte range of the file.
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            let mut node = TreeNode::new_file("disk.qcow2".to_string(), disk_size);
+            node.file_length = Some(disk_size);
+            node
+        }
+        Err(e) => return Err(e.into()),
+    };
 
     root.add_child(disk_node);
     root.calculate_directory_size();
     Ok(root)
 }
 
+// ── Virtual disk reader ───────────────────────────────────────────────────────
+
+/// `Read + Seek` view of the virtual disk inside a QCOW2 image.
+///
+/// The L1 table is loaded by [`open`](Self::open); L2 tables are read on
+/// demand, one cached at a time. Clusters with no L1 or L2 mapping, and
+/// version-3 clusters flagged as zero, read back as zeros. An overlay
+/// that names a backing file is refused with [`Error::BackingFile`].
+/// Reading a compressed cluster fails with
+/// [`io::ErrorKind::Unsupported`].
+#[derive(Debug)]
+pub struct VirtualDisk<R> {
+    inner: R,
+    size: u64,
+    cluster_bits: u32,
+    l1: Vec<u64>,
+    /// The most recently used L2 table, keyed by L1 index.
+    cached_l2: Option<(usize, Vec<u64>)>,
+    pos: u64,
+}
+
+impl<R: Read + Seek> VirtualDisk<R> {
+    /// Parse the header and L1 table of `inner`.
+    pub fn open(mut inner: R) -> Result<Self, Error> {
+        inner.seek(SeekFrom::Start(0))?;
+        let header = read_header(&mut inner)?;
+
+        // The L1 table must fit in the file; this also bounds the
+        // allocation against a corrupt `l1_size`.
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        let l1_len = header.l1_size as u64 * 8;
+        if header
+            .l1_table_offset
+            .checked_add(l1_len)
+            .map_or(true, |end| end > file_len)
+        {
+            return Err(Error::BadL1Table);
+        }
+        let mut raw = vec![0u8; l1_len as usize];
+        inner.seek(SeekFrom::Start(header.l1_table_offset))?;
+        inner.read_exact(&mut raw)?;
+        let l1 = raw
+            .chunks_exact(8)
+            .map(|e| u64::from_be_bytes(e.try_into().unwrap()))
+            .collect();
+
+        Ok(Self {
+            inner,
+            size: header.disk_size,
+            cluster_bits: header.cluster_bits,
+            l1,
+            cached_l2: None,
+            pos: 0,
+        })
+    }
+
+    /// Virtual disk size in bytes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// `true` iff the virtual disk is zero bytes long.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Give back the wrapped QCOW2 file.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The runs of the virtual disk in the file: each allocated cluster,
+    /// and one hole for every stretch of zero or unallocated clusters.
+    /// Fails with [`io::ErrorKind::Unsupported`] on a compressed cluster.
+    fn extents(&mut self) -> io::Result<Vec<Extent>> {
+        let mut extents: Vec<Extent> = Vec::new();
+        let mut pos = 0;
+        while pos < self.size {
+            let (location, run) = self.map(pos)?;
+            let run = run.min(self.size - pos);
+            match (location, extents.last_mut()) {
+                (None, Some(last)) if last.location.is_none() => last.length += run,
+                (None, _) => extents.push(Extent::hole(run)),
+                (Some(at), _) => extents.push(Extent::data(at, run)),
+            }
+            pos += run;
+        }
+        Ok(extents)
+    }
+
+    /// Resolve virtual offset `pos` to a file offset (`None` for a zero
+    /// or unallocated cluster) and the number of bytes that map the same
+    /// way: the rest of the cluster, or of the L2 table's span when the
+    /// table itself is absent.
+    fn map(&mut self, pos: u64) -> io::Result<(Option<u64>, u64)> {
+        let cluster_size = 1u64 << self.cluster_bits;
+        let l2_entries = cluster_size / 8;
+        let cluster = pos >> self.cluster_bits;
+        let within = pos & (cluster_size - 1);
+        let run = cluster_size - within;
+        let l1_index = (cluster / l2_entries) as usize;
+        let l2_index = (cluster % l2_entries) as usize;
+
+        let l2_offset = match self.l1.get(l1_index) {
+            Some(&entry) if entry & OFFSET_MASK != 0 => entry & OFFSET_MASK,
+            _ => {
+                let span = cluster_size * l2_entries;
+                return Ok((None, span - (pos & (span - 1))));
+            }
+        };
+        if self.cached_l2.as_ref().map(|(i, _)| *i) != Some(l1_index) {
+            let mut raw = vec![0u8; cluster_size as usize];
+            self.inner.seek(SeekFrom::Start(l2_offset))?;
+            self.inner.read_exact(&mut raw)?;
+            let table = raw
+                .chunks_exact(8)
+                .map(|e| u64::from_be_bytes(e.try_into().unwrap()))
+                .collect();
+            self.cached_l2 = Some((l1_index, table));
+        }
+        let entry = self.cached_l2.as_ref().expect("just cached").1[l2_index];
+        if entry & L2_COMPRESSED != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "QCOW2 compressed clusters are not supported",
+            ));
+        }
+        match entry & OFFSET_MASK {
+            0 => Ok((None, run)),
+            _ if entry & L2_ZERO != 0 => Ok((None, run)),
+            offset => Ok((Some(offset + within), run)),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for VirtualDisk<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let (location, run) = self.map(self.pos)?;
+        let want = run.min(self.size - self.pos).min(buf.len() as u64) as usize;
+        let n = match location {
+            None => {
+                buf[..want].fill(0);
+                want
+            }
+            Some(at) => {
+                self.inner.seek(SeekFrom::Start(at))?;
+                let n = self.inner.read(&mut buf[..want])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "QCOW2 cluster lies past the end of the file",
+                    ));
+                }
+                n
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for VirtualDisk<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(d) => (self.size, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        self.pos = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
 // ── Unit tests ────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -304,6 +607,13 @@ mod tests {
         // [60] nb_snapshots = 0
         // [64] snapshots_offset = 0  (already zero)
 
+        // [72] incompatible_features = 0 (version 3 header is 104 bytes)
+        if version == 3 {
+            buf.resize(104, 0);
+            buf[96..100].copy_from_slice(&4u32.to_be_bytes()); // refcount_order
+            buf[100..104].copy_from_slice(&104u32.to_be_bytes()); // header_length
+        }
+
         buf
     }
 
@@ -313,7 +623,10 @@ mod tests {
         disk_size: u64,
         encryption_method: u32,
     ) -> Vec<u8> {
-        build_header(version, cluster_bits, disk_size, encryption_method)
+        // Room for the one-entry L1 table, left empty: nothing allocated.
+        let mut img = build_header(version, cluster_bits, disk_size, encryption_method);
+        img.resize(196608 + 8, 0);
+        img
     }
 
     // ── Detection tests ───────────────────────────────────────────────────
@@ -443,6 +756,7 @@ mod tests {
             disk.file_location, None,
             "QCOW2 disk.qcow2 must have file_location=None (L1/L2 indirection)"
         );
+        assert_eq!(disk.extents, vec![Extent::hole(DEFAULT_DISK_SIZE)]);
     }
 
     #[test]
@@ -574,4 +888,144 @@ mod tests {
         let mut c = Cursor::new(data);
         assert!(matches!(read_header(&mut c), Err(Error::BadMagic)));
     }
+
+    // ── VirtualDisk ───────────────────────────────────────────────────────
+
+    /// 4-cluster disk with 512-byte clusters (64 L2 entries per table).
+    /// L1 at 512 → L2 at 1024. Cluster 0 at 1536, cluster 1 unmapped,
+    /// cluster 2 at 2048, cluster 3 flagged zero (but pointing at data).
+    fn build_qcow2_with_clusters(version: u32) -> Vec<u8> {
+        let mut img = build_header(version, 9, 4 * 512, 0);
+        img[36..40].copy_from_slice(&1u32.to_be_bytes()); // l1_size
+        img[40..48].copy_from_slice(&512u64.to_be_bytes()); // l1_table_offset
+        img.resize(5 * 512, 0);
+        img[512..520].copy_from_slice(&(1024u64 | 1 << 63).to_be_bytes());
+        img[1024..1032].copy_from_slice(&(1536u64 | 1 << 63).to_be_bytes());
+        img[1040..1048].copy_from_slice(&2048u64.to_be_bytes());
+        img[1048..1056].copy_from_slice(&(2048u64 | L2_ZERO).to_be_bytes());
+        img[1536..1541].copy_from_slice(b"qcow!");
+        img[2048..2560].fill(0x77);
+        img
+    }
+
+    #[test]
+    fn virtual_disk_walks_l1_and_l2() {
+        let img = build_qcow2_with_clusters(3);
+        let mut disk = VirtualDisk::open(Cursor::new(img)).unwrap();
+        assert_eq!(disk.len(), 2048);
+        let mut out = Vec::new();
+        disk.read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 2048);
+        assert_eq!(&out[..5], b"qcow!");
+        assert!(out[512..1024].iter().all(|&b| b == 0), "unmapped cluster");
+        assert!(out[1024..1536].iter().all(|&b| b == 0x77));
+        assert!(out[1536..].iter().all(|&b| b == 0), "zero-flagged cluster");
+    }
+
+    #[test]
+    fn disk_node_maps_clusters() {
+        let img = build_qcow2_with_clusters(3);
+        let root = detect_and_parse(&mut Cursor::new(&img)).unwrap();
+        let node = &root.children[0];
+        assert_eq!(node.file_location, None);
+        assert_eq!(
+            node.extents,
+            vec![
+                Extent::data(1536, 512),
+                Extent::hole(512),
+                Extent::data(2048, 512),
+                Extent::hole(512),
+            ]
+        );
+
+        let mut through_node = Vec::new();
+        crate::cat_node(&mut Cursor::new(&img), node, &mut through_node).unwrap();
+        let mut through_disk = Vec::new();
+        VirtualDisk::open(Cursor::new(&img))
+            .unwrap()
+            .read_to_end(&mut through_disk)
+            .unwrap();
+        assert_eq!(through_node, through_disk);
+    }
+
+    #[test]
+    fn virtual_disk_compressed_cluster_is_unsupported() {
+        let mut img = build_qcow2_with_clusters(3);
+        img[1024..1032].copy_from_slice(&(1536u64 | L2_COMPRESSED).to_be_bytes());
+        let mut disk = VirtualDisk::open(Cursor::new(img.clone())).unwrap();
+        let mut buf = [0u8; 8];
+        let err = disk.read_exact(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        // The tree still lists the disk, without extents to read it by.
+        let root = detect_and_parse(&mut Cursor::new(img)).unwrap();
+        let node = &root.children[0];
+        assert_eq!(node.file_length, Some(2048));
+        assert!(node.extents.is_empty());
+        assert!(node.data_extents().is_none());
+    }
+
+    #[test]
+    fn virtual_disk_rejects_l1_past_eof() {
+        let mut img = build_qcow2_with_clusters(2);
+        img[36..40].copy_from_slice(&1_000_000u32.to_be_bytes());
+        assert!(matches!(
+            VirtualDisk::open(Cursor::new(img)),
+            Err(Error::BadL1Table)
+        ));
+    }
+
+    #[test]
+    fn incompatible_features_are_unsupported() {
+        for bit in 1..5 {
+            let mut img = build_qcow2_with_clusters(3);
+            img[72..80].copy_from_slice(&(1u64 << bit).to_be_bytes());
+            let err = VirtualDisk::open(Cursor::new(img)).unwrap_err();
+            assert!(matches!(err, Error::UnsupportedFeatures(b) if b == 1 << bit));
+            assert!(matches!(
+                crate::Error::from(err),
+                crate::Error::Unsupported { .. }
+            ));
+        }
+        // The dirty bit alone is accepted, but not alongside another bit.
+        let mut img = build_qcow2_with_clusters(3);
+        img[72..80].copy_from_slice(&FEATURE_DIRTY.to_be_bytes());
+        let mut disk = VirtualDisk::open(Cursor::new(img.clone())).unwrap();
+        let mut out = Vec::new();
+        disk.read_to_end(&mut out).unwrap();
+        assert_eq!(&out[..5], b"qcow!");
+        assert!(detect_and_parse(&mut Cursor::new(img.clone())).is_ok());
+        img[72..80].copy_from_slice(&(FEATURE_DIRTY | 2).to_be_bytes());
+        assert!(matches!(
+            VirtualDisk::open(Cursor::new(img)),
+            Err(Error::UnsupportedFeatures(3))
+        ));
+        // Version 2 has no such field: the bytes are left alone.
+        let mut img = build_qcow2_with_clusters(2);
+        img[72..80].copy_from_slice(&2u64.to_be_bytes());
+        assert!(VirtualDisk::open(Cursor::new(img)).is_ok());
+    }
+
+    #[test]
+    fn backing_file_is_unsupported() {
+        let mut img = build_qcow2_with_clusters(3);
+        img[8..16].copy_from_slice(&2048u64.to_be_bytes());
+        img[16..20].copy_from_slice(&8u32.to_be_bytes());
+        let err = VirtualDisk::open(Cursor::new(img.clone())).unwrap_err();
+        assert!(matches!(err, Error::BackingFile));
+        assert!(matches!(
+            crate::Error::from(err),
+            crate::Error::Unsupported { .. }
+        ));
+        assert!(matches!(
+            detect_and_parse(&mut Cursor::new(img)),
+            Err(Error::BackingFile)
+        ));
+    }
+
+    #[test]
+    fn error_display_bad_l1_table() {
+        let msg = format!("{}", Error::BadL1Table);
+        assert!(msg.contains("L1"), "got: {msg}");
+    }
 }
@@ -7,14 +7,16 @@
 //! MBR, and finally returns a single-partition tree representing the
 //! whole image.
 //!
-//! This module deliberately does **not** recurse into the filesystems
-//! that live inside each partition — that requires per-FS readers
-//! (FAT, NTFS, ext, …) which are tracked under their own feature
-//! flags. For now, callers get a `TreeNode` whose children point at
-//! the partition byte ranges; `cat_node` will hand them the raw
-//! partition contents.
+//! [`detect_and_parse`] stops at the partition table: callers get a
+//! `TreeNode` whose children point at the partition byte ranges, and
+//! `cat_node` hands them the raw partition contents.
+//! [`detect_and_parse_recursive`] goes one step further and parses the
+//! filesystem inside each partition (or inside the whole image, when
+//! there is no partition table) with whichever filesystem readers are
+//! enabled, so `/partition-0-type-83/etc/fstab` resolves straight from
+//! a `dd` dump.
 
-use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 
 use crate::formats::{gpt, mbr};
 use crate::tree::TreeNode;
@@ -57,14 +59,29 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        let message = e.to_string();
+        match e {
+            Error::NoPartitionTable => crate::Error::NotRecognized {
+                format: None,
+                message,
+            },
+            Error::Mbr(inner) => crate::Error::from(inner).with_message(message),
+            Error::Gpt(inner) => crate::Error::from(inner).with_message(message),
+            Error::Io(e) => e.into(),
+        }
+    }
+}
+
 /// Try GPT, then MBR. If both fail, returns a TreeNode whose only
 /// child is the whole image, named `"image"`. This is the lenient
 /// behaviour `cat_node` consumers usually want.
 ///
-/// The `image` child's `file_length` is the file's actual size on
-/// disk, looked up via `Metadata::len()`. If the metadata query
-/// fails, a length-0 child is emitted and `cat_node` will refuse it.
-pub fn detect_and_parse(file: &mut File) -> Result<TreeNode, Error> {
+/// The `image` child's `file_length` is the stream's length, found by
+/// seeking to its end. If that seek fails, a length-0 child is emitted
+/// and `cat_node` will refuse it.
+pub fn detect_and_parse<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>, Error> {
     // GPT first: a GPT disk has a protective MBR that would otherwise
     // get reported as "one weird partition".
     match gpt::detect_and_parse(file) {
@@ -84,7 +101,7 @@ pub fn detect_and_parse(file: &mut File) -> Result<TreeNode, Error> {
     }
 
     // No partition table — treat the file as a single anonymous blob.
-    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let size = file.seek(SeekFrom::End(0)).unwrap_or(0);
     let mut root = TreeNode::new_directory("/".to_string());
     let child = if size == 0 {
         TreeNode::new_file("image".to_string(), 0)
@@ -96,10 +113,38 @@ pub fn detect_and_parse(file: &mut File) -> Result<TreeNode, Error> {
     Ok(root)
 }
 
+/// Like [`detect_and_parse`], but also parses the filesystem inside each
+/// partition, or inside the `image` child of an unpartitioned image.
+///
+/// A partition holding a recognised filesystem becomes a directory of
+/// the same name containing its tree, with every location translated to
+/// an absolute offset in `file`, so `cat_node` reads straight from the
+/// disk image. Partitions that are not recognised, or whose filesystem
+/// fails to parse, stay raw byte ranges.
+///
+/// A boot sector with an empty partition table — which is what a bare
+/// FAT or NTFS volume looks like to the MBR reader — is parsed as a
+/// filesystem spanning the whole image, whose tree is returned directly.
+pub fn detect_and_parse_recursive<R: Read + Seek>(
+    file: &mut R,
+) -> Result<TreeNode<'static>, Error> {
+    let mut root = detect_and_parse(file)?;
+    if root.children.is_empty() {
+        let size = file.seek(SeekFrom::End(0))?;
+        if let Ok((fs, _)) = crate::probe::open_filesystem_at(file, 0, size) {
+            return Ok(fs);
+        }
+        return Ok(root);
+    }
+    crate::probe::descend_into_partitions(file, &mut root);
+    Ok(root)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::{Seek, SeekFrom, Write};
+    use std::fs::File;
+    use std::io::Write;
 
     fn scratch(bytes: &[u8], tag: &str) -> std::path::PathBuf {
         let dir = std::env::temp_dir();
@@ -296,4 +341,89 @@ mod tests {
         assert_eq!(tree.children[0].size, (65 - 34 + 1) * 512);
         std::fs::remove_file(&path).ok();
     }
+
+    // ── Recursive descent ─────────────────────────────────────────────────────
+
+    /// Six-sector FAT12 volume with one file, `README.TXT` = "hello world\n",
+    /// in cluster 2 (sector 4).
+    #[cfg(feature = "fat")]
+    fn fat12_volume() -> Vec<u8> {
+        let mut v = vec![0u8; 512 * 6];
+        v[11..13].copy_from_slice(&512u16.to_le_bytes()); // bytes_per_sector
+        v[13] = 1; // sectors_per_cluster
+        v[14..16].copy_from_slice(&1u16.to_le_bytes()); // reserved_sectors
+        v[16] = 2; // num_fats
+        v[17..19].copy_from_slice(&16u16.to_le_bytes()); // root_entry_count
+        v[19..21].copy_from_slice(&6u16.to_le_bytes()); // total_sectors_16
+        v[21] = 0xF8; // media_type
+        v[22..24].copy_from_slice(&1u16.to_le_bytes()); // fat_size_16
+        v[510] = 0x55;
+        v[511] = 0xAA;
+        for fat in [512, 1024] {
+            v[fat..fat + 5].copy_from_slice(&[0xF8, 0xFF, 0xFF, 0xFF, 0x0F]);
+        }
+        let rd = 512 * 3;
+        v[rd..rd + 11].copy_from_slice(b"README  TXT");
+        v[rd + 11] = 0x20; // ATTR_ARCHIVE
+        v[rd + 26..rd + 28].copy_from_slice(&2u16.to_le_bytes()); // first cluster
+        v[rd + 28..rd + 32].copy_from_slice(&12u32.to_le_bytes()); // size
+        v[512 * 4..512 * 4 + 12].copy_from_slice(b"hello world\n");
+        v
+    }
+
+    #[cfg(feature = "fat")]
+    #[test]
+    fn recursive_descends_into_mbr_partition() {
+        // Partition 0: FAT12 at LBA 8. Partition 1: unrecognised bytes.
+        let mut img = vec![0u8; 64 * 512];
+        img[0x1FE] = 0x55;
+        img[0x1FF] = 0xAA;
+        for (slot, (type_code, lba, count)) in [(0x01u8, 8u32, 6u32), (0x83, 32, 8)]
+            .into_iter()
+            .enumerate()
+        {
+            let off = 0x1BE + 16 * slot;
+            img[off + 4] = type_code;
+            img[off + 8..off + 12].copy_from_slice(&lba.to_le_bytes());
+            img[off + 12..off + 16].copy_from_slice(&count.to_le_bytes());
+        }
+        img[8 * 512..14 * 512].copy_from_slice(&fat12_volume());
+
+        let mut cur = std::io::Cursor::new(img);
+        let tree = detect_and_parse_recursive(&mut cur).unwrap();
+
+        let part = tree.find_node("partition-0-type-01").unwrap();
+        assert!(part.is_directory);
+        let file = tree
+            .find_node("partition-0-type-01/README.TXT")
+            .expect("file inside the partition");
+        assert_eq!(file.file_location, Some((8 + 4) * 512));
+        let mut out = Vec::new();
+        crate::cat_node(&mut cur, file, &mut out).unwrap();
+        assert_eq!(out, b"hello world\n");
+
+        let raw = tree.find_node("partition-1-type-83").unwrap();
+        assert!(!raw.is_directory);
+        assert_eq!(raw.file_location, Some(32 * 512));
+        assert_eq!(tree.size, 12 + 8 * 512);
+    }
+
+    #[cfg(feature = "fat")]
+    #[test]
+    fn recursive_parses_unpartitioned_volume() {
+        let mut cur = std::io::Cursor::new(fat12_volume());
+        assert!(detect_and_parse(&mut cur).unwrap().children.is_empty());
+        let tree = detect_and_parse_recursive(&mut cur).unwrap();
+        let file = tree.find_node("README.TXT").unwrap();
+        assert_eq!(file.file_location, Some(4 * 512));
+    }
+
+    #[test]
+    fn recursive_leaves_unrecognised_image_alone() {
+        let mut cur = std::io::Cursor::new(vec![0u8; 4096]);
+        let tree = detect_and_parse_recursive(&mut cur).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].name, "image");
+        assert!(!tree.children[0].is_directory);
+    }
 }
@@ -22,6 +22,13 @@
 //! the byte count) followed by the raw data. We refuse to decompress;
 //! if bit 15 is clear we return [`Error::Compressed`].
 //!
+//! ## Metadata
+//!
+//! Every inode's 16-byte common header carries permissions, uid/gid
+//! indices into the ID table, and an mtime; these become the node's
+//! [`Metadata`]. The ID table is read best-effort:
+//! if it is missing or compressed, owners are left unset.
+//!
 //! ## Depth limit
 //!
 //! The directory recursion is bounded at 64 levels to prevent
@@ -53,7 +60,11 @@
 
 use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem};
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Metadata, Timestamp, TreeNode};
 
 const MAGIC_LE: u32 = 0x7371_7368;
 const MAGIC_BE: u32 = 0x6873_7173;
@@ -79,6 +90,11 @@ const INODE_LDIR: u16 = 8;
 const INODE_LREG: u16 = 9;
 const INODE_LSYMLINK: u16 = 10;
 
+/// Longest symlink target we will decode.
+const MAX_SYMLINK_LEN: usize = 4096;
+/// IDs per ID-table metadata block (8 KiB of u32s).
+const IDS_PER_BLOCK: usize = 2048;
+
 /// Parse errors for the SquashFS reader.
 #[derive(Debug)]
 pub enum Error {
@@ -133,7 +149,19 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Squashfs, e),
+            Error::BadVersion | Error::Compressed => crate::Error::unsupported(Format::Squashfs, e),
+            e => crate::Error::corrupt(Format::Squashfs, e),
+        }
+    }
+}
+
 /// Parsed SquashFS superblock (96 bytes at offset 0).
+#[derive(Debug)]
 struct Superblock {
     block_size: u32,
     flags: u16,
@@ -141,6 +169,8 @@ struct Superblock {
     root_inode: u64,
     inode_table_start: u64,
     directory_table_start: u64,
+    id_table_start: u64,
+    id_count: u16,
 }
 
 impl Superblock {
@@ -191,9 +221,11 @@ impl Superblock {
         }
 
         let flags = u16_at(24);
+        let id_count = u16_at(26);
         let version_major = u16_at(28);
         let version_minor = u16_at(30);
         let root_inode = u64_at(32);
+        let id_table_start = u64_at(48);
         let inode_table_start = u64_at(64);
         let directory_table_start = u64_at(72);
 
@@ -207,6 +239,8 @@ impl Superblock {
             root_inode,
             inode_table_start,
             directory_table_start,
+            id_table_start,
+            id_count,
         })
     }
 
@@ -267,6 +301,56 @@ fn read_inode_block<R: Read + Seek>(
     seek_to_metadata_block(r, inode_table_start, block_idx)
 }
 
+/// Read the ID table: a list of u64 pointers to metadata blocks, each
+/// holding up to 2048 little-endian u32 uid/gid values.
+fn read_id_table<R: Read + Seek>(r: &mut R, sb: &Superblock) -> Result<Vec<u32>, Error> {
+    let count = sb.id_count as usize;
+    let mut pointers = vec![0u8; count.div_ceil(IDS_PER_BLOCK) * 8];
+    r.seek(SeekFrom::Start(sb.id_table_start))?;
+    r.read_exact(&mut pointers)?;
+
+    let mut ids = Vec::with_capacity(count);
+    for ptr in pointers.chunks_exact(8) {
+        r.seek(SeekFrom::Start(u64::from_le_bytes(ptr.try_into().unwrap())))?;
+        let block = read_metadata_block(r)?;
+        ids.extend(
+            block
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap())),
+        );
+    }
+    ids.truncate(count);
+    Ok(ids)
+}
+
+/// Map an inode type (basic or extended) to its entry kind.
+fn entry_kind(inode_type: u16) -> EntryKind {
+    match inode_type {
+        INODE_DIR | INODE_LDIR => EntryKind::Directory,
+        INODE_SYMLINK | INODE_LSYMLINK => EntryKind::Symlink,
+        4 | 11 => EntryKind::BlockDevice,
+        5 | 12 => EntryKind::CharDevice,
+        6 | 13 => EntryKind::Fifo,
+        7 | 14 => EntryKind::Socket,
+        _ => EntryKind::File,
+    }
+}
+
+/// Decode the inode common header: type(u16) permissions(u16)
+/// uid_idx(u16) gid_idx(u16) mtime(u32) inode_number(u32).
+fn header_metadata(header: &[u8], ids: &[u32]) -> Metadata {
+    let u16le = |off: usize| u16::from_le_bytes([header[off], header[off + 1]]);
+    let mtime = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    Metadata {
+        kind: entry_kind(u16le(0)),
+        mode: Some(u16le(2) as u32 & 0o7777),
+        uid: ids.get(u16le(4) as usize).copied(),
+        gid: ids.get(u16le(6) as usize).copied(),
+        mtime: Some(Timestamp::new(mtime as i64, 0)),
+        ..Metadata::default()
+    }
+}
+
 /// One parsed inode, normalized across basic and extended variants.
 struct Inode {
     inode_type: u16,
@@ -274,6 +358,8 @@ struct Inode {
     dir_info: Option<(u32, u16, u32)>,
     /// For regular files: (absolute byte offset in image, file_size, block_sizes, fragment_idx).
     reg_info: Option<(u64, u64, Vec<u32>, u32)>,
+    /// For symlinks: the target path, if it fits in the inode body.
+    symlink_target: Option<String>,
 }
 
 /// Parse the type-specific body of an inode (the bytes after the 16-byte common header).
@@ -316,6 +402,7 @@ fn parse_inode_body(body: &[u8], inode_type: u16, block_size: u32) -> Result<Ino
                 inode_type,
                 dir_info: Some((start_block, offset, file_size)),
                 reg_info: None,
+                symlink_target: None,
             })
         }
         INODE_LDIR => {
@@ -329,6 +416,7 @@ fn parse_inode_body(body: &[u8], inode_type: u16, block_size: u32) -> Result<Ino
                 inode_type,
                 dir_info: Some((start_block, offset, file_size)),
                 reg_info: None,
+                symlink_target: None,
             })
         }
         INODE_REG => {
@@ -345,6 +433,7 @@ fn parse_inode_body(body: &[u8], inode_type: u16, block_size: u32) -> Result<Ino
                 inode_type,
                 dir_info: None,
                 reg_info: Some((start_block, file_size, block_sizes, fragment)),
+                symlink_target: None,
             })
         }
         INODE_LREG => {
@@ -361,13 +450,30 @@ fn parse_inode_body(body: &[u8], inode_type: u16, block_size: u32) -> Result<Ino
                 inode_type,
                 dir_info: None,
                 reg_info: Some((start_block, file_size, block_sizes, fragment)),
+                symlink_target: None,
+            })
+        }
+        INODE_SYMLINK | INODE_LSYMLINK => {
+            // nlink(u32) target_size(u32) target[target_size]; an oversized or
+            // truncated target is dropped rather than failing the whole parse.
+            let symlink_target = (body.len() >= 8)
+                .then(|| u32le(4) as usize)
+                .filter(|&len| len <= MAX_SYMLINK_LEN)
+                .and_then(|len| body.get(8..8 + len))
+                .map(|target| String::from_utf8_lossy(target).into_owned());
+            Ok(Inode {
+                inode_type,
+                dir_info: None,
+                reg_info: None,
+                symlink_target,
             })
         }
-        // Symlinks and everything else (device, fifo, socket) — no data location.
+        // Everything else (device, fifo, socket) — no data location.
         _ => Ok(Inode {
             inode_type,
             dir_info: None,
             reg_info: None,
+            symlink_target: None,
         }),
     }
 }
@@ -398,15 +504,16 @@ fn file_location_for_reg(start_block: u64, block_sizes: &[u32], fragment: u32) -
     Some(start_block)
 }
 
-/// Read and parse the inode at `(block_idx, offset)` from the inode table.
+/// Read and parse the inode at `(block_idx, offset)` from the inode table,
+/// along with the metadata in its common header.
 fn read_and_parse_inode<R: Read + Seek>(
     r: &mut R,
-    inode_table_start: u64,
+    sb: &Superblock,
+    ids: &[u32],
     block_idx: u64,
     offset: u16,
-    block_size: u32,
-) -> Result<Inode, Error> {
-    let block_data = read_inode_block(r, inode_table_start, block_idx)?;
+) -> Result<(Inode, Metadata), Error> {
+    let block_data = read_inode_block(r, sb.inode_table_start, block_idx)?;
     let off = offset as usize;
     if block_data.len() < off + 16 {
         return Err(Error::Io(io::Error::new(
@@ -415,19 +522,31 @@ fn read_and_parse_inode<R: Read + Seek>(
         )));
     }
     let inode_type = u16::from_le_bytes([block_data[off], block_data[off + 1]]);
+    let mut metadata = header_metadata(&block_data[off..off + 16], ids);
     let body = &block_data[off + 16..];
-    parse_inode_body(body, inode_type, block_size)
+    let inode = parse_inode_body(body, inode_type, sb.block_size)?;
+    metadata.link_target = inode.symlink_target.clone();
+    Ok((inode, metadata))
 }
 
-/// Parse a directory table region and return a list of
-/// `(entry_name, inode_block_idx, inode_offset_within_block)`.
+/// One directory listing entry: the child's name, where its inode lives,
+/// and the basic inode type the directory records for it.
+struct DirRef {
+    name: String,
+    block_idx: u64,
+    offset: u16,
+    inode_type: u16,
+}
+
+/// Parse a directory table region and return its entries, without `.`
+/// and `..`.
 fn parse_directory<R: Read + Seek>(
     r: &mut R,
     directory_table_start: u64,
     dir_start_block: u32,
     dir_offset: u16,
     dir_file_size: u32,
-) -> Result<Vec<(String, u64, u16)>, Error> {
+) -> Result<Vec<DirRef>, Error> {
     let block_data = seek_to_metadata_block(r, directory_table_start, dir_start_block as u64)?;
 
     let off = dir_offset as usize;
@@ -443,7 +562,7 @@ fn parse_directory<R: Read + Seek>(
     let total = (dir_file_size as usize).min(available);
     let dir_bytes = &block_data[off..off + total];
 
-    let mut entries: Vec<(String, u64, u16)> = Vec::new();
+    let mut entries: Vec<DirRef> = Vec::new();
     let mut pos = 0usize;
 
     // Directory listing: sequence of (12-byte header + (count+1) 8-byte-plus-name entries).
@@ -475,7 +594,9 @@ fn parse_directory<R: Read + Seek>(
             let entry_inode_offset = u16::from_le_bytes([dir_bytes[pos], dir_bytes[pos + 1]]);
             // s16 inode_offset at [2..4] — used only for relative inode number; we
             // navigate by block_idx + entry_offset directly so we skip it.
-            // u16 type at [4..6] — we re-read the inode header for the authoritative type.
+            // u16 type at [4..6] — the basic inode type. The eager parser re-reads
+            // the inode header for the authoritative type; the lazy one trusts it.
+            let inode_type = u16::from_le_bytes([dir_bytes[pos + 4], dir_bytes[pos + 5]]);
             let name_size =
                 u16::from_le_bytes([dir_bytes[pos + 6], dir_bytes[pos + 7]]) as usize + 1;
             pos += 8;
@@ -490,7 +611,12 @@ fn parse_directory<R: Read + Seek>(
                 continue;
             }
 
-            entries.push((name, header_start_block as u64, entry_inode_offset));
+            entries.push(DirRef {
+                name,
+                block_idx: header_start_block as u64,
+                offset: entry_inode_offset,
+                inode_type,
+            });
         }
     }
 
@@ -501,64 +627,85 @@ fn parse_directory<R: Read + Seek>(
 fn build_tree<R: Read + Seek>(
     r: &mut R,
     sb: &Superblock,
+    ids: &[u32],
     name: String,
     block_idx: u64,
     offset: u16,
     depth: usize,
-) -> Result<TreeNode, Error> {
+) -> Result<TreeNode<'static>, Error> {
     if depth > MAX_DEPTH {
         return Err(Error::TooDeep);
     }
 
-    let inode = read_and_parse_inode(r, sb.inode_table_start, block_idx, offset, sb.block_size)?;
+    let (inode, metadata) = read_and_parse_inode(r, sb, ids, block_idx, offset)?;
 
     match inode.inode_type {
         INODE_DIR | INODE_LDIR => {
-            let (dir_start_block, dir_offset, dir_file_size) =
-                inode.dir_info.expect("dir_info always set for dir inodes");
-
-            let mut node = TreeNode::new_directory(name);
-            let child_refs = parse_directory(
-                r,
-                sb.directory_table_start,
-                dir_start_block,
-                dir_offset,
-                dir_file_size,
-            )?;
-
-            for (child_name, child_block_idx, child_inode_offset) in child_refs {
-                let child = build_tree(
+            let _level = limits::enter(block_idx << 16 | offset as u64)?;
+            let mut node = TreeNode::new_directory(name).with_metadata(metadata);
+            let children = read_children(r, sb, &inode)?;
+            limits::dir_entries(children.len() as u64)?;
+            for child_ref in children {
+                limits::entry(&child_ref.name)?;
+                // The compressed metadata block holding the child's inode.
+                let block = sb.inode_table_start + child_ref.block_idx;
+                match build_tree(
                     r,
                     sb,
-                    child_name,
-                    child_block_idx,
-                    child_inode_offset,
+                    ids,
+                    child_ref.name,
+                    child_ref.block_idx,
+                    child_ref.offset,
                     depth + 1,
-                )?;
-                node.add_child(child);
+                ) {
+                    Ok(child) => node.add_child(child),
+                    Err(e) => recover::skip(Some(block), "inode", e)?,
+                }
             }
             Ok(node)
         }
+        _ => Ok(inode_node(name, inode, metadata)),
+    }
+}
+
+/// List the directory table entries of a directory inode.
+fn read_children<R: Read + Seek>(
+    r: &mut R,
+    sb: &Superblock,
+    inode: &Inode,
+) -> Result<Vec<DirRef>, Error> {
+    let (dir_start_block, dir_offset, dir_file_size) =
+        inode.dir_info.expect("dir_info always set for dir inodes");
+    parse_directory(
+        r,
+        sb.directory_table_start,
+        dir_start_block,
+        dir_offset,
+        dir_file_size,
+    )
+}
+
+/// Build the childless node for a parsed inode.
+fn inode_node(name: String, inode: Inode, metadata: Metadata) -> TreeNode<'static> {
+    match inode.inode_type {
+        INODE_DIR | INODE_LDIR => TreeNode::new_directory(name).with_metadata(metadata),
         INODE_REG | INODE_LREG => {
             let (start_block, file_size, block_sizes, fragment) =
                 inode.reg_info.expect("reg_info always set for reg inodes");
 
             let location = file_location_for_reg(start_block, &block_sizes, fragment);
-            if let Some(loc) = location {
-                Ok(TreeNode::new_file_with_location(
-                    name, file_size, loc, file_size,
-                ))
+            let node = if let Some(loc) = location {
+                TreeNode::new_file_with_location(name, file_size, loc, file_size)
             } else {
                 let mut node = TreeNode::new_file(name, file_size);
                 node.file_length = Some(file_size);
-                Ok(node)
-            }
+                node
+            };
+            node.with_metadata(metadata)
         }
-        INODE_SYMLINK | INODE_LSYMLINK | 4..=7 | 11..=14 => {
-            // Symlinks, device nodes, FIFOs, sockets — zero-size, no location.
-            Ok(TreeNode::new_file(name, 0))
-        }
-        _ => Ok(TreeNode::new_file(name, 0)),
+        // Symlinks, device nodes, FIFOs, sockets (and unknown types) —
+        // zero-size, no location; the kind lives in the metadata.
+        _ => TreeNode::new_file(name, 0).with_metadata(metadata),
     }
 }
 
@@ -594,7 +741,11 @@ fn detect_inner<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 ///
 /// Only images with both `UNCOMPRESSED_INODES` and `UNCOMPRESSED_DATA`
 /// flags set are supported. Compressed images return [`Error::Compressed`].
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Squashfs, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
     let sb = Superblock::read(r)?;
 
     if !sb.is_inodes_uncompressed() || !sb.is_data_uncompressed() {
@@ -605,11 +756,92 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
     let root_block_idx = sb.root_inode >> 16;
     let root_offset = (sb.root_inode & 0xFFFF) as u16;
 
-    let mut root = build_tree(r, &sb, "/".to_string(), root_block_idx, root_offset, 0)?;
+    // Owners are best-effort: a missing or compressed ID table leaves them unset.
+    let ids = read_id_table(r, &sb).unwrap_or_default();
+    let mut root = build_tree(
+        r,
+        &sb,
+        &ids,
+        "/".to_string(),
+        root_block_idx,
+        root_offset,
+        0,
+    )?;
     root.calculate_directory_size();
     Ok(root)
 }
 
+/// A SquashFS image opened for lazy, per-directory reads.
+///
+/// Only the superblock and ID table are read up front. Handles are inode
+/// references (`block_idx << 16 | offset`); listing a directory reads its
+/// directory table entries but none of the child inodes. The same
+/// restriction as [`detect_and_parse`] applies: inodes and data must be
+/// stored uncompressed.
+#[derive(Debug)]
+pub struct SquashFs<R> {
+    image: R,
+    sb: Superblock,
+    ids: Vec<u32>,
+}
+
+impl<R: Read + Seek> SquashFs<R> {
+    /// Read the superblock and ID table of `image`.
+    pub fn open(mut image: R) -> Result<Self, Error> {
+        let sb = Superblock::read(&mut image)?;
+        if !sb.is_inodes_uncompressed() || !sb.is_data_uncompressed() {
+            return Err(Error::Compressed);
+        }
+        let ids = read_id_table(&mut image, &sb).unwrap_or_default();
+        Ok(Self { image, sb, ids })
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    fn inode(&mut self, entry: &DirEntry) -> Result<(Inode, Metadata), Error> {
+        let (block_idx, offset) = (entry.id >> 16, (entry.id & 0xFFFF) as u16);
+        read_and_parse_inode(&mut self.image, &self.sb, &self.ids, block_idx, offset)
+    }
+}
+
+impl<R: Read + Seek> Filesystem for SquashFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Squashfs
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, self.sb.root_inode)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> crate::Result<Vec<DirEntry>> {
+        let (inode, _) = self.inode(dir)?;
+        if inode.dir_info.is_none() {
+            return Err(not_a_directory(dir));
+        }
+        Ok(read_children(&mut self.image, &self.sb, &inode)?
+            .into_iter()
+            .map(|c| {
+                let is_directory = matches!(c.inode_type, INODE_DIR | INODE_LDIR);
+                DirEntry::new(c.name, is_directory, c.block_idx << 16 | c.offset as u64)
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> crate::Result<TreeNode<'static>> {
+        let (inode, metadata) = self.inode(entry)?;
+        Ok(inode_node(entry.name.clone(), inode, metadata))
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Unit tests using an in-memory SquashFS image builder
 // ---------------------------------------------------------------------------
@@ -758,7 +990,7 @@ mod tests {
         image
     }
 
-    fn parse_image(image: &[u8]) -> TreeNode {
+    fn parse_image(image: &[u8]) -> TreeNode<'static> {
         let mut c = Cursor::new(image);
         detect_and_parse(&mut c).expect("detect_and_parse failed")
     }
@@ -1125,7 +1357,16 @@ mod tests {
         let mut data = vec![0x08u8, 0x80]; // header: uncompressed, size=8
         data.extend_from_slice(&[0u8; 8]); // 8-byte content (< 16 needed for common header)
         let mut c = Cursor::new(&data);
-        let result = read_and_parse_inode(&mut c, 0, 0, 0, 4096);
+        let sb = Superblock {
+            block_size: 4096,
+            flags: 0,
+            root_inode: 0,
+            inode_table_start: 0,
+            directory_table_start: 0,
+            id_table_start: 0,
+            id_count: 0,
+        };
+        let result = read_and_parse_inode(&mut c, &sb, &[], 0, 0);
         assert!(matches!(result, Err(Error::Io(_))));
     }
 
@@ -1217,7 +1458,7 @@ mod tests {
         let img = build_image("f.txt", b"x");
         let mut c = Cursor::new(&img);
         let sb = Superblock::read(&mut c).unwrap();
-        let result = build_tree(&mut c, &sb, "x".to_string(), 0, 0, MAX_DEPTH + 1);
+        let result = build_tree(&mut c, &sb, &[], "x".to_string(), 0, 0, MAX_DEPTH + 1);
         assert!(matches!(result, Err(Error::TooDeep)));
     }
 
@@ -1256,6 +1497,53 @@ mod tests {
         assert_eq!(child.size, 0);
     }
 
+    #[test]
+    fn symlink_inode_carries_target() {
+        // Rewrite the file inode body as nlink(u32) target_size(u32) target.
+        let mut img = build_image("link", b"target");
+        img[130..132].copy_from_slice(&INODE_SYMLINK.to_le_bytes());
+        img[150..154].copy_from_slice(&6u32.to_le_bytes());
+        img[154..160].copy_from_slice(b"../etc");
+        let tree = parse_image(&img);
+        let child = &tree.children[0];
+        assert_eq!(child.metadata.kind, EntryKind::Symlink);
+        assert_eq!(child.metadata.link_target.as_deref(), Some("../etc"));
+    }
+
+    #[test]
+    fn inode_header_metadata_resolves_ids() {
+        let mut img = build_image("f.txt", b"x");
+        // Append an uncompressed ID block holding [0, 1000] and a pointer to it.
+        let id_block = img.len() as u64;
+        img.extend_from_slice(&0x8008u16.to_le_bytes());
+        img.extend_from_slice(&0u32.to_le_bytes());
+        img.extend_from_slice(&1000u32.to_le_bytes());
+        let id_table = img.len() as u64;
+        img.extend_from_slice(&id_block.to_le_bytes());
+        img[26..28].copy_from_slice(&2u16.to_le_bytes());
+        img[48..56].copy_from_slice(&id_table.to_le_bytes());
+        // File inode: uid_idx=1, gid_idx=0, mtime=2000-01-01.
+        img[134..136].copy_from_slice(&1u16.to_le_bytes());
+        img[138..142].copy_from_slice(&946_684_800u32.to_le_bytes());
+
+        let tree = parse_image(&img);
+        assert_eq!(tree.metadata.kind, EntryKind::Directory);
+        assert_eq!(tree.metadata.mode, Some(0o755));
+        let m = &tree.children[0].metadata;
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!(m.mode, Some(0o644));
+        assert_eq!((m.uid, m.gid), (Some(1000), Some(0)));
+        assert_eq!(m.mtime, Some(Timestamp::new(946_684_800, 0)));
+    }
+
+    #[test]
+    fn missing_id_table_leaves_owner_unset() {
+        let tree = parse_image(&build_image("f.txt", b"x"));
+        let m = &tree.children[0].metadata;
+        assert_eq!((m.uid, m.gid), (None, None));
+        assert_eq!(m.mode, Some(0o644));
+    }
+
     #[test]
     fn unknown_inode_type_returns_zero_size_file() {
         // Patch file inode type to 20 → falls into catch-all → zero-size file.
@@ -1327,4 +1615,33 @@ mod tests {
         let mut c = Cursor::new(data);
         assert!(matches!(detect_inner(&mut c), Err(Error::BadMagic)));
     }
+
+    // ── SquashFs ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn squash_fs_lookup_and_read() {
+        let img = build_image("hello.txt", b"hello, squashfs");
+        let mut fs = SquashFs::open(Cursor::new(&img)).unwrap();
+        let root = fs.root();
+        let entries = fs.read_dir(&root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].is_directory);
+
+        let file = fs.lookup("hello.txt").unwrap().unwrap();
+        let mut out = Vec::new();
+        fs.open(&file).unwrap().read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"hello, squashfs");
+        assert!(fs.read_dir(&file).is_err());
+    }
+
+    #[test]
+    fn squash_fs_to_tree_matches_detect_and_parse() {
+        let img = build_image("f.txt", b"data");
+        let eager = parse_image(&img);
+        let lazy = SquashFs::open(Cursor::new(&img))
+            .unwrap()
+            .to_tree()
+            .unwrap();
+        assert_eq!(format!("{:?}", lazy), format!("{:?}", eager));
+    }
 }
@@ -9,12 +9,13 @@
 //!
 //! - Magic detection: `ustar\0` (POSIX) and `ustar  \0` (GNU).
 //! - Header parsing: name, size, typeflag (regular file, hard link,
-//!   symbolic link, directory).
+//!   symbolic link, directory, character/block device, FIFO), plus mode,
+//!   uid, gid, mtime and link name as [`Metadata`].
 //! - GNU long-name extension (type `L`): up to 64 KiB long filenames.
-//! - GNU long-link extension (type `K`): long symlink targets (parsed but
-//!   symlinks appear as zero-byte files in the tree).
-//! - PAX extended header (type `x` / `g`): `path` and `size` overrides
-//!   applied before the next entry. Other PAX keys are ignored.
+//! - GNU long-link extension (type `K`): long symlink / hard-link targets.
+//! - PAX extended header (type `x` / `g`): `path`, `size`, `linkpath`,
+//!   `mtime`, `uid` and `gid` overrides applied before the next entry.
+//!   Other PAX keys are ignored.
 //! - `file_location` is set for regular files so `cat_node` can read them
 //!   without understanding the TAR framing.
 //! - Directory structure reconstructed from slash-delimited names.
@@ -27,9 +28,15 @@
 //! - Sparse files (GNU `S` and `0S` entries).
 //! - Multi-volume TAR archives.
 
+use std::borrow::Cow;
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::image_io::source::{self, narrow, Borrowed, Bytes, Source, Stream};
+use crate::image_io::RandomAccess;
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Metadata, Timestamp, TreeNode};
 
 // ── Constants ─────────────────────────────────────────────────────────────────
 
@@ -41,7 +48,10 @@ const TYPE_REGULAR: u8 = b'0';
 const TYPE_REGULAR_ALT: u8 = b'\0'; // older archives
 const TYPE_HARD_LINK: u8 = b'1';
 const TYPE_SYMLINK: u8 = b'2';
+const TYPE_CHAR_DEVICE: u8 = b'3';
+const TYPE_BLOCK_DEVICE: u8 = b'4';
 const TYPE_DIR: u8 = b'5';
+const TYPE_FIFO: u8 = b'6';
 const TYPE_GNU_LONG_NAME: u8 = b'L';
 const TYPE_GNU_LONG_LINK: u8 = b'K';
 const TYPE_PAX_LOCAL: u8 = b'x';
@@ -86,9 +96,19 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::NotTar => crate::Error::not_recognized(Format::Tar, e),
+            e => crate::Error::corrupt(Format::Tar, e),
+        }
+    }
+}
+
 // ── Detection ─────────────────────────────────────────────────────────────────
 
-fn has_ustar_magic(block: &[u8; 512]) -> bool {
+fn has_ustar_magic(block: &[u8]) -> bool {
     let magic = &block[USTAR_MAGIC_OFFSET..USTAR_MAGIC_OFFSET + 6];
     magic == b"ustar\0" || magic == b"ustar "
 }
@@ -112,15 +132,21 @@ fn parse_name(field: &[u8]) -> String {
     String::from_utf8_lossy(&field[..end]).into_owned()
 }
 
+/// Read a NUL-padded name field, borrowing it from the image if it can.
+fn borrow_name<'a>(field: Bytes<'a, '_>) -> Cow<'a, str> {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    field.slice(..end).utf8_lossy()
+}
+
 /// Build the full entry name from the POSIX prefix + name fields.
-fn entry_name(block: &[u8; 512]) -> String {
-    let name = parse_name(&block[0..100]);
+fn entry_name<'a>(block: Bytes<'a, '_>) -> Cow<'a, str> {
+    let name = borrow_name(block.slice(0..100));
     // POSIX ustar prefix at offset 345 (155 bytes).
     let prefix = parse_name(&block[345..500]);
     if prefix.is_empty() {
         name
     } else {
-        format!("{}/{}", prefix, name)
+        Cow::Owned(format!("{}/{}", prefix, name))
     }
 }
 
@@ -150,34 +176,127 @@ fn parse_pax(body: &[u8]) -> (Option<String>, Option<u64>) {
     (path, size)
 }
 
+/// Metadata overrides carried by a PAX extended header.
+#[derive(Default)]
+struct PaxMetadata {
+    link_path: Option<String>,
+    mtime: Option<Timestamp>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+/// Parse the metadata keys (`linkpath`, `mtime`, `uid`, `gid`) of a PAX
+/// extended header body. `mtime` may carry a decimal fraction.
+fn parse_pax_metadata(body: &[u8]) -> PaxMetadata {
+    let mut meta = PaxMetadata::default();
+    let s = String::from_utf8_lossy(body);
+    for line in s.split('\n') {
+        let rest = line.find(' ').and_then(|i| line.get(i + 1..)).unwrap_or("");
+        let Some((key, val)) = rest.split_once('=') else {
+            continue;
+        };
+        match key {
+            "linkpath" => meta.link_path = Some(val.to_string()),
+            "mtime" => meta.mtime = parse_pax_time(val),
+            "uid" => meta.uid = val.parse().ok(),
+            "gid" => meta.gid = val.parse().ok(),
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// Parse a PAX timestamp such as `1234567890.5` or `-1.25`.
+fn parse_pax_time(val: &str) -> Option<Timestamp> {
+    let (whole, frac) = val.split_once('.').unwrap_or((val, ""));
+    let secs: i64 = whole.parse().ok()?;
+    let digits: String = frac.chars().take(9).collect();
+    let mut nanos: u32 = if digits.is_empty() {
+        0
+    } else {
+        digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32)
+    };
+    // The fraction extends away from zero, so negative times borrow a second.
+    let secs = if whole.starts_with('-') && nanos > 0 {
+        nanos = 1_000_000_000 - nanos;
+        secs.checked_sub(1)?
+    } else {
+        secs
+    };
+    Some(Timestamp::new(secs, nanos))
+}
+
+/// Map a header typeflag to an entry kind. Anything not listed — including
+/// the old `'\0'` and contiguous `'7'` types — is a regular file.
+fn entry_kind(typeflag: u8) -> EntryKind {
+    match typeflag {
+        TYPE_HARD_LINK => EntryKind::Hardlink,
+        TYPE_SYMLINK => EntryKind::Symlink,
+        TYPE_CHAR_DEVICE => EntryKind::CharDevice,
+        TYPE_BLOCK_DEVICE => EntryKind::BlockDevice,
+        TYPE_DIR => EntryKind::Directory,
+        TYPE_FIFO => EntryKind::Fifo,
+        _ => EntryKind::File,
+    }
+}
+
+/// Decode the ustar mode, uid, gid and mtime fields of a header block.
+fn header_metadata(block: &[u8], kind: EntryKind) -> Metadata {
+    Metadata {
+        kind,
+        mode: Some(parse_octal(&block[100..108]) as u32 & 0o7777),
+        uid: Some(parse_octal(&block[108..116]) as u32),
+        gid: Some(parse_octal(&block[116..124]) as u32),
+        mtime: Some(Timestamp::new(parse_octal(&block[136..148]) as i64, 0)),
+        ..Metadata::default()
+    }
+}
+
 // ── Archive scanning ──────────────────────────────────────────────────────────
 
-struct TarEntry {
-    name: String,
+struct TarEntry<'a> {
+    name: Cow<'a, str>,
     size: u64,
     is_dir: bool,
     /// Byte offset of the entry's data (first byte after the 512-byte header).
     data_offset: u64,
+    metadata: Metadata,
 }
 
-fn scan_entries<R: Read + Seek>(r: &mut R) -> Result<Vec<TarEntry>, Error> {
-    r.seek(SeekFrom::Start(0))?;
+fn scan_entries<'a, S: Source<'a>>(src: &mut S) -> Result<Vec<TarEntry<'a>>, Error> {
+    let archive_len = src.len()?;
     let mut entries = Vec::new();
+    match scan_headers(src, archive_len, &mut entries) {
+        Ok(()) => {}
+        Err(Error::NotTar) => return Err(Error::NotTar),
+        // A truncated or unreadable archive keeps the entries before the damage.
+        Err(e) => recover::skip(None, "header", e)?,
+    }
+    Ok(entries)
+}
 
+fn scan_headers<'a, S: Source<'a>>(
+    src: &mut S,
+    archive_len: u64,
+    entries: &mut Vec<TarEntry<'a>>,
+) -> Result<(), Error> {
     // State for GNU long-name / PAX overrides that apply to the next entry.
-    let mut pending_name: Option<String> = None;
+    let mut pending_name: Option<Cow<'a, str>> = None;
     let mut pending_size: Option<u64> = None;
+    let mut pending_link: Option<String> = None;
+    let mut pending_meta = PaxMetadata::default();
 
     let mut consecutive_zero = 0u32;
+    let mut header_pos = 0u64;
 
     loop {
-        let header_pos = r.stream_position()?;
-        let mut block = [0u8; 512];
-        match r.read_exact(&mut block) {
-            Ok(()) => {}
+        let header = match src.bytes(header_pos, BLOCK as usize) {
+            Ok(header) => header,
             Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
             Err(e) => return Err(Error::Io(e)),
-        }
+        };
+        let block = Bytes::of(&header);
+        let data_offset = header_pos + BLOCK;
 
         // Two consecutive all-zero blocks = end-of-archive.
         if block.iter().all(|&b| b == 0) {
@@ -185,6 +304,7 @@ fn scan_entries<R: Read + Seek>(r: &mut R) -> Result<Vec<TarEntry>, Error> {
             if consecutive_zero >= 2 {
                 break;
             }
+            header_pos = data_offset;
             continue;
         }
         consecutive_zero = 0;
@@ -194,86 +314,112 @@ fn scan_entries<R: Read + Seek>(r: &mut R) -> Result<Vec<TarEntry>, Error> {
         }
 
         let typeflag = block[TYPEFLAG_OFFSET];
-        let raw_name = entry_name(&block);
         let raw_size = parse_octal(&block[124..136]);
-        let data_offset = header_pos + BLOCK;
 
         // Number of 512-byte blocks to skip over the data.
         let data_blocks = raw_size.div_ceil(BLOCK);
+        header_pos = data_offset.saturating_add(data_blocks * BLOCK);
 
         match typeflag {
             TYPE_GNU_LONG_NAME => {
-                // Next 512*data_blocks bytes contain the long filename.
-                let mut name_bytes = vec![0u8; raw_size as usize];
-                r.read_exact(&mut name_bytes)?;
-                let null_end = name_bytes
-                    .iter()
-                    .position(|&b| b == 0)
-                    .unwrap_or(name_bytes.len());
-                pending_name = Some(String::from_utf8_lossy(&name_bytes[..null_end]).into_owned());
-                // Pad to block boundary.
-                let leftover = data_blocks * BLOCK - raw_size;
-                if leftover > 0 {
-                    r.seek(SeekFrom::Current(leftover as i64))?;
-                }
+                // The data blocks hold the long filename.
+                limits::alloc(raw_size)?;
+                let name_bytes = src.bytes(data_offset, raw_size as usize)?;
+                pending_name = Some(borrow_name(Bytes::of(&name_bytes)));
                 continue;
             }
             TYPE_GNU_LONG_LINK => {
-                // Long symlink target — consume and ignore (symlinks become
-                // zero-byte files in the tree).
-                r.seek(SeekFrom::Current((data_blocks * BLOCK) as i64))?;
+                // Long symlink / hard-link target for the next entry.
+                limits::alloc(raw_size)?;
+                let link_bytes = src.bytes(data_offset, raw_size as usize)?;
+                pending_link = Some(parse_name(&link_bytes));
                 continue;
             }
             TYPE_PAX_LOCAL | TYPE_PAX_GLOBAL => {
-                let mut pax_bytes = vec![0u8; raw_size as usize];
-                r.read_exact(&mut pax_bytes)?;
-                let leftover = data_blocks * BLOCK - raw_size;
-                if leftover > 0 {
-                    r.seek(SeekFrom::Current(leftover as i64))?;
-                }
+                limits::alloc(raw_size)?;
+                let pax_bytes = src.bytes(data_offset, raw_size as usize)?;
                 let (p, s) = parse_pax(&pax_bytes);
                 if p.is_some() {
-                    pending_name = p;
+                    pending_name = p.map(Cow::Owned);
                 }
                 if s.is_some() {
                     pending_size = s;
                 }
+                let meta = parse_pax_metadata(&pax_bytes);
+                pending_meta = PaxMetadata {
+                    link_path: meta.link_path.or(pending_meta.link_path),
+                    mtime: meta.mtime.or(pending_meta.mtime),
+                    uid: meta.uid.or(pending_meta.uid),
+                    gid: meta.gid.or(pending_meta.gid),
+                };
                 continue;
             }
             _ => {}
         }
 
         // Apply pending overrides.
-        let raw = pending_name.take().unwrap_or(raw_name);
+        let raw = match pending_name.take() {
+            Some(name) => name,
+            None => entry_name(block),
+        };
         let size = pending_size.take().unwrap_or(raw_size);
         // Strip the leading "./" that `tar -C dir -cf archive .` stores.
-        let name = raw.trim_start_matches("./").to_string();
+        let name = narrow(raw, |n| n.trim_start_matches("./"));
 
         let is_dir = typeflag == TYPE_DIR || name.ends_with('/');
-        let is_file = typeflag == TYPE_REGULAR
-            || typeflag == TYPE_REGULAR_ALT
-            || typeflag == TYPE_HARD_LINK
-            || typeflag == TYPE_SYMLINK;
+        let is_file = matches!(
+            typeflag,
+            TYPE_REGULAR
+                | TYPE_REGULAR_ALT
+                | TYPE_HARD_LINK
+                | TYPE_SYMLINK
+                | TYPE_CHAR_DEVICE
+                | TYPE_BLOCK_DEVICE
+                | TYPE_FIFO
+        );
+
+        let kind = if is_dir {
+            EntryKind::Directory
+        } else {
+            entry_kind(typeflag)
+        };
+        let mut metadata = header_metadata(&block, kind);
+        let link = pending_link.take();
+        let pax = std::mem::take(&mut pending_meta);
+        if matches!(kind, EntryKind::Symlink | EntryKind::Hardlink) {
+            metadata.link_target = pax
+                .link_path
+                .or(link)
+                .or_else(|| Some(parse_name(&block[157..257])));
+        }
+        metadata.mtime = pax.mtime.or(metadata.mtime);
+        metadata.uid = pax.uid.or(metadata.uid);
+        metadata.gid = pax.gid.or(metadata.gid);
 
         if is_file || is_dir {
+            if is_file && data_offset.saturating_add(size) > archive_len {
+                recover::note(
+                    Some(data_offset - BLOCK),
+                    "header",
+                    format_args!("data of '{name}' runs past the end of the archive"),
+                );
+            }
             entries.push(TarEntry {
-                name: name.trim_end_matches('/').to_string(),
+                name: narrow(name, |n| n.trim_end_matches('/')),
                 size,
                 is_dir,
                 data_offset,
+                metadata,
             });
         }
-
-        // Skip over data blocks.
-        r.seek(SeekFrom::Current((data_blocks * BLOCK) as i64))?;
     }
 
-    Ok(entries)
+    Ok(())
 }
 
 // ── Tree construction ─────────────────────────────────────────────────────────
 
-fn build_tree(entries: Vec<TarEntry>) -> TreeNode {
+fn build_tree(entries: Vec<TarEntry<'_>>) -> TreeNode<'_> {
     use std::collections::HashMap;
 
     let mut nodes: HashMap<String, TreeNode> = HashMap::new();
@@ -286,19 +432,20 @@ fn build_tree(entries: Vec<TarEntry>) -> TreeNode {
 
         // Create ancestor directories.
         let mut acc = String::new();
-        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
-        for (i, component) in components.iter().enumerate() {
+        let components = source::split_path(&entry.name);
+        for (i, component) in components.into_iter().filter(|c| !c.is_empty()).enumerate() {
             if i > 0 {
                 acc.push('/');
             }
-            acc.push_str(component);
+            acc.push_str(&component);
             nodes
                 .entry(acc.clone())
-                .or_insert_with(|| TreeNode::new_directory((*component).to_string()));
+                .or_insert_with(|| TreeNode::new_directory(component));
         }
 
         // Update leaf node.
         if let Some(node) = nodes.get_mut(path) {
+            node.metadata = entry.metadata.clone();
             if entry.is_dir {
                 node.is_directory = true;
             } else {
@@ -323,10 +470,10 @@ fn build_tree(entries: Vec<TarEntry>) -> TreeNode {
         children_of.entry(parent).or_default().push(path.clone());
     }
 
-    fn attach(
-        node: &mut TreeNode,
+    fn attach<'a>(
+        node: &mut TreeNode<'a>,
         key: &str,
-        nodes: &mut HashMap<String, TreeNode>,
+        nodes: &mut HashMap<String, TreeNode<'a>>,
         children_of: &HashMap<String, Vec<String>>,
     ) {
         if let Some(child_keys) = children_of.get(key) {
@@ -341,7 +488,7 @@ fn build_tree(entries: Vec<TarEntry>) -> TreeNode {
         }
     }
 
-    let mut root = TreeNode::new_directory("/".to_string());
+    let mut root = TreeNode::new_directory("/");
     attach(&mut root, "", &mut nodes, &children_of);
     root.calculate_directory_size();
     root
@@ -369,10 +516,27 @@ pub fn detect<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 ///
 /// The root node is named `"/"`. Regular files have `file_location` set so
 /// `cat_node` can read them directly from the TAR without extraction.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Tar, None, r, |r| parse_tree(&mut Stream(r)))
+}
+
+/// Parse a TAR archive that is already in memory or mapped.
+///
+/// Entry names stored whole in a header or GNU long-name block borrow
+/// from `image`; names joined from a ustar prefix or given by a PAX
+/// `path` record are owned.
+pub fn detect_and_parse_image<R: RandomAccess + ?Sized>(image: &R) -> Result<TreeNode<'_>, Error> {
+    limits::scope(Format::Tar, None, || parse_tree(&mut Borrowed(image)))
+}
+
+fn parse_tree<'a, S: Source<'a>>(src: &mut S) -> Result<TreeNode<'a>, Error> {
     // Verify magic first.
-    detect(r)?;
-    let entries = scan_entries(r)?;
+    let first = src.bytes(0, BLOCK as usize)?;
+    if !has_ustar_magic(&first) {
+        return Err(Error::NotTar);
+    }
+    let entries = scan_entries(src)?;
+    limits::paths(entries.iter().map(|e| &*e.name))?;
     Ok(build_tree(entries))
 }
 
@@ -646,8 +810,10 @@ mod tests {
         // All-0xFF first block: non-zero, no ustar magic, entries empty → Error::NotTar.
         let mut data = vec![0xFFu8; 512];
         data.extend_from_slice(&[0u8; 1024]); // two zero EOF blocks
-        let mut c = Cursor::new(data);
-        assert!(matches!(scan_entries(&mut c), Err(Error::NotTar)));
+        assert!(matches!(
+            scan_entries(&mut Borrowed(&data)),
+            Err(Error::NotTar)
+        ));
     }
 
     // ── parse_octal ───────────────────────────────────────────────────────────
@@ -681,10 +847,25 @@ mod tests {
         block[..7].copy_from_slice(b"foo.txt");
         block[345..353].copy_from_slice(b"myprefix");
         block[257..263].copy_from_slice(b"ustar\0");
-        let name = entry_name(&block);
+        let name = entry_name(Bytes::Image(&block));
         assert_eq!(name, "myprefix/foo.txt");
     }
 
+    #[test]
+    fn image_names_borrow_from_the_archive() {
+        let data = make_ustar("dir/file.txt", b"hi");
+        let root = detect_and_parse_image(&data).unwrap();
+        let dir = &root.children[0];
+        assert!(matches!(dir.name, Cow::Borrowed("dir")));
+        assert!(matches!(dir.children[0].name, Cow::Borrowed("file.txt")));
+        let streamed = detect_and_parse(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+
+        let data = make_gnu_long_name_tar(&"n".repeat(150), b"x");
+        let root = detect_and_parse_image(&data).unwrap();
+        assert!(matches!(root.children[0].name, Cow::Borrowed(_)));
+    }
+
     // ── parse_pax ─────────────────────────────────────────────────────────────
 
     #[test]
@@ -718,6 +899,34 @@ mod tests {
         assert!(size.is_none());
     }
 
+    #[test]
+    fn parse_pax_metadata_keys() {
+        let body = b"30 linkpath=/some/where/else\n20 mtime=1234567890.5\n12 uid=1000\n10 gid=50\n";
+        let meta = parse_pax_metadata(body);
+        assert_eq!(meta.link_path.as_deref(), Some("/some/where/else"));
+        assert_eq!(meta.mtime, Some(Timestamp::new(1_234_567_890, 500_000_000)));
+        assert_eq!((meta.uid, meta.gid), (Some(1000), Some(50)));
+    }
+
+    #[test]
+    fn parse_pax_time_negative_fraction_borrows() {
+        assert_eq!(
+            parse_pax_time("-1.25"),
+            Some(Timestamp::new(-2, 750_000_000))
+        );
+        assert_eq!(parse_pax_time("42"), Some(Timestamp::new(42, 0)));
+        assert_eq!(parse_pax_time("x"), None);
+    }
+
+    #[test]
+    fn parse_pax_time_out_of_range_borrow_is_none() {
+        assert_eq!(parse_pax_time("-9223372036854775808.5"), None);
+        assert_eq!(
+            parse_pax_time("-9223372036854775808"),
+            Some(Timestamp::new(i64::MIN, 0))
+        );
+    }
+
     // ── GNU long name handling ────────────────────────────────────────────────
 
     fn make_gnu_long_name_tar(long_name: &str, data: &[u8]) -> Vec<u8> {
@@ -767,6 +976,36 @@ mod tests {
         assert!(f.is_some(), "long-name file should be in tree");
     }
 
+    #[test]
+    fn lenient_parse_keeps_entries_before_a_truncated_header() {
+        use crate::limits::ParseOptions;
+        use crate::probe::{parse_as_lenient, Format};
+        // A sound entry, then a long-name header whose name is cut off.
+        let mut tar = make_ustar("a.txt", b"hi");
+        tar.extend_from_slice(&make_gnu_long_name_tar(&"n".repeat(600), b"")[..612]);
+        assert!(detect_and_parse(&mut Cursor::new(&tar)).is_err());
+
+        let options = ParseOptions::default();
+        let parsed = parse_as_lenient(&mut Cursor::new(&tar), Format::Tar, &options).unwrap();
+        assert_eq!(parsed.root.children.len(), 1);
+        assert!(parsed.root.find_node("a.txt").is_some());
+        assert_eq!(parsed.warnings.len(), 1, "{:?}", parsed.warnings);
+        assert_eq!(parsed.warnings[0].structure, "header");
+    }
+
+    #[test]
+    fn lenient_parse_reports_data_past_the_end() {
+        use crate::limits::ParseOptions;
+        use crate::probe::{parse_as_lenient, Format};
+        let mut tar = make_ustar("cut.bin", b"abc");
+        tar[124..136].copy_from_slice(format!("{:011o}\0", 10_000).as_bytes());
+        let options = ParseOptions::default();
+        let parsed = parse_as_lenient(&mut Cursor::new(&tar), Format::Tar, &options).unwrap();
+        assert_eq!(parsed.root.find_node("cut.bin").unwrap().size, 10_000);
+        assert_eq!(parsed.warnings[0].offset, Some(0));
+        assert!(parsed.warnings[0].reason.contains("'cut.bin'"));
+    }
+
     // ── GNU long link (type K) ────────────────────────────────────────────────
 
     fn make_gnu_long_link_tar(target: &str, after_name: &str, after_data: &[u8]) -> Vec<u8> {
@@ -807,6 +1046,22 @@ mod tests {
         assert!(root.find_node("/real_file.txt").is_some());
     }
 
+    #[test]
+    fn gnu_long_link_becomes_symlink_target() {
+        let mut tar = make_gnu_long_link_tar("/very/long/symlink/target/path", "x", b"");
+        // Turn the trailing regular entry into a symlink.
+        let hdr = tar.len() - 1536;
+        tar[hdr + 156] = TYPE_SYMLINK;
+        patch_header(&mut tar[hdr..], 0, b"link");
+        let root = detect_and_parse(&mut Cursor::new(&tar)).unwrap();
+        let m = &root.find_node("/link").unwrap().metadata;
+        assert_eq!(m.kind, EntryKind::Symlink);
+        assert_eq!(
+            m.link_target.as_deref(),
+            Some("/very/long/symlink/target/path")
+        );
+    }
+
     // ── PAX extended header (type x) ─────────────────────────────────────────
 
     fn make_pax_tar(pax_path: &str, actual_data: &[u8]) -> Vec<u8> {
@@ -888,6 +1143,58 @@ mod tests {
         assert!(!n.is_directory);
     }
 
+    /// Rewrite a header field and fix up the checksum.
+    fn patch_header(buf: &mut [u8], offset: usize, value: &[u8]) {
+        buf[offset..offset + value.len()].copy_from_slice(value);
+        buf[148..156].fill(b' ');
+        let ck: u32 = buf[..512].iter().map(|&b| b as u32).sum();
+        buf[148..156].copy_from_slice(format!("{:06o}\0 ", ck).as_bytes());
+    }
+
+    #[test]
+    fn header_carries_mode_owner_and_mtime() {
+        let mut tar = make_ustar_with_type("f.txt", TYPE_REGULAR, 0);
+        patch_header(&mut tar, 100, b"0000644\0");
+        patch_header(&mut tar, 108, b"0001750\0");
+        patch_header(&mut tar, 116, b"0000144\0");
+        patch_header(&mut tar, 136, b"07033241600\0");
+        let root = detect_and_parse(&mut Cursor::new(&tar)).unwrap();
+        let m = &root.find_node("/f.txt").unwrap().metadata;
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!(m.mode, Some(0o644));
+        assert_eq!((m.uid, m.gid), (Some(1000), Some(100)));
+        assert_eq!(m.mtime, Some(Timestamp::new(946_684_800, 0)));
+    }
+
+    #[test]
+    fn symlink_and_hard_link_carry_targets() {
+        let mut tar = make_ustar_with_type("sym", TYPE_SYMLINK, 0);
+        patch_header(&mut tar, 157, b"target.txt");
+        let mut hard = make_ustar_with_type("hard", TYPE_HARD_LINK, 0);
+        patch_header(&mut hard, 157, b"sym");
+        tar.truncate(512);
+        tar.extend_from_slice(&hard);
+        let root = detect_and_parse(&mut Cursor::new(&tar)).unwrap();
+        let sym = &root.find_node("/sym").unwrap().metadata;
+        assert_eq!(sym.kind, EntryKind::Symlink);
+        assert_eq!(sym.link_target.as_deref(), Some("target.txt"));
+        let hard = &root.find_node("/hard").unwrap().metadata;
+        assert_eq!(hard.kind, EntryKind::Hardlink);
+        assert_eq!(hard.link_target.as_deref(), Some("sym"));
+    }
+
+    #[test]
+    fn device_and_fifo_entries_appear_with_kind() {
+        let mut tar = make_ustar_with_type("tty", TYPE_CHAR_DEVICE, 0);
+        tar.truncate(512);
+        tar.extend_from_slice(&make_ustar_with_type("pipe", TYPE_FIFO, 0));
+        let root = detect_and_parse(&mut Cursor::new(&tar)).unwrap();
+        let tty = root.find_node("/tty").unwrap();
+        assert_eq!(tty.metadata.kind, EntryKind::CharDevice);
+        let pipe = root.find_node("/pipe").unwrap();
+        assert_eq!(pipe.metadata.kind, EntryKind::Fifo);
+    }
+
     #[test]
     fn regular_alt_type_is_regular_file() {
         // TYPE_REGULAR_ALT ('\0') should be treated as a regular file.
@@ -10,7 +10,13 @@
 //! - **Dynamic VHDs**: footer *copy* at byte 0 (the authoritative footer
 //!   is the last 512 bytes), dynamic disk header at byte 512, Block
 //!   Allocation Table follows. Data is fragmented across BAT-indexed
-//!   blocks. Reported with `file_location = None`.
+//!   blocks. Reported with `file_location = None` and one extent per
+//!   block — a hole where the BAT marks the block unallocated — so
+//!   `cat_node` and `NodeReader` read the guest disk directly.
+//!
+//! Either kind can be read as a flat disk through [`VirtualDisk`], which
+//! resolves the BAT on the fly and can be handed to any other parser in
+//! the crate (`raw`, `fat`, `ntfs`, `ext`, …).
 //!
 //! **Differencing VHDs** (disk_type 4) are not supported and return
 //! `Error::UnsupportedType(4)`. Parent resolution requires traversing
@@ -52,7 +58,9 @@
 
 use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::{Extent, TreeNode};
 
 /// Magic bytes in a VHD footer at offset 0.
 const FOOTER_COOKIE: &[u8; 8] = b"conectix";
@@ -70,8 +78,6 @@ const DISK_TYPE_FIXED: u32 = 2;
 const DISK_TYPE_DYNAMIC: u32 = 3;
 
 /// Sentinel BAT entry meaning "block not allocated".
-/// Used in tests to build minimal Dynamic VHD images.
-#[cfg(test)]
 const BAT_ENTRY_UNUSED: u32 = 0xFFFF_FFFF;
 
 // ── Error type ────────────────────────────────────────────────────────────────
@@ -127,6 +133,17 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Vhd, e),
+            Error::UnsupportedType(_) => crate::Error::unsupported(Format::Vhd, e),
+            e => crate::Error::corrupt(Format::Vhd, e),
+        }
+    }
+}
+
 // ── Footer ────────────────────────────────────────────────────────────────────
 
 /// Parsed VHD footer fields we actually use.
@@ -252,9 +269,14 @@ fn detect_inner<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 /// the raw disk data occupies bytes `0..current_size` of the file.
 ///
 /// For Dynamic VHDs, `disk.img` has `file_location = None` because
-/// data is scattered across BAT-indexed blocks. The virtual size is
-/// still reported correctly in `file_length`.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
+/// data is scattered across BAT-indexed blocks; its `extents` list each
+/// block's data region, with holes for unallocated blocks. To hand the
+/// guest disk to another parser, use [`VirtualDisk`].
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Vhd, None, r, parse_tree)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
     // Find file length and read footer from the end.
     let file_len = r.seek(SeekFrom::End(0))?;
     if file_len < FOOTER_SIZE {
@@ -271,7 +293,7 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
 }
 
 /// Build the tree for a Fixed VHD. Data occupies bytes 0..current_size.
-fn parse_fixed(current_size: u64, file_len: u64) -> Result<TreeNode, Error> {
+fn parse_fixed(current_size: u64, file_len: u64) -> Result<TreeNode<'static>, Error> {
     // Validate that current_size doesn't exceed the available data region.
     // A fixed VHD is: [disk data: current_size bytes] [footer: 512 bytes].
     let data_region = file_len.saturating_sub(FOOTER_SIZE);
@@ -291,13 +313,36 @@ fn parse_fixed(current_size: u64, file_len: u64) -> Result<TreeNode, Error> {
     Ok(root)
 }
 
-/// Build the tree for a Dynamic VHD. Data is fragmented across BAT blocks;
-/// we report the virtual size but set `file_location = None`.
+/// Build the tree for a Dynamic VHD. Data is fragmented across BAT
+/// blocks, so `disk.img` gets one extent per allocated block and a hole
+/// for each unallocated one.
 fn parse_dynamic<R: Read + Seek>(
     r: &mut R,
     footer: &Footer,
     file_len: u64,
-) -> Result<TreeNode, Error> {
+) -> Result<TreeNode<'static>, Error> {
+    let layout = read_dynamic_layout(r, footer, file_len)?;
+    let current_size = footer.current_size;
+
+    let mut root = TreeNode::new_directory("/".to_string());
+    let disk_node = TreeNode::new_file_with_extents(
+        "disk.img".to_string(),
+        current_size,
+        layout.extents(current_size),
+    );
+
+    root.add_child(disk_node);
+    root.calculate_directory_size();
+    Ok(root)
+}
+
+/// Read and validate the 1024-byte Dynamic Disk Header named by the
+/// footer's `data_offset`.
+fn read_dynamic_header<R: Read + Seek>(
+    r: &mut R,
+    footer: &Footer,
+    file_len: u64,
+) -> Result<[u8; 1024], Error> {
     // The Dynamic Disk Header lives at data_offset (= 512 for standard VHDs).
     // Validate data_offset before seeking to avoid seeking past EOF on corrupt images.
     let dyn_header_offset = footer.data_offset;
@@ -318,19 +363,229 @@ fn parse_dynamic<R: Read + Seek>(
     if &hdr[0..8] != DYN_HEADER_COOKIE {
         return Err(Error::BadDynamicHeader);
     }
+    Ok(hdr)
+}
 
-    // For Dynamic VHDs we report the virtual size without mapping blocks.
-    let current_size = footer.current_size;
+// ── Virtual disk reader ───────────────────────────────────────────────────────
 
-    let mut root = TreeNode::new_directory("/".to_string());
+/// How virtual-disk offsets map onto the VHD file.
+#[derive(Debug)]
+enum Layout {
+    /// Virtual byte `n` is file byte `n`.
+    Fixed,
+    /// Virtual block `n` lives at sector `bat[n]`, after a sector bitmap
+    /// of `bitmap_size` bytes. `0xFFFF_FFFF` marks an unallocated block.
+    Dynamic {
+        block_size: u64,
+        bitmap_size: u64,
+        bat: Vec<u32>,
+    },
+}
 
-    // file_location = None: data is scattered across BAT blocks.
-    let mut disk_node = TreeNode::new_file("disk.img".to_string(), current_size);
-    disk_node.file_length = Some(current_size);
+impl Layout {
+    /// The runs of a `size`-byte virtual disk in the file: the data
+    /// region of each allocated block, and a hole for each unallocated
+    /// one.
+    fn extents(&self, size: u64) -> Vec<Extent> {
+        match self {
+            Layout::Fixed => vec![Extent::data(0, size)],
+            Layout::Dynamic {
+                block_size,
+                bitmap_size,
+                bat,
+            } => {
+                let blocks = size.div_ceil(*block_size);
+                let mut extents: Vec<Extent> = (0..blocks)
+                    .map(|block| match bat.get(block as usize) {
+                        Some(&sector) if sector != BAT_ENTRY_UNUSED => {
+                            Extent::data(sector as u64 * 512 + bitmap_size, *block_size)
+                        }
+                        _ => Extent::hole(*block_size),
+                    })
+                    .collect();
+                if let Some(last) = extents.last_mut() {
+                    last.length -= blocks * block_size - size;
+                }
+                extents
+            }
+        }
+    }
+}
 
-    root.add_child(disk_node);
-    root.calculate_directory_size();
-    Ok(root)
+/// Read the dynamic header and Block Allocation Table of a Dynamic VHD.
+fn read_dynamic_layout<R: Read + Seek>(
+    r: &mut R,
+    footer: &Footer,
+    file_len: u64,
+) -> Result<Layout, Error> {
+    let hdr = read_dynamic_header(r, footer, file_len)?;
+    let table_offset = u64::from_be_bytes(hdr[16..24].try_into().unwrap());
+    let entries = u32::from_be_bytes(hdr[28..32].try_into().unwrap()) as u64;
+    let block_size = u32::from_be_bytes(hdr[32..36].try_into().unwrap()) as u64;
+    if block_size == 0 || block_size % 512 != 0 {
+        return Err(Error::BadDynamicHeader);
+    }
+    // The BAT must fit in the file; this also bounds the
+    // allocation against a corrupt `max_table_entries`.
+    let table_len = entries * 4;
+    if table_offset
+        .checked_add(table_len)
+        .map_or(true, |end| end > file_len)
+    {
+        return Err(Error::TooShort);
+    }
+    let mut raw = vec![0u8; table_len as usize];
+    r.seek(SeekFrom::Start(table_offset))?;
+    r.read_exact(&mut raw)?;
+    let bat = raw
+        .chunks_exact(4)
+        .map(|e| u32::from_be_bytes([e[0], e[1], e[2], e[3]]))
+        .collect();
+    // One bit per sector, padded to a whole sector.
+    let bitmap_size = (block_size / 512).div_ceil(8).next_multiple_of(512);
+    Ok(Layout::Dynamic {
+        block_size,
+        bitmap_size,
+        bat,
+    })
+}
+
+/// `Read + Seek` view of the virtual disk inside a Fixed or Dynamic VHD.
+///
+/// Dynamic VHDs are resolved through the Block Allocation Table on every
+/// read; blocks the BAT marks unallocated read back as zeros. The
+/// per-block sector bitmaps are not consulted, since a sector absent
+/// from the bitmap is zero on disk anyway. Hand the reader to any other
+/// parser in the crate to look inside the guest disk:
+///
+/// ```no_run
+/// use isomage::formats::vhd;
+///
+/// let file = std::fs::File::open("guest.vhd")?;
+/// let mut disk = vhd::VirtualDisk::open(file)?;
+/// let (root, found) = isomage::open_any(&mut disk)?;
+/// # Ok::<(), isomage::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct VirtualDisk<R> {
+    inner: R,
+    size: u64,
+    layout: Layout,
+    pos: u64,
+}
+
+impl<R: Read + Seek> VirtualDisk<R> {
+    /// Parse the footer (and, for Dynamic VHDs, the dynamic header and
+    /// BAT) of `inner`.
+    pub fn open(mut inner: R) -> Result<Self, Error> {
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        if file_len < FOOTER_SIZE {
+            return Err(Error::TooShort);
+        }
+        let footer = read_footer(&mut inner, file_len - FOOTER_SIZE)?;
+
+        let layout = match footer.disk_type {
+            DISK_TYPE_FIXED => {
+                if footer.current_size > file_len - FOOTER_SIZE {
+                    return Err(Error::TooShort);
+                }
+                Layout::Fixed
+            }
+            DISK_TYPE_DYNAMIC => read_dynamic_layout(&mut inner, &footer, file_len)?,
+            other => return Err(Error::UnsupportedType(other)),
+        };
+
+        Ok(Self {
+            inner,
+            size: footer.current_size,
+            layout,
+            pos: 0,
+        })
+    }
+
+    /// Virtual disk size in bytes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// `true` iff the virtual disk is zero bytes long.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Give back the wrapped VHD file.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Resolve virtual offset `pos` to a file offset (`None` for an
+    /// unallocated block) and the number of bytes that stay contiguous.
+    fn map(&self, pos: u64) -> (Option<u64>, u64) {
+        match &self.layout {
+            Layout::Fixed => (Some(pos), self.size - pos),
+            Layout::Dynamic {
+                block_size,
+                bitmap_size,
+                bat,
+            } => {
+                let block = pos / block_size;
+                let within = pos % block_size;
+                let run = block_size - within;
+                match bat.get(block as usize) {
+                    Some(&sector) if sector != BAT_ENTRY_UNUSED => {
+                        (Some(sector as u64 * 512 + bitmap_size + within), run)
+                    }
+                    _ => (None, run),
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for VirtualDisk<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let (location, run) = self.map(self.pos);
+        let want = run.min(self.size - self.pos).min(buf.len() as u64) as usize;
+        let n = match location {
+            None => {
+                buf[..want].fill(0);
+                want
+            }
+            Some(at) => {
+                self.inner.seek(SeekFrom::Start(at))?;
+                let n = self.inner.read(&mut buf[..want])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "VHD block data lies past the end of the file",
+                    ));
+                }
+                n
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for VirtualDisk<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(d) => (self.size, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        self.pos = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
 }
 
 // ── Unit tests ────────────────────────────────────────────────────────────────
@@ -761,4 +1016,119 @@ mod tests {
             Err(Error::BadDynamicHeader)
         ));
     }
+
+    // ── VirtualDisk ───────────────────────────────────────────────────────
+
+    /// Dynamic VHD with 4 KiB blocks and a 3-entry BAT: block 0 holds
+    /// `block0`, block 1 is unallocated, block 2 holds all `0xCC`.
+    fn build_dynamic_vhd_with_data(block0: &[u8]) -> Vec<u8> {
+        const BLOCK: u32 = 4096;
+        let virtual_size = 3 * BLOCK as u64;
+        let footer = build_footer(DISK_TYPE_DYNAMIC, virtual_size, 512);
+
+        let mut dyn_hdr = [0u8; 1024];
+        dyn_hdr[0..8].copy_from_slice(DYN_HEADER_COOKIE);
+        dyn_hdr[16..24].copy_from_slice(&1536u64.to_be_bytes()); // table_offset
+        dyn_hdr[28..32].copy_from_slice(&3u32.to_be_bytes()); // max_table_entries
+        dyn_hdr[32..36].copy_from_slice(&BLOCK.to_be_bytes());
+
+        // BAT occupies one sector at 1536; blocks follow at sector 4 and
+        // sector 13, each a 512-byte bitmap plus 4 KiB of data.
+        let mut bat = vec![0u8; 512];
+        bat[0..4].copy_from_slice(&4u32.to_be_bytes());
+        bat[4..8].copy_from_slice(&BAT_ENTRY_UNUSED.to_be_bytes());
+        bat[8..12].copy_from_slice(&13u32.to_be_bytes());
+
+        let mut image = Vec::new();
+        image.extend_from_slice(&footer);
+        image.extend_from_slice(&dyn_hdr);
+        image.extend_from_slice(&bat);
+        let mut block = vec![0xFFu8; 512]; // bitmap
+        block.extend_from_slice(block0);
+        block.resize(512 + BLOCK as usize, 0);
+        image.extend_from_slice(&block);
+        image.extend_from_slice(&[0xFF; 512]);
+        image.extend_from_slice(&[0xCC; BLOCK as usize]);
+        image.extend_from_slice(&footer);
+        image
+    }
+
+    #[test]
+    fn virtual_disk_fixed_reads_data_region() {
+        let mut img = build_fixed_vhd(1024);
+        img[..5].copy_from_slice(b"hello");
+        let mut disk = VirtualDisk::open(Cursor::new(img)).unwrap();
+        assert_eq!(disk.len(), 1024);
+        let mut out = Vec::new();
+        disk.read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 1024, "footer must not be exposed");
+        assert_eq!(&out[..5], b"hello");
+    }
+
+    #[test]
+    fn virtual_disk_dynamic_resolves_bat() {
+        let img = build_dynamic_vhd_with_data(b"guest data");
+        let mut disk = VirtualDisk::open(Cursor::new(img)).unwrap();
+        assert_eq!(disk.len(), 3 * 4096);
+
+        let mut out = Vec::new();
+        disk.read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 3 * 4096);
+        assert_eq!(&out[..10], b"guest data");
+        assert!(out[4096..8192].iter().all(|&b| b == 0), "unallocated block");
+        assert!(out[8192..].iter().all(|&b| b == 0xCC));
+
+        // A read straddling the allocated/unallocated boundary.
+        disk.seek(SeekFrom::Start(4090)).unwrap();
+        let mut buf = [0xAAu8; 12];
+        disk.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0u8; 12]);
+    }
+
+    #[test]
+    fn dynamic_vhd_node_maps_bat_blocks() {
+        let img = build_dynamic_vhd_with_data(b"guest data");
+        let root = detect_and_parse(&mut Cursor::new(&img)).unwrap();
+        let node = &root.children[0];
+        assert_eq!(node.file_location, None);
+        assert_eq!(
+            node.extents,
+            vec![
+                Extent::data(4 * 512 + 512, 4096),
+                Extent::hole(4096),
+                Extent::data(13 * 512 + 512, 4096),
+            ]
+        );
+
+        let mut through_node = Vec::new();
+        crate::cat_node(&mut Cursor::new(&img), node, &mut through_node).unwrap();
+        let mut through_disk = Vec::new();
+        VirtualDisk::open(Cursor::new(&img))
+            .unwrap()
+            .read_to_end(&mut through_disk)
+            .unwrap();
+        assert_eq!(through_node, through_disk);
+    }
+
+    #[test]
+    fn virtual_disk_bat_past_eof_is_an_error() {
+        let mut img = build_dynamic_vhd_with_data(b"x");
+        // Point block 0 far beyond the end of the file.
+        img[1536..1540].copy_from_slice(&0x00FF_0000u32.to_be_bytes());
+        let mut disk = VirtualDisk::open(Cursor::new(img)).unwrap();
+        let mut buf = [0u8; 16];
+        let err = disk.read_exact(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn virtual_disk_rejects_differencing() {
+        let mut img = build_fixed_vhd(512);
+        let footer = build_footer(4, 512, 512);
+        img[512..].copy_from_slice(&footer);
+        assert!(matches!(
+            VirtualDisk::open(Cursor::new(img)),
+            Err(Error::UnsupportedType(4))
+        ));
+    }
 }
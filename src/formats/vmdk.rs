@@ -34,21 +34,31 @@
 //! [78]   u8[433] pad
 //! ```
 //!
+//! ## Reading the virtual disk
+//!
+//! [`VirtualDisk`] exposes the disk as `Read + Seek`, resolving each read
+//! through the grain directory and grain tables. Unallocated grains read
+//! as zeros. Hand it to `raw`, `fat`, `ntfs`, `ext`, … to parse the
+//! guest disk.
+//!
 //! ## TreeNode output
 //!
 //! Because VMDK grain data is addressed through a grain directory /
-//! grain table indirection it is not contiguous in the file. We therefore
-//! set `file_location = None` and report only the virtual size in
-//! `file_length`:
+//! grain table indirection it is not contiguous in the file. The disk
+//! node therefore carries one extent per allocated grain and a hole for
+//! each unallocated one, so `cat_node` returns the same bytes as
+//! [`VirtualDisk`]:
 //!
 //! ```text
 //! / (dir)
-//! └─ disk.vmdk (file, file_location=None, file_length=capacity*512)
+//! └─ disk.vmdk (file, extents = grains, file_length=capacity*512)
 //! ```
 
 use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::{Extent, TreeNode};
 
 // ── Constants ─────────────────────────────────────────────────────────────────
 
@@ -61,6 +71,10 @@ const HEADER_SIZE: u64 = 512;
 /// Sector size in bytes (always 512 for VMDK).
 const SECTOR_SIZE: u64 = 512;
 
+/// Grain table entries per grain table. The format fixes it at 512;
+/// `open` refuses any other value rather than size tables from it.
+const GTES_PER_GT: u32 = 512;
+
 // ── Error type ────────────────────────────────────────────────────────────────
 
 /// Reasons `detect` or `detect_and_parse` can fail.
@@ -74,6 +88,10 @@ pub enum Error {
     UnsupportedVersion(u32),
     /// `compress_algorithm == 1` (deflate / streamOptimized). Not supported.
     Compressed,
+    /// `grain_size` is 0, `num_gtes_per_gt` is not 512, or the grain
+    /// directory does not fit in the file, so the virtual disk cannot be
+    /// read.
+    BadGrainDirectory,
     /// Underlying I/O error.
     Io(io::Error),
 }
@@ -94,6 +112,9 @@ impl std::fmt::Display for Error {
                 f,
                 "VMDK streamOptimized (deflate-compressed) images are not supported"
             ),
+            Error::BadGrainDirectory => {
+                write!(f, "VMDK grain size or grain directory is invalid")
+            }
             Error::Io(e) => write!(f, "VMDK I/O error: {e}"),
         }
     }
@@ -114,16 +135,34 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Vmdk, e),
+            Error::UnsupportedVersion(_) | Error::Compressed => {
+                crate::Error::unsupported(Format::Vmdk, e)
+            }
+            e => crate::Error::corrupt(Format::Vmdk, e),
+        }
+    }
+}
+
 // ── Header ────────────────────────────────────────────────────────────────────
 
 /// Parsed VMDK SparseExtentHeader fields we actually use downstream.
 ///
 /// `version` and `compress_algorithm` are validated inside `read_header`
-/// before this struct is constructed; they are not propagated here because
-/// the caller needs only the virtual disk size.
+/// before this struct is constructed; they are not propagated here.
 struct Header {
     /// Virtual disk capacity in 512-byte sectors.
     capacity: u64,
+    /// Grain size in sectors.
+    grain_size: u64,
+    /// Grain table entries per grain table.
+    num_gtes_per_gt: u32,
+    /// Sector offset of the grain directory.
+    gd_offset: u64,
 }
 
 /// Read and parse the 512-byte SparseExtentHeader from `r`.
@@ -165,7 +204,16 @@ fn read_header<R: Read + Seek>(r: &mut R) -> Result<Header, Error> {
         return Err(Error::Compressed);
     }
 
-    Ok(Header { capacity })
+    let grain_size = u64::from_le_bytes(buf[20..28].try_into().unwrap());
+    let num_gtes_per_gt = u32::from_le_bytes(buf[44..48].try_into().unwrap());
+    let gd_offset = u64::from_le_bytes(buf[56..64].try_into().unwrap());
+
+    Ok(Header {
+        capacity,
+        grain_size,
+        num_gtes_per_gt,
+        gd_offset,
+    })
 }
 
 // ── Detection ─────────────────────────────────────────────────────────────────
@@ -217,30 +265,217 @@ fn detect_inner<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 /// └── disk.vmdk (file, size = virtual disk size in bytes)
 /// ```
 ///
-/// `file_location` is always `None` because VMDK grain data is addressed
-/// through a grain directory / grain table indirection and is not
-/// contiguous in the file. The virtual size is reported via `file_length`.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
-    let header = read_header(r)?;
-
-    // Virtual disk size in bytes. Use checked_mul to catch corrupt headers
-    // that would overflow a u64 (e.g. capacity = u64::MAX / 512 + 1).
-    let virtual_size = header
-        .capacity
-        .checked_mul(SECTOR_SIZE)
-        .ok_or(Error::BadMagic)?;
+/// VMDK grain data is addressed through a grain directory / grain table
+/// indirection and is not contiguous in the file, so `disk.vmdk` gets
+/// one extent per allocated grain and a hole for each unallocated or
+/// zeroed one. The virtual size is reported via `file_length`.
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Vmdk, None, r, parse_tree)
+}
 
-    let mut root = TreeNode::new_directory("/".to_string());
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    let mut disk = VirtualDisk::open(r)?;
+    let virtual_size = disk.len();
+    let extents = disk.extents()?;
 
-    // file_location = None: grain data is fragmented via the grain directory.
-    let mut disk_node = TreeNode::new_file("disk.vmdk".to_string(), virtual_size);
-    disk_node.file_length = Some(virtual_size);
+    let mut root = TreeNode::new_directory("/".to_string());
+    let disk_node = TreeNode::new_file_with_extents("disk.vmdk".to_string(), virtual_size, extents);
 
     root.add_child(disk_node);
     root.calculate_directory_size();
     Ok(root)
 }
 
+// ── Virtual disk reader ───────────────────────────────────────────────────────
+
+/// `Read + Seek` view of the virtual disk inside a sparse VMDK extent.
+///
+/// The grain directory is loaded by [`open`](Self::open); grain tables
+/// are read on demand, one cached at a time. Grains whose directory or
+/// table entry is 0 are unallocated and read as zeros, as are the
+/// "zeroed grain" entries (value 1) written by newer VMware versions.
+#[derive(Debug)]
+pub struct VirtualDisk<R> {
+    inner: R,
+    size: u64,
+    /// Grain size in bytes.
+    grain_bytes: u64,
+    num_gtes_per_gt: u64,
+    /// Sector offset of each grain table, by grain-directory index.
+    directory: Vec<u32>,
+    /// The most recently used grain table, keyed by directory index.
+    cached_table: Option<(usize, Vec<u32>)>,
+    pos: u64,
+}
+
+impl<R: Read + Seek> VirtualDisk<R> {
+    /// Parse the header and grain directory of `inner`.
+    pub fn open(mut inner: R) -> Result<Self, Error> {
+        let header = read_header(&mut inner)?;
+        let size = header
+            .capacity
+            .checked_mul(SECTOR_SIZE)
+            .ok_or(Error::BadGrainDirectory)?;
+        if header.grain_size == 0 || header.num_gtes_per_gt != GTES_PER_GT {
+            return Err(Error::BadGrainDirectory);
+        }
+        let grain_bytes = header
+            .grain_size
+            .checked_mul(SECTOR_SIZE)
+            .ok_or(Error::BadGrainDirectory)?;
+        let num_gtes_per_gt = header.num_gtes_per_gt as u64;
+
+        let grains = header.capacity.div_ceil(header.grain_size);
+        let tables = grains.div_ceil(num_gtes_per_gt);
+        // The grain directory must fit in the file; this also bounds the
+        // allocation against a corrupt header.
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        let gd_start = header
+            .gd_offset
+            .checked_mul(SECTOR_SIZE)
+            .ok_or(Error::BadGrainDirectory)?;
+        if tables
+            .checked_mul(4)
+            .and_then(|len| gd_start.checked_add(len))
+            .map_or(true, |end| end > file_len)
+        {
+            return Err(Error::BadGrainDirectory);
+        }
+        let mut raw = vec![0u8; tables as usize * 4];
+        inner.seek(SeekFrom::Start(gd_start))?;
+        inner.read_exact(&mut raw)?;
+        let directory = raw
+            .chunks_exact(4)
+            .map(|e| u32::from_le_bytes([e[0], e[1], e[2], e[3]]))
+            .collect();
+
+        Ok(Self {
+            inner,
+            size,
+            grain_bytes,
+            num_gtes_per_gt,
+            directory,
+            cached_table: None,
+            pos: 0,
+        })
+    }
+
+    /// Virtual disk size in bytes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// `true` iff the virtual disk is zero bytes long.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Give back the wrapped VMDK file.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The runs of the virtual disk in the file: each allocated grain,
+    /// and one hole for every stretch of unallocated grains.
+    fn extents(&mut self) -> io::Result<Vec<Extent>> {
+        let mut extents: Vec<Extent> = Vec::new();
+        let mut pos = 0;
+        while pos < self.size {
+            let (location, run) = self.map(pos)?;
+            let run = run.min(self.size - pos);
+            match (location, extents.last_mut()) {
+                (None, Some(last)) if last.location.is_none() => last.length += run,
+                (None, _) => extents.push(Extent::hole(run)),
+                (Some(at), _) => extents.push(Extent::data(at, run)),
+            }
+            pos += run;
+        }
+        Ok(extents)
+    }
+
+    /// Resolve virtual offset `pos` to a file offset (`None` for an
+    /// unallocated grain) and the number of bytes that map the same way:
+    /// the rest of the grain, or of the grain table's span when the
+    /// table itself is absent.
+    fn map(&mut self, pos: u64) -> io::Result<(Option<u64>, u64)> {
+        let grain = pos / self.grain_bytes;
+        let within = pos % self.grain_bytes;
+        let run = self.grain_bytes - within;
+        let gd_index = (grain / self.num_gtes_per_gt) as usize;
+        let gt_index = (grain % self.num_gtes_per_gt) as usize;
+
+        let gt_sector = match self.directory.get(gd_index) {
+            Some(&0) | None => {
+                let span = self.grain_bytes.saturating_mul(self.num_gtes_per_gt);
+                return Ok((None, span - pos % span));
+            }
+            Some(&sector) => sector,
+        };
+        if self.cached_table.as_ref().map(|(i, _)| *i) != Some(gd_index) {
+            let mut raw = vec![0u8; self.num_gtes_per_gt as usize * 4];
+            self.inner
+                .seek(SeekFrom::Start(gt_sector as u64 * SECTOR_SIZE))?;
+            self.inner.read_exact(&mut raw)?;
+            let table = raw
+                .chunks_exact(4)
+                .map(|e| u32::from_le_bytes([e[0], e[1], e[2], e[3]]))
+                .collect();
+            self.cached_table = Some((gd_index, table));
+        }
+        let table = &self.cached_table.as_ref().expect("just cached").1;
+        match table[gt_index] {
+            0 | 1 => Ok((None, run)),
+            sector => Ok((Some(sector as u64 * SECTOR_SIZE + within), run)),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for VirtualDisk<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let (location, run) = self.map(self.pos)?;
+        let want = run.min(self.size - self.pos).min(buf.len() as u64) as usize;
+        let n = match location {
+            None => {
+                buf[..want].fill(0);
+                want
+            }
+            Some(at) => {
+                self.inner.seek(SeekFrom::Start(at))?;
+                let n = self.inner.read(&mut buf[..want])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "VMDK grain lies past the end of the file",
+                    ));
+                }
+                n
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for VirtualDisk<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(d) => (self.size, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        self.pos = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
 // ── Unit tests ────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -312,10 +547,13 @@ mod tests {
         buf
     }
 
-    /// Build a minimal sparse VMDK image (just the 512-byte header).
+    /// Build a minimal sparse VMDK image: the 512-byte header and an
+    /// empty grain directory at sector 2, so every grain is unallocated.
     fn build_vmdk(version: u32, capacity_sectors: u64, compress_algorithm: u8) -> Vec<u8> {
         let header = build_sparse_header(version, capacity_sectors, compress_algorithm);
-        header.to_vec()
+        let mut img = header.to_vec();
+        img.resize(3 * 512, 0);
+        img
     }
 
     // ── Detection tests ───────────────────────────────────────────────────
@@ -439,7 +677,7 @@ mod tests {
 
     #[test]
     fn parse_file_location_is_none() {
-        // Grain data is fragmented via the grain directory; location is always None.
+        // No grain is allocated, so the whole disk is one hole.
         let img = build_vmdk(1, 2048, 0);
         let mut c = Cursor::new(&img);
         let root = detect_and_parse(&mut c).expect("parse sparse VMDK");
@@ -448,6 +686,7 @@ mod tests {
             disk.file_location, None,
             "sparse VMDK disk.vmdk should have file_location=None (grain directory indirection)"
         );
+        assert_eq!(disk.extents, vec![Extent::hole(2048 * 512)]);
     }
 
     // ── Error Display / source ────────────────────────────────────────────────
@@ -540,4 +779,109 @@ mod tests {
         let mut c = Cursor::new(data);
         assert!(matches!(read_header(&mut c), Err(Error::BadMagic)));
     }
+
+    // ── VirtualDisk ───────────────────────────────────────────────────────
+
+    /// Virtual size of [`build_vmdk_with_grains`] in sectors: 520
+    /// 8-sector grains, so two grain tables.
+    const GRAINS_CAPACITY: u64 = 520 * 8;
+
+    /// VMDK with 8-sector grains. GD at sector 1 → GT 0 at sectors 2-5,
+    /// GT 1 absent. GT 0: grain 0 at sector 6, grain 1 unallocated,
+    /// grain 2 at sector 14, grain 3 "zeroed".
+    fn build_vmdk_with_grains() -> Vec<u8> {
+        let mut header = build_sparse_header(1, GRAINS_CAPACITY, 0);
+        header[20..28].copy_from_slice(&8u64.to_le_bytes()); // grain_size
+        header[56..64].copy_from_slice(&1u64.to_le_bytes()); // gd_offset
+
+        let mut img = vec![0u8; 22 * 512];
+        img[..512].copy_from_slice(&header);
+        img[512..516].copy_from_slice(&2u32.to_le_bytes());
+        for (i, gte) in [6u32, 0, 14, 1].iter().enumerate() {
+            let at = 1024 + i * 4;
+            img[at..at + 4].copy_from_slice(&gte.to_le_bytes());
+        }
+        img[6 * 512..6 * 512 + 5].copy_from_slice(b"grain");
+        img[14 * 512..22 * 512].fill(0x5A);
+        img
+    }
+
+    #[test]
+    fn virtual_disk_resolves_grain_tables() {
+        let mut disk = VirtualDisk::open(Cursor::new(build_vmdk_with_grains())).unwrap();
+        assert_eq!(disk.len(), GRAINS_CAPACITY * 512);
+        let mut out = Vec::new();
+        disk.read_to_end(&mut out).unwrap();
+        assert_eq!(out.len() as u64, GRAINS_CAPACITY * 512);
+        assert_eq!(&out[..5], b"grain");
+        assert!(out[4096..8192].iter().all(|&b| b == 0), "unallocated grain");
+        assert!(out[8192..12288].iter().all(|&b| b == 0x5A));
+        assert!(
+            out[12288..].iter().all(|&b| b == 0),
+            "zeroed grain, absent GT"
+        );
+    }
+
+    #[test]
+    fn disk_node_maps_grains() {
+        let img = build_vmdk_with_grains();
+        let root = detect_and_parse(&mut Cursor::new(&img)).unwrap();
+        let node = &root.children[0];
+        assert_eq!(node.file_location, None);
+        assert_eq!(
+            node.extents,
+            vec![
+                Extent::data(6 * 512, 4096),
+                Extent::hole(4096),
+                Extent::data(14 * 512, 4096),
+                Extent::hole(GRAINS_CAPACITY * 512 - 3 * 4096),
+            ]
+        );
+
+        let mut through_node = Vec::new();
+        crate::cat_node(&mut Cursor::new(&img), node, &mut through_node).unwrap();
+        let mut through_disk = Vec::new();
+        VirtualDisk::open(Cursor::new(&img))
+            .unwrap()
+            .read_to_end(&mut through_disk)
+            .unwrap();
+        assert_eq!(through_node, through_disk);
+    }
+
+    #[test]
+    fn virtual_disk_seek_then_read_mid_grain() {
+        let mut disk = VirtualDisk::open(Cursor::new(build_vmdk_with_grains())).unwrap();
+        disk.seek(SeekFrom::Start(8192 - 2)).unwrap();
+        let mut buf = [0u8; 4];
+        disk.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 0x5A, 0x5A]);
+    }
+
+    #[test]
+    fn virtual_disk_rejects_directory_past_eof() {
+        let mut img = build_vmdk_with_grains();
+        img[56..64].copy_from_slice(&1000u64.to_le_bytes());
+        assert!(matches!(
+            VirtualDisk::open(Cursor::new(img)),
+            Err(Error::BadGrainDirectory)
+        ));
+    }
+
+    #[test]
+    fn virtual_disk_rejects_odd_grain_table_size() {
+        for gtes in [0, 4, u32::MAX] {
+            let mut img = build_vmdk_with_grains();
+            img[44..48].copy_from_slice(&gtes.to_le_bytes());
+            assert!(matches!(
+                VirtualDisk::open(Cursor::new(img)),
+                Err(Error::BadGrainDirectory)
+            ));
+        }
+    }
+
+    #[test]
+    fn error_display_bad_grain_directory() {
+        let msg = format!("{}", Error::BadGrainDirectory);
+        assert!(msg.contains("grain"), "got: {msg}");
+    }
 }
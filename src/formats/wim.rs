@@ -8,7 +8,8 @@
 //! each image (that would require LZX/XPRESS codec deps). It reads:
 //!
 //! - The 208-byte header to detect the format and extract image count.
-//! - The XML metadata blob (when uncompressed) to extract image names.
+//! - The XML metadata blob (when uncompressed) to extract image names and
+//!   their creation / last-modification times.
 //! - Returns a [`TreeNode`] tree with one directory child per image.
 //!
 //! If the XML data resource is compressed (flags bit `0x04` set) we
@@ -47,7 +48,10 @@
 
 use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Metadata, Timestamp, TreeNode};
 
 // ── Magic ──────────────────────────────────────────────────────────────────────
 
@@ -109,6 +113,17 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::TooShort | Error::BadMagic => crate::Error::not_recognized(Format::Wim, e),
+            Error::Compressed => crate::Error::unsupported(Format::Wim, e),
+            e => crate::Error::corrupt(Format::Wim, e),
+        }
+    }
+}
+
 // ── Parsed header types ────────────────────────────────────────────────────────
 
 /// Parsed RESHDR_DISK (resource header, 24 bytes).
@@ -241,6 +256,10 @@ struct ImageEntry {
     name: Option<String>,
     /// Value of the `<TOTALBYTES>` element, if present.
     total_bytes: Option<u64>,
+    /// `<CREATIONTIME>`, if present.
+    created: Option<Timestamp>,
+    /// `<LASTMODIFICATIONTIME>`, if present.
+    modified: Option<Timestamp>,
 }
 
 /// Parse UTF-16 LE bytes into a Rust `String` (lossy).
@@ -316,6 +335,8 @@ fn parse_xml(xml: &str) -> Vec<ImageEntry> {
             index,
             name,
             total_bytes,
+            created: parse_filetime(image_body, "CREATIONTIME"),
+            modified: parse_filetime(image_body, "LASTMODIFICATIONTIME"),
         });
 
         pos = image_end;
@@ -324,6 +345,20 @@ fn parse_xml(xml: &str) -> Vec<ImageEntry> {
     images
 }
 
+/// Parse a `<TAG><HIGHPART>0x…</HIGHPART><LOWPART>0x…</LOWPART></TAG>`
+/// element holding a Windows `FILETIME` split into two hex halves.
+fn parse_filetime(text: &str, tag: &str) -> Option<Timestamp> {
+    let (body, _) = extract_tag(text, tag, 0)?;
+    let half = |name: &str| -> Option<u64> {
+        let (v, _) = extract_tag(body, name, 0)?;
+        let v = v.trim();
+        let hex = v.strip_prefix("0x").or_else(|| v.strip_prefix("0X"))?;
+        u32::from_str_radix(hex, 16).ok().map(u64::from)
+    };
+    let ticks = (half("HIGHPART")? << 32) | half("LOWPART")?;
+    (ticks != 0).then(|| Timestamp::from_windows_filetime(ticks))
+}
+
 /// Parse `NAME="VALUE"` or `NAME='VALUE'` from an attribute string,
 /// returning VALUE parsed as u32.
 fn parse_attr_u32(text: &str, name: &str) -> Option<u32> {
@@ -359,16 +394,12 @@ fn parse_attr_u32(text: &str, name: &str) -> Option<u32> {
 ///
 /// Returns [`Error::Compressed`] when the XML data resource is stored
 /// compressed, and [`Error::BadMagic`] when the file is not a WIM.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
-    let header = read_header(r)?;
-
-    let xml_res = header.xml_data;
-
-    // We can only read uncompressed XML without a codec dep.
-    if xml_res.is_compressed() {
-        return Err(Error::Compressed);
-    }
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Wim, None, r, parse_tree)
+}
 
+/// Read and decode the uncompressed XML resource `xml_res`.
+fn read_xml<R: Read + Seek>(r: &mut R, xml_res: &ResHdr) -> Result<String, Error> {
     // Validate original_size before allocating: bounds-check against the
     // actual file size and impose a 64 MiB cap so a corrupt header can't
     // drive an OOM. WIM XML metadata is always well under 1 MiB in practice.
@@ -384,6 +415,7 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
     // Read the raw XML bytes.
     r.seek(SeekFrom::Start(xml_res.offset))?;
     let read_len = xml_res.original_size as usize;
+    limits::alloc(xml_res.original_size)?;
     let mut raw = vec![0u8; read_len];
     r.read_exact(&mut raw).map_err(|e| {
         if e.kind() == io::ErrorKind::UnexpectedEof {
@@ -394,7 +426,27 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
     })?;
 
     // Decode UTF-16 LE.
-    let xml_text = utf16le_to_string(&raw)?;
+    utf16le_to_string(&raw)
+}
+
+fn parse_tree<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    let header = read_header(r)?;
+
+    let xml_res = header.xml_data;
+
+    // We can only read uncompressed XML without a codec dep.
+    if xml_res.is_compressed() {
+        return Err(Error::Compressed);
+    }
+
+    // An unreadable XML resource leaves only the header's image count.
+    let xml_text = match read_xml(r, &xml_res) {
+        Ok(text) => text,
+        Err(e) => {
+            recover::skip(Some(xml_res.offset), "XML metadata", e)?;
+            String::new()
+        }
+    };
 
     // Parse image entries from XML.
     let mut entries = parse_xml(&xml_text);
@@ -414,12 +466,18 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
             root.add_child(node);
         }
     } else {
+        limits::dir_entries(entries.len() as u64)?;
         for entry in &entries {
             let name = match &entry.name {
                 Some(n) if !n.is_empty() => n.clone(),
                 _ => format!("Image {}", entry.index),
             };
-            let mut node = TreeNode::new_directory(name);
+            limits::entry(&name)?;
+            let mut node = TreeNode::new_directory(name).with_metadata(Metadata {
+                btime: entry.created,
+                mtime: entry.modified,
+                ..Metadata::new(EntryKind::Directory)
+            });
             // Populate size from TOTALBYTES if available.
             if let Some(tb) = entry.total_bytes {
                 node.size = tb;
@@ -614,6 +672,22 @@ mod tests {
         assert_eq!(entries[1].total_bytes, Some(8_000_000_000));
     }
 
+    #[test]
+    fn parse_xml_image_times() {
+        let xml = r#"<WIM><IMAGE INDEX="1"><NAME>X</NAME>
+<CREATIONTIME><HIGHPART>0x01BF53EB</HIGHPART><LOWPART>0x256D4000</LOWPART></CREATIONTIME>
+<LASTMODIFICATIONTIME><HIGHPART>0x01BF53EB</HIGHPART><LOWPART>0x2605D680</LOWPART></LASTMODIFICATIONTIME>
+</IMAGE></WIM>"#;
+        let images = parse_xml(xml);
+        // 0x01BF53EB_256D4000 is 2000-01-01T00:00:00Z; the second is +1 s.
+        assert_eq!(images[0].created, Some(Timestamp::new(946_684_800, 0)));
+        assert_eq!(images[0].modified, Some(Timestamp::new(946_684_801, 0)));
+        assert_eq!(
+            parse_filetime("<CREATIONTIME></CREATIONTIME>", "CREATIONTIME"),
+            None
+        );
+    }
+
     #[test]
     fn parse_xml_empty_wim() {
         let entries = parse_xml("<WIM></WIM>");
@@ -16,7 +16,12 @@
 //!   without decompression.
 //! - Directory entries and path components are reconstructed from the
 //!   `/`-delimited names in the central directory.
-//! - ZIP file comments and extra-field extensions are skipped gracefully.
+//! - Entry metadata: the DOS modification time (overridden by the `UT`
+//!   extended-timestamp extra field when present) and, for archives made on
+//!   Unix, the mode bits and file type in the external attributes. Stored
+//!   symlinks carry their target.
+//! - ZIP file comments and other extra-field extensions are skipped
+//!   gracefully.
 //!
 //! ## What is NOT implemented
 //!
@@ -28,10 +33,16 @@
 //!   `cat_node` returns an error rather than exposing ciphertext.
 //! - Multi-volume / split archives.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::tree::TreeNode;
+use crate::image_io::source::{self, Borrowed, Bytes, Source, Stream};
+use crate::image_io::RandomAccess;
+use crate::limits;
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Metadata, Timestamp, TreeNode};
 
 // ── Magic / signature constants ───────────────────────────────────────────────
 
@@ -51,6 +62,15 @@ const MAX_EOCD_SEARCH: u64 = 65535 + EOCD_MIN_SIZE;
 const FLAG_ENCRYPTED: u16 = 0x0001;
 const METHOD_STORED: u16 = 0;
 
+/// "Version made by" host systems whose external attributes carry a Unix
+/// mode in the high 16 bits.
+const HOST_UNIX: u8 = 3;
+const HOST_OSX: u8 = 19;
+/// Extended-timestamp ("UT") extra field tag.
+const EXTRA_EXTENDED_TIMESTAMP: u16 = 0x5455;
+/// Longest symlink target we will read from a stored entry.
+const MAX_SYMLINK_LEN: u64 = 4096;
+
 // ── Error type ────────────────────────────────────────────────────────────────
 
 /// Errors that can arise while detecting or parsing a ZIP archive.
@@ -92,6 +112,16 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e.into(),
+            Error::NotZip => crate::Error::not_recognized(Format::Zip, e),
+            e => crate::Error::corrupt(Format::Zip, e),
+        }
+    }
+}
+
 // ── EOCD location ─────────────────────────────────────────────────────────────
 
 struct EocdInfo {
@@ -99,17 +129,15 @@ struct EocdInfo {
     cd_size: u64,
 }
 
-fn find_eocd<R: Read + Seek>(r: &mut R) -> Result<EocdInfo, Error> {
-    let file_len = r.seek(SeekFrom::End(0))?;
+fn find_eocd<'a, S: Source<'a>>(src: &mut S) -> Result<EocdInfo, Error> {
+    let file_len = src.len()?;
     if file_len < EOCD_MIN_SIZE {
         return Err(Error::NotZip);
     }
 
     let search_start = file_len.saturating_sub(MAX_EOCD_SEARCH);
     let search_len = (file_len - search_start) as usize;
-    r.seek(SeekFrom::Start(search_start))?;
-    let mut buf = vec![0u8; search_len];
-    r.read_exact(&mut buf)?;
+    let buf = src.bytes(search_start, search_len)?;
 
     // Scan backwards for EOCD signature.
     let eocd_pos = buf
@@ -134,16 +162,12 @@ fn find_eocd<R: Read + Seek>(r: &mut R) -> Result<EocdInfo, Error> {
             return Err(Error::NotZip);
         }
         let locator_abs = eocd_abs - EOCD64_LOCATOR_SIZE;
-        r.seek(SeekFrom::Start(locator_abs))?;
-        let mut loc = [0u8; 20];
-        r.read_exact(&mut loc)?;
+        let loc = src.bytes(locator_abs, EOCD64_LOCATOR_SIZE as usize)?;
         if u32::from_le_bytes([loc[0], loc[1], loc[2], loc[3]]) != EOCD64_LOCATOR_SIG {
             return Err(Error::NotZip);
         }
         let eocd64_abs = u64::from_le_bytes(loc[8..16].try_into().unwrap());
-        r.seek(SeekFrom::Start(eocd64_abs))?;
-        let mut e64 = [0u8; EOCD64_SIZE as usize];
-        r.read_exact(&mut e64)?;
+        let e64 = src.bytes(eocd64_abs, EOCD64_SIZE as usize)?;
         if u32::from_le_bytes([e64[0], e64[1], e64[2], e64[3]]) != EOCD64_SIG {
             return Err(Error::NotZip);
         }
@@ -160,17 +184,19 @@ fn find_eocd<R: Read + Seek>(r: &mut R) -> Result<EocdInfo, Error> {
 
 // ── Central directory parsing ─────────────────────────────────────────────────
 
-struct CdEntry {
+struct CdEntry<'a> {
     /// Slash-delimited name as stored in the CD (may end with `/` for dirs).
-    name: String,
+    name: Cow<'a, str>,
     method: u16,
     is_encrypted: bool,
     uncompressed_size: u64,
     /// Byte offset of the local file header for this entry.
     local_header_offset: u64,
+    metadata: Metadata,
 }
 
-fn parse_central_directory(buf: &[u8]) -> Result<Vec<CdEntry>, Error> {
+fn parse_central_directory<'a>(cd: Bytes<'a, '_>) -> Result<Vec<CdEntry<'a>>, Error> {
+    let buf = &*cd;
     let mut entries = Vec::new();
     let mut pos = 0usize;
 
@@ -180,9 +206,12 @@ fn parse_central_directory(buf: &[u8]) -> Result<Vec<CdEntry>, Error> {
             break;
         }
         if pos + 46 > buf.len() {
-            return Err(Error::BadCentralDirectory);
+            // Keep the entries before the damaged one.
+            recover::skip(None, "central directory entry", Error::BadCentralDirectory)?;
+            break;
         }
 
+        let host_system = buf[pos + 5];
         let general_flags = u16::from_le_bytes([buf[pos + 8], buf[pos + 9]]);
         let method = u16::from_le_bytes([buf[pos + 10], buf[pos + 11]]);
         let compressed_size =
@@ -192,16 +221,19 @@ fn parse_central_directory(buf: &[u8]) -> Result<Vec<CdEntry>, Error> {
         let name_len = u16::from_le_bytes([buf[pos + 28], buf[pos + 29]]) as usize;
         let extra_len = u16::from_le_bytes([buf[pos + 30], buf[pos + 31]]) as usize;
         let comment_len = u16::from_le_bytes([buf[pos + 32], buf[pos + 33]]) as usize;
+        let external_attrs =
+            u32::from_le_bytes([buf[pos + 38], buf[pos + 39], buf[pos + 40], buf[pos + 41]]);
         let local_header_offset =
             u32::from_le_bytes([buf[pos + 42], buf[pos + 43], buf[pos + 44], buf[pos + 45]]) as u64;
 
         let name_start = pos + 46;
         let name_end = name_start + name_len;
         if name_end > buf.len() {
-            return Err(Error::BadCentralDirectory);
+            recover::skip(None, "central directory entry", Error::BadCentralDirectory)?;
+            break;
         }
 
-        let name = String::from_utf8_lossy(&buf[name_start..name_end]).into_owned();
+        let name = cd.slice(name_start..name_end).utf8_lossy();
 
         // Resolve ZIP64 extra field if any sentinel values are present.
         let extra_start = name_end;
@@ -217,12 +249,29 @@ fn parse_central_directory(buf: &[u8]) -> Result<Vec<CdEntry>, Error> {
             (compressed_size, uncompressed_size, local_header_offset)
         };
 
+        let mut metadata = Metadata {
+            mtime: Timestamp::from_dos(
+                u16::from_le_bytes([buf[pos + 14], buf[pos + 15]]),
+                u16::from_le_bytes([buf[pos + 12], buf[pos + 13]]),
+            ),
+            ..Metadata::default()
+        };
+        if let Some(mtime) = extended_mtime(&buf[extra_start..extra_end]) {
+            metadata.mtime = Some(mtime);
+        }
+        let unix_mode = external_attrs >> 16;
+        if matches!(host_system, HOST_UNIX | HOST_OSX) && unix_mode != 0 {
+            metadata.kind = EntryKind::from_posix_mode(unix_mode);
+            metadata.mode = Some(unix_mode & 0o7777);
+        }
+
         entries.push(CdEntry {
             name,
             method,
             is_encrypted: general_flags & FLAG_ENCRYPTED != 0,
             uncompressed_size: uncomp,
             local_header_offset: lh_off,
+            metadata,
         });
 
         pos = name_end + extra_len + comment_len;
@@ -264,13 +313,46 @@ fn parse_zip64_extra(extra: &[u8], comp: u64, uncomp: u64, offset: u64) -> (u64,
     (comp_out, uncomp_out, offset_out)
 }
 
+/// Find the modification time in a `UT` extended-timestamp extra field.
+/// The central-directory copy holds only a flags byte and, when bit 0 is
+/// set, a signed 32-bit Unix mtime.
+fn extended_mtime(extra: &[u8]) -> Option<Timestamp> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let tag = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let size = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        pos += 4;
+        let field = extra.get(pos..pos + size)?;
+        if tag == EXTRA_EXTENDED_TIMESTAMP && size >= 5 && field[0] & 1 != 0 {
+            let secs = i32::from_le_bytes(field[1..5].try_into().unwrap());
+            return Some(Timestamp::new(secs as i64, 0));
+        }
+        pos += size;
+    }
+    None
+}
+
+/// Read a stored symlink's target (the entry's data) at `offset`.
+fn read_link_target<'a, S: Source<'a>>(src: &mut S, offset: u64, len: u64) -> Option<String> {
+    if len > MAX_SYMLINK_LEN {
+        return None;
+    }
+    let target = src.bytes(offset, len as usize).ok()?;
+    Some(String::from_utf8_lossy(&target).into_owned())
+}
+
 /// Compute the byte offset of the actual file data by reading the local
 /// file header at `lh_offset`. Returns `None` if the header is invalid.
-fn local_data_offset<R: Read + Seek>(r: &mut R, lh_offset: u64) -> Option<u64> {
-    r.seek(SeekFrom::Start(lh_offset)).ok()?;
-    let mut hdr = [0u8; 30];
-    r.read_exact(&mut hdr).ok()?;
+fn local_data_offset<'a, S: Source<'a>>(src: &mut S, lh_offset: u64) -> Option<u64> {
+    let hdr = match src.bytes(lh_offset, 30) {
+        Ok(hdr) => hdr,
+        Err(e) => {
+            recover::note(Some(lh_offset), "local file header", e);
+            return None;
+        }
+    };
     if u32::from_le_bytes([hdr[0], hdr[1], hdr[2], hdr[3]]) != LFH_SIG {
+        recover::note(Some(lh_offset), "local file header", "bad signature");
         return None;
     }
     // Defend against mismatched CD/LFH flags: if the LFH marks the entry as
@@ -291,7 +373,7 @@ fn local_data_offset<R: Read + Seek>(r: &mut R, lh_offset: u64) -> Option<u64> {
 /// The `HashMap` maps each slash-path (without leading slash, without trailing
 /// slash) to its node. After all entries are inserted we do a single pass to
 /// wire parent→child relationships.
-fn build_tree<R: Read + Seek>(r: &mut R, entries: Vec<CdEntry>) -> TreeNode {
+fn build_tree<'a, S: Source<'a>>(src: &mut S, entries: Vec<CdEntry<'a>>) -> TreeNode<'a> {
     // path (no leading slash) → node
     let mut nodes: HashMap<String, TreeNode> = HashMap::new();
 
@@ -303,29 +385,42 @@ fn build_tree<R: Read + Seek>(r: &mut R, entries: Vec<CdEntry>) -> TreeNode {
 
         // Ensure every ancestor directory exists.
         let mut acc = String::new();
-        for (i, component) in raw.split('/').enumerate() {
+        for (i, component) in source::split_path(&entry.name).into_iter().enumerate() {
             if component.is_empty() {
                 continue;
             }
             if i > 0 {
                 acc.push('/');
             }
-            acc.push_str(component);
+            acc.push_str(&component);
             nodes
                 .entry(acc.clone())
-                .or_insert_with(|| TreeNode::new_directory(component.to_string()));
+                .or_insert_with(|| TreeNode::new_directory(component));
         }
 
         // Update the leaf with file metadata.
         let is_dir = entry.name.ends_with('/') || entry.name.ends_with('\\');
-        if !is_dir {
-            if let Some(node) = nodes.get_mut(raw) {
+        if let Some(node) = nodes.get_mut(raw) {
+            if is_dir {
+                node.metadata = Metadata {
+                    kind: EntryKind::Directory,
+                    ..entry.metadata.clone()
+                };
+            } else {
                 node.is_directory = false;
                 node.size = entry.uncompressed_size;
                 node.file_length = Some(entry.uncompressed_size);
                 if entry.method == METHOD_STORED && !entry.is_encrypted {
-                    node.file_location = local_data_offset(r, entry.local_header_offset);
+                    node.file_location = local_data_offset(src, entry.local_header_offset);
                 }
+                let mut metadata = entry.metadata.clone();
+                if metadata.kind == EntryKind::Directory {
+                    metadata.kind = EntryKind::File;
+                }
+                if let (EntryKind::Symlink, Some(location)) = (metadata.kind, node.file_location) {
+                    metadata.link_target = read_link_target(src, location, entry.uncompressed_size);
+                }
+                node.metadata = metadata;
             }
         }
     }
@@ -346,10 +441,10 @@ fn build_tree<R: Read + Seek>(r: &mut R, entries: Vec<CdEntry>) -> TreeNode {
     }
 
     // Recursive attachment using a helper that drains `nodes`.
-    fn attach(
-        node: &mut TreeNode,
+    fn attach<'a>(
+        node: &mut TreeNode<'a>,
         key: &str,
-        nodes: &mut HashMap<String, TreeNode>,
+        nodes: &mut HashMap<String, TreeNode<'a>>,
         children_of: &HashMap<String, Vec<String>>,
     ) {
         if let Some(child_keys) = children_of.get(key) {
@@ -364,7 +459,7 @@ fn build_tree<R: Read + Seek>(r: &mut R, entries: Vec<CdEntry>) -> TreeNode {
         }
     }
 
-    let mut root = TreeNode::new_directory("/".to_string());
+    let mut root = TreeNode::new_directory("/");
     attach(&mut root, "", &mut nodes, &children_of);
     root.calculate_directory_size();
     root
@@ -376,7 +471,7 @@ fn build_tree<R: Read + Seek>(r: &mut R, entries: Vec<CdEntry>) -> TreeNode {
 /// Stream position is restored on both success and failure.
 pub fn detect<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
     let saved = r.stream_position().unwrap_or(0);
-    let result = find_eocd(r).map(|_| ());
+    let result = find_eocd(&mut Stream(&mut *r)).map(|_| ());
     let _ = r.seek(SeekFrom::Start(saved));
     result
 }
@@ -385,10 +480,22 @@ pub fn detect<R: Read + Seek>(r: &mut R) -> Result<(), Error> {
 ///
 /// The root node is named `"/"`. Stored (uncompressed) files have
 /// `file_location` set so `cat_node` can read them directly.
-pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
-    let eocd = find_eocd(r)?;
+pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode<'static>, Error> {
+    limits::metered(Format::Zip, None, r, |r| parse_tree(&mut Stream(r)))
+}
 
-    let file_len = r.seek(SeekFrom::End(0))?;
+/// Parse a ZIP archive that is already in memory or mapped.
+///
+/// Entry names borrow from the central directory in `image` when they
+/// are valid UTF-8.
+pub fn detect_and_parse_image<R: RandomAccess + ?Sized>(image: &R) -> Result<TreeNode<'_>, Error> {
+    limits::scope(Format::Zip, None, || parse_tree(&mut Borrowed(image)))
+}
+
+fn parse_tree<'a, S: Source<'a>>(src: &mut S) -> Result<TreeNode<'a>, Error> {
+    let eocd = find_eocd(src)?;
+
+    let file_len = src.len()?;
     let cd_end = eocd
         .cd_offset
         .checked_add(eocd.cd_size)
@@ -397,12 +504,12 @@ pub fn detect_and_parse<R: Read + Seek>(r: &mut R) -> Result<TreeNode, Error> {
         return Err(Error::BadCentralDirectory);
     }
 
-    r.seek(SeekFrom::Start(eocd.cd_offset))?;
-    let mut cd_buf = vec![0u8; eocd.cd_size as usize];
-    r.read_exact(&mut cd_buf)?;
+    limits::alloc(eocd.cd_size)?;
+    let cd = src.bytes(eocd.cd_offset, eocd.cd_size as usize)?;
 
-    let entries = parse_central_directory(&cd_buf)?;
-    Ok(build_tree(r, entries))
+    let entries = parse_central_directory(Bytes::of(&cd))?;
+    limits::paths(entries.iter().map(|e| &*e.name))?;
+    Ok(build_tree(src, entries))
 }
 
 // ── Write API (`write` feature) ───────────────────────────────────────────────
@@ -654,6 +761,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unix_entry_carries_mode_mtime_and_symlink_target() {
+        let mut zip = make_stored_zip(b"link", b"target.txt");
+        let cd = 30 + 4 + 10;
+        zip[cd + 5] = HOST_UNIX;
+        let date: u16 = ((2001 - 1980) << 9) | (9 << 5) | 9;
+        let time: u16 = (12 << 11) | (30 << 5);
+        zip[cd + 12..cd + 14].copy_from_slice(&time.to_le_bytes());
+        zip[cd + 14..cd + 16].copy_from_slice(&date.to_le_bytes());
+        zip[cd + 38..cd + 42].copy_from_slice(&(0o120777u32 << 16).to_le_bytes());
+        let root = detect_and_parse(&mut Cursor::new(&zip)).unwrap();
+        let m = &root.find_node("/link").unwrap().metadata;
+        assert_eq!(m.kind, EntryKind::Symlink);
+        assert_eq!(m.mode, Some(0o777));
+        assert_eq!(m.mtime, Timestamp::from_civil(2001, 9, 9, 12, 30, 0));
+        assert_eq!(m.link_target.as_deref(), Some("target.txt"));
+    }
+
+    #[test]
+    fn non_unix_host_leaves_mode_unset() {
+        let mut zip = make_stored_zip(b"a.txt", b"x");
+        let cd = 30 + 5 + 1;
+        zip[cd + 38..cd + 42].copy_from_slice(&0x20u32.to_le_bytes()); // DOS archive bit
+        let root = detect_and_parse(&mut Cursor::new(&zip)).unwrap();
+        let m = &root.find_node("/a.txt").unwrap().metadata;
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!(m.mode, None);
+        assert_eq!(m.mtime, None);
+    }
+
+    #[test]
+    fn extended_timestamp_extra_gives_mtime() {
+        let mut extra = vec![0x99, 0x99, 0, 0]; // empty unknown field first
+        extra.extend_from_slice(&EXTRA_EXTENDED_TIMESTAMP.to_le_bytes());
+        extra.extend_from_slice(&5u16.to_le_bytes());
+        extra.push(1);
+        extra.extend_from_slice(&946_684_800i32.to_le_bytes());
+        assert_eq!(extended_mtime(&extra), Some(Timestamp::new(946_684_800, 0)));
+        assert_eq!(extended_mtime(&extra[..4]), None);
+    }
+
     #[test]
     fn detect_rejects_non_zip() {
         let mut c = Cursor::new(b"this is not a zip file at all");
@@ -674,6 +822,22 @@ mod tests {
         assert_eq!(root.children[0].children[0].children[0].name, "c.txt");
     }
 
+    #[test]
+    fn image_names_borrow_from_the_central_directory() {
+        let zip = make_stored_zip(b"a/b/c.txt", b"nested");
+        let root = detect_and_parse_image(&zip).unwrap();
+        let c = root.find_node("a/b/c.txt").unwrap();
+        assert!(matches!(c.name, Cow::Borrowed("c.txt")));
+        assert!(matches!(root.children[0].name, Cow::Borrowed("a")));
+        let streamed = detect_and_parse(&mut Cursor::new(&zip)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+
+        // Invalid UTF-8 is replaced, so the name is owned.
+        let zip = make_stored_zip(b"bad\xff.txt", b"x");
+        let root = detect_and_parse_image(&zip).unwrap();
+        assert!(matches!(root.children[0].name, Cow::Owned(_)));
+    }
+
     #[test]
     fn directory_size_roll_up() {
         let zip = make_stored_zip(b"docs/readme.txt", b"hello world");
@@ -920,7 +1084,7 @@ mod tests {
         buf.extend_from_slice(&CDR_SIG.to_le_bytes());
         buf.extend_from_slice(&[0u8; 20]); // only 24 bytes total, need 46
         assert!(matches!(
-            parse_central_directory(&buf),
+            parse_central_directory(Bytes::Buffer(&buf)),
             Err(Error::BadCentralDirectory)
         ));
     }
@@ -933,7 +1097,7 @@ mod tests {
         // name_len at offset 28 = 100, but only 0 bytes follow
         buf[28..30].copy_from_slice(&100u16.to_le_bytes());
         assert!(matches!(
-            parse_central_directory(&buf),
+            parse_central_directory(Bytes::Buffer(&buf)),
             Err(Error::BadCentralDirectory)
         ));
     }
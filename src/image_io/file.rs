@@ -0,0 +1,467 @@
+//! Positional-read file image with a block cache.
+//!
+//! [`FileImage`] is the [`RandomAccessMut`] counterpart to
+//! `MmapImage` for images that shouldn't be mapped: on NFS or SMB a
+//! page fault can stall for seconds, and an I/O error turns into
+//! `SIGBUS` instead of an `Err`. Every read here is a `pread` (a
+//! `seek_read` on Windows) of whole blocks, and the most recently used
+//! blocks stay in memory.
+//!
+//! The defaults are tuned for parsers, which make many small reads —
+//! volume descriptors, directory records, inodes — clustered around a
+//! few places in the image:
+//!
+//! - **Small blocks** (32 KiB), so a scattered lookup doesn't drag in
+//!   megabytes it won't use.
+//! - **Adaptive read-ahead**: a miss on the block right after the last
+//!   one loaded reads the next 32 blocks in one call; a miss anywhere
+//!   else reads just its own block. Walking a large directory or a
+//!   file sequentially costs one syscall per MiB, while hopping
+//!   between records costs one per block.
+//! - **Large reads bypass the cache.** A read bigger than the
+//!   read-ahead window (file data being copied out) goes straight
+//!   into the caller's buffer and leaves the cached metadata alone.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::{RandomAccessMut, ReadAt};
+
+const DEFAULT_BLOCK_SIZE: usize = 32 * 1024;
+const DEFAULT_CACHE_BLOCKS: usize = 256;
+const DEFAULT_READ_AHEAD: usize = 32;
+
+/// A disc image read with positional reads through an LRU block cache.
+///
+/// Implements [`RandomAccessMut`] for borrowed-slice reads and
+/// `Read + Seek`, so it can be handed to
+/// [`detect_and_parse_filesystem`](crate::detect_and_parse_filesystem)
+/// and the other stream entry points in place of a `File`.
+///
+/// The cache assumes the image doesn't change while it's open.
+///
+/// # Example
+///
+/// ```no_run
+/// use isomage::image_io::{FileImage, RandomAccessMut};
+///
+/// let mut img = FileImage::open("/mnt/nfs/disc.iso")?
+///     .block_size(16 * 1024)
+///     .cache_blocks(1024);
+/// // The Standard Identifier of the PVD, ECMA-119 §8.4.2.
+/// assert_eq!(img.read_at_mut(32768 + 1, 5)?, b"CD001");
+/// let root = isomage::detect_and_parse_filesystem(&mut img, "disc.iso")?;
+/// println!("{:?}", img.stats());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct FileImage {
+    file: File,
+    len: u64,
+    block_size: usize,
+    capacity: usize,
+    read_ahead: usize,
+    blocks: Vec<Block>,
+    /// Block number → index into `blocks`.
+    slots: HashMap<u64, usize>,
+    /// Bumped on every block access; the block with the lowest
+    /// `used` is the least recently used.
+    tick: u64,
+    /// The highest block loaded by the last miss, to spot sequential
+    /// access.
+    last_loaded: Option<u64>,
+    scratch: Vec<u8>,
+    cursor: u64,
+    stats: CacheStats,
+}
+
+#[derive(Debug)]
+struct Block {
+    number: u64,
+    data: Vec<u8>,
+    used: u64,
+}
+
+/// Counters for tuning a [`FileImage`]'s cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// Block lookups served from memory.
+    pub hits: u64,
+    /// Block lookups that had to read from the file.
+    pub misses: u64,
+    /// Positional reads issued, including ones that bypassed the
+    /// cache. Read-ahead makes this lower than `misses`.
+    pub reads: u64,
+    /// Bytes read from the file.
+    pub bytes: u64,
+}
+
+impl FileImage {
+    /// Open `path` read-only with the default cache: 32 KiB blocks,
+    /// 256 of them (8 MiB), and 32 blocks of read-ahead.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+
+    /// Wrap an already-open file. Its current position is irrelevant;
+    /// `FileImage` never moves it.
+    pub fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file,
+            len,
+            block_size: DEFAULT_BLOCK_SIZE,
+            capacity: DEFAULT_CACHE_BLOCKS,
+            read_ahead: DEFAULT_READ_AHEAD,
+            blocks: Vec::new(),
+            slots: HashMap::new(),
+            tick: 0,
+            last_loaded: None,
+            scratch: Vec::new(),
+            cursor: 0,
+            stats: CacheStats::default(),
+        })
+    }
+
+    /// Set the cache block size in bytes (at least 512) and empty the
+    /// cache.
+    pub fn block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes.max(512);
+        self.clear_cache();
+        self
+    }
+
+    /// Set how many blocks the cache holds (at least 1), emptying it.
+    pub fn cache_blocks(mut self, count: usize) -> Self {
+        self.capacity = count.max(1);
+        self.clear_cache();
+        self
+    }
+
+    /// Set how many blocks a sequential miss loads at once. `1`
+    /// turns read-ahead off. Capped at the cache size.
+    pub fn read_ahead(mut self, blocks: usize) -> Self {
+        self.read_ahead = blocks.max(1);
+        self
+    }
+
+    /// Total image size in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// `true` iff the image is zero bytes (an empty file).
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hit, miss and read counts since the image was opened.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Drop every cached block.
+    pub fn clear_cache(&mut self) {
+        self.blocks.clear();
+        self.slots.clear();
+        self.last_loaded = None;
+    }
+
+    /// Give back the file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+
+    fn window(&self) -> usize {
+        self.read_ahead.min(self.capacity)
+    }
+
+    /// Whether a read of `len` bytes skips the cache.
+    fn bypasses(&self, len: usize) -> bool {
+        len > self.block_size * self.window()
+    }
+
+    fn pread(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.stats.reads += 1;
+        self.stats.bytes += buf.len() as u64;
+        self.file.read_exact_at(buf, offset)
+    }
+
+    /// Index into `blocks` of block `number`, loading it (and any
+    /// read-ahead) on a miss.
+    fn block(&mut self, number: u64) -> io::Result<usize> {
+        self.tick += 1;
+        if let Some(&slot) = self.slots.get(&number) {
+            self.stats.hits += 1;
+            self.blocks[slot].used = self.tick;
+            return Ok(slot);
+        }
+        self.stats.misses += 1;
+
+        let block_size = self.block_size as u64;
+        let total = self.len.div_ceil(block_size);
+        let sequential = self.last_loaded.and_then(|b| b.checked_add(1)) == Some(number);
+        let wanted = if sequential { self.window() } else { 1 };
+        // Stop the run at the image end or at a block already cached.
+        let count = (1..wanted as u64)
+            .take_while(|i| number + i < total && !self.slots.contains_key(&(number + i)))
+            .count() as u64
+            + 1;
+
+        let start = number * block_size;
+        let end = (start + count * block_size).min(self.len);
+        let mut buf = std::mem::take(&mut self.scratch);
+        buf.resize((end - start) as usize, 0);
+        let read = self.pread(&mut buf, start);
+        if let Err(e) = read {
+            self.scratch = buf;
+            return Err(e);
+        }
+        // Insert the requested block last so it is the most recently
+        // used and never the one evicted for its own read-ahead.
+        for i in (0..count).rev() {
+            let from = (i * block_size) as usize;
+            let to = (from + self.block_size).min(buf.len());
+            self.insert(number + i, &buf[from..to]);
+        }
+        self.scratch = buf;
+        self.last_loaded = Some(number + count - 1);
+        Ok(self.slots[&number])
+    }
+
+    fn insert(&mut self, number: u64, bytes: &[u8]) {
+        self.tick += 1;
+        let slot = if self.blocks.len() < self.capacity {
+            self.blocks.push(Block {
+                number,
+                data: Vec::with_capacity(self.block_size),
+                used: 0,
+            });
+            self.blocks.len() - 1
+        } else {
+            let (slot, victim) = self
+                .blocks
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, block)| block.used)
+                .map(|(slot, block)| (slot, block.number))
+                .expect("cache holds at least one block");
+            self.slots.remove(&victim);
+            slot
+        };
+        let block = &mut self.blocks[slot];
+        block.number = number;
+        block.used = self.tick;
+        block.data.clear();
+        block.data.extend_from_slice(bytes);
+        self.slots.insert(number, slot);
+    }
+}
+
+impl RandomAccessMut for FileImage {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Reads inside one block borrow straight from the cache; reads
+    /// across blocks, or too large to cache, are assembled in a
+    /// scratch buffer.
+    fn read_at_mut(&mut self, offset: u64, len: usize) -> io::Result<&[u8]> {
+        let end = offset
+            .checked_add(len as u64)
+            .filter(|&end| end <= self.len)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "read past image end"))?;
+        if len == 0 {
+            return Ok(&[]);
+        }
+        if self.bypasses(len) {
+            let mut buf = std::mem::take(&mut self.scratch);
+            buf.resize(len, 0);
+            let read = self.pread(&mut buf, offset);
+            self.scratch = buf;
+            read?;
+            return Ok(&self.scratch[..len]);
+        }
+
+        let block_size = self.block_size as u64;
+        let (first, last) = (offset / block_size, (end - 1) / block_size);
+        if first == last {
+            let slot = self.block(first)?;
+            let from = (offset - first * block_size) as usize;
+            return Ok(&self.blocks[slot].data[from..from + len]);
+        }
+        let mut out = std::mem::take(&mut self.scratch);
+        out.clear();
+        for number in first..=last {
+            let slot = match self.block(number) {
+                Ok(slot) => slot,
+                Err(e) => {
+                    self.scratch = out;
+                    return Err(e);
+                }
+            };
+            let base = number * block_size;
+            let from = offset.max(base) - base;
+            let to = end.min(base + block_size) - base;
+            out.extend_from_slice(&self.blocks[slot].data[from as usize..to as usize]);
+        }
+        // `block` reuses `scratch` for its own reads, so the result is
+        // only put there once every block is in.
+        self.scratch = out;
+        Ok(&self.scratch)
+    }
+}
+
+impl Read for FileImage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.cursor);
+        let n = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+        if n == 0 {
+            return Ok(0);
+        }
+        if self.bypasses(n) {
+            let offset = self.cursor;
+            self.pread(&mut buf[..n], offset)?;
+        } else {
+            let offset = self.cursor;
+            buf[..n].copy_from_slice(self.read_at_mut(offset, n)?);
+        }
+        self.cursor += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for FileImage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(n) => self.cursor.checked_add_signed(n),
+            SeekFrom::End(n) => self.len.checked_add_signed(n),
+        };
+        self.cursor = new_pos
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// 10 000 bytes where byte `i` is `i % 251`, so misplaced reads
+    /// show up as wrong values.
+    fn image() -> (tempfile::NamedTempFile, Vec<u8>) {
+        let bytes: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        tmp.write_all(&bytes).unwrap();
+        (tmp, bytes)
+    }
+
+    fn open(tmp: &tempfile::NamedTempFile) -> FileImage {
+        FileImage::open(tmp.path())
+            .unwrap()
+            .block_size(512)
+            .cache_blocks(4)
+            .read_ahead(2)
+    }
+
+    #[test]
+    fn reads_match_the_file() {
+        let (tmp, bytes) = image();
+        let mut img = open(&tmp);
+        assert_eq!(RandomAccessMut::len(&img), 10_000);
+        for (offset, len) in [
+            (0, 16),
+            (500, 30),
+            (511, 2),
+            (1000, 1500),
+            (9_990, 10),
+            (3, 0),
+        ] {
+            let got = img.read_at_mut(offset as u64, len).unwrap();
+            assert_eq!(got, &bytes[offset..offset + len], "{}+{}", offset, len);
+        }
+        let err = img.read_at_mut(9_995, 6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = img.read_at_mut(u64::MAX, 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn sequential_misses_read_ahead() {
+        let (tmp, _) = image();
+        let mut img = open(&tmp);
+        // Block 0 is a cold miss (one block); block 1 follows it and
+        // pulls in block 2 as well.
+        for offset in [0, 512, 1024] {
+            img.read_at_mut(offset, 100).unwrap();
+        }
+        let stats = img.stats();
+        assert_eq!((stats.hits, stats.misses, stats.reads), (1, 2, 2));
+        // A jump elsewhere reads only its own block.
+        img.read_at_mut(8_000, 10).unwrap();
+        assert_eq!(img.stats().bytes, 512 + 1024 + 512);
+    }
+
+    #[test]
+    fn least_recently_used_block_is_evicted() {
+        let (tmp, _) = image();
+        let mut img = FileImage::open(tmp.path())
+            .unwrap()
+            .block_size(512)
+            .cache_blocks(2)
+            .read_ahead(1);
+        for offset in [0, 2048, 0, 4096] {
+            img.read_at_mut(offset, 1).unwrap();
+        }
+        // Block 4 (offset 2048) was the least recently used.
+        img.read_at_mut(0, 1).unwrap();
+        assert_eq!(img.stats().hits, 2);
+        img.read_at_mut(2048, 1).unwrap();
+        assert_eq!(img.stats().misses, 4);
+    }
+
+    #[test]
+    fn large_reads_bypass_the_cache() {
+        let (tmp, bytes) = image();
+        let mut img = open(&tmp);
+        let got = img.read_at_mut(100, 5_000).unwrap().to_vec();
+        assert_eq!(got, &bytes[100..5_100]);
+        let stats = img.stats();
+        assert_eq!((stats.misses, stats.reads), (0, 1));
+    }
+
+    #[test]
+    fn read_and_seek() {
+        let (tmp, bytes) = image();
+        let mut img = open(&tmp);
+        img.seek(SeekFrom::End(-20)).unwrap();
+        let mut tail = Vec::new();
+        img.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &bytes[9_980..]);
+        img.seek(SeekFrom::Start(700)).unwrap();
+        let mut buf = [0u8; 600];
+        img.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, bytes[700..1_300]);
+        assert!(img.seek(SeekFrom::Current(-2_000)).is_err());
+    }
+
+    #[test]
+    fn parses_an_iso() {
+        let path = "test_data/test_linux.iso";
+        if !Path::new(path).exists() {
+            eprintln!("Skipping test: {} not found", path);
+            return;
+        }
+        let mut img = FileImage::open(path).unwrap();
+        let root = crate::detect_and_parse_filesystem(&mut img, path).unwrap();
+        let mut file = File::open(path).unwrap();
+        let expected = crate::detect_and_parse_filesystem(&mut file, path).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", expected));
+        assert!(img.stats().hits > 0);
+    }
+}
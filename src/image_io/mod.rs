@@ -15,19 +15,38 @@
 //! `MmapImage` (`--features mmap`), the parser path is unchanged
 //! but the underlying syscalls disappear into the page cache.
 //!
-//! The eventual `TreeNode.name → Cow<'a, str>` refactor (planned
-//! for v3.0) will let parsers borrow names directly out of the
-//! mmap slice. That's a separate, breaking change tracked in the
-//! v3-scope-expansion prompt log.
+//! Where mmap is the wrong tool — images on network filesystems,
+//! mostly — [`FileImage`] reads through `pread` and a block cache
+//! instead, behind [`RandomAccessMut`]. Images already in memory are
+//! [`RandomAccess`] as they are: `[u8]`, `Vec<u8>` and
+//! `Cursor<impl AsRef<[u8]>>` all implement it.
+//!
+//! The ISO 9660, UDF, ext, ZIP and TAR parsers read through a
+//! crate-private `Source` that is either a `Read + Seek` stream or a
+//! [`RandomAccess`] image. Given an image — [`crate::detect_and_parse_image`]
+//! or [`crate::probe::parse_image_as`] — they borrow names straight out
+//! of it into `TreeNode.name: Cow<'a, str>`. The other parsers read an
+//! image through [`ImageReader`], a `Read + Seek` cursor over it.
 
-use std::io;
+use std::io::{self, Cursor};
 
+#[cfg(any(unix, windows))]
+pub mod file;
 #[cfg(feature = "mmap")]
 pub mod mmap;
 
+#[cfg(any(unix, windows))]
+pub use file::{CacheStats, FileImage};
 #[cfg(feature = "mmap")]
 pub use mmap::MmapImage;
 
+pub mod reader;
+pub(crate) mod source;
+pub mod window;
+
+pub use reader::ImageReader;
+pub use window::Window;
+
 /// Read arbitrary byte ranges out of a disc image without copying.
 ///
 /// Where `Read + Seek` returns bytes by filling a caller-provided
@@ -61,15 +80,15 @@ pub trait RandomAccess {
     /// buffer (mmap or scratch) must outlive every borrow.
     ///
     /// Implementations that need to read into a scratch buffer (e.g.
-    /// `FileImage`) take `&mut self` via the [`RandomAccessMut`]
+    /// [`FileImage`]) take `&mut self` via the [`RandomAccessMut`]
     /// extension trait. This trait is reserved for implementations
     /// where the bytes are already resident.
     fn read_at(&self, offset: u64, len: usize) -> io::Result<&[u8]>;
 }
 
 /// Mutable extension of [`RandomAccess`] for implementations that
-/// can't satisfy reads without scratch space (e.g. an over-File
-/// reader that fills a per-call buffer).
+/// can't satisfy reads without scratch space, such as [`FileImage`],
+/// which fills blocks of its cache from the file.
 ///
 /// Splitting this from [`RandomAccess`] keeps mmap-backed images
 /// usable behind a `&` reference, which matters when multiple
@@ -89,6 +108,104 @@ pub trait RandomAccessMut {
     fn read_at_mut(&mut self, offset: u64, len: usize) -> io::Result<&[u8]>;
 }
 
+impl RandomAccess for [u8] {
+    fn len(&self) -> u64 {
+        <[u8]>::len(self) as u64
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<&[u8]> {
+        usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.get(start..start.checked_add(len)?))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "read past image end"))
+    }
+}
+
+impl RandomAccess for Vec<u8> {
+    fn len(&self) -> u64 {
+        self.as_slice().len() as u64
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<&[u8]> {
+        self.as_slice().read_at(offset, len)
+    }
+}
+
+/// Reads ignore the cursor position; offsets are always from the start
+/// of the buffer.
+impl<T: AsRef<[u8]>> RandomAccess for Cursor<T> {
+    fn len(&self) -> u64 {
+        self.get_ref().as_ref().len() as u64
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<&[u8]> {
+        self.get_ref().as_ref().read_at(offset, len)
+    }
+}
+
+impl<T: RandomAccess + ?Sized> RandomAccess for &T {
+    fn len(&self) -> u64 {
+        (**self).len()
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<&[u8]> {
+        (**self).read_at(offset, len)
+    }
+}
+
+/// Positional reads through a shared reference.
+///
+/// Unlike `Read + Seek`, a read carries its own offset and leaves no
+/// cursor behind, so one handle can serve several threads at once —
+/// this is what [`extract_node_parallel`](crate::extract_node_parallel)
+/// reads through. `File` implements it with `pread` (`seek_read` on
+/// Windows), and every [`RandomAccess`] image gets it by copying out of
+/// the borrowed slice.
+pub trait ReadAt {
+    /// Fill `buf` with the bytes starting at `offset`. Returns
+    /// `io::ErrorKind::UnexpectedEof` if the image ends first.
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+}
+
+impl<T: RandomAccess + ?Sized> ReadAt for T {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        buf.copy_from_slice(self.read_at(offset, buf.len())?);
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl ReadAt for std::fs::File {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)
+    }
+}
+
+#[cfg(windows)]
+impl ReadAt for std::fs::File {
+    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+        use std::os::windows::fs::FileExt;
+        // `seek_read` may return short counts; loop like `read_exact`.
+        while !buf.is_empty() {
+            match self.seek_read(buf, offset) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "read past image end",
+                    ))
+                }
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +254,36 @@ mod tests {
         assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
     }
 
+    #[test]
+    fn read_at_over_random_access_and_file() {
+        let mut buf = [0u8; 3];
+        InMemory(b"abcdefgh").read_exact_at(&mut buf, 2).unwrap();
+        assert_eq!(&buf, b"cde");
+
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut tmp, b"abcdefgh").unwrap();
+        let file = tmp.as_file();
+        file.read_exact_at(&mut buf, 5).unwrap();
+        assert_eq!(&buf, b"fgh");
+        let err = ReadAt::read_exact_at(file, &mut buf, 6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn in_memory_images() {
+        let bytes = b"abcdefgh".to_vec();
+        assert_eq!(bytes.read_at(2, 3).unwrap(), b"cde");
+        assert_eq!(RandomAccess::len(&bytes[..]), 8);
+        let by_ref: &[u8] = &bytes;
+        assert_eq!(RandomAccess::read_at(&by_ref, 6, 2).unwrap(), b"gh");
+        let mut cursor = Cursor::new(&bytes[..]);
+        cursor.set_position(5);
+        assert_eq!(cursor.read_at(0, 1).unwrap(), b"a");
+        for err in [bytes.read_at(7, 2), bytes.read_at(u64::MAX, 1)] {
+            assert_eq!(err.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
     // A trivial RandomAccessMut impl to exercise the default is_empty().
     struct InMemoryMut<'a>(&'a [u8]);
     impl<'a> RandomAccessMut for InMemoryMut<'a> {
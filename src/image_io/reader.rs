@@ -0,0 +1,98 @@
+//! A resident image, presented as a stream.
+//!
+//! Parsers that have not moved onto the crate's `Source` abstraction
+//! still take `Read + Seek`. [`ImageReader`] lets them read a
+//! [`RandomAccess`] image — a `Vec<u8>`, a `&[u8]` or an `MmapImage` —
+//! through a cursor of its own, copying only the bytes each read asks
+//! for instead of the whole image up front.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::RandomAccess;
+
+/// `Read + Seek` over a borrowed [`RandomAccess`] image.
+///
+/// Seeking past the end is allowed, as with `File`; reads there return
+/// `0`. The image is never copied as a whole, so an image larger than
+/// the address space reads the same as a small one.
+#[derive(Debug)]
+pub struct ImageReader<'a, R: ?Sized> {
+    image: &'a R,
+    pos: u64,
+}
+
+impl<'a, R: RandomAccess + ?Sized> ImageReader<'a, R> {
+    /// Wrap `image` with the cursor at offset `0`.
+    pub fn new(image: &'a R) -> Self {
+        Self { image, pos: 0 }
+    }
+
+    /// Give back the wrapped image.
+    pub fn into_inner(self) -> &'a R {
+        self.image
+    }
+}
+
+impl<R: RandomAccess + ?Sized> Read for ImageReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.image.len();
+        if self.pos >= len || buf.is_empty() {
+            return Ok(0);
+        }
+        let want = usize::try_from(len - self.pos).map_or(buf.len(), |left| left.min(buf.len()));
+        buf[..want].copy_from_slice(self.image.read_at(self.pos, want)?);
+        self.pos += want as u64;
+        Ok(want)
+    }
+}
+
+impl<R: RandomAccess + ?Sized> Seek for ImageReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n);
+            }
+            SeekFrom::End(d) => (self.image.len(), d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        match base.checked_add_signed(delta) {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_follow_the_cursor() {
+        let image = b"0123456789".to_vec();
+        let mut r = ImageReader::new(&image);
+        let mut b = [0u8; 4];
+        r.read_exact(&mut b).unwrap();
+        assert_eq!(&b, b"0123");
+        assert_eq!(r.seek(SeekFrom::End(-3)).unwrap(), 7);
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"789");
+        assert!(r.seek(SeekFrom::Current(-11)).is_err());
+    }
+
+    #[test]
+    fn past_the_end_reads_nothing() {
+        let image: &[u8] = b"abc";
+        let mut r = ImageReader::new(image);
+        r.seek(SeekFrom::Start(10)).unwrap();
+        let mut b = [0u8; 4];
+        assert_eq!(r.read(&mut b).unwrap(), 0);
+    }
+}
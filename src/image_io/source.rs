@@ -0,0 +1,164 @@
+//! Where the ISO 9660, UDF, ext, ZIP and TAR parsers get their bytes
+//! from.
+//!
+//! Each parser is written once against [`Source`], which hands out
+//! byte ranges as `Cow<'a, [u8]>`. Over a `Read + Seek` stream every
+//! range is read into a fresh buffer (`'a = 'static`); over a
+//! [`RandomAccess`] image it is borrowed straight out of the image, and
+//! names decoded from it can borrow too. [`Bytes`] carries that
+//! distinction down to the name decoders.
+
+use std::borrow::Cow;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::slice::SliceIndex;
+
+use super::RandomAccess;
+
+/// A byte source the parsers read descriptors and directories through.
+pub(crate) trait Source<'a> {
+    /// The `len` bytes at `offset`. Errors with `UnexpectedEof` if the
+    /// range runs past the end of the image.
+    fn bytes(&mut self, offset: u64, len: usize) -> io::Result<Cow<'a, [u8]>>;
+
+    /// Total image size in bytes.
+    fn len(&mut self) -> io::Result<u64>;
+}
+
+/// A `Read + Seek` stream; every read is copied into an owned buffer.
+pub(crate) struct Stream<'r, R: ?Sized>(pub(crate) &'r mut R);
+
+impl<R: Read + Seek + ?Sized> Source<'static> for Stream<'_, R> {
+    fn bytes(&mut self, offset: u64, len: usize) -> io::Result<Cow<'static, [u8]>> {
+        self.0.seek(SeekFrom::Start(offset))?;
+        let mut buffer = vec![0u8; len];
+        self.0.read_exact(&mut buffer)?;
+        Ok(Cow::Owned(buffer))
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        self.0.seek(SeekFrom::End(0))
+    }
+}
+
+/// A resident image; reads borrow from it for `'a`.
+pub(crate) struct Borrowed<'a, R: ?Sized>(pub(crate) &'a R);
+
+impl<'a, R: RandomAccess + ?Sized> Source<'a> for Borrowed<'a, R> {
+    fn bytes(&mut self, offset: u64, len: usize) -> io::Result<Cow<'a, [u8]>> {
+        crate::limits::read(len as u64)?;
+        self.0.read_at(offset, len).map(Cow::Borrowed)
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        Ok(self.0.len())
+    }
+}
+
+/// A view into bytes returned by a [`Source`]: either borrowed from the
+/// image for `'a`, or from a scratch buffer that lives only for `'b`.
+/// Decoded names borrow in the first case and are copied in the second.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Bytes<'a, 'b> {
+    Image(&'a [u8]),
+    Buffer(&'b [u8]),
+}
+
+impl<'a, 'b> Bytes<'a, 'b> {
+    /// View a range returned by [`Source::bytes`].
+    pub(crate) fn of(bytes: &'b Cow<'a, [u8]>) -> Self {
+        match bytes {
+            Cow::Borrowed(image) => Bytes::Image(image),
+            Cow::Owned(buffer) => Bytes::Buffer(buffer),
+        }
+    }
+
+    /// Narrow the view. Panics on an out-of-range index, like slicing.
+    pub(crate) fn slice<I: SliceIndex<[u8], Output = [u8]>>(self, index: I) -> Self {
+        match self {
+            Bytes::Image(image) => Bytes::Image(&image[index]),
+            Bytes::Buffer(buffer) => Bytes::Buffer(&buffer[index]),
+        }
+    }
+
+    /// The viewed bytes, borrowed from the image or copied.
+    pub(crate) fn to_cow(self) -> Cow<'a, [u8]> {
+        match self {
+            Bytes::Image(image) => Cow::Borrowed(image),
+            Bytes::Buffer(buffer) => Cow::Owned(buffer.to_vec()),
+        }
+    }
+
+    /// Decode as UTF-8, replacing invalid sequences. Borrows from the
+    /// image when the bytes are valid.
+    pub(crate) fn utf8_lossy(self) -> Cow<'a, str> {
+        match self {
+            Bytes::Image(image) => String::from_utf8_lossy(image),
+            Bytes::Buffer(buffer) => Cow::Owned(String::from_utf8_lossy(buffer).into_owned()),
+        }
+    }
+}
+
+impl Deref for Bytes<'_, '_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match *self {
+            Bytes::Image(image) => image,
+            Bytes::Buffer(buffer) => buffer,
+        }
+    }
+}
+
+/// Apply a trimming function to a name without giving up the borrow.
+pub(crate) fn narrow<'a>(name: Cow<'a, str>, trim: impl FnOnce(&str) -> &str) -> Cow<'a, str> {
+    match name {
+        Cow::Borrowed(name) => Cow::Borrowed(trim(name)),
+        Cow::Owned(name) => {
+            let trimmed = trim(&name);
+            if trimmed.len() == name.len() {
+                Cow::Owned(name)
+            } else {
+                Cow::Owned(trimmed.to_string())
+            }
+        }
+    }
+}
+
+/// Split `path` at each `/`, borrowing the components when `path` does.
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub(crate) fn split_path<'a>(path: &Cow<'a, str>) -> Vec<Cow<'a, str>> {
+    match path {
+        Cow::Borrowed(path) => path.split('/').map(Cow::Borrowed).collect(),
+        Cow::Owned(path) => path.split('/').map(|c| Cow::Owned(c.to_string())).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn stream_copies_and_image_borrows() {
+        let data = b"NAME.TXT;1".to_vec();
+        let owned = Stream(&mut Cursor::new(&data)).bytes(0, 10).unwrap();
+        assert!(matches!(owned, Cow::Owned(_)));
+        let borrowed = Borrowed(&data).bytes(0, 10).unwrap();
+        assert!(matches!(borrowed, Cow::Borrowed(_)));
+
+        fn trim(name: &str) -> &str {
+            name.split(';').next().unwrap_or(name)
+        }
+        let name = narrow(Bytes::of(&borrowed).utf8_lossy(), trim);
+        assert!(matches!(name, Cow::Borrowed("NAME.TXT")));
+        let name = narrow(Bytes::of(&owned).slice(..8).utf8_lossy(), trim);
+        assert!(matches!(name, Cow::Owned(_)));
+        assert_eq!(name, "NAME.TXT");
+
+        assert_eq!(Borrowed(&data).len().unwrap(), 10);
+        assert_eq!(Stream(&mut Cursor::new(&data)).len().unwrap(), 10);
+        let err = Borrowed(&data).bytes(8, 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,136 @@
+//! A byte range of a larger image, presented as a stream of its own.
+//!
+//! Partition tables describe filesystems by their byte range inside the
+//! disk. The filesystem parsers, on the other hand, expect the volume to
+//! start at offset 0 — ISO 9660 seeks straight to sector 16, UDF to its
+//! anchor at sector 256. [`Window`] bridges the two: it wraps any
+//! `Read + Seek` and maps offset `0` onto `start`, reporting `len` as the
+//! end of the stream.
+//!
+//! Offsets inside a parsed [`TreeNode`](crate::TreeNode) come back
+//! relative to the window; [`TreeNode::shift_locations`](crate::TreeNode::shift_locations)
+//! translates them back to the outer image.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// `Read + Seek` over bytes `start..start + len` of an inner stream.
+///
+/// Reads stop at the window's end even when the inner stream continues.
+/// Seeking past the end is allowed, as with `File`; reads there return
+/// `0`. The inner stream is re-positioned on every read, so several
+/// windows over the same `&mut R` may be used one after another.
+#[derive(Debug)]
+pub struct Window<R> {
+    inner: R,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Window<R> {
+    /// Wrap `inner`, exposing `len` bytes starting at `start`. The
+    /// window's cursor begins at its own offset `0`.
+    pub fn new(inner: R, start: u64, len: u64) -> Self {
+        Self {
+            inner,
+            start,
+            len,
+            pos: 0,
+        }
+    }
+
+    /// Byte offset of the window inside the inner stream.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Length of the window in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// `true` iff the window covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Give back the wrapped stream. Its position is unspecified.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek> Read for Window<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let want = (self.len - self.pos).min(buf.len() as u64) as usize;
+        let at = self
+            .start
+            .checked_add(self.pos)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "window offset overflow"))?;
+        self.inner.seek(SeekFrom::Start(at))?;
+        let n = self.inner.read(&mut buf[..want])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for Window<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n);
+            }
+            SeekFrom::End(d) => (self.len, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        match base.checked_add_signed(delta) {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn reads_are_offset_and_clamped() {
+        let mut w = Window::new(Cursor::new(b"0123456789".to_vec()), 3, 4);
+        let mut out = Vec::new();
+        w.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"3456");
+        assert_eq!(w.len(), 4);
+        assert_eq!(w.start(), 3);
+    }
+
+    #[test]
+    fn seek_end_is_window_end() {
+        let mut w = Window::new(Cursor::new(b"0123456789".to_vec()), 2, 5);
+        assert_eq!(w.seek(SeekFrom::End(0)).unwrap(), 5);
+        assert_eq!(w.seek(SeekFrom::End(-2)).unwrap(), 3);
+        let mut b = [0u8; 4];
+        assert_eq!(w.read(&mut b).unwrap(), 2);
+        assert_eq!(&b[..2], b"56");
+        assert!(w.seek(SeekFrom::Current(-10)).is_err());
+    }
+
+    #[test]
+    fn window_past_inner_end_reads_short() {
+        let mut w = Window::new(Cursor::new(b"abc".to_vec()), 1, 10);
+        let mut out = Vec::new();
+        w.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"bc");
+    }
+}
@@ -0,0 +1,324 @@
+//! Hash-indexed path lookup.
+//!
+//! [`TreeNode::find_node`] scans `children` linearly at every level,
+//! which is fine for one lookup and slow for a hundred thousand. A
+//! [`PathIndex`] walks the tree once and answers each lookup with a single
+//! hash probe.
+//!
+//! Names pass through a [`Normalize`] implementation both when the index
+//! is built and when it is queried, so lookups can follow the way the
+//! source filesystem compares names: [`Exact`] for ISO 9660, UDF, ext and the
+//! archive formats, [`UpCase`] for NTFS (ideally loaded from the volume's
+//! own `$UpCase`), [`FatUpper`] for FAT and [`HfsFold`] for HFS+. Any
+//! `Fn(&str) -> String` works as a normalizer too.
+//!
+//! ```
+//! use isomage::index::{PathIndex, UpCase};
+//! use isomage::TreeNode;
+//!
+//! let mut root = TreeNode::new_directory("/");
+//! let mut windows = TreeNode::new_directory("Windows");
+//! windows.add_child(TreeNode::new_file("notepad.exe", 200_000));
+//! root.add_child(windows);
+//!
+//! let index = PathIndex::with_normalizer(&root, UpCase::default());
+//! assert_eq!(index.get("/WINDOWS/Notepad.EXE").unwrap().size, 200_000);
+//! assert!(PathIndex::new(&root).get("/WINDOWS/Notepad.EXE").is_none());
+//! ```
+
+use crate::tree::TreeNode;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Maps a name to the form two names must share to be the same entry.
+pub trait Normalize {
+    /// The normalized form of one path component.
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str>;
+}
+
+impl<F: Fn(&str) -> String> Normalize for F {
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str> {
+        Cow::Owned(self(name))
+    }
+}
+
+/// Names match byte for byte.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Exact;
+
+impl Normalize for Exact {
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str> {
+        Cow::Borrowed(name)
+    }
+}
+
+/// FAT's rule: names match after upper-casing. Each character maps to at
+/// most one character, as in Windows, so `ß` stays `ß` rather than
+/// becoming `SS`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FatUpper;
+
+impl Normalize for FatUpper {
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str> {
+        if !name.chars().any(|c| simple_upper(c) != c) {
+            return Cow::Borrowed(name);
+        }
+        Cow::Owned(name.chars().map(simple_upper).collect())
+    }
+}
+
+/// NTFS's rule: every UTF-16 code unit is mapped through the volume's
+/// `$UpCase` table.
+///
+/// [`UpCase::default`] builds a table from Unicode's simple upper-case
+/// mappings, which agrees with the table recent Windows versions write
+/// for every name in practice. Volumes formatted by older Windows
+/// versions carry older tables; load those with [`UpCase::from_le_bytes`]
+/// (see `NtfsFs::upcase` with `--features ntfs`).
+#[derive(Debug, Clone)]
+pub struct UpCase {
+    table: Vec<u16>,
+}
+
+impl UpCase {
+    /// Parse the contents of `$UpCase`: little-endian `u16`s, one per
+    /// code unit. Units past the end of a short table map to themselves.
+    pub fn from_le_bytes(bytes: &[u8]) -> Self {
+        let table = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        Self { table }
+    }
+
+    /// The upper-case form of one UTF-16 code unit.
+    pub fn map(&self, unit: u16) -> u16 {
+        self.table.get(unit as usize).copied().unwrap_or(unit)
+    }
+}
+
+impl Default for UpCase {
+    fn default() -> Self {
+        let table = (0..=u16::MAX)
+            .map(|unit| {
+                char::from_u32(unit.into())
+                    .and_then(|c| u16::try_from(u32::from(simple_upper(c))).ok())
+                    .unwrap_or(unit)
+            })
+            .collect();
+        Self { table }
+    }
+}
+
+impl Normalize for UpCase {
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str> {
+        if !name.encode_utf16().any(|unit| self.map(unit) != unit) {
+            return Cow::Borrowed(name);
+        }
+        let units: Vec<u16> = name.encode_utf16().map(|unit| self.map(unit)).collect();
+        Cow::Owned(String::from_utf16_lossy(&units))
+    }
+}
+
+/// HFS+'s rule (Apple TN1150, "Case-Insensitive String Comparison"):
+/// names match after lower-casing, ignoring the zero-width and
+/// bidirectional formatting characters the catalog comparison skips.
+///
+/// HFS+ also stores names in decomposed form; names decoded from the
+/// catalog already are, and callers looking up precomposed input should
+/// decompose it first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HfsFold;
+
+impl HfsFold {
+    fn ignorable(c: char) -> bool {
+        matches!(
+            c,
+            '\u{200C}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{206A}'..='\u{206F}' | '\u{FEFF}'
+        )
+    }
+}
+
+impl Normalize for HfsFold {
+    fn normalize<'s>(&self, name: &'s str) -> Cow<'s, str> {
+        if !name
+            .chars()
+            .any(|c| Self::ignorable(c) || simple_lower(c) != c)
+        {
+            return Cow::Borrowed(name);
+        }
+        Cow::Owned(
+            name.chars()
+                .filter(|&c| !Self::ignorable(c))
+                .map(simple_lower)
+                .collect(),
+        )
+    }
+}
+
+fn simple_upper(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+fn simple_lower(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+/// Every node of a tree keyed by its normalized path, for constant-time
+/// lookup.
+///
+/// The index borrows the tree, so it reflects the tree as it was when
+/// built. Where two paths normalize to the same key — `README` and
+/// `readme` in a case-sensitive image indexed with [`FatUpper`] — the
+/// one that comes first in child order wins.
+#[derive(Debug, Clone)]
+pub struct PathIndex<'t, 'a, N = Exact> {
+    nodes: HashMap<String, &'t TreeNode<'a>>,
+    normalize: N,
+}
+
+impl<'t, 'a> PathIndex<'t, 'a> {
+    /// Index `root` with names compared exactly, like
+    /// [`TreeNode::find_node`].
+    pub fn new(root: &'t TreeNode<'a>) -> Self {
+        Self::with_normalizer(root, Exact)
+    }
+}
+
+impl<'t, 'a, N: Normalize> PathIndex<'t, 'a, N> {
+    /// Index `root`, comparing names after `normalize`.
+    pub fn with_normalizer(root: &'t TreeNode<'a>, normalize: N) -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(String::new(), root);
+        let mut index = Self { nodes, normalize };
+        index.insert_children(root, "");
+        index
+    }
+
+    fn insert_children(&mut self, dir: &'t TreeNode<'a>, prefix: &str) {
+        for child in &dir.children {
+            let mut key = String::with_capacity(prefix.len() + 1 + child.name.len());
+            key.push_str(prefix);
+            if !prefix.is_empty() {
+                key.push('/');
+            }
+            key.push_str(&self.normalize.normalize(&child.name));
+            if child.is_directory {
+                self.insert_children(child, &key);
+            }
+            self.nodes.entry(key).or_insert(child);
+        }
+    }
+
+    /// The node at `path`, `/`-separated, relative to the indexed root.
+    /// Leading, trailing and repeated `/` are ignored; `""` and `"/"`
+    /// name the root.
+    pub fn get(&self, path: &str) -> Option<&'t TreeNode<'a>> {
+        let mut key = String::with_capacity(path.len());
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            if !key.is_empty() {
+                key.push('/');
+            }
+            key.push_str(&self.normalize.normalize(segment));
+        }
+        self.nodes.get(&key).copied()
+    }
+
+    /// Number of indexed nodes, the root included.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always `false`: the root is always indexed.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The normalizer names are compared with.
+    pub fn normalizer(&self) -> &N {
+        &self.normalize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> TreeNode<'static> {
+        let mut root = TreeNode::new_directory("/");
+        let mut docs = TreeNode::new_directory("Docs");
+        docs.add_child(TreeNode::new_file("Straße.txt", 1));
+        docs.add_child(TreeNode::new_file("README", 2));
+        docs.add_child(TreeNode::new_file("readme", 3));
+        root.add_child(docs);
+        root.add_child(TreeNode::new_file("a.txt", 4));
+        root
+    }
+
+    #[test]
+    fn exact_index_agrees_with_find_node() {
+        let root = tree();
+        let index = PathIndex::new(&root);
+        assert_eq!(index.len(), 6);
+        for entry in root.walk() {
+            assert!(std::ptr::eq(index.get(&entry.path).unwrap(), entry.node));
+        }
+        assert!(std::ptr::eq(index.get("/").unwrap(), &root));
+        assert!(std::ptr::eq(index.get("").unwrap(), &root));
+        assert_eq!(index.get("Docs//readme/").unwrap().size, 3);
+        assert!(index.get("docs/README").is_none());
+        assert!(index.get("a.txt/x").is_none());
+    }
+
+    #[test]
+    fn normalizers() {
+        let root = tree();
+        let fat = PathIndex::with_normalizer(&root, FatUpper);
+        assert_eq!(fat.len(), 5);
+        assert_eq!(fat.get("DOCS/readme").unwrap().size, 2);
+        assert_eq!(fat.get("docs/STRAßE.TXT").unwrap().size, 1);
+        assert!(fat.get("docs/STRASSE.TXT").is_none());
+
+        let ntfs = PathIndex::with_normalizer(&root, UpCase::default());
+        assert_eq!(ntfs.get("/DOCS/straße.TXT").unwrap().size, 1);
+        assert_eq!(ntfs.get("A.TXT").unwrap().size, 4);
+
+        let hfs = PathIndex::with_normalizer(&root, HfsFold);
+        assert_eq!(hfs.get("docs/Rea\u{200D}dMe").unwrap().size, 2);
+
+        let custom = PathIndex::with_normalizer(&root, |name: &str| name.replace('.', "_"));
+        assert_eq!(custom.get("Docs/Straße_txt").unwrap().size, 1);
+    }
+
+    #[test]
+    fn normalize_borrows_when_unchanged() {
+        assert!(matches!(FatUpper.normalize("A.TXT"), Cow::Borrowed(_)));
+        assert!(matches!(HfsFold.normalize("a.txt"), Cow::Borrowed(_)));
+        assert!(matches!(
+            UpCase::default().normalize("ÄÖ"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(UpCase::default().normalize("äö"), "ÄÖ");
+        assert_eq!(HfsFold.normalize("\u{FEFF}ÄB"), "äb");
+    }
+
+    #[test]
+    fn upcase_table_from_bytes() {
+        let mut bytes: Vec<u8> = (0..128u16).flat_map(|unit| unit.to_le_bytes()).collect();
+        // An old table that only folds ASCII `a` to `A`.
+        bytes[b'a' as usize * 2] = b'A';
+        let upcase = UpCase::from_le_bytes(&bytes);
+        assert_eq!(upcase.map(u16::from(b'a')), u16::from(b'A'));
+        assert_eq!(upcase.map(u16::from(b'b')), u16::from(b'b'));
+        assert_eq!(upcase.map(0x00E4), 0x00E4);
+        assert_eq!(upcase.normalize("abä"), "Abä");
+    }
+}
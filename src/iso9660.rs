@@ -1,25 +1,164 @@
 //! ISO 9660 / ECMA-119 parser, with the Joliet (Unicode filenames) and
-//! Rock Ridge (POSIX long filenames) extensions.
+//! Rock Ridge extensions.
 //!
-//! The entry points are [`parse_iso9660`] and [`parse_iso9660_verbose`].
+//! Rock Ridge (RRIP 1.12) supplies POSIX names, modes, link counts,
+//! ownership, symlink targets, device numbers and timestamps, reading
+//! System Use continuation areas as needed. Directories that mkisofs
+//! relocated to `rr_moved` to stay within eight levels are listed where
+//! they belong. A disc with both extensions is read through its Rock
+//! Ridge hierarchy; Joliet names are used only when Rock Ridge is absent.
+//!
+//! The entry points are [`parse_iso9660`], [`parse_iso9660_verbose`] and
+//! [`parse_iso9660_observed`].
 //! Both return a [`crate::TreeNode`] tree rooted at `"/"` on success.
-
-use crate::tree::TreeNode;
-use crate::Result;
+//! [`parse_iso9660_image`] parses an image that is already in memory,
+//! borrowing names out of it.
+//!
+//! A file stored in several directory records (ISO 9660 level 3
+//! multi-extent files, needed past 4 GiB) is one node with one extent
+//! per record, and an interleaved file's extents skip its gaps.
+//!
+//! On a multisession image the parsers read the last session, as an
+//! operating system mounting the disc would; [`list_sessions`] finds
+//! every session and [`parse_iso9660_session`] reads an earlier one.
+//!
+//! On a bootable image the parsers also list each El Torito boot image
+//! under a virtual `[BOOT]` directory; see [`crate::eltorito`].
+
+use crate::eltorito;
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem};
+use crate::image_io::source::{narrow, Borrowed, Bytes, Source, Stream};
+use crate::image_io::RandomAccess;
+use crate::limits;
+use crate::observe::{self, Observer, StderrObserver};
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
+use crate::volume::IsoVolume;
+use crate::{Error, Result};
 // `File` is no longer mentioned by the parser; entry points are
 // generic over `R: Read + Seek` as of v3.0. Keeping the imports
 // minimal matches the rest of the crate's style.
+use std::borrow::Cow;
 use std::io::{Read, Seek, SeekFrom};
 
 const SECTOR_SIZE: u64 = 2048;
 const PRIMARY_VOLUME_DESCRIPTOR_SECTOR: u64 = 16;
+/// Sectors searched past the end of one session for the next one's
+/// volume descriptors. Between the first two sessions of a CD-R the
+/// lead-out, lead-in and pregap take 11,400.
+const MAX_SESSION_GAP: u64 = 16_384;
+/// Sectors read at once while searching that gap.
+const SESSION_GAP_CHUNK: u64 = 64;
+/// A CD holds at most 99 sessions.
+const MAX_SESSIONS: usize = 99;
 
 #[derive(Debug, Clone)]
-pub struct DirectoryRecord {
+pub struct DirectoryRecord<'a> {
     pub extent_location: u32,
     pub data_length: u32,
     pub is_directory: bool,
-    pub filename: String,
+    /// Borrowed from the image when it was read through
+    /// [`parse_iso9660_image`] and needed no decoding.
+    pub filename: Cow<'a, str>,
+    /// Recording date and time (ECMA-119 9.1.5), if set.
+    pub recorded_at: Option<Timestamp>,
+}
+
+/// Rock Ridge `PX` (POSIX file attributes) fields we keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PosixAttributes {
+    mode: u32,
+    nlink: u32,
+    uid: u32,
+    gid: u32,
+}
+
+/// A decoded directory entry: its first directory record and metadata,
+/// and where its data is. A file of more than one record (multi-extent,
+/// ECMA-119 6.5.1) has the runs and total size of all of them.
+#[derive(Debug)]
+struct Entry<'a> {
+    record: DirectoryRecord<'a>,
+    metadata: Metadata,
+    /// File size in bytes, which a multi-extent file can take past 4 GiB.
+    size: u64,
+    /// Data runs: one per record, or one per file unit for an
+    /// interleaved record.
+    runs: Vec<Extent>,
+    /// Multi-Extent flag of the last record: the file continues in the
+    /// next one.
+    continues: bool,
+}
+
+impl<'a> Entry<'a> {
+    /// Whether `next` is the next record of this multi-extent file.
+    fn continues_with(&self, next: &Entry) -> bool {
+        self.continues && !next.record.is_directory && next.record.filename == self.record.filename
+    }
+
+    /// Take in the next record of this multi-extent file.
+    fn append(&mut self, next: Entry) {
+        self.size += next.size;
+        self.runs.extend(next.runs);
+        self.continues = next.continues;
+    }
+
+    /// Build the childless node for the entry, named `name`.
+    fn node(self, name: Cow<'a, str>) -> TreeNode<'a> {
+        if self.record.is_directory {
+            TreeNode::new_directory(name).with_metadata(self.metadata)
+        } else {
+            TreeNode::new_file_with_extents(name, self.size, self.runs).with_metadata(self.metadata)
+        }
+    }
+}
+
+/// Continuation areas followed for one directory record before the
+/// rest of its System Use entries are given up on.
+const MAX_CONTINUATIONS: usize = 32;
+
+/// The Rock Ridge entries (RRIP 1.12) of one directory record, gathered
+/// from its System Use field and any continuation areas.
+#[derive(Debug, Default)]
+struct RockRidge<'a> {
+    /// `NM` name parts, in order.
+    name: Vec<Cow<'a, [u8]>>,
+    posix: Option<PosixAttributes>,
+    /// `SL` target, and whether its last component continues in the
+    /// next component record.
+    symlink: Option<(String, bool)>,
+    /// `PN` device number.
+    device: Option<(u32, u32)>,
+    /// `TF` creation, modification, access and attribute change times.
+    btime: Option<Timestamp>,
+    mtime: Option<Timestamp>,
+    atime: Option<Timestamp>,
+    ctime: Option<Timestamp>,
+    /// `CL`: the record stands in for a directory relocated to this
+    /// sector.
+    child_link: Option<u32>,
+    /// `RE`: the record is a relocated directory, listed where its `CL`
+    /// record is.
+    relocated: bool,
+}
+
+/// One session of a (possibly multisession) image, found by
+/// [`list_sessions`].
+///
+/// Each session has its own volume descriptor set 16 sectors past its
+/// start, and its directories may point at files written by earlier
+/// sessions: every sector address on the disc is absolute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// First sector of the session; `0` for the first.
+    pub start_lba: u32,
+    /// Volume Space Size of the session's Primary Volume Descriptor, in
+    /// sectors from the start of the disc (ECMA-119 8.4.8).
+    pub volume_space_size: u32,
+    /// Volume Identifier of the session's Primary Volume Descriptor,
+    /// trailing spaces removed.
+    pub volume_id: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -33,7 +172,7 @@ enum VolumeDescriptorType {
 /// Equivalent to `parse_iso9660_verbose(file, false)`. Errors out cleanly
 /// (returns `Err`, never panics) on images whose volume descriptors don't
 /// validate.
-pub fn parse_iso9660<R: Read + Seek>(file: &mut R) -> Result<TreeNode> {
+pub fn parse_iso9660<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>> {
     parse_iso9660_verbose(file, false)
 }
 
@@ -44,57 +183,341 @@ pub fn parse_iso9660<R: Read + Seek>(file: &mut R) -> Result<TreeNode> {
 /// `&mut File`, so consumers can feed it an `MmapImage`, a
 /// `Cursor<Vec<u8>>`, or any other byte-source that implements the
 /// trait pair.
-pub fn parse_iso9660_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<TreeNode> {
-    // Scan all volume descriptors to find Primary and Joliet
-    let mut primary_vd: Option<Vec<u8>> = None;
-    let mut joliet_vd: Option<Vec<u8>> = None;
-
-    let mut sector = PRIMARY_VOLUME_DESCRIPTOR_SECTOR;
-    loop {
-        file.seek(SeekFrom::Start(sector * SECTOR_SIZE))?;
-        let mut buffer = vec![0u8; SECTOR_SIZE as usize];
-        if file.read_exact(&mut buffer).is_err() {
+pub fn parse_iso9660_verbose<R: Read + Seek>(
+    file: &mut R,
+    verbose: bool,
+) -> Result<TreeNode<'static>> {
+    if verbose {
+        parse_iso9660_observed(file, &mut StderrObserver)
+    } else {
+        parse_iso9660_observed(file, &mut ())
+    }
+}
+
+/// Like [`parse_iso9660`], but sends spec-section-tagged
+/// [`Diagnostic`](crate::observe::Diagnostic) events to `observer`.
+pub fn parse_iso9660_observed<R: Read + Seek>(
+    file: &mut R,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'static>> {
+    limits::metered(Format::Iso9660, None, file, |file| {
+        parse(&mut Stream(file), None, observer)
+    })
+}
+
+/// Find every session of an ISO 9660 image, first to last. A
+/// single-session image has one.
+///
+/// ```no_run
+/// use isomage::iso9660::{list_sessions, parse_iso9660_session};
+///
+/// let mut disc = std::fs::File::open("backup.iso")?;
+/// let sessions = list_sessions(&mut disc)?;
+/// for session in &sessions {
+///     println!("{} at sector {}", session.volume_id, session.start_lba);
+/// }
+/// let first = parse_iso9660_session(&mut disc, &sessions[0])?;
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn list_sessions<R: Read + Seek>(file: &mut R) -> Result<Vec<Session>> {
+    limits::metered(Format::Iso9660, None, file, |file| {
+        let sessions = find_sessions(&mut Stream(file), &mut ())?;
+        if sessions.is_empty() {
+            return Err(Error::not_recognized(
+                Format::Iso9660,
+                "Not a valid ISO 9660 filesystem",
+            ));
+        }
+        Ok(sessions)
+    })
+}
+
+/// Like [`parse_iso9660`], but read `session` instead of the last one.
+pub fn parse_iso9660_session<R: Read + Seek>(
+    file: &mut R,
+    session: &Session,
+) -> Result<TreeNode<'static>> {
+    limits::metered(Format::Iso9660, None, file, |file| {
+        parse(&mut Stream(file), Some(session.start_lba), &mut ())
+    })
+}
+
+/// Parse an ISO 9660 image that is already in memory or mapped.
+///
+/// Names that need no decoding — plain ISO 9660 and single-entry Rock
+/// Ridge names — borrow from `image` instead of being copied; Joliet
+/// names are UCS-2 on disc and are always owned. Call
+/// [`TreeNode::into_owned`] to detach the tree from the image.
+///
+/// ```no_run
+/// let bytes = std::fs::read("disc.iso")?;
+/// let root = isomage::iso9660::parse_iso9660_image(&bytes)?;
+/// println!("{} entries", root.children.len());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_iso9660_image<R: RandomAccess + ?Sized>(image: &R) -> Result<TreeNode<'_>> {
+    parse_iso9660_image_observed(image, &mut ())
+}
+
+/// Like [`parse_iso9660_image`], but sends diagnostics to `observer`.
+pub fn parse_iso9660_image_observed<'a, R: RandomAccess + ?Sized>(
+    image: &'a R,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'a>> {
+    limits::scope(Format::Iso9660, None, || {
+        parse(&mut Borrowed(image), None, observer)
+    })
+}
+
+/// Parse the session starting at sector `session`, or the last one.
+fn parse<'a, S: Source<'a>>(
+    src: &mut S,
+    session: Option<u32>,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'a>> {
+    let start = match session {
+        Some(start) => start,
+        None => last_session(src, observer)?,
+    };
+    let volume = open_volume(src, start, observer)?;
+    let root_record = volume.root_record;
+
+    let mut root_node = TreeNode::new_directory("/");
+    parse_directory(
+        src,
+        &root_record,
+        &mut root_node,
+        volume.vd_type,
+        volume.rock_ridge,
+        observer,
+    )?;
+
+    if let Some(sector) = volume.boot_catalog {
+        match eltorito::decode_catalog(src, sector) {
+            Ok(catalog) => {
+                if root_node.find_node(eltorito::BOOT_DIRECTORY).is_none() {
+                    let boot = eltorito::boot_directory(src, &catalog, &root_node);
+                    root_node.add_child(boot);
+                }
+            }
+            Err(e) => recover::note(Some(sector as u64 * SECTOR_SIZE), "boot catalog", e),
+        }
+    }
+
+    root_node.calculate_directory_size();
+    Ok(root_node)
+}
+
+/// What [`open_volume`] learns from the volume descriptors: the chosen
+/// descriptor's root record, where that record lives, which name
+/// encoding applies below it, and where the El Torito boot catalog is.
+pub(crate) struct Volume<'a> {
+    root_record: DirectoryRecord<'a>,
+    root_offset: u64,
+    vd_type: VolumeDescriptorType,
+    rock_ridge: bool,
+    pub(crate) boot_catalog: Option<u32>,
+}
+
+/// The Primary Volume Descriptor of the session starting at sector
+/// `start`, if there is one.
+fn session_descriptor<'a, S: Source<'a>>(src: &mut S, start: u64) -> Option<Cow<'a, [u8]>> {
+    let mut sector = start + PRIMARY_VOLUME_DESCRIPTOR_SECTOR;
+    while let Ok(buffer) = src.bytes(sector * SECTOR_SIZE, SECTOR_SIZE as usize) {
+        if &buffer[1..6] != b"CD001" || buffer[0] == 255 {
             break;
         }
+        if buffer[0] == 1 {
+            return Some(buffer);
+        }
+        sector += 1;
+    }
+    None
+}
+
+/// The Volume Space Size of a Primary Volume Descriptor, in sectors.
+fn volume_space_size(pvd: &[u8]) -> u32 {
+    u32::from_le_bytes([pvd[80], pvd[81], pvd[82], pvd[83]])
+}
+
+/// The first sector in `from..to` at which a later session starts.
+///
+/// The sectors where such a session's descriptors would begin are read
+/// [`SESSION_GAP_CHUNK`] at a time, and only those carrying the `CD001`
+/// signature are looked at more closely, so an empty gap costs a few
+/// large reads rather than one per sector. `to + 16` must not pass the
+/// end of the image.
+fn next_session<'a, S: Source<'a>>(src: &mut S, from: u64, to: u64) -> Option<u64> {
+    let mut chunk_start = from;
+    while chunk_start < to {
+        let count = SESSION_GAP_CHUNK.min(to - chunk_start);
+        let chunk = src
+            .bytes(
+                (chunk_start + PRIMARY_VOLUME_DESCRIPTOR_SECTOR) * SECTOR_SIZE,
+                (count * SECTOR_SIZE) as usize,
+            )
+            .ok()?;
+        for (i, sector) in chunk.chunks_exact(SECTOR_SIZE as usize).enumerate() {
+            let s = chunk_start + i as u64;
+            if &sector[1..6] == b"CD001"
+                && session_descriptor(src, s).is_some_and(|pvd| {
+                    volume_space_size(&pvd) as u64 > s + PRIMARY_VOLUME_DESCRIPTOR_SECTOR
+                })
+            {
+                return Some(s);
+            }
+        }
+        chunk_start += count;
+    }
+    None
+}
 
+/// Find the sessions of the image, first to last; none if it is not ISO
+/// 9660.
+///
+/// Each session's Volume Space Size says where its data ends. The next
+/// session, if any, starts within [`MAX_SESSION_GAP`] sectors of there,
+/// and its own Volume Space Size reaches past its descriptors — which
+/// tells it apart from an ISO image stored as a file.
+fn find_sessions<'a, S: Source<'a>>(
+    src: &mut S,
+    observer: &mut dyn Observer,
+) -> Result<Vec<Session>> {
+    let image_sectors = src.len()? / SECTOR_SIZE;
+
+    let mut sessions = Vec::new();
+    let mut start = 0u64;
+    while let Some(pvd) = session_descriptor(src, start) {
+        let session = Session {
+            start_lba: start as u32,
+            volume_space_size: volume_space_size(&pvd),
+            volume_id: String::from_utf8_lossy(&pvd[40..72])
+                .trim_end_matches([' ', '\0'])
+                .to_string(),
+        };
+        observe::info(
+            observer,
+            Format::Iso9660,
+            "ECMA-119 8.4.8",
+            format_args!(
+                "Session {} at sector {}, {} sectors",
+                sessions.len() + 1,
+                session.start_lba,
+                session.volume_space_size
+            ),
+        );
+        let end =
+            (session.volume_space_size as u64).max(start + PRIMARY_VOLUME_DESCRIPTOR_SECTOR + 1);
+        sessions.push(session);
+        if sessions.len() == MAX_SESSIONS {
+            break;
+        }
+
+        let last = (end + MAX_SESSION_GAP)
+            .min(image_sectors.saturating_sub(PRIMARY_VOLUME_DESCRIPTOR_SECTOR))
+            .min(u32::MAX as u64);
+        match next_session(src, end, last) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+    Ok(sessions)
+}
+
+/// The first sector of the last session; `0` when there is only one, or
+/// none, which [`open_volume`] then reports.
+pub(crate) fn last_session<'a, S: Source<'a>>(
+    src: &mut S,
+    observer: &mut dyn Observer,
+) -> Result<u32> {
+    Ok(find_sessions(src, observer)?
+        .last()
+        .map_or(0, |session| session.start_lba))
+}
+
+/// Scan the volume descriptor set of the session starting at sector
+/// `start`, pick a hierarchy — Primary with Rock Ridge, else Joliet,
+/// else plain Primary — and decode its root directory record.
+pub(crate) fn open_volume<'a, S: Source<'a>>(
+    src: &mut S,
+    start: u32,
+    observer: &mut dyn Observer,
+) -> Result<Volume<'a>> {
+    // Scan all volume descriptors to find Primary and Joliet
+    let mut primary_vd: Option<(u64, Cow<'a, [u8]>)> = None;
+    let mut joliet_vd: Option<(u64, Cow<'a, [u8]>)> = None;
+    let mut boot_catalog = None;
+
+    let first = start as u64 + PRIMARY_VOLUME_DESCRIPTOR_SECTOR;
+    let mut sector = first;
+    while let Ok(buffer) = src.bytes(sector * SECTOR_SIZE, SECTOR_SIZE as usize) {
         // Check for ISO 9660 signature
         if &buffer[1..6] != b"CD001" {
-            if sector == PRIMARY_VOLUME_DESCRIPTOR_SECTOR {
-                if verbose {
-                    eprintln!(
-                        "  ISO 9660 signature 'CD001' not found at sector {}. Found: {:?}",
+            if sector == first {
+                observe::info(
+                    observer,
+                    Format::Iso9660,
+                    "ECMA-119 8.1",
+                    format_args!(
+                        "signature 'CD001' not found at sector {}. Found: {:?}",
                         sector,
                         String::from_utf8_lossy(&buffer[1..6])
-                    );
-                }
-                return Err("Not a valid ISO 9660 filesystem".into());
+                    ),
+                );
+                return Err(Error::not_recognized(
+                    Format::Iso9660,
+                    "Not a valid ISO 9660 filesystem",
+                ));
             }
             break;
         }
 
         let vd_type = buffer[0];
         match vd_type {
-            1 => {
-                if verbose {
-                    eprintln!("  Found Primary Volume Descriptor at sector {}", sector);
+            0 => {
+                if let Some(catalog) = eltorito::catalog_sector(&buffer) {
+                    observe::info(
+                        observer,
+                        Format::Iso9660,
+                        "El Torito 2.0",
+                        format_args!(
+                            "Found Boot Record at sector {}, boot catalog at sector {}",
+                            sector, catalog
+                        ),
+                    );
+                    boot_catalog = Some(catalog);
                 }
-                primary_vd = Some(buffer);
+            }
+            1 => {
+                observe::info(
+                    observer,
+                    Format::Iso9660,
+                    "ECMA-119 8.4",
+                    format_args!("Found Primary Volume Descriptor at sector {}", sector),
+                );
+                primary_vd = Some((sector, buffer));
             }
             2 => {
                 // Supplementary Volume Descriptor — check if Joliet
                 // Joliet is indicated by escape sequences in bytes 88-90
                 let escape = &buffer[88..91];
                 if escape == b"%/@" || escape == b"%/C" || escape == b"%/E" {
-                    if verbose {
-                        eprintln!("  Found Joliet Volume Descriptor at sector {}", sector);
-                    }
-                    joliet_vd = Some(buffer);
+                    observe::info(
+                        observer,
+                        Format::Iso9660,
+                        "ECMA-119 8.5",
+                        format_args!("Found Joliet Volume Descriptor at sector {}", sector),
+                    );
+                    joliet_vd = Some((sector, buffer));
                 }
             }
             255 => {
-                if verbose {
-                    eprintln!("  Volume Descriptor Set Terminator at sector {}", sector);
-                }
+                observe::info(
+                    observer,
+                    Format::Iso9660,
+                    "ECMA-119 8.3",
+                    format_args!("Volume Descriptor Set Terminator at sector {}", sector),
+                );
                 break;
             }
             _ => {}
@@ -102,65 +525,90 @@ pub fn parse_iso9660_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Res
         sector += 1;
     }
 
-    // Prefer Joliet (Unicode filenames) over Primary
-    let (buffer, vd_type) = if let Some(buf) = joliet_vd {
-        (buf, VolumeDescriptorType::Joliet)
-    } else if let Some(buf) = primary_vd {
-        (buf, VolumeDescriptorType::Primary)
-    } else {
-        return Err("Not a valid ISO 9660 filesystem".into());
+    // Rock Ridge lives in the Primary hierarchy and has what Joliet
+    // lacks: POSIX modes, symlinks, device numbers, times and relocated
+    // directories. Read that hierarchy when its root carries Rock Ridge,
+    // as Linux does; otherwise prefer Joliet's Unicode names.
+    let rock_ridge = primary_vd
+        .as_ref()
+        .map(|(sector, buffer)| {
+            let root_offset = sector * SECTOR_SIZE + 156;
+            let record = parse_directory_record(
+                Bytes::of(buffer).slice(156..),
+                VolumeDescriptorType::Primary,
+            )
+            .map_err(|e| Error::corrupt_at(Format::Iso9660, root_offset, e))?;
+            detect_rock_ridge(src, &record)
+        })
+        .transpose();
+    let use_rock_ridge = match rock_ridge {
+        Ok(found) => found.unwrap_or(false),
+        // A damaged Primary root does not stop a Joliet read.
+        Err(_) if joliet_vd.is_some() => false,
+        Err(e) => return Err(e),
+    };
+    let ((vd_sector, buffer), vd_type) = match (primary_vd, joliet_vd) {
+        (Some(vd), _) if use_rock_ridge => (vd, VolumeDescriptorType::Primary),
+        (_, Some(vd)) => (vd, VolumeDescriptorType::Joliet),
+        (Some(vd), None) => (vd, VolumeDescriptorType::Primary),
+        (None, None) => {
+            return Err(Error::not_recognized(
+                Format::Iso9660,
+                "Not a valid ISO 9660 filesystem",
+            ))
+        }
     };
 
-    if verbose {
-        eprintln!(
-            "  Using {} Volume Descriptor",
+    observe::info(
+        observer,
+        Format::Iso9660,
+        "ECMA-119 8.4",
+        format_args!(
+            "Using {} Volume Descriptor",
             if vd_type == VolumeDescriptorType::Joliet {
                 "Joliet"
             } else {
                 "Primary"
             }
+        ),
+    );
+    if use_rock_ridge {
+        observe::info(
+            observer,
+            Format::Iso9660,
+            "SUSP 5.3",
+            format_args!("Rock Ridge extensions detected"),
         );
     }
 
     // Parse root directory record (starts at offset 156)
-    let root_record = parse_directory_record(&buffer[156..], vd_type)?;
-    if verbose {
-        eprintln!(
-            "  Root directory at sector {}, size {} bytes",
+    let root_offset = vd_sector * SECTOR_SIZE + 156;
+    let root_record = parse_directory_record(Bytes::of(&buffer).slice(156..), vd_type)
+        .map_err(|e| Error::corrupt_at(Format::Iso9660, root_offset, e))?;
+    observe::info(
+        observer,
+        Format::Iso9660,
+        "ECMA-119 8.4.18",
+        format_args!(
+            "Root directory at sector {}, size {} bytes",
             root_record.extent_location, root_record.data_length
-        );
-    }
-
-    // Check for Rock Ridge (we'll detect it when parsing the root directory)
-    let mut root_node = TreeNode::new_directory("/".to_string());
-    let use_rock_ridge = if vd_type == VolumeDescriptorType::Primary {
-        detect_rock_ridge(file, &root_record)?
-    } else {
-        false
-    };
-    if verbose && use_rock_ridge {
-        eprintln!("  Rock Ridge extensions detected");
-    }
+        ),
+    );
 
-    parse_directory(
-        file,
-        &root_record,
-        &mut root_node,
+    Ok(Volume {
+        root_record,
+        root_offset: vd_sector * SECTOR_SIZE + 156,
         vd_type,
-        use_rock_ridge,
-        verbose,
-    )?;
-
-    root_node.calculate_directory_size();
-    Ok(root_node)
+        rock_ridge: use_rock_ridge,
+        boot_catalog,
+    })
 }
 
-fn detect_rock_ridge<R: Read + Seek>(file: &mut R, dir_record: &DirectoryRecord) -> Result<bool> {
-    file.seek(SeekFrom::Start(
+fn detect_rock_ridge<'a, S: Source<'a>>(src: &mut S, dir_record: &DirectoryRecord) -> Result<bool> {
+    let buffer = src.bytes(
         dir_record.extent_location as u64 * SECTOR_SIZE,
-    ))?;
-    let mut buffer = vec![0u8; dir_record.data_length.min(4096) as usize];
-    file.read_exact(&mut buffer)?;
+        dir_record.data_length.min(4096) as usize,
+    )?;
 
     // Look at the first directory record's system use area for Rock Ridge signatures
     if buffer.len() < 34 {
@@ -184,14 +632,104 @@ fn detect_rock_ridge<R: Read + Seek>(file: &mut R, dir_record: &DirectoryRecord)
     Ok(false)
 }
 
-fn parse_directory_record(data: &[u8], vd_type: VolumeDescriptorType) -> Result<DirectoryRecord> {
+/// Describe the volume of the last session from its Primary, Joliet
+/// and Enhanced Volume Descriptors.
+pub(crate) fn describe_volume<'a, S: Source<'a>>(
+    src: &mut S,
+    observer: &mut dyn Observer,
+) -> Result<IsoVolume> {
+    let first = last_session(src, observer)? as u64 + PRIMARY_VOLUME_DESCRIPTOR_SECTOR;
+    let mut primary: Option<(u64, Cow<'a, [u8]>)> = None;
+    let mut joliet: Option<(Cow<'a, [u8]>, u8)> = None;
+    let mut iso9660_1999 = false;
+
+    let mut sector = first;
+    while let Ok(buffer) = src.bytes(sector * SECTOR_SIZE, SECTOR_SIZE as usize) {
+        if &buffer[1..6] != b"CD001" || buffer[0] == 255 {
+            break;
+        }
+        match buffer[0] {
+            1 if primary.is_none() => primary = Some((sector, buffer)),
+            // An Enhanced Volume Descriptor is a Supplementary one with
+            // version 2 (ISO 9660:1999 8.5.2).
+            2 if buffer[6] == 2 => iso9660_1999 = true,
+            2 if joliet.is_none() => {
+                let level = match &buffer[88..91] {
+                    b"%/@" => Some(1),
+                    b"%/C" => Some(2),
+                    b"%/E" => Some(3),
+                    _ => None,
+                };
+                joliet = level.map(|level| (buffer, level));
+            }
+            _ => {}
+        }
+        sector += 1;
+    }
+    let Some((pvd_sector, pvd)) = primary else {
+        return Err(Error::not_recognized(
+            Format::Iso9660,
+            "Not a valid ISO 9660 filesystem",
+        ));
+    };
+
+    // A Joliet identifier is UCS-2 in the same field; use it where given.
+    let text = |range: std::ops::Range<usize>| {
+        let joliet = joliet.as_ref().map(|(svd, _)| {
+            let units: Vec<u16> = svd[range.clone()]
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+                .trim_end_matches([' ', '\0'])
+                .to_string()
+        });
+        joliet.filter(|id| !id.is_empty()).unwrap_or_else(|| {
+            String::from_utf8_lossy(&pvd[range])
+                .trim_end_matches([' ', '\0'])
+                .to_string()
+        })
+    };
+
+    let root_offset = pvd_sector * SECTOR_SIZE + 156;
+    let root_record =
+        parse_directory_record(Bytes::of(&pvd).slice(156..), VolumeDescriptorType::Primary)
+            .map_err(|e| Error::corrupt_at(Format::Iso9660, root_offset, e))?;
+    Ok(IsoVolume {
+        system_id: text(8..40),
+        volume_id: text(40..72),
+        volume_set_id: text(190..318),
+        publisher_id: text(318..446),
+        preparer_id: text(446..574),
+        application_id: text(574..702),
+        created: parse_long_date(&pvd[813..830]),
+        modified: parse_long_date(&pvd[830..847]),
+        expires: parse_long_date(&pvd[847..864]),
+        effective: parse_long_date(&pvd[864..881]),
+        volume_space_size: u32::from_le_bytes([pvd[80], pvd[81], pvd[82], pvd[83]]),
+        joliet_level: joliet.as_ref().map(|&(_, level)| level),
+        rock_ridge: detect_rock_ridge(src, &root_record)?,
+        iso9660_1999,
+    })
+}
+
+fn parse_directory_record<'a>(
+    data: Bytes<'a, '_>,
+    vd_type: VolumeDescriptorType,
+) -> Result<DirectoryRecord<'a>> {
     if data.len() < 34 {
-        return Err("Directory record too short".into());
+        return Err(Error::corrupt(
+            Format::Iso9660,
+            "Directory record too short",
+        ));
     }
 
     let length = data[0];
     if length == 0 {
-        return Err("Zero-length directory record".into());
+        return Err(Error::corrupt(
+            Format::Iso9660,
+            "Zero-length directory record",
+        ));
     }
 
     let extent_location = u32::from_le_bytes([data[2], data[3], data[4], data[5]]);
@@ -201,19 +739,23 @@ fn parse_directory_record(data: &[u8], vd_type: VolumeDescriptorType) -> Result<
 
     // Ensure data[33..33+filename_length] is in bounds
     if 33 + filename_length > data.len() {
-        return Err("Directory record filename extends past buffer".into());
+        return Err(Error::corrupt(
+            Format::Iso9660,
+            "Directory record filename extends past buffer",
+        ));
     }
 
     let is_directory = (file_flags & 0x02) != 0;
+    let recorded_at = parse_recording_date(&data[18..25]);
 
     let filename = if filename_length == 0 || (filename_length == 1 && data[33] == 0) {
         // ECMA-119 7.6.12: a single 0x00 byte is the special "." (current)
         // directory entry; an empty filename is also treated as "." here.
-        ".".to_string()
+        Cow::Borrowed(".")
     } else if filename_length == 1 && data[33] == 1 {
         // ECMA-119 7.6.12: a single 0x01 byte is the special ".." (parent)
         // directory entry.
-        "..".to_string()
+        Cow::Borrowed("..")
     } else if vd_type == VolumeDescriptorType::Joliet {
         // Joliet uses UCS-2 big-endian encoding
         let utf16_data: Vec<u16> = data[33..33 + filename_length]
@@ -222,20 +764,22 @@ fn parse_directory_record(data: &[u8], vd_type: VolumeDescriptorType) -> Result<
             .collect();
         let raw_name = String::from_utf16_lossy(&utf16_data);
         // Remove ISO 9660 version suffix (;1, ;2, etc.)
-        if let Some(semicolon_pos) = raw_name.find(';') {
+        Cow::Owned(if let Some(semicolon_pos) = raw_name.find(';') {
             raw_name[..semicolon_pos].to_string()
         } else {
             raw_name
-        }
+        })
     } else {
-        let raw_name = String::from_utf8_lossy(&data[33..33 + filename_length]);
+        let raw_name = data.slice(33..33 + filename_length).utf8_lossy();
         // Remove ISO 9660 version suffix (;1, ;2, etc.) and trailing periods
-        let cleaned_name = if let Some(semicolon_pos) = raw_name.find(';') {
-            &raw_name[..semicolon_pos]
-        } else {
-            &raw_name
-        };
-        cleaned_name.trim_end_matches('.').to_string()
+        narrow(raw_name, |name| {
+            let cleaned_name = if let Some(semicolon_pos) = name.find(';') {
+                &name[..semicolon_pos]
+            } else {
+                name
+            };
+            cleaned_name.trim_end_matches('.')
+        })
     };
 
     Ok(DirectoryRecord {
@@ -243,72 +787,373 @@ fn parse_directory_record(data: &[u8], vd_type: VolumeDescriptorType) -> Result<
         data_length,
         is_directory,
         filename,
+        recorded_at,
     })
 }
 
-fn extract_rock_ridge_name(
-    data: &[u8],
+/// Decode the 7-byte directory record date (ECMA-119 9.1.5): years since
+/// 1900, month, day, hour, minute, second, and the offset from GMT in
+/// 15-minute intervals. An all-zero field means "not specified".
+fn parse_recording_date(b: &[u8]) -> Option<Timestamp> {
+    if b.iter().all(|&x| x == 0) {
+        return None;
+    }
+    let t = Timestamp::from_civil(
+        1900 + b[0] as i64,
+        b[1] as u32,
+        b[2] as u32,
+        b[3] as u32,
+        b[4] as u32,
+        b[5] as u32,
+    )?;
+    Some(t.with_utc_offset(b[6] as i8 as i32 * 15))
+}
+
+/// Decode a 17-byte volume descriptor date and time (ECMA-119
+/// 8.4.26.1): sixteen ASCII digits — year to hundredths of a second —
+/// then the offset from GMT in 15-minute units. All zeros means "not
+/// specified".
+fn parse_long_date(b: &[u8]) -> Option<Timestamp> {
+    let digits = b.get(..16)?;
+    if !digits.iter().all(u8::is_ascii_digit) || digits.iter().all(|&d| d == b'0') {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| {
+        digits[range]
+            .iter()
+            .fold(0u32, |n, d| n * 10 + (d - b'0') as u32)
+    };
+    let t = Timestamp::from_civil(
+        num(0..4) as i64,
+        num(4..6),
+        num(6..8),
+        num(8..10),
+        num(10..12),
+        num(12..14),
+    )?;
+    let t = Timestamp::new(t.secs, num(14..16) * 10_000_000);
+    Some(t.with_utc_offset(b[16] as i8 as i32 * 15))
+}
+
+/// The System Use field of a directory record (`raw`, `record_length`
+/// bytes long), or `None` if the name leaves no room for one.
+fn system_use<'a, 'b>(
+    raw: Bytes<'a, 'b>,
     record_length: usize,
     filename_length: usize,
-) -> Option<String> {
+) -> Option<Bytes<'a, 'b>> {
     // System use area starts after the filename + padding byte for even alignment
     let su_start = 33 + filename_length + ((filename_length + 1) % 2);
-    if su_start >= record_length {
+    if su_start >= record_length || record_length > raw.len() {
         return None;
     }
+    Some(raw.slice(su_start..record_length))
+}
 
-    let su_area = &data[su_start..record_length];
+/// Split a System Use area into its SUSP entries (SUSP 4.1): two
+/// signature bytes, a length, a version, then data. Stops at the first
+/// entry whose length does not fit.
+fn susp_entries<'a, 'b>(area: Bytes<'a, 'b>) -> impl Iterator<Item = Bytes<'a, 'b>> {
     let mut offset = 0;
-    let mut name_parts: Vec<u8> = Vec::new();
+    std::iter::from_fn(move || {
+        if offset + 4 > area.len() {
+            return None;
+        }
+        let entry_len = area[offset + 2] as usize;
+        if entry_len < 4 || offset + entry_len > area.len() {
+            return None;
+        }
+        let entry = area.slice(offset..offset + entry_len);
+        offset += entry_len;
+        Some(entry)
+    })
+}
 
-    while offset + 4 <= su_area.len() {
-        let sig = &su_area[offset..offset + 2];
-        let entry_len = su_area[offset + 2] as usize;
-        if entry_len < 4 || offset + entry_len > su_area.len() {
-            break;
+/// Both-endian 32-bit field at `at` (ECMA-119 7.3.3); the little-endian
+/// half is read.
+fn both_endian(entry: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]])
+}
+
+/// Decode a `PX` entry: mode, links, uid and gid, each a both-endian
+/// 32-bit field (RRIP 4.1.1).
+fn decode_px(entry: &[u8]) -> Option<PosixAttributes> {
+    (entry.len() >= 36).then(|| PosixAttributes {
+        mode: both_endian(entry, 4),
+        nlink: both_endian(entry, 12),
+        uid: both_endian(entry, 20),
+        gid: both_endian(entry, 28),
+    })
+}
+
+impl<'a> RockRidge<'a> {
+    /// Read the Rock Ridge entries of the record `raw`, following `CE`
+    /// continuation areas. A continuation area that cannot be read ends
+    /// the entries.
+    fn read<S: Source<'a>>(
+        src: &mut S,
+        raw: Bytes<'a, '_>,
+        filename_length: usize,
+    ) -> Result<Self> {
+        let mut rock_ridge = RockRidge::default();
+        let Some(area) = system_use(raw, raw.len(), filename_length) else {
+            return Ok(rock_ridge);
+        };
+        let mut next = rock_ridge.absorb(area);
+        for _ in 0..MAX_CONTINUATIONS {
+            let Some((offset, len)) = next else {
+                break;
+            };
+            limits::alloc(len as u64)?;
+            let area = match src.bytes(offset, len) {
+                Ok(area) => area,
+                Err(e) => {
+                    recover::note(Some(offset), "continuation area", e);
+                    break;
+                }
+            };
+            next = rock_ridge.absorb(Bytes::of(&area));
+        }
+        Ok(rock_ridge)
+    }
+
+    /// Take in the entries of one System Use area. Returns where its `CE`
+    /// entry says the entries continue, as a byte offset and length.
+    fn absorb(&mut self, area: Bytes<'a, '_>) -> Option<(u64, usize)> {
+        let mut continuation = None;
+        for entry in susp_entries(area) {
+            match &entry[..2] {
+                // SUSP 5.1: block, offset and length of the continuation.
+                b"CE" if entry.len() >= 28 => {
+                    let block = both_endian(&entry, 4) as u64;
+                    let offset = both_endian(&entry, 12) as u64;
+                    let len = both_endian(&entry, 20) as usize;
+                    continuation = Some((block * SECTOR_SIZE + offset, len));
+                }
+                // SUSP 5.4: no more entries in this area.
+                b"ST" => break,
+                // RRIP 4.1.4. Flags 0x02 (".") and 0x04 ("..") carry no
+                // name.
+                b"NM" if entry.len() >= 5 && entry[4] & 0x06 == 0 => {
+                    self.name.push(entry.slice(5..).to_cow());
+                }
+                b"PX" => self.posix = decode_px(&entry).or(self.posix),
+                b"SL" if entry.len() >= 5 => self.symlink_components(&entry[5..]),
+                // RRIP 4.1.2: high and low device number. Old writers put
+                // a whole 16-bit dev_t in the low word.
+                b"PN" if entry.len() >= 20 => {
+                    let high = both_endian(&entry, 4);
+                    let low = both_endian(&entry, 12);
+                    self.device = Some(if high == 0 && low & !0xFF != 0 {
+                        (low >> 8, low & 0xFF)
+                    } else {
+                        (high, low)
+                    });
+                }
+                b"TF" if entry.len() >= 5 => self.timestamps(entry[4], &entry[5..]),
+                // RRIP 4.1.5.1 and 4.1.5.3.
+                b"CL" if entry.len() >= 12 => self.child_link = Some(both_endian(&entry, 4)),
+                b"RE" => self.relocated = true,
+                _ => {}
+            }
         }
+        continuation
+    }
 
-        if sig == b"NM" {
-            // Rock Ridge Alternate Name entry
-            // byte 3 = version, byte 4 = flags
-            let flags = su_area[offset + 4];
-            if flags & 0x02 != 0 {
-                // CURRENT (.) - skip
-            } else if flags & 0x04 != 0 {
-                // PARENT (..) - skip
+    /// Append the component records of an `SL` entry (RRIP 4.1.3.1) to
+    /// the symlink target.
+    fn symlink_components(&mut self, mut records: &[u8]) {
+        let (target, continues) = self.symlink.get_or_insert_with(Default::default);
+        while records.len() >= 2 {
+            let flags = records[0];
+            let len = (records[1] as usize).min(records.len() - 2);
+            let content = &records[2..2 + len];
+            records = &records[2 + len..];
+
+            if !*continues && !target.is_empty() && !target.ends_with('/') {
+                target.push('/');
+            }
+            match flags & 0x0E {
+                0x02 => target.push('.'),
+                0x04 => target.push_str(".."),
+                0x08 => {
+                    if target.is_empty() {
+                        target.push('/');
+                    }
+                }
+                _ => target.push_str(&String::from_utf8_lossy(content)),
+            }
+            *continues = flags & 0x01 != 0;
+        }
+    }
+
+    /// Decode a `TF` entry's times (RRIP 4.1.6): one per flag bit set,
+    /// in bit order, 17 bytes each when flag 0x80 is set and 7 otherwise.
+    fn timestamps(&mut self, flags: u8, mut times: &[u8]) {
+        let size = if flags & 0x80 != 0 { 17 } else { 7 };
+        for bit in 0..7 {
+            if flags & (1 << bit) == 0 {
+                continue;
+            }
+            if times.len() < size {
+                break;
+            }
+            let time = if size == 17 {
+                parse_long_date(&times[..17])
             } else {
-                name_parts.extend_from_slice(&su_area[offset + 5..offset + entry_len]);
+                parse_recording_date(&times[..7])
+            };
+            times = &times[size..];
+            match bit {
+                0 => self.btime = time,
+                1 => self.mtime = time,
+                2 => self.atime = time,
+                3 => self.ctime = time,
+                // Backup, expiration and effective times.
+                _ => {}
             }
         }
+    }
 
-        offset += entry_len;
+    /// The `NM` name, if the record has one. A name in a single entry
+    /// can borrow from the image.
+    fn name(&mut self) -> Option<Cow<'a, str>> {
+        match std::mem::take(&mut self.name).as_mut_slice() {
+            [] => None,
+            [part] => Some(match std::mem::take(part) {
+                Cow::Borrowed(part) => String::from_utf8_lossy(part),
+                Cow::Owned(part) => Cow::Owned(String::from_utf8_lossy(&part).into_owned()),
+            }),
+            parts => {
+                let joined: Vec<u8> = parts.iter().flat_map(|part| part.iter().copied()).collect();
+                Some(Cow::Owned(String::from_utf8_lossy(&joined).into_owned()))
+            }
+        }
     }
+}
 
-    if name_parts.is_empty() {
-        None
+/// Decode the directory record at the start of `raw` (exactly one record,
+/// `raw.len()` bytes long) into its record and metadata, applying its
+/// Rock Ridge entries when `use_rock_ridge` is set.
+///
+/// A record with a Rock Ridge `CL` entry is decoded as the directory it
+/// stands in for. A directory whose `RE` entry marks it as relocated
+/// decodes to `None`: it is listed at its `CL` record instead.
+fn decode_entry<'a, S: Source<'a>>(
+    src: &mut S,
+    raw: Bytes<'a, '_>,
+    vd_type: VolumeDescriptorType,
+    use_rock_ridge: bool,
+) -> Result<Option<Entry<'a>>> {
+    let mut record = parse_directory_record(raw, vd_type)?;
+    let mut metadata = Metadata::new(if record.is_directory {
+        EntryKind::Directory
     } else {
-        Some(String::from_utf8_lossy(&name_parts).to_string())
+        EntryKind::File
+    });
+    metadata.mtime = record.recorded_at;
+
+    if !use_rock_ridge
+        || vd_type != VolumeDescriptorType::Primary
+        || record.filename == "."
+        || record.filename == ".."
+    {
+        return entry(raw, record, metadata).map(Some);
+    }
+
+    let mut rock_ridge = RockRidge::read(src, raw, raw[32] as usize)?;
+    if rock_ridge.relocated && record.is_directory {
+        return Ok(None);
+    }
+    if let Some(name) = rock_ridge.name() {
+        record.filename = name;
+    }
+    if let Some(sector) = rock_ridge.child_link {
+        // The relocated directory's own "." record gives its size.
+        let dot = src.bytes(sector as u64 * SECTOR_SIZE, 34)?;
+        let dot = parse_directory_record(Bytes::of(&dot), vd_type)?;
+        record.extent_location = sector;
+        record.data_length = dot.data_length;
+        record.is_directory = true;
+        metadata.kind = EntryKind::Directory;
+    }
+    if let Some(px) = rock_ridge.posix {
+        metadata.mode = Some(px.mode & 0o7777);
+        metadata.nlink = Some(px.nlink.into());
+        metadata.uid = Some(px.uid);
+        metadata.gid = Some(px.gid);
+        if !record.is_directory {
+            metadata.kind = EntryKind::from_posix_mode(px.mode);
+        }
+    }
+    if let Some((target, _)) = rock_ridge.symlink {
+        if !record.is_directory {
+            metadata.kind = EntryKind::Symlink;
+            metadata.link_target = Some(target);
+        }
     }
+    if matches!(
+        metadata.kind,
+        EntryKind::CharDevice | EntryKind::BlockDevice
+    ) {
+        metadata.device = rock_ridge.device;
+    }
+    metadata.mtime = rock_ridge.mtime.or(metadata.mtime);
+    metadata.atime = rock_ridge.atime;
+    metadata.ctime = rock_ridge.ctime;
+    metadata.btime = rock_ridge.btime;
+    entry(raw, record, metadata).map(Some)
 }
 
-fn parse_directory<R: Read + Seek>(
-    file: &mut R,
+/// Complete the [`Entry`] for `record`, decoded from `raw`, with its data
+/// runs.
+///
+/// An interleaved record (ECMA-119 6.4.3) stores its data in file units
+/// of `raw[26]` sectors, each followed by a gap of `raw[27]` sectors.
+fn entry<'a>(raw: Bytes, record: DirectoryRecord<'a>, metadata: Metadata) -> Result<Entry<'a>> {
+    let start = record.extent_location as u64 * SECTOR_SIZE;
+    let size = record.data_length as u64;
+    let unit = raw[26] as u64 * SECTOR_SIZE;
+    let gap = raw[27] as u64 * SECTOR_SIZE;
+    let runs = if record.is_directory {
+        Vec::new()
+    } else if unit == 0 || size <= unit {
+        vec![Extent::data(start, size)]
+    } else {
+        let count = size.div_ceil(unit);
+        limits::alloc(count * std::mem::size_of::<Extent>() as u64)?;
+        (0..count)
+            .map(|i| Extent::data(start + i * (unit + gap), unit.min(size - i * unit)))
+            .collect()
+    };
+    Ok(Entry {
+        continues: raw[25] & 0x80 != 0 && !record.is_directory,
+        record,
+        metadata,
+        size,
+        runs,
+    })
+}
+
+/// Read the extent of `dir_record` and decode every entry except `.`
+/// and `..`, returning each with the absolute byte offset of its (first)
+/// directory record. The records of a multi-extent file are merged into
+/// one entry.
+fn read_entries<'a, S: Source<'a>>(
+    src: &mut S,
     dir_record: &DirectoryRecord,
-    parent_node: &mut TreeNode,
     vd_type: VolumeDescriptorType,
     use_rock_ridge: bool,
-    verbose: bool,
-) -> Result<()> {
+) -> Result<Vec<(u64, Entry<'a>)>> {
+    let mut entries: Vec<(u64, Entry<'a>)> = Vec::new();
     if !dir_record.is_directory || dir_record.data_length == 0 {
-        return Ok(());
+        return Ok(entries);
     }
 
-    file.seek(SeekFrom::Start(
-        dir_record.extent_location as u64 * SECTOR_SIZE,
-    ))?;
-
-    let mut buffer = vec![0u8; dir_record.data_length as usize];
-    file.read_exact(&mut buffer)?;
+    let base = dir_record.extent_location as u64 * SECTOR_SIZE;
+    limits::alloc(dir_record.data_length.into())?;
+    let buffer = src.bytes(base, dir_record.data_length as usize)?;
+    let bytes = Bytes::of(&buffer);
 
     let mut offset = 0;
     while offset < buffer.len() {
@@ -325,62 +1170,216 @@ fn parse_directory<R: Read + Seek>(
 
         let record_length = buffer[offset] as usize;
         if record_length == 0 || offset + record_length > buffer.len() {
+            recover::note(
+                Some(base + offset as u64),
+                "directory record",
+                "record runs past the end of the directory extent",
+            );
             break;
         }
 
-        if let Ok(mut record) = parse_directory_record(&buffer[offset..], vd_type) {
-            // Try Rock Ridge alternate name
-            if use_rock_ridge
-                && vd_type == VolumeDescriptorType::Primary
-                && record.filename != "."
-                && record.filename != ".."
+        let raw = bytes.slice(offset..offset + record_length);
+        match decode_entry(src, raw, vd_type, use_rock_ridge) {
+            // Skip "." and ".." entries
+            Ok(Some(entry)) if entry.record.filename == "." || entry.record.filename == ".." => {}
+            Ok(Some(entry)) => match entries.last_mut() {
+                Some((_, file)) if file.continues_with(&entry) => file.append(entry),
+                _ => entries.push((base + offset as u64, entry)),
+            },
+            // A relocated directory, listed where it belongs.
+            Ok(None) => {}
+            Err(e @ Error::LimitExceeded { .. }) => return Err(e),
+            Err(e) => recover::note(Some(base + offset as u64), "directory record", e),
+        }
+
+        offset += record_length;
+    }
+
+    Ok(entries)
+}
+
+fn parse_directory<'a, S: Source<'a>>(
+    src: &mut S,
+    dir_record: &DirectoryRecord,
+    parent_node: &mut TreeNode<'a>,
+    vd_type: VolumeDescriptorType,
+    use_rock_ridge: bool,
+    observer: &mut dyn Observer,
+) -> Result<()> {
+    // A directory whose records lead back to an ancestor's extent would
+    // otherwise be listed forever.
+    let _level = limits::enter(dir_record.extent_location.into())?;
+    let entries = read_entries(src, dir_record, vd_type, use_rock_ridge)?;
+    limits::dir_entries(entries.len() as u64)?;
+    for (_, entry) in entries {
+        let record = entry.record.clone();
+        limits::entry(&record.filename)?;
+        observe::info(
+            observer,
+            Format::Iso9660,
+            "ECMA-119 9.1",
+            format_args!(
+                "Found {}: {}",
+                if record.is_directory { "dir" } else { "file" },
+                record.filename
+            ),
+        );
+        let mut node = entry.node(record.filename.clone());
+        if record.is_directory {
+            if let Err(e) =
+                parse_directory(src, &record, &mut node, vd_type, use_rock_ridge, observer)
             {
-                let filename_length = buffer[offset + 32] as usize;
-                if let Some(rr_name) = extract_rock_ridge_name(
-                    &buffer[offset..offset + record_length],
-                    record_length,
-                    filename_length,
-                ) {
-                    record.filename = rr_name;
-                }
+                let extent = record.extent_location as u64 * SECTOR_SIZE;
+                recover::skip(Some(extent), "directory", e)?;
             }
+        }
+        parent_node.add_child(node);
+    }
 
-            // Skip "." and ".." entries
-            if record.filename != "." && record.filename != ".." {
-                if verbose {
-                    eprintln!(
-                        "    Found {}: {}",
-                        if record.is_directory { "dir" } else { "file" },
-                        record.filename
-                    );
-                }
-                if record.is_directory {
-                    let mut dir_node = TreeNode::new_directory(record.filename.clone());
-                    parse_directory(
-                        file,
-                        &record,
-                        &mut dir_node,
-                        vd_type,
-                        use_rock_ridge,
-                        verbose,
-                    )?;
-                    parent_node.add_child(dir_node);
-                } else {
-                    let file_node = TreeNode::new_file_with_location(
-                        record.filename.clone(),
-                        record.data_length as u64,
-                        record.extent_location as u64 * SECTOR_SIZE,
-                        record.data_length as u64,
-                    );
-                    parent_node.add_child(file_node);
-                }
+    Ok(())
+}
+
+/// An ISO 9660 image opened for lazy, per-directory reads.
+///
+/// Uses the same hierarchy (Rock Ridge, else Joliet, else Primary) as
+/// [`parse_iso9660`], but reads a directory's extent only when it is
+/// listed. Handles locate directory records by their byte offset in the
+/// image. The virtual `[BOOT]` directory is not listed; read the boot
+/// images through [`crate::eltorito`].
+///
+/// ```no_run
+/// use isomage::iso9660::IsoFs;
+/// use isomage::Filesystem;
+/// use std::io::Read;
+///
+/// let mut fs = IsoFs::open(std::fs::File::open("disc.iso")?)?;
+/// let entry = fs.lookup("etc/hostname")?.ok_or("not in ISO")?;
+/// let mut text = String::new();
+/// fs.open(&entry)?.read_to_string(&mut text)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct IsoFs<R> {
+    image: R,
+    root_offset: u64,
+    vd_type: VolumeDescriptorType,
+    rock_ridge: bool,
+}
+
+impl<R: Read + Seek> IsoFs<R> {
+    /// Read the volume descriptors of the last session of `image`. No
+    /// directory is read yet.
+    pub fn open(mut image: R) -> Result<Self> {
+        let start = last_session(&mut Stream(&mut image), &mut ())?;
+        Self::open_at(image, start)
+    }
+
+    /// Like [`open`](Self::open), but read `session` instead of the last
+    /// one.
+    pub fn open_session(image: R, session: &Session) -> Result<Self> {
+        Self::open_at(image, session.start_lba)
+    }
+
+    fn open_at(mut image: R, start: u32) -> Result<Self> {
+        let volume = open_volume(&mut Stream(&mut image), start, &mut ())?;
+        Ok(Self {
+            image,
+            root_offset: volume.root_offset,
+            vd_type: volume.vd_type,
+            rock_ridge: volume.rock_ridge,
+        })
+    }
+
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    /// Re-read the entry a handle points at, following the records of a
+    /// multi-extent file.
+    fn entry_at(&mut self, offset: u64) -> Result<Entry<'static>> {
+        let (mut entry, mut next) = self.record_at(offset)?;
+        while entry.continues {
+            let Ok((more, after)) = self.record_at(next) else {
+                break;
+            };
+            if !entry.continues_with(&more) {
+                break;
             }
+            entry.append(more);
+            next = after;
         }
+        Ok(entry)
+    }
 
-        offset += record_length;
+    /// Decode the directory record at `offset`, or at the start of the
+    /// next sector if the rest of this one is padding. Returns the entry
+    /// and the offset just past the record.
+    fn record_at(&mut self, mut offset: u64) -> Result<(Entry<'static>, u64)> {
+        let mut len = [0u8; 1];
+        self.image.seek(SeekFrom::Start(offset))?;
+        self.image.read_exact(&mut len)?;
+        if len[0] == 0 && offset % SECTOR_SIZE != 0 {
+            offset = offset.next_multiple_of(SECTOR_SIZE);
+            self.image.seek(SeekFrom::Start(offset))?;
+            self.image.read_exact(&mut len)?;
+        }
+        let mut raw = vec![0u8; len[0] as usize];
+        if let Some(first) = raw.first_mut() {
+            *first = len[0];
+            self.image.read_exact(&mut raw[1..])?;
+        }
+        let entry = decode_entry(
+            &mut Stream(&mut self.image),
+            Bytes::Buffer(&raw),
+            self.vd_type,
+            self.rock_ridge,
+        )?
+        .ok_or_else(|| {
+            Error::corrupt_at(
+                Format::Iso9660,
+                offset,
+                "Rock Ridge relocated directory has no place in the tree",
+            )
+        })?;
+        Ok((entry, offset + raw.len() as u64))
     }
+}
 
-    Ok(())
+impl<R: Read + Seek> Filesystem for IsoFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Iso9660
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, self.root_offset)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> Result<Vec<DirEntry>> {
+        let (Entry { record, .. }, _) = self.record_at(dir.id)?;
+        if !record.is_directory {
+            return Err(not_a_directory(dir));
+        }
+        let mut image = Stream(&mut self.image);
+        let entries = read_entries(&mut image, &record, self.vd_type, self.rock_ridge)?;
+        Ok(entries
+            .into_iter()
+            .map(|(offset, entry)| {
+                let record = entry.record;
+                DirEntry::new(record.filename.into_owned(), record.is_directory, offset)
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> Result<TreeNode<'static>> {
+        Ok(self.entry_at(entry.id)?.node(entry.name.clone().into()))
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
 }
 
 #[cfg(test)]
@@ -390,6 +1389,31 @@ mod tests {
 
     const S: usize = 2048; // sector size
 
+    /// The Rock Ridge `PX` entry in a directory record's own System Use
+    /// field.
+    fn extract_rock_ridge_px(
+        data: &[u8],
+        record_length: usize,
+        filename_length: usize,
+    ) -> Option<PosixAttributes> {
+        let area = system_use(Bytes::Buffer(data), record_length, filename_length)?;
+        susp_entries(area)
+            .filter(|entry| &entry[..2] == b"PX")
+            .find_map(|entry| decode_px(&entry))
+    }
+
+    /// The Rock Ridge `NM` name in a directory record's own System Use
+    /// field.
+    fn extract_rock_ridge_name<'a>(
+        data: Bytes<'a, '_>,
+        record_length: usize,
+        filename_length: usize,
+    ) -> Option<Cow<'a, str>> {
+        let mut rock_ridge = RockRidge::default();
+        rock_ridge.absorb(system_use(data, record_length, filename_length)?);
+        rock_ridge.name()
+    }
+
     /// Build a minimal ISO 9660 Primary-only image (no Joliet).
     /// Puts one file "HELLO.TXT" in the root directory.
     fn make_iso_primary_only() -> Vec<u8> {
@@ -518,14 +1542,18 @@ mod tests {
     #[test]
     fn directory_record_too_short_errors() {
         let buf = [0u8; 10]; // less than 34 bytes
-        assert!(parse_directory_record(&buf, VolumeDescriptorType::Primary).is_err());
+        assert!(
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).is_err()
+        );
     }
 
     #[test]
     fn directory_record_zero_length_errors() {
         let mut buf = [0u8; 40];
         buf[0] = 0; // length = 0 → error
-        assert!(parse_directory_record(&buf, VolumeDescriptorType::Primary).is_err());
+        assert!(
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).is_err()
+        );
     }
 
     #[test]
@@ -534,7 +1562,8 @@ mod tests {
         buf[0] = 34;
         buf[32] = 1; // filename_length = 1
         buf[33] = 0; // filename = 0x00 → "."
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert_eq!(rec.filename, ".");
     }
 
@@ -544,7 +1573,8 @@ mod tests {
         buf[0] = 34;
         buf[32] = 1;
         buf[33] = 1; // 0x01 → ".."
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert_eq!(rec.filename, "..");
     }
 
@@ -555,7 +1585,8 @@ mod tests {
         buf[0] = (33 + name.len()) as u8;
         buf[32] = name.len() as u8;
         buf[33..33 + name.len()].copy_from_slice(name);
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert_eq!(rec.filename, "FILE.TXT");
     }
 
@@ -567,7 +1598,8 @@ mod tests {
         buf[0] = (33 + name.len()) as u8;
         buf[32] = name.len() as u8;
         buf[33..33 + name.len()].copy_from_slice(&name);
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Joliet).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Joliet).unwrap();
         assert_eq!(rec.filename, "hi");
     }
 
@@ -578,7 +1610,8 @@ mod tests {
         buf[25] = 0x02; // directory flag
         buf[32] = 1;
         buf[33] = 0;
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert!(rec.is_directory);
     }
 
@@ -669,8 +1702,8 @@ mod tests {
         data[su_off + 4] = 0; // flags = 0 (normal name)
         data[su_off + 5..su_off + 13].copy_from_slice(b"longname");
 
-        let result = extract_rock_ridge_name(&data, 60, 1);
-        assert_eq!(result, Some("longname".to_string()));
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 60, 1);
+        assert_eq!(result.as_deref(), Some("longname"));
     }
 
     #[test]
@@ -687,10 +1720,27 @@ mod tests {
         data[su_off + 3] = 1;
         data[su_off + 4] = 0x04; // PARENT flag
         data[su_off + 5..su_off + 13].copy_from_slice(b"ignored!");
-        let result = extract_rock_ridge_name(&data, 60, 1);
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 60, 1);
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn parse_iso9660_image_borrows_names() {
+        let img = make_iso_primary_only();
+        let root = parse_iso9660_image(&img).unwrap();
+        let hello = root.find_node("/HELLO.TXT").unwrap();
+        assert!(matches!(hello.name, Cow::Borrowed("HELLO.TXT")));
+        let streamed = parse_iso9660(&mut Cursor::new(&img)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+
+        // Joliet names are UCS-2 on disc, so they are always decoded.
+        let img = make_iso_joliet();
+        let root = parse_iso9660_image(&img).unwrap();
+        assert!(matches!(root.children[0].name, Cow::Owned(_)));
+        let streamed = parse_iso9660(&mut Cursor::new(&img)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+    }
+
     // ── real images ───────────────────────────────────────────────────────────
 
     #[test]
@@ -725,7 +1775,9 @@ mod tests {
         let mut buf = [0u8; 50];
         buf[0] = 50;
         buf[32] = 100; // filename_length = 100, overflows
-        assert!(parse_directory_record(&buf, VolumeDescriptorType::Primary).is_err());
+        assert!(
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).is_err()
+        );
     }
 
     #[test]
@@ -736,7 +1788,8 @@ mod tests {
         buf[0] = (33 + name.len()) as u8;
         buf[32] = name.len() as u8;
         buf[33..33 + name.len()].copy_from_slice(name);
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert_eq!(rec.filename, "HELLO");
     }
 
@@ -751,7 +1804,8 @@ mod tests {
         buf[0] = (33 + name.len()) as u8;
         buf[32] = name.len() as u8;
         buf[33..33 + name.len()].copy_from_slice(&name);
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Joliet).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Joliet).unwrap();
         assert_eq!(rec.filename, "hi.txt");
     }
 
@@ -761,7 +1815,8 @@ mod tests {
         let mut buf = [0u8; 40];
         buf[0] = 34;
         buf[32] = 0; // filename_length = 0
-        let rec = parse_directory_record(&buf, VolumeDescriptorType::Primary).unwrap();
+        let rec =
+            parse_directory_record(Bytes::Buffer(&buf), VolumeDescriptorType::Primary).unwrap();
         assert_eq!(rec.filename, ".");
     }
 
@@ -772,7 +1827,7 @@ mod tests {
         // su_start >= record_length → return None (line 257).
         let data = vec![0u8; 40];
         // filename_length=10 → su_start = 33+10+((10+1)%2) = 33+10+1 = 44 > record_length=40
-        let result = extract_rock_ridge_name(&data, 40, 10);
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 40, 10);
         assert_eq!(result, None);
     }
 
@@ -787,7 +1842,7 @@ mod tests {
         data[su_off + 3] = 1;
         data[su_off + 4] = 0x02; // CURRENT flag
         data[su_off + 5..su_off + 13].copy_from_slice(b"ignored!");
-        let result = extract_rock_ridge_name(&data, 60, 1);
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 60, 1);
         assert_eq!(
             result, None,
             "CURRENT-flagged NM entry should produce no name"
@@ -802,7 +1857,7 @@ mod tests {
         data[su_off] = b'N';
         data[su_off + 1] = b'M';
         data[su_off + 2] = 2; // entry_len=2 < 4 → break
-        let result = extract_rock_ridge_name(&data, 60, 1);
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 60, 1);
         assert_eq!(result, None);
     }
 
@@ -823,8 +1878,8 @@ mod tests {
         data[su_off + 6] = b'i';
         data[su_off + 7] = b'!';
         // After processing this entry: offset=8, 8+4=12 > 8=su_area.len() → while exits normally.
-        let result = extract_rock_ridge_name(&data, 42, 1);
-        assert_eq!(result, Some("hi!".to_string()));
+        let result = extract_rock_ridge_name(Bytes::Buffer(&data), 42, 1);
+        assert_eq!(result.as_deref(), Some("hi!"));
     }
 
     // ── parse_iso9660: additional parse paths ────────────────────────────────
@@ -939,17 +1994,18 @@ mod tests {
             extent_location: 0,
             data_length: 100,
             is_directory: false,
-            filename: "file.txt".to_string(),
+            filename: "file.txt".into(),
+            recorded_at: None,
         };
         let mut parent = crate::tree::TreeNode::new_directory("/".to_string());
         let mut c = Cursor::new(img);
         let result = parse_directory(
-            &mut c,
+            &mut Stream(&mut c),
             &rec,
             &mut parent,
             VolumeDescriptorType::Primary,
             false,
-            false,
+            &mut (),
         );
         assert!(result.is_ok());
         assert!(parent.children.is_empty());
@@ -1238,4 +2294,802 @@ mod tests {
             "Rock Ridge NM name not applied"
         );
     }
+
+    #[test]
+    fn recording_date_decodes_with_gmt_offset() {
+        // 2024-03-05 12:34:56 at GMT+1 (4 × 15 min) → 11:34:56 UTC.
+        let t = parse_recording_date(&[124, 3, 5, 12, 34, 56, 4]).unwrap();
+        assert_eq!(t, Timestamp::from_civil(2024, 3, 5, 11, 34, 56).unwrap());
+        assert!(parse_recording_date(&[0; 7]).is_none());
+        assert!(parse_recording_date(&[124, 13, 5, 0, 0, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn rock_ridge_px_sets_mode_uid_gid_and_kind() {
+        // Record with a 3-byte name, so the system use area starts at 36.
+        let mut rec = vec![0u8; 36 + 36];
+        rec[0] = rec.len() as u8;
+        rec[32] = 3;
+        rec[33..36].copy_from_slice(b"L;1");
+        let px = &mut rec[36..];
+        px[0..2].copy_from_slice(b"PX");
+        px[2] = 36;
+        px[3] = 1;
+        px[4..8].copy_from_slice(&0o120777u32.to_le_bytes()); // symlink, rwxrwxrwx
+        px[12..16].copy_from_slice(&1u32.to_le_bytes());
+        px[20..24].copy_from_slice(&1000u32.to_le_bytes());
+        px[28..32].copy_from_slice(&100u32.to_le_bytes());
+        let attrs = extract_rock_ridge_px(&rec, rec.len(), 3).unwrap();
+        assert_eq!(
+            attrs,
+            PosixAttributes {
+                mode: 0o120777,
+                nlink: 1,
+                uid: 1000,
+                gid: 100
+            }
+        );
+        assert_eq!(EntryKind::from_posix_mode(attrs.mode), EntryKind::Symlink);
+    }
+
+    #[test]
+    fn parse_iso9660_file_carries_recording_date() {
+        let mut img = make_iso_primary_only();
+        let root = {
+            let mut c = Cursor::new(img.clone());
+            parse_iso9660(&mut c).unwrap()
+        };
+        assert!(root.metadata.kind == EntryKind::Directory);
+        // Stamp a date on every record in the root directory.
+        let root_lba = u32::from_le_bytes(img[16 * S + 158..16 * S + 162].try_into().unwrap());
+        let dir = root_lba as usize * S;
+        let mut off = dir;
+        while img[off] != 0 {
+            img[off + 18..off + 25].copy_from_slice(&[99, 12, 31, 23, 59, 59, 0]);
+            off += img[off] as usize;
+        }
+        let mut c = Cursor::new(img);
+        let root = parse_iso9660(&mut c).unwrap();
+        let file = root.children.iter().find(|n| !n.is_directory).unwrap();
+        assert_eq!(file.metadata.kind, EntryKind::File);
+        assert_eq!(
+            file.metadata.mtime,
+            Timestamp::from_civil(1999, 12, 31, 23, 59, 59)
+        );
+    }
+
+    // ── IsoFs ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn iso_fs_lists_and_reads_lazily() {
+        let mut fs = IsoFs::open(Cursor::new(make_iso_primary_only())).unwrap();
+        let root = fs.root();
+        let names: Vec<String> = fs
+            .read_dir(&root)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["HELLO.TXT"]);
+
+        let hello = fs.lookup("/HELLO.TXT").unwrap().unwrap();
+        assert!(!hello.is_directory);
+        assert_eq!(fs.stat(&hello).unwrap().size, 11);
+        let mut text = String::new();
+        fs.open(&hello).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "Hello World");
+        assert!(fs.read_dir(&hello).is_err());
+    }
+
+    #[test]
+    fn iso_fs_prefers_joliet_like_the_eager_parser() {
+        let img = make_iso_joliet();
+        let eager = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        let lazy = IsoFs::open(Cursor::new(img)).unwrap().to_tree().unwrap();
+        assert!(lazy.find_node("hi.txt").is_some());
+        assert_eq!(
+            format!("{:?}", lazy.children),
+            format!("{:?}", eager.children)
+        );
+        assert_eq!(lazy.size, eager.size);
+    }
+
+    #[test]
+    fn directory_loop_is_corrupt() {
+        // Turn HELLO.TXT into a subdirectory whose extent is the root's.
+        let mut img = make_iso_primary_only();
+        let e2 = 18 * S + 68;
+        img[e2 + 2..e2 + 6].copy_from_slice(&18u32.to_le_bytes());
+        img[e2 + 10..e2 + 14].copy_from_slice(&(S as u32).to_le_bytes());
+        img[e2 + 25] = 0x02;
+        let err = parse_iso9660(&mut Cursor::new(img.clone())).unwrap_err();
+        assert!(matches!(err, Error::Corrupt { .. }), "{err:?}");
+        assert!(err.to_string().contains("directory loop"), "{err}");
+        let err = parse_iso9660_image(&img).unwrap_err();
+        assert!(matches!(err, Error::Corrupt { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn parse_options_cap_the_parse() {
+        use crate::limits::{Limit, ParseOptions};
+        let img = make_iso_primary_only();
+        let options = ParseOptions::new().max_entries(0);
+        let err =
+            crate::probe::parse_as_with(&mut Cursor::new(img.clone()), Format::Iso9660, &options)
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                format: Format::Iso9660,
+                limit: Limit::Entries,
+                max: 0
+            }
+        ));
+        let options = ParseOptions::new().max_name_len(4);
+        let err = crate::detect_and_parse_filesystem_with(
+            &mut Cursor::new(img.clone()),
+            "t.iso",
+            &options,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("more than 4 bytes in one name"),
+            "{err}"
+        );
+        let options = ParseOptions::new().max_entries(1);
+        assert!(
+            crate::probe::parse_as_with(&mut Cursor::new(img), Format::Iso9660, &options).is_ok()
+        );
+    }
+
+    #[test]
+    fn lenient_parse_skips_a_damaged_directory() {
+        use crate::limits::ParseOptions;
+        // HELLO.TXT becomes a subdirectory whose extent is past the end.
+        let mut img = make_iso_primary_only();
+        let e2 = 18 * S + 68;
+        img[e2 + 2..e2 + 6].copy_from_slice(&1000u32.to_le_bytes());
+        img[e2 + 10..e2 + 14].copy_from_slice(&(S as u32).to_le_bytes());
+        img[e2 + 25] = 0x02;
+        assert!(parse_iso9660(&mut Cursor::new(img.clone())).is_err());
+
+        let options = ParseOptions::default();
+        let parsed =
+            crate::detect_and_parse_filesystem_lenient(&mut Cursor::new(img), "t.iso", &options)
+                .unwrap();
+        let hello = parsed.root.find_node("HELLO.TXT").unwrap();
+        assert!(hello.is_directory && hello.children.is_empty());
+        assert_eq!(parsed.warnings.len(), 1, "{:?}", parsed.warnings);
+        let warning = &parsed.warnings[0];
+        assert_eq!(warning.format, Format::Iso9660);
+        assert_eq!(warning.structure, "directory");
+        assert_eq!(warning.offset, Some(1000 * S as u64));
+    }
+
+    #[test]
+    fn lenient_parse_drops_a_directory_loop() {
+        use crate::limits::ParseOptions;
+        let mut img = make_iso_primary_only();
+        let e2 = 18 * S + 68;
+        img[e2 + 2..e2 + 6].copy_from_slice(&18u32.to_le_bytes());
+        img[e2 + 10..e2 + 14].copy_from_slice(&(S as u32).to_le_bytes());
+        img[e2 + 25] = 0x02;
+        let parsed = crate::probe::parse_as_lenient(
+            &mut Cursor::new(img),
+            Format::Iso9660,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        // The looping directory appears once, empty.
+        let hello = parsed.root.find_node("HELLO.TXT").unwrap();
+        assert!(hello.children.is_empty());
+        assert!(parsed.warnings[0].reason.contains("directory loop"));
+    }
+
+    #[test]
+    fn lenient_parse_of_a_sound_image_has_no_warnings() {
+        use crate::limits::ParseOptions;
+        let img = make_iso_joliet();
+        let parsed = crate::probe::parse_as_lenient(
+            &mut Cursor::new(img.clone()),
+            Format::Iso9660,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert!(parsed.warnings.is_empty());
+        let strict = parse_iso9660(&mut Cursor::new(img)).unwrap();
+        assert_eq!(format!("{:?}", parsed.root), format!("{strict:?}"));
+    }
+
+    /// [`make_iso_primary_only`] with an El Torito Boot Record at sector
+    /// 17 whose catalog (sector 20) boots HELLO.TXT on x86 and a 4 KiB FAT
+    /// image at sector 21 on EFI.
+    fn make_bootable_iso() -> Vec<u8> {
+        let mut img = make_iso_primary_only();
+        img.resize(S * 24, 0);
+
+        // Boot Record replaces the terminator; the scan stops at the
+        // root directory in sector 18.
+        let br = 17 * S;
+        img[br] = 0;
+        img[br + 7..br + 30].copy_from_slice(b"EL TORITO SPECIFICATION");
+        img[br + 71..br + 75].copy_from_slice(&20u32.to_le_bytes());
+
+        let cat = 20 * S;
+        img[cat] = 1; // validation entry, x86
+        img[cat + 30] = 0x55;
+        img[cat + 31] = 0xAA;
+        let sum = img[cat..cat + 32].chunks_exact(2).fold(0u16, |s, w| {
+            s.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+        });
+        img[cat + 28..cat + 30].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+        // Default entry: HELLO.TXT, no emulation, 4 virtual sectors.
+        img[cat + 32] = 0x88;
+        img[cat + 38..cat + 40].copy_from_slice(&4u16.to_le_bytes());
+        img[cat + 40..cat + 44].copy_from_slice(&19u32.to_le_bytes());
+        // Final section header for EFI with one entry.
+        img[cat + 64] = 0x91;
+        img[cat + 65] = 0xEF;
+        img[cat + 66] = 1;
+        img[cat + 96] = 0x88;
+        img[cat + 102..cat + 104].copy_from_slice(&1u16.to_le_bytes());
+        img[cat + 104..cat + 108].copy_from_slice(&21u32.to_le_bytes());
+
+        // FAT boot sector: 512-byte sectors, 8 of them.
+        let efi = 21 * S;
+        img[efi + 11..efi + 13].copy_from_slice(&512u16.to_le_bytes());
+        img[efi + 19..efi + 21].copy_from_slice(&8u16.to_le_bytes());
+        img[efi + 510] = 0x55;
+        img[efi + 511] = 0xAA;
+        img[efi + 4095] = 0xEF;
+        img
+    }
+
+    #[test]
+    fn boot_images_are_listed_under_boot() {
+        let img = make_bootable_iso();
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        let boot = root.find_node("[BOOT]").unwrap();
+        let names: Vec<_> = boot.children.iter().map(|c| c.name.as_ref()).collect();
+        assert_eq!(names, ["x86.img", "efi.img"]);
+
+        // The x86 image is the ISO file at its sector; the EFI image is
+        // sized by its FAT boot sector, not its one-sector load count.
+        let x86 = root.find_node("[BOOT]/x86.img").unwrap();
+        assert_eq!(x86.size, 11);
+        let efi = root.find_node("[BOOT]/efi.img").unwrap();
+        assert_eq!(efi.size, 4096);
+        let mut out = Vec::new();
+        crate::cat_node(&mut Cursor::new(img.clone()), efi, &mut out).unwrap();
+        assert_eq!(out, &img[21 * S..21 * S + 4096]);
+
+        let borrowed = parse_iso9660_image(&img).unwrap();
+        assert!(borrowed.find_node("[BOOT]/efi.img").is_some());
+    }
+
+    #[test]
+    fn read_boot_catalog_reports_entries() {
+        use crate::eltorito::{read_boot_catalog, Emulation, Platform};
+        let catalog = read_boot_catalog(&mut Cursor::new(make_bootable_iso()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(catalog.offset, 20 * S as u64);
+        assert_eq!(catalog.entries.len(), 2);
+        assert_eq!(catalog.entries[0].platform, Platform::X86);
+        assert_eq!(catalog.entries[0].sector_count, 4);
+        assert_eq!(catalog.entries[1].platform, Platform::Efi);
+        assert_eq!(catalog.entries[1].emulation, Emulation::NoEmulation);
+        assert_eq!(catalog.entries[1].load_rba, 21);
+
+        let plain = read_boot_catalog(&mut Cursor::new(make_iso_primary_only())).unwrap();
+        assert!(plain.is_none());
+    }
+
+    #[test]
+    fn damaged_boot_catalog_leaves_the_tree() {
+        use crate::limits::ParseOptions;
+        let mut img = make_bootable_iso();
+        img[20 * S + 30] = 0; // validation key
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        assert!(root.find_node("[BOOT]").is_none());
+        assert!(root.find_node("HELLO.TXT").is_some());
+
+        let parsed = crate::probe::parse_as_lenient(
+            &mut Cursor::new(img),
+            Format::Iso9660,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].structure, "boot catalog");
+    }
+
+    /// A two-session image: session 1 is [`make_iso_primary_only`]
+    /// (sectors 0-19); session 2 starts at sector 30 and lists HELLO.TXT
+    /// from session 1 and NEW.TXT at sector 49.
+    fn make_multisession_iso() -> Vec<u8> {
+        let mut img = make_iso_primary_only();
+        img[16 * S + 80..16 * S + 84].copy_from_slice(&20u32.to_le_bytes());
+        img[16 * S + 40..16 * S + 45].copy_from_slice(b"FIRST");
+        img.resize(S * 50, 0);
+
+        // Session 2: PVD at 30 + 16, terminator, root directory at 48.
+        let pvd = 46 * S;
+        img.copy_within(16 * S..17 * S, pvd);
+        img[pvd + 40..pvd + 45].copy_from_slice(b"LATER");
+        img[pvd + 80..pvd + 84].copy_from_slice(&50u32.to_le_bytes());
+        img[pvd + 156 + 2..pvd + 156 + 6].copy_from_slice(&48u32.to_le_bytes());
+        img.copy_within(17 * S..18 * S, 47 * S);
+
+        let dir = 48 * S;
+        img.copy_within(18 * S..18 * S + 112, dir);
+        let e3 = dir + 112;
+        let name = b"NEW.TXT;1";
+        img[e3] = (33 + name.len()) as u8;
+        img[e3 + 2..e3 + 6].copy_from_slice(&49u32.to_le_bytes());
+        img[e3 + 10..e3 + 14].copy_from_slice(&5u32.to_le_bytes());
+        img[e3 + 32] = name.len() as u8;
+        img[e3 + 33..e3 + 33 + name.len()].copy_from_slice(name);
+        img[49 * S..49 * S + 5].copy_from_slice(b"later");
+        img
+    }
+
+    #[test]
+    fn multisession_image_reads_the_last_session() {
+        let img = make_multisession_iso();
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        assert!(root.find_node("HELLO.TXT").is_some());
+        assert!(root.find_node("NEW.TXT").is_some());
+
+        let sessions = list_sessions(&mut Cursor::new(img.clone())).unwrap();
+        let starts: Vec<_> = sessions.iter().map(|s| s.start_lba).collect();
+        assert_eq!(starts, [0, 30]);
+        assert_eq!(sessions[0].volume_id, "FIRST");
+        assert_eq!(sessions[1].volume_space_size, 50);
+
+        let first = parse_iso9660_session(&mut Cursor::new(img.clone()), &sessions[0]).unwrap();
+        assert!(first.find_node("HELLO.TXT").is_some());
+        assert!(first.find_node("NEW.TXT").is_none());
+
+        let mut fs = IsoFs::open_session(Cursor::new(img.clone()), &sessions[0]).unwrap();
+        assert!(fs.lookup("NEW.TXT").unwrap().is_none());
+        let mut fs = IsoFs::open(Cursor::new(img)).unwrap();
+        assert!(fs.lookup("NEW.TXT").unwrap().is_some());
+    }
+
+    #[test]
+    fn single_session_and_stored_isos_are_one_session() {
+        let mut img = make_iso_primary_only();
+        img[16 * S + 80..16 * S + 84].copy_from_slice(&20u32.to_le_bytes());
+        assert_eq!(
+            list_sessions(&mut Cursor::new(img.clone())).unwrap().len(),
+            1
+        );
+
+        // An ISO stored after the volume, e.g. a file appended to the
+        // image, counts its Volume Space Size from its own start.
+        let stored = img.clone();
+        img.resize(S * 22, 0);
+        img.extend_from_slice(&stored);
+        assert_eq!(list_sessions(&mut Cursor::new(img)).unwrap().len(), 1);
+
+        let err = list_sessions(&mut Cursor::new(vec![0u8; S * 20])).unwrap_err();
+        assert!(matches!(err, Error::NotRecognized { .. }));
+    }
+
+    /// Counts the reads made through it.
+    struct CountingSource<S> {
+        inner: S,
+        reads: usize,
+    }
+
+    impl<'a, S: Source<'a>> Source<'a> for CountingSource<S> {
+        fn bytes(&mut self, offset: u64, len: usize) -> std::io::Result<Cow<'a, [u8]>> {
+            self.reads += 1;
+            self.inner.bytes(offset, len)
+        }
+
+        fn len(&mut self) -> std::io::Result<u64> {
+            self.inner.len()
+        }
+    }
+
+    #[test]
+    fn session_gap_is_read_in_chunks() {
+        // One session followed by a large trailing partition: the whole
+        // gap is searched and nothing is found.
+        let mut img = make_iso_primary_only();
+        img[16 * S + 80..16 * S + 84].copy_from_slice(&20u32.to_le_bytes());
+        img.resize(S * (20 + MAX_SESSION_GAP as usize + 1000), 0);
+        let mut cursor = Cursor::new(img);
+        let mut src = CountingSource {
+            inner: Stream(&mut cursor),
+            reads: 0,
+        };
+        let sessions = find_sessions(&mut src, &mut ()).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let chunks = (MAX_SESSION_GAP / SESSION_GAP_CHUNK) as usize;
+        assert!(src.reads <= chunks + 8, "{} reads", src.reads);
+
+        // A second session is still found through the chunked search.
+        let img = make_multisession_iso();
+        let mut cursor = Cursor::new(img);
+        let mut src = CountingSource {
+            inner: Stream(&mut cursor),
+            reads: 0,
+        };
+        let starts: Vec<_> = find_sessions(&mut src, &mut ())
+            .unwrap()
+            .iter()
+            .map(|s| s.start_lba)
+            .collect();
+        assert_eq!(starts, [0, 30]);
+    }
+
+    /// A SUSP entry with signature `sig` and `data` after the version.
+    fn susp(sig: &[u8; 2], data: &[u8]) -> Vec<u8> {
+        let mut entry = vec![sig[0], sig[1], (4 + data.len()) as u8, 1];
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    /// A both-endian 32-bit field.
+    fn both(v: u32) -> [u8; 8] {
+        let mut b = [0u8; 8];
+        b[..4].copy_from_slice(&v.to_le_bytes());
+        b[4..].copy_from_slice(&v.to_be_bytes());
+        b
+    }
+
+    /// A directory record named `name` with System Use entries `su`.
+    fn record(name: &[u8], extent: u32, size: u32, dir: bool, su: &[u8]) -> Vec<u8> {
+        let pad = (name.len() + 1) % 2;
+        let mut r = vec![0u8; 33 + name.len() + pad];
+        r[2..10].copy_from_slice(&both(extent));
+        r[10..18].copy_from_slice(&both(size));
+        r[25] = if dir { 0x02 } else { 0 };
+        r[32] = name.len() as u8;
+        r[33..33 + name.len()].copy_from_slice(name);
+        r.extend_from_slice(su);
+        if r.len() % 2 == 1 {
+            r.push(0);
+        }
+        r[0] = r.len() as u8;
+        r
+    }
+
+    fn px(mode: u32, nlink: u32) -> Vec<u8> {
+        [both(mode), both(nlink), both(0), both(0)].concat()
+    }
+
+    /// A Rock Ridge image exercising PX, SL, PN, TF, CE and directory
+    /// relocation:
+    ///
+    /// - sector 18: root with `link` (→ `/usr/lib`), `tty`, `notes.txt`
+    ///   (name and times in a continuation area at sector 20), `deep`
+    ///   (a `CL` placeholder for sector 22) and `rr_moved`;
+    /// - sector 21: `rr_moved`, holding the relocated `deep` (`RE`);
+    /// - sector 22: `deep`, holding `x.txt`.
+    fn make_rock_ridge_iso() -> Vec<u8> {
+        let mut img = vec![0u8; S * 24];
+        let pvd = 16 * S;
+        img[pvd] = 1;
+        img[pvd + 1..pvd + 6].copy_from_slice(b"CD001");
+        let root = record(&[0], 18, S as u32, true, &[]);
+        img[pvd + 156..pvd + 156 + root.len()].copy_from_slice(&root);
+        img[17 * S] = 255;
+        img[17 * S + 1..17 * S + 6].copy_from_slice(b"CD001");
+
+        let dir = |img: &mut Vec<u8>, sector: usize, parent: u32, records: &[Vec<u8>]| {
+            let mut at = sector * S;
+            let dot_su = if sector == 18 {
+                susp(b"SP", &[0xBE, 0xEF, 0])
+            } else {
+                Vec::new()
+            };
+            let dot = record(&[0], sector as u32, S as u32, true, &dot_su);
+            let dotdot = record(&[1], parent, S as u32, true, &[]);
+            for r in [&dot, &dotdot].into_iter().chain(records) {
+                img[at..at + r.len()].copy_from_slice(r);
+                at += r.len();
+            }
+        };
+
+        // `/usr/lib`, its last component split over two SL entries.
+        let mut link_su = susp(b"NM", b"\0link");
+        link_su.extend(susp(b"PX", &px(0o120777, 1)));
+        link_su.extend(susp(
+            b"SL",
+            &[0x01, 0x08, 0, 0, 3, b'u', b's', b'r', 0x01, 2, b'l', b'i'],
+        ));
+        link_su.extend(susp(b"SL", &[0, 0, 1, b'b']));
+
+        let mut tty_su = susp(b"NM", b"\0tty");
+        tty_su.extend(susp(b"PX", &px(0o020620, 1)));
+        tty_su.extend(susp(b"PN", &[both(4), both(1)].concat()));
+
+        // The name and times of NOTES.TXT continue at sector 20.
+        let ce = susp(b"CE", &[both(20), both(0), both(64)].concat());
+        let mut cont = susp(b"NM", b"\0notes.txt");
+        let mut tf = vec![0x06]; // modify, access
+        tf.extend_from_slice(&[124, 3, 5, 12, 0, 0, 0]);
+        tf.extend_from_slice(&[124, 3, 6, 12, 0, 0, 0]);
+        cont.extend(susp(b"TF", &tf));
+        cont.extend(susp(b"ST", &[]));
+        cont.extend(susp(b"NM", b"\0ignored"));
+        img[20 * S..20 * S + cont.len()].copy_from_slice(&cont);
+
+        let mut deep_su = susp(b"NM", b"\0deep");
+        deep_su.extend(susp(b"CL", &both(22)));
+        let mut moved_su = susp(b"NM", b"\0deep");
+        moved_su.extend(susp(b"RE", &[]));
+
+        dir(
+            &mut img,
+            18,
+            18,
+            &[
+                record(b"LINK.;1", 0, 0, false, &link_su),
+                record(b"TTY.;1", 0, 0, false, &tty_su),
+                record(b"NOTES.TXT;1", 19, 5, false, &ce),
+                record(b"DEEP.;1", 0, 0, false, &deep_su),
+                record(b"RR_MOVED", 21, S as u32, true, &susp(b"NM", b"\0rr_moved")),
+            ],
+        );
+        img[19 * S..19 * S + 5].copy_from_slice(b"notes");
+        dir(
+            &mut img,
+            21,
+            18,
+            &[record(b"DEEP", 22, S as u32, true, &moved_su)],
+        );
+        dir(
+            &mut img,
+            22,
+            18,
+            &[record(b"X.TXT;1", 19, 5, false, &susp(b"NM", b"\0x.txt"))],
+        );
+        img
+    }
+
+    #[test]
+    fn rock_ridge_symlinks_devices_and_times() {
+        let img = make_rock_ridge_iso();
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+
+        let link = root.find_node("link").unwrap();
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+        assert_eq!(link.metadata.link_target.as_deref(), Some("/usr/lib"));
+        assert_eq!(link.metadata.nlink, Some(1));
+
+        let tty = root.find_node("tty").unwrap();
+        assert_eq!(tty.metadata.kind, EntryKind::CharDevice);
+        assert_eq!(tty.metadata.device, Some((4, 1)));
+        assert_eq!(tty.metadata.mode, Some(0o620));
+
+        // Found through the CE continuation area; the ST entry ends it.
+        let notes = root.find_node("notes.txt").unwrap();
+        assert_eq!(
+            notes.metadata.mtime,
+            Timestamp::from_civil(2024, 3, 5, 12, 0, 0)
+        );
+        assert_eq!(
+            notes.metadata.atime,
+            Timestamp::from_civil(2024, 3, 6, 12, 0, 0)
+        );
+
+        let borrowed = parse_iso9660_image(&img).unwrap();
+        assert!(borrowed.find_node("notes.txt").is_some());
+    }
+
+    #[test]
+    fn rock_ridge_is_preferred_over_joliet() {
+        // A Joliet descriptor alongside the Rock Ridge primary, its root
+        // at sector 23 listing the symlink as a plain file.
+        let mut img = make_rock_ridge_iso();
+        let svd = 17 * S;
+        img[svd] = 2;
+        img[svd + 88..svd + 91].copy_from_slice(b"%/E");
+        let jroot = record(&[0], 23, S as u32, true, &[]);
+        img[svd + 156..svd + 156 + jroot.len()].copy_from_slice(&jroot);
+        let name: Vec<u8> = "LINK"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes())
+            .collect();
+        let mut at = 23 * S;
+        for r in [
+            jroot.clone(),
+            record(&[1], 23, S as u32, true, &[]),
+            record(&name, 19, 5, false, &[]),
+        ] {
+            img[at..at + r.len()].copy_from_slice(&r);
+            at += r.len();
+        }
+
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        assert!(root.find_node("LINK").is_none());
+        let link = root.find_node("link").unwrap();
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+        assert_eq!(link.metadata.link_target.as_deref(), Some("/usr/lib"));
+        assert!(root.find_node("deep/x.txt").is_some());
+
+        let borrowed = parse_iso9660_image(&img).unwrap();
+        assert_eq!(
+            borrowed.find_node("tty").unwrap().metadata.device,
+            Some((4, 1))
+        );
+
+        let mut fs = IsoFs::open(Cursor::new(img)).unwrap();
+        let link = fs.lookup("link").unwrap().unwrap();
+        let link = fs.stat(&link).unwrap();
+        assert_eq!(link.metadata.kind, EntryKind::Symlink);
+    }
+
+    #[test]
+    fn rock_ridge_relocated_directory_is_listed_in_place() {
+        let img = make_rock_ridge_iso();
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        let deep = root.find_node("deep").unwrap();
+        assert!(deep.is_directory);
+        assert!(root.find_node("deep/x.txt").is_some());
+        assert!(root.find_node("rr_moved").unwrap().children.is_empty());
+
+        let mut fs = IsoFs::open(Cursor::new(img)).unwrap();
+        let x = fs.lookup("deep/x.txt").unwrap().unwrap();
+        assert!(!x.is_directory);
+        let moved = fs.lookup("rr_moved").unwrap().unwrap();
+        assert!(fs.read_dir(&moved).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rock_ridge_long_form_times() {
+        let t = parse_long_date(b"2024030512000050\x04").unwrap();
+        assert_eq!(
+            t.secs,
+            Timestamp::from_civil(2024, 3, 5, 11, 0, 0).unwrap().secs
+        );
+        assert_eq!(t.nanos, 500_000_000);
+        assert!(parse_long_date(b"0000000000000000\0").is_none());
+    }
+
+    /// [`make_iso_primary_only`] plus `BIG.DAT`, a multi-extent file of
+    /// three records (sectors 20, 22 and 21), `HUGE.DAT`, two records
+    /// totalling over 4 GiB, and `INTER.DAT`, interleaved one sector on,
+    /// one sector off from sector 23.
+    fn make_multi_extent_iso() -> Vec<u8> {
+        let mut img = make_iso_primary_only();
+        img.resize(S * 28, 0);
+        let mut at = 18 * S + 112;
+        let mut push =
+            |img: &mut Vec<u8>, name: &[u8], extent: u32, size: u32, flags: u8, unit: u8| {
+                let r = at;
+                img[r] = (33 + name.len() + (name.len() + 1) % 2) as u8;
+                img[r + 2..r + 6].copy_from_slice(&extent.to_le_bytes());
+                img[r + 10..r + 14].copy_from_slice(&size.to_le_bytes());
+                img[r + 25] = flags;
+                img[r + 26] = unit;
+                img[r + 27] = unit;
+                img[r + 32] = name.len() as u8;
+                img[r + 33..r + 33 + name.len()].copy_from_slice(name);
+                at += img[r] as usize;
+            };
+        push(&mut img, b"BIG.DAT;1", 20, S as u32, 0x80, 0);
+        push(&mut img, b"BIG.DAT;1", 22, S as u32, 0x80, 0);
+        push(&mut img, b"BIG.DAT;1", 21, 100, 0, 0);
+        push(&mut img, b"HUGE.DAT;1", 0, 0xFFFF_F800, 0x80, 0);
+        push(&mut img, b"HUGE.DAT;1", 0, 0xFFFF_F800, 0, 0);
+        push(&mut img, b"INTER.DAT;1", 23, 5000, 0, 1);
+        for sector in 20..28 {
+            img[sector * S..(sector + 1) * S].fill(sector as u8);
+        }
+        img
+    }
+
+    #[test]
+    fn multi_extent_records_are_one_file() {
+        let img = make_multi_extent_iso();
+        let root = parse_iso9660(&mut Cursor::new(img.clone())).unwrap();
+        let names: Vec<_> = root.children.iter().map(|c| c.name.as_ref()).collect();
+        assert_eq!(names, ["HELLO.TXT", "BIG.DAT", "HUGE.DAT", "INTER.DAT"]);
+
+        let big = root.find_node("BIG.DAT").unwrap();
+        assert_eq!(big.size, 2 * S as u64 + 100);
+        assert_eq!(
+            big.extents,
+            [
+                Extent::data(20 * S as u64, S as u64),
+                Extent::data(22 * S as u64, S as u64),
+                Extent::data(21 * S as u64, 100),
+            ]
+        );
+        let mut out = Vec::new();
+        crate::cat_node(&mut Cursor::new(img.clone()), big, &mut out).unwrap();
+        assert_eq!(out[..S], [20; S]);
+        assert_eq!(out[S..2 * S], [22; S]);
+        assert_eq!(out[2 * S..], [21; 100]);
+
+        let huge = root.find_node("HUGE.DAT").unwrap();
+        assert_eq!(huge.size, 2 * 0xFFFF_F800);
+
+        let mut fs = IsoFs::open(Cursor::new(img)).unwrap();
+        let entry = fs.lookup("BIG.DAT").unwrap().unwrap();
+        assert_eq!(fs.stat(&entry).unwrap().extents, big.extents);
+        assert_eq!(fs.read_dir(&fs.root()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn interleaved_file_skips_the_gaps() {
+        let img = make_multi_extent_iso();
+        let root = parse_iso9660_image(&img).unwrap();
+        let inter = root.find_node("INTER.DAT").unwrap();
+        assert_eq!(inter.size, 5000);
+        let mut out = Vec::new();
+        crate::cat_node(&mut Cursor::new(&img), inter, &mut out).unwrap();
+        assert_eq!(out.len(), 5000);
+        assert!(out[..S].iter().all(|&b| b == 23));
+        assert!(out[S..2 * S].iter().all(|&b| b == 25));
+        assert!(out[2 * S..].iter().all(|&b| b == 27));
+    }
+
+    #[test]
+    fn volume_info_reads_the_primary_descriptor() {
+        let mut img = make_iso_primary_only();
+        let pvd = 16 * S;
+        img[pvd + 8..pvd + 72].fill(b' ');
+        img[pvd + 190..pvd + 702].fill(b' ');
+        img[pvd + 8..pvd + 13].copy_from_slice(b"LINUX");
+        img[pvd + 40..pvd + 48].copy_from_slice(b"ARCHIVE1");
+        img[pvd + 80..pvd + 84].copy_from_slice(&20u32.to_le_bytes());
+        img[pvd + 318..pvd + 325].copy_from_slice(b"ACME CO");
+        img[pvd + 574..pvd + 587].copy_from_slice(b"MKISOFS 2.01A");
+        // Created 2024-06-15 12:30:45 at UTC+2; the others unset.
+        img[pvd + 813..pvd + 829].copy_from_slice(b"2024061512304500");
+        img[pvd + 829] = 8;
+        img[pvd + 847..pvd + 863].fill(b'0');
+
+        let volume = describe_volume(&mut Borrowed(&img), &mut ()).unwrap();
+        assert_eq!(volume.system_id, "LINUX");
+        assert_eq!(volume.volume_id, "ARCHIVE1");
+        assert_eq!(volume.publisher_id, "ACME CO");
+        assert_eq!(volume.preparer_id, "");
+        assert_eq!(volume.application_id, "MKISOFS 2.01A");
+        assert_eq!(volume.volume_space_size, 20);
+        let created = Timestamp::from_civil(2024, 6, 15, 12, 30, 45).unwrap();
+        assert_eq!(volume.created, Some(created.with_utc_offset(120)));
+        assert_eq!(volume.modified, None);
+        assert_eq!(volume.expires, None);
+        assert_eq!(volume.joliet_level, None);
+        assert!(!volume.rock_ridge);
+        assert!(!volume.iso9660_1999);
+
+        let volume = describe_volume(&mut Borrowed(&make_rock_ridge_iso()), &mut ()).unwrap();
+        assert!(volume.rock_ridge);
+    }
+
+    #[test]
+    fn volume_info_prefers_joliet_identifiers() {
+        let mut img = make_iso_joliet();
+        let pvd = 16 * S;
+        img[pvd + 40..pvd + 72].fill(b' ');
+        img[pvd + 40..pvd + 45].copy_from_slice(b"PHOTO");
+        img[pvd + 318..pvd + 321].copy_from_slice(b"ME ");
+        let svd = 17 * S;
+        img[svd + 88..svd + 91].copy_from_slice(b"%/E");
+        let label: Vec<u8> = "Fotos 2024 ✓"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes())
+            .chain([0; 8])
+            .collect();
+        img[svd + 40..svd + 40 + label.len()].copy_from_slice(&label);
+        // An Enhanced Volume Descriptor in place of the terminator.
+        img[18 * S] = 2;
+        img[18 * S + 6] = 2;
+
+        let volume = describe_volume(&mut Borrowed(&img), &mut ()).unwrap();
+        assert_eq!(volume.volume_id, "Fotos 2024 ✓");
+        // Left blank in the Joliet descriptor.
+        assert_eq!(volume.publisher_id, "ME");
+        assert_eq!(volume.joliet_level, Some(3));
+        assert!(volume.iso9660_1999);
+    }
 }
@@ -7,9 +7,10 @@
 //!
 //! - Parses ISO 9660 (with Joliet and Rock Ridge extensions) and UDF
 //!   disc images into a [`TreeNode`] hierarchy rooted at `"/"`.
-//! - Lets you stream a single file's bytes ([`cat_node`]) or extract a
-//!   file or subtree to disk ([`extract_node`]) without loading the
-//!   whole image into memory.
+//! - Lets you stream a single file's bytes ([`cat_node`]) or a byte range
+//!   of it ([`read_node_range`]), open it as a seekable reader
+//!   ([`open_node`]) or extract a file or subtree to disk
+//!   ([`extract_node`]) without loading the whole image into memory.
 //! - Never writes to the input image: read-only by design.
 //!
 //! Detection is automatic — call [`detect_and_parse_filesystem`] and the
@@ -24,6 +25,14 @@
 //! adversarial ISOs whose directory entries (e.g. Rock Ridge `NM`
 //! records) attempt to write outside the destination via `..` traversal.
 //!
+//! Every parse runs under the caps in a [`ParseOptions`] — entries,
+//! directory depth, buffer sizes, bytes read — and reports a directory
+//! that contains itself as [`Error::Corrupt`], so an untrusted image
+//! cannot exhaust memory or loop forever (see [`limits`]). For damaged
+//! media, [`detect_and_parse_filesystem_lenient`] skips what it cannot
+//! read and returns the rest with a list of [`Warning`]s (see
+//! [`recover`]).
+//!
 //! [`cat_node`] returns `Ok(())` when the downstream writer closes
 //! its pipe (`BrokenPipe`), matching standard Unix pipeline behaviour
 //! (e.g. `cat_node(&node, &mut stdout())?` piped to `head`).
@@ -44,9 +53,22 @@
 //! # Ok::<(), isomage::Error>(())
 //! ```
 
+pub mod diff;
+pub mod eltorito;
+pub mod error;
+pub mod extract;
+pub mod filesystem;
+pub mod index;
 pub mod iso9660;
+pub mod limits;
+pub mod manifest;
+pub mod observe;
+pub mod probe;
+pub mod recover;
 pub mod tree;
 pub mod udf;
+pub mod volume;
+pub mod walk;
 
 // v3.0 infrastructure. The `image_io` module is always compiled
 // (the `RandomAccess` trait is free of unsafe and free of deps);
@@ -64,26 +86,29 @@ pub mod simd;
 // without conditional imports.
 pub mod formats;
 
-pub use tree::TreeNode;
+pub use diff::{diff_images, diff_trees, DiffOptions};
+pub use error::{Error, Result};
+pub use extract::{
+    extract_node_observed, extract_node_parallel, extract_node_parallel_observed,
+    extract_node_with, ExtractOptions, ExtractSummary, OnExisting,
+};
+pub use filesystem::{DirEntry, Filesystem, NodeReader};
+pub use index::PathIndex;
+pub use limits::{Limit, ParseOptions};
+pub use observe::{Observer, StderrObserver};
+pub use probe::{open_any, Confidence, Detection, Format};
+pub use recover::{Parsed, Warning};
+pub use tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
+pub use volume::VolumeInfo;
+pub use walk::{Filter, Walk, WalkEntry};
 
 // `File` is no longer named by the public API as of v3.0 — the
 // reader entry points are generic over `R: Read + Seek`. `File`
 // remains the canonical example in doc-tests (which `use` it
 // themselves) and is used as `std::fs::File::create` in the
-// extraction code path below.
-use std::fs::create_dir_all;
+// `extract` module.
+use std::borrow::Cow;
 use std::io::{self, Read, Seek, SeekFrom, Write};
-use std::path::{Path, PathBuf};
-
-/// The error type returned by every fallible public function in this crate.
-///
-/// Boxed so the library doesn't pin callers to a specific error enum, and
-/// `Send + Sync + 'static` so it composes cleanly with thread spawning,
-/// `anyhow::Error`, and async runtimes.
-pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
-
-/// The result type returned by every fallible public function in this crate.
-pub type Result<T> = std::result::Result<T, Error>;
 
 /// Parse the filesystem contained in `file`, returning the root node of
 /// the directory tree.
@@ -91,6 +116,9 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Tries ISO 9660 first (including Joliet and Rock Ridge extensions),
 /// then UDF (including metadata partitions and multi-extent files).
 /// Returns an error describing both parsers' failures if neither matches.
+/// It is [`Error::NotRecognized`] unless one of them recognized its format
+/// and then failed, in which case that failure's variant is kept — a
+/// damaged ISO reports [`Error::Corrupt`].
 ///
 /// `filename` is used only in the error message — it is not opened.
 ///
@@ -108,8 +136,63 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub fn detect_and_parse_filesystem<R: Read + Seek>(
     file: &mut R,
     filename: &str,
-) -> Result<TreeNode> {
-    detect_and_parse_filesystem_verbose(file, filename, false)
+) -> Result<TreeNode<'static>> {
+    detect(
+        file,
+        filename,
+        &mut (),
+        false,
+        &ParseOptions::default(),
+        None,
+    )
+}
+
+/// Like [`detect_and_parse_filesystem`], under the caps in `options`.
+/// The ISO 9660 and UDF attempts each get their own budget; see
+/// [`limits`].
+///
+/// # Example
+///
+/// ```no_run
+/// use isomage::{detect_and_parse_filesystem_with, ParseOptions};
+///
+/// let mut file = std::fs::File::open("upload.iso")?;
+/// let options = ParseOptions::new().max_entries(50_000);
+/// let root = detect_and_parse_filesystem_with(&mut file, "upload.iso", &options)?;
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn detect_and_parse_filesystem_with<R: Read + Seek>(
+    file: &mut R,
+    filename: &str,
+    options: &ParseOptions,
+) -> Result<TreeNode<'static>> {
+    detect(file, filename, &mut (), false, options, None)
+}
+
+/// Like [`detect_and_parse_filesystem_with`], as a lenient parse:
+/// damaged directory records and File Entries are skipped and reported
+/// in [`Parsed::warnings`] instead of failing the parse. See [`recover`].
+///
+/// # Example
+///
+/// ```no_run
+/// use isomage::{detect_and_parse_filesystem_lenient, ParseOptions};
+///
+/// let mut file = std::fs::File::open("scratched.iso")?;
+/// let parsed = detect_and_parse_filesystem_lenient(&mut file, "scratched.iso", &ParseOptions::default())?;
+/// for warning in &parsed.warnings {
+///     eprintln!("{warning}");
+/// }
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn detect_and_parse_filesystem_lenient<R: Read + Seek>(
+    file: &mut R,
+    filename: &str,
+    options: &ParseOptions,
+) -> Result<Parsed<'static>> {
+    let mut warnings = Vec::new();
+    let root = detect(file, filename, &mut (), false, options, Some(&mut warnings))?;
+    Ok(Parsed { root, warnings })
 }
 
 /// Like [`detect_and_parse_filesystem`], but prints spec-section-tagged
@@ -124,21 +207,69 @@ pub fn detect_and_parse_filesystem_verbose<R: Read + Seek>(
     file: &mut R,
     filename: &str,
     verbose: bool,
-) -> Result<TreeNode> {
-    let mut errors = Vec::new();
-
+) -> Result<TreeNode<'static>> {
+    let options = ParseOptions::default();
     if verbose {
+        detect(file, filename, &mut StderrObserver, true, &options, None)
+    } else {
+        detect(file, filename, &mut (), false, &options, None)
+    }
+}
+
+/// Like [`detect_and_parse_filesystem`], but sends the image size, the
+/// signature bytes at the key sectors and each parser's diagnostics to
+/// `observer` as [`observe::Event::Diagnostic`]s.
+pub fn detect_and_parse_filesystem_observed<R: Read + Seek>(
+    file: &mut R,
+    filename: &str,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'static>> {
+    detect(
+        file,
+        filename,
+        observer,
+        true,
+        &ParseOptions::default(),
+        None,
+    )
+}
+
+/// `scan` adds the size and key-sector dump, which cost extra reads and
+/// are only worth doing for an observer that wants them. With
+/// `warnings`, each attempt is lenient and the successful one's
+/// warnings are stored there.
+fn detect<R: Read + Seek>(
+    file: &mut R,
+    filename: &str,
+    observer: &mut dyn Observer,
+    scan: bool,
+    options: &ParseOptions,
+    mut warnings: Option<&mut Vec<Warning>>,
+) -> Result<TreeNode<'static>> {
+    let mut failures = Failures::default();
+
+    if scan {
         // Show file size
         let file_size = file.seek(SeekFrom::End(0))?;
         file.seek(SeekFrom::Start(0))?;
-        eprintln!(
-            "File size: {} bytes ({:.2} GB)",
-            file_size,
-            file_size as f64 / (1024.0 * 1024.0 * 1024.0)
+        observe::info(
+            observer,
+            None,
+            "",
+            format_args!(
+                "File size: {} bytes ({:.2} GB)",
+                file_size,
+                file_size as f64 / (1024.0 * 1024.0 * 1024.0)
+            ),
         );
 
         // Show what's at key sectors
-        eprintln!("Scanning key sectors for filesystem signatures...");
+        observe::info(
+            observer,
+            None,
+            "",
+            format_args!("Scanning key sectors for filesystem signatures..."),
+        );
         for (sector, desc) in [
             (16, "ISO 9660 PVD / UDF VRS"),
             (17, "UDF VRS"),
@@ -159,54 +290,213 @@ pub fn detect_and_parse_filesystem_verbose<R: Read + Seek>(
                         }
                     })
                     .collect();
-                eprintln!("  Sector {:>3} ({}): {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x}  |{}|",
-                    sector, desc, buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], buf[6], buf[7], &printable[..8]);
+                observe::info(
+                    observer,
+                    None,
+                    "",
+                    format_args!(
+                        "Sector {:>3} ({}): {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x}  |{}|",
+                        sector, desc, buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], buf[6], buf[7], &printable[..8]
+                    ),
+                );
             }
         }
         file.seek(SeekFrom::Start(0))?;
     }
 
-    if verbose {
-        eprintln!("\nAttempting ISO 9660 parsing...");
-    }
-    match iso9660::parse_iso9660_verbose(file, verbose) {
+    observe::info(
+        observer,
+        None,
+        "",
+        format_args!("Attempting ISO 9660 parsing..."),
+    );
+    let iso = limits::metered(Format::Iso9660, Some(options), &mut *file, |file| {
+        recover::collect(warnings.as_deref_mut(), || {
+            iso9660::parse_iso9660_observed(file, observer)
+        })
+    });
+    match iso {
         Ok(root) => return Ok(root),
-        Err(e) => {
-            if verbose {
-                eprintln!("  ISO 9660 parsing failed: {}", e);
-            }
-            errors.push(format!("ISO 9660: {}", e));
-        }
+        Err(e) => failures.push(Format::Iso9660, e, observer),
     }
 
     // Seek back to start before trying next parser
     file.seek(SeekFrom::Start(0))?;
-    if verbose {
-        eprintln!("\nAttempting UDF parsing...");
+    observe::info(
+        observer,
+        None,
+        "",
+        format_args!("Attempting UDF parsing..."),
+    );
+    let udf = limits::metered(Format::Udf, Some(options), &mut *file, |file| {
+        recover::collect(warnings, || udf::parse_udf_observed(file, observer))
+    });
+    match udf {
+        Ok(root) => return Ok(root),
+        Err(e) => failures.push(Format::Udf, e, observer),
     }
-    match udf::parse_udf_verbose(file, verbose) {
+
+    Err(failures.into_error(filename))
+}
+
+/// Like [`detect_and_parse_filesystem`], but for an image that is already
+/// in memory or mapped: a `Vec<u8>`, a byte slice, or an
+/// `image_io::MmapImage` (with `--features mmap`).
+///
+/// The tree borrows from `image`. Names that need no decoding (plain
+/// ISO 9660, single-entry Rock Ridge, and UTF-8-valid 8-bit UDF names)
+/// point into the image rather than being copied; Joliet and 16-bit UDF
+/// names are owned. Use [`TreeNode::into_owned`] to keep the tree after
+/// the image is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use isomage::detect_and_parse_image;
+///
+/// let bytes = std::fs::read("disc.iso")?;
+/// let root = detect_and_parse_image(&bytes, "disc.iso")?;
+/// assert_eq!(root.name, "/");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn detect_and_parse_image<'a, R: image_io::RandomAccess + ?Sized>(
+    image: &'a R,
+    filename: &str,
+) -> Result<TreeNode<'a>> {
+    detect_and_parse_image_with(image, filename, &ParseOptions::default())
+}
+
+/// Like [`detect_and_parse_image`], under the caps in `options`. The
+/// ISO 9660 and UDF attempts each get their own budget; see [`limits`].
+///
+/// # Example
+///
+/// ```no_run
+/// use isomage::{detect_and_parse_image_with, ParseOptions};
+///
+/// let bytes = std::fs::read("upload.iso")?;
+/// let options = ParseOptions::new().max_entries(50_000);
+/// let root = detect_and_parse_image_with(&bytes, "upload.iso", &options)?;
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn detect_and_parse_image_with<'a, R: image_io::RandomAccess + ?Sized>(
+    image: &'a R,
+    filename: &str,
+    options: &ParseOptions,
+) -> Result<TreeNode<'a>> {
+    detect_image(image, filename, &mut (), options, None)
+}
+
+/// Like [`detect_and_parse_image_with`], as a lenient parse: damaged
+/// directory records and File Entries are skipped and reported in
+/// [`Parsed::warnings`] instead of failing the parse. See [`recover`].
+pub fn detect_and_parse_image_lenient<'a, R: image_io::RandomAccess + ?Sized>(
+    image: &'a R,
+    filename: &str,
+    options: &ParseOptions,
+) -> Result<Parsed<'a>> {
+    let mut warnings = Vec::new();
+    let root = detect_image(image, filename, &mut (), options, Some(&mut warnings))?;
+    Ok(Parsed { root, warnings })
+}
+
+/// Like [`detect_and_parse_image`], but sends each parser's diagnostics
+/// to `observer`.
+pub fn detect_and_parse_image_observed<'a, R: image_io::RandomAccess + ?Sized>(
+    image: &'a R,
+    filename: &str,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'a>> {
+    detect_image(image, filename, observer, &ParseOptions::default(), None)
+}
+
+/// [`detect`] for an image in memory.
+fn detect_image<'a, R: image_io::RandomAccess + ?Sized>(
+    image: &'a R,
+    filename: &str,
+    observer: &mut dyn Observer,
+    options: &ParseOptions,
+    mut warnings: Option<&mut Vec<Warning>>,
+) -> Result<TreeNode<'a>> {
+    let mut failures = Failures::default();
+    observe::info(
+        observer,
+        None,
+        "",
+        format_args!("Attempting ISO 9660 parsing..."),
+    );
+    let iso = limits::scope(Format::Iso9660, Some(options), || {
+        recover::collect(warnings.as_deref_mut(), || {
+            iso9660::parse_iso9660_image_observed(image, observer)
+        })
+    });
+    match iso {
         Ok(root) => return Ok(root),
-        Err(e) => {
-            if verbose {
-                eprintln!("  UDF parsing failed: {}", e);
-            }
-            errors.push(format!("UDF: {}", e));
-        }
+        Err(e) => failures.push(Format::Iso9660, e, observer),
+    }
+    observe::info(
+        observer,
+        None,
+        "",
+        format_args!("Attempting UDF parsing..."),
+    );
+    let udf = limits::scope(Format::Udf, Some(options), || {
+        recover::collect(warnings, || udf::parse_udf_image_observed(image, observer))
+    });
+    match udf {
+        Ok(root) => return Ok(root),
+        Err(e) => failures.push(Format::Udf, e, observer),
     }
+    Err(failures.into_error(filename))
+}
+
+/// The parsers that turned an image down during detection.
+#[derive(Default)]
+struct Failures {
+    errors: Vec<String>,
+    /// The first failure from a parser that did recognize its format.
+    cause: Option<Error>,
+}
 
-    let mut msg = format!("Unable to detect supported filesystem in {}", filename);
-    if !errors.is_empty() {
-        msg.push_str("\nDetails:\n  - ");
-        msg.push_str(&errors.join("\n  - "));
+impl Failures {
+    fn push(&mut self, format: Format, e: Error, observer: &mut dyn Observer) {
+        let name = match format {
+            Format::Iso9660 => "ISO 9660",
+            _ => "UDF",
+        };
+        observe::info(
+            observer,
+            format,
+            "",
+            format_args!("{} parsing failed: {}", name, e),
+        );
+        self.errors.push(format!("{}: {}", name, e));
+        if self.cause.is_none() && !matches!(e, Error::NotRecognized { .. }) {
+            self.cause = Some(e);
+        }
     }
 
-    Err(msg.into())
+    fn into_error(self, filename: &str) -> Error {
+        let mut msg = format!("Unable to detect supported filesystem in {}", filename);
+        if !self.errors.is_empty() {
+            msg.push_str("\nDetails:\n  - ");
+            msg.push_str(&self.errors.join("\n  - "));
+        }
+
+        let cause = self.cause.unwrap_or(Error::NotRecognized {
+            format: None,
+            message: String::new(),
+        });
+        cause.with_message(msg)
+    }
 }
 
 /// Stream a file from the ISO to `writer` in fixed-size chunks.
 ///
 /// `node` must reference a file (not a directory) and must carry the
-/// `file_location` / `file_length` pair populated by the parsers.
+/// `file_location` / `file_length` pair or the `extents` list populated
+/// by the parsers. Multi-extent files are stitched together in order;
+/// sparse holes are written as zeros.
 ///
 /// **Broken pipe handling.** If `writer` returns `ErrorKind::BrokenPipe`
 /// (e.g. a downstream `head` closed the pipe early), this function
@@ -229,48 +519,153 @@ pub fn detect_and_parse_filesystem_verbose<R: Read + Seek>(
 ///
 /// let mut out = Vec::new();
 /// cat_node(&mut file, node, &mut out)?;
-/// # Ok::<(), isomage::Error>(())
+/// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub fn cat_node<R: Read + Seek, W: Write>(
     file: &mut R,
     node: &TreeNode,
     writer: &mut W,
 ) -> Result<()> {
-    if node.is_directory {
-        return Err(format!("'{}' is a directory, not a file", node.name).into());
-    }
-    let (location, length) = match (node.file_location, node.file_length) {
-        (Some(l), Some(n)) => (l, n),
-        _ => return Err("File location information not available".into()),
-    };
+    let (extents, length) = file_data(node)?;
+
+    copy_extents(file, &extents, length, |buf| match writer.write_all(buf) {
+        Ok(()) => Ok(true),
+        // Downstream closed the pipe; that's normal for `| head`, not an error.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(false),
+        Err(e) => Err(e.into()),
+    })
+}
 
-    file.seek(SeekFrom::Start(location))?;
-    let mut remaining: u64 = length;
-    let buf_cap = remaining.min(EXTRACT_CHUNK_SIZE as u64) as usize;
-    let mut buffer = vec![0u8; buf_cap];
+/// Write bytes `offset .. offset + len` of a file to `writer`, and
+/// return how many were written.
+///
+/// The range is clamped to the file, the way a read past end-of-file
+/// is: a range that starts at or after the end writes nothing, and one
+/// that runs past it stops there, so `len` may be `u64::MAX` for "to the
+/// end". Only the extents overlapping the range are read — one seek per
+/// extent — and holes in sparse files are written as zeros.
+///
+/// `node` must reference a file carrying a data location, as for
+/// [`cat_node`]. Unlike [`cat_node`], every write error is returned,
+/// `BrokenPipe` included: a server streaming a range needs to know the
+/// client went away.
+///
+/// # Example
+///
+/// Serving `Range: bytes=1048576-2097151` of a video on a Blu-ray image:
+///
+/// ```no_run
+/// use std::fs::File;
+/// use isomage::{detect_and_parse_filesystem, read_node_range};
+///
+/// let mut file = File::open("bluray.iso")?;
+/// let root = detect_and_parse_filesystem(&mut file, "bluray.iso")?;
+/// let node = root.find_node("BDMV/STREAM/00000.m2ts").ok_or("not in image")?;
+///
+/// let mut body = Vec::new();
+/// let sent = read_node_range(&mut file, node, 1 << 20, 1 << 20, &mut body)?;
+/// assert_eq!(sent, body.len() as u64);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn read_node_range<R: Read + Seek, W: Write>(
+    file: &mut R,
+    node: &TreeNode,
+    offset: u64,
+    len: u64,
+    writer: &mut W,
+) -> Result<u64> {
+    let (extents, length) = file_data(node)?;
+    let start = offset.min(length);
+    let count = len.min(length - start);
+    let extents = slice_extents(&extents, start, count);
+
+    copy_extents(file, &extents, count, |buf| {
+        writer.write_all(buf)?;
+        Ok(true)
+    })?;
+    Ok(count)
+}
 
-    while remaining > 0 {
-        let to_read = remaining.min(EXTRACT_CHUNK_SIZE as u64) as usize;
-        let buf = &mut buffer[..to_read];
-        file.read_exact(buf)?;
+/// A file node's data runs and length, or the error for a directory or
+/// a file the parser could not locate.
+fn file_data<'n>(node: &'n TreeNode) -> Result<(Cow<'n, [Extent]>, u64)> {
+    if node.is_directory {
+        return Err(Error::NotAFile {
+            name: node.name.to_string(),
+        });
+    }
+    let extents = node.data_extents().ok_or_else(|| Error::NoData {
+        name: node.name.to_string(),
+    })?;
+    Ok((extents, node.file_length.unwrap_or(node.size)))
+}
 
-        match writer.write_all(buf) {
-            Ok(()) => {}
-            // Downstream closed the pipe; that's normal for `| head`, not an error.
-            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
-            Err(e) => return Err(e.into()),
+/// The runs covering bytes `offset .. offset + len` of a file laid out
+/// as `extents`, trimmed at both ends.
+fn slice_extents(extents: &[Extent], offset: u64, len: u64) -> Vec<Extent> {
+    let end = offset + len;
+    let mut sliced = Vec::new();
+    let mut start = 0u64;
+    for extent in extents {
+        if start >= end {
+            break;
+        }
+        let extent_end = start.saturating_add(extent.length);
+        if extent_end > offset {
+            let from = start.max(offset);
+            let to = extent_end.min(end);
+            sliced.push(Extent {
+                location: extent.location.map(|l| l.saturating_add(from - start)),
+                length: to - from,
+            });
         }
-        remaining -= to_read as u64;
+        start = extent_end;
     }
-    Ok(())
+    sliced
+}
+
+/// Open a file for reading as a seekable stream over `file`.
+///
+/// Where [`cat_node`] pushes the whole file into a writer, the returned
+/// [`NodeReader`] is pulled from, so a file inside the image can be
+/// handed straight to another parser — a `squashfs.img` on a live ISO,
+/// a ZIP inside a TAR — without a temporary copy. Offsets are relative
+/// to the start of the file; multi-extent files read as one stream,
+/// holes read as zeros, and reads stop at the file's length, so bytes
+/// outside the file are never returned.
+///
+/// `node` must reference a file carrying a data location, as for
+/// [`cat_node`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::{Read, Seek, SeekFrom};
+/// use isomage::{detect_and_parse_filesystem, open_node};
+///
+/// let mut file = File::open("live.iso")?;
+/// let root = detect_and_parse_filesystem(&mut file, "live.iso")?;
+/// let node = root.find_node("casper/filesystem.squashfs").ok_or("not in ISO")?;
+///
+/// let mut inner = open_node(&mut file, node)?;
+/// inner.seek(SeekFrom::Start(0))?;
+/// let mut magic = [0u8; 4];
+/// inner.read_exact(&mut magic)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn open_node<'a, R: Read + Seek>(
+    file: &'a mut R,
+    node: &TreeNode,
+) -> Result<NodeReader<'a, R>> {
+    NodeReader::new(file, node)
 }
 
 /// Extract `node` (a file or a directory subtree) to `output_path` on disk.
 ///
-/// The output directory is created if it doesn't exist. For each file
-/// extracted, a `Extracted: <path>` line is printed to stderr; for each
-/// directory created, `Created directory: <path>`. Files larger than 100 MB
-/// also report percentage progress on stderr.
+/// The output directory is created if it doesn't exist. Existing files
+/// are replaced, and nothing is printed. For overwrite policies, metadata
+/// restore, symlinks, filters or a dry run, use [`extract_node_with`].
 ///
 /// # Safety against malicious ISOs
 ///
@@ -282,12 +677,10 @@ pub fn cat_node<R: Read + Seek, W: Write>(
 /// claim a name like `../../etc/passwd` will produce a clear error, not
 /// silently overwrite host files.
 ///
-/// **Symlink limitation.** This check is lexical-only and does not follow
-/// symlinks. If the output directory already contains a symlink pointing
-/// outside it (e.g. `out/link → /etc`), extracting a file named `link/passwd`
-/// into `out/` would follow the symlink. To avoid this, extract into a freshly
-/// created empty directory, or audit the destination for pre-existing symlinks
-/// before calling this function.
+/// Extraction never writes through a symlink. A directory entry whose
+/// output path is an existing symlink (e.g. `out/link → /etc`) is refused,
+/// and an existing file or symlink at a file's output path is removed
+/// before the new file is created, not opened.
 ///
 /// # Example
 ///
@@ -299,142 +692,79 @@ pub fn cat_node<R: Read + Seek, W: Write>(
 /// let root = detect_and_parse_filesystem(&mut file, "disc.iso")?;
 /// let subtree = root.find_node("docs").ok_or("not in ISO")?;
 /// extract_node(&mut file, subtree, "/tmp/disc-docs")?;
-/// # Ok::<(), isomage::Error>(())
+/// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub fn extract_node<R: Read + Seek>(
     file: &mut R,
     node: &TreeNode,
     output_path: &str,
 ) -> Result<()> {
-    create_dir_all(output_path)
-        .map_err(|e| format!("cannot create output directory '{}': {}", output_path, e))?;
-    let root = std::fs::canonicalize(output_path).map_err(|e| {
-        format!(
-            "cannot canonicalize output directory '{}': {}",
-            output_path, e
-        )
-    })?;
-
-    // The synthetic root node ("/") is the tree root from the parser. We
-    // don't want to create a literal "/" subdirectory in the destination —
-    // its children become the top level instead.
-    if node.is_directory && node.name == "/" {
-        for child in &node.children {
-            extract_into(file, child, &root, &root)?;
-        }
-        Ok(())
-    } else {
-        extract_into(file, node, &root, &root)
-    }
+    extract_node_with(file, node, output_path, &ExtractOptions::default()).map(|_| ())
 }
 
 const EXTRACT_CHUNK_SIZE: usize = 8 * 1024 * 1024; // 8 MB chunks
 
-/// Reject names that, if joined to a parent path, could escape it or
-/// produce ambiguous filesystem behaviour.
-///
-/// Conservative on purpose: rejects anything ISO/UDF parsers could
-/// possibly stamp into a `TreeNode.name` that the host filesystem would
-/// then interpret as something other than a single in-directory entry.
-fn validate_entry_name(name: &str) -> Result<()> {
-    if name.is_empty() || name == "." || name == ".." {
-        return Err(format!("refusing to extract entry with unsafe name {:?}", name).into());
-    }
-    if name.contains('/') || name.contains('\\') || name.contains('\0') {
-        return Err(format!(
-            "refusing to extract entry whose name contains a path separator or NUL byte: {:?}",
-            name
-        )
-        .into());
-    }
-    Ok(())
-}
-
-/// Compute the on-disk target for `name` inside `here`, verifying the
-/// result stays under `root`. Defense in depth: even with
-/// `validate_entry_name` already called, we re-check `starts_with(root)`
-/// in case some future caller bypasses validation.
-fn safe_join(root: &Path, here: &Path, name: &str) -> Result<PathBuf> {
-    validate_entry_name(name)?;
-    let target = here.join(name);
-    if !target.starts_with(root) {
-        return Err(format!(
-            "path escape: entry '{}' would write outside output directory {}",
-            name,
-            root.display()
-        )
-        .into());
-    }
-    Ok(target)
-}
-
-fn extract_into<R: Read + Seek>(
+/// Feed the first `length` bytes of a file laid out as `extents` to
+/// `sink`, at most [`EXTRACT_CHUNK_SIZE`] bytes at a time. Holes are
+/// delivered as zeros. `sink` returns `Ok(false)` to stop early.
+pub(crate) fn copy_extents<R: Read + Seek + ?Sized>(
     file: &mut R,
-    node: &TreeNode,
-    root: &Path,
-    here: &Path,
+    extents: &[Extent],
+    length: u64,
+    mut sink: impl FnMut(&[u8]) -> Result<bool>,
 ) -> Result<()> {
-    let target = safe_join(root, here, &node.name)?;
-
-    if node.is_directory {
-        create_dir_all(&target)?;
-        eprintln!("Created directory: {}", target.display());
-        for child in &node.children {
-            extract_into(file, child, root, &target)?;
-        }
-    } else {
-        extract_file_at(file, node, &target)?;
-    }
-    Ok(())
-}
-
-fn extract_file_at<R: Read + Seek>(file: &mut R, node: &TreeNode, target: &Path) -> Result<()> {
-    let (location, length) = match (node.file_location, node.file_length) {
-        (Some(l), Some(n)) => (l, n),
-        _ => return Err("File location information not available for extraction".into()),
-    };
-
-    file.seek(SeekFrom::Start(location))?;
-
-    if let Some(parent) = target.parent() {
-        create_dir_all(parent)?;
-    }
-
-    let mut output_file = std::fs::File::create(target)
-        .map_err(|e| format!("cannot create '{}': {}", target.display(), e))?;
-
-    let mut remaining: u64 = length;
+    let mut remaining = length;
     let buf_cap = remaining.min(EXTRACT_CHUNK_SIZE as u64) as usize;
     let mut buffer = vec![0u8; buf_cap];
 
-    while remaining > 0 {
-        let to_read = remaining.min(EXTRACT_CHUNK_SIZE as u64) as usize;
-        let buf = &mut buffer[..to_read];
-        file.read_exact(buf)?;
-        output_file.write_all(buf)?;
-        remaining -= to_read as u64;
-
-        // Print progress for large files (> 100 MB)
-        if length > 100 * 1024 * 1024 {
-            let done = length - remaining;
-            eprint!(
-                "\r  Extracting {}: {:.1}%",
-                node.name,
-                done as f64 / length as f64 * 100.0
-            );
+    for extent in extents {
+        if remaining == 0 {
+            break;
+        }
+        let mut run = extent.length.min(remaining);
+        if let Some(location) = extent.location {
+            file.seek(SeekFrom::Start(location))?;
+        }
+        while run > 0 {
+            let to_read = run.min(EXTRACT_CHUNK_SIZE as u64) as usize;
+            let buf = &mut buffer[..to_read];
+            if extent.is_hole() {
+                buf.fill(0);
+            } else {
+                file.read_exact(buf)?;
+            }
+            if !sink(buf)? {
+                return Ok(());
+            }
+            run -= to_read as u64;
+            remaining -= to_read as u64;
         }
-    }
-    if length > 100 * 1024 * 1024 {
-        eprintln!();
     }
 
-    eprintln!("Extracted: {}", target.display());
+    if remaining > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "extent list covers only {} of {} bytes",
+                length - remaining,
+                length
+            ),
+        )
+        .into());
+    }
     Ok(())
 }
 
+/// Prefix an I/O error's message with what was being attempted, keeping
+/// its kind.
+pub(crate) fn io_context(e: io::Error, what: String) -> Error {
+    io::Error::new(e.kind(), format!("{}: {}", what, e)).into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extract::{safe_join, validate_entry_name};
     use std::fs::File;
     use std::path::Path;
 
@@ -454,14 +784,14 @@ mod tests {
         Some(File::open(&path).unwrap_or_else(|_| panic!("Failed to open test file: {}", path)))
     }
 
-    fn parse_linux_iso() -> Option<(File, TreeNode)> {
+    fn parse_linux_iso() -> Option<(File, TreeNode<'static>)> {
         let mut file = require_test_file("test_linux.iso")?;
         let root = detect_and_parse_filesystem(&mut file, "test_linux.iso")
             .expect("Failed to parse test_linux.iso");
         Some((file, root))
     }
 
-    fn parse_macos_iso() -> Option<(File, TreeNode)> {
+    fn parse_macos_iso() -> Option<(File, TreeNode<'static>)> {
         let mut file = require_test_file("test_macos.iso")?;
         let root = detect_and_parse_filesystem(&mut file, "test_macos.iso")
             .expect("Failed to parse test_macos.iso");
@@ -487,6 +817,185 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_image_parse_matches_stream_parse() {
+        for test_file in &["test_linux.iso", "test_macos.iso"] {
+            if let Some(mut file) = require_test_file(test_file) {
+                let bytes = std::fs::read(test_file_path(test_file)).unwrap();
+                let borrowed = detect_and_parse_image(&bytes, test_file).unwrap();
+                let streamed = detect_and_parse_filesystem(&mut file, test_file).unwrap();
+                assert_eq!(format!("{:?}", borrowed), format!("{:?}", streamed));
+                let owned: TreeNode<'static> = borrowed.into_owned();
+                assert_eq!(owned.children.len(), streamed.children.len());
+            }
+        }
+        let err = detect_and_parse_image(&[0u8; 4096][..], "zeros.bin").unwrap_err();
+        assert!(matches!(err, Error::NotRecognized { .. }));
+    }
+
+    #[test]
+    fn test_lazy_iso_matches_eager_tree() {
+        if let Some((file, eager)) = parse_linux_iso() {
+            let mut fs = iso9660::IsoFs::open(file).unwrap();
+            let lazy = fs.to_tree().unwrap();
+            assert_eq!(
+                format!("{:?}", lazy.children),
+                format!("{:?}", eager.children)
+            );
+            assert_eq!(lazy.size, eager.size);
+        }
+    }
+
+    #[test]
+    fn test_open_node_matches_cat_node() {
+        if let Some((mut file, root)) = parse_linux_iso() {
+            let node = root.find_node("etc/hostname").expect("etc/hostname");
+            let mut expected = Vec::new();
+            cat_node(&mut file, node, &mut expected).unwrap();
+
+            let mut reader = open_node(&mut file, node).unwrap();
+            assert_eq!(reader.len(), expected.len() as u64);
+            let mut all = Vec::new();
+            reader.read_to_end(&mut all).unwrap();
+            assert_eq!(all, expected);
+
+            reader.seek(SeekFrom::Start(1)).unwrap();
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).unwrap();
+            assert_eq!(rest, expected[1..]);
+
+            let etc = root.find_node("etc").unwrap();
+            assert!(open_node(&mut file, etc).is_err());
+        }
+    }
+
+    #[test]
+    fn test_open_node_stays_inside_the_file() {
+        // Two runs with a hole between them; every byte outside is '#'.
+        let mut image = std::io::Cursor::new(b"##ab####cd##".to_vec());
+        let node = TreeNode::new_file_with_extents(
+            "f".to_string(),
+            5,
+            vec![Extent::data(2, 2), Extent::hole(1), Extent::data(8, 4)],
+        );
+        let mut reader = open_node(&mut image, &node).unwrap();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, b"ab\0cd");
+
+        reader.seek(SeekFrom::End(-3)).unwrap();
+        let mut buf = [0u8; 8];
+        let n = reader.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"\0");
+        reader.seek(SeekFrom::Start(100)).unwrap();
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_node_range_every_range() {
+        // Runs of 3, 2 (hole) and 4 bytes; every byte outside is '#'.
+        let mut image = std::io::Cursor::new(b"#abc####defg##".to_vec());
+        let node = TreeNode::new_file_with_extents(
+            "f".to_string(),
+            9,
+            vec![Extent::data(1, 3), Extent::hole(2), Extent::data(8, 4)],
+        );
+        let whole = b"abc\0\0defg";
+        for offset in 0..12u64 {
+            for len in 0..12u64 {
+                let mut out = Vec::new();
+                let n = read_node_range(&mut image, &node, offset, len, &mut out).unwrap();
+                let from = (offset as usize).min(9);
+                let to = (offset + len).min(9) as usize;
+                assert_eq!(out, whole[from..to.max(from)], "{offset}+{len}");
+                assert_eq!(n, out.len() as u64);
+            }
+        }
+        let mut out = Vec::new();
+        read_node_range(&mut image, &node, 4, u64::MAX, &mut out).unwrap();
+        assert_eq!(out, b"\0defg");
+    }
+
+    #[test]
+    fn test_read_node_range_errors() {
+        let mut image = std::io::Cursor::new(vec![0u8; 16]);
+        let mut out = Vec::new();
+        let dir = TreeNode::new_directory("d");
+        let err = read_node_range(&mut image, &dir, 0, 1, &mut out).unwrap_err();
+        assert!(matches!(err, Error::NotAFile { .. }));
+        let unplaced = TreeNode::new_file("f", 4);
+        let err = read_node_range(&mut image, &unplaced, 0, 1, &mut out).unwrap_err();
+        assert!(matches!(err, Error::NoData { .. }));
+
+        // The extent list stops short of the file's length.
+        let short = TreeNode::new_file_with_extents("f", 8, vec![Extent::data(0, 4)]);
+        assert!(read_node_range(&mut image, &short, 2, 4, &mut out).is_err());
+        assert_eq!(
+            read_node_range(&mut image, &short, 1, 2, &mut out).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_read_node_range_matches_cat_node() {
+        if let Some((mut file, root)) = parse_linux_iso() {
+            let node = root.find_node("etc/hostname").expect("etc/hostname");
+            let mut whole = Vec::new();
+            cat_node(&mut file, node, &mut whole).unwrap();
+            let mut part = Vec::new();
+            read_node_range(&mut file, node, 2, 3, &mut part).unwrap();
+            assert_eq!(part, whole[2..5]);
+        }
+    }
+
+    #[test]
+    fn test_diff_images() {
+        let (Some((mut linux, linux_root)), Some((mut macos, macos_root))) =
+            (parse_linux_iso(), parse_macos_iso())
+        else {
+            return;
+        };
+        let options = DiffOptions::new().content(true);
+        let mut again = File::open(test_file_path("test_linux.iso")).unwrap();
+        let same = diff_images(&mut linux, &linux_root, &mut again, &linux_root, &options).unwrap();
+        assert!(same.is_empty(), "{same:?}");
+
+        let differences =
+            diff_images(&mut linux, &linux_root, &mut macos, &macos_root, &options).unwrap();
+        assert!(!differences.is_empty());
+        assert!(differences.windows(2).all(|w| w[0].path < w[1].path));
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        for (_, root) in parse_linux_iso().into_iter().chain(parse_macos_iso()) {
+            let mut json = Vec::new();
+            manifest::write_json_lines(&root, &mut json).unwrap();
+            let back = manifest::read_manifest(&json[..]).unwrap();
+            let mut again = Vec::new();
+            manifest::write_json_lines(&back, &mut again).unwrap();
+            assert_eq!(
+                String::from_utf8(again).unwrap(),
+                String::from_utf8(json).unwrap()
+            );
+
+            let mut mtree = Vec::new();
+            manifest::write_mtree(&root, &mut mtree).unwrap();
+            let back = manifest::read_manifest(&mtree[..]).unwrap();
+            assert_eq!(back.walk().count(), root.walk().count());
+            for entry in root.walk() {
+                let node = back.find_node(&entry.path).expect(&entry.path);
+                assert_eq!(node.size, entry.node.size, "{}", entry.path);
+                assert_eq!(
+                    node.data_extents(),
+                    entry.node.data_extents(),
+                    "{}",
+                    entry.path
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_filesystem_detection() {
         for test_file in &["test_linux.iso", "test_macos.iso"] {
@@ -516,7 +1025,9 @@ mod tests {
         let mut file = File::open(&garbage_path).unwrap();
         let result = detect_and_parse_filesystem(&mut file, "garbage.iso");
         assert!(result.is_err(), "Garbage data should fail to parse");
-        let err = result.unwrap_err().to_string();
+        let err = result.unwrap_err();
+        assert!(matches!(err, Error::NotRecognized { format: None, .. }));
+        let err = err.to_string();
         assert!(
             err.contains("Unable to detect"),
             "Error should mention detection failure, got: {}",
@@ -619,7 +1130,10 @@ mod tests {
     #[test]
     fn test_tree_structure_validation() {
         for (name, parser) in [
-            ("linux", parse_linux_iso as fn() -> Option<(File, TreeNode)>),
+            (
+                "linux",
+                parse_linux_iso as fn() -> Option<(File, TreeNode<'static>)>,
+            ),
             ("macos", parse_macos_iso),
         ] {
             if let Some((_file, root)) = parser() {
@@ -1180,6 +1694,53 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("Unable to detect"));
     }
 
+    #[test]
+    fn detect_reports_the_corrupt_format_not_the_unrecognized_ones() {
+        // UDF VRS markers but no AVDP: ISO 9660 does not recognise the
+        // image, UDF does and then finds it damaged.
+        let mut img = vec![0u8; 2048 * 270];
+        img[16 * 2048 + 1..16 * 2048 + 6].copy_from_slice(b"BEA01");
+        img[17 * 2048 + 1..17 * 2048 + 6].copy_from_slice(b"NSR03");
+        img[18 * 2048 + 1..18 * 2048 + 6].copy_from_slice(b"TEA01");
+        let mut c = std::io::Cursor::new(img);
+        let err = detect_and_parse_filesystem(&mut c, "broken.udf").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Corrupt {
+                format: Format::Udf,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("Unable to detect"));
+    }
+
+    #[test]
+    fn detect_and_parse_image_with_applies_options() {
+        let mut img = make_minimal_iso();
+        // A third root entry, "A;1", past the "." and ".." records.
+        let dir = 18 * 2048 + 68;
+        img[dir] = 36;
+        img[dir + 32] = 3;
+        img[dir + 33..dir + 36].copy_from_slice(b"A;1");
+
+        let root = detect_and_parse_image_with(&img, "a.iso", &ParseOptions::default()).unwrap();
+        assert_eq!(root.children.len(), 1);
+        let options = ParseOptions::new().max_entries(0);
+        let err = detect_and_parse_image_with(&img, "a.iso", &options).unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{err:?}");
+
+        // A subdirectory whose extent lies past the end of the image
+        // fails a strict parse and is left empty by a lenient one.
+        img[dir + 2..dir + 6].copy_from_slice(&500u32.to_le_bytes());
+        img[dir + 10..dir + 14].copy_from_slice(&2048u32.to_le_bytes());
+        img[dir + 25] = 0x02;
+        assert!(detect_and_parse_image(&img, "a.iso").is_err());
+        let parsed =
+            detect_and_parse_image_lenient(&img, "a.iso", &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.root.children.len(), 1);
+        assert!(!parsed.warnings.is_empty());
+    }
+
     #[test]
     fn detect_and_parse_verbose_true_garbage() {
         // Exercise verbose=true on garbage: hits all verbose eprintln branches
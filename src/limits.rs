@@ -0,0 +1,764 @@
+//! Resource limits for parsing untrusted images.
+//!
+//! A crafted image can claim a billion directory entries, a directory
+//! that contains itself, or a 4 GiB directory extent. [`ParseOptions`]
+//! caps what one parse may do, and every parser checks those caps as it
+//! reads: each entry it adds, each directory level it descends, each
+//! buffer it sizes from an on-disk field and each byte it reads from the
+//! image. A parse that goes over a cap stops with
+//! [`Error::LimitExceeded`](crate::Error::LimitExceeded); a directory
+//! that turns out to be its own ancestor stops it with
+//! [`Error::Corrupt`](crate::Error::Corrupt).
+//!
+//! Every parse runs under limits. [`parse_as`](crate::probe::parse_as),
+//! [`open_any`](crate::open_any), the `formats::*::detect_and_parse`
+//! functions and the ISO 9660 and UDF entry points use
+//! [`ParseOptions::default`], whose caps are far above anything a real
+//! image needs. Pass tighter options through
+//! [`parse_as_with`](crate::probe::parse_as_with),
+//! [`open_any_with`](crate::probe::open_any_with) or
+//! [`detect_and_parse_filesystem_with`](crate::detect_and_parse_filesystem_with).
+//! The limits apply to each format attempt separately; a filesystem found
+//! inside a partition or disk-image container counts against the budget of
+//! the parse that found it.
+//!
+//! ```no_run
+//! use isomage::limits::ParseOptions;
+//!
+//! let options = ParseOptions::new()
+//!     .max_entries(100_000)
+//!     .max_depth(32)
+//!     .max_read(1 << 30);
+//! let mut upload = std::fs::File::open("upload.img")?;
+//! match isomage::probe::open_any_with(&mut upload, &options) {
+//!     Ok((root, found)) => println!("{}: {} entries", found.format, root.children.len()),
+//!     Err(isomage::Error::LimitExceeded { limit, .. }) => println!("rejected: too many {limit}"),
+//!     Err(e) => println!("rejected: {e}"),
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::probe::Format;
+use crate::recover::Warning;
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Caps on the work one parse may do. See the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    max_entries: u64,
+    max_depth: u64,
+    max_dir_entries: u64,
+    max_name_len: u64,
+    max_alloc: u64,
+    max_read: u64,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000_000,
+            max_depth: 256,
+            max_dir_entries: 1_000_000,
+            max_name_len: 1024,
+            max_alloc: 1 << 30,
+            max_read: u64::MAX,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The defaults: 10 million entries, 256 directory levels, a million
+    /// entries in one directory, 1024-byte names, 1 GiB of metadata
+    /// buffers and no cap on bytes read.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No caps at all. Loops in the directory graph are still reported.
+    pub fn unlimited() -> Self {
+        Self {
+            max_entries: u64::MAX,
+            max_depth: u64::MAX,
+            max_dir_entries: u64::MAX,
+            max_name_len: u64::MAX,
+            max_alloc: u64::MAX,
+            max_read: u64::MAX,
+        }
+    }
+
+    /// Files, directories and other entries in the whole tree.
+    pub fn max_entries(mut self, max: u64) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Levels of directories below the root.
+    pub fn max_depth(mut self, max: u64) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Entries listed in any one directory.
+    pub fn max_dir_entries(mut self, max: u64) -> Self {
+        self.max_dir_entries = max;
+        self
+    }
+
+    /// Bytes in one entry name, after decoding to UTF-8.
+    pub fn max_name_len(mut self, max: u64) -> Self {
+        self.max_name_len = max;
+        self
+    }
+
+    /// Bytes of buffers sized from fields in the image — directory
+    /// extents, allocation tables, catalogs — summed over the parse.
+    pub fn max_alloc(mut self, max: u64) -> Self {
+        self.max_alloc = max;
+        self
+    }
+
+    /// Bytes read from the image, summed over the parse. This bounds the
+    /// parse's running time as well as its I/O.
+    pub fn max_read(mut self, max: u64) -> Self {
+        self.max_read = max;
+        self
+    }
+
+    fn cap(&self, limit: Limit) -> u64 {
+        match limit {
+            Limit::Entries => self.max_entries,
+            Limit::Depth => self.max_depth,
+            Limit::DirEntries => self.max_dir_entries,
+            Limit::NameLength => self.max_name_len,
+            Limit::Alloc => self.max_alloc,
+            Limit::Read => self.max_read,
+        }
+    }
+}
+
+/// Which [`ParseOptions`] cap a parse went over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// [`ParseOptions::max_entries`].
+    Entries,
+    /// [`ParseOptions::max_depth`].
+    Depth,
+    /// [`ParseOptions::max_dir_entries`].
+    DirEntries,
+    /// [`ParseOptions::max_name_len`].
+    NameLength,
+    /// [`ParseOptions::max_alloc`].
+    Alloc,
+    /// [`ParseOptions::max_read`].
+    Read,
+}
+
+impl fmt::Display for Limit {
+    /// What the cap counts, e.g. `"entries in one directory"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Entries => "entries",
+            Limit::Depth => "directory levels",
+            Limit::DirEntries => "entries in one directory",
+            Limit::NameLength => "bytes in one name",
+            Limit::Alloc => "bytes of metadata buffers",
+            Limit::Read => "bytes read",
+        })
+    }
+}
+
+/// Why a parse was stopped. Travels through the parsers inside an
+/// `io::Error`, so every format's existing error path carries it, and
+/// becomes a [`crate::Error`] at the API boundary.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Violation {
+    Limit {
+        format: Format,
+        limit: Limit,
+        max: u64,
+    },
+    Loop {
+        format: Format,
+        id: u64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Violation::Limit { format, limit, max } => {
+                write!(f, "{format} parse stopped: more than {max} {limit}")
+            }
+            Violation::Loop { format, id } => {
+                write!(f, "{format} directory loop: directory {id} contains itself")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl Violation {
+    /// The violation carried by `e`, if it is one.
+    pub(crate) fn of(e: &io::Error) -> Option<Violation> {
+        e.get_ref()?.downcast_ref::<Violation>().copied()
+    }
+
+    pub(crate) fn into_error(self) -> crate::Error {
+        match self {
+            Violation::Limit { format, limit, max } => {
+                crate::Error::LimitExceeded { format, limit, max }
+            }
+            Violation::Loop { format, .. } => crate::Error::corrupt(format, self),
+        }
+    }
+}
+
+impl From<Violation> for io::Error {
+    fn from(v: Violation) -> Self {
+        io::Error::other(v)
+    }
+}
+
+/// What the running parse has used so far.
+struct Budget {
+    options: ParseOptions,
+    format: Format,
+    entries: u64,
+    alloc: u64,
+    read: u64,
+    /// IDs of the directories being listed, root first.
+    path: Vec<u64>,
+    /// Whether a [`Metered`] reader already counts this parse's reads.
+    metered: bool,
+    /// The first violation. Once set every check fails, so a parser that
+    /// skips entries it cannot read still stops promptly.
+    tripped: Option<Violation>,
+    /// Structures skipped so far, when the parse is lenient.
+    warnings: Option<Vec<Warning>>,
+}
+
+thread_local! {
+    static BUDGET: RefCell<Option<Budget>> = const { RefCell::new(None) };
+}
+
+impl Budget {
+    fn new(format: Format, options: &ParseOptions) -> Self {
+        Self {
+            options: options.clone(),
+            format,
+            entries: 0,
+            alloc: 0,
+            read: 0,
+            path: Vec::new(),
+            metered: false,
+            tripped: None,
+            warnings: None,
+        }
+    }
+
+    fn check(&mut self, limit: Limit, used: u64) -> io::Result<()> {
+        if let Some(v) = self.tripped {
+            return Err(v.into());
+        }
+        let max = self.options.cap(limit);
+        if used > max {
+            return Err(self.trip(Violation::Limit {
+                format: self.format,
+                limit,
+                max,
+            }));
+        }
+        Ok(())
+    }
+
+    fn trip(&mut self, v: Violation) -> io::Error {
+        self.tripped.get_or_insert(v);
+        v.into()
+    }
+}
+
+/// Run `f` against the active budget, if there is one.
+fn with_budget(f: impl FnOnce(&mut Budget) -> io::Result<()>) -> io::Result<()> {
+    BUDGET.with(|b| match b.borrow_mut().as_mut() {
+        Some(budget) => f(budget),
+        None => Ok(()),
+    })
+}
+
+/// Run the parse `f` of a resident image under limits. With `options`
+/// it gets a fresh budget; without, it joins the parse already running
+/// on this thread (a filesystem inside a partition) or, if there is
+/// none, starts one with the defaults.
+pub(crate) fn scope<T, E: From<io::Error>>(
+    format: Format,
+    options: Option<&ParseOptions>,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let joined = options.is_none() && BUDGET.with(|b| b.borrow().is_some());
+    if joined {
+        let saved = BUDGET.with(|b| {
+            let mut b = b.borrow_mut();
+            let budget = b.as_mut().expect("checked above");
+            let format = std::mem::replace(&mut budget.format, format);
+            (format, std::mem::take(&mut budget.path))
+        });
+        let result = f();
+        let tripped = BUDGET.with(|b| {
+            let mut b = b.borrow_mut();
+            let budget = b.as_mut().expect("budget outlives the scopes that join it");
+            (budget.format, budget.path) = saved;
+            budget.tripped
+        });
+        return match tripped {
+            Some(v) => Err(io::Error::from(v).into()),
+            None => result,
+        };
+    }
+
+    let default = ParseOptions::default();
+    let budget = Budget::new(format, options.unwrap_or(&default));
+    let outer = BUDGET.with(|b| b.borrow_mut().replace(budget));
+    // Put the caller's budget back even if `f` panics.
+    struct Restore(Option<Option<Budget>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let outer = self.0.take().expect("dropped once");
+            BUDGET.with(|b| *b.borrow_mut() = outer);
+        }
+    }
+    let restore = Restore(Some(outer));
+    let result = f();
+    let tripped = BUDGET.with(|b| b.borrow().as_ref().and_then(|budget| budget.tripped));
+    drop(restore);
+    match tripped {
+        Some(v) => Err(io::Error::from(v).into()),
+        None => result,
+    }
+}
+
+/// Like [`scope`], for a parse that reads through `r`: `f` gets `r`
+/// wrapped so every byte read counts against
+/// [`ParseOptions::max_read`].
+pub(crate) fn metered<R: Read + Seek, T, E: From<io::Error>>(
+    format: Format,
+    options: Option<&ParseOptions>,
+    r: R,
+    f: impl FnOnce(&mut Metered<R>) -> Result<T, E>,
+) -> Result<T, E> {
+    scope(format, options, || {
+        // An enclosing parse's reader already counts these reads.
+        let mut count = false;
+        with_budget(|budget| {
+            count = !std::mem::replace(&mut budget.metered, true);
+            Ok(())
+        })?;
+        let mut metered = Metered { inner: r, count };
+        let result = f(&mut metered);
+        if count {
+            with_budget(|budget| {
+                budget.metered = false;
+                Ok(())
+            })?;
+        }
+        result
+    })
+}
+
+/// Count one entry named `name` into the tree.
+pub(crate) fn entry(name: &str) -> io::Result<()> {
+    with_budget(|budget| {
+        budget.entries += 1;
+        budget.check(Limit::Entries, budget.entries)?;
+        budget.check(Limit::NameLength, name.len() as u64)
+    })
+}
+
+/// Check a directory's listing of `count` entries.
+pub(crate) fn dir_entries(count: u64) -> io::Result<()> {
+    with_budget(|budget| budget.check(Limit::DirEntries, count))
+}
+
+/// Check that an entry `depth` levels below the root may be read.
+#[cfg(any(feature = "hfsplus", feature = "zip", feature = "tar"))]
+pub(crate) fn depth(depth: u64) -> io::Result<()> {
+    with_budget(|budget| budget.check(Limit::Depth, depth))
+}
+
+/// Check an archive's member paths, for formats that build the tree
+/// from `/`-separated names rather than from directory listings. Each
+/// member counts as one entry named by its last component, as deep as
+/// its parent path, and listed in that parent.
+#[cfg(any(feature = "zip", feature = "tar"))]
+pub(crate) fn paths<'p>(paths: impl IntoIterator<Item = &'p str>) -> io::Result<()> {
+    let mut listed = std::collections::HashMap::<&str, u64>::new();
+    for path in paths {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            continue;
+        }
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        entry(name)?;
+        depth(parent.split('/').filter(|c| !c.is_empty()).count() as u64)?;
+        let count = listed.entry(parent).or_default();
+        *count += 1;
+        dir_entries(*count)?;
+    }
+    Ok(())
+}
+
+/// Charge a buffer of `bytes` sized from a field in the image. Call it
+/// before allocating.
+pub(crate) fn alloc(bytes: u64) -> io::Result<()> {
+    with_budget(|budget| {
+        budget.alloc = budget.alloc.saturating_add(bytes);
+        budget.check(Limit::Alloc, budget.alloc)
+    })
+}
+
+/// Charge `bytes` read from a resident image.
+pub(crate) fn read(bytes: u64) -> io::Result<()> {
+    with_budget(|budget| {
+        budget.read = budget.read.saturating_add(bytes);
+        budget.check(Limit::Read, budget.read)
+    })
+}
+
+/// Record that the damaged `structure` at `offset` was skipped for
+/// `reason`. Returns whether it was: only a lenient parse that has not
+/// gone over a cap skips anything.
+pub(crate) fn recover(
+    offset: Option<u64>,
+    structure: &'static str,
+    reason: &dyn fmt::Display,
+) -> bool {
+    BUDGET.with(|b| {
+        let mut b = b.borrow_mut();
+        let Some(budget) = b.as_mut().filter(|budget| budget.tripped.is_none()) else {
+            return false;
+        };
+        let format = budget.format;
+        match &mut budget.warnings {
+            Some(warnings) => {
+                warnings.push(Warning {
+                    format,
+                    offset,
+                    structure,
+                    reason: reason.to_string(),
+                });
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Run `f` as a lenient parse within the active budget, returning what
+/// it skipped alongside its result.
+pub(crate) fn lenient<T>(f: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    let outer = BUDGET.with(|b| {
+        b.borrow_mut()
+            .as_mut()
+            .map(|budget| budget.warnings.replace(Vec::new()))
+    });
+    let result = f();
+    let warnings = BUDGET.with(|b| {
+        b.borrow_mut()
+            .as_mut()
+            .and_then(|budget| std::mem::replace(&mut budget.warnings, outer.flatten()))
+    });
+    (result, warnings.unwrap_or_default())
+}
+
+/// Run `f`, one of several parses tried in turn within the active
+/// budget, dropping the warnings it recorded if it fails.
+pub(crate) fn attempt<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let mark = BUDGET.with(|b| {
+        b.borrow()
+            .as_ref()
+            .and_then(|budget| budget.warnings.as_ref().map(Vec::len))
+    });
+    let result = f();
+    if let (Err(_), Some(mark)) = (&result, mark) {
+        BUDGET.with(|b| {
+            if let Some(warnings) = b.borrow_mut().as_mut().and_then(|b| b.warnings.as_mut()) {
+                warnings.truncate(mark);
+            }
+        });
+    }
+    result
+}
+
+/// Start listing the directory `id` — an inode, record or catalog
+/// number, or the directory's byte offset, whatever identifies a
+/// directory in the format. Fails if `id` is already being listed
+/// further up (a loop) or the new level is deeper than
+/// [`ParseOptions::max_depth`]. The level ends when the returned guard
+/// drops.
+pub(crate) fn enter(id: u64) -> io::Result<Level> {
+    let mut entered = false;
+    with_budget(|budget| {
+        if budget.path.contains(&id) {
+            if let Some(v) = budget.tripped {
+                return Err(v.into());
+            }
+            let v = Violation::Loop {
+                format: budget.format,
+                id,
+            };
+            // A lenient parse drops the looping directory and goes on.
+            if budget.warnings.is_some() {
+                return Err(v.into());
+            }
+            return Err(budget.trip(v));
+        }
+        // The root's own level is 0.
+        budget.check(Limit::Depth, budget.path.len() as u64)?;
+        budget.path.push(id);
+        entered = true;
+        Ok(())
+    })?;
+    Ok(Level { entered })
+}
+
+/// One directory level opened by [`enter`].
+#[must_use]
+pub(crate) struct Level {
+    entered: bool,
+}
+
+impl Drop for Level {
+    fn drop(&mut self) {
+        if self.entered {
+            let _ = with_budget(|budget| {
+                budget.path.pop();
+                Ok(())
+            });
+        }
+    }
+}
+
+/// A reader whose reads count against [`ParseOptions::max_read`], and
+/// which refuses to read once the parse has been stopped.
+#[derive(Debug)]
+pub(crate) struct Metered<R> {
+    inner: R,
+    count: bool,
+}
+
+impl<R: Read> Read for Metered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.count {
+            return self.inner.read(buf);
+        }
+        with_budget(|budget| budget.check(Limit::Read, budget.read))?;
+        let n = self.inner.read(buf)?;
+        read(n as u64)?;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for Metered<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        self.inner.stream_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<T>(options: &ParseOptions, f: impl FnOnce() -> io::Result<T>) -> crate::Result<T> {
+        scope(Format::Tar, Some(options), || {
+            f().map_err(crate::Error::from)
+        })
+    }
+
+    #[test]
+    fn checks_pass_without_a_parse() {
+        assert!(entry(&"x".repeat(100_000)).is_ok());
+        assert!(alloc(u64::MAX).is_ok());
+        let _level = enter(1).unwrap();
+        let _again = enter(1).unwrap();
+    }
+
+    #[test]
+    fn entries_and_names() {
+        let options = ParseOptions::new().max_entries(2).max_name_len(4);
+        assert!(run(&options, || {
+            entry("a")?;
+            entry("abcd")
+        })
+        .is_ok());
+        let err = run(&options, || {
+            for _ in 0..3 {
+                entry("a")?;
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::LimitExceeded {
+                format: Format::Tar,
+                limit: Limit::Entries,
+                max: 2,
+            }
+        ));
+        assert_eq!(err.to_string(), "TAR parse stopped: more than 2 entries");
+        let err = run(&options, || entry("abcde")).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::LimitExceeded {
+                limit: Limit::NameLength,
+                ..
+            }
+        ));
+    }
+
+    #[cfg(any(feature = "zip", feature = "tar"))]
+    #[test]
+    fn archive_paths() {
+        let limit_of = |options: &ParseOptions, names: &[&str]| match run(options, || {
+            paths(names.iter().copied())
+        }) {
+            Ok(()) => None,
+            Err(crate::Error::LimitExceeded { limit, .. }) => Some(limit),
+            Err(e) => panic!("{e}"),
+        };
+        let options = ParseOptions::new().max_depth(1).max_dir_entries(2);
+        assert_eq!(limit_of(&options, &["a/", "a/b", "a/c", "d"]), None);
+        assert_eq!(limit_of(&options, &["a/b/c"]), Some(Limit::Depth));
+        assert_eq!(
+            limit_of(&options, &["a/b", "a/c", "a/d"]),
+            Some(Limit::DirEntries)
+        );
+        let options = ParseOptions::new().max_name_len(3);
+        assert_eq!(limit_of(&options, &["long/abc"]), None);
+        assert_eq!(limit_of(&options, &["abcd/"]), Some(Limit::NameLength));
+    }
+
+    #[test]
+    fn violation_sticks_even_when_swallowed() {
+        let options = ParseOptions::new().max_alloc(10);
+        let err = run(&options, || {
+            let _ = alloc(11);
+            // A parser that skips what it cannot read still stops.
+            assert!(entry("a").is_err());
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::LimitExceeded {
+                limit: Limit::Alloc,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn levels_and_loops() {
+        let options = ParseOptions::new().max_depth(2);
+        assert!(run(&options, || {
+            let _root = enter(2)?;
+            let _a = enter(10)?;
+            let _b = enter(11)?;
+            Ok(())
+        })
+        .is_ok());
+        let err = run(&options, || {
+            let _root = enter(2)?;
+            let _a = enter(10)?;
+            let _b = enter(11)?;
+            let _c = enter(12)?;
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::LimitExceeded {
+                limit: Limit::Depth,
+                max: 2,
+                ..
+            }
+        ));
+
+        // Siblings may share nothing with the path; revisiting an
+        // ancestor is a loop.
+        let err = run(&ParseOptions::unlimited(), || {
+            let _root = enter(2)?;
+            drop(enter(10)?);
+            let _a = enter(10)?;
+            let _loop = enter(2)?;
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::Corrupt {
+                format: Format::Tar,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("loop"), "{err}");
+    }
+
+    #[test]
+    fn nested_parses_share_the_budget() {
+        let options = ParseOptions::new().max_entries(3);
+        let err = run(&options, || {
+            entry("disk")?;
+            let inner: io::Result<()> = scope(Format::Fat, None, || {
+                let _root = enter(0)?;
+                entry("a")?;
+                entry("b")?;
+                entry("c")
+            });
+            inner
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::LimitExceeded {
+                format: Format::Fat,
+                limit: Limit::Entries,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reads_are_metered_once() {
+        let data = vec![0u8; 100];
+        let options = ParseOptions::new().max_read(151);
+        let mut cursor = io::Cursor::new(&data);
+        let result = metered(Format::Tar, Some(&options), &mut cursor, |r| {
+            let mut buf = Vec::new();
+            r.read_to_end(&mut buf)?;
+            r.seek(SeekFrom::Start(0))?;
+            // A nested parse over the same reader is not counted twice.
+            metered(Format::Fat, None, &mut *r, |inner| {
+                inner.read_exact(&mut [0u8; 50])
+            })?;
+            r.read_exact(&mut [0u8; 1])?;
+            assert!(r.read_exact(&mut [0u8; 1]).is_err());
+            Ok::<_, crate::Error>(())
+        });
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::Error::LimitExceeded {
+                format: Format::Tar,
+                limit: Limit::Read,
+                max: 151,
+            }
+        ));
+    }
+}
@@ -0,0 +1,1229 @@
+//! Manifests: a parsed tree written out as text, and read back.
+//!
+//! Three formats, one line per entry, in depth-first order:
+//!
+//! - [`write_json_lines`]: one JSON object per line carrying everything
+//!   in the tree — path, kind, size, data location and extents, and every
+//!   [`Metadata`] field the format recorded. This is the lossless one.
+//! - [`write_mtree`]: BSD `mtree(5)` with full paths, as written by
+//!   `bsdtar --format=mtree`. Data locations go in an `extents` keyword
+//!   that other mtree readers don't know; strip it before feeding the
+//!   file to `mtree -f`.
+//! - [`write_bodyfile`]: The Sleuth Kit 3.x `bodyfile`, for building a
+//!   timeline with `mactime`. It has no field for data locations, and
+//!   records unknown times as `0`.
+//!
+//! [`read_manifest`] parses JSON lines or mtree (told apart by the first
+//! line) back into a [`TreeNode`].
+//!
+//! ```
+//! use isomage::manifest::{read_manifest, write_json_lines};
+//! use isomage::TreeNode;
+//!
+//! let mut root = TreeNode::new_directory("/");
+//! root.add_child(TreeNode::new_file_with_location("boot.cat", 2048, 36864, 2048));
+//!
+//! let mut out = Vec::new();
+//! write_json_lines(&root, &mut out)?;
+//! assert_eq!(
+//!     String::from_utf8_lossy(&out).lines().nth(1),
+//!     Some(r#"{"path":"/boot.cat","type":"file","size":2048,"location":36864,"length":2048}"#)
+//! );
+//! let back = read_manifest(&out[..])?;
+//! assert_eq!(back.find_node("boot.cat").unwrap().file_location, Some(36864));
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
+use crate::Result;
+
+/// Write `root` and everything below it as JSON lines.
+///
+/// Keys are `path` (`/`-separated, the root being `"/"`), `type`, `size`,
+/// then as recorded: `location`, `length`, `extents` (`[[location,
+/// length], …]`, `null` location for holes), `mode`, `uid`, `gid`,
+/// `nlink`, `device` (`[major, minor]`), `mtime`, `atime`, `ctime`,
+/// `btime` (each `[secs, nanos]`) and `link`.
+/// `type` is one of `dir`, `file`, `symlink`, `hardlink`, `char`,
+/// `block`, `fifo` and `socket`.
+pub fn write_json_lines<W: Write>(root: &TreeNode, writer: &mut W) -> Result<()> {
+    for (path, node) in entries(root) {
+        let mut line = String::from("{\"path\":");
+        json_string(&mut line, &path);
+        let _ = write!(
+            line,
+            ",\"type\":\"{}\",\"size\":{}",
+            kind_name(kind(node)),
+            node.size
+        );
+        if let Some(location) = node.file_location {
+            let _ = write!(line, ",\"location\":{location}");
+        }
+        if let Some(length) = node.file_length {
+            let _ = write!(line, ",\"length\":{length}");
+        }
+        if !node.extents.is_empty() {
+            line.push_str(",\"extents\":[");
+            for (i, extent) in node.extents.iter().enumerate() {
+                if i > 0 {
+                    line.push(',');
+                }
+                match extent.location {
+                    Some(location) => {
+                        let _ = write!(line, "[{location},{}]", extent.length);
+                    }
+                    None => {
+                        let _ = write!(line, "[null,{}]", extent.length);
+                    }
+                }
+            }
+            line.push(']');
+        }
+        let metadata = &node.metadata;
+        for (key, value) in [
+            ("mode", metadata.mode),
+            ("uid", metadata.uid),
+            ("gid", metadata.gid),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(line, ",\"{key}\":{value}");
+            }
+        }
+        if let Some(nlink) = metadata.nlink {
+            let _ = write!(line, ",\"nlink\":{nlink}");
+        }
+        if let Some((major, minor)) = metadata.device {
+            let _ = write!(line, ",\"device\":[{major},{minor}]");
+        }
+        for (key, time) in times(metadata) {
+            if let Some(time) = time {
+                let _ = write!(line, ",\"{key}\":[{},{}]", time.secs, time.nanos);
+            }
+        }
+        if let Some(link) = &metadata.link_target {
+            line.push_str(",\"link\":");
+            json_string(&mut line, link);
+        }
+        line.push_str("}\n");
+        writer.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write `root` and everything below it as an `mtree(5)` specification
+/// with full paths (`./etc/hostname`), the root being `.`.
+///
+/// Each line carries `type` and whichever of `size`, `mode`, `uid`,
+/// `gid`, `nlink`, `device` (`native,MAJOR,MINOR`, for character and
+/// block devices), `time` (mtime, `secs.nanos`) and `link` are known, plus
+/// `extents=LOCATION:LENGTH,…` (`-` for a hole) for files whose data the
+/// parser located. Names are encoded as `vis(3)` does, with `\ooo`
+/// escapes for whitespace, `\`, `#`, `=` and anything non-ASCII.
+pub fn write_mtree<W: Write>(root: &TreeNode, writer: &mut W) -> Result<()> {
+    writer.write_all(b"#mtree\n")?;
+    for (path, node) in entries(root) {
+        let mut line = String::from(".");
+        if path != "/" {
+            vis(&mut line, &path);
+        }
+        let kind = kind(node);
+        let type_name = match kind {
+            EntryKind::Directory => "dir",
+            EntryKind::Symlink => "link",
+            EntryKind::CharDevice => "char",
+            EntryKind::BlockDevice => "block",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::File | EntryKind::Hardlink => "file",
+        };
+        let _ = write!(line, " type={type_name}");
+        if !node.is_directory {
+            let _ = write!(line, " size={}", node.size);
+        }
+        let metadata = &node.metadata;
+        if let Some(mode) = metadata.mode {
+            let _ = write!(line, " mode={mode:o}");
+        }
+        if let Some(uid) = metadata.uid {
+            let _ = write!(line, " uid={uid}");
+        }
+        if let Some(gid) = metadata.gid {
+            let _ = write!(line, " gid={gid}");
+        }
+        if let Some(nlink) = metadata.nlink {
+            let _ = write!(line, " nlink={nlink}");
+        }
+        if let (EntryKind::CharDevice | EntryKind::BlockDevice, Some((major, minor))) =
+            (kind, metadata.device)
+        {
+            let _ = write!(line, " device=native,{major},{minor}");
+        }
+        if let Some(time) = metadata.mtime {
+            let _ = write!(line, " time={}.{:09}", time.secs, time.nanos);
+        }
+        if let Some(link) = &metadata.link_target {
+            line.push_str(" link=");
+            vis(&mut line, link);
+        }
+        if let Some(extents) = node.data_extents() {
+            if !extents.is_empty() {
+                line.push_str(" extents=");
+                for (i, extent) in extents.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    match extent.location {
+                        Some(location) => {
+                            let _ = write!(line, "{location}:{}", extent.length);
+                        }
+                        None => {
+                            let _ = write!(line, "-:{}", extent.length);
+                        }
+                    }
+                }
+            }
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write `root` and everything below it in The Sleuth Kit's `bodyfile`
+/// format:
+/// `MD5|name|inode|mode_as_string|UID|GID|size|atime|mtime|ctime|crtime`.
+///
+/// `MD5` and `inode` are `0`, as are unknown IDs and times. Symlinks are
+/// named `path -> target`, as `fls` writes them. A `|`, `\` or control
+/// character in a name is written as a `\ooo` octal escape, so every
+/// entry stays on one line with eleven fields.
+pub fn write_bodyfile<W: Write>(root: &TreeNode, writer: &mut W) -> Result<()> {
+    for (path, node) in entries(root) {
+        let metadata = &node.metadata;
+        let mut name = String::new();
+        body_escape(&mut name, &path);
+        if let (EntryKind::Symlink, Some(target)) = (kind(node), &metadata.link_target) {
+            name.push_str(" -> ");
+            body_escape(&mut name, target);
+        }
+        let secs = |time: Option<Timestamp>| time.map_or(0, |time| time.secs);
+        let line = format!(
+            "0|{name}|0|{}|{}|{}|{}|{}|{}|{}|{}\n",
+            mode_string(kind(node), metadata.mode),
+            metadata.uid.unwrap_or(0),
+            metadata.gid.unwrap_or(0),
+            node.size,
+            secs(metadata.atime),
+            secs(metadata.mtime),
+            secs(metadata.ctime),
+            secs(metadata.btime),
+        );
+        writer.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Parse a manifest written by [`write_json_lines`] or [`write_mtree`]
+/// back into a tree. The format is taken from the first non-blank line:
+/// `#mtree` (or any `#` comment) means mtree, `{` means JSON lines.
+///
+/// Entries may come in any order as long as each parent comes before
+/// its children; a missing parent directory is created empty. Directory
+/// sizes are taken from the manifest where given and otherwise summed.
+///
+/// mtree input may also be in the classic relative form, with `..`
+/// lines leaving a directory, and may use `/set` and `/unset`; unknown
+/// keywords are ignored. Malformed lines fail with an
+/// [`io::ErrorKind::InvalidData`] error naming the line.
+pub fn read_manifest<R: BufRead>(reader: R) -> Result<TreeNode<'static>> {
+    let mut builder = Builder::new();
+    let mut mtree: Option<Mtree> = None;
+    let mut first = true;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fail = |message: String| -> crate::Error {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("manifest line {}: {message}", number + 1),
+            )
+            .into()
+        };
+        if first {
+            first = false;
+            if line.starts_with('#') {
+                mtree = Some(Mtree::default());
+            }
+        }
+        let entry = match &mut mtree {
+            Some(mtree) => match mtree.line(line).map_err(fail)? {
+                Some(entry) => entry,
+                None => continue,
+            },
+            None => json_entry(line).map_err(fail)?,
+        };
+        builder.insert(entry).map_err(fail)?;
+    }
+    Ok(builder.finish())
+}
+
+/// Every node with its path, the root as `/`.
+fn entries<'t, 'a>(root: &'t TreeNode<'a>) -> impl Iterator<Item = (String, &'t TreeNode<'a>)> {
+    std::iter::once(("/".to_string(), root))
+        .chain(root.walk().map(|entry| (entry.path, entry.node)))
+}
+
+fn kind(node: &TreeNode) -> EntryKind {
+    if node.is_directory {
+        EntryKind::Directory
+    } else {
+        node.metadata.kind
+    }
+}
+
+fn kind_name(kind: EntryKind) -> &'static str {
+    match kind {
+        EntryKind::File => "file",
+        EntryKind::Directory => "dir",
+        EntryKind::Symlink => "symlink",
+        EntryKind::Hardlink => "hardlink",
+        EntryKind::CharDevice => "char",
+        EntryKind::BlockDevice => "block",
+        EntryKind::Fifo => "fifo",
+        EntryKind::Socket => "socket",
+    }
+}
+
+fn times(metadata: &Metadata) -> [(&'static str, Option<Timestamp>); 4] {
+    [
+        ("mtime", metadata.mtime),
+        ("atime", metadata.atime),
+        ("ctime", metadata.ctime),
+        ("btime", metadata.btime),
+    ]
+}
+
+/// `ls -l` style mode with the `fls` type prefix, e.g. `d/drwxr-xr-x`.
+fn mode_string(kind: EntryKind, mode: Option<u32>) -> String {
+    let (meta, name) = match kind {
+        EntryKind::Directory => ('d', 'd'),
+        EntryKind::Symlink => ('l', 'l'),
+        EntryKind::CharDevice => ('c', 'c'),
+        EntryKind::BlockDevice => ('b', 'b'),
+        EntryKind::Fifo => ('p', 'p'),
+        EntryKind::Socket => ('s', 'h'),
+        EntryKind::File | EntryKind::Hardlink => ('r', 'r'),
+    };
+    let mode = mode.unwrap_or(0);
+    let mut out = format!("{name}/{meta}");
+    for (shift, special, set_char, plain_char) in [
+        (6, 0o4000, 's', 'S'),
+        (3, 0o2000, 's', 'S'),
+        (0, 0o1000, 't', 'T'),
+    ] {
+        let bits = (mode >> shift) & 7;
+        out.push(if bits & 4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => set_char,
+            (false, true) => plain_char,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// `vis(3)` with `VIS_WHITE | VIS_OCTAL`, plus `#` and `=` which mtree
+/// treats specially.
+fn vis(out: &mut String, s: &str) {
+    for &byte in s.as_bytes() {
+        if byte.is_ascii_graphic() && !matches!(byte, b'\\' | b'#' | b'=') {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    }
+}
+
+/// Escape the bodyfile field separator, `\` and control characters as
+/// `\ooo`, the way [`vis`] does for mtree.
+fn body_escape(out: &mut String, s: &str) {
+    for c in s.chars() {
+        if c == '|' || c == '\\' || c.is_ascii_control() {
+            let _ = write!(out, "\\{:03o}", c as u32);
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+fn unvis(s: &str) -> std::result::Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match octal {
+            Some(digits) => {
+                let value = digits.iter().fold(0u32, |v, d| v * 8 + u32::from(d - b'0'));
+                out.push(u8::try_from(value).map_err(|_| format!("bad escape in {s:?}"))?);
+                i += 4;
+            }
+            None => match bytes.get(i + 1) {
+                Some(b's') => {
+                    out.push(b' ');
+                    i += 2;
+                }
+                Some(&c @ (b'\\' | b'#' | b'=')) => {
+                    out.push(c);
+                    i += 2;
+                }
+                _ => return Err(format!("bad escape in {s:?}")),
+            },
+        }
+    }
+    String::from_utf8(out).map_err(|_| format!("{s:?} is not UTF-8"))
+}
+
+/// One manifest line, decoded.
+#[derive(Debug, Default)]
+struct Entry {
+    path: String,
+    kind: EntryKind,
+    size: Option<u64>,
+    location: Option<u64>,
+    length: Option<u64>,
+    extents: Vec<Extent>,
+    metadata: Metadata,
+}
+
+impl Entry {
+    fn into_node(self, name: String) -> TreeNode<'static> {
+        let mut node = if self.kind == EntryKind::Directory {
+            TreeNode::new_directory(name)
+        } else {
+            TreeNode::new_file(name, self.size.unwrap_or(0))
+        };
+        node.size = self.size.unwrap_or(0);
+        node.file_location = self.location;
+        node.file_length = self.length;
+        node.extents = self.extents;
+        node.with_metadata(Metadata {
+            kind: self.kind,
+            ..self.metadata
+        })
+    }
+}
+
+/// Builds a tree from entries given parent-first, in any order otherwise.
+struct Builder {
+    nodes: Vec<(TreeNode<'static>, Vec<usize>)>,
+    by_path: HashMap<String, usize>,
+    /// Directories whose size the manifest did not give, to be summed.
+    sum_size: Vec<bool>,
+}
+
+impl Builder {
+    fn new() -> Self {
+        let root = TreeNode::new_directory("/");
+        Self {
+            nodes: vec![(root, Vec::new())],
+            by_path: HashMap::from([(String::new(), 0)]),
+            sum_size: vec![true],
+        }
+    }
+
+    fn insert(&mut self, mut entry: Entry) -> std::result::Result<(), String> {
+        let full_path = std::mem::take(&mut entry.path);
+        let path: Vec<&str> = full_path.split('/').filter(|c| !c.is_empty()).collect();
+        if path.iter().any(|c| *c == "." || *c == "..") {
+            return Err(format!("path {full_path:?} is not normalized"));
+        }
+        let sum_size = entry.kind == EntryKind::Directory && entry.size.is_none();
+        let Some((name, parents)) = path.split_last() else {
+            if entry.kind != EntryKind::Directory {
+                return Err("the root must be a directory".into());
+            }
+            let mut root = entry.into_node("/".into());
+            root.children.clear();
+            self.nodes[0].0 = root;
+            self.sum_size[0] = sum_size;
+            return Ok(());
+        };
+        let mut parent = 0;
+        let mut key = String::new();
+        for component in parents {
+            if !key.is_empty() {
+                key.push('/');
+            }
+            key.push_str(component);
+            parent = match self.by_path.get(&key) {
+                Some(&index) => index,
+                None => self.push(
+                    parent,
+                    key.clone(),
+                    TreeNode::new_directory(component.to_string()),
+                    true,
+                ),
+            };
+            if !self.nodes[parent].0.is_directory {
+                return Err(format!("{key:?} is not a directory"));
+            }
+        }
+        if !key.is_empty() {
+            key.push('/');
+        }
+        key.push_str(name);
+        let node = entry.into_node(name.to_string());
+        match self.by_path.get(&key) {
+            // An implied directory, now described.
+            Some(&index) if self.nodes[index].0.is_directory && node.is_directory => {
+                self.nodes[index].0 = node;
+                self.sum_size[index] = sum_size;
+            }
+            Some(_) => return Err(format!("duplicate path {key:?}")),
+            None => {
+                self.push(parent, key, node, sum_size);
+            }
+        }
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        parent: usize,
+        key: String,
+        node: TreeNode<'static>,
+        sum_size: bool,
+    ) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push((node, Vec::new()));
+        self.sum_size.push(sum_size);
+        self.nodes[parent].1.push(index);
+        self.by_path.insert(key, index);
+        index
+    }
+
+    fn finish(mut self) -> TreeNode<'static> {
+        // Children always have higher indices than their parent, so
+        // attaching from the back completes every subtree before its
+        // parent is attached.
+        let mut built: Vec<Option<TreeNode<'static>>> = Vec::with_capacity(self.nodes.len());
+        let mut children: Vec<Vec<usize>> = Vec::with_capacity(self.nodes.len());
+        for (node, kids) in self.nodes.drain(..) {
+            built.push(Some(node));
+            children.push(kids);
+        }
+        for index in (0..built.len()).rev() {
+            let kids: Vec<TreeNode<'static>> = children[index]
+                .iter()
+                .filter_map(|&kid| built[kid].take())
+                .collect();
+            let node = built[index].as_mut().expect("attached before its parent");
+            node.children = kids;
+            if self.sum_size[index] {
+                node.size = node.children.iter().map(|child| child.size).sum();
+            }
+        }
+        built[0].take().expect("root")
+    }
+}
+
+fn json_entry(line: &str) -> std::result::Result<Entry, String> {
+    let mut parser = Json {
+        bytes: line.as_bytes(),
+        pos: 0,
+    };
+    let Value::Object(fields) = parser.value()? else {
+        return Err("expected a JSON object".into());
+    };
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err("trailing characters after the object".into());
+    }
+
+    let mut entry = Entry::default();
+    let mut path = None;
+    for (key, value) in fields {
+        let unsigned = |value: &Value| match value {
+            Value::Number(n) => u64::try_from(*n).map_err(|_| format!("{key} out of range")),
+            _ => Err(format!("{key} must be a number")),
+        };
+        let id = |value: &Value| {
+            unsigned(value)
+                .and_then(|n| u32::try_from(n).map_err(|_| format!("{key} out of range")))
+        };
+        match key.as_str() {
+            "path" => match value {
+                Value::String(s) => path = Some(s),
+                _ => return Err("path must be a string".into()),
+            },
+            "type" => {
+                entry.kind = match &value {
+                    Value::String(s) => kind_from_name(s),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("unknown type {value:?}"))?;
+            }
+            "size" => entry.size = Some(unsigned(&value)?),
+            "location" => entry.location = Some(unsigned(&value)?),
+            "length" => entry.length = Some(unsigned(&value)?),
+            "extents" => {
+                let Value::Array(runs) = value else {
+                    return Err("extents must be an array".into());
+                };
+                for run in runs {
+                    match run {
+                        Value::Array(pair) if pair.len() == 2 => {
+                            let length = unsigned(&pair[1])?;
+                            entry.extents.push(match &pair[0] {
+                                Value::Null => Extent::hole(length),
+                                location => Extent::data(unsigned(location)?, length),
+                            });
+                        }
+                        _ => return Err("each extent must be [location, length]".into()),
+                    }
+                }
+            }
+            "mode" => entry.metadata.mode = Some(id(&value)?),
+            "uid" => entry.metadata.uid = Some(id(&value)?),
+            "gid" => entry.metadata.gid = Some(id(&value)?),
+            "nlink" => entry.metadata.nlink = Some(unsigned(&value)?),
+            "device" => match &value {
+                Value::Array(pair) if pair.len() == 2 => {
+                    entry.metadata.device = Some((id(&pair[0])?, id(&pair[1])?));
+                }
+                _ => return Err("device must be [major, minor]".into()),
+            },
+            "mtime" | "atime" | "ctime" | "btime" => {
+                let time = match &value {
+                    Value::Array(pair) if pair.len() == 2 => match (&pair[0], &pair[1]) {
+                        (Value::Number(secs), Value::Number(nanos)) => i64::try_from(*secs)
+                            .ok()
+                            .zip(u32::try_from(*nanos).ok().filter(|n| *n < 1_000_000_000))
+                            .map(|(secs, nanos)| Timestamp::new(secs, nanos)),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+                .ok_or_else(|| format!("{key} must be [secs, nanos]"))?;
+                match key.as_str() {
+                    "mtime" => entry.metadata.mtime = Some(time),
+                    "atime" => entry.metadata.atime = Some(time),
+                    "ctime" => entry.metadata.ctime = Some(time),
+                    _ => entry.metadata.btime = Some(time),
+                }
+            }
+            "link" => match value {
+                Value::String(s) => entry.metadata.link_target = Some(s),
+                _ => return Err("link must be a string".into()),
+            },
+            // Written by a later version; ignore.
+            _ => {}
+        }
+    }
+    entry.path = path.ok_or("missing path")?;
+    Ok(entry)
+}
+
+fn kind_from_name(name: &str) -> Option<EntryKind> {
+    Some(match name {
+        "file" => EntryKind::File,
+        "dir" => EntryKind::Directory,
+        "symlink" => EntryKind::Symlink,
+        "hardlink" => EntryKind::Hardlink,
+        "char" => EntryKind::CharDevice,
+        "block" => EntryKind::BlockDevice,
+        "fifo" => EntryKind::Fifo,
+        "socket" => EntryKind::Socket,
+        _ => return None,
+    })
+}
+
+/// The subset of JSON the writer produces: objects, arrays, strings,
+/// integers, `null` and booleans.
+#[derive(Debug)]
+enum Value {
+    Null,
+    /// Only seen in keys the reader skips.
+    Bool,
+    Number(i128),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+struct Json<'s> {
+    bytes: &'s [u8],
+    pos: usize,
+}
+
+impl Json<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> std::result::Result<(), String> {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!(
+                "expected '{}' at column {}",
+                byte as char,
+                self.pos + 1
+            ))
+        }
+    }
+
+    fn value(&mut self) -> std::result::Result<Value, String> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Value::Object(fields));
+                        }
+                        _ => {
+                            return Err(format!("expected ',' or '}}' at column {}", self.pos + 1))
+                        }
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(format!("expected ',' or ']' at column {}", self.pos + 1)),
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .ok()
+                    .and_then(|digits| digits.parse().ok())
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("bad number at column {}", start + 1))
+            }
+            _ => {
+                for (word, value) in [
+                    ("null", Value::Null),
+                    ("true", Value::Bool),
+                    ("false", Value::Bool),
+                ] {
+                    if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(format!("unexpected input at column {}", self.pos + 1))
+            }
+        }
+    }
+
+    fn string(&mut self) -> std::result::Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            let rest = &self.bytes[self.pos..];
+            let run = rest
+                .iter()
+                .position(|&b| b == b'"' || b == b'\\')
+                .ok_or("unterminated string")?;
+            out.push_str(std::str::from_utf8(&rest[..run]).map_err(|_| "string is not UTF-8")?);
+            self.pos += run + 1;
+            if rest[run] == b'"' {
+                return Ok(out);
+            }
+            let escape = *self.bytes.get(self.pos).ok_or("unterminated string")?;
+            self.pos += 1;
+            match escape {
+                b'"' => out.push('"'),
+                b'\\' => out.push('\\'),
+                b'/' => out.push('/'),
+                b'b' => out.push('\u{8}'),
+                b'f' => out.push('\u{c}'),
+                b'n' => out.push('\n'),
+                b'r' => out.push('\r'),
+                b't' => out.push('\t'),
+                b'u' => {
+                    let mut unit = self.hex4()?;
+                    let mut units = vec![unit];
+                    if (0xD800..0xDC00).contains(&unit)
+                        && self.bytes[self.pos..].starts_with(b"\\u")
+                    {
+                        self.pos += 2;
+                        unit = self.hex4()?;
+                        units.push(unit);
+                    }
+                    out.push_str(&String::from_utf16(&units).map_err(|_| "bad \\u escape")?);
+                }
+                _ => return Err(format!("bad escape at column {}", self.pos)),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> std::result::Result<u16, String> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u16::from_str_radix(d, 16).ok())
+            .ok_or("bad \\u escape")?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+/// mtree parsing state: `/set` defaults and, for the relative form, the
+/// current directory.
+#[derive(Default)]
+struct Mtree {
+    defaults: HashMap<String, String>,
+    cwd: Vec<String>,
+}
+
+impl Mtree {
+    fn line(&mut self, line: &str) -> std::result::Result<Option<Entry>, String> {
+        if line.starts_with('#') {
+            return Ok(None);
+        }
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap_or_default();
+        match first {
+            "/set" => {
+                for word in words {
+                    let (key, value) = word
+                        .split_once('=')
+                        .ok_or(format!("bad keyword {word:?}"))?;
+                    self.defaults.insert(key.to_string(), value.to_string());
+                }
+                return Ok(None);
+            }
+            "/unset" => {
+                for word in words {
+                    if word == "all" {
+                        self.defaults.clear();
+                    }
+                    self.defaults.remove(word);
+                }
+                return Ok(None);
+            }
+            ".." => {
+                self.cwd.pop().ok_or("'..' above the root")?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let name = unvis(first)?;
+        let full = first.contains('/');
+        let path = if full {
+            name.strip_prefix('.').unwrap_or(&name).to_string()
+        } else if name == "." {
+            String::new()
+        } else {
+            format!("{}/{name}", self.cwd.join("/"))
+        };
+
+        let mut keywords = self.defaults.clone();
+        for word in words {
+            let (key, value) = word
+                .split_once('=')
+                .ok_or(format!("bad keyword {word:?}"))?;
+            keywords.insert(key.to_string(), value.to_string());
+        }
+
+        let mut entry = Entry {
+            path: path.clone(),
+            ..Entry::default()
+        };
+        let number = |key: &str, value: &str, radix: u32| {
+            u64::from_str_radix(value, radix).map_err(|_| format!("bad {key} {value:?}"))
+        };
+        let id = |key: &str, value: &str, radix: u32| {
+            number(key, value, radix)
+                .and_then(|n| u32::try_from(n).map_err(|_| format!("bad {key} {value:?}")))
+        };
+        for (key, value) in &keywords {
+            match key.as_str() {
+                "type" => {
+                    entry.kind = match value.as_str() {
+                        "file" => EntryKind::File,
+                        "dir" => EntryKind::Directory,
+                        "link" => EntryKind::Symlink,
+                        "char" => EntryKind::CharDevice,
+                        "block" => EntryKind::BlockDevice,
+                        "fifo" => EntryKind::Fifo,
+                        "socket" => EntryKind::Socket,
+                        _ => return Err(format!("unknown type {value:?}")),
+                    }
+                }
+                "size" => entry.size = Some(number(key, value, 10)?),
+                "mode" => entry.metadata.mode = Some(id(key, value, 8)?),
+                "uid" => entry.metadata.uid = Some(id(key, value, 10)?),
+                "gid" => entry.metadata.gid = Some(id(key, value, 10)?),
+                "nlink" => entry.metadata.nlink = Some(number(key, value, 10)?),
+                // `format,major,minor`, as libarchive writes it; the
+                // format name is not checked.
+                "device" => match value.split(',').collect::<Vec<_>>()[..] {
+                    [_, major, minor] => {
+                        entry.metadata.device = Some((id(key, major, 10)?, id(key, minor, 10)?));
+                    }
+                    _ => return Err(format!("bad device {value:?}")),
+                },
+                "time" => {
+                    let (secs, nanos) = value.split_once('.').unwrap_or((value, "0"));
+                    let secs = secs.parse().map_err(|_| format!("bad time {value:?}"))?;
+                    let nanos = nanos
+                        .parse()
+                        .ok()
+                        .filter(|n| *n < 1_000_000_000)
+                        .ok_or(format!("bad time {value:?}"))?;
+                    entry.metadata.mtime = Some(Timestamp::new(secs, nanos));
+                }
+                "link" => entry.metadata.link_target = Some(unvis(value)?),
+                "extents" => {
+                    for run in value.split(',') {
+                        let (location, length) =
+                            run.split_once(':').ok_or(format!("bad extent {run:?}"))?;
+                        let length = number(key, length, 10)?;
+                        entry.extents.push(match location {
+                            "-" => Extent::hole(length),
+                            location => Extent::data(number(key, location, 10)?, length),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        if entry.kind == EntryKind::File && entry.metadata.link_target.is_some() {
+            entry.kind = EntryKind::Hardlink;
+        }
+        if entry.kind != EntryKind::Directory {
+            // A single run covering the file is a plain location, as
+            // parsers store it.
+            let size = entry.size.unwrap_or(0);
+            match entry.extents[..] {
+                [] => {}
+                [Extent {
+                    location: Some(location),
+                    length,
+                }] if length == size => {
+                    entry.location = Some(location);
+                    entry.length = Some(size);
+                    entry.extents.clear();
+                }
+                _ => entry.length = Some(size),
+            }
+        } else if !full && !path.is_empty() {
+            self.cwd = path
+                .split('/')
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        Ok(Some(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tree using every field the manifests carry.
+    fn tree() -> TreeNode<'static> {
+        let mut root = TreeNode::new_directory("/").with_metadata(Metadata {
+            mode: Some(0o755),
+            ..Metadata::new(EntryKind::Directory)
+        });
+        let mut etc = TreeNode::new_directory("etc");
+        etc.add_child(
+            TreeNode::new_file_with_location("host name", 18, 4096, 18).with_metadata(Metadata {
+                mode: Some(0o4644),
+                uid: Some(0),
+                gid: Some(10),
+                nlink: Some(2),
+                mtime: Some(Timestamp::new(1_700_000_000, 123_456_789)),
+                atime: Some(Timestamp::new(-5, 0)),
+                btime: Some(Timestamp::new(1, 2)),
+                ..Metadata::new(EntryKind::File)
+            }),
+        );
+        etc.add_child(TreeNode::new_file_with_extents(
+            "sparse=\"db\"\\",
+            10_000,
+            vec![
+                Extent::data(8192, 4096),
+                Extent::hole(4096),
+                Extent::data(0, 4096),
+            ],
+        ));
+        root.add_child(etc);
+        root.add_child(TreeNode::new_file("unlocated#ñ", 5));
+        root.add_child(TreeNode::new_file_with_location("empty", 0, 0, 0));
+        root.add_child(TreeNode::new_file("link", 0).with_metadata(Metadata {
+            link_target: Some("etc/host name".into()),
+            ..Metadata::new(EntryKind::Symlink)
+        }));
+        root.add_child(TreeNode::new_file("hard", 0).with_metadata(Metadata {
+            link_target: Some("etc/host name".into()),
+            ..Metadata::new(EntryKind::Hardlink)
+        }));
+        root.add_child(TreeNode::new_file("tty", 0).with_metadata(Metadata {
+            device: Some((4, 64)),
+            ..Metadata::new(EntryKind::CharDevice)
+        }));
+        root.calculate_directory_size();
+        root
+    }
+
+    fn json(root: &TreeNode) -> String {
+        let mut out = Vec::new();
+        write_json_lines(root, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn mtree(root: &TreeNode) -> String {
+        let mut out = Vec::new();
+        write_mtree(root, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        let text = json(&tree());
+        assert_eq!(text.lines().count(), 9);
+        assert_eq!(
+            text.lines().nth(2).unwrap(),
+            r#"{"path":"/etc/host name","type":"file","size":18,"location":4096,"length":18,"mode":2468,"uid":0,"gid":10,"nlink":2,"mtime":[1700000000,123456789],"atime":[-5,0],"btime":[1,2]}"#
+        );
+        assert_eq!(
+            text.lines().nth(8).unwrap(),
+            r#"{"path":"/tty","type":"char","size":0,"device":[4,64]}"#
+        );
+        assert!(text.contains(r#""extents":[[8192,4096],[null,4096],[0,1808]]"#));
+        assert!(text.contains(r#""path":"/etc/sparse=\"db\"\\""#));
+
+        let back = read_manifest(text.as_bytes()).unwrap();
+        assert_eq!(json(&back), text);
+        assert_eq!(
+            back.find_node("link").unwrap().metadata.kind,
+            EntryKind::Symlink
+        );
+        let host = back.find_node("etc/host name").unwrap();
+        assert_eq!(host.metadata.nlink, Some(2));
+        assert_eq!(
+            back.find_node("tty").unwrap().metadata.device,
+            Some((4, 64))
+        );
+    }
+
+    #[test]
+    fn mtree_round_trip() {
+        let text = mtree(&tree());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "#mtree");
+        assert_eq!(lines[1], ". type=dir mode=755");
+        assert_eq!(
+            lines[3],
+            "./etc/host\\040name type=file size=18 mode=4644 uid=0 gid=10 nlink=2 \
+             time=1700000000.123456789 extents=4096:18"
+        );
+        assert_eq!(
+            lines[4],
+            "./etc/sparse\\075\"db\"\\134 type=file size=10000 extents=8192:4096,-:4096,0:1808"
+        );
+        assert_eq!(lines[5], "./unlocated\\043\\303\\261 type=file size=5");
+        assert_eq!(lines[7], "./link type=link size=0 link=etc/host\\040name");
+        assert_eq!(lines[9], "./tty type=char size=0 device=native,4,64");
+
+        // mtree keeps only mtime and has no hard link type of its own;
+        // everything else comes back.
+        let back = read_manifest(text.as_bytes()).unwrap();
+        assert_eq!(mtree(&back), text);
+        let host = back.find_node("etc/host name").unwrap();
+        assert_eq!(host.file_location, Some(4096));
+        assert_eq!(host.metadata.mode, Some(0o4644));
+        assert_eq!(host.metadata.atime, None);
+        assert_eq!(host.metadata.nlink, Some(2));
+        assert_eq!(
+            back.find_node("tty").unwrap().metadata.device,
+            Some((4, 64))
+        );
+        assert_eq!(
+            back.find_node("hard").unwrap().metadata.kind,
+            EntryKind::Hardlink
+        );
+        assert_eq!(back.find_node("etc").unwrap().size, 18 + 10_000);
+    }
+
+    #[test]
+    fn mtree_relative_form() {
+        let text = "\
+#mtree
+/set type=file uid=0 gid=0 mode=644
+. type=dir
+etc type=dir mode=755
+    passwd size=1024
+    ssh type=dir
+        sshd\\137config size=3
+    ..
+..
+readme size=7 uid=1000
+/unset uid
+orphan size=1
+";
+        let root = read_manifest(text.as_bytes()).unwrap();
+        let config = root.find_node("etc/ssh/sshd_config").unwrap();
+        assert_eq!((config.size, config.metadata.mode), (3, Some(0o644)));
+        assert_eq!(root.find_node("etc").unwrap().metadata.mode, Some(0o755));
+        assert_eq!(root.find_node("readme").unwrap().metadata.uid, Some(1000));
+        assert_eq!(root.find_node("orphan").unwrap().metadata.uid, None);
+        assert_eq!(root.size, 1024 + 3 + 7 + 1);
+    }
+
+    #[test]
+    fn bodyfile() {
+        let mut out = Vec::new();
+        write_bodyfile(&tree(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "0|/|0|d/drwxr-xr-x|0|0|10023|0|0|0|0");
+        assert_eq!(
+            lines[2],
+            "0|/etc/host name|0|r/rrwSr--r--|0|10|18|-5|1700000000|0|1"
+        );
+        assert_eq!(
+            lines[6],
+            "0|/link -> etc/host name|0|l/l---------|0|0|0|0|0|0|0"
+        );
+        assert_eq!(lines[8], "0|/tty|0|c/c---------|0|0|0|0|0|0|0");
+        assert!(lines.iter().all(|line| line.split('|').count() == 11));
+
+        // Separators and line breaks in names are escaped.
+        let mut root = TreeNode::new_directory("/");
+        root.add_child(TreeNode::new_file("a|b\nc\\d", 1));
+        root.add_child(TreeNode::new_file("l", 0).with_metadata(Metadata {
+            link_target: Some("x|y".into()),
+            ..Metadata::new(EntryKind::Symlink)
+        }));
+        let mut out = Vec::new();
+        write_bodyfile(&root, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "0|/a\\174b\\012c\\134d|0|r/r---------|0|0|1|0|0|0|0"
+        );
+        assert_eq!(lines[2], "0|/l -> x\\174y|0|l/l---------|0|0|0|0|0|0|0");
+    }
+
+    #[test]
+    fn read_manifest_errors() {
+        let invalid = |text: &str| {
+            let err = read_manifest(text.as_bytes()).unwrap_err();
+            assert!(
+                matches!(&err, crate::Error::Io(e) if e.kind() == io::ErrorKind::InvalidData),
+                "{err:?}"
+            );
+            err.to_string()
+        };
+        assert!(invalid("{\"path\":\"/a\"}\n{\"path\":\"/a\"}").starts_with("manifest line 2:"));
+        invalid("{\"path\":\"/a\",\"type\":\"nope\"}");
+        invalid("{\"path\":\"/a\",\"size\":-1}");
+        invalid("{\"path\":\"/a\"} x");
+        invalid("{\"size\":1}");
+        invalid("{\"path\":\"/a\",\"type\":\"file\"}\n{\"path\":\"/a/b\"}");
+        invalid("{\"path\":\"/../a\"}");
+        invalid("#mtree\n..");
+        invalid("#mtree\n./a type=file size=x");
+        invalid("#mtree\n./a\\9 type=file");
+        invalid("#mtree\n./a type=char device=8");
+        invalid("{\"path\":\"/a\",\"device\":[1]}");
+
+        // Unknown keys and keywords are skipped; missing parents appear.
+        let root = read_manifest(&b"{\"path\":\"/x/y\",\"future\":[true,{}]}\n"[..]).unwrap();
+        assert!(root.find_node("x").unwrap().is_directory);
+        assert_eq!(read_manifest(&b"\n"[..]).unwrap().children.len(), 0);
+        let root = read_manifest(&b"#mtree\n./a type=file sha256=00\n"[..]).unwrap();
+        assert!(root.find_node("a").is_some());
+        let root = read_manifest(&b"{\"path\":\"/\\u00e9\\ud83d\\ude00\"}"[..]).unwrap();
+        assert_eq!(root.children[0].name, "é😀");
+    }
+}
@@ -0,0 +1,196 @@
+//! Structured progress and diagnostic events.
+//!
+//! Parsing and extraction report what they are doing to an
+//! [`Observer`] instead of printing. The `*_observed` entry points —
+//! [`detect_and_parse_filesystem_observed`](crate::detect_and_parse_filesystem_observed),
+//! [`parse_iso9660_observed`](crate::iso9660::parse_iso9660_observed),
+//! [`parse_udf_observed`](crate::udf::parse_udf_observed) and
+//! [`extract_node_observed`](crate::extract_node_observed) — take one;
+//! the `*_verbose` functions are the same calls with a
+//! [`StderrObserver`].
+//!
+//! `()` is the observer that ignores everything, and any
+//! `FnMut(&Event)` closure is an observer:
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use isomage::observe::Event;
+//!
+//! let mut file = File::open("disc.iso")?;
+//! let mut log = Vec::new();
+//! let root = isomage::detect_and_parse_filesystem_observed(
+//!     &mut file,
+//!     "disc.iso",
+//!     &mut |event: &Event| {
+//!         if let Event::Diagnostic(d) = event {
+//!             log.push(format!("{} {}", d.section, d.message));
+//!         }
+//!     },
+//! )?;
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use crate::probe::Format;
+
+/// Receives [`Event`]s. Events arrive in order on the calling thread.
+pub trait Observer {
+    /// Handle one event. The event borrows from the caller, so copy out
+    /// anything that must outlive the call.
+    fn event(&mut self, event: &Event<'_>);
+}
+
+/// Ignores every event.
+impl Observer for () {
+    fn event(&mut self, _event: &Event<'_>) {}
+}
+
+impl<F: FnMut(&Event<'_>)> Observer for F {
+    fn event(&mut self, event: &Event<'_>) {
+        self(event)
+    }
+}
+
+/// Something that happened during parsing or extraction.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// A parser step worth logging: a structure found, a fallback taken
+    /// or a damaged record skipped.
+    Diagnostic(Diagnostic<'a>),
+    /// Extraction created (or, with [`ExtractOptions::dry_run`], would
+    /// create) a directory.
+    ///
+    /// [`ExtractOptions::dry_run`]: crate::ExtractOptions::dry_run
+    DirectoryCreated { path: &'a Path },
+    /// Extraction is about to write a file, symlink or hard link of
+    /// `length` bytes.
+    EntryStarted { path: &'a Path, length: u64 },
+    /// Another chunk of the current file has been written; `done` of
+    /// `total` bytes so far. Not sent on a dry run.
+    BytesCopied {
+        path: &'a Path,
+        done: u64,
+        total: u64,
+    },
+    /// The entry from the last `EntryStarted` is complete.
+    EntryFinished { path: &'a Path, bytes: u64 },
+}
+
+/// How much a [`Diagnostic`] matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Progress through the image's structures.
+    Info,
+    /// Something was wrong and the parser worked around it.
+    Warning,
+}
+
+/// A parser message tagged with where in the spec it comes from.
+#[derive(Debug)]
+pub struct Diagnostic<'a> {
+    /// The format being parsed, or `None` for format-neutral probing.
+    pub format: Option<Format>,
+    /// The spec and section of the structure involved, e.g.
+    /// `"ECMA-119 8.4"` or `"ECMA-167 3/10.2"`. Empty when none applies.
+    pub section: &'static str,
+    pub level: Level,
+    pub message: fmt::Arguments<'a>,
+}
+
+/// Prints diagnostics and extraction progress to stderr, in the format
+/// the `*_verbose` functions use.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StderrObserver;
+
+impl Observer for StderrObserver {
+    fn event(&mut self, event: &Event<'_>) {
+        match event {
+            Event::Diagnostic(d) => {
+                let warning = match d.level {
+                    Level::Warning => "warning: ",
+                    Level::Info => "",
+                };
+                match (d.format, d.section) {
+                    (_, "") => eprintln!("{}{}", warning, d.message),
+                    (Some(format), section) => {
+                        eprintln!("[{} {}] {}{}", format, section, warning, d.message)
+                    }
+                    (None, section) => eprintln!("[{}] {}{}", section, warning, d.message),
+                }
+            }
+            Event::DirectoryCreated { path } => {
+                eprintln!("Created directory: {}", path.display())
+            }
+            Event::EntryFinished { path, .. } => eprintln!("Extracted: {}", path.display()),
+            _ => {}
+        }
+    }
+}
+
+/// Send an [`Level::Info`] diagnostic.
+pub(crate) fn info(
+    observer: &mut dyn Observer,
+    format: impl Into<Option<Format>>,
+    section: &'static str,
+    message: fmt::Arguments<'_>,
+) {
+    emit(observer, format.into(), section, Level::Info, message);
+}
+
+/// Send a [`Level::Warning`] diagnostic.
+pub(crate) fn warn(
+    observer: &mut dyn Observer,
+    format: impl Into<Option<Format>>,
+    section: &'static str,
+    message: fmt::Arguments<'_>,
+) {
+    emit(observer, format.into(), section, Level::Warning, message);
+}
+
+fn emit(
+    observer: &mut dyn Observer,
+    format: Option<Format>,
+    section: &'static str,
+    level: Level,
+    message: fmt::Arguments<'_>,
+) {
+    observer.event(&Event::Diagnostic(Diagnostic {
+        format,
+        section,
+        level,
+        message,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closures_and_unit_are_observers() {
+        let mut seen = Vec::new();
+        let mut record = |event: &Event<'_>| {
+            if let Event::Diagnostic(d) = event {
+                seen.push((d.level, d.section, d.message.to_string()));
+            }
+        };
+        info(
+            &mut record,
+            Format::Udf,
+            "ECMA-167 3/10.2",
+            format_args!("AVDP at {}", 256),
+        );
+        warn(&mut record, None, "", format_args!("odd"));
+        info(&mut (), Format::Iso9660, "", format_args!("dropped"));
+        assert_eq!(
+            seen,
+            [
+                (Level::Info, "ECMA-167 3/10.2", "AVDP at 256".to_string()),
+                (Level::Warning, "", "odd".to_string()),
+            ]
+        );
+    }
+}
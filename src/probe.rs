@@ -0,0 +1,702 @@
+//! Auto-detection across every feature-enabled format.
+//!
+//! [`open_any`] is the one entry point for "I have an image, I don't know
+//! what it is". It runs every enabled format's magic-byte check with
+//! [`probe`], ranks the matches by [`Confidence`], and parses with the
+//! best one, falling back to the next candidate if that parse fails.
+//!
+//! Several formats routinely overlap: an isohybrid ISO carries an MBR
+//! (and often a GPT), a FAT or NTFS boot sector ends in the same
+//! `0x55AA` as an MBR, and a fixed VHD is a raw disk with a footer. A
+//! long, checksummed or structurally validated signature therefore
+//! outranks a two-byte magic, and equal confidences fall back to the
+//! order of [`Format::ALL`], which lists wrappers before what they wrap
+//! and specific filesystems before bare partition tables.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::image_io::{ImageReader, RandomAccess, Window};
+use crate::limits::{self, ParseOptions};
+use crate::recover::{self, Parsed, Warning};
+use crate::tree::TreeNode;
+use crate::{Error, Result};
+
+/// An image or filesystem format `isomage` can recognise.
+///
+/// Every variant exists regardless of which Cargo features are enabled;
+/// [`Format::is_enabled`] says whether this build can parse it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    Iso9660,
+    Udf,
+    Mbr,
+    Gpt,
+    Fat,
+    Ntfs,
+    Ext,
+    HfsPlus,
+    Apfs,
+    Squashfs,
+    Vhd,
+    Vmdk,
+    Qcow2,
+    Dmg,
+    Wim,
+    Zip,
+    Tar,
+}
+
+impl Format {
+    /// Every format, in tie-break order: when two matches have the same
+    /// [`Confidence`], the one listed first wins.
+    pub const ALL: &'static [Format] = &[
+        Format::Vhd,
+        Format::Vmdk,
+        Format::Qcow2,
+        Format::Dmg,
+        Format::Wim,
+        Format::Iso9660,
+        Format::Udf,
+        Format::Gpt,
+        Format::Apfs,
+        Format::HfsPlus,
+        Format::Ntfs,
+        Format::Fat,
+        Format::Ext,
+        Format::Squashfs,
+        Format::Zip,
+        Format::Tar,
+        Format::Mbr,
+    ];
+
+    /// Human-readable name, e.g. `"ISO 9660"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Iso9660 => "ISO 9660",
+            Format::Udf => "UDF",
+            Format::Mbr => "MBR",
+            Format::Gpt => "GPT",
+            Format::Fat => "FAT",
+            Format::Ntfs => "NTFS",
+            Format::Ext => "ext2/3/4",
+            Format::HfsPlus => "HFS+",
+            Format::Apfs => "APFS",
+            Format::Squashfs => "SquashFS",
+            Format::Vhd => "VHD",
+            Format::Vmdk => "VMDK",
+            Format::Qcow2 => "QCOW2",
+            Format::Dmg => "DMG",
+            Format::Wim => "WIM",
+            Format::Zip => "ZIP",
+            Format::Tar => "TAR",
+        }
+    }
+
+    /// Whether this is a filesystem that can sit inside a partition, as
+    /// opposed to a partition table, disk-image container or archive.
+    pub fn is_filesystem(self) -> bool {
+        matches!(
+            self,
+            Format::Iso9660
+                | Format::Udf
+                | Format::Fat
+                | Format::Ntfs
+                | Format::Ext
+                | Format::HfsPlus
+                | Format::Apfs
+                | Format::Squashfs
+        )
+    }
+
+    /// Whether this build was compiled with the feature for this format.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Format::Iso9660 => cfg!(feature = "iso"),
+            Format::Udf => cfg!(feature = "udf"),
+            Format::Mbr => cfg!(feature = "mbr"),
+            Format::Gpt => cfg!(feature = "gpt"),
+            Format::Fat => cfg!(feature = "fat"),
+            Format::Ntfs => cfg!(feature = "ntfs"),
+            Format::Ext => cfg!(feature = "ext"),
+            Format::HfsPlus => cfg!(feature = "hfsplus"),
+            Format::Apfs => cfg!(feature = "apfs"),
+            Format::Squashfs => cfg!(feature = "squashfs"),
+            Format::Vhd => cfg!(feature = "vhd"),
+            Format::Vmdk => cfg!(feature = "vmdk"),
+            Format::Qcow2 => cfg!(feature = "qcow2"),
+            Format::Dmg => cfg!(feature = "dmg"),
+            Format::Wim => cfg!(feature = "wim"),
+            Format::Zip => cfg!(feature = "zip"),
+            Format::Tar => cfg!(feature = "tar"),
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How strongly a probe's evidence points at its format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// A short signature with nothing else behind it, e.g. `0x55AA` with
+    /// an empty MBR partition table.
+    Low,
+    /// A short magic number, or a signature that other formats can also
+    /// carry (ext's 2-byte magic, a ZIP end-of-central-directory record).
+    Medium,
+    /// A long or structurally validated signature.
+    High,
+}
+
+/// One format match found by [`probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    /// The format that matched.
+    pub format: Format,
+    /// Byte offset within the reader where the format's volume or
+    /// container starts.
+    pub offset: u64,
+    /// How strong the match is.
+    pub confidence: Confidence,
+}
+
+/// Run every enabled format's detector against `r` and return the
+/// matches, best first.
+///
+/// Probing only reads; a format whose signature check hits an I/O error
+/// (typically a short image) is treated as not matching.
+pub fn probe<R: Read + Seek>(r: &mut R) -> Result<Vec<Detection>> {
+    let mut found = Vec::new();
+    for &format in Format::ALL {
+        if !format.is_enabled() {
+            continue;
+        }
+        r.seek(SeekFrom::Start(0))?;
+        if let Some(confidence) = probe_format(r, format) {
+            found.push(Detection {
+                format,
+                offset: 0,
+                confidence,
+            });
+        }
+    }
+    // Stable sort keeps `Format::ALL` order among equal confidences.
+    found.sort_by_key(|d| std::cmp::Reverse(d.confidence));
+    r.seek(SeekFrom::Start(0))?;
+    Ok(found)
+}
+
+/// Detect the format of `r` and parse it, returning the tree together
+/// with a description of what was found.
+///
+/// Candidates from [`probe`] are tried best first; if the best match's
+/// parser rejects the image, the next one is tried. The error lists every
+/// candidate's failure when none parses.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let mut file = File::open("disk.img")?;
+/// let (root, found) = isomage::open_any(&mut file)?;
+/// println!("{} at byte {}: {} entries", found.format, found.offset, root.children.len());
+/// # Ok::<(), isomage::Error>(())
+/// ```
+pub fn open_any<R: Read + Seek>(r: &mut R) -> Result<(TreeNode<'static>, Detection)> {
+    open_any_with(r, &ParseOptions::default())
+}
+
+/// [`open_any`] under the caps in `options`. Each candidate format's
+/// attempt gets its own budget; see [`crate::limits`].
+pub fn open_any_with<R: Read + Seek>(
+    r: &mut R,
+    options: &ParseOptions,
+) -> Result<(TreeNode<'static>, Detection)> {
+    let candidates = probe(r)?;
+    parse_best(r, candidates, Some(options), None)
+}
+
+/// [`open_any_with`] as a lenient parse: damaged structures are skipped
+/// and reported in [`Parsed::warnings`]. See [`crate::recover`].
+///
+/// A candidate that fails outright still falls through to the next one;
+/// the first that yields a tree wins, however many warnings it has.
+pub fn open_any_lenient<R: Read + Seek>(
+    r: &mut R,
+    options: &ParseOptions,
+) -> Result<(Parsed<'static>, Detection)> {
+    let candidates = probe(r)?;
+    let mut warnings = Vec::new();
+    let (root, detection) = parse_best(r, candidates, Some(options), Some(&mut warnings))?;
+    Ok((Parsed { root, warnings }, detection))
+}
+
+/// Detect and parse the filesystem occupying `len` bytes at `offset`
+/// of `r`, typically one partition of a disk image.
+///
+/// Only [filesystem](Format::is_filesystem) formats are considered. The
+/// volume is read through a [`Window`], so the parsers see it starting
+/// at offset 0; the returned tree's locations are then shifted back to
+/// absolute offsets into `r`, and the [`Detection`] reports `offset`.
+pub fn open_filesystem_at<R: Read + Seek>(
+    r: &mut R,
+    offset: u64,
+    len: u64,
+) -> Result<(TreeNode<'static>, Detection)> {
+    let mut window = Window::new(&mut *r, offset, len);
+    let candidates = probe(&mut window)?
+        .into_iter()
+        .filter(|d| d.format.is_filesystem())
+        .collect();
+    let (mut root, mut detection) = parse_best(&mut window, candidates, None, None)?;
+    root.shift_locations(offset);
+    detection.offset = offset;
+    Ok((root, detection))
+}
+
+/// Replace each partition child of `root` that holds a recognisable
+/// filesystem with a directory of the same name containing that
+/// filesystem's tree.
+///
+/// Children are the partition byte ranges produced by the `mbr`, `gpt`
+/// and `raw` parsers. A partition whose contents are not recognised, or
+/// fail to parse, stays a plain file over its raw bytes.
+#[cfg(any(feature = "mbr", feature = "gpt"))]
+pub(crate) fn descend_into_partitions<R: Read + Seek>(r: &mut R, root: &mut TreeNode) {
+    for child in &mut root.children {
+        if child.is_directory {
+            continue;
+        }
+        let (Some(start), Some(len)) = (child.file_location, child.file_length) else {
+            continue;
+        };
+        match open_filesystem_at(r, start, len) {
+            Ok((fs, _)) => {
+                let name = std::mem::take(&mut child.name);
+                *child = TreeNode { name, ..fs };
+            }
+            // Nothing recognisable is an ordinary partition; a filesystem
+            // that was found but failed is damage.
+            Err(Error::NotRecognized { .. }) => {}
+            Err(e) => recover::note(Some(start), "partition", e),
+        }
+    }
+    root.calculate_directory_size();
+}
+
+/// Try `candidates` in order, returning the first successful parse.
+/// With `options`, each attempt gets a fresh budget; without, the
+/// attempts join the parse already running (a partition's filesystem).
+/// With `warnings` as well, each attempt is lenient and the successful
+/// one's warnings are stored there.
+fn parse_best<R: Read + Seek>(
+    r: &mut R,
+    candidates: Vec<Detection>,
+    options: Option<&ParseOptions>,
+    mut warnings: Option<&mut Vec<Warning>>,
+) -> Result<(TreeNode<'static>, Detection)> {
+    if candidates.is_empty() {
+        return Err(Error::NotRecognized {
+            format: None,
+            message: "no supported image or filesystem format detected".into(),
+        });
+    }
+
+    // Keep the most likely format's error for its category; the message
+    // lists every attempt.
+    let mut first = None;
+    let mut errors = Vec::new();
+    for detection in candidates {
+        r.seek(SeekFrom::Start(detection.offset))?;
+        let format = detection.format;
+        let parsed = match options {
+            Some(options) => limits::metered(format, Some(options), &mut *r, |r| {
+                recover::collect(warnings.as_deref_mut(), || parse_as(r, format))
+            }),
+            None => limits::attempt(|| parse_as(r, format)),
+        };
+        match parsed {
+            Ok(root) => return Ok((root, detection)),
+            Err(e) => {
+                errors.push(format!("{}: {}", detection.format, e));
+                first.get_or_insert(e);
+            }
+        }
+    }
+    let message = format!(
+        "every detected format failed to parse:\n  - {}",
+        errors.join("\n  - ")
+    );
+    Err(first
+        .expect("candidates is non-empty")
+        .with_message(message))
+}
+
+/// Parse `r` as `format`.
+///
+/// This is the uniform wrapper over the per-format entry points, whose
+/// signatures and error types differ. Position `r` at the start of the
+/// image first. Returns an error if the format is not enabled in this
+/// build.
+pub fn parse_as<R: Read + Seek>(r: &mut R, format: Format) -> Result<TreeNode<'static>> {
+    match format {
+        #[cfg(feature = "iso")]
+        Format::Iso9660 => crate::iso9660::parse_iso9660(r),
+        #[cfg(feature = "udf")]
+        Format::Udf => crate::udf::parse_udf(r),
+        #[cfg(feature = "mbr")]
+        Format::Mbr => Ok(crate::formats::mbr::detect_and_parse(r)?),
+        #[cfg(feature = "gpt")]
+        Format::Gpt => Ok(crate::formats::gpt::detect_and_parse(r)?),
+        #[cfg(feature = "fat")]
+        Format::Fat => Ok(crate::formats::fat::detect_and_parse(r)?),
+        #[cfg(feature = "ntfs")]
+        Format::Ntfs => Ok(crate::formats::ntfs::detect_and_parse(r)?),
+        #[cfg(feature = "ext")]
+        Format::Ext => Ok(crate::formats::ext::detect_and_parse(r)?),
+        #[cfg(feature = "hfsplus")]
+        Format::HfsPlus => Ok(crate::formats::hfsplus::detect_and_parse(r)?),
+        #[cfg(feature = "apfs")]
+        Format::Apfs => Ok(crate::formats::apfs::detect_and_parse(r)?),
+        #[cfg(feature = "squashfs")]
+        Format::Squashfs => Ok(crate::formats::squashfs::detect_and_parse(r)?),
+        #[cfg(feature = "vhd")]
+        Format::Vhd => Ok(crate::formats::vhd::detect_and_parse(r)?),
+        #[cfg(feature = "vmdk")]
+        Format::Vmdk => Ok(crate::formats::vmdk::detect_and_parse(r)?),
+        #[cfg(feature = "qcow2")]
+        Format::Qcow2 => Ok(crate::formats::qcow2::detect_and_parse(r)?),
+        #[cfg(feature = "dmg")]
+        Format::Dmg => Ok(crate::formats::dmg::detect_and_parse(r)?),
+        #[cfg(feature = "wim")]
+        Format::Wim => Ok(crate::formats::wim::detect_and_parse(r)?),
+        #[cfg(feature = "zip")]
+        Format::Zip => Ok(crate::formats::zip::detect_and_parse(r)?),
+        #[cfg(feature = "tar")]
+        Format::Tar => Ok(crate::formats::tar::detect_and_parse(r)?),
+        #[allow(unreachable_patterns)]
+        _ => {
+            let _ = r;
+            Err(Error::unsupported(
+                format,
+                format!("{format} support is not enabled in this build"),
+            ))
+        }
+    }
+}
+
+/// [`parse_as`] for an image that is already in memory or mapped.
+///
+/// The ISO 9660, UDF, ext, ZIP and TAR parsers read `image` in place and
+/// borrow names out of it where the on-disk bytes are already the name.
+/// Every other format is parsed through an [`ImageReader`] over `image`
+/// and owns its names.
+///
+/// ```no_run
+/// use isomage::probe::{parse_image_as, Format};
+///
+/// let bytes = std::fs::read("rootfs.img")?;
+/// let root = parse_image_as(&bytes, Format::Ext)?;
+/// println!("{} entries", root.children.len());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_image_as<R: RandomAccess + ?Sized>(image: &R, format: Format) -> Result<TreeNode<'_>> {
+    match format {
+        #[cfg(feature = "iso")]
+        Format::Iso9660 => crate::iso9660::parse_iso9660_image(image),
+        #[cfg(feature = "udf")]
+        Format::Udf => crate::udf::parse_udf_image(image),
+        #[cfg(feature = "ext")]
+        Format::Ext => Ok(crate::formats::ext::detect_and_parse_image(image)?),
+        #[cfg(feature = "zip")]
+        Format::Zip => Ok(crate::formats::zip::detect_and_parse_image(image)?),
+        #[cfg(feature = "tar")]
+        Format::Tar => Ok(crate::formats::tar::detect_and_parse_image(image)?),
+        _ => parse_as(&mut ImageReader::new(image), format),
+    }
+}
+
+/// [`parse_as`] under the caps in `options`; see [`crate::limits`].
+pub fn parse_as_with<R: Read + Seek>(
+    r: &mut R,
+    format: Format,
+    options: &ParseOptions,
+) -> Result<TreeNode<'static>> {
+    limits::metered(format, Some(options), r, |r| parse_as(r, format))
+}
+
+/// [`parse_as_with`] as a lenient parse: damaged structures are skipped
+/// and reported in [`Parsed::warnings`]. See [`crate::recover`].
+pub fn parse_as_lenient<R: Read + Seek>(
+    r: &mut R,
+    format: Format,
+    options: &ParseOptions,
+) -> Result<Parsed<'static>> {
+    let mut warnings = Vec::new();
+    let root = limits::metered(format, Some(options), r, |r| {
+        recover::collect(Some(&mut warnings), || parse_as(r, format))
+    })?;
+    Ok(Parsed { root, warnings })
+}
+
+/// Check one format's signature at the reader's current position.
+fn probe_format<R: Read + Seek>(r: &mut R, format: Format) -> Option<Confidence> {
+    match format {
+        #[cfg(feature = "iso")]
+        Format::Iso9660 => probe_iso9660(r),
+        #[cfg(feature = "udf")]
+        Format::Udf => probe_udf(r),
+        #[cfg(feature = "mbr")]
+        Format::Mbr => probe_mbr(r),
+        #[cfg(feature = "gpt")]
+        Format::Gpt => {
+            let mut sector = [0u8; 512];
+            r.seek(SeekFrom::Start(512)).ok()?;
+            r.read_exact(&mut sector).ok()?;
+            crate::formats::gpt::parse_header_sector(&sector)
+                .is_ok()
+                .then_some(Confidence::High)
+        }
+        #[cfg(feature = "fat")]
+        Format::Fat => crate::formats::fat::detect(r).then_some(Confidence::High),
+        #[cfg(feature = "ntfs")]
+        Format::Ntfs => crate::formats::ntfs::detect(r).then_some(Confidence::High),
+        #[cfg(feature = "ext")]
+        Format::Ext => crate::formats::ext::detect(r).then_some(Confidence::Medium),
+        #[cfg(feature = "hfsplus")]
+        Format::HfsPlus => crate::formats::hfsplus::parse_volume_header(r)
+            .is_ok()
+            .then_some(Confidence::Medium),
+        #[cfg(feature = "apfs")]
+        Format::Apfs => crate::formats::apfs::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "squashfs")]
+        Format::Squashfs => crate::formats::squashfs::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "vhd")]
+        Format::Vhd => crate::formats::vhd::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "vmdk")]
+        Format::Vmdk => crate::formats::vmdk::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "qcow2")]
+        Format::Qcow2 => crate::formats::qcow2::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "dmg")]
+        Format::Dmg => crate::formats::dmg::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "wim")]
+        Format::Wim => crate::formats::wim::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[cfg(feature = "zip")]
+        Format::Zip => crate::formats::zip::detect(r)
+            .is_ok()
+            .then_some(Confidence::Medium),
+        #[cfg(feature = "tar")]
+        Format::Tar => crate::formats::tar::detect(r)
+            .is_ok()
+            .then_some(Confidence::High),
+        #[allow(unreachable_patterns)]
+        _ => {
+            let _ = r;
+            None
+        }
+    }
+}
+
+/// Number of 2048-byte sectors after sector 16 scanned for volume
+/// descriptors (ISO 9660) or the volume recognition sequence (UDF).
+#[cfg(any(feature = "iso", feature = "udf"))]
+const DESCRIPTOR_SCAN_SECTORS: u64 = 16;
+
+/// Read the 6-byte header (type + standard identifier) of each 2048-byte
+/// sector from 16 onwards, stopping at the first short read.
+#[cfg(any(feature = "iso", feature = "udf"))]
+fn descriptor_headers<R: Read + Seek>(r: &mut R) -> Vec<[u8; 6]> {
+    let mut headers = Vec::new();
+    for sector in 16..16 + DESCRIPTOR_SCAN_SECTORS {
+        let mut head = [0u8; 6];
+        if r.seek(SeekFrom::Start(sector * 2048)).is_err() || r.read_exact(&mut head).is_err() {
+            break;
+        }
+        headers.push(head);
+    }
+    headers
+}
+
+/// ECMA-119 §8: a primary volume descriptor (type 1, `CD001`) is a
+/// strong match; other `CD001` descriptors alone are a weaker one.
+#[cfg(feature = "iso")]
+fn probe_iso9660<R: Read + Seek>(r: &mut R) -> Option<Confidence> {
+    let headers = descriptor_headers(r);
+    let cd001: Vec<_> = headers.iter().filter(|h| &h[1..6] == b"CD001").collect();
+    if cd001.iter().any(|h| h[0] == 1) {
+        Some(Confidence::High)
+    } else if !cd001.is_empty() {
+        Some(Confidence::Medium)
+    } else {
+        None
+    }
+}
+
+/// ECMA-167 §2/3: an `NSR02`/`NSR03` descriptor in the volume recognition
+/// sequence, strengthened by an anchor volume descriptor pointer (tag 2)
+/// at sector 256.
+#[cfg(feature = "udf")]
+fn probe_udf<R: Read + Seek>(r: &mut R) -> Option<Confidence> {
+    let nsr = descriptor_headers(r)
+        .iter()
+        .any(|h| &h[1..6] == b"NSR02" || &h[1..6] == b"NSR03");
+    if !nsr {
+        return None;
+    }
+    let mut tag = [0u8; 2];
+    let anchored = r.seek(SeekFrom::Start(256 * 2048)).is_ok()
+        && r.read_exact(&mut tag).is_ok()
+        && u16::from_le_bytes(tag) == 2;
+    Some(if anchored {
+        Confidence::High
+    } else {
+        Confidence::Medium
+    })
+}
+
+/// `0x55AA` plus a parseable partition table. An empty table is only a
+/// weak match: FAT and NTFS boot sectors end in the same signature.
+#[cfg(feature = "mbr")]
+fn probe_mbr<R: Read + Seek>(r: &mut R) -> Option<Confidence> {
+    let mut sector = [0u8; 512];
+    r.read_exact(&mut sector).ok()?;
+    match crate::formats::mbr::parse_sector(&sector) {
+        Ok(parts) if !parts.is_empty() => Some(Confidence::Medium),
+        Ok(_) | Err(crate::formats::mbr::Error::ProtectiveMbr) => Some(Confidence::Low),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A blank image with an ISO 9660 PVD at sector 16.
+    #[cfg(feature = "iso")]
+    fn iso_signature() -> Vec<u8> {
+        let mut img = vec![0u8; 18 * 2048];
+        img[16 * 2048] = 1;
+        img[16 * 2048 + 1..16 * 2048 + 6].copy_from_slice(b"CD001");
+        img[17 * 2048] = 255;
+        img[17 * 2048 + 1..17 * 2048 + 6].copy_from_slice(b"CD001");
+        img
+    }
+
+    #[test]
+    fn all_lists_every_format_once() {
+        let mut seen = std::collections::HashSet::new();
+        for f in Format::ALL {
+            assert!(seen.insert(*f), "{f} listed twice");
+        }
+        assert_eq!(seen.len(), 17);
+    }
+
+    #[cfg(feature = "iso")]
+    #[test]
+    fn probe_finds_iso_signature() {
+        let found = probe(&mut Cursor::new(iso_signature())).unwrap();
+        assert_eq!(found[0].format, Format::Iso9660);
+        assert_eq!(found[0].confidence, Confidence::High);
+        assert_eq!(found[0].offset, 0);
+    }
+
+    #[test]
+    fn probe_empty_image_finds_nothing() {
+        let found = probe(&mut Cursor::new(vec![0u8; 4096])).unwrap();
+        assert!(found.is_empty());
+        let err = open_any(&mut Cursor::new(vec![0u8; 4096])).unwrap_err();
+        assert!(err.to_string().contains("no supported"));
+    }
+
+    #[cfg(all(feature = "iso", feature = "udf"))]
+    #[test]
+    fn udf_without_anchor_is_medium() {
+        let mut img = iso_signature();
+        img[17 * 2048 + 1..17 * 2048 + 6].copy_from_slice(b"NSR02");
+        let found = probe(&mut Cursor::new(img)).unwrap();
+        let udf = found.iter().find(|d| d.format == Format::Udf).unwrap();
+        assert_eq!(udf.confidence, Confidence::Medium);
+        // The ISO PVD still ranks first.
+        assert_eq!(found[0].format, Format::Iso9660);
+    }
+
+    #[cfg(feature = "iso")]
+    #[test]
+    fn open_any_reports_every_failure() {
+        // A PVD signature with nothing behind it: detected, but unparseable.
+        let err = open_any(&mut Cursor::new(iso_signature())).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("every detected format failed"), "{msg}");
+        assert!(msg.contains("ISO 9660:"), "{msg}");
+    }
+
+    #[test]
+    fn confidence_orders_low_to_high() {
+        assert!(Confidence::Low < Confidence::Medium);
+        assert!(Confidence::Medium < Confidence::High);
+    }
+
+    #[cfg(all(feature = "tar", feature = "mbr"))]
+    #[test]
+    fn parse_image_as_borrows_where_the_parser_can() {
+        let mut tar = vec![0u8; 3 * 512];
+        tar[..5].copy_from_slice(b"a.txt");
+        tar[124..136].copy_from_slice(b"00000000000\0");
+        tar[156] = b'0';
+        tar[257..263].copy_from_slice(b"ustar\0");
+        let root = parse_image_as(&tar, Format::Tar).unwrap();
+        assert!(matches!(
+            root.children[0].name,
+            std::borrow::Cow::Borrowed("a.txt")
+        ));
+
+        // MBR is read through a cursor and parses as `parse_as` does.
+        let mut mbr = vec![0u8; 4096];
+        mbr[446 + 4] = 0x83;
+        mbr[446 + 8] = 1;
+        mbr[446 + 12] = 4;
+        mbr[510] = 0x55;
+        mbr[511] = 0xAA;
+        let borrowed = parse_image_as(&mbr, Format::Mbr).unwrap();
+        let streamed = parse_as(&mut Cursor::new(&mbr), Format::Mbr).unwrap();
+        assert_eq!(format!("{borrowed:?}"), format!("{streamed:?}"));
+        assert_eq!(borrowed.children.len(), 1);
+    }
+
+    #[cfg(feature = "mbr")]
+    #[test]
+    fn empty_mbr_is_low_confidence() {
+        let mut img = vec![0u8; 512];
+        img[510] = 0x55;
+        img[511] = 0xAA;
+        let found = probe(&mut Cursor::new(img)).unwrap();
+        assert_eq!(
+            found,
+            vec![Detection {
+                format: Format::Mbr,
+                offset: 0,
+                confidence: Confidence::Low,
+            }]
+        );
+    }
+}
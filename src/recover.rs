@@ -0,0 +1,223 @@
+//! Lenient parsing of damaged images.
+//!
+//! By default a parser stops at the first structure it cannot decode —
+//! a directory extent that reads short, a File Entry with a bad tag, an
+//! MFT record whose fixups do not match — or, in a few places, quietly
+//! leaves it out. A lenient parse instead skips the damaged structure,
+//! keeps everything it can still reach, and returns the tree together
+//! with a [`Warning`] for each thing it skipped or worked around.
+//!
+//! [`parse_as_lenient`](crate::probe::parse_as_lenient),
+//! [`open_any_lenient`](crate::probe::open_any_lenient),
+//! [`detect_and_parse_filesystem_lenient`](crate::detect_and_parse_filesystem_lenient)
+//! and [`detect_and_parse_image_lenient`](crate::detect_and_parse_image_lenient)
+//! run a lenient parse. The
+//! [`ParseOptions`](crate::limits::ParseOptions) caps still apply: a
+//! parse that goes over one fails as it would otherwise. A directory
+//! that contains itself is left empty with a warning instead of failing
+//! the parse. What the volume header or superblock says is still
+//! trusted, so an image whose top-level structures are unreadable fails
+//! in lenient mode too.
+//!
+//! ```no_run
+//! use isomage::limits::ParseOptions;
+//!
+//! let mut stick = std::fs::File::open("usb.img")?;
+//! let (parsed, found) = isomage::probe::open_any_lenient(&mut stick, &ParseOptions::default())?;
+//! println!("{}: {} entries at the root", found.format, parsed.root.children.len());
+//! for warning in &parsed.warnings {
+//!     eprintln!("skipped: {warning}");
+//! }
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::TreeNode;
+use std::fmt;
+
+/// A damaged structure that a lenient parse skipped or worked around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The format being parsed — for a filesystem inside a partition,
+    /// the filesystem's.
+    pub format: Format,
+    /// Byte offset of the structure in the image, where the parser
+    /// knows it.
+    pub offset: Option<u64>,
+    /// What was damaged, e.g. `"directory record"` or `"MFT record"`.
+    pub structure: &'static str,
+    /// Why it could not be used.
+    pub reason: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.format, self.structure, self.reason)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (at byte {offset})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of a lenient parse: the tree as far as it could be read,
+/// and what was skipped on the way.
+#[derive(Debug, Clone)]
+pub struct Parsed<'a> {
+    /// The root of the recovered tree.
+    pub root: TreeNode<'a>,
+    /// Every structure that was skipped, in the order the parser met
+    /// them. Empty for an undamaged image.
+    pub warnings: Vec<Warning>,
+}
+
+/// Run the parse `f`, leniently if `warnings` is given, storing what it
+/// skipped there when it succeeds. Call within a budget.
+pub(crate) fn collect<T, E>(
+    warnings: Option<&mut Vec<Warning>>,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    match warnings {
+        Some(warnings) => {
+            let (result, skipped) = limits::lenient(f);
+            let value = result?;
+            *warnings = skipped;
+            Ok(value)
+        }
+        None => f(),
+    }
+}
+
+/// Skip the damaged `structure` at `offset` that failed with `err`. A
+/// lenient parse records a warning and goes on; any other stops with
+/// `err`.
+pub(crate) fn skip<E: fmt::Display>(
+    offset: Option<u64>,
+    structure: &'static str,
+    err: E,
+) -> Result<(), E> {
+    if limits::recover(offset, structure, &err) {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+/// Report the damaged `structure` at `offset`, which the parser leaves
+/// out or works around in every mode.
+pub(crate) fn note(offset: Option<u64>, structure: &'static str, reason: impl fmt::Display) {
+    limits::recover(offset, structure, &reason);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::{self, ParseOptions};
+    use std::io;
+
+    fn damaged() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, "bad tag")
+    }
+
+    /// Run `f` as a parse, leniently if `lenient`.
+    fn parse<T>(
+        lenient: bool,
+        f: impl FnOnce() -> io::Result<T>,
+    ) -> (crate::Result<T>, Vec<Warning>) {
+        let mut warnings = Vec::new();
+        let result = limits::scope(Format::Udf, Some(&ParseOptions::default()), || {
+            collect(lenient.then_some(&mut warnings), || {
+                f().map_err(crate::Error::from)
+            })
+        });
+        (result, warnings)
+    }
+
+    #[test]
+    fn warning_display() {
+        let warning = Warning {
+            format: Format::Udf,
+            offset: Some(4096),
+            structure: "file entry",
+            reason: "bad tag".into(),
+        };
+        assert_eq!(
+            warning.to_string(),
+            "UDF file entry: bad tag (at byte 4096)"
+        );
+        let warning = Warning {
+            offset: None,
+            ..warning
+        };
+        assert_eq!(warning.to_string(), "UDF file entry: bad tag");
+    }
+
+    #[test]
+    fn skip_fails_a_strict_parse() {
+        let (result, warnings) = parse(false, || skip(Some(7), "file entry", damaged()));
+        assert!(result.is_err());
+        assert!(warnings.is_empty());
+        // Outside any parse there is nothing to recover into.
+        assert!(skip(None, "file entry", damaged()).is_err());
+    }
+
+    #[test]
+    fn lenient_parse_records_and_goes_on() {
+        let (result, warnings) = parse(true, || {
+            skip(Some(7), "file entry", damaged())?;
+            note(None, "directory", "truncated");
+            Ok(1)
+        });
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].offset, Some(7));
+        assert_eq!(warnings[0].reason, "bad tag");
+        assert_eq!(warnings[1].structure, "directory");
+    }
+
+    #[test]
+    fn lenient_parse_drops_loops_but_not_limits() {
+        let (result, warnings) = parse(true, || {
+            let _root = limits::enter(1)?;
+            if let Err(e) = limits::enter(1) {
+                skip(Some(1), "directory", e)?;
+            }
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert!(
+            warnings[0].reason.contains("directory loop"),
+            "{warnings:?}"
+        );
+
+        let mut warnings = Vec::new();
+        let options = ParseOptions::new().max_entries(0);
+        let result = limits::scope(Format::Udf, Some(&options), || {
+            collect(Some(&mut warnings), || {
+                if let Err(e) = limits::entry("a") {
+                    skip(None, "file entry", e)?;
+                }
+                Ok::<_, crate::Error>(())
+            })
+        });
+        assert!(matches!(result, Err(crate::Error::LimitExceeded { .. })));
+    }
+
+    #[test]
+    fn failed_attempts_leave_no_warnings() {
+        let (result, warnings) = parse(true, || {
+            let _ = limits::attempt(|| {
+                note(None, "superblock", "not this format");
+                Err::<(), _>(damaged())
+            });
+            limits::attempt(|| {
+                note(None, "inode", "kept");
+                Ok(())
+            })
+        });
+        assert!(result.is_ok());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].reason, "kept");
+    }
+}
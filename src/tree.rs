@@ -3,76 +3,463 @@
 //!
 //! Every parser produces a [`TreeNode`] tree rooted at `"/"`. Files
 //! carry a `(file_location, file_length)` byte-range pointing into the
-//! original image; the bytes themselves are not loaded until
+//! original image — or, for fragmented and sparse files, an ordered
+//! list of [`Extent`]s; the bytes themselves are not loaded until
 //! [`crate::cat_node`] or [`crate::extract_node`] asks for them.
+//!
+//! Each node also carries a format-neutral [`Metadata`] record —
+//! timestamps, POSIX permissions, ownership, [`EntryKind`] and symlink
+//! target — filled in from whatever the on-disk format records.
+//!
+//! Names are `Cow<'a, str>`. Trees parsed from a `Read + Seek` stream
+//! own every name and are `TreeNode<'static>`; trees parsed from an
+//! in-memory [`RandomAccess`](crate::image_io::RandomAccess) image by
+//! [`detect_and_parse_image`](crate::detect_and_parse_image) borrow
+//! names straight out of the image where the on-disk encoding is
+//! already UTF-8, and live as long as the image does.
+
+use crate::walk::Walk;
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One run of a file's bytes, in file order.
+///
+/// A data extent points at `length` bytes starting at `location` in the
+/// original image. A hole (`location == None`) is a sparse run that reads
+/// back as `length` zero bytes and occupies no space in the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    /// Byte offset of the run inside the image, or `None` for a hole.
+    pub location: Option<u64>,
+    /// Length of the run in bytes.
+    pub length: u64,
+}
+
+impl Extent {
+    /// A run of `length` bytes stored at `location` in the image.
+    pub fn data(location: u64, length: u64) -> Self {
+        Self {
+            location: Some(location),
+            length,
+        }
+    }
+
+    /// A sparse run of `length` bytes that reads as zeros.
+    pub fn hole(length: u64) -> Self {
+        Self {
+            location: None,
+            length,
+        }
+    }
+
+    /// `true` for sparse runs.
+    pub fn is_hole(&self) -> bool {
+        self.location.is_none()
+    }
+}
+
+/// What kind of filesystem object a node represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EntryKind {
+    /// A regular file.
+    #[default]
+    File,
+    /// A directory.
+    Directory,
+    /// A symbolic link; the target is in [`Metadata::link_target`].
+    Symlink,
+    /// An archive entry that is a hard link to another entry; the
+    /// target path is in [`Metadata::link_target`].
+    Hardlink,
+    /// A character device.
+    CharDevice,
+    /// A block device.
+    BlockDevice,
+    /// A named pipe.
+    Fifo,
+    /// A Unix domain socket.
+    Socket,
+}
+
+impl EntryKind {
+    /// Map the file-type bits (`S_IFMT`) of a POSIX `st_mode` value.
+    /// Unknown types map to [`EntryKind::File`].
+    pub fn from_posix_mode(mode: u32) -> Self {
+        match mode & 0o170000 {
+            0o040000 => Self::Directory,
+            0o120000 => Self::Symlink,
+            0o020000 => Self::CharDevice,
+            0o060000 => Self::BlockDevice,
+            0o010000 => Self::Fifo,
+            0o140000 => Self::Socket,
+            _ => Self::File,
+        }
+    }
+}
+
+/// A point in time, as seconds and nanoseconds relative to the Unix
+/// epoch (UTC). `secs` is negative for times before 1970, which FAT,
+/// ISO 9660, HFS+ and NTFS can all express.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    /// Whole seconds since 1970-01-01T00:00:00Z.
+    pub secs: i64,
+    /// Sub-second part, `0..1_000_000_000`.
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    /// Build a timestamp from Unix seconds and nanoseconds.
+    pub fn new(secs: i64, nanos: u32) -> Self {
+        Self { secs, nanos }
+    }
+
+    /// Build a timestamp from a UTC calendar date and time. Returns
+    /// `None` if any field is out of range, including a day past the end
+    /// of its month.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use isomage::tree::Timestamp;
+    /// let t = Timestamp::from_civil(2000, 1, 1, 0, 0, 0).unwrap();
+    /// assert_eq!(t.secs, 946_684_800);
+    ///
+    /// let leap_day = Timestamp::from_civil(2024, 2, 29, 0, 0, 0).unwrap();
+    /// assert_eq!(leap_day.secs, 1_709_164_800);
+    /// assert!(Timestamp::from_civil(2000, 2, 29, 0, 0, 0).is_some());
+    /// assert!(Timestamp::from_civil(2023, 2, 29, 0, 0, 0).is_none());
+    /// assert!(Timestamp::from_civil(1900, 2, 29, 0, 0, 0).is_none());
+    /// assert!(Timestamp::from_civil(2024, 2, 30, 0, 0, 0).is_none());
+    /// assert!(Timestamp::from_civil(2024, 4, 31, 0, 0, 0).is_none());
+    /// assert!(Timestamp::from_civil(2024, 12, 31, 0, 0, 0).is_some());
+    /// ```
+    pub fn from_civil(
+        year: i64,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let month_days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if !(1..=month_days).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+        // Days since the epoch (Howard Hinnant's days_from_civil).
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y.rem_euclid(400);
+        let mp = (month as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+        let secs = days * 86_400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64;
+        Some(Self::new(secs, 0))
+    }
+
+    /// Decode an MS-DOS date/time pair (2-second resolution), as used by
+    /// FAT and ZIP. DOS time is local with no zone; it is taken as UTC.
+    /// Returns `None` for a zero date ("not recorded") or invalid fields.
+    pub fn from_dos(date: u16, time: u16) -> Option<Self> {
+        if date == 0 {
+            return None;
+        }
+        Self::from_civil(
+            1980 + (date >> 9) as i64,
+            ((date >> 5) & 0x0F) as u32,
+            (date & 0x1F) as u32,
+            (time >> 11) as u32,
+            ((time >> 5) & 0x3F) as u32,
+            (time & 0x1F) as u32 * 2,
+        )
+    }
+
+    /// Convert a Windows `FILETIME` (100 ns ticks since 1601-01-01 UTC),
+    /// as used by NTFS and WIM.
+    pub fn from_windows_filetime(ticks: u64) -> Self {
+        const EPOCH_DIFF_SECS: i64 = 11_644_473_600;
+        let secs = (ticks / 10_000_000) as i64 - EPOCH_DIFF_SECS;
+        let nanos = (ticks % 10_000_000) as u32 * 100;
+        Self::new(secs, nanos)
+    }
+
+    /// Shift a local time recorded with a UTC offset (in minutes east of
+    /// Greenwich) to UTC.
+    pub fn with_utc_offset(self, offset_minutes: i32) -> Self {
+        Self::new(self.secs - offset_minutes as i64 * 60, self.nanos)
+    }
+
+    /// Convert to a [`SystemTime`], e.g. for restoring file times.
+    pub fn to_system_time(self) -> SystemTime {
+        let nanos = Duration::from_nanos(self.nanos as u64);
+        if self.secs >= 0 {
+            UNIX_EPOCH + Duration::from_secs(self.secs as u64) + nanos
+        } else {
+            UNIX_EPOCH - Duration::from_secs(self.secs.unsigned_abs()) + nanos
+        }
+    }
+}
+
+/// Format-neutral per-entry metadata.
+///
+/// Every field the source format does not record is `None`; parsers
+/// never invent values. `kind` always agrees with
+/// [`TreeNode::is_directory`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// What the entry is.
+    pub kind: EntryKind,
+    /// Permission bits (`0o7777`: rwx for user/group/other plus
+    /// setuid, setgid and sticky). The file-type bits live in `kind`.
+    pub mode: Option<u32>,
+    /// Owning user ID.
+    pub uid: Option<u32>,
+    /// Owning group ID.
+    pub gid: Option<u32>,
+    /// Last content modification.
+    pub mtime: Option<Timestamp>,
+    /// Last access.
+    pub atime: Option<Timestamp>,
+    /// Last status (inode / metadata) change.
+    pub ctime: Option<Timestamp>,
+    /// Creation ("birth") time.
+    pub btime: Option<Timestamp>,
+    /// Target of a symlink or hard link, as stored on disk.
+    pub link_target: Option<String>,
+    /// Number of hard links to the entry.
+    pub nlink: Option<u64>,
+    /// `(major, minor)` device number of a character or block device.
+    pub device: Option<(u32, u32)>,
+}
+
+impl Metadata {
+    /// Metadata with only `kind` set.
+    pub fn new(kind: EntryKind) -> Self {
+        Self {
+            kind,
+            ..Self::default()
+        }
+    }
+}
 
 /// One entry in a parsed disc: either a directory (with `children`) or a
 /// file (with `file_location` and `file_length` pointing into the image).
 ///
+/// A file's data is described one of two ways. Files stored as a single
+/// contiguous run carry `file_location`; fragmented or sparse files leave
+/// `file_location` at `None` and list their runs in `extents` instead.
+/// [`TreeNode::data_extents`] resolves either form.
+///
 /// The root of the tree is always a directory named `"/"`. Sizes for
 /// directories are populated by [`TreeNode::calculate_directory_size`]
 /// after the tree is built — until then a directory's `size` is `0`.
 #[derive(Debug, Clone)]
-pub struct TreeNode {
+pub struct TreeNode<'a> {
     /// Last component of the entry's path. The root is named `"/"`.
-    pub name: String,
+    /// Borrowed from the image when the parser could; see the
+    /// [module docs](self).
+    pub name: Cow<'a, str>,
     /// File length in bytes for files; total of all descendants for
     /// directories (after [`calculate_directory_size`](Self::calculate_directory_size)).
     pub size: u64,
     /// `true` for directories, `false` for regular files.
     pub is_directory: bool,
     /// Direct children. Empty for files.
-    pub children: Vec<TreeNode>,
+    pub children: Vec<TreeNode<'a>>,
     /// Byte offset of the file's data inside the original image, if known.
     pub file_location: Option<u64>,
     /// File length in bytes, if known. Equal to `size` for files.
     pub file_length: Option<u64>,
+    /// Ordered data runs for files that are not one contiguous range.
+    /// Empty when `file_location` alone describes the data.
+    pub extents: Vec<Extent>,
+    /// Timestamps, permissions, ownership, kind and link target.
+    pub metadata: Metadata,
 }
 
-impl TreeNode {
+impl<'a> TreeNode<'a> {
     /// Construct a file node without a location. Useful for tests or
     /// for parsers that resolve the location in a later pass.
-    pub fn new_file(name: String, size: u64) -> Self {
+    pub fn new_file(name: impl Into<Cow<'a, str>>, size: u64) -> Self {
         Self {
-            name,
+            name: name.into(),
             size,
             is_directory: false,
             children: Vec::new(),
             file_location: None,
             file_length: None,
+            extents: Vec::new(),
+            metadata: Metadata::new(EntryKind::File),
         }
     }
 
     /// Construct a file node with both its byte-range location and its
     /// length stamped in. This is the constructor parsers should
     /// generally use for real files.
-    pub fn new_file_with_location(name: String, size: u64, location: u64, length: u64) -> Self {
+    pub fn new_file_with_location(
+        name: impl Into<Cow<'a, str>>,
+        size: u64,
+        location: u64,
+        length: u64,
+    ) -> Self {
         Self {
-            name,
+            name: name.into(),
             size,
             is_directory: false,
             children: Vec::new(),
             file_location: Some(location),
             file_length: Some(length),
+            extents: Vec::new(),
+            metadata: Metadata::new(EntryKind::File),
+        }
+    }
+
+    /// Construct a file node whose data is spread over several runs.
+    ///
+    /// `extents` are given in file order and may include holes. Adjacent
+    /// runs are merged and the list is trimmed to `size` bytes, so parsers
+    /// can pass whole clusters or blocks. When everything collapses into a
+    /// single data run the node is identical to one built with
+    /// [`new_file_with_location`](Self::new_file_with_location).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use isomage::tree::{Extent, TreeNode};
+    /// let node = TreeNode::new_file_with_extents(
+    ///     "f.bin".to_string(),
+    ///     6000,
+    ///     vec![Extent::data(8192, 4096), Extent::hole(4096)],
+    /// );
+    /// assert_eq!(node.file_location, None);
+    /// assert_eq!(node.extents, vec![Extent::data(8192, 4096), Extent::hole(1904)]);
+    /// ```
+    pub fn new_file_with_extents(
+        name: impl Into<Cow<'a, str>>,
+        size: u64,
+        extents: Vec<Extent>,
+    ) -> Self {
+        let name = name.into();
+        let mut runs: Vec<Extent> = Vec::with_capacity(extents.len());
+        let mut remaining = size;
+        for ext in extents {
+            if remaining == 0 {
+                break;
+            }
+            let length = ext.length.min(remaining);
+            if length == 0 {
+                continue;
+            }
+            remaining -= length;
+            if let Some(last) = runs.last_mut() {
+                let adjacent = match (last.location, ext.location) {
+                    (Some(a), Some(b)) => a.checked_add(last.length) == Some(b),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if adjacent {
+                    last.length += length;
+                    continue;
+                }
+            }
+            runs.push(Extent {
+                location: ext.location,
+                length,
+            });
+        }
+
+        match runs.as_slice() {
+            [Extent {
+                location: Some(location),
+                length,
+            }] if *length == size => Self::new_file_with_location(name, size, *location, size),
+            _ => Self {
+                name,
+                size,
+                is_directory: false,
+                children: Vec::new(),
+                file_location: None,
+                file_length: Some(size),
+                extents: runs,
+                metadata: Metadata::new(EntryKind::File),
+            },
         }
     }
 
     /// Construct an empty directory node. `size` is `0` until
     /// [`calculate_directory_size`](Self::calculate_directory_size) is
     /// called.
-    pub fn new_directory(name: String) -> Self {
+    pub fn new_directory(name: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            name,
+            name: name.into(),
             size: 0,
             is_directory: true,
             children: Vec::new(),
             file_location: None,
             file_length: None,
+            extents: Vec::new(),
+            metadata: Metadata::new(EntryKind::Directory),
+        }
+    }
+
+    /// Replace this node's metadata, builder-style. Parsers use this to
+    /// attach what they decoded from the on-disk entry.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// The file's data runs, in file order, or `None` if the parser could
+    /// not locate the data.
+    ///
+    /// Single-range files yield one extent built from `file_location` /
+    /// `file_length`; multi-extent files borrow `extents` directly.
+    pub fn data_extents(&self) -> Option<Cow<'_, [Extent]>> {
+        if self.is_directory {
+            return None;
+        }
+        if !self.extents.is_empty() {
+            return Some(Cow::Borrowed(&self.extents));
+        }
+        match (self.file_location, self.file_length) {
+            (Some(location), Some(length)) => {
+                Some(Cow::Owned(vec![Extent::data(location, length)]))
+            }
+            // An empty file has no runs but is still readable.
+            (None, Some(0)) => Some(Cow::Borrowed(&[])),
+            _ => None,
+        }
+    }
+
+    /// Copy every borrowed name, detaching the tree from the image it
+    /// was parsed from.
+    pub fn into_owned(self) -> TreeNode<'static> {
+        TreeNode {
+            name: Cow::Owned(self.name.into_owned()),
+            size: self.size,
+            is_directory: self.is_directory,
+            children: self
+                .children
+                .into_iter()
+                .map(TreeNode::into_owned)
+                .collect(),
+            file_location: self.file_location,
+            file_length: self.file_length,
+            extents: self.extents,
+            metadata: self.metadata,
         }
     }
 
     /// Append a child to this directory. Order is preserved.
-    pub fn add_child(&mut self, child: TreeNode) {
+    pub fn add_child(&mut self, child: TreeNode<'a>) {
         self.children.push(child);
     }
 
@@ -92,6 +479,26 @@ impl TreeNode {
         }
     }
 
+    /// Add `delta` to every byte offset in the subtree: `file_location`
+    /// and the location of each non-hole extent.
+    ///
+    /// Used when a tree was parsed from a [`Window`](crate::image_io::Window)
+    /// onto part of a larger image, so that offsets point into the outer
+    /// image again.
+    pub fn shift_locations(&mut self, delta: u64) {
+        if let Some(location) = self.file_location.as_mut() {
+            *location = location.saturating_add(delta);
+        }
+        for ext in &mut self.extents {
+            if let Some(location) = ext.location.as_mut() {
+                *location = location.saturating_add(delta);
+            }
+        }
+        for child in &mut self.children {
+            child.shift_locations(delta);
+        }
+    }
+
     /// Look up a node by slash-separated path relative to this node.
     /// Leading slashes are tolerated, so `find_node("/etc/hostname")`
     /// and `find_node("etc/hostname")` are equivalent.
@@ -112,7 +519,7 @@ impl TreeNode {
     /// assert!(root.find_node("/etc/hostname").is_some());
     /// assert!(root.find_node("etc/missing").is_none());
     /// ```
-    pub fn find_node(&self, path: &str) -> Option<&TreeNode> {
+    pub fn find_node(&self, path: &str) -> Option<&TreeNode<'a>> {
         let path = path.trim_start_matches('/');
         if path.is_empty() {
             return Some(self);
@@ -138,4 +545,45 @@ impl TreeNode {
 
         None
     }
+
+    /// Like [`find_node`](Self::find_node), but a path segment that has
+    /// no exact match matches a child whose name differs only in case.
+    /// FAT, NTFS and HFS+ resolve names this way; an exact match is
+    /// still preferred where a case-sensitive image has both.
+    ///
+    /// ```
+    /// use isomage::TreeNode;
+    /// let mut root = TreeNode::new_directory("/");
+    /// let mut efi = TreeNode::new_directory("EFI");
+    /// efi.add_child(TreeNode::new_file("BOOTX64.EFI", 1024));
+    /// root.add_child(efi);
+    ///
+    /// assert!(root.find_node("efi/bootx64.efi").is_none());
+    /// assert!(root.find_node_ignore_case("efi/bootx64.efi").is_some());
+    /// ```
+    pub fn find_node_ignore_case(&self, path: &str) -> Option<&TreeNode<'a>> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |node, segment| {
+                node.children
+                    .iter()
+                    .find(|child| child.name == segment)
+                    .or_else(|| {
+                        node.children
+                            .iter()
+                            .find(|child| crate::walk::eq_ignore_case(&child.name, segment))
+                    })
+            })
+    }
+
+    /// Every node below this one, depth-first and in child order, with
+    /// its path. See [`walk`](crate::walk) for filtering.
+    pub fn walk(&self) -> Walk<'_, 'a> {
+        Walk::new(self, false)
+    }
+
+    /// Every node below this one, level by level.
+    pub fn walk_breadth_first(&self) -> Walk<'_, 'a> {
+        Walk::new(self, true)
+    }
 }
@@ -1,14 +1,25 @@
 //! UDF (ECMA-167) parser. Supports metadata partitions and multi-extent
 //! files — enough for typical CD/DVD/Blu-ray media.
 //!
-//! The entry points are [`parse_udf`] and [`parse_udf_verbose`]. Both
-//! return a [`crate::TreeNode`] tree rooted at `"/"` on success.
-
-use crate::tree::TreeNode;
-use crate::Result;
+//! The entry points are [`parse_udf`], [`parse_udf_verbose`] and
+//! [`parse_udf_observed`]. All three return a [`crate::TreeNode`] tree
+//! rooted at `"/"` on success. [`parse_udf_image`] parses an image that
+//! is already in memory, borrowing names out of it.
+
+use crate::filesystem::{not_a_directory, DirEntry, Filesystem};
+use crate::image_io::source::{Borrowed, Bytes, Source, Stream};
+use crate::image_io::RandomAccess;
+use crate::limits;
+use crate::observe::{self, Observer, StderrObserver};
+use crate::probe::Format;
+use crate::recover;
+use crate::tree::{EntryKind, Extent, Metadata, Timestamp, TreeNode};
+use crate::volume::UdfVolume;
+use crate::{Error, Result};
 // `File` is no longer mentioned by the parser; entry points are
 // generic over `R: Read + Seek` as of v3.0.
-use std::io::{Read, Seek, SeekFrom};
+use std::borrow::Cow;
+use std::io::{Read, Seek};
 
 const SECTOR_SIZE: u64 = 2048;
 
@@ -38,13 +49,23 @@ struct MetadataPartitionInfo {
     partition_ref: u16,
 }
 
+/// One allocation descriptor from a File Entry. `recorded` is `false` for
+/// extent types 1 and 2 (allocated-or-not but never written), which read
+/// back as zeros.
+#[derive(Debug, Clone, Copy)]
+struct AllocExtent {
+    length: u32,
+    location: u32,
+    recorded: bool,
+}
+
 /// Represents a file's allocation — possibly spanning multiple extents.
 #[derive(Debug, Clone)]
-struct FileAllocation {
-    extents: Vec<ExtentAd>,
+struct FileAllocation<'a> {
+    extents: Vec<AllocExtent>,
     total_length: u64,
     /// For inline data (ad_type 3), the raw data is stored here.
-    inline_data: Option<Vec<u8>>,
+    inline_data: Option<Cow<'a, [u8]>>,
 }
 
 fn read_extent_ad(buffer: &[u8]) -> ExtentAd {
@@ -67,7 +88,7 @@ fn read_long_ad(buffer: &[u8]) -> LongAd {
 /// Equivalent to `parse_udf_verbose(file, false)`. Errors out cleanly
 /// (returns `Err`, never panics) on images whose anchor or partition
 /// descriptors don't validate.
-pub fn parse_udf<R: Read + Seek>(file: &mut R) -> Result<TreeNode> {
+pub fn parse_udf<R: Read + Seek>(file: &mut R) -> Result<TreeNode<'static>> {
     parse_udf_verbose(file, false)
 }
 
@@ -78,47 +99,123 @@ pub fn parse_udf<R: Read + Seek>(file: &mut R) -> Result<TreeNode> {
 /// `&mut File`, so consumers can feed it an `MmapImage`, a
 /// `Cursor<Vec<u8>>`, or any other byte-source that implements
 /// both traits.
-pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<TreeNode> {
-    // Check for UDF markers in the Volume Recognition Sequence (sectors 16-31)
-    let mut found_udf_marker = false;
+pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<TreeNode<'static>> {
     if verbose {
-        eprintln!("Scanning sectors 16-31 for UDF Volume Recognition Sequence...");
+        parse_udf_observed(file, &mut StderrObserver)
+    } else {
+        parse_udf_observed(file, &mut ())
     }
+}
+
+/// Like [`parse_udf`], but sends spec-section-tagged
+/// [`Diagnostic`](crate::observe::Diagnostic) events to `observer`.
+pub fn parse_udf_observed<R: Read + Seek>(
+    file: &mut R,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'static>> {
+    limits::metered(Format::Udf, None, file, |file| {
+        parse(&mut Stream(file), observer)
+    })
+}
+
+/// Parse a UDF image that is already in memory or mapped.
+///
+/// 8-bit (CS0 compression ID 8) names that are valid UTF-8 borrow from
+/// `image` instead of being copied when their directory is recorded in
+/// one extent; 16-bit names are always owned. Call
+/// [`TreeNode::into_owned`] to detach the tree from the image.
+///
+/// ```no_run
+/// let bytes = std::fs::read("disc.iso")?;
+/// let root = isomage::udf::parse_udf_image(&bytes)?;
+/// println!("{} entries", root.children.len());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_udf_image<R: RandomAccess + ?Sized>(image: &R) -> Result<TreeNode<'_>> {
+    parse_udf_image_observed(image, &mut ())
+}
+
+/// Like [`parse_udf_image`], but sends diagnostics to `observer`.
+pub fn parse_udf_image_observed<'a, R: RandomAccess + ?Sized>(
+    image: &'a R,
+    observer: &mut dyn Observer,
+) -> Result<TreeNode<'a>> {
+    limits::scope(Format::Udf, None, || parse(&mut Borrowed(image), observer))
+}
+
+fn parse<'a, S: Source<'a>>(src: &mut S, observer: &mut dyn Observer) -> Result<TreeNode<'a>> {
+    let (partition_start, root_icb_long_ad) = locate_root(src, observer)?;
+
+    let mut root_node = TreeNode::new_directory("/");
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 4/14.1",
+        format_args!("Parsing root directory..."),
+    );
+    parse_directory(
+        src,
+        partition_start,
+        &root_icb_long_ad,
+        &mut root_node,
+        observer,
+    )?;
+
+    root_node.calculate_directory_size();
+    Ok(root_node)
+}
+
+/// Validate the Volume Recognition Sequence and follow the anchor to the
+/// Main Volume Descriptor Sequence.
+fn main_vds<'a, S: Source<'a>>(src: &mut S, observer: &mut dyn Observer) -> Result<ExtentAd> {
+    // Check for UDF markers in the Volume Recognition Sequence (sectors 16-31)
+    let mut found_udf_marker = false;
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 2/9.1",
+        format_args!("Scanning sectors 16-31 for UDF Volume Recognition Sequence..."),
+    );
     for sector in 16..32 {
-        if file.seek(SeekFrom::Start(sector * SECTOR_SIZE)).is_err() {
+        let Ok(buffer) = src.bytes(sector * SECTOR_SIZE, 16) else {
             continue;
-        }
-        let mut buffer = [0u8; 16];
-        if file.read_exact(&mut buffer).is_err() {
-            continue;
-        }
+        };
 
         let id = &buffer[1..6];
         if id == b"NSR02" || id == b"NSR03" || id == b"BEA01" || id == b"TEA01" {
-            if verbose {
-                eprintln!(
-                    "  Found UDF marker '{:?}' at sector {}",
+            observe::info(
+                observer,
+                Format::Udf,
+                "ECMA-167 2/9.1",
+                format_args!(
+                    "Found UDF marker '{:?}' at sector {}",
                     String::from_utf8_lossy(id),
                     sector
-                );
-            }
+                ),
+            );
             found_udf_marker = true;
             break;
         }
     }
 
     if !found_udf_marker {
-        return Err("Not a valid UDF filesystem (no VRS markers found)".into());
+        return Err(Error::not_recognized(
+            Format::Udf,
+            "Not a valid UDF filesystem (no VRS markers found)",
+        ));
     }
 
     // Try to find the Anchor Volume Descriptor Pointer (AVDP).
     // ECMA-167 §8.4.2 mandates sector 256, and also sector N and N-256 for
     // multi-session discs.  Compact images (e.g. hdiutil on macOS) sometimes
     // place the AVDP earlier, so we scan a short candidate list.
-    if verbose {
-        eprintln!("Looking for Anchor Volume Descriptor Pointer...");
-    }
-    let image_size = file.seek(SeekFrom::End(0)).unwrap_or(0);
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 3/10.2",
+        format_args!("Looking for Anchor Volume Descriptor Pointer..."),
+    );
+    let image_size = src.len().unwrap_or(0);
     let last_sector = image_size / SECTOR_SIZE;
     // Candidates: standard position 256, then last, last-256, and a compact
     // fallback scan for images smaller than 256 sectors.
@@ -136,38 +233,110 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
         }
     }
 
-    let mut avdp_buffer = [0u8; 512];
-    let mut found_avdp = false;
+    let mut found_avdp = None;
     for candidate in &candidates {
-        if file.seek(SeekFrom::Start(candidate * SECTOR_SIZE)).is_err() {
+        let Ok(avdp_buffer) = src.bytes(candidate * SECTOR_SIZE, 512) else {
             continue;
-        }
-        if file.read_exact(&mut avdp_buffer).is_err() {
-            continue;
-        }
+        };
         let tag_id = u16::from_le_bytes([avdp_buffer[0], avdp_buffer[1]]);
         if tag_id == 2 {
-            if verbose {
-                eprintln!("  Found AVDP at sector {}", candidate);
-            }
-            found_avdp = true;
+            observe::info(
+                observer,
+                Format::Udf,
+                "ECMA-167 3/10.2",
+                format_args!("Found AVDP at sector {}", candidate),
+            );
+            found_avdp = Some(avdp_buffer);
             break;
         }
     }
-    if !found_avdp {
-        if verbose {
-            eprintln!("  AVDP not found in any candidate sector");
-        }
-        return Err("UDF detected but no Anchor Volume Descriptor Pointer found.".into());
-    }
+    let Some(avdp_buffer) = found_avdp else {
+        observe::info(
+            observer,
+            Format::Udf,
+            "ECMA-167 3/10.2",
+            format_args!("AVDP not found in any candidate sector"),
+        );
+        return Err(Error::corrupt(
+            Format::Udf,
+            "UDF detected but no Anchor Volume Descriptor Pointer found.",
+        ));
+    };
 
     let main_vds_extent = read_extent_ad(&avdp_buffer[16..24]);
-    if verbose {
-        eprintln!(
-            "  Found AVDP. Main VDS at sector {}, length {}",
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 3/10.2",
+        format_args!(
+            "Found AVDP. Main VDS at sector {}, length {}",
             main_vds_extent.location, main_vds_extent.length
-        );
+        ),
+    );
+
+    Ok(main_vds_extent)
+}
+
+/// Describe the logical volume from the Primary and Logical Volume
+/// Descriptors of the Main Volume Descriptor Sequence.
+pub(crate) fn describe_volume<'a, S: Source<'a>>(
+    src: &mut S,
+    observer: &mut dyn Observer,
+) -> Result<UdfVolume> {
+    let extent = main_vds(src, observer)?;
+    let mut sector = extent.location as u64;
+    let end_sector = sector + (extent.length as u64).div_ceil(SECTOR_SIZE);
+
+    let mut pvd = None;
+    let mut lvd = None;
+    while sector < end_sector {
+        let buffer = src.bytes(sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        match u16::from_le_bytes([buffer[0], buffer[1]]) {
+            1 if pvd.is_none() => pvd = Some(buffer),
+            6 if lvd.is_none() => lvd = Some(buffer),
+            8 => break,
+            _ => {}
+        }
+        sector += 1;
     }
+    let Some(lvd) = lvd else {
+        return Err(Error::corrupt(
+            Format::Udf,
+            "no Logical Volume Descriptor in the Main Volume Descriptor Sequence",
+        ));
+    };
+
+    // The domain identifier's suffix starts with the UDF revision
+    // (UDF 2.1.5.3).
+    let domain = parse_regid(&lvd[216..248]);
+    let udf_revision = u16::from_le_bytes([lvd[240], lvd[241]]);
+    let udf_revision =
+        (domain.starts_with("*OSTA UDF") && udf_revision != 0).then_some(udf_revision);
+    Ok(UdfVolume {
+        logical_volume_id: parse_dstring(&lvd[84..212]),
+        volume_id: pvd
+            .as_deref()
+            .map_or_else(String::new, |pvd| parse_dstring(&pvd[24..56])),
+        volume_set_id: pvd
+            .as_deref()
+            .map_or_else(String::new, |pvd| parse_dstring(&pvd[72..200])),
+        recorded: pvd
+            .as_deref()
+            .and_then(|pvd| parse_udf_timestamp(&pvd[376..388])),
+        domain,
+        udf_revision,
+        implementation_id: parse_regid(&lvd[272..304]),
+    })
+}
+
+/// Find the Main Volume Descriptor Sequence, follow it to the partition
+/// and File Set Descriptor, and return the partition's start
+/// sector with the root directory's ICB.
+fn locate_root<'a, S: Source<'a>>(
+    src: &mut S,
+    observer: &mut dyn Observer,
+) -> Result<(u64, LongAd)> {
+    let main_vds_extent = main_vds(src, observer)?;
 
     // Collect partition info and parse LVD
     let mut partitions: Vec<PartitionInfo> = Vec::new();
@@ -177,16 +346,17 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
     let mut sector = main_vds_extent.location as u64;
     let end_sector = sector + (main_vds_extent.length as u64).div_ceil(SECTOR_SIZE);
 
-    if verbose {
-        eprintln!(
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 3/8.4",
+        format_args!(
             "Parsing Main Volume Descriptor Sequence (sectors {} to {})...",
             sector, end_sector
-        );
-    }
+        ),
+    );
     while sector < end_sector {
-        file.seek(SeekFrom::Start(sector * SECTOR_SIZE))?;
-        let mut vds_buffer = vec![0u8; SECTOR_SIZE as usize];
-        file.read_exact(&mut vds_buffer)?;
+        let vds_buffer = src.bytes(sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
 
         let vds_tag_id = u16::from_le_bytes([vds_buffer[0], vds_buffer[1]]);
 
@@ -200,12 +370,15 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                     vds_buffer[190],
                     vds_buffer[191],
                 ]) as u64;
-                if verbose {
-                    eprintln!(
-                        "  Found Partition Descriptor #{}: starts at sector {}",
+                observe::info(
+                    observer,
+                    Format::Udf,
+                    "ECMA-167 3/10.5",
+                    format_args!(
+                        "Found Partition Descriptor #{}: starts at sector {}",
                         part_num, part_start
-                    );
-                }
+                    ),
+                );
                 partitions.push(PartitionInfo {
                     number: part_num,
                     start_sector: part_start,
@@ -215,11 +388,15 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                 // Logical Volume Descriptor
                 // FSD location at offset 248
                 root_fsd_long_ad = Some(read_long_ad(&vds_buffer[248..264]));
-                if verbose {
-                    let ad = root_fsd_long_ad.unwrap();
-                    eprintln!(
-                        "  Found Logical Volume Descriptor. FSD at location {} in partition {}",
-                        ad.location, ad.partition
+                if let Some(ad) = root_fsd_long_ad {
+                    observe::info(
+                        observer,
+                        Format::Udf,
+                        "ECMA-167 3/10.6",
+                        format_args!(
+                            "Found Logical Volume Descriptor. FSD at location {} in partition {}",
+                            ad.location, ad.partition
+                        ),
                     );
                 }
 
@@ -236,12 +413,15 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                     vds_buffer[270],
                     vds_buffer[271],
                 ]);
-                if verbose {
-                    eprintln!(
-                        "    {} partition maps, table length {} bytes",
+                observe::info(
+                    observer,
+                    Format::Udf,
+                    "ECMA-167 3/10.7",
+                    format_args!(
+                        "{} partition maps, table length {} bytes",
                         num_partition_maps, map_table_length
-                    );
-                }
+                    ),
+                );
 
                 // Partition maps start at offset 440
                 let mut map_offset = 440usize;
@@ -255,30 +435,36 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                         break;
                     } // malformed map: avoid infinite loop
 
-                    if verbose {
-                        eprintln!(
-                            "    Partition map {}: type {}, length {}",
+                    observe::info(
+                        observer,
+                        Format::Udf,
+                        "ECMA-167 3/10.7",
+                        format_args!(
+                            "Partition map {}: type {}, length {}",
                             map_idx, map_type, map_length
-                        );
-                    }
+                        ),
+                    );
 
                     if map_type == 2 && map_length >= 64 {
                         let id_string = &vds_buffer[map_offset + 5..map_offset + 28];
 
-                        if verbose {
-                            let id_printable: String = id_string
-                                .iter()
-                                .take_while(|&&b| b != 0)
-                                .map(|&b| {
-                                    if (0x20..0x7f).contains(&b) {
-                                        b as char
-                                    } else {
-                                        '.'
-                                    }
-                                })
-                                .collect();
-                            eprintln!("      Type 2 identifier: '{}'", id_printable);
-                        }
+                        let id_printable: String = id_string
+                            .iter()
+                            .take_while(|&&b| b != 0)
+                            .map(|&b| {
+                                if (0x20..0x7f).contains(&b) {
+                                    b as char
+                                } else {
+                                    '.'
+                                }
+                            })
+                            .collect();
+                        observe::info(
+                            observer,
+                            Format::Udf,
+                            "ECMA-167 3/10.7.3",
+                            format_args!("Type 2 identifier: '{}'", id_printable),
+                        );
 
                         if id_string.starts_with(b"*UDF Metadata Partition") {
                             let meta_part_ref = u16::from_le_bytes([
@@ -291,12 +477,15 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                                 vds_buffer[map_offset + 42],
                                 vds_buffer[map_offset + 43],
                             ]);
-                            if verbose {
-                                eprintln!(
-                                    "      Metadata Partition: file at location {} in partition {}",
+                            observe::info(
+                                observer,
+                                Format::Udf,
+                                "UDF 2.60 2.2.10",
+                                format_args!(
+                                    "Metadata Partition: file at location {} in partition {}",
                                     meta_file_loc, meta_part_ref
-                                );
-                            }
+                                ),
+                            );
                             metadata_partition = Some(MetadataPartitionInfo {
                                 file_location: meta_file_loc,
                                 partition_ref: meta_part_ref,
@@ -308,9 +497,12 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
                 }
             }
             8 => {
-                if verbose {
-                    eprintln!("  Found Terminating Descriptor at sector {}", sector);
-                }
+                observe::info(
+                    observer,
+                    Format::Udf,
+                    "ECMA-167 3/10.9",
+                    format_args!("Found Terminating Descriptor at sector {}", sector),
+                );
                 break;
             }
             _ => {}
@@ -318,50 +510,72 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
         sector += 1;
     }
 
-    let fsd_long_ad =
-        root_fsd_long_ad.ok_or("Failed to find File Set Descriptor location in LVD")?;
+    let fsd_long_ad = root_fsd_long_ad.ok_or_else(|| {
+        Error::corrupt(
+            Format::Udf,
+            "Failed to find File Set Descriptor location in LVD",
+        )
+    })?;
 
     // Find the partition that the FSD references
     let fsd_partition_ref = fsd_long_ad.partition;
 
     // Determine where to read the FSD from
     let (fsd_sector, partition_start) = if let Some(ref meta_info) = metadata_partition {
-        if verbose {
-            eprintln!("FSD is in metadata partition, reading via metadata file...");
-        }
+        observe::info(
+            observer,
+            Format::Udf,
+            "UDF 2.60 2.2.13",
+            format_args!("FSD is in metadata partition, reading via metadata file..."),
+        );
 
         let meta_phys_partition = partitions
             .iter()
             .find(|p| p.number == meta_info.partition_ref)
-            .ok_or("Cannot find physical partition for metadata file")?;
+            .ok_or_else(|| {
+                Error::corrupt(
+                    Format::Udf,
+                    "Cannot find physical partition for metadata file",
+                )
+            })?;
 
         let meta_fe_sector = meta_phys_partition.start_sector + meta_info.file_location as u64;
-        if verbose {
-            eprintln!("  Metadata File Entry at sector {}", meta_fe_sector);
-        }
+        observe::info(
+            observer,
+            Format::Udf,
+            "UDF 2.60 2.2.13",
+            format_args!("Metadata File Entry at sector {}", meta_fe_sector),
+        );
 
-        file.seek(SeekFrom::Start(meta_fe_sector * SECTOR_SIZE))?;
-        let mut meta_fe_buffer = vec![0u8; SECTOR_SIZE as usize];
-        file.read_exact(&mut meta_fe_buffer)?;
+        let meta_fe_buffer = src.bytes(meta_fe_sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
 
         let meta_tag_id = u16::from_le_bytes([meta_fe_buffer[0], meta_fe_buffer[1]]);
-        if verbose {
-            eprintln!("  Metadata FE tag: {}", meta_tag_id);
-        }
+        observe::info(
+            observer,
+            Format::Udf,
+            "UDF 2.60 2.2.13",
+            format_args!("Metadata FE tag: {}", meta_tag_id),
+        );
 
         // Read first extent of metadata file
-        let meta_alloc = get_file_allocation(&meta_fe_buffer)?;
+        let meta_alloc = get_file_allocation(Bytes::of(&meta_fe_buffer))?;
         let first_extent = meta_alloc
             .extents
-            .first()
-            .ok_or("Metadata file has no allocation extents")?;
-
-        if verbose {
-            eprintln!(
-                "  Metadata file extent: location {}, length {}",
+            .iter()
+            .find(|e| e.recorded)
+            .ok_or_else(|| {
+                Error::corrupt(Format::Udf, "Metadata file has no allocation extents")
+            })?;
+
+        observe::info(
+            observer,
+            Format::Udf,
+            "UDF 2.60 2.2.13",
+            format_args!(
+                "Metadata file extent: location {}, length {}",
                 first_extent.location, first_extent.length
-            );
-        }
+            ),
+        );
 
         let metadata_data_sector = meta_phys_partition.start_sector + first_extent.location as u64;
         let fsd_offset_in_metadata = fsd_long_ad.location as u64;
@@ -375,7 +589,7 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
             .iter()
             .find(|p| p.number == fsd_partition_ref)
             .or_else(|| partitions.first())
-            .ok_or("No partition found")?;
+            .ok_or_else(|| Error::corrupt(Format::Udf, "No partition found"))?;
 
         (
             partition.start_sector + fsd_long_ad.location as u64,
@@ -383,79 +597,82 @@ pub fn parse_udf_verbose<R: Read + Seek>(file: &mut R, verbose: bool) -> Result<
         )
     };
 
-    if verbose {
-        eprintln!("Reading File Set Descriptor at sector {}...", fsd_sector);
-    }
-    file.seek(SeekFrom::Start(fsd_sector * SECTOR_SIZE))?;
-    let mut fsd_buffer = [0u8; 512];
-    file.read_exact(&mut fsd_buffer)?;
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 4/14.1",
+        format_args!("Reading File Set Descriptor at sector {}...", fsd_sector),
+    );
+    let mut fsd_buffer = src.bytes(fsd_sector * SECTOR_SIZE, 512)?;
 
     let fsd_tag_id = u16::from_le_bytes([fsd_buffer[0], fsd_buffer[1]]);
     if fsd_tag_id != 256 {
-        if verbose {
-            eprintln!(
-                "  Tag {} at expected FSD location, scanning nearby...",
+        observe::info(
+            observer,
+            Format::Udf,
+            "ECMA-167 4/14.1",
+            format_args!(
+                "Tag {} at expected FSD location, scanning nearby...",
                 fsd_tag_id
-            );
-        }
+            ),
+        );
         let mut found_fsd = false;
         for offset in 1..32 {
-            file.seek(SeekFrom::Start((fsd_sector + offset) * SECTOR_SIZE))?;
-            file.read_exact(&mut fsd_buffer)?;
+            fsd_buffer = src.bytes((fsd_sector + offset) * SECTOR_SIZE, 512)?;
             let tag = u16::from_le_bytes([fsd_buffer[0], fsd_buffer[1]]);
             if tag == 256 {
-                if verbose {
-                    eprintln!(
-                        "  Found FSD at sector {} (offset +{})",
+                observe::info(
+                    observer,
+                    Format::Udf,
+                    "ECMA-167 4/14.1",
+                    format_args!(
+                        "Found FSD at sector {} (offset +{})",
                         fsd_sector + offset,
                         offset
-                    );
-                }
+                    ),
+                );
                 found_fsd = true;
                 break;
             }
         }
         if !found_fsd {
-            return Err(format!(
-                "Invalid File Set Descriptor tag: expected 256, found {}",
-                fsd_tag_id
-            )
-            .into());
+            return Err(Error::corrupt(
+                Format::Udf,
+                format!(
+                    "Invalid File Set Descriptor tag: expected 256, found {}",
+                    fsd_tag_id
+                ),
+            ));
         }
     }
 
     let root_icb_long_ad = read_long_ad(&fsd_buffer[400..416]);
-    if verbose {
-        eprintln!(
-            "  Found FSD. Root ICB at location {} in partition {}",
+    observe::info(
+        observer,
+        Format::Udf,
+        "ECMA-167 4/14.1",
+        format_args!(
+            "Found FSD. Root ICB at location {} in partition {}",
             root_icb_long_ad.location, root_icb_long_ad.partition
-        );
-    }
+        ),
+    );
 
-    let mut root_node = TreeNode::new_directory("/".to_string());
-    if verbose {
-        eprintln!("Parsing root directory...");
-    }
-    parse_directory(
-        file,
-        partition_start,
-        &root_icb_long_ad,
-        &mut root_node,
-        verbose,
-    )?;
-
-    root_node.calculate_directory_size();
-    Ok(root_node)
+    Ok((partition_start, root_icb_long_ad))
 }
 
 /// Parse all allocation descriptors from a File Entry buffer, supporting multi-extent files.
-fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
+fn get_file_allocation<'a>(fe_buffer: Bytes<'a, '_>) -> Result<FileAllocation<'a>> {
     let tag_id = u16::from_le_bytes([fe_buffer[0], fe_buffer[1]]);
 
     let (ad_length_offset, ea_length_offset, ad_data_offset_base) = match tag_id {
         261 => (172, 168, 176usize), // File Entry
         266 => (212, 208, 216usize), // Extended File Entry
-        _ => return Err(format!("Unsupported ICB tag: {}", tag_id).into()),
+        _ => {
+            return Err(Error::unsupported(
+                Format::Udf,
+                format!("Unsupported ICB tag: {}", tag_id),
+            ))
+        }
     };
 
     // ICB tag flags (at offset 20-21 in ICB tag, which starts at offset 16)
@@ -509,10 +726,13 @@ fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
                     break;
                 } // Next extent of allocation descriptors — not yet supported
 
-                // Type 0 = recorded and allocated, Type 1 = allocated but not recorded (sparse)
-                if extent_type == 0 {
-                    extents.push(ExtentAd { length, location });
-                }
+                // Type 0 = recorded and allocated; types 1 and 2 are not
+                // recorded (sparse) and read as zeros.
+                extents.push(AllocExtent {
+                    length,
+                    location,
+                    recorded: extent_type == 0,
+                });
                 total_length += length as u64;
                 pos += 8;
             }
@@ -543,9 +763,11 @@ fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
                     break;
                 }
 
-                if extent_type == 0 {
-                    extents.push(ExtentAd { length, location });
-                }
+                extents.push(AllocExtent {
+                    length,
+                    location,
+                    recorded: extent_type == 0,
+                });
                 total_length += length as u64;
                 pos += 16;
             }
@@ -554,7 +776,7 @@ fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
             // Inline data — embedded directly in the file entry at the AD area
             let end = (ad_offset + ad_length).min(fe_buffer.len());
             if ad_offset < end {
-                inline_data = Some(fe_buffer[ad_offset..end].to_vec());
+                inline_data = Some(fe_buffer.slice(ad_offset..end).to_cow());
                 total_length = (end - ad_offset) as u64;
             }
         }
@@ -564,14 +786,21 @@ fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
                 let ext = read_extent_ad(&fe_buffer[ad_offset..ad_offset + 8]);
                 if ext.length > 0 {
                     total_length = ext.length as u64;
-                    extents.push(ext);
+                    extents.push(AllocExtent {
+                        length: ext.length,
+                        location: ext.location,
+                        recorded: true,
+                    });
                 }
             }
         }
     }
 
     if extents.is_empty() && inline_data.is_none() {
-        return Err("No allocation extents found in file entry".into());
+        return Err(Error::corrupt(
+            Format::Udf,
+            "No allocation extents found in file entry",
+        ));
     }
 
     Ok(FileAllocation {
@@ -581,52 +810,90 @@ fn get_file_allocation(fe_buffer: &[u8]) -> Result<FileAllocation> {
     })
 }
 
-fn parse_directory<R: Read + Seek>(
-    file: &mut R,
+/// One live File Identifier Descriptor: not deleted, not the parent
+/// link, and with a non-empty name.
+struct Fid<'a> {
+    name: Cow<'a, str>,
+    is_directory: bool,
+    icb: LongAd,
+}
+
+/// Read the File Entry at `icb_long_ad` and decode the directory stream
+/// it describes. Returns the entry's raw FE sector with the live FIDs.
+fn read_fids<'a, S: Source<'a>>(
+    src: &mut S,
     partition_start: u64,
     icb_long_ad: &LongAd,
-    parent_node: &mut TreeNode,
-    verbose: bool,
-) -> Result<()> {
+    observer: &mut dyn Observer,
+) -> Result<(Cow<'a, [u8]>, Vec<Fid<'a>>)> {
     // Read the file entry to get allocation info
-    file.seek(SeekFrom::Start(
+    let fe_buffer = src.bytes(
         (partition_start + icb_long_ad.location as u64) * SECTOR_SIZE,
-    ))?;
-    let mut fe_buffer = vec![0u8; SECTOR_SIZE as usize];
-    file.read_exact(&mut fe_buffer)?;
+        SECTOR_SIZE as usize,
+    )?;
 
-    let alloc = get_file_allocation(&fe_buffer)?;
+    let alloc = get_file_allocation(Bytes::of(&fe_buffer))?;
 
-    if verbose {
-        if alloc.inline_data.is_some() {
-            eprintln!("  Directory has inline data, {} bytes", alloc.total_length);
-        } else {
-            eprintln!(
-                "  Directory has {} extent(s), total {} bytes",
+    if alloc.inline_data.is_some() {
+        observe::info(
+            observer,
+            Format::Udf,
+            "ECMA-167 4/14.9",
+            format_args!("Directory has inline data, {} bytes", alloc.total_length),
+        );
+    } else {
+        observe::info(
+            observer,
+            Format::Udf,
+            "ECMA-167 4/14.9",
+            format_args!(
+                "Directory has {} extent(s), total {} bytes",
                 alloc.extents.len(),
                 alloc.total_length
-            );
-        }
+            ),
+        );
     }
 
-    // Read directory data — either inline or from extents
+    // Read directory data — either inline or from extents. Inline data
+    // and a directory recorded in one extent are used as read, so names
+    // can borrow from them.
+    if alloc.inline_data.is_none() {
+        limits::alloc(alloc.total_length)?;
+    }
     let buffer = if let Some(data) = alloc.inline_data {
         data
+    } else if let [extent] = alloc.extents[..] {
+        if extent.recorded {
+            src.bytes(
+                (partition_start + extent.location as u64) * SECTOR_SIZE,
+                extent.length as usize,
+            )?
+        } else {
+            Cow::Owned(vec![0u8; extent.length as usize])
+        }
     } else {
-        let cap = usize::try_from(alloc.total_length)
-            .map_err(|_| format!("directory too large: {} bytes", alloc.total_length))?;
+        let cap = usize::try_from(alloc.total_length).map_err(|_| {
+            Error::corrupt(
+                Format::Udf,
+                format!("directory too large: {} bytes", alloc.total_length),
+            )
+        })?;
         let mut buf = Vec::with_capacity(cap);
         for extent in &alloc.extents {
-            file.seek(SeekFrom::Start(
-                (partition_start + extent.location as u64) * SECTOR_SIZE,
-            ))?;
-            let mut chunk = vec![0u8; extent.length as usize];
-            file.read_exact(&mut chunk)?;
-            buf.extend_from_slice(&chunk);
+            if extent.recorded {
+                buf.extend_from_slice(&src.bytes(
+                    (partition_start + extent.location as u64) * SECTOR_SIZE,
+                    extent.length as usize,
+                )?);
+            } else {
+                buf.resize(buf.len() + extent.length as usize, 0);
+            }
         }
-        buf
+        Cow::Owned(buf)
     };
+    let bytes = Bytes::of(&buffer);
 
+    let mut fids = Vec::new();
     let mut offset = 0;
     while offset < buffer.len() {
         if offset + 40 > buffer.len() {
@@ -641,12 +908,17 @@ fn parse_directory<R: Read + Seek>(
 
         if tag_id != 257 {
             // File Identifier Descriptor
-            if verbose {
-                eprintln!(
-                    "    Warning: Expected FID (257) at offset {}, found {}",
-                    offset, tag_id
-                );
-            }
+            recover::note(
+                None,
+                "file identifier descriptor",
+                format_args!("expected tag 257, found {tag_id} at byte {offset} of the directory"),
+            );
+            observe::warn(
+                observer,
+                Format::Udf,
+                "ECMA-167 4/14.4",
+                format_args!("Expected FID (257) at offset {}, found {}", offset, tag_id),
+            );
             break;
         }
 
@@ -657,19 +929,24 @@ fn parse_directory<R: Read + Seek>(
 
         let name_offset = offset + 38 + length_of_iu;
         if name_offset + length_of_fi > buffer.len() {
-            if verbose {
-                eprintln!(
-                    "    Warning: FID name offset out of bounds at offset {}",
-                    offset
-                );
-            }
+            recover::note(
+                None,
+                "file identifier descriptor",
+                format_args!("name at byte {offset} of the directory runs past its end"),
+            );
+            observe::warn(
+                observer,
+                Format::Udf,
+                "ECMA-167 4/14.4",
+                format_args!("FID name offset out of bounds at offset {}", offset),
+            );
             break;
         }
 
         let name = if length_of_fi == 0 {
-            String::new()
+            Cow::Borrowed("")
         } else {
-            parse_udf_name(&buffer[name_offset..name_offset + length_of_fi])
+            parse_udf_name(bytes.slice(name_offset..name_offset + length_of_fi))
         };
 
         let is_directory = (file_characteristics & 0x02) != 0;
@@ -677,88 +954,421 @@ fn parse_directory<R: Read + Seek>(
         let is_parent = (file_characteristics & 0x08) != 0;
 
         if !is_deleted && !is_parent && !name.is_empty() {
-            if verbose {
-                eprintln!(
-                    "    Found {}: {}",
+            observe::info(
+                observer,
+                Format::Udf,
+                "ECMA-167 4/14.4",
+                format_args!(
+                    "Found {}: {}",
                     if is_directory { "dir" } else { "file" },
                     name
+                ),
+            );
+            fids.push(Fid {
+                name,
+                is_directory,
+                icb,
+            });
+        }
+
+        // FIDs are padded to 4-byte boundaries
+        let fid_length = 38 + length_of_iu + length_of_fi;
+        offset += (fid_length + 3) & !3;
+    }
+
+    Ok((fe_buffer, fids))
+}
+
+fn parse_directory<'a, S: Source<'a>>(
+    src: &mut S,
+    partition_start: u64,
+    icb_long_ad: &LongAd,
+    parent_node: &mut TreeNode<'a>,
+    observer: &mut dyn Observer,
+) -> Result<()> {
+    let _level = limits::enter(partition_start + icb_long_ad.location as u64)?;
+    let (fe_buffer, fids) = read_fids(src, partition_start, icb_long_ad, observer)?;
+    parent_node.metadata = directory_metadata(&fe_buffer);
+    limits::dir_entries(fids.len() as u64)?;
+
+    for fid in fids {
+        limits::entry(&fid.name)?;
+        if fid.is_directory {
+            let mut dir_node = TreeNode::new_directory(fid.name);
+            if let Err(e) = parse_directory(src, partition_start, &fid.icb, &mut dir_node, observer)
+            {
+                let offset = (partition_start + fid.icb.location as u64) * SECTOR_SIZE;
+                recover::note(Some(offset), "directory", &e);
+                observe::warn(
+                    observer,
+                    Format::Udf,
+                    "ECMA-167 4/14.4",
+                    format_args!("Failed to parse subdirectory '{}': {}", dir_node.name, e),
                 );
             }
-            if is_directory {
-                let mut dir_node = TreeNode::new_directory(name);
-                if let Err(e) = parse_directory(file, partition_start, &icb, &mut dir_node, verbose)
-                {
-                    if verbose {
-                        eprintln!("      Warning: Failed to parse subdirectory: {}", e);
-                    }
-                }
-                parent_node.add_child(dir_node);
-            } else {
-                match get_file_info(file, partition_start, &icb) {
-                    Ok(alloc) => {
-                        let file_node = if let Some(first) = alloc.extents.first() {
-                            // Extent-based file: record location for extraction
-                            TreeNode::new_file_with_location(
-                                name,
-                                alloc.total_length,
-                                (partition_start + first.location as u64) * SECTOR_SIZE,
-                                alloc.total_length,
+            parent_node.add_child(dir_node);
+        } else {
+            let file_node = file_node(src, partition_start, fid.name, &fid.icb, observer);
+            parent_node.add_child(file_node);
+        }
+    }
+
+    Ok(())
+}
+
+/// A directory's metadata, from its File Entry.
+fn directory_metadata(fe_buffer: &[u8]) -> Metadata {
+    Metadata {
+        kind: EntryKind::Directory,
+        ..parse_file_entry_metadata(fe_buffer)
+    }
+}
+
+/// Build the node for a non-directory entry. A File Entry that cannot be
+/// decoded yields an empty, location-less file rather than an error.
+fn file_node<'a, S: Source<'a>>(
+    src: &mut S,
+    partition_start: u64,
+    name: Cow<'a, str>,
+    icb: &LongAd,
+    observer: &mut dyn Observer,
+) -> TreeNode<'a> {
+    match get_file_info(src, partition_start, icb) {
+        Ok((alloc, mut metadata)) => {
+            if metadata.kind == EntryKind::Symlink {
+                metadata.link_target = read_small_file(src, partition_start, &alloc)
+                    .ok()
+                    .map(|data| decode_symlink_target(&data));
+            }
+            let file_node = if !alloc.extents.is_empty() {
+                // Extent-based file: record every run for extraction
+                let extents = alloc
+                    .extents
+                    .iter()
+                    .map(|e| {
+                        if e.recorded {
+                            Extent::data(
+                                (partition_start + e.location as u64) * SECTOR_SIZE,
+                                e.length as u64,
                             )
                         } else {
-                            // Inline data (ad_type 3): size known but no extent location
-                            TreeNode::new_file(name, alloc.total_length)
-                        };
-                        parent_node.add_child(file_node);
-                    }
-                    Err(e) => {
-                        if verbose {
-                            eprintln!("      Warning: Failed to get file extent: {}", e);
+                            Extent::hole(e.length as u64)
                         }
-                        let file_node = TreeNode::new_file(name, 0);
-                        parent_node.add_child(file_node);
-                    }
-                }
-            }
+                    })
+                    .collect();
+                TreeNode::new_file_with_extents(name, alloc.total_length, extents)
+            } else {
+                // Inline data (ad_type 3): size known but no extent location
+                TreeNode::new_file(name, alloc.total_length)
+            };
+            file_node.with_metadata(metadata)
+        }
+        Err(e) => {
+            let offset = (partition_start + icb.location as u64) * SECTOR_SIZE;
+            recover::note(Some(offset), "file entry", &e);
+            observe::warn(
+                observer,
+                Format::Udf,
+                "ECMA-167 4/14.9",
+                format_args!("Failed to get file extent for '{}': {}", name, e),
+            );
+            TreeNode::new_file(name, 0)
         }
+    }
+}
 
-        // FIDs are padded to 4-byte boundaries
-        let fid_length = 38 + length_of_iu + length_of_fi;
-        offset += (fid_length + 3) & !3;
+/// A UDF image opened for lazy, per-directory reads.
+///
+/// Locates the partition and File Set Descriptor the way [`parse_udf`]
+/// does, then reads a directory's File Identifier Descriptors only when
+/// it is listed. Handles locate File Entries by their block inside the
+/// partition.
+#[derive(Debug)]
+pub struct UdfFs<R> {
+    image: R,
+    partition_start: u64,
+    root_icb: LongAd,
+}
+
+impl<R: Read + Seek> UdfFs<R> {
+    /// Find the root directory of `image`. No directory is read yet.
+    pub fn open(mut image: R) -> Result<Self> {
+        let (partition_start, root_icb) = locate_root(&mut Stream(&mut image), &mut ())?;
+        Ok(Self {
+            image,
+            partition_start,
+            root_icb,
+        })
     }
 
-    Ok(())
+    /// Give back the image.
+    pub fn into_inner(self) -> R {
+        self.image
+    }
+
+    fn icb(&self, entry: &DirEntry) -> LongAd {
+        LongAd {
+            length: 0,
+            location: entry.id as u32,
+            partition: self.root_icb.partition,
+        }
+    }
 }
 
-fn get_file_info<R: Read + Seek>(
-    file: &mut R,
+impl<R: Read + Seek> Filesystem for UdfFs<R> {
+    type Image = R;
+
+    fn format(&self) -> Format {
+        Format::Udf
+    }
+
+    fn root(&self) -> DirEntry {
+        DirEntry::new("/".to_string(), true, self.root_icb.location as u64)
+    }
+
+    fn read_dir(&mut self, dir: &DirEntry) -> Result<Vec<DirEntry>> {
+        if !dir.is_directory {
+            return Err(not_a_directory(dir));
+        }
+        let icb = self.icb(dir);
+        let mut image = Stream(&mut self.image);
+        let (_, fids) = read_fids(&mut image, self.partition_start, &icb, &mut ())?;
+        Ok(fids
+            .into_iter()
+            .map(|fid| {
+                DirEntry::new(
+                    fid.name.into_owned(),
+                    fid.is_directory,
+                    fid.icb.location as u64,
+                )
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, entry: &DirEntry) -> Result<TreeNode<'static>> {
+        let icb = self.icb(entry);
+        let mut image = Stream(&mut self.image);
+        if entry.is_directory {
+            let fe_buffer = image.bytes(
+                (self.partition_start + icb.location as u64) * SECTOR_SIZE,
+                SECTOR_SIZE as usize,
+            )?;
+            Ok(TreeNode::new_directory(entry.name.clone())
+                .with_metadata(directory_metadata(&fe_buffer)))
+        } else {
+            let name = entry.name.clone().into();
+            Ok(file_node(
+                &mut image,
+                self.partition_start,
+                name,
+                &icb,
+                &mut (),
+            ))
+        }
+    }
+
+    fn image(&mut self) -> &mut R {
+        &mut self.image
+    }
+}
+
+fn get_file_info<'a, S: Source<'a>>(
+    src: &mut S,
     partition_start: u64,
     icb_long_ad: &LongAd,
-) -> Result<FileAllocation> {
-    file.seek(SeekFrom::Start(
+) -> Result<(FileAllocation<'a>, Metadata)> {
+    let fe_buffer = src.bytes(
         (partition_start + icb_long_ad.location as u64) * SECTOR_SIZE,
-    ))?;
-    let mut fe_buffer = vec![0u8; SECTOR_SIZE as usize];
-    file.read_exact(&mut fe_buffer)?;
+        SECTOR_SIZE as usize,
+    )?;
+
+    Ok((
+        get_file_allocation(Bytes::of(&fe_buffer))?,
+        parse_file_entry_metadata(&fe_buffer),
+    ))
+}
+
+/// Largest file [`read_small_file`] will load (symlink bodies).
+const MAX_SMALL_FILE: u64 = 64 * 1024;
+
+/// Read the whole content of a small file — inline or extent-based —
+/// into memory. Used for symlink bodies.
+fn read_small_file<'a, S: Source<'a>>(
+    src: &mut S,
+    partition_start: u64,
+    alloc: &FileAllocation,
+) -> Result<Vec<u8>> {
+    if let Some(data) = &alloc.inline_data {
+        return Ok(data.to_vec());
+    }
+    if alloc.total_length > MAX_SMALL_FILE {
+        return Err(Error::corrupt(
+            Format::Udf,
+            format!("file too large to load: {} bytes", alloc.total_length),
+        ));
+    }
+    let mut out = Vec::with_capacity(alloc.total_length as usize);
+    for extent in &alloc.extents {
+        if extent.recorded {
+            out.extend_from_slice(&src.bytes(
+                (partition_start + extent.location as u64) * SECTOR_SIZE,
+                extent.length as usize,
+            )?);
+        } else {
+            out.resize(out.len() + extent.length as usize, 0);
+        }
+    }
+    Ok(out)
+}
+
+/// Decode an ECMA-167 1/7.3 timestamp (12 bytes). Returns `None` for an
+/// all-zero (unrecorded) or out-of-range value.
+fn parse_udf_timestamp(b: &[u8]) -> Option<Timestamp> {
+    let type_and_tz = u16::from_le_bytes([b[0], b[1]]);
+    let year = i16::from_le_bytes([b[2], b[3]]);
+    if year == 0 && b[4] == 0 {
+        return None;
+    }
+    let t = Timestamp::from_civil(
+        year as i64,
+        b[4] as u32,
+        b[5] as u32,
+        b[6] as u32,
+        b[7] as u32,
+        b[8] as u32,
+    )?;
+    // Centiseconds, hundreds of microseconds, microseconds.
+    let nanos = b[9] as u32 * 10_000_000 + b[10] as u32 * 100_000 + b[11] as u32 * 1_000;
+    let nanos = if nanos < 1_000_000_000 { nanos } else { 0 };
+    // Type 1 is local time; the low 12 bits are a signed offset from UTC
+    // in minutes, with -2047 meaning "not specified".
+    let tz = ((type_and_tz & 0x0FFF) << 4) as i16 >> 4;
+    let t = if type_and_tz >> 12 == 1 && tz != -2047 {
+        t.with_utc_offset(tz as i32)
+    } else {
+        t
+    };
+    Some(Timestamp::new(t.secs, nanos))
+}
+
+/// Pull the format-neutral metadata out of a File Entry (tag 261,
+/// ECMA-167 4/14.9) or Extended File Entry (tag 266, 4/14.17).
+fn parse_file_entry_metadata(fe: &[u8]) -> Metadata {
+    let tag_id = u16::from_le_bytes([fe[0], fe[1]]);
+    let (atime, mtime, btime, ctime) = match tag_id {
+        261 => (72, 84, None, 96),
+        266 => (80, 92, Some(104), 116),
+        _ => return Metadata::default(),
+    };
+    let le32 = |at: usize| u32::from_le_bytes([fe[at], fe[at + 1], fe[at + 2], fe[at + 3]]);
+
+    // ICB tag (4/14.6) starts at 16: file type at +11, flags at +18.
+    let kind = match fe[27] {
+        4 => EntryKind::Directory,
+        6 => EntryKind::BlockDevice,
+        7 => EntryKind::CharDevice,
+        9 => EntryKind::Fifo,
+        10 => EntryKind::Socket,
+        12 => EntryKind::Symlink,
+        _ => EntryKind::File,
+    };
+    let icb_flags = u16::from_le_bytes([fe[34], fe[35]]);
+
+    // Permissions (4/14.9.5): other in bits 0-4, group 5-9, owner 10-14,
+    // each with execute=1, write=2, read=4 — the same order as POSIX.
+    let perms = le32(44);
+    let mut mode = (perms & 0x7) | ((perms >> 5) & 0x7) << 3 | ((perms >> 10) & 0x7) << 6;
+    if icb_flags & 0x40 != 0 {
+        mode |= 0o4000;
+    }
+    if icb_flags & 0x80 != 0 {
+        mode |= 0o2000;
+    }
+    if icb_flags & 0x100 != 0 {
+        mode |= 0o1000;
+    }
+
+    // 0xFFFFFFFF means "no owner/group recorded" (4/14.9.3-4).
+    let id = |v: u32| (v != u32::MAX).then_some(v);
+    Metadata {
+        kind,
+        mode: Some(mode),
+        uid: id(le32(36)),
+        gid: id(le32(40)),
+        mtime: parse_udf_timestamp(&fe[mtime..mtime + 12]),
+        atime: parse_udf_timestamp(&fe[atime..atime + 12]),
+        ctime: parse_udf_timestamp(&fe[ctime..ctime + 12]),
+        btime: btime.and_then(|at| parse_udf_timestamp(&fe[at..at + 12])),
+        link_target: None,
+        nlink: None,
+        device: None,
+    }
+}
+
+/// Decode a symbolic link body: a sequence of path components
+/// (ECMA-167 4/14.16.1) joined with `/`.
+fn decode_symlink_target(data: &[u8]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut absolute = false;
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let component_type = data[pos];
+        let end = pos + 4 + data[pos + 1] as usize;
+        if end > data.len() {
+            break;
+        }
+        match component_type {
+            // 1 = implementation-specific root, 2 = root directory.
+            1 | 2 => {
+                absolute = true;
+                parts.clear();
+            }
+            3 => parts.push("..".to_string()),
+            4 => parts.push(".".to_string()),
+            5 => parts.push(parse_udf_name(Bytes::Buffer(&data[pos + 4..end])).into_owned()),
+            _ => {}
+        }
+        pos = end;
+    }
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
+
+/// Decode a fixed-length dstring (ECMA-167 1/7.2.12), whose last byte
+/// is the length of the part in use.
+fn parse_dstring(field: &[u8]) -> String {
+    let used = (*field.last().unwrap_or(&0) as usize).min(field.len() - 1);
+    parse_udf_name(Bytes::Buffer(&field[..used])).into_owned()
+}
 
-    get_file_allocation(&fe_buffer)
+/// The identifier of an entity identifier (ECMA-167 1/7.4), e.g.
+/// `"*OSTA UDF Compliant"`.
+fn parse_regid(field: &[u8]) -> String {
+    String::from_utf8_lossy(&field[1..24])
+        .trim_end_matches(['\0', ' '])
+        .to_string()
 }
 
-fn parse_udf_name(data: &[u8]) -> String {
+fn parse_udf_name<'a>(data: Bytes<'a, '_>) -> Cow<'a, str> {
     if data.is_empty() {
-        return String::new();
+        return Cow::Borrowed("");
     }
 
     let compression_id = data[0];
     if compression_id == 8 {
-        String::from_utf8_lossy(&data[1..]).to_string()
+        data.slice(1..).utf8_lossy()
     } else if compression_id == 16 {
         let utf16_data: Vec<u16> = data[1..]
             .chunks_exact(2)
             .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
             .collect();
-        String::from_utf16_lossy(&utf16_data)
+        Cow::Owned(String::from_utf16_lossy(&utf16_data))
     } else {
-        String::from_utf8_lossy(data).to_string()
+        data.utf8_lossy()
     }
 }
 
@@ -925,6 +1535,63 @@ mod tests {
         buf
     }
 
+    // ── File Entry metadata ───────────────────────────────────────────────────
+
+    /// 12-byte ECMA-167 timestamp, type 1 (local) with `tz` minutes offset.
+    /// `fields` is month, day, hour, minute, second, centiseconds.
+    fn udf_ts(tz: i16, year: i16, fields: [u8; 6]) -> [u8; 12] {
+        let mut b = [0u8; 12];
+        let type_tz = (1u16 << 12) | (tz as u16 & 0x0FFF);
+        b[0..2].copy_from_slice(&type_tz.to_le_bytes());
+        b[2..4].copy_from_slice(&year.to_le_bytes());
+        b[4..10].copy_from_slice(&fields);
+        b
+    }
+
+    #[test]
+    fn udf_timestamp_applies_timezone_and_fraction() {
+        // 2020-06-01 10:00:00.25 at UTC-5 → 15:00:00.25 UTC.
+        let t = parse_udf_timestamp(&udf_ts(-300, 2020, [6, 1, 10, 0, 0, 25])).unwrap();
+        assert_eq!(
+            t.secs,
+            Timestamp::from_civil(2020, 6, 1, 15, 0, 0).unwrap().secs
+        );
+        assert_eq!(t.nanos, 250_000_000);
+        assert!(parse_udf_timestamp(&[0u8; 12]).is_none());
+    }
+
+    #[test]
+    fn file_entry_metadata_reads_owner_mode_and_times() {
+        let mut fe = make_fe_buf(261, 0, &[]);
+        fe[27] = 5; // regular file
+        w16(&mut fe, 34, 0x40); // setuid
+        w32(&mut fe, 36, 1000);
+        w32(&mut fe, 40, u32::MAX); // no group recorded
+        w32(&mut fe, 44, (0x7 << 10) | (0x5 << 5) | 0x4); // rwx r-x r--
+        fe[84..96].copy_from_slice(&udf_ts(0, 2001, [2, 3, 4, 5, 6, 0]));
+        let m = parse_file_entry_metadata(&fe);
+        assert_eq!(m.kind, EntryKind::File);
+        assert_eq!(m.mode, Some(0o4754));
+        assert_eq!(m.uid, Some(1000));
+        assert_eq!(m.gid, None);
+        assert_eq!(m.mtime, Timestamp::from_civil(2001, 2, 3, 4, 5, 6));
+        assert_eq!(m.btime, None);
+
+        fe[27] = 12;
+        assert_eq!(parse_file_entry_metadata(&fe).kind, EntryKind::Symlink);
+    }
+
+    #[test]
+    fn symlink_target_components_decode() {
+        // "/" + "usr" + ".." + "lib"
+        let mut body = vec![2u8, 0, 0, 0];
+        body.extend_from_slice(&[5, 4, 0, 0, 8, b'u', b's', b'r']);
+        body.extend_from_slice(&[3, 0, 0, 0]);
+        body.extend_from_slice(&[5, 4, 0, 0, 8, b'l', b'i', b'b']);
+        assert_eq!(decode_symlink_target(&body), "/usr/../lib");
+        assert_eq!(decode_symlink_target(&body[4..]), "usr/../lib");
+    }
+
     #[test]
     fn file_alloc_short_ad() {
         // One 8-byte short AD: length=1024, location=5
@@ -932,22 +1599,25 @@ mod tests {
         w32(&mut ad, 0, 1024); // raw_length = 1024 (type=0)
         w32(&mut ad, 4, 5); // location = 5
         let buf = make_fe_buf(261, 0, &ad);
-        let alloc = get_file_allocation(&buf).unwrap();
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
         assert_eq!(alloc.total_length, 1024);
         assert_eq!(alloc.extents.len(), 1);
         assert_eq!(alloc.extents[0].location, 5);
     }
 
     #[test]
-    fn file_alloc_short_ad_sparse_skipped() {
-        // Type 1 (allocated but not recorded — sparse): only AD is sparse,
-        // leaving extents empty → get_file_allocation returns Err.
+    fn file_alloc_short_ad_sparse_kept_as_hole() {
+        // Type 1 (allocated but not recorded — sparse): kept as an
+        // unrecorded extent so the file reads back as zeros.
         let mut ad = vec![0u8; 8];
         let raw = (1u32 << 30) | 512; // extent_type=1, length=512
         w32(&mut ad, 0, raw);
         w32(&mut ad, 4, 99);
         let buf = make_fe_buf(261, 0, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
+        assert_eq!(alloc.total_length, 512);
+        assert_eq!(alloc.extents.len(), 1);
+        assert!(!alloc.extents[0].recorded);
     }
 
     #[test]
@@ -958,7 +1628,7 @@ mod tests {
         w32(&mut ad, 4, 10); // location
         w16(&mut ad, 8, 0); // partition
         let buf = make_fe_buf(261, 1, &ad);
-        let alloc = get_file_allocation(&buf).unwrap();
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
         assert_eq!(alloc.total_length, 4096);
         assert_eq!(alloc.extents.len(), 1);
     }
@@ -967,10 +1637,10 @@ mod tests {
     fn file_alloc_inline_data() {
         let content = b"hello inline";
         let buf = make_fe_buf(261, 3, content);
-        let alloc = get_file_allocation(&buf).unwrap();
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
         assert_eq!(alloc.total_length, content.len() as u64);
         assert!(alloc.inline_data.is_some());
-        assert_eq!(alloc.inline_data.unwrap(), content);
+        assert_eq!(alloc.inline_data.unwrap(), &content[..]);
     }
 
     #[test]
@@ -983,14 +1653,14 @@ mod tests {
         w32(&mut buf, 208, 0); // EA length = 0
         w32(&mut buf, 212, content.len() as u32); // AD length
         buf[216..216 + content.len()].copy_from_slice(content);
-        let alloc = get_file_allocation(&buf).unwrap();
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
         assert_eq!(alloc.total_length, content.len() as u64);
     }
 
     #[test]
     fn file_alloc_rejects_unknown_tag() {
         let buf = make_fe_buf(999, 0, &[]);
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     #[test]
@@ -1000,7 +1670,7 @@ mod tests {
         w32(&mut ad, 0, 512); // length=512
         w32(&mut ad, 4, 7); // location=7
         let buf = make_fe_buf(261, 2, &ad);
-        let alloc = get_file_allocation(&buf).unwrap();
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
         assert_eq!(alloc.total_length, 512);
     }
 
@@ -1009,27 +1679,27 @@ mod tests {
     #[test]
     fn udf_name_cs0_8bit() {
         let data = [8u8, b'h', b'i'];
-        assert_eq!(parse_udf_name(&data), "hi");
+        assert_eq!(parse_udf_name(Bytes::Buffer(&data)), "hi");
     }
 
     #[test]
     fn udf_name_cs0_16bit() {
         // compression_id=16, UTF-16 big-endian 'A' (0x0041)
         let data = [16u8, 0x00, 0x41];
-        assert_eq!(parse_udf_name(&data), "A");
+        assert_eq!(parse_udf_name(Bytes::Buffer(&data)), "A");
     }
 
     #[test]
     fn udf_name_fallback_raw() {
         // Unknown compression ID → raw UTF-8 lossy
         let data = [42u8, b'x', b'y'];
-        let name = parse_udf_name(&data);
+        let name = parse_udf_name(Bytes::Buffer(&data));
         assert!(!name.is_empty());
     }
 
     #[test]
     fn udf_name_empty() {
-        assert_eq!(parse_udf_name(&[]), "");
+        assert_eq!(parse_udf_name(Bytes::Buffer(&[])), "");
     }
 
     // ── parse_udf error paths ─────────────────────────────────────────────────
@@ -1051,6 +1721,13 @@ mod tests {
         let mut c = Cursor::new(img);
         let err = parse_udf(&mut c).unwrap_err();
         assert!(err.to_string().contains("Anchor") || err.to_string().contains("AVDP"));
+        assert!(matches!(
+            err,
+            Error::Corrupt {
+                format: Format::Udf,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -1104,6 +1781,17 @@ mod tests {
         assert!(node.is_some(), "hello.txt should be in root");
     }
 
+    #[test]
+    fn parse_udf_image_borrows_names() {
+        let img = make_udf_image();
+        let root = parse_udf_image(&img).unwrap();
+        let hello = root.find_node("/hello.txt").unwrap();
+        assert!(matches!(hello.name, Cow::Borrowed("hello.txt")));
+        let streamed = parse_udf(&mut Cursor::new(&img)).unwrap();
+        assert_eq!(format!("{:?}", root), format!("{:?}", streamed));
+        assert!(parse_udf_image(&img[..4096]).is_err());
+    }
+
     #[test]
     fn parse_udf_verbose_synthetic_image() {
         let img = make_udf_image();
@@ -1113,6 +1801,26 @@ mod tests {
         assert_eq!(root.name, "/");
     }
 
+    #[test]
+    fn parse_udf_observed_tags_diagnostics() {
+        use crate::observe::{Event, Level};
+        let mut seen = Vec::new();
+        let mut c = Cursor::new(make_udf_image());
+        parse_udf_observed(&mut c, &mut |event: &Event<'_>| {
+            if let Event::Diagnostic(d) = event {
+                assert_eq!(d.format, Some(Format::Udf));
+                seen.push((d.level, d.section, d.message.to_string()));
+            }
+        })
+        .unwrap();
+        assert!(seen.contains(&(
+            Level::Info,
+            "ECMA-167 3/10.2",
+            "Found AVDP at sector 256".to_string()
+        )));
+        assert!(seen.iter().all(|(_, section, _)| !section.is_empty()));
+    }
+
     // ── Additional FID / parse_directory coverage ─────────────────────────────
 
     /// Build a UDF image whose root directory FID buffer contains:
@@ -1366,8 +2074,8 @@ mod tests {
     }
 
     /// Image with an extent-based file entry (ad_type=0, short ADs) rather
-    /// than inline data. Exercises the `alloc.extents.first() → Some` branch
-    /// in parse_directory, which emits a TreeNode with a file_location.
+    /// than inline data. Exercises the extent-based branch in
+    /// parse_directory, which emits a TreeNode with a file_location.
     fn make_udf_image_extent_file() -> Vec<u8> {
         let mut img = vec![0u8; S * 280];
 
@@ -1475,6 +2183,42 @@ mod tests {
         assert_eq!(node.file_location.unwrap(), 264 * 2048);
     }
 
+    #[test]
+    fn parse_udf_fragmented_sparse_file_reads_through_extents() {
+        let mut img = make_udf_image_extent_file();
+        // Three short ADs: 2048 bytes at location 5, a 2048-byte sparse
+        // extent, then the original 512 bytes at location 4.
+        let fe = 263 * S;
+        w32(&mut img, fe + 172, 24);
+        w32(&mut img, fe + 176, 2048);
+        w32(&mut img, fe + 180, 5);
+        w32(&mut img, fe + 184, (1u32 << 30) | 2048);
+        w32(&mut img, fe + 188, 0);
+        w32(&mut img, fe + 192, 512);
+        w32(&mut img, fe + 196, 4);
+        img[265 * S..266 * S].fill(0xCD);
+
+        let mut c = Cursor::new(img);
+        let root = parse_udf(&mut c).expect("parse should succeed");
+        let node = root.find_node("/data.bin").unwrap();
+        assert_eq!(node.size, 4608);
+        assert!(node.file_location.is_none());
+        assert_eq!(
+            node.extents,
+            vec![
+                Extent::data(265 * 2048, 2048),
+                Extent::hole(2048),
+                Extent::data(264 * 2048, 512),
+            ]
+        );
+
+        let mut out = Vec::new();
+        crate::cat_node(&mut c, node, &mut out).unwrap();
+        assert!(out[..2048].iter().all(|&b| b == 0xCD));
+        assert!(out[2048..4096].iter().all(|&b| b == 0));
+        assert!(out[4096..].iter().all(|&b| b == 0xAB));
+    }
+
     /// Test that `get_file_allocation` with inline data where `ad_offset >= end`
     /// (empty inline data) returns an error (no extents, no inline_data).
     #[test]
@@ -1482,13 +2226,12 @@ mod tests {
         // ad_type=3 (inline) but AD length=0 → end==ad_offset → inline_data=None
         let buf = make_fe_buf(261, 3, &[]);
         assert!(
-            get_file_allocation(&buf).is_err(),
+            get_file_allocation(Bytes::Buffer(&buf)).is_err(),
             "empty inline data should error (no extents, no inline_data)"
         );
     }
 
-    /// Verify `file_alloc_short_ad_sparse_skipped` also exercises the
-    /// `extent_type == 3` (next-extent-of-ADs) early break.
+    /// Short AD with extent_type=3 (next-extent-of-ADs): early break.
     #[test]
     fn file_alloc_short_ad_next_extent_type_breaks() {
         let mut ad = vec![0u8; 8];
@@ -1497,7 +2240,7 @@ mod tests {
         w32(&mut ad, 4, 7);
         let buf = make_fe_buf(261, 0, &ad);
         // Type 3 means "next extent of ADs" — break immediately → no extents → Err
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     /// Same for long ADs (ad_type=1): extent_type=3 should break.
@@ -1508,18 +2251,20 @@ mod tests {
         w32(&mut ad, 0, raw);
         w32(&mut ad, 4, 7);
         let buf = make_fe_buf(261, 1, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
-    /// Long AD with extent_type=1 (sparse): skipped → no extents → Err.
+    /// Long AD with extent_type=1 (sparse): kept as an unrecorded extent.
     #[test]
-    fn file_alloc_long_ad_sparse_skipped() {
+    fn file_alloc_long_ad_sparse_kept_as_hole() {
         let mut ad = vec![0u8; 16];
         let raw = (1u32 << 30) | 512;
         w32(&mut ad, 0, raw);
         w32(&mut ad, 4, 7);
         let buf = make_fe_buf(261, 1, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).unwrap();
+        assert_eq!(alloc.extents.len(), 1);
+        assert!(!alloc.extents[0].recorded);
     }
 
     // ── get_file_allocation: zero-length and fallback paths ───────────────────
@@ -1530,7 +2275,7 @@ mod tests {
         // No extents recorded → Err.
         let ad = vec![0u8; 8];
         let buf = make_fe_buf(261, 0, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     #[test]
@@ -1538,7 +2283,7 @@ mod tests {
         // Long AD with all-zero bytes → raw_length=0 → length=0 → break.
         let ad = vec![0u8; 16];
         let buf = make_fe_buf(261, 1, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     #[test]
@@ -1548,7 +2293,7 @@ mod tests {
         w32(&mut ad, 0, 512u32); // length = 512
         w32(&mut ad, 4, 5u32); // location = 5
         let buf = make_fe_buf(261, 2, &ad);
-        let alloc = get_file_allocation(&buf).expect("fallback should succeed");
+        let alloc = get_file_allocation(Bytes::Buffer(&buf)).expect("fallback should succeed");
         assert_eq!(alloc.extents.len(), 1);
         assert_eq!(alloc.extents[0].length, 512);
     }
@@ -1827,7 +2572,7 @@ mod tests {
         // Covers line 569 (closing `}` of `if ext.length > 0`).
         let ad = vec![0u8; 8];
         let buf = make_fe_buf(261, 2, &ad);
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     #[test]
@@ -1839,7 +2584,7 @@ mod tests {
         w16(&mut buf, 18, 2); // ad_type=2 (fallback branch)
                               // ea_length=0 at 168, ad_length=0 at 172 → ad_offset=176, ad_length=0
                               // 176+8=184 > 180 → condition FALSE, body skipped
-        assert!(get_file_allocation(&buf).is_err());
+        assert!(get_file_allocation(Bytes::Buffer(&buf)).is_err());
     }
 
     // ── parse_directory: short FID tail (line 633) ────────────────────────────
@@ -2175,4 +2920,86 @@ mod tests {
         assert_eq!(root.name, "/");
         assert!(root.is_directory);
     }
+
+    // ── UdfFs ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn udf_fs_reads_sparse_file_through_handle() {
+        let mut img = make_udf_image_extent_file();
+        let fe = 263 * S;
+        w32(&mut img, fe + 172, 16);
+        w32(&mut img, fe + 176, (1u32 << 30) | 2048);
+        w32(&mut img, fe + 180, 0);
+        w32(&mut img, fe + 184, 512);
+        w32(&mut img, fe + 188, 4);
+
+        let mut fs = UdfFs::open(Cursor::new(img)).unwrap();
+        let entry = fs.lookup("data.bin").unwrap().unwrap();
+        assert!(!entry.is_directory);
+        let mut out = Vec::new();
+        fs.open(&entry).unwrap().read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 2560);
+        assert!(out[..2048].iter().all(|&b| b == 0));
+        assert!(out[2048..].iter().all(|&b| b == 0xAB));
+    }
+
+    #[test]
+    fn udf_fs_to_tree_matches_parse_udf() {
+        let img = make_udf_image_with_subdir();
+        let eager = parse_udf(&mut Cursor::new(img.clone())).unwrap();
+        let mut fs = UdfFs::open(Cursor::new(img)).unwrap();
+        let mydir = fs.lookup("mydir").unwrap().unwrap();
+        assert!(mydir.is_directory);
+        assert!(fs.read_dir(&mydir).unwrap().is_empty());
+        let lazy = fs.to_tree().unwrap();
+        assert_eq!(format!("{:?}", lazy), format!("{:?}", eager));
+    }
+
+    #[test]
+    fn volume_info_reads_the_logical_volume_descriptor() {
+        let mut img = make_udf_image();
+        // Turn the Partition Descriptor into a Primary Volume Descriptor
+        // and move the Partition Descriptor to the terminator's sector.
+        img.copy_within(257 * S..258 * S, 259 * S);
+        w32(&mut img, 256 * S + 16, (4 * S) as u32);
+        img[257 * S..258 * S].fill(0);
+        let pvd = 257 * S;
+        w16(&mut img, pvd, 1);
+        img[pvd + 24] = 8;
+        img[pvd + 25..pvd + 31].copy_from_slice(b"DVD_01");
+        img[pvd + 55] = 7;
+        w16(&mut img, pvd + 376, 0x1000); // UTC
+        w16(&mut img, pvd + 378, 2023);
+        img[pvd + 380..pvd + 385].copy_from_slice(&[11, 2, 9, 15, 0]);
+
+        let lvd = 258 * S;
+        let label: Vec<u8> = "Vacation ✓"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes())
+            .collect();
+        img[lvd + 84] = 16;
+        img[lvd + 85..lvd + 85 + label.len()].copy_from_slice(&label);
+        img[lvd + 211] = (1 + label.len()) as u8;
+        img[lvd + 217..lvd + 236].copy_from_slice(b"*OSTA UDF Compliant");
+        w16(&mut img, lvd + 240, 0x0250);
+        img[lvd + 273..lvd + 283].copy_from_slice(b"*mkudffs 2");
+
+        let volume = describe_volume(&mut Borrowed(&img), &mut ()).unwrap();
+        assert_eq!(volume.logical_volume_id, "Vacation ✓");
+        assert_eq!(volume.volume_id, "DVD_01");
+        assert_eq!(volume.volume_set_id, "");
+        assert_eq!(
+            volume.recorded,
+            Timestamp::from_civil(2023, 11, 2, 9, 15, 0)
+        );
+        assert_eq!(volume.domain, "*OSTA UDF Compliant");
+        assert_eq!(volume.udf_revision, Some(0x0250));
+        assert_eq!(volume.implementation_id, "*mkudffs 2");
+        // The tree still parses with the descriptors in that order.
+        assert!(parse_udf(&mut Cursor::new(img)).is_ok());
+
+        let volume = describe_volume(&mut Borrowed(&make_udf_image()), &mut ()).unwrap();
+        assert_eq!(volume.logical_volume_id, "");
+        assert_eq!(volume.udf_revision, None);
+    }
 }
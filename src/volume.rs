@@ -0,0 +1,181 @@
+//! Volume-level information from ISO 9660 and UDF volume descriptors.
+//!
+//! The tree parsers describe the files on a disc; [`read_volume_info`]
+//! describes the disc itself — the identifiers and dates its mastering
+//! software recorded, and which extensions it uses. A UDF bridge disc
+//! carries both descriptor sets, so both halves of [`VolumeInfo`] can be
+//! present.
+//!
+//! ```no_run
+//! let mut disc = std::fs::File::open("archive.iso")?;
+//! let info = isomage::volume::read_volume_info(&mut disc)?;
+//! if let Some(iso) = &info.iso9660 {
+//!     println!("{} by {}", iso.volume_id, iso.publisher_id);
+//! }
+//! if let Some(udf) = &info.udf {
+//!     println!("UDF {:?}: {}", udf.udf_revision, udf.logical_volume_id);
+//! }
+//! # Ok::<(), isomage::Error>(())
+//! ```
+
+use crate::image_io::source::Stream;
+use crate::iso9660;
+use crate::limits;
+use crate::probe::Format;
+use crate::tree::Timestamp;
+use crate::udf;
+use crate::{Error, Result};
+use std::io::{Read, Seek};
+
+/// What the volume descriptors of an image say about it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VolumeInfo {
+    /// The ISO 9660 volume, if the image has one.
+    pub iso9660: Option<IsoVolume>,
+    /// The UDF logical volume, if the image has one.
+    pub udf: Option<UdfVolume>,
+}
+
+/// The Primary Volume Descriptor of an ISO 9660 image (ECMA-119 8.4),
+/// with the extensions it uses.
+///
+/// Identifiers have their trailing spaces removed. Where a Joliet
+/// Supplementary Volume Descriptor gives an identifier, its Unicode
+/// spelling is used. An identifier that starts with `_` names a file in
+/// the root directory that holds the text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IsoVolume {
+    /// System Identifier: the system that can use the boot sectors.
+    pub system_id: String,
+    /// Volume Identifier, the disc's label.
+    pub volume_id: String,
+    /// Volume Set Identifier.
+    pub volume_set_id: String,
+    /// Publisher Identifier.
+    pub publisher_id: String,
+    /// Data Preparer Identifier.
+    pub preparer_id: String,
+    /// Application Identifier: the software that mastered the image.
+    pub application_id: String,
+    /// Volume Creation Date and Time.
+    pub created: Option<Timestamp>,
+    /// Volume Modification Date and Time.
+    pub modified: Option<Timestamp>,
+    /// Volume Expiration Date and Time, after which the volume is
+    /// obsolete.
+    pub expires: Option<Timestamp>,
+    /// Volume Effective Date and Time, before which the volume should
+    /// not be used.
+    pub effective: Option<Timestamp>,
+    /// Volume Space Size in sectors.
+    pub volume_space_size: u32,
+    /// Joliet level (1 to 3) of the Joliet descriptor, if there is one.
+    pub joliet_level: Option<u8>,
+    /// Whether the root directory carries Rock Ridge entries.
+    pub rock_ridge: bool,
+    /// Whether there is an ISO 9660:1999 Enhanced Volume Descriptor.
+    pub iso9660_1999: bool,
+}
+
+/// The Logical Volume Descriptor of a UDF image (ECMA-167 3/10.6) and
+/// the identifiers of its Primary Volume Descriptor (3/10.1).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UdfVolume {
+    /// Logical Volume Identifier, the disc's label.
+    pub logical_volume_id: String,
+    /// Volume Identifier of the Primary Volume Descriptor.
+    pub volume_id: String,
+    /// Volume Set Identifier of the Primary Volume Descriptor.
+    pub volume_set_id: String,
+    /// Recording Date and Time of the Primary Volume Descriptor.
+    pub recorded: Option<Timestamp>,
+    /// Domain Identifier, `"*OSTA UDF Compliant"` for UDF media.
+    pub domain: String,
+    /// UDF revision from the domain identifier, as binary-coded decimal:
+    /// `0x0250` is UDF 2.50.
+    pub udf_revision: Option<u16>,
+    /// Implementation Identifier: the software that wrote the volume.
+    pub implementation_id: String,
+}
+
+/// Read the volume descriptors of `file`: the last session's for ISO
+/// 9660, and the Main Volume Descriptor Sequence's for UDF.
+///
+/// Errors if `file` has neither, or if the descriptors it has are
+/// malformed.
+pub fn read_volume_info<R: Read + Seek>(file: &mut R) -> Result<VolumeInfo> {
+    let iso9660 = found(limits::metered(Format::Iso9660, None, &mut *file, |file| {
+        iso9660::describe_volume(&mut Stream(file), &mut ())
+    }))?;
+    let udf = found(limits::metered(Format::Udf, None, &mut *file, |file| {
+        udf::describe_volume(&mut Stream(file), &mut ())
+    }))?;
+    if iso9660.is_none() && udf.is_none() {
+        return Err(Error::NotRecognized {
+            format: None,
+            message: "no ISO 9660 or UDF volume descriptors found".into(),
+        });
+    }
+    Ok(VolumeInfo { iso9660, udf })
+}
+
+/// `None` where the format's signature is absent.
+fn found<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotRecognized { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn neither_format_is_an_error() {
+        let mut c = Cursor::new(vec![0u8; 64 * 2048]);
+        let err = read_volume_info(&mut c).unwrap_err();
+        assert!(matches!(err, Error::NotRecognized { format: None, .. }));
+    }
+
+    #[test]
+    fn bridge_disc_has_both_volumes() {
+        // ISO 9660 descriptors at sectors 16-17, then a UDF Volume
+        // Recognition Sequence and an anchor at 256, as on a UDF bridge
+        // disc.
+        const S: usize = 2048;
+        let mut img = vec![0u8; 270 * S];
+        let pvd = 16 * S;
+        img[pvd] = 1;
+        img[pvd + 1..pvd + 6].copy_from_slice(b"CD001");
+        img[pvd + 40..pvd + 72].fill(b' ');
+        img[pvd + 40..pvd + 46].copy_from_slice(b"BRIDGE");
+        img[pvd + 156] = 34;
+        img[pvd + 156 + 2..pvd + 156 + 6].copy_from_slice(&21u32.to_le_bytes());
+        img[pvd + 156 + 10..pvd + 156 + 14].copy_from_slice(&(S as u32).to_le_bytes());
+        img[pvd + 156 + 25] = 2;
+        img[pvd + 156 + 32] = 1;
+        img[17 * S] = 255;
+        img[17 * S + 1..17 * S + 6].copy_from_slice(b"CD001");
+        img[18 * S + 1..18 * S + 6].copy_from_slice(b"BEA01");
+        img[19 * S + 1..19 * S + 6].copy_from_slice(b"NSR02");
+        img[20 * S + 1..20 * S + 6].copy_from_slice(b"TEA01");
+
+        let avdp = 256 * S;
+        img[avdp] = 2;
+        img[avdp + 16..avdp + 20].copy_from_slice(&(2 * S as u32).to_le_bytes());
+        img[avdp + 20..avdp + 24].copy_from_slice(&257u32.to_le_bytes());
+        let lvd = 257 * S;
+        img[lvd] = 6;
+        img[lvd + 84] = 8;
+        img[lvd + 85..lvd + 91].copy_from_slice(b"BRIDGE");
+        img[lvd + 211] = 7;
+        img[258 * S] = 8;
+
+        let info = read_volume_info(&mut Cursor::new(img)).unwrap();
+        assert_eq!(info.iso9660.unwrap().volume_id, "BRIDGE");
+        assert_eq!(info.udf.unwrap().logical_volume_id, "BRIDGE");
+    }
+}
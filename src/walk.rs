@@ -0,0 +1,410 @@
+//! Walking a [`TreeNode`] tree.
+//!
+//! [`TreeNode::walk`] and [`TreeNode::walk_breadth_first`] yield every
+//! entry below a node as a [`WalkEntry`] carrying its path, and
+//! [`Walk::filter_with`] narrows that to the entries a [`Filter`] selects
+//! by glob, kind and size. [`TreeNode::find_node_ignore_case`] is the
+//! lookup for images whose users type paths in the wrong case.
+//!
+//! Paths are `/`-separated and start with `/`, relative to the node the
+//! walk started from; they round-trip through [`TreeNode::find_node`].
+//!
+//! ```
+//! use isomage::walk::Filter;
+//! use isomage::{EntryKind, TreeNode};
+//!
+//! let mut root = TreeNode::new_directory("/");
+//! let mut etc = TreeNode::new_directory("etc");
+//! etc.add_child(TreeNode::new_file("grub.cfg", 900));
+//! etc.add_child(TreeNode::new_file("hostname", 18));
+//! root.add_child(etc);
+//!
+//! let paths: Vec<String> = root.walk().map(|entry| entry.path).collect();
+//! assert_eq!(paths, ["/etc", "/etc/grub.cfg", "/etc/hostname"]);
+//!
+//! let filter = Filter::new().glob("**/*.CFG").ignore_case(true).kind(EntryKind::File);
+//! let configs: Vec<String> = root.walk().filter_with(filter).map(|e| e.path).collect();
+//! assert_eq!(configs, ["/etc/grub.cfg"]);
+//! ```
+
+use crate::tree::{EntryKind, TreeNode};
+use std::collections::VecDeque;
+
+/// One entry reached by a [`Walk`].
+#[derive(Debug, Clone)]
+pub struct WalkEntry<'t, 'a> {
+    /// `/`-separated path from the node the walk started at, with a
+    /// leading `/`.
+    pub path: String,
+    /// `1` for the starting node's children, `2` for theirs, and so on.
+    pub depth: usize,
+    /// The entry itself.
+    pub node: &'t TreeNode<'a>,
+}
+
+/// Iterator over every node below a [`TreeNode`], in depth-first
+/// pre-order or breadth-first order. Children are visited in the order
+/// the parser listed them. The starting node itself is not yielded.
+#[derive(Debug, Clone)]
+pub struct Walk<'t, 'a> {
+    pending: VecDeque<WalkEntry<'t, 'a>>,
+    breadth_first: bool,
+}
+
+impl<'t, 'a> Walk<'t, 'a> {
+    pub(crate) fn new(root: &'t TreeNode<'a>, breadth_first: bool) -> Self {
+        let mut walk = Self {
+            pending: VecDeque::new(),
+            breadth_first,
+        };
+        walk.descend(&WalkEntry {
+            path: String::new(),
+            depth: 0,
+            node: root,
+        });
+        walk
+    }
+
+    /// Keep only the entries `filter` selects, and don't descend into
+    /// directories it excludes.
+    pub fn filter_with(self, filter: Filter) -> Filtered<'t, 'a> {
+        Filtered { walk: self, filter }
+    }
+
+    fn pop(&mut self) -> Option<WalkEntry<'t, 'a>> {
+        if self.breadth_first {
+            self.pending.pop_front()
+        } else {
+            self.pending.pop_back()
+        }
+    }
+
+    fn descend(&mut self, entry: &WalkEntry<'t, 'a>) {
+        let children = entry.node.children.iter().map(|child| WalkEntry {
+            path: format!("{}/{}", entry.path, child.name),
+            depth: entry.depth + 1,
+            node: child,
+        });
+        if self.breadth_first {
+            self.pending.extend(children);
+        } else {
+            // Popped from the back, so push in reverse to keep order.
+            self.pending.extend(children.rev());
+        }
+    }
+}
+
+impl<'t, 'a> Iterator for Walk<'t, 'a> {
+    type Item = WalkEntry<'t, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.pop()?;
+        self.descend(&entry);
+        Some(entry)
+    }
+}
+
+/// A [`Walk`] narrowed by a [`Filter`]; see [`Walk::filter_with`].
+#[derive(Debug, Clone)]
+pub struct Filtered<'t, 'a> {
+    walk: Walk<'t, 'a>,
+    filter: Filter,
+}
+
+impl<'t, 'a> Iterator for Filtered<'t, 'a> {
+    type Item = WalkEntry<'t, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = self.walk.pop()?;
+            if self.filter.excludes(&entry.path) {
+                continue;
+            }
+            self.walk.descend(&entry);
+            if self.filter.matches(&entry) {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+/// Which entries of a walk to keep.
+///
+/// Every condition given must hold: the path matches one of the
+/// [`glob`](Self::glob) patterns (if any), the kind is one of the
+/// [`kind`](Self::kind)s (if any), and the size is within
+/// [`min_size`](Self::min_size) and [`max_size`](Self::max_size).
+/// An empty filter keeps everything.
+///
+/// Patterns use the syntax of [`ExtractOptions::include`](crate::ExtractOptions::include):
+/// `*` and `?` match within one path component, and a `**` component
+/// matches any number of components. They are matched against the
+/// entry's path without its leading `/`.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    globs: Vec<String>,
+    exclude: Vec<String>,
+    kinds: Vec<EntryKind>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    ignore_case: bool,
+}
+
+impl Filter {
+    /// A filter that keeps every entry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep entries whose path matches `pattern`. May be given several
+    /// times; any one matching is enough.
+    pub fn glob(mut self, pattern: impl Into<String>) -> Self {
+        self.globs.push(pattern.into());
+        self
+    }
+
+    /// Drop entries whose path matches `pattern`, and everything below a
+    /// matching directory. Exclusion wins over [`glob`](Self::glob).
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Keep entries of `kind`. May be given several times. A node with
+    /// `is_directory` set counts as [`EntryKind::Directory`] whatever its
+    /// metadata says.
+    pub fn kind(mut self, kind: EntryKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Keep entries of at least `bytes`. Directory sizes are the total
+    /// of their contents, as in [`TreeNode::size`].
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    /// Keep entries of at most `bytes`.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Match patterns without regard to case, as FAT, NTFS and HFS+
+    /// lookups do.
+    pub fn ignore_case(mut self, yes: bool) -> Self {
+        self.ignore_case = yes;
+        self
+    }
+
+    /// `true` if `entry` passes every condition but [`exclude`](Self::exclude),
+    /// which [`Walk::filter_with`] applies while walking.
+    pub fn matches(&self, entry: &WalkEntry) -> bool {
+        let node = entry.node;
+        let kind = if node.is_directory {
+            EntryKind::Directory
+        } else {
+            node.metadata.kind
+        };
+        let path = entry.path.trim_start_matches('/');
+        (self.kinds.is_empty() || self.kinds.contains(&kind))
+            && self.min_size.map_or(true, |min| node.size >= min)
+            && self.max_size.map_or(true, |max| node.size <= max)
+            && (self.globs.is_empty()
+                || self
+                    .globs
+                    .iter()
+                    .any(|p| glob_match(p, path, self.ignore_case)))
+    }
+
+    fn excludes(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        self.exclude
+            .iter()
+            .any(|p| glob_match(p, path, self.ignore_case))
+    }
+}
+
+/// `true` if two names are equal ignoring case. Uses full Unicode
+/// lowercasing, so `"STRASSE"` and `"straße"` differ but `"ÄPFEL"` and
+/// `"äpfel"` match.
+pub(crate) fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Match a `/`-separated path against a pattern: `*` and `?` within a
+/// component, `**` as a whole component for any number of components.
+pub(crate) fn glob_match(pattern: &str, path: &str, ignore_case: bool) -> bool {
+    let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_components(&pattern, &path, ignore_case)
+}
+
+fn match_components(pattern: &[&str], path: &[&str], ignore_case: bool) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=path.len()).any(|skip| match_components(rest, &path[skip..], ignore_case))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                let fold = |s: &str| -> Vec<char> {
+                    if ignore_case {
+                        s.chars().flat_map(char::to_lowercase).collect()
+                    } else {
+                        s.chars().collect()
+                    }
+                };
+                match_component(&fold(first), &fold(name))
+                    && match_components(rest, path, ignore_case)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_component(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_component(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_component(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Metadata;
+
+    /// `/a.txt`, `/docs/b.md`, `/docs/old/c.md`, `/docs/link`.
+    fn tree() -> TreeNode<'static> {
+        let mut root = TreeNode::new_directory("/");
+        root.add_child(TreeNode::new_file("a.txt", 10));
+        let mut docs = TreeNode::new_directory("docs");
+        docs.add_child(TreeNode::new_file("b.md", 2000));
+        let mut old = TreeNode::new_directory("old");
+        old.add_child(TreeNode::new_file("c.md", 30));
+        docs.add_child(old);
+        docs.add_child(
+            TreeNode::new_file("link", 0).with_metadata(Metadata::new(EntryKind::Symlink)),
+        );
+        root.add_child(docs);
+        root.calculate_directory_size();
+        root
+    }
+
+    fn paths<'t>(entries: impl Iterator<Item = WalkEntry<'t, 'static>>) -> Vec<String> {
+        entries.map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn walk_orders() {
+        let root = tree();
+        assert_eq!(
+            paths(root.walk()),
+            [
+                "/a.txt",
+                "/docs",
+                "/docs/b.md",
+                "/docs/old",
+                "/docs/old/c.md",
+                "/docs/link"
+            ]
+        );
+        assert_eq!(
+            paths(root.walk_breadth_first()),
+            [
+                "/a.txt",
+                "/docs",
+                "/docs/b.md",
+                "/docs/old",
+                "/docs/link",
+                "/docs/old/c.md"
+            ]
+        );
+        let depths: Vec<usize> = root.walk().map(|e| e.depth).collect();
+        assert_eq!(depths, [1, 1, 2, 2, 3, 2]);
+        for entry in root.walk() {
+            assert!(std::ptr::eq(
+                root.find_node(&entry.path).unwrap(),
+                entry.node
+            ));
+        }
+        let docs = root.find_node("docs").unwrap();
+        assert_eq!(paths(docs.walk()), ["/b.md", "/old", "/old/c.md", "/link"]);
+        assert_eq!(root.find_node("docs/b.md").unwrap().walk().count(), 0);
+    }
+
+    #[test]
+    fn filters() {
+        let root = tree();
+        let run = |filter: Filter| paths(root.walk().filter_with(filter));
+        assert_eq!(
+            run(Filter::new().glob("**/*.md")),
+            ["/docs/b.md", "/docs/old/c.md"]
+        );
+        assert_eq!(run(Filter::new().glob("*")), ["/a.txt", "/docs"]);
+        assert_eq!(
+            run(Filter::new().kind(EntryKind::Directory)),
+            ["/docs", "/docs/old"]
+        );
+        assert_eq!(run(Filter::new().kind(EntryKind::Symlink)), ["/docs/link"]);
+        assert_eq!(
+            run(Filter::new()
+                .kind(EntryKind::File)
+                .min_size(20)
+                .max_size(2000)),
+            ["/docs/b.md", "/docs/old/c.md"]
+        );
+        assert_eq!(
+            run(Filter::new().exclude("docs/old").glob("**/*.md")),
+            ["/docs/b.md"]
+        );
+        assert!(run(Filter::new().glob("**/*.MD")).is_empty());
+        assert_eq!(
+            run(Filter::new().glob("DOCS/*.MD").ignore_case(true)),
+            ["/docs/b.md"]
+        );
+        assert_eq!(run(Filter::new()).len(), 6);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*.md", "b.md", false));
+        assert!(!glob_match("*.md", "docs/b.md", false));
+        assert!(glob_match("**/*.md", "docs/b.md", false));
+        assert!(glob_match("**/*.md", "b.md", false));
+        assert!(glob_match("docs/**", "docs/x/y", false));
+        assert!(glob_match("d?cs", "docs", false));
+        assert!(!glob_match("docs", "docs/b.md", false));
+        assert!(glob_match("?.txt", "é.txt", false));
+        assert!(glob_match("ÉTÉ/*", "été/x", true));
+        assert!(!glob_match("ÉTÉ/*", "été/x", false));
+    }
+
+    #[test]
+    fn case_insensitive_names() {
+        assert!(eq_ignore_case("README.TXT", "readme.txt"));
+        assert!(eq_ignore_case("ÄPFEL", "äpfel"));
+        assert!(!eq_ignore_case("readme", "readme.txt"));
+
+        let mut root = tree();
+        root.add_child(TreeNode::new_file("A.TXT", 1));
+        assert!(std::ptr::eq(
+            root.find_node_ignore_case("/DOCS/Old/C.MD").unwrap(),
+            root.find_node("docs/old/c.md").unwrap()
+        ));
+        // An exact match wins over a case-folded one.
+        assert_eq!(root.find_node_ignore_case("A.TXT").unwrap().size, 1);
+        assert_eq!(root.find_node_ignore_case("a.txt").unwrap().size, 10);
+        assert!(std::ptr::eq(
+            root.find_node_ignore_case("/").unwrap(),
+            &root
+        ));
+        assert!(root.find_node_ignore_case("docs/missing").is_none());
+    }
+}
@@ -16,7 +16,7 @@ use isomage::TreeNode;
 
 /// Assert there's a child of `root` at `slash_path` and return it.
 /// `slash_path` is forward-slash-separated and may start with `/`.
-pub fn assert_path_exists<'a>(root: &'a TreeNode, slash_path: &str) -> &'a TreeNode {
+pub fn assert_path_exists<'a, 't>(root: &'a TreeNode<'t>, slash_path: &str) -> &'a TreeNode<'t> {
     match root.find_node(slash_path) {
         Some(n) => n,
         None => panic!(
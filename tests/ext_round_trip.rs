@@ -23,7 +23,7 @@ use isomage::formats::ext;
 
 /// Parse an ext image from raw bytes and assert basic invariants.
 /// Returns the root TreeNode.
-fn parse_image(bytes: &[u8]) -> isomage::TreeNode {
+fn parse_image(bytes: &[u8]) -> isomage::TreeNode<'static> {
     let mut c = Cursor::new(bytes.to_vec());
     assert!(
         ext::detect(&mut c),
@@ -238,7 +238,7 @@ fn ext4_unicode_filenames() {
     assert_tree_invariants(&root);
 
     // At least the ASCII-range parts of the names should be visible.
-    let names: Vec<&str> = root.children.iter().map(|n| n.name.as_str()).collect();
+    let names: Vec<&str> = root.children.iter().map(|n| n.name.as_ref()).collect();
     assert!(
         names.iter().any(|n| n.contains("caf")),
         "expected café.txt in tree, got: {names:?}"
@@ -608,3 +608,40 @@ fn ext4_symlinks() {
         "symlink should not appear as directory"
     );
 }
+
+// ── Test 16: lazy Filesystem matches the eager tree ───────────────────────────
+
+#[test]
+fn ext4_lazy_filesystem_matches_eager() {
+    use isomage::Filesystem;
+
+    let Some(_) = tools::MKFS_EXT4.require_or_skip() else {
+        return;
+    };
+
+    let rt = RoundTrip::new("ext4-lazy")
+        .with(&tools::MKFS_EXT4)
+        .image_size(8 * 1024 * 1024)
+        .source_file("a/b/c/deep.txt", b"deep content\n" as &[u8])
+        .source_file("a/top.txt", b"top content\n" as &[u8])
+        .args(["-F", "-d", "$SRC_DIR", "$IMAGE"])
+        .build();
+
+    let image = fs::read(rt.image_path()).expect("read image");
+    let eager = parse_image(&image);
+
+    let mut lazy = ext::ExtFs::open(Cursor::new(image)).expect("ExtFs::open");
+    let deep = lazy
+        .lookup("a/b/c/deep.txt")
+        .expect("lookup")
+        .expect("deep.txt present");
+    let mut content = Vec::new();
+    lazy.open(&deep)
+        .expect("open")
+        .read_to_end(&mut content)
+        .unwrap();
+    assert_eq!(content, b"deep content\n");
+
+    let tree = lazy.to_tree().expect("to_tree");
+    assert_eq!(format!("{:?}", tree), format!("{:?}", eager));
+}
@@ -100,7 +100,7 @@ fn two_partitions_different_types() {
 
     let tree = parse_from_bytes(&image);
     assert_eq!(tree.children.len(), 2);
-    let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+    let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_ref()).collect();
     assert!(
         names[0].starts_with("EFI-"),
         "first partition name = {:?}",
@@ -128,7 +128,7 @@ fn two_partitions_different_types() {
 /// Helper: parse a GPT image straight from bytes by materializing
 /// to a tempfile and calling the v2-style entry. When the parser
 /// generalization PR lands this collapses to a single `Cursor::new`.
-fn parse_from_bytes(image: &[u8]) -> isomage::TreeNode {
+fn parse_from_bytes(image: &[u8]) -> isomage::TreeNode<'static> {
     use std::fs::File;
     use std::io::Write;
     let dir = tempfile::TempDir::with_prefix("isomage-gpt-").unwrap();
@@ -229,7 +229,7 @@ fn require_or_skip_returns_some_for_present() {
 
 // ---- Assertions ----------------------------------------------------------
 
-fn fake_tree() -> TreeNode {
+fn fake_tree() -> TreeNode<'static> {
     let mut root = TreeNode::new_directory("/".to_string());
     root.add_child(TreeNode::new_file_with_location(
         "partition-0-type-83".to_string(),
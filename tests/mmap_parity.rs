@@ -39,12 +39,12 @@ fn trees_equal(a: &TreeNode, b: &TreeNode) -> bool {
         .all(|(ac, bc)| trees_equal(ac, bc))
 }
 
-fn parse_via_file(path: &Path) -> TreeNode {
+fn parse_via_file(path: &Path) -> TreeNode<'static> {
     let mut f = File::open(path).expect("open test ISO");
     detect_and_parse_filesystem(&mut f, &path.to_string_lossy()).expect("parse via File")
 }
 
-fn parse_via_mmap(path: &Path) -> TreeNode {
+fn parse_via_mmap(path: &Path) -> TreeNode<'static> {
     let mut img = MmapImage::open(path).expect("mmap test ISO");
     detect_and_parse_filesystem(&mut img, &path.to_string_lossy()).expect("parse via MmapImage")
 }
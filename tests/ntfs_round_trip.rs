@@ -22,7 +22,7 @@ use isomage::formats::ntfs;
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Parse an NTFS image from raw bytes and assert basic invariants.
-fn parse_image(bytes: &[u8]) -> isomage::TreeNode {
+fn parse_image(bytes: &[u8]) -> isomage::TreeNode<'static> {
     let mut c = Cursor::new(bytes.to_vec());
     assert!(
         ntfs::detect(&mut c),
@@ -18,7 +18,7 @@ use isomage::formats::qcow2;
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Parse a QCOW2 image from raw bytes and return the root TreeNode.
-fn parse_image(bytes: &[u8]) -> isomage::TreeNode {
+fn parse_image(bytes: &[u8]) -> isomage::TreeNode<'static> {
     let mut c = Cursor::new(bytes.to_vec());
     qcow2::detect(&mut c).expect("qcow2::detect returned Err for a freshly-minted QCOW2");
     let mut c2 = Cursor::new(bytes.to_vec());
@@ -118,7 +118,8 @@ fn qcow2_file_structure() {
         "disk.qcow2 should have no children"
     );
 
-    // QCOW2 data is addressed through L1/L2 tables; file_location is always None.
+    // QCOW2 data is addressed through L1/L2 tables, so the node has extents
+    // (one hole for a fresh image) and no single file_location.
     assert_eq!(
         disk.file_location, None,
         "QCOW2 disk.qcow2 should have file_location=None (L1/L2 table indirection)"
@@ -184,3 +185,24 @@ fn qcow2_qemu_img_info_cross_validate() {
     assert_eq!(root.children.len(), 1);
     assert_eq!(root.children[0].name, "disk.qcow2");
 }
+
+// ── Test 6: VirtualDisk over a fresh image ────────────────────────────────────
+
+#[test]
+fn qcow2_virtual_disk_reads_unallocated_as_zeros() {
+    let Some(_) = tools::QEMU_IMG.require_or_skip() else {
+        return;
+    };
+
+    let bytes = RoundTrip::new("qcow2-virtual-disk")
+        .with(&tools::QEMU_IMG)
+        .args(["create", "-f", "qcow2", "$IMAGE", "2M"])
+        .build_bytes();
+
+    let mut disk = qcow2::VirtualDisk::open(Cursor::new(bytes)).expect("open virtual disk");
+    assert_eq!(disk.len(), 2 * 1024 * 1024);
+    let mut out = Vec::new();
+    std::io::Read::read_to_end(&mut disk, &mut out).expect("read virtual disk");
+    assert_eq!(out.len() as u64, disk.len());
+    assert!(out.iter().all(|&b| b == 0), "fresh disk must read as zeros");
+}
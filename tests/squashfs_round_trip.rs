@@ -25,7 +25,7 @@ use isomage::formats::squashfs;
 const NOCOMPRESS_FLAGS: &[&str] = &["-noI", "-noD", "-noF", "-noX", "-noappend"];
 
 /// Helper: build a SquashFS image from a populated `$SRC_DIR` and parse it.
-fn build_and_parse(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode> {
+fn build_and_parse(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode<'static>> {
     let _ = tools::MKSQUASHFS.require_or_skip()?;
 
     let mut rt = RoundTrip::new(name).with(&tools::MKSQUASHFS);
@@ -22,7 +22,7 @@ use isomage::formats::tar;
 
 /// Build a ustar TAR from `sources` (relative path → bytes).
 /// Returns the parsed `TreeNode` tree, or `None` if `tar` is not available.
-fn build_tar(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode> {
+fn build_tar(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode<'static>> {
     let _ = tools::TAR.require_or_skip()?;
 
     let mut rt = RoundTrip::new(name).with(&tools::TAR);
@@ -18,7 +18,7 @@ use isomage::formats::vmdk;
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Parse a VMDK image from raw bytes and return the root TreeNode.
-fn parse_image(bytes: &[u8]) -> isomage::TreeNode {
+fn parse_image(bytes: &[u8]) -> isomage::TreeNode<'static> {
     let mut c = Cursor::new(bytes.to_vec());
     vmdk::detect(&mut c).expect("vmdk::detect returned Err for a freshly-minted VMDK");
     let mut c2 = Cursor::new(bytes.to_vec());
@@ -116,7 +116,8 @@ fn vmdk_file_structure() {
         "disk.vmdk should have no children"
     );
 
-    // Sparse VMDK: grain data is fragmented via the GD; file_location=None.
+    // Sparse VMDK: grain data is mapped through the GD, so the node has
+    // extents (one hole for a fresh image) and no single file_location.
     assert_eq!(
         disk.file_location, None,
         "sparse VMDK disk.vmdk should have file_location=None (grain directory indirection)"
@@ -193,3 +194,24 @@ fn vmdk_two_gb_split() {
         "vmdk::detect should succeed for twoGbMaxExtentSparse first extent: {result:?}"
     );
 }
+
+// ── VirtualDisk over a fresh image ────────────────────────────────────────────
+
+#[test]
+fn vmdk_virtual_disk_reads_unallocated_as_zeros() {
+    let Some(_) = tools::QEMU_IMG.require_or_skip() else {
+        return;
+    };
+
+    let bytes = RoundTrip::new("vmdk-virtual-disk")
+        .with(&tools::QEMU_IMG)
+        .args(["create", "-f", "vmdk", "$IMAGE", "2M"])
+        .build_bytes();
+
+    let mut disk = vmdk::VirtualDisk::open(Cursor::new(bytes)).expect("open virtual disk");
+    assert_eq!(disk.len(), 2 * 1024 * 1024);
+    let mut out = Vec::new();
+    std::io::Read::read_to_end(&mut disk, &mut out).expect("read virtual disk");
+    assert_eq!(out.len() as u64, disk.len());
+    assert!(out.iter().all(|&b| b == 0), "fresh disk must read as zeros");
+}
@@ -22,7 +22,7 @@ use isomage::formats::zip;
 
 /// Build a ZIP from `sources` (relative path → bytes) with `zip -0` (stored).
 /// Returns the parsed `TreeNode` tree, or `None` if `zip` is not available.
-fn build_stored_zip(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode> {
+fn build_stored_zip(name: &str, sources: &[(&str, &[u8])]) -> Option<isomage::TreeNode<'static>> {
     let _ = tools::ZIP.require_or_skip()?;
 
     let mut rt = RoundTrip::new(name).with(&tools::ZIP);